@@ -0,0 +1,39 @@
+//! Alarm status of the readings, see [`Ds28ea00Group::read_readings_iter`](crate::Ds28ea00Group::read_readings_iter).
+use crate::Temperature;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+/// Alarm threshold crossed by a reading.
+pub enum AlarmDirection {
+    /// The temperature is at or above the TH threshold.
+    High,
+    /// The temperature is at or below the TL threshold.
+    Low,
+}
+
+impl AlarmDirection {
+    /// Compares a temperature with the TH and TL thresholds as the device does, i.e. only the
+    /// integer part of the temperature, rounded down.
+    pub(crate) fn check(temperature: Temperature, high: i8, low: i8) -> Option<Self> {
+        let degrees = temperature.to_bits() >> 16;
+        if degrees >= high as i32 {
+            Some(AlarmDirection::High)
+        } else if degrees <= low as i32 {
+            Some(AlarmDirection::Low)
+        } else {
+            None
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+/// A temperature reading along with the alarm status of the device.
+pub struct Reading {
+    /// The temperature read from the device.
+    pub temperature: Temperature,
+    /// The threshold crossed by the temperature, or `None` if it is within the thresholds.
+    pub alarm: Option<AlarmDirection>,
+}
@@ -0,0 +1,165 @@
+//! Temperature readout with block transfers, for 1-Wire masters implementing [`OneWireBlock`].
+use embedded_onewire::{OneWire, OneWireResult};
+
+use crate::{
+    DS28EA00_READ_SCRATCH, DS28EA00_TOGGLE_PIO, DS28EA00_TOGGLE_PIO_OFF, DS28EA00_TOGGLE_PIO_ON,
+    Ds28ea00Group, Family, ONEWIRE_MATCH_ROM, ONEWIRE_MATCH_ROM_OD, ONEWIRE_SKIP_ROM,
+    ONEWIRE_SKIP_ROM_OD, ReadError, ReadSettings, Temperature, calibration,
+};
+
+/// A 1-Wire master that can write and read several bytes in a single transfer with its host.
+///
+/// The byte-wise methods of [`OneWire`] cost at least one host transaction per byte, e.g. an I2C
+/// write and a status poll per byte on the DS2484, so reading a device spends most of its time on
+/// the host bus. Masters with block commands, or drivers that queue the bytes of a transfer,
+/// implement this trait to read the devices with
+/// [`read_temperatures_block`](Ds28ea00Group::read_temperatures_block).
+pub trait OneWireBlock: OneWire {
+    /// Writes `bytes` to the bus, in order.
+    fn write_block(&mut self, bytes: &[u8]) -> OneWireResult<(), Self::BusError>;
+
+    /// Reads `buf.len()` bytes from the bus.
+    fn read_block(&mut self, buf: &mut [u8]) -> OneWireResult<(), Self::BusError>;
+}
+
+impl<const N: usize> Ds28ea00Group<N> {
+    /// Reads the temperatures from all DS28EA00 devices in the group using block transfers.
+    ///
+    /// This reads the devices as [`read_temperatures_detailed`](Self::read_temperatures_detailed) does,
+    /// but sends the ROM command, ROM address and read scratchpad command of a device as one block and
+    /// reads the scratchpad back as another, instead of transferring each byte on its own.
+    /// # Arguments
+    /// * `bus` - A mutable reference to a type that implements the [`OneWireBlock`] trait.
+    /// * `crc` - A boolean indicating whether to validate the CRC of the read data.
+    /// # Returns
+    /// An iterator over tuples of the ROM address and either the temperature reading or the [`ReadError`]
+    /// encountered while reading that device.
+    pub fn read_temperatures_block<O: OneWireBlock>(
+        &mut self,
+        bus: &mut O,
+        crc: bool,
+    ) -> impl Iterator<Item = (u64, Result<Temperature, ReadError>)> + '_ {
+        let options = self.read_settings();
+        let calibrations = &self.calibrations[..self.calibrated];
+        let resolutions = &self.resolutions[..self.resolved];
+        for ((rom, temp), state) in self.roms[..self.devices]
+            .iter_mut()
+            .zip(self.state[..self.devices].iter_mut())
+        {
+            let options = options.for_device(resolutions, *rom);
+            #[cfg(any(test, feature = "stats"))]
+            let bus = &mut crate::stats::Counted::new(&mut *bus, &mut state.stats);
+            let res = Self::retry(bus, options.retries, |bus| {
+                let res = Self::read_temperature_block_once(bus, *rom, temp, crc, options);
+                #[cfg(any(test, feature = "stats"))]
+                bus.record(&res);
+                res
+            });
+            if res.is_ok() {
+                *temp = calibration::lookup(calibrations, *rom).apply(*temp);
+            }
+            state.error = res.err().as_ref().map(ReadError::from);
+        }
+        self.roms[..self.devices]
+            .iter()
+            .zip(self.state[..self.devices].iter())
+            .map(|((rom, temp), state)| (*rom, state.error.map_or(Ok(*temp), Err)))
+    }
+
+    fn read_temperature_block_once<O: OneWireBlock>(
+        bus: &mut O,
+        rom: u64,
+        temp: &mut Temperature,
+        crc: bool,
+        options: ReadSettings,
+    ) -> OneWireResult<(), O::BusError> {
+        let mut cmd = [0; 12];
+        let len = Self::address_block(bus, rom, options.single, &mut cmd);
+        cmd[len] = DS28EA00_READ_SCRATCH;
+        bus.reset()?;
+        bus.write_block(&cmd[..=len])?;
+        let mut buf = [0; 9];
+        let buf = if crc { &mut buf[..] } else { &mut buf[..2] };
+        bus.read_block(buf)?;
+        Self::decode_readout(buf, options.resolution, temp)?;
+        if options.toggle_pio && Family::from_rom(rom) == Some(Family::Ds28ea00) {
+            cmd[len..len + 3].copy_from_slice(&[
+                DS28EA00_TOGGLE_PIO,
+                DS28EA00_TOGGLE_PIO_ON,
+                DS28EA00_TOGGLE_PIO_OFF,
+            ]);
+            bus.reset()?;
+            bus.write_block(&cmd[..len + 3])?;
+        }
+        Ok(())
+    }
+
+    /// Writes the ROM command addressing `rom` to the start of `cmd`, as [`OneWire::address`] sends it.
+    ///
+    /// # Returns
+    /// The length of the ROM command.
+    fn address_block<O: OneWire>(bus: &mut O, rom: u64, single: bool, cmd: &mut [u8]) -> usize {
+        let od = bus.get_overdrive_mode();
+        if single {
+            cmd[0] = if od {
+                ONEWIRE_SKIP_ROM_OD
+            } else {
+                ONEWIRE_SKIP_ROM
+            };
+            1
+        } else {
+            cmd[0] = if od {
+                ONEWIRE_MATCH_ROM_OD
+            } else {
+                ONEWIRE_MATCH_ROM
+            };
+            cmd[1..9].copy_from_slice(&rom.to_le_bytes());
+            9
+        }
+    }
+}
+
+mod test {
+    #[test]
+    fn test_read_block() {
+        use crate::{Ds28ea00Group, Family, ReadError, Temperature, mock::*};
+        use alloc::vec::Vec;
+        let mut devices = [
+            MockDevice::new(0x42, 0x1234, Temperature::from_millidegrees(20_000)),
+            MockDevice::new(0x42, 0x5678, Temperature::from_millidegrees(-5_500)),
+            MockDevice::new(0x28, 0x9abc, Temperature::from_millidegrees(22_250)),
+        ];
+        let roms = devices.map(|dev| dev.rom());
+        let mut bus = MockBus::new(&mut devices);
+        let mut group = Ds28ea00Group::<3>::default()
+            .with_families(&[Family::Ds28ea00, Family::Ds18b20])
+            .with_toggle_pio(true);
+        assert_eq!(group.enumerate(&mut bus).unwrap(), 3);
+        let expected = |rom| match roms.iter().position(|r| *r == rom).unwrap() {
+            0 => 20_000,
+            1 => -5_500,
+            _ => 22_250,
+        };
+        let blocks = bus.blocks();
+        for (rom, res) in group.read_temperatures_block(&mut bus, true) {
+            assert_eq!(res.unwrap().millidegrees(), expected(rom));
+        }
+        // a command and a readout block per device, and the PIO toggle of the DS28EA00s
+        assert_eq!(bus.blocks() - blocks, 3 * 2 + 2);
+        bus.device_mut(roms[1])
+            .unwrap()
+            .set_corrupt_scratchpad(true);
+        let res: Vec<_> = group.read_temperatures_block(&mut bus, true).collect();
+        for (rom, res) in res {
+            if rom == roms[1] {
+                assert_eq!(res, Err(ReadError::InvalidCrc));
+            } else {
+                assert_eq!(res.unwrap().millidegrees(), expected(rom));
+            }
+        }
+        // matches the byte-wise readout
+        let block: Vec<_> = group.read_temperatures_block(&mut bus, false).collect();
+        let bytes: Vec<_> = group.read_temperatures_detailed(&mut bus, false).collect();
+        assert_eq!(block, bytes);
+    }
+}
@@ -0,0 +1,226 @@
+//! Linear calibration of the devices in a [`Ds28ea00Group`], see [`Ds28ea00Group::set_calibration`].
+use fixed::types::I16F16;
+
+use crate::{Ds28ea00Group, RomError, Temperature};
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+/// Linear calibration of a device, applied to its readings as `raw * gain + offset`.
+pub struct Calibration {
+    /// Offset added to the scaled reading.
+    pub offset: Temperature,
+    /// Gain the reading is scaled by.
+    pub gain: I16F16,
+}
+
+impl Default for Calibration {
+    fn default() -> Self {
+        Self::IDENTITY
+    }
+}
+
+impl Calibration {
+    /// The calibration that leaves the readings as they are.
+    pub const IDENTITY: Self = Self {
+        offset: Temperature::ZERO,
+        gain: I16F16::ONE,
+    };
+
+    /// Creates a calibration from its offset and gain.
+    pub const fn new(offset: Temperature, gain: I16F16) -> Self {
+        Self { offset, gain }
+    }
+
+    /// Computes the calibration mapping two raw readings of a device onto their reference
+    /// temperatures, e.g. the readings in an ice bath and in boiling water.
+    ///
+    /// # Returns
+    /// The calibration, or `None` if the raw readings are equal or the gain is out of range.
+    pub fn from_two_points(
+        (raw_low, ref_low): (Temperature, Temperature),
+        (raw_high, ref_high): (Temperature, Temperature),
+    ) -> Option<Self> {
+        let span = raw_high.to_fixed().checked_sub(raw_low.to_fixed())?;
+        let gain = ref_high
+            .to_fixed()
+            .checked_sub(ref_low.to_fixed())?
+            .checked_div(span)?;
+        let offset = ref_low
+            .to_fixed()
+            .checked_sub(raw_low.to_fixed().checked_mul(gain)?)?;
+        Some(Self::new(Temperature::from_fixed(offset), gain))
+    }
+
+    /// Applies the calibration to a raw reading, saturating at the range of [`Temperature`].
+    pub fn apply(&self, raw: Temperature) -> Temperature {
+        Temperature::from_fixed(
+            raw.to_fixed()
+                .saturating_mul(self.gain)
+                .saturating_add(self.offset.to_fixed()),
+        )
+    }
+}
+
+#[cfg(feature = "defmt")]
+impl defmt::Format for Calibration {
+    fn format(&self, f: defmt::Formatter) {
+        defmt::write!(
+            f,
+            "x{=f32} + {=f32} °C",
+            self.gain.to_num::<f32>(),
+            self.offset.celsius()
+        );
+    }
+}
+
+/// The calibration of `rom` in `table`, or [`Calibration::IDENTITY`] if it is not calibrated.
+pub(crate) fn lookup(table: &[(u64, Calibration)], rom: u64) -> Calibration {
+    table
+        .iter()
+        .find_map(|(r, calibration)| (*r == rom).then_some(*calibration))
+        .unwrap_or(Calibration::IDENTITY)
+}
+
+impl<const N: usize> Ds28ea00Group<N> {
+    /// Sets the calibration of a device, applied to its temperature readings before they are returned.
+    ///
+    /// The calibrations are kept by ROM code, independently of the devices found by
+    /// [`enumerate`](Self::enumerate), so that they can be set before the bus is searched. The alarm
+    /// thresholds stored on the devices are compared with the raw readings by the devices themselves.
+    /// # Arguments
+    /// * `rom` - The ROM code of the device.
+    /// * `offset` - The offset added to the scaled reading.
+    /// * `gain` - The gain the raw reading is scaled by.
+    ///
+    /// # Returns
+    /// [`RomError::Full`] if the table holds the calibrations of `N` other devices.
+    pub fn set_calibration(
+        &mut self,
+        rom: u64,
+        offset: Temperature,
+        gain: I16F16,
+    ) -> Result<(), RomError> {
+        let calibration = Calibration::new(offset, gain);
+        let table = &mut self.calibrations[..self.calibrated];
+        if let Some((_, entry)) = table.iter_mut().find(|(r, _)| *r == rom) {
+            *entry = calibration;
+            return Ok(());
+        }
+        if self.calibrated == N {
+            return Err(RomError::Full);
+        }
+        self.calibrations[self.calibrated] = (rom, calibration);
+        self.calibrated += 1;
+        Ok(())
+    }
+
+    /// Removes the calibration of a device, whose readings are returned as they are read.
+    ///
+    /// # Returns
+    /// `true` if the device was calibrated.
+    pub fn clear_calibration(&mut self, rom: u64) -> bool {
+        let Some(idx) = self.calibrations[..self.calibrated]
+            .iter()
+            .position(|(r, _)| *r == rom)
+        else {
+            return false;
+        };
+        self.calibrations[idx..self.calibrated].rotate_left(1);
+        self.calibrated -= 1;
+        true
+    }
+
+    /// Returns the calibration of a device, or `None` if it is not calibrated.
+    pub fn calibration(&self, rom: u64) -> Option<Calibration> {
+        self.calibrations[..self.calibrated]
+            .iter()
+            .find_map(|(r, calibration)| (*r == rom).then_some(*calibration))
+    }
+
+    /// Returns the calibration table, to be stored and restored with
+    /// [`import_calibrations`](Self::import_calibrations).
+    pub fn calibrations(&self) -> &[(u64, Calibration)] {
+        &self.calibrations[..self.calibrated]
+    }
+
+    /// Replaces the calibration table, e.g. with one exported by [`calibrations`](Self::calibrations).
+    ///
+    /// # Returns
+    /// [`RomError::Full`] if the table holds more than `N` devices, in which case the calibrations
+    /// are left unchanged.
+    pub fn import_calibrations(&mut self, table: &[(u64, Calibration)]) -> Result<(), RomError> {
+        if table.len() > N {
+            return Err(RomError::Full);
+        }
+        self.calibrated = 0;
+        for (rom, calibration) in table {
+            self.set_calibration(*rom, calibration.offset, calibration.gain)?;
+        }
+        Ok(())
+    }
+}
+
+mod test {
+    #[test]
+    fn test_calibration() {
+        use crate::{Calibration, Ds28ea00Group, ReadOptions, RomError, Temperature, mock::*};
+        use alloc::vec::Vec;
+        use fixed::types::I16F16;
+        let t = Temperature::from_millidegrees;
+        // two-point calibration of a device reading 0.5 °C in an ice bath and 99 °C in boiling water
+        let calibration =
+            Calibration::from_two_points((t(500), t(0)), (t(99_000), t(100_000))).unwrap();
+        assert!(calibration.apply(t(500)).millidegrees().abs() <= 1);
+        assert!((calibration.apply(t(99_000)).millidegrees() - 100_000).abs() <= 1);
+        assert_eq!(
+            Calibration::from_two_points((t(500), t(0)), (t(500), t(100_000))),
+            None
+        );
+        let mut devices = [
+            MockDevice::new(0x42, 0x1234, t(20_000)),
+            MockDevice::new(0x42, 0x5678, t(21_000)),
+        ];
+        let roms = devices.map(|dev| dev.rom());
+        let mut bus = MockBus::new(&mut devices);
+        let mut group = Ds28ea00Group::<2>::default();
+        // set before the devices are found
+        group
+            .set_calibration(roms[0], t(-250), I16F16::from_num(2))
+            .unwrap();
+        group.set_calibration(roms[1], t(0), I16F16::ONE).unwrap();
+        assert_eq!(
+            group.set_calibration(0xdead, t(0), I16F16::ONE),
+            Err(RomError::Full)
+        );
+        assert_eq!(group.enumerate(&mut bus).unwrap(), 2);
+        let expected = |rom| if rom == roms[0] { 39_750 } else { 21_000 };
+        for (rom, temp) in group
+            .read_temperatures(&mut bus, ReadOptions::new())
+            .unwrap()
+        {
+            assert_eq!(temp.millidegrees(), expected(*rom));
+        }
+        for (rom, res) in group.read_temperatures_block(&mut bus, true) {
+            assert_eq!(res.unwrap().millidegrees(), expected(rom));
+        }
+        // exported and imported
+        let table: Vec<_> = group.calibrations().to_vec();
+        assert!(group.clear_calibration(roms[0]));
+        assert!(!group.clear_calibration(roms[0]));
+        assert_eq!(group.calibration(roms[0]), None);
+        let temps = group
+            .read_temperatures(&mut bus, ReadOptions::new())
+            .unwrap();
+        assert!(
+            temps
+                .iter()
+                .all(|(rom, temp)| *rom != roms[0] || temp.millidegrees() == 20_000)
+        );
+        group.import_calibrations(&table).unwrap();
+        assert_eq!(group.calibrations(), &table[..]);
+        assert_eq!(
+            group.calibration(roms[0]),
+            Some(Calibration::new(t(-250), I16F16::from_num(2)))
+        );
+    }
+}
@@ -3,11 +3,61 @@
 //! # DS28EA00
 //!
 //! A no-std implementation of the DS28EA00 1-Wire temperature sensors in a group.
+//!
+//! The DS18B20 and DS1822 share the scratchpad layout and function commands of the DS28EA00,
+//! and can be enumerated and read in the same group by selecting the supported [`Family`] codes.
+//!
+//! ## Features
+//! - `alloc`: `MultiBusGroup`, reading groups on several buses after a single conversion wait.
+//! - `mock`: `mock::MockBus`, a simulated bus of devices.
+//! - `stats`: `BusStats`, counters of the resets, presence failures, CRC errors and retries of
+//!   every device, see `Ds28ea00Group::bus_stats`.
+//! - `defmt`, `serde`: formatting and serialization of the public types.
+#[cfg(any(test, feature = "alloc"))]
+extern crate alloc;
+
+use core::time::Duration;
+
 use embedded_hal::delay::DelayNs;
 use embedded_onewire::{
     OneWire, OneWireCrc, OneWireError, OneWireResult, OneWireSearch, OneWireSearchKind,
 };
 use fixed::types::I12F4;
+/// Temperature data type used by the DS28EA00 devices.
+///
+/// The devices report temperatures with a fixed-point format of 12 bits for the integer part and 4 bits
+/// for the fractional part, which this type represents exactly.
+pub use piccthermo_core::Temperature;
+
+mod alarm;
+mod block;
+mod calibration;
+#[cfg(any(test, feature = "mock"))]
+pub mod mock;
+#[cfg(any(test, feature = "alloc"))]
+mod multi;
+mod options;
+mod pio;
+mod resolution;
+mod sensor;
+#[cfg(feature = "serde")]
+mod serialize;
+mod session;
+mod statistics;
+#[cfg(any(test, feature = "stats"))]
+mod stats;
+
+pub use alarm::{AlarmDirection, Reading};
+pub use block::OneWireBlock;
+pub use calibration::Calibration;
+#[cfg(any(test, feature = "alloc"))]
+pub use multi::{BusReadout, MultiBusGroup};
+pub use options::{CrcPolicy, ErrorPolicy, ReadOptions};
+pub use pio::PioState;
+pub use session::ConversionSession;
+pub use statistics::GroupStatistics;
+#[cfg(any(test, feature = "stats"))]
+pub use stats::BusStats;
 
 #[derive(Debug)]
 /// Represents a group of DS28EA00 devices on the 1-Wire bus.
@@ -15,11 +65,27 @@ use fixed::types::I12F4;
 pub struct Ds28ea00Group<const N: usize> {
     devices: usize,
     roms: [(u64, Temperature); N],
+    state: [DeviceState; N],
     resolution: ReadoutResolution,
     low: i8,
     high: i8,
     toggle_pio: bool,
     overdrive: bool,
+    skip_invalid_roms: bool,
+    invalid_roms: usize,
+    retries: u8,
+    families: u8,
+    assume_single: bool,
+    single: bool,
+    sort_order: SortOrder,
+    device_order: [u64; N],
+    device_order_len: usize,
+    calibrations: [(u64, Calibration); N],
+    calibrated: usize,
+    resolutions: [(u64, ReadoutResolution); N],
+    resolved: usize,
+    /// Elapsed time of the last [`read_ready_devices`](Self::read_ready_devices) of the running conversion.
+    ready_read: Duration,
 }
 
 impl<const N: usize> Default for Ds28ea00Group<N> {
@@ -41,15 +107,33 @@ impl<const N: usize> Ds28ea00Group<N> {
         Self {
             devices: 0,
             roms: [(0, Temperature::ZERO); N],
+            state: [DeviceState::new(); N],
             resolution: ReadoutResolution::default(),
             low: -40,
             high: 85,
             toggle_pio: false,
             overdrive: false,
+            skip_invalid_roms: false,
+            invalid_roms: 0,
+            retries: 0,
+            families: Family::Ds28ea00.mask(),
+            assume_single: false,
+            single: false,
+            sort_order: SortOrder::Search,
+            device_order: [0; N],
+            device_order_len: 0,
+            calibrations: [(0, Calibration::IDENTITY); N],
+            calibrated: 0,
+            resolutions: [(0, ReadoutResolution::default()); N],
+            resolved: 0,
+            ready_read: Duration::ZERO,
         }
     }
 
     /// Sets the temperature readout resolution for the DS28EA00 devices.
+    ///
+    /// The resolution is written by [`enumerate`](Self::enumerate), and changed afterwards with
+    /// [`set_resolution`](Self::set_resolution).
     pub fn with_resolution(mut self, resolution: ReadoutResolution) -> Self {
         self.resolution = resolution;
         self
@@ -82,49 +166,506 @@ impl<const N: usize> Ds28ea00Group<N> {
         self
     }
 
+    /// Enables or disables skipping of ROM codes that fail the CRC check during enumeration.
+    ///
+    /// By default, a ROM code with an invalid CRC aborts [`enumerate`](Self::enumerate) with
+    /// [`OneWireError::InvalidCrc`]. When enabled, such ROM codes are left out of the device table,
+    /// the search continues with the next device, and the number of rejected ROM codes is
+    /// available through [`invalid_roms`](Self::invalid_roms).
+    pub fn with_skip_invalid_roms(mut self, skip: bool) -> Self {
+        self.skip_invalid_roms = skip;
+        self
+    }
+
+    /// Sets the number of times a failed enumeration or readout of a device is retried.
+    ///
+    /// Only transient errors, see [`ReadError::is_transient`], are retried, after resetting the bus.
+    /// Other errors, and transient errors that persist through all retries, are returned as they
+    /// occur. By default, nothing is retried.
+    pub fn with_retries(mut self, retries: u8) -> Self {
+        self.retries = retries;
+        self
+    }
+
+    /// Assumes that exactly one device is connected to the bus.
+    ///
+    /// [`enumerate`](Self::enumerate) then reads the ROM of the device directly instead of searching the bus,
+    /// and the device is always addressed with skip-ROM. This must only be used on a single-drop bus, as all
+    /// devices respond at once otherwise.
+    pub fn assume_single_device(mut self) -> Self {
+        self.assume_single = true;
+        self
+    }
+
+    /// Sets the order of the devices in the group, applied after every [`enumerate`](Self::enumerate).
+    ///
+    /// The devices are kept in the same order across enumerations of the same devices, so that the
+    /// index of a device in [`roms`](Self::roms) refers to the same physical device. See
+    /// [`with_device_order`](Self::with_device_order) to place given devices first.
+    pub fn with_sort_order(mut self, order: SortOrder) -> Self {
+        self.sort_order = order;
+        self
+    }
+
+    /// Sets the ROM codes of devices to place first in the group, in the given order.
+    ///
+    /// Devices of the group that are not listed follow in the order set by
+    /// [`with_sort_order`](Self::with_sort_order). Only the first `N` ROM codes are kept.
+    pub fn with_device_order(mut self, roms: &[u64]) -> Self {
+        self.device_order_len = roms.len().min(N);
+        self.device_order[..self.device_order_len].copy_from_slice(&roms[..self.device_order_len]);
+        self
+    }
+
+    /// Sets the device families that are enumerated and read by this group.
+    ///
+    /// By default, only [`Family::Ds28ea00`] devices are enumerated. Devices of
+    /// families not in this list are ignored during the search. PIO toggling only
+    /// applies to the DS28EA00 devices in the group, as the other families lack PIO pins.
+    pub fn with_families(mut self, families: &[Family]) -> Self {
+        self.families = families.iter().fold(0, |mask, f| mask | f.mask());
+        self
+    }
+
+    /// Check whether a ROM code belongs to one of the families supported by this group.
+    pub fn supports(&self, rom: u64) -> bool {
+        Family::from_rom(rom).is_some_and(|f| self.families & f.mask() != 0)
+    }
+
     /// Enumerates the DS28EA00 devices on the 1-Wire bus.
     ///
     /// This method searches for devices on the bus, addresses them, and applies the configuration settings.
+    /// If the search finds exactly one device on the bus, it is read with skip-ROM addressing, which saves
+    /// sending its ROM in every transaction.
     /// # Arguments
     /// * `bus` - A mutable reference to a type that implements the [`OneWire`] trait.
     ///
     /// # Returns
     /// A result containing the number of devices found and configured, or an error if the operation fails.
+    /// The search is started over if it fails, as set by [`with_retries`](Self::with_retries).
     pub fn enumerate<O: OneWire>(&mut self, bus: &mut O) -> OneWireResult<usize, O::BusError> {
+        self.enumerate_filtered(bus, |_| true)
+    }
+
+    /// Enumerates the devices on the 1-Wire bus like [`enumerate`](Self::enumerate), leaving out
+    /// the devices that are not kept by `keep`.
+    ///
+    /// The devices that are left out are neither configured nor read by the group, and do not take
+    /// a slot of the device table, so that a group of `N` devices can be filled from a bus with more
+    /// devices, e.g. leaving out the sensors known to be faulty.
+    /// # Arguments
+    /// * `bus` - A mutable reference to a type that implements the [`OneWire`] trait.
+    /// * `keep` - Called with the ROM of every supported device found, returns whether the device is
+    ///   added to the group.
+    ///
+    /// # Returns
+    /// A result containing the number of devices added to the group and configured, or an error if
+    /// the operation fails.
+    #[doc(alias = "enumerate_with_filter")]
+    pub fn enumerate_filtered<O: OneWire>(
+        &mut self,
+        bus: &mut O,
+        mut keep: impl FnMut(u64) -> bool,
+    ) -> OneWireResult<usize, O::BusError> {
+        let retries = self.retries;
+        let mut left_out = false;
+        let mut keep = |rom| {
+            let kept = keep(rom);
+            left_out |= !kept;
+            kept
+        };
+        #[cfg(any(test, feature = "stats"))]
+        let stats = self.rom_stats();
+        Self::retry(bus, retries, |bus| self.search(bus, &mut keep))?;
+        self.sort_by(self.sort_order);
+        #[cfg(any(test, feature = "stats"))]
+        self.restore_stats(&stats);
+        self.configure(bus, left_out)
+    }
+
+    /// Adds a device with a known ROM code to the group, without searching the bus.
+    ///
+    /// This builds the group from a known list of ROM codes, skipping the search at startup, or adds
+    /// a device that was replaced at runtime. The device is appended to the group and read with
+    /// match-ROM addressing, but is not configured: call [`apply_configuration`](Self::apply_configuration)
+    /// once the devices are added.
+    /// # Arguments
+    /// * `rom` - The ROM code of the device.
+    ///
+    /// # Returns
+    /// The number of devices in the group, or the reason the ROM code was not added.
+    pub fn add_rom(&mut self, rom: u64) -> Result<usize, RomError> {
+        if !OneWireCrc::validate(&rom.to_le_bytes()) {
+            return Err(RomError::InvalidCrc);
+        }
+        if !self.supports(rom) {
+            return Err(RomError::Unsupported);
+        }
+        if self.roms().any(|r| r == rom) {
+            return Err(RomError::Duplicate);
+        }
+        if self.devices == N {
+            return Err(RomError::Full);
+        }
+        self.roms[self.devices] = (rom, Temperature::ZERO);
+        self.state[self.devices] = DeviceState::new();
+        self.devices += 1;
+        // other devices may be on the bus
+        self.single = false;
+        Ok(self.devices)
+    }
+
+    /// Removes a device from the group, e.g. a failed sensor, without enumerating the bus again.
+    ///
+    /// The order of the other devices is kept.
+    /// # Arguments
+    /// * `rom` - The ROM code of the device.
+    ///
+    /// # Returns
+    /// `true` if the device was in the group.
+    pub fn remove_rom(&mut self, rom: u64) -> bool {
+        let Some(idx) = self.roms().position(|r| r == rom) else {
+            return false;
+        };
+        self.roms[idx..self.devices].rotate_left(1);
+        self.state[idx..self.devices].rotate_left(1);
+        self.devices -= 1;
+        // the remaining devices, if any, are not known to be alone on the bus
+        self.single = false;
+        true
+    }
+
+    /// Writes the configuration to every device of the group in turn, e.g. after adding the devices
+    /// with [`add_rom`](Self::add_rom).
+    ///
+    /// Devices on the bus that are not in the group are left as they are.
+    /// # Arguments
+    /// * `bus` - A mutable reference to a type that implements the [`OneWire`] trait.
+    ///
+    /// # Returns
+    /// A result containing the number of devices configured, or an error if the operation fails.
+    pub fn apply_configuration<O: OneWire>(
+        &mut self,
+        bus: &mut O,
+    ) -> OneWireResult<usize, O::BusError> {
+        self.configure(bus, true)
+    }
+
+    /// Changes the readout resolution of the group at runtime, e.g. to switch between a fast 9-bit
+    /// scan and precise 12-bit readings.
+    ///
+    /// The configuration of the group is written with the new resolution to every device of the
+    /// group in turn, and the new resolution is used by the next conversions and readouts. If the
+    /// write fails, the new resolution is still kept, and written by the next
+    /// [`enumerate`](Self::enumerate) or [`apply_configuration`](Self::apply_configuration).
+    /// Devices with their own resolution, see [`set_device_resolution`](Self::set_device_resolution),
+    /// keep it.
+    ///
+    /// If `persist` is set, the configuration is also copied to the EEPROM of every device, so that
+    /// the devices power up with it. The copy takes up to 10 ms per device, waited for with `delay`.
+    /// Devices powered parasitically need a strong pull-up during the copy, which is not provided.
+    /// # Arguments
+    /// * `bus` - A mutable reference to a type that implements the [`OneWire`] trait.
+    /// * `delay` - A mutable reference to a type that implements the [`DelayNs`] trait, to wait for
+    ///   the copies to the EEPROM.
+    /// * `resolution` - The new readout resolution.
+    /// * `persist` - Whether to copy the configuration to the EEPROM of the devices.
+    ///
+    /// # Returns
+    /// A result containing the number of devices configured, or an error if the operation fails.
+    pub fn set_resolution<O: OneWire, D: DelayNs>(
+        &mut self,
+        bus: &mut O,
+        delay: &mut D,
+        resolution: ReadoutResolution,
+        persist: bool,
+    ) -> OneWireResult<usize, O::BusError> {
+        self.resolution = resolution;
+        let devices = self.configure(bus, true)?;
+        if persist {
+            for (rom, _) in self.roms[..self.devices].iter() {
+                bus.address(Some(*rom))?;
+                bus.write_byte(DS28EA00_COPY_SCRATCH)?;
+                delay.delay_us(DS28EA00_COPY_SCRATCH_US);
+            }
+        }
+        Ok(devices)
+    }
+
+    /// Fills the device table from a search of the bus, or from the ROM of the only device.
+    fn search<O: OneWire>(
+        &mut self,
+        bus: &mut O,
+        keep: &mut impl FnMut(u64) -> bool,
+    ) -> OneWireResult<(), O::BusError> {
         self.devices = 0; // reset device count
-        let mut search = OneWireSearch::with_family(bus, OneWireSearchKind::Normal, Self::family());
+        self.invalid_roms = 0; // reset rejected ROM count
+        self.single = false;
+        if self.assume_single {
+            let rom = Self::read_rom(bus)?;
+            if self.supports(rom) && keep(rom) {
+                self.roms[0].0 = rom;
+                self.devices = 1;
+                self.single = true;
+            }
+            return Ok(());
+        }
+        let mut found = 0; // devices on the bus, including unsupported ones
+        let mut complete = false; // whether the search ran to the end of the bus
+        // The search is not restricted to a single family code, since that would end the
+        // search at the first device of another family on a mixed chain.
+        let mut search = OneWireSearch::new(bus, OneWireSearchKind::Normal);
         // conduct search
-        while let Some(rom) = search.next()? {
+        loop {
+            let rom = match search.next() {
+                Ok(Some(rom)) if self.supports(rom) && keep(rom) => rom,
+                Ok(Some(_)) => {
+                    found += 1;
+                    continue; // unsupported family, or left out
+                }
+                Ok(None) => {
+                    complete = true;
+                    break;
+                }
+                Err(OneWireError::InvalidCrc)
+                    if self.skip_invalid_roms && self.invalid_roms < N =>
+                {
+                    self.invalid_roms += 1; // the search state has advanced past the corrupted ROM
+                    found += 1;
+                    continue;
+                }
+                Err(e) => return Err(e),
+            };
             self.roms[self.devices].0 = rom;
             self.devices += 1;
+            found += 1;
             if self.devices == N {
                 break;
             }
         }
-        if self.toggle_pio {
-            // turn all PIO pins on
-            bus.address(None)?;
-            bus.write_byte(DS28EA00_TOGGLE_PIO)?;
-            bus.write_byte(DS28EA00_TOGGLE_PIO_OFF)?;
-            bus.write_byte(DS28EA00_TOGGLE_PIO_ON)?;
+        self.single = complete && found == 1 && self.devices == 1;
+        Ok(())
+    }
+
+    /// Runs `op`, running it again up to `retries` times after a bus reset if it fails with a
+    /// transient error.
+    fn retry<O: OneWire, T>(
+        bus: &mut O,
+        retries: u8,
+        op: impl FnMut(&mut O) -> OneWireResult<T, O::BusError>,
+    ) -> OneWireResult<T, O::BusError> {
+        Self::retry_until(bus, retries, || false, op)
+    }
+
+    /// Runs `op` as [`retry`](Self::retry) does, without running it again once `expired` returns `true`.
+    fn retry_until<O: OneWire, T>(
+        bus: &mut O,
+        retries: u8,
+        mut expired: impl FnMut() -> bool,
+        mut op: impl FnMut(&mut O) -> OneWireResult<T, O::BusError>,
+    ) -> OneWireResult<T, O::BusError> {
+        let mut attempt = 0;
+        loop {
+            match op(bus) {
+                Err(e) if attempt < retries && ReadError::from(&e).is_transient() && !expired() => {
+                    attempt += 1;
+                    match bus.reset() {
+                        // the next attempt resets the bus again
+                        Ok(_) | Err(OneWireError::NoDevicePresent) => {}
+                        Err(e) => return Err(e),
+                    }
+                }
+                res => return res,
+            }
         }
-        // address all devices
-        bus.address(None)?;
-        // apply configuration
-        bus.write_byte(DS28EA00_WRITE_SCRATCH)?;
-        bus.write_byte(self.low as _)?;
-        bus.write_byte(self.high as _)?;
-        bus.write_byte(self.resolution as _)?;
-        if self.toggle_pio {
-            // turn all PIO pins off
-            bus.address(None)?;
-            bus.write_byte(DS28EA00_TOGGLE_PIO)?;
-            bus.write_byte(DS28EA00_TOGGLE_PIO_ON)?;
-            bus.write_byte(DS28EA00_TOGGLE_PIO_OFF)?;
+    }
+
+    /// Sorts the devices of the group.
+    ///
+    /// The devices listed with [`with_device_order`](Self::with_device_order) are placed first, the other
+    /// devices follow in the given order. This is applied with the order set by
+    /// [`with_sort_order`](Self::with_sort_order) after every [`enumerate`](Self::enumerate), and can be used
+    /// to reorder the devices, e.g. after [`discover_chain_order`](Self::discover_chain_order).
+    /// # Arguments
+    /// * `order` - The order of the devices that are not listed with [`with_device_order`](Self::with_device_order).
+    pub fn sort_by(&mut self, order: SortOrder) {
+        let key = |idx: usize, rom: u64| {
+            let listed = self.device_order[..self.device_order_len]
+                .iter()
+                .position(|r| *r == rom)
+                .unwrap_or(usize::MAX);
+            let order = match order {
+                SortOrder::Search => idx as u64,
+                SortOrder::Rom => rom,
+                SortOrder::Key(key) => (key(rom) as u64) << 32 | idx as u64,
+            };
+            (listed, order)
+        };
+        let mut keys = [(0, 0); N];
+        for (idx, (k, (rom, _))) in keys.iter_mut().zip(self.roms.iter()).enumerate() {
+            *k = key(idx, *rom);
+        }
+        // insertion sort, moving the device state along with the ROM codes
+        for i in 1..self.devices {
+            let mut j = i;
+            while j > 0 && keys[j - 1] > keys[j] {
+                keys.swap(j - 1, j);
+                self.roms.swap(j - 1, j);
+                self.state.swap(j - 1, j);
+                j -= 1;
+            }
+        }
+    }
+
+    /// Reads the ROM of the only device on the bus.
+    fn read_rom<O: OneWire>(bus: &mut O) -> OneWireResult<u64, O::BusError> {
+        bus.reset()?;
+        bus.write_byte(ONEWIRE_READ_ROM)?;
+        let mut buf = [0; 8];
+        for b in buf.iter_mut() {
+            *b = bus.read_byte()?;
+        }
+        if !OneWireCrc::validate(&buf) {
+            return Err(OneWireError::InvalidCrc);
+        }
+        Ok(u64::from_le_bytes(buf))
+    }
+
+    /// Applies the configuration to all enumerated devices.
+    ///
+    /// The configuration is broadcast to all devices on the bus, or if some devices were left out
+    /// of the group or have their own resolution, written to every device of the group in turn.
+    fn configure<O: OneWire>(
+        &mut self,
+        bus: &mut O,
+        left_out: bool,
+    ) -> OneWireResult<usize, O::BusError> {
+        let left_out = left_out || self.resolved > 0;
+        let devices = self.roms[..self.devices]
+            .iter()
+            .map(|(rom, _)| Some(*rom))
+            .filter(|_| left_out);
+        for rom in core::iter::once(None).filter(|_| !left_out).chain(devices) {
+            if self.toggle_pio {
+                // turn the PIO pins on
+                bus.address(rom)?;
+                bus.write_byte(DS28EA00_TOGGLE_PIO)?;
+                bus.write_byte(DS28EA00_TOGGLE_PIO_OFF)?;
+                bus.write_byte(DS28EA00_TOGGLE_PIO_ON)?;
+            }
+            // address the devices
+            bus.address(rom)?;
+            // apply configuration, in the order of scratchpad bytes 2 to 4
+            bus.write_byte(DS28EA00_WRITE_SCRATCH)?;
+            bus.write_byte(self.high as _)?; // TH
+            bus.write_byte(self.low as _)?; // TL
+            bus.write_byte(rom.map_or(self.resolution, |rom| self.device_resolution(rom)) as _)?;
+            if self.toggle_pio {
+                // turn the PIO pins off
+                bus.address(rom)?;
+                bus.write_byte(DS28EA00_TOGGLE_PIO)?;
+                bus.write_byte(DS28EA00_TOGGLE_PIO_ON)?;
+                bus.write_byte(DS28EA00_TOGGLE_PIO_OFF)?;
+            }
         }
         Ok(self.devices)
     }
 
+    /// Verifies that the configuration written during [`enumerate`](Self::enumerate) was applied.
+    ///
+    /// This method reads back the scratchpad of every device in the group and compares the TH, TL and
+    /// configuration bytes with the configured thresholds and resolution. A device whose scratchpad
+    /// fails the CRC check is treated as not configured.
+    /// # Arguments
+    /// * `bus` - A mutable reference to a type that implements the [`OneWire`] trait.
+    ///
+    /// # Returns
+    /// A result containing an iterator over the ROM addresses of the devices whose configuration
+    /// did not take, or an error if the bus operation fails.
+    pub fn verify_configuration<O: OneWire>(
+        &mut self,
+        bus: &mut O,
+    ) -> OneWireResult<impl Iterator<Item = u64> + '_, O::BusError> {
+        let resolutions = &self.resolutions[..self.resolved];
+        for ((rom, _), state) in self.roms[..self.devices]
+            .iter()
+            .zip(self.state[..self.devices].iter_mut())
+        {
+            let resolution = resolution::lookup(resolutions, self.resolution, *rom);
+            let expected = [self.high as u8, self.low as u8, resolution as u8];
+            state.configured = match Self::read_scratchpad_internal(bus, *rom, self.single) {
+                Ok(buf) => buf[2..5] == expected,
+                Err(OneWireError::InvalidCrc) => false,
+                Err(e) => return Err(e),
+            };
+        }
+        Ok(self.roms[..self.devices]
+            .iter()
+            .zip(self.state[..self.devices].iter())
+            .filter_map(|((rom, _), state)| (!state.configured).then_some(*rom)))
+    }
+
+    /// Reads the scratchpad of a device in the group.
+    ///
+    /// The scratchpad holds the last temperature reading, the TH and TL alarm thresholds, the
+    /// configuration byte, the reserved bytes and the CRC, in the order of the datasheet. Reads that
+    /// fail the CRC check are retried as configured with [`with_retries`](Self::with_retries).
+    /// # Arguments
+    /// * `bus` - A mutable reference to a type that implements the [`OneWire`] trait.
+    /// * `rom` - The ROM address of the device.
+    /// # Returns
+    /// A result containing the nine bytes of the scratchpad, or an error if the operation fails.
+    pub fn read_scratchpad<O: OneWire>(
+        &self,
+        bus: &mut O,
+        rom: u64,
+    ) -> OneWireResult<[u8; 9], O::BusError> {
+        let single = self.single && self.roms[0].0 == rom;
+        Self::retry(bus, self.retries, |bus| {
+            Self::read_scratchpad_internal(bus, rom, single)
+        })
+    }
+
+    /// Writes the TH, TL and configuration bytes of the scratchpad of a device in the group.
+    ///
+    /// The bytes are not copied to the EEPROM, and are overwritten with the configuration of the group
+    /// by the next [`enumerate`](Self::enumerate). A configuration byte that changes the resolution of the
+    /// device is not reflected in [`conversion_time`](Self::conversion_time).
+    /// # Arguments
+    /// * `bus` - A mutable reference to a type that implements the [`OneWire`] trait.
+    /// * `rom` - The ROM address of the device.
+    /// * `th` - The high alarm threshold, in degrees Celsius.
+    /// * `tl` - The low alarm threshold, in degrees Celsius.
+    /// * `cfg` - The configuration byte.
+    pub fn write_scratchpad<O: OneWire>(
+        &self,
+        bus: &mut O,
+        rom: u64,
+        th: i8,
+        tl: i8,
+        cfg: u8,
+    ) -> OneWireResult<(), O::BusError> {
+        let single = self.single && self.roms[0].0 == rom;
+        Self::select(bus, rom, single)?; // address device
+        bus.write_byte(DS28EA00_WRITE_SCRATCH)?;
+        bus.write_byte(th as _)?; // TH
+        bus.write_byte(tl as _)?; // TL
+        bus.write_byte(cfg)?;
+        Ok(())
+    }
+
+    /// Returns `true` if the group is read with skip-ROM addressing, because it holds the only device on the bus.
+    pub fn single_device(&self) -> bool {
+        self.single
+    }
+
+    /// Number of ROM codes rejected due to an invalid CRC during the last enumeration.
+    ///
+    /// This is always zero unless skipping was enabled with [`with_skip_invalid_roms`](Self::with_skip_invalid_roms).
+    pub fn invalid_roms(&self) -> usize {
+        self.invalid_roms
+    }
+
     /// Enumerate the ROMs found
     pub fn roms(&self) -> impl Iterator<Item = u64> {
         self.roms[..self.devices].iter().map(|(x, _)| *x)
@@ -149,17 +690,84 @@ impl<const N: usize> Ds28ea00Group<N> {
         Ok(())
     }
 
+    /// Enable overdrive mode if the devices respond at overdrive speed, and fall back to standard speed
+    /// otherwise.
+    ///
+    /// Once the bus is switched to overdrive speed, the presence of the devices is checked, and the
+    /// scratchpad of the first enumerated device is read back with its CRC. The check is retried as set
+    /// by [`with_retries`](Self::with_retries). If it fails, the bus is switched back to standard speed,
+    /// and reset so that the devices that did switch return to standard speed.
+    /// # Arguments
+    /// * `bus` - A mutable reference to a type that implements the [`OneWire`] trait.
+    /// * `delay` - A mutable reference to a type that implements the [`DelayNs`] trait, to let the bus settle
+    ///   after switching speed.
+    /// # Returns
+    /// `true` if overdrive mode is active, `false` if the group fell back to standard speed. Errors other than
+    /// a missing presence pulse or an invalid CRC are returned once the group fell back to standard speed.
+    pub fn try_enable_overdrive<O: OneWire, D: DelayNs>(
+        &mut self,
+        bus: &mut O,
+        delay: &mut D,
+    ) -> OneWireResult<bool, O::BusError> {
+        self.enable_overdrive(bus)?;
+        delay.delay_us(OVERDRIVE_SETTLE_US);
+        let first = self.roms[..self.devices].first().map(|(rom, _)| *rom);
+        let single = self.single;
+        let check = Self::retry(bus, self.retries, |bus| match first {
+            Some(rom) => Self::read_scratchpad_internal(bus, rom, single).map(|_| ()),
+            None => bus.reset().map(|_| ()),
+        });
+        let Err(e) = check else {
+            return Ok(true);
+        };
+        self.disable_overdrive(bus)?;
+        match bus.reset() {
+            Ok(_) | Err(OneWireError::NoDevicePresent) => {}
+            Err(e) => return Err(e),
+        }
+        if ReadError::from(&e).is_transient() {
+            Ok(false)
+        } else {
+            Err(e)
+        }
+    }
+
     /// Triggers a temperature conversion on all DS28EA00 devices in the group.
     /// This method addresses all devices, sends the command to start the conversion,
-    /// and waits for the conversion to complete based on the configured resolution.
+    /// and waits for the conversion of the slowest device to complete, see [`conversion_time`](Self::conversion_time).
     ///
     /// # Arguments
     /// * `bus` - A mutable reference to a type that implements the [`OneWire`] trait.
     /// * `delay` - A mutable reference to a type that implements the [`DelayNs`] trait to wait for the conversion to complete.
     pub fn trigger_temperature_conversion<O: OneWire, D: DelayNs>(
-        &self,
+        &mut self,
         bus: &mut O,
         delay: &mut D,
+    ) -> OneWireResult<(), O::BusError> {
+        self.start_temperature_conversion(bus)?;
+        delay.delay_us(self.conversion_time().as_micros() as _); // wait till conversion is finished
+        Ok(())
+    }
+
+    /// Starts a temperature conversion on all DS28EA00 devices in the group without waiting for it to complete.
+    ///
+    /// The temperatures can be read out after [`Ds28ea00Group::conversion_time`] has elapsed, or
+    /// device by device with [`read_ready_devices`](Self::read_ready_devices).
+    ///
+    /// # Arguments
+    /// * `bus` - A mutable reference to a type that implements the [`OneWire`] trait.
+    pub fn start_temperature_conversion<O: OneWire>(
+        &mut self,
+        bus: &mut O,
+    ) -> OneWireResult<(), O::BusError> {
+        self.ready_read = Duration::ZERO; // no device of this conversion was read yet
+        self.send_temperature_conversion(bus)
+    }
+
+    /// Sends the commands of [`start_temperature_conversion`](Self::start_temperature_conversion).
+    fn send_temperature_conversion<O: OneWire>(
+        &self,
+        bus: &mut O,
     ) -> OneWireResult<(), O::BusError> {
         bus.address(None)?; // address all devices
         bus.write_byte(DS28EA00_START_CONV)?; // start temperature conversion
@@ -169,37 +777,189 @@ impl<const N: usize> Ds28ea00Group<N> {
             bus.write_byte(DS28EA00_TOGGLE_PIO_OFF)?; // turn on PIO
             bus.write_byte(DS28EA00_TOGGLE_PIO_ON)?; // turn on PIO
         }
-        delay.delay_us(self.resolution.delay_us()); // wait till conversion is finished
         Ok(())
     }
 
+    /// Returns the time a temperature conversion takes at the configured resolution, or at the highest
+    /// resolution of the devices in the group, see [`set_device_resolution`](Self::set_device_resolution).
+    pub fn conversion_time(&self) -> Duration {
+        self.roms[..self.devices]
+            .iter()
+            .map(|(rom, _)| self.device_resolution(*rom).conversion_time())
+            .max()
+            .unwrap_or(self.resolution.conversion_time())
+    }
+
     /// Reads the temperatures from all DS28EA00 devices in the group.
-    /// This method addresses each device, reads the temperature data, and validates the CRC if requested.
+    /// This method addresses each device, reads the temperature data, and validates the CRC as set by `options`.
     /// # Arguments
     /// * `bus` - A mutable reference to a type that implements the [`OneWire`] trait.
-    /// * `crc` - A boolean indicating whether to validate the CRC of the read data.
+    /// * `options` - The CRC and error policies, retries and timeout of the readout, see [`ReadOptions`].
     /// # Returns
     /// A result containing a slice of tuples, each containing the ROM address and the temperature reading,
     /// or an error if the operation fails.
     pub fn read_temperatures<O: OneWire>(
         &mut self,
         bus: &mut O,
-        crc: bool,
-        ignore_errors: bool,
+        options: ReadOptions,
     ) -> OneWireResult<&[(u64, Temperature)], O::BusError> {
-        for (rom, temp) in self.roms[..self.devices].iter_mut() {
-            let res = Self::read_temperature_internal(bus, *rom, temp, crc, self.toggle_pio);
+        let settings = ReadSettings {
+            retries: options.retries.unwrap_or(self.retries),
+            timeout: options.timeout,
+            ..self.read_settings()
+        };
+        let crc = options.crc == CrcPolicy::Verify;
+        for ((rom, temp), state) in self.roms[..self.devices]
+            .iter_mut()
+            .zip(self.state[..self.devices].iter_mut())
+        {
+            let calibration = calibration::lookup(&self.calibrations[..self.calibrated], *rom);
+            let settings = settings.for_device(&self.resolutions[..self.resolved], *rom);
+            let res =
+                Self::read_temperature_internal(bus, *rom, temp, crc, settings, state, calibration);
             if let Err(e) = res {
-                if !ignore_errors {
-                    return Err(e);
-                } else {
-                    *temp = Temperature::from_num(-85); // Set to -85 on error
+                match options.errors {
+                    ErrorPolicy::Abort => return Err(e),
+                    ErrorPolicy::Substitute => *temp = ErrorPolicy::SENTINEL,
                 }
             }
         }
         Ok(&self.roms[..self.devices])
     }
 
+    /// Computes the statistics of the most recent readout of the group.
+    ///
+    /// # Arguments
+    /// * `max_deviation` - Readings that deviate from the group median by more than this amount are flagged as outliers.
+    ///
+    /// # Returns
+    /// The statistics of the readout, or `None` if no devices have been enumerated.
+    pub fn statistics(&self, max_deviation: Temperature) -> Option<GroupStatistics<N>> {
+        GroupStatistics::new(&self.roms[..self.devices], max_deviation)
+    }
+
+    /// Reads the temperatures from all DS28EA00 devices in the group, recording the outcome of each read.
+    ///
+    /// Unlike [`read_temperatures`](Self::read_temperatures), this method never aborts on a per-device error
+    /// and never substitutes a sentinel temperature. Every device is read, and the result for each device
+    /// is reported individually.
+    /// # Arguments
+    /// * `bus` - A mutable reference to a type that implements the [`OneWire`] trait.
+    /// * `crc` - A boolean indicating whether to validate the CRC of the read data.
+    /// # Returns
+    /// An iterator over tuples of the ROM address and either the temperature reading or the [`ReadError`]
+    /// encountered while reading that device.
+    pub fn read_temperatures_detailed<O: OneWire>(
+        &mut self,
+        bus: &mut O,
+        crc: bool,
+    ) -> impl Iterator<Item = (u64, Result<Temperature, ReadError>)> + '_ {
+        self.read_temperatures_iter(bus, crc).for_each(drop);
+        self.roms[..self.devices]
+            .iter()
+            .zip(self.state[..self.devices].iter())
+            .map(|((rom, temp), state)| (*rom, state.error.map_or(Ok(*temp), Err)))
+    }
+
+    /// Reads the temperatures from the DS28EA00 devices in the group one at a time.
+    ///
+    /// Each device is only read when the iterator is advanced, so that the readings can be processed
+    /// as they arrive. Devices left when the iterator is dropped are not read, and keep their previous
+    /// readings and errors.
+    /// # Arguments
+    /// * `bus` - A mutable reference to a type that implements the [`OneWire`] trait.
+    /// * `crc` - A boolean indicating whether to validate the CRC of the read data.
+    /// # Returns
+    /// An iterator over tuples of the ROM address and either the temperature reading or the [`ReadError`]
+    /// encountered while reading that device.
+    pub fn read_temperatures_iter<O: OneWire>(
+        &mut self,
+        bus: &mut O,
+        crc: bool,
+    ) -> impl Iterator<Item = (u64, Result<Temperature, ReadError>)> {
+        self.read_readings_iter(bus, crc)
+            .map(|(rom, res)| (rom, res.map(|reading| reading.temperature)))
+    }
+
+    /// Reads the temperatures and the alarm status from the DS28EA00 devices in the group one at a time,
+    /// as [`read_temperatures_iter`](Self::read_temperatures_iter) does.
+    ///
+    /// The alarm status is found as the device does for a conditional search. With `crc`, the full
+    /// scratchpad is read and the temperature is compared with the TH and TL thresholds stored on the
+    /// device, otherwise with the thresholds configured with [`with_t_high`](Self::with_t_high) and
+    /// [`with_t_low`](Self::with_t_low).
+    /// # Arguments
+    /// * `bus` - A mutable reference to a type that implements the [`OneWire`] trait.
+    /// * `crc` - A boolean indicating whether to validate the CRC of the read data.
+    /// # Returns
+    /// An iterator over tuples of the ROM address and either the reading or the [`ReadError`]
+    /// encountered while reading that device.
+    pub fn read_readings_iter<O: OneWire>(
+        &mut self,
+        bus: &mut O,
+        crc: bool,
+    ) -> impl Iterator<Item = (u64, Result<Reading, ReadError>)> {
+        let options = self.read_settings();
+        let thresholds = (self.high, self.low);
+        let calibrations = &self.calibrations[..self.calibrated];
+        let resolutions = &self.resolutions[..self.resolved];
+        self.roms[..self.devices]
+            .iter_mut()
+            .zip(self.state[..self.devices].iter_mut())
+            .map(move |((rom, temp), state)| {
+                let calibration = calibration::lookup(calibrations, *rom);
+                let res = Self::read_temperature_internal(
+                    bus,
+                    *rom,
+                    temp,
+                    crc,
+                    options.for_device(resolutions, *rom),
+                    state,
+                    calibration,
+                );
+                state.error = res.as_ref().err().map(ReadError::from);
+                let res = res.map(|stored| {
+                    let (high, low) = stored.unwrap_or(thresholds);
+                    Reading {
+                        temperature: *temp,
+                        alarm: AlarmDirection::check(*temp, high, low),
+                    }
+                });
+                (*rom, res.map_err(|e| ReadError::from(&e)))
+            })
+    }
+
+    /// Reads the temperatures from the DS28EA00 devices in the group into a buffer provided by the caller.
+    ///
+    /// The readout stops at the first error, as with [`read_temperatures`](Self::read_temperatures).
+    /// # Arguments
+    /// * `bus` - A mutable reference to a type that implements the [`OneWire`] trait.
+    /// * `crc` - A boolean indicating whether to validate the CRC of the read data.
+    /// * `buf` - The buffer receiving the ROM address and temperature reading of each device, in the order
+    ///   of [`roms`](Self::roms). Devices beyond the length of the buffer are not read.
+    /// # Returns
+    /// A result containing the number of entries written to `buf`, or an error if the operation fails.
+    pub fn read_temperatures_into<O: OneWire>(
+        &mut self,
+        bus: &mut O,
+        crc: bool,
+        buf: &mut [(u64, Temperature)],
+    ) -> OneWireResult<usize, O::BusError> {
+        let count = buf.len().min(self.devices);
+        let options = self.read_settings();
+        for (((rom, temp), state), out) in self.roms[..count]
+            .iter_mut()
+            .zip(self.state[..count].iter_mut())
+            .zip(buf.iter_mut())
+        {
+            let calibration = calibration::lookup(&self.calibrations[..self.calibrated], *rom);
+            let options = options.for_device(&self.resolutions[..self.resolved], *rom);
+            Self::read_temperature_internal(bus, *rom, temp, crc, options, state, calibration)?;
+            *out = (*rom, *temp);
+        }
+        Ok(count)
+    }
+
     /// Reads the temperature from a specific DS28EA00 device.
     /// This method addresses the device by its ROM address, reads the temperature data,
     /// and validates the CRC if requested.
@@ -218,49 +978,199 @@ impl<const N: usize> Ds28ea00Group<N> {
         crc: bool,
     ) -> OneWireResult<Temperature, O::BusError> {
         let mut temp = Temperature::ZERO; // Initialize temperature
-        self.trigger_temperature_conversion(bus, delay)?; // Trigger temperature conversion
-        Self::read_temperature_internal(bus, rom, &mut temp, crc, self.toggle_pio)?; // Read temperature
+        self.send_temperature_conversion(bus)?; // Trigger temperature conversion
+        delay.delay_us(self.conversion_time().as_micros() as _);
+        let options = ReadSettings {
+            single: self.single && self.roms[0].0 == rom,
+            ..self.read_settings()
+        }
+        .for_device(&self.resolutions[..self.resolved], rom);
+        let state = &mut DeviceState::new(); // not counted in the group
+        let calibration = calibration::lookup(self.calibrations(), rom);
+        Self::read_temperature_internal(bus, rom, &mut temp, crc, options, state, calibration)?; // Read temperature
         Ok(temp)
     }
 
+    /// Address `rom`, or all devices if it is the only device on the bus.
+    fn select<O: OneWire>(bus: &mut O, rom: u64, single: bool) -> OneWireResult<(), O::BusError> {
+        bus.address(if single { None } else { Some(rom) })
+    }
+
+    /// The settings of the group used to read a device.
+    fn read_settings(&self) -> ReadSettings {
+        ReadSettings {
+            single: self.single,
+            toggle_pio: self.toggle_pio,
+            retries: self.retries,
+            resolution: self.resolution,
+            timeout: None,
+        }
+    }
+
+    /// Reads the temperature of a device, retrying transient errors, and applies its `calibration`.
+    ///
+    /// # Returns
+    /// The TH and TL thresholds stored on the device, if the full scratchpad was read.
+    #[cfg_attr(not(any(test, feature = "stats")), allow(unused_variables))]
     fn read_temperature_internal<O: OneWire>(
         bus: &mut O,
         rom: u64,
         temp: &mut Temperature,
         crc: bool,
-        toggle_pio: bool,
-    ) -> OneWireResult<(), O::BusError> {
-        bus.address(Some(rom))?; // address device
+        options: ReadSettings,
+        state: &mut DeviceState,
+        calibration: Calibration,
+    ) -> OneWireResult<Option<(i8, i8)>, O::BusError> {
+        #[cfg(any(test, feature = "stats"))]
+        let bus = &mut stats::Counted::new(bus, &mut state.stats);
+        let start = options.timeout.map(|(_, clock)| clock());
+        let expired = || match (options.timeout, start) {
+            (Some((timeout, clock)), Some(start)) => clock().saturating_sub(start) >= timeout,
+            _ => false,
+        };
+        Self::retry_until(bus, options.retries, expired, |bus| {
+            let res = Self::read_temperature_once(bus, rom, temp, crc, options);
+            #[cfg(any(test, feature = "stats"))]
+            bus.record(&res);
+            res
+        })
+        .inspect(|_| *temp = calibration.apply(*temp))
+    }
+
+    fn read_temperature_once<O: OneWire>(
+        bus: &mut O,
+        rom: u64,
+        temp: &mut Temperature,
+        crc: bool,
+        options: ReadSettings,
+    ) -> OneWireResult<Option<(i8, i8)>, O::BusError> {
+        let ReadSettings {
+            single,
+            toggle_pio,
+            resolution,
+            ..
+        } = options;
+        Self::select(bus, rom, single)?; // address device
         bus.write_byte(DS28EA00_READ_SCRATCH)?; // Read scratchpad
-        if !crc {
-            let mut buf = [0; 2];
-            for b in buf.iter_mut() {
-                *b = bus.read_byte()?;
-            }
-            *temp = I12F4::from_le_bytes([buf[0] & ReadoutResolution::default().bitmask(), buf[1]]);
+        let mut buf = [0; 9];
+        let buf = if crc { &mut buf[..] } else { &mut buf[..2] };
+        for b in buf.iter_mut() {
+            *b = bus.read_byte()?;
+        }
+        let thresholds = Self::decode_readout(buf, resolution, temp)?;
+        if toggle_pio && Family::from_rom(rom) == Some(Family::Ds28ea00) {
+            Self::select(bus, rom, single)?; // address device
+            bus.write_byte(DS28EA00_TOGGLE_PIO)?;
+            bus.write_byte(DS28EA00_TOGGLE_PIO_ON)?;
+            bus.write_byte(DS28EA00_TOGGLE_PIO_OFF)?;
+        }
+        Ok(thresholds)
+    }
+
+    /// Decodes the temperature from the first two bytes of the scratchpad, or from the full scratchpad
+    /// after validating its CRC.
+    ///
+    /// # Returns
+    /// The TH and TL thresholds stored on the device, if the full scratchpad was read.
+    fn decode_readout<E>(
+        buf: &[u8],
+        resolution: ReadoutResolution,
+        temp: &mut Temperature,
+    ) -> OneWireResult<Option<(i8, i8)>, E> {
+        let full = buf.len() == 9;
+        if full && !OneWireCrc::validate(buf) {
+            return Err(OneWireError::InvalidCrc);
+        }
+        *temp = decode_scratchpad(buf, resolution);
+        Ok(full.then(|| (buf[2] as i8, buf[3] as i8)))
+    }
+
+    fn read_scratchpad_internal<O: OneWire>(
+        bus: &mut O,
+        rom: u64,
+        single: bool,
+    ) -> OneWireResult<[u8; 9], O::BusError> {
+        Self::select(bus, rom, single)?; // address device
+        bus.write_byte(DS28EA00_READ_SCRATCH)?; // Read scratchpad
+        let mut buf = [0; 9];
+        for b in buf.iter_mut() {
+            *b = bus.read_byte()?;
+        }
+        if OneWireCrc::validate(&buf) {
+            Ok(buf)
         } else {
-            let mut buf = [0; 9];
+            Err(OneWireError::InvalidCrc)
+        }
+    }
+
+    /// Sorts the group in the physical order of the chain using the sequence detection function of the DS28EA00.
+    ///
+    /// This requires the EN pin of the first device to be grounded and the DONE pin of every device to be
+    /// connected to the EN pin of the next one. Devices that are not found in the chain, e.g. other families
+    /// which do not support the chain function, are kept at the end of the group in their previous order.
+    /// # Arguments
+    /// * `bus` - A mutable reference to a type that implements the [`OneWire`] trait.
+    /// # Returns
+    /// A result containing the number of devices of the group found in the chain, or an error if the operation fails.
+    pub fn discover_chain_order<O: OneWire>(
+        &mut self,
+        bus: &mut O,
+    ) -> OneWireResult<usize, O::BusError> {
+        bus.address(None)?; // address all devices
+        Self::chain_control(bus, DS28EA00_CHAIN_ON)?;
+        let res = self.sort_by_chain(bus);
+        bus.address(None)?; // address all devices
+        Self::chain_control(bus, DS28EA00_CHAIN_OFF)?;
+        res
+    }
+
+    fn sort_by_chain<O: OneWire>(&mut self, bus: &mut O) -> OneWireResult<usize, O::BusError> {
+        let mut sorted = 0;
+        // every device in the chain is found exactly once, so this bounds the search on a faulty chain
+        for _ in 0..N {
+            // only the device in chain mode with EN low responds
+            bus.reset()?;
+            bus.write_byte(DS28EA00_CONDITIONAL_READ_ROM)?;
+            let mut buf = [0; 8];
             for b in buf.iter_mut() {
                 *b = bus.read_byte()?;
             }
-            if OneWireCrc::validate(&buf) {
-                *temp =
-                    I12F4::from_le_bytes([buf[0] & ReadoutResolution::default().bitmask(), buf[1]]);
-            } else {
+            if buf == [0xff; 8] {
+                break; // end of the chain
+            }
+            if !OneWireCrc::validate(&buf) {
                 return Err(OneWireError::InvalidCrc);
             }
+            // the device stays selected, and pulls the EN pin of the next device low
+            Self::chain_control(bus, DS28EA00_CHAIN_DONE)?;
+            let rom = u64::from_le_bytes(buf);
+            if let Some(idx) = self.roms[sorted..self.devices]
+                .iter()
+                .position(|(r, _)| *r == rom)
+            {
+                self.roms.swap(sorted, sorted + idx);
+                self.state.swap(sorted, sorted + idx);
+                sorted += 1;
+            }
         }
-        if toggle_pio {
-            bus.address(Some(rom))?; // address device
-            bus.write_byte(DS28EA00_TOGGLE_PIO)?;
-            bus.write_byte(DS28EA00_TOGGLE_PIO_ON)?;
-            bus.write_byte(DS28EA00_TOGGLE_PIO_OFF)?;
+        Ok(sorted)
+    }
+
+    fn chain_control<O: OneWire>(bus: &mut O, control: u8) -> OneWireResult<(), O::BusError> {
+        bus.write_byte(DS28EA00_CHAIN)?;
+        bus.write_byte(control)?;
+        bus.write_byte(!control)?;
+        if bus.read_byte()? != DS28EA00_CHAIN_CONFIRM {
+            return Err(OneWireError::InvalidValue("chain command not confirmed"));
         }
         Ok(())
     }
 
     /// Turn on the LED of a DS28EA00 device.
     ///
+    /// This drives PIOA and PIOB in opposite states, see [`pio_write`](Self::pio_write) to set the pins
+    /// independently.
+    ///
     /// # Arguments
     /// * `rom` - The ROM address of the DS28EA00 device.
     /// * `bus` - A mutable reference to a type that implements the [`OneWire`] trait.
@@ -306,13 +1216,197 @@ impl<const N: usize> Ds28ea00Group<N> {
     }
 }
 
-/// Temperature data type used by the DS28EA00 devices.
+#[cfg(feature = "defmt")]
+impl<const N: usize> defmt::Format for Ds28ea00Group<N> {
+    fn format(&self, f: defmt::Formatter) {
+        defmt::write!(
+            f,
+            "Ds28ea00Group {{ devices: {=usize}, resolution: {}, low: {=i8}, high: {=i8}, toggle_pio: {=bool}, overdrive: {=bool} }}",
+            self.devices,
+            self.resolution,
+            self.low,
+            self.high,
+            self.toggle_pio,
+            self.overdrive
+        );
+        for (rom, temp) in self.roms[..self.devices].iter() {
+            defmt::write!(f, "\n  {=u64:#018x}: {=f32} °C", *rom, temp.celsius());
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone)]
+/// Book-keeping for a single device in a [`Ds28ea00Group`].
+struct DeviceState {
+    /// Error encountered during the last detailed readout.
+    error: Option<ReadError>,
+    /// Whether the last configuration readback matched.
+    configured: bool,
+    /// Bus health counters since the last clear.
+    #[cfg(any(test, feature = "stats"))]
+    stats: BusStats,
+}
+
+impl DeviceState {
+    const fn new() -> Self {
+        Self {
+            error: None,
+            configured: true,
+            #[cfg(any(test, feature = "stats"))]
+            stats: BusStats::new(),
+        }
+    }
+}
+
+/// Settings of a [`Ds28ea00Group`] used to read a device.
+#[derive(Debug, Clone, Copy)]
+struct ReadSettings {
+    /// The device is the only one on the bus, and is addressed with a skip ROM.
+    single: bool,
+    toggle_pio: bool,
+    retries: u8,
+    resolution: ReadoutResolution,
+    /// The time after which a device is no longer retried, and the clock measuring it.
+    timeout: Option<(Duration, fn() -> Duration)>,
+}
+
+impl ReadSettings {
+    /// The settings used to read `rom`, at its resolution in `resolutions` if it has its own.
+    fn for_device(self, resolutions: &[(u64, ReadoutResolution)], rom: u64) -> Self {
+        Self {
+            resolution: resolution::lookup(resolutions, self.resolution, rom),
+            ..self
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+/// Reason a single device could not be read by [`Ds28ea00Group::read_temperatures_detailed`].
+pub enum ReadError {
+    /// The scratchpad contents failed the CRC check.
+    InvalidCrc,
+    /// The device did not respond with a presence pulse when addressed.
+    NoDevicePresent,
+    /// A short circuit was detected on the bus.
+    ShortCircuit,
+    /// The bus master reported an error while communicating with the device.
+    Bus,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+/// Reason a ROM code was not added to the group by [`Ds28ea00Group::add_rom`], or to the calibration
+/// table by [`Ds28ea00Group::set_calibration`].
+pub enum RomError {
+    /// The ROM code failed the CRC check.
+    InvalidCrc,
+    /// The family of the device is not enumerated by the group, see [`Ds28ea00Group::with_families`].
+    Unsupported,
+    /// The device is already in the group.
+    Duplicate,
+    /// The group holds `N` devices already.
+    Full,
+}
+
+impl ReadError {
+    /// Returns `true` for the errors caused by noise on the bus, e.g. a corrupted scratchpad or a
+    /// missed presence pulse, which are retried as set by [`Ds28ea00Group::with_retries`].
+    pub fn is_transient(&self) -> bool {
+        matches!(self, ReadError::InvalidCrc | ReadError::NoDevicePresent)
+    }
+}
+
+impl<E> From<&OneWireError<E>> for ReadError {
+    fn from(value: &OneWireError<E>) -> Self {
+        match value {
+            OneWireError::InvalidCrc => ReadError::InvalidCrc,
+            OneWireError::NoDevicePresent => ReadError::NoDevicePresent,
+            OneWireError::ShortCircuit => ReadError::ShortCircuit,
+            _ => ReadError::Bus,
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, Default)]
+/// Order of the devices in a [`Ds28ea00Group`], see [`Ds28ea00Group::with_sort_order`].
+pub enum SortOrder {
+    /// The order in which the search finds the devices, i.e. ascending ROM codes read from the least
+    /// significant bit, which changes when devices are added or removed.
+    #[default]
+    Search,
+    /// Ascending ROM codes.
+    Rom,
+    /// Ascending value of a key computed from the ROM code, e.g. the CRC32 hash used as a sensor ID.
+    /// Devices with the same key are kept in the order of the search.
+    Key(fn(u64) -> u32),
+}
+
+#[repr(u8)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+/// 1-Wire temperature sensor families supported by [`Ds28ea00Group`].
+pub enum Family {
+    /// DS28EA00 digital thermometer with sequence detect and PIO.
+    Ds28ea00 = 0x42,
+    /// DS18B20 programmable resolution digital thermometer.
+    Ds18b20 = 0x28,
+    /// DS1822 econo digital thermometer.
+    Ds1822 = 0x22,
+}
+
+impl Family {
+    /// Returns the family code of the devices.
+    pub const fn code(&self) -> u8 {
+        *self as u8
+    }
+
+    /// Returns the family of the device with the given ROM code, if supported.
+    pub fn from_rom(rom: u64) -> Option<Self> {
+        Self::try_from(rom.to_le_bytes()[0]).ok()
+    }
+
+    const fn mask(&self) -> u8 {
+        match self {
+            Family::Ds28ea00 => 0b001,
+            Family::Ds18b20 => 0b010,
+            Family::Ds1822 => 0b100,
+        }
+    }
+}
+
+impl TryFrom<u8> for Family {
+    type Error = &'static str;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0x42 => Ok(Family::Ds28ea00),
+            0x28 => Ok(Family::Ds18b20),
+            0x22 => Ok(Family::Ds1822),
+            _ => Err("Unsupported family code"),
+        }
+    }
+}
+
+/// Decodes the temperature from the first two bytes of a scratchpad read at `resolution`.
+///
+/// The bits of the temperature register below the resolution are undefined and cleared, so a
+/// 9-bit reading is a multiple of 0.5 °C. The devices hold 85 °C from power-on until their first
+/// conversion.
 ///
-/// This type represents a temperature value with a fixed-point format of 12 bits for the integer part and 4 bits for the fractional part.
-pub type Temperature = I12F4;
+/// # Panics
+/// If `bytes` holds fewer than two bytes.
+pub fn decode_scratchpad(bytes: &[u8], resolution: ReadoutResolution) -> Temperature {
+    I12F4::from_le_bytes([bytes[0] & resolution.bitmask(), bytes[1]]).into()
+}
 
 #[repr(u8)]
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 /// Represents the readout resolution of the DS28EA00 devices.
 /// The resolution determines the time required for the temperature conversion and the precision of the temperature readings.
 pub enum ReadoutResolution {
@@ -322,17 +1416,40 @@ pub enum ReadoutResolution {
     Resolution10bit = 0x3f,
     /// 11-bit resolution, with a conversion time of 375 ms.
     Resolution11bit = 0x5f,
+    #[default]
     /// 12-bit resolution, with a conversion time of 750 ms.
     Resolution12bit = 0x7f,
 }
 
-impl Default for ReadoutResolution {
-    fn default() -> Self {
-        Self::Resolution12bit
+impl ReadoutResolution {
+    /// Returns the time a temperature conversion takes at this resolution.
+    pub fn conversion_time(&self) -> Duration {
+        Duration::from_micros(self.delay_us() as _)
+    }
+
+    /// Returns the number of bits of the temperature readings at this resolution.
+    pub fn bits(&self) -> u8 {
+        use ReadoutResolution::*;
+        match self {
+            Resolution9bit => 9,
+            Resolution10bit => 10,
+            Resolution11bit => 11,
+            Resolution12bit => 12,
+        }
+    }
+
+    /// Returns the highest resolution whose conversion completes within `period`.
+    ///
+    /// Falls back to [`ReadoutResolution::Resolution9bit`] if `period` is shorter than
+    /// every conversion time.
+    pub fn for_period(period: Duration) -> Self {
+        use ReadoutResolution::*;
+        [Resolution12bit, Resolution11bit, Resolution10bit]
+            .into_iter()
+            .find(|res| res.conversion_time() <= period)
+            .unwrap_or(Resolution9bit)
     }
-}
 
-impl ReadoutResolution {
     pub(crate) fn delay_us(&self) -> u32 {
         use ReadoutResolution::*;
         match self {
@@ -370,11 +1487,13 @@ impl TryFrom<u8> for ReadoutResolution {
     }
 }
 
-#[allow(unused)]
+/// Time the bus is left idle after switching to overdrive speed, in microseconds.
+const OVERDRIVE_SETTLE_US: u32 = 100;
 const DS28EA00_READ_SCRATCH: u8 = 0xbe;
 const DS28EA00_WRITE_SCRATCH: u8 = 0x4e;
-#[allow(unused)]
 const DS28EA00_COPY_SCRATCH: u8 = 0x48;
+/// Time the devices take to copy the scratchpad to the EEPROM, in microseconds.
+const DS28EA00_COPY_SCRATCH_US: u32 = 10_000;
 const DS28EA00_START_CONV: u8 = 0x44;
 #[allow(unused)]
 const DS28EA00_READ_POWERMODE: u8 = 0xb4;
@@ -383,3 +1502,711 @@ const DS28EA00_RECALL_EEPROM: u8 = 0xb8;
 const DS28EA00_TOGGLE_PIO: u8 = 0xa5;
 const DS28EA00_TOGGLE_PIO_ON: u8 = 0b11111101;
 const DS28EA00_TOGGLE_PIO_OFF: u8 = !0b11111101;
+const DS28EA00_PIO_CONFIRM: u8 = 0xaa;
+const DS28EA00_READ_PIO: u8 = 0xf5;
+const DS28EA00_CHAIN: u8 = 0x99;
+const DS28EA00_CHAIN_ON: u8 = 0x5a;
+const DS28EA00_CHAIN_OFF: u8 = 0x3c;
+const DS28EA00_CHAIN_DONE: u8 = 0x96;
+const DS28EA00_CHAIN_CONFIRM: u8 = 0xaa;
+const DS28EA00_CONDITIONAL_READ_ROM: u8 = 0x0f;
+const ONEWIRE_READ_ROM: u8 = 0x33;
+const ONEWIRE_MATCH_ROM: u8 = 0x55;
+const ONEWIRE_MATCH_ROM_OD: u8 = 0x69;
+const ONEWIRE_SKIP_ROM: u8 = 0xcc;
+const ONEWIRE_SKIP_ROM_OD: u8 = 0x3c;
+
+mod test {
+    #[test]
+    fn test_enumerate_and_read() {
+        use super::{Ds28ea00Group, Family, ReadOptions, Temperature, mock::*};
+        let mut devices = [
+            MockDevice::new(0x42, 0x1234, Temperature::from_millidegrees(21_500)),
+            MockDevice::new(0x42, 0x5678, Temperature::from_millidegrees(-10_125)),
+            MockDevice::new(0x28, 0x9abc, Temperature::from_millidegrees(30_500)),
+            MockDevice::new(0x10, 0xdef0, Temperature::ZERO), // unsupported family
+        ];
+        let roms = devices.map(|dev| dev.rom());
+        let mut bus = MockBus::new(&mut devices);
+        let mut group = Ds28ea00Group::<4>::default()
+            .with_families(&[Family::Ds28ea00, Family::Ds18b20])
+            .with_t_low(-20)
+            .with_t_high(40)
+            .with_toggle_pio(false);
+        assert_eq!(group.enumerate(&mut bus).unwrap(), 3);
+        assert!(!group.single_device());
+        assert!(group.roms().all(|rom| roms[..3].contains(&rom)));
+        assert_eq!(bus.devices()[0].configuration().0, 40);
+        assert_eq!(bus.devices()[0].configuration().1, -20);
+        assert_eq!(group.verify_configuration(&mut bus).unwrap().count(), 0);
+        group.start_temperature_conversion(&mut bus).unwrap();
+        assert_eq!(bus.conversions(), 1);
+        let temps = group
+            .read_temperatures(&mut bus, ReadOptions::new())
+            .unwrap();
+        for (rom, temp) in temps {
+            let expected = match roms.iter().position(|r| r == rom).unwrap() {
+                0 => 21_500,
+                1 => -10_125,
+                _ => 30_500,
+            };
+            assert_eq!(temp.millidegrees(), expected);
+        }
+        group.led_toggle(&mut bus, roms[1], true).unwrap();
+        assert!(!bus.devices()[0].led());
+        assert!(bus.devices()[1].led());
+    }
+
+    #[test]
+    fn test_add_remove_rom() {
+        use super::{Ds28ea00Group, Family, RomError, Temperature, mock::*};
+        use alloc::vec::Vec;
+        let mut devices = [
+            MockDevice::new(0x42, 0x1234, Temperature::from_millidegrees(21_500)),
+            MockDevice::new(0x42, 0x5678, Temperature::from_millidegrees(-10_125)),
+            MockDevice::new(0x28, 0x9abc, Temperature::from_millidegrees(30_500)),
+        ];
+        let roms = devices.map(|dev| dev.rom());
+        let mut bus = MockBus::new(&mut devices);
+        let mut group = Ds28ea00Group::<2>::default().with_t_high(40);
+        assert_eq!(group.add_rom(roms[1]), Ok(1));
+        assert_eq!(group.add_rom(roms[1]), Err(RomError::Duplicate));
+        assert_eq!(group.add_rom(roms[2]), Err(RomError::Unsupported));
+        assert_eq!(group.add_rom(roms[0] ^ 1), Err(RomError::InvalidCrc));
+        assert_eq!(group.add_rom(roms[0]), Ok(2));
+        let extra = MockDevice::new(0x42, 0xdef0, Temperature::ZERO).rom();
+        assert_eq!(group.add_rom(extra), Err(RomError::Full));
+        assert!(!group.single_device());
+        assert_eq!(group.roms().collect::<Vec<_>>(), [roms[1], roms[0]]);
+        // only the devices of the group are configured
+        assert_eq!(group.apply_configuration(&mut bus).unwrap(), 2);
+        assert_eq!(bus.devices()[0].configuration().0, 40);
+        assert_ne!(bus.devices()[2].configuration().0, 40);
+        let temps: Vec<_> = group.read_temperatures_detailed(&mut bus, true).collect();
+        assert_eq!(temps[0].1.unwrap().millidegrees(), -10_125);
+        assert_eq!(temps[1].1.unwrap().millidegrees(), 21_500);
+        assert!(group.remove_rom(roms[1]));
+        assert!(!group.remove_rom(roms[1]));
+        assert_eq!(group.roms().collect::<Vec<_>>(), [roms[0]]);
+        let temps: Vec<_> = group.read_temperatures_detailed(&mut bus, true).collect();
+        assert_eq!(temps.len(), 1);
+        assert_eq!(temps[0].1.unwrap().millidegrees(), 21_500);
+        let mut group = Ds28ea00Group::<2>::default().with_families(&[Family::Ds18b20]);
+        assert_eq!(group.add_rom(roms[2]), Ok(1));
+    }
+
+    #[test]
+    fn test_read_iter() {
+        use super::{Ds28ea00Group, ReadError, Temperature, mock::*};
+        let mut devices = [
+            MockDevice::new(0x42, 0x1234, Temperature::from_millidegrees(20_000)),
+            MockDevice::new(0x42, 0x5678, Temperature::from_millidegrees(21_000)),
+            MockDevice::new(0x42, 0x9abc, Temperature::from_millidegrees(22_000)),
+        ];
+        let mut bus = MockBus::new(&mut devices);
+        let mut group = Ds28ea00Group::<3>::default();
+        assert_eq!(group.enumerate(&mut bus).unwrap(), 3);
+        let roms = [0, 1, 2].map(|i| group.roms().nth(i).unwrap());
+        // only the first device is read
+        let (rom, res) = group.read_temperatures_iter(&mut bus, true).next().unwrap();
+        assert_eq!(rom, roms[0]);
+        assert!(res.is_ok());
+        bus.device_mut(roms[1])
+            .unwrap()
+            .set_corrupt_scratchpad(true);
+        let mut buf = [(0, Temperature::ZERO); 1];
+        assert_eq!(
+            group
+                .read_temperatures_into(&mut bus, true, &mut buf)
+                .unwrap(),
+            1
+        );
+        assert_eq!(buf[0].0, roms[0]);
+        let mut buf = [(0, Temperature::ZERO); 4];
+        assert!(
+            group
+                .read_temperatures_into(&mut bus, true, &mut buf)
+                .is_err()
+        );
+        bus.device_mut(roms[1])
+            .unwrap()
+            .set_corrupt_scratchpad(false);
+        assert_eq!(
+            group
+                .read_temperatures_into(&mut bus, true, &mut buf)
+                .unwrap(),
+            3
+        );
+        // a missing device is read as all ones, as long as the other devices answer the reset
+        bus.device_mut(roms[2]).unwrap().set_present(false);
+        let (rom, res) = group.read_temperatures_iter(&mut bus, true).last().unwrap();
+        assert_eq!((rom, res), (roms[2], Err(ReadError::InvalidCrc)));
+    }
+
+    #[test]
+    fn test_conversion_session() {
+        use super::{Ds28ea00Group, ReadoutResolution, Temperature, mock::*};
+        let mut devices = [
+            MockDevice::new(0x42, 0x1234, Temperature::from_millidegrees(20_000)),
+            MockDevice::new(0x42, 0x5678, Temperature::from_millidegrees(21_000)),
+        ];
+        let mut bus = MockBus::new(&mut devices);
+        let mut group =
+            Ds28ea00Group::<2>::default().with_resolution(ReadoutResolution::Resolution9bit);
+        group.enumerate(&mut bus).unwrap();
+        let session = group.begin_conversion(&mut bus).unwrap();
+        assert_eq!(
+            session.delay(),
+            ReadoutResolution::Resolution9bit.conversion_time()
+        );
+        assert_eq!(session.devices(), 2);
+        assert_eq!(
+            session.ready_at(core::time::Duration::from_millis(1_000)),
+            core::time::Duration::from_micros(1_093_750)
+        );
+        assert_eq!(bus.conversions(), 1);
+        assert!(session.collect(&mut bus, true).all(|(_, res)| res.is_ok()));
+    }
+
+    #[test]
+    fn test_enumerate_filtered() {
+        use super::{Ds28ea00Group, ReadOptions, Temperature, mock::*};
+        let mut devices = [0x1234, 0x5678, 0x9abc]
+            .map(|serial| MockDevice::new(0x42, serial, Temperature::from_millidegrees(21_000)));
+        let roms = devices.map(|dev| dev.rom());
+        let mut bus = MockBus::new(&mut devices);
+        let unconfigured = bus.devices()[1].configuration();
+        let mut group = Ds28ea00Group::<4>::default()
+            .with_t_high(40)
+            .with_toggle_pio(false);
+        assert_eq!(
+            group
+                .enumerate_filtered(&mut bus, |rom| rom != roms[1])
+                .unwrap(),
+            2
+        );
+        assert!(group.roms().all(|rom| rom != roms[1]));
+        // the devices left out are not configured
+        assert_eq!(bus.devices()[0].configuration().0, 40);
+        assert_eq!(bus.devices()[1].configuration(), unconfigured);
+        let temps = group
+            .read_temperatures(&mut bus, ReadOptions::new())
+            .unwrap();
+        assert_eq!(temps.len(), 2);
+        // the only device kept is still addressed by its ROM
+        assert_eq!(
+            group
+                .enumerate_filtered(&mut bus, |rom| rom == roms[2])
+                .unwrap(),
+            1
+        );
+        assert!(!group.single_device());
+        // the devices left out do not take a slot of a full table
+        let mut group = Ds28ea00Group::<2>::default().with_toggle_pio(false);
+        assert_eq!(
+            group
+                .enumerate_filtered(&mut bus, |rom| rom != roms[0])
+                .unwrap(),
+            2
+        );
+        assert!(group.roms().eq([roms[1], roms[2]]));
+    }
+
+    #[test]
+    fn test_single_device() {
+        use super::{Ds28ea00Group, ReadOptions, Temperature, mock::*};
+        let mut devices = [MockDevice::new(
+            0x42,
+            0x1234,
+            Temperature::from_millidegrees(25_000),
+        )];
+        let mut bus = MockBus::new(&mut devices);
+        let mut group = Ds28ea00Group::<2>::default().with_toggle_pio(false);
+        assert_eq!(group.enumerate(&mut bus).unwrap(), 1);
+        assert!(group.single_device());
+        let temps = group
+            .read_temperatures(&mut bus, ReadOptions::new())
+            .unwrap();
+        assert_eq!(temps[0].1.millidegrees(), 25_000);
+        let mut group = Ds28ea00Group::<2>::default().assume_single_device();
+        assert_eq!(group.enumerate(&mut bus).unwrap(), 1);
+        assert_eq!(group.roms().next(), Some(bus.devices()[0].rom()));
+    }
+
+    #[test]
+    fn test_read_errors() {
+        use super::{Ds28ea00Group, ErrorPolicy, ReadError, ReadOptions, Temperature, mock::*};
+        use embedded_onewire::OneWireError;
+        let mut devices = [
+            MockDevice::new(0x42, 0x1234, Temperature::from_millidegrees(21_000)),
+            MockDevice::new(0x42, 0x5678, Temperature::from_millidegrees(22_000)),
+        ];
+        let corrupt = devices[1].rom();
+        let mut bus = MockBus::new(&mut devices);
+        let mut group = Ds28ea00Group::<2>::default().with_toggle_pio(false);
+        assert_eq!(group.enumerate(&mut bus).unwrap(), 2);
+        bus.device_mut(corrupt)
+            .unwrap()
+            .set_corrupt_scratchpad(true);
+        for (rom, res) in group.read_temperatures_detailed(&mut bus, true) {
+            if rom == corrupt {
+                assert_eq!(res, Err(ReadError::InvalidCrc));
+            } else {
+                assert_eq!(res.unwrap().millidegrees(), 21_000);
+            }
+        }
+        // the CRC is not checked
+        assert_eq!(group.read_temperatures_detailed(&mut bus, false).count(), 2);
+        assert!(matches!(
+            group.read_temperatures(&mut bus, ReadOptions::new()),
+            Err(OneWireError::InvalidCrc)
+        ));
+        // a sentinel temperature replaces the failed readings
+        let temps = group
+            .read_temperatures(
+                &mut bus,
+                ReadOptions::new().with_errors(ErrorPolicy::Substitute),
+            )
+            .unwrap();
+        assert!(temps.iter().any(|(_, temp)| temp.millidegrees() == -85_000));
+        // glitches on the bus fail the devices addressed during the glitch only
+        bus.fail_resets(1);
+        let errors = group
+            .read_temperatures_detailed(&mut bus, true)
+            .filter(|(_, res)| *res == Err(ReadError::NoDevicePresent))
+            .count();
+        assert_eq!(errors, 1);
+        bus.set_short_circuit(true);
+        assert!(matches!(
+            group.start_temperature_conversion(&mut bus),
+            Err(OneWireError::ShortCircuit)
+        ));
+        bus.set_short_circuit(false);
+        for dev in [0, 1].map(|i| bus.devices()[i].rom()) {
+            bus.device_mut(dev).unwrap().set_present(false);
+        }
+        assert!(matches!(
+            group.enumerate(&mut bus),
+            Err(OneWireError::NoDevicePresent)
+        ));
+    }
+
+    #[test]
+    fn test_overdrive() {
+        use super::{Ds28ea00Group, Temperature, mock::*};
+        use embedded_hal::delay::DelayNs;
+        use embedded_onewire::{OneWire, OneWireError};
+        struct NoDelay;
+        impl DelayNs for NoDelay {
+            fn delay_ns(&mut self, _ns: u32) {}
+        }
+        let mut devices = [
+            MockDevice::new(0x42, 0x1234, Temperature::from_millidegrees(21_000)),
+            MockDevice::new(0x42, 0x5678, Temperature::from_millidegrees(22_000)),
+        ];
+        let mut bus = MockBus::new(&mut devices);
+        let mut group = Ds28ea00Group::<2>::default();
+        assert_eq!(group.enumerate(&mut bus).unwrap(), 2);
+        assert!(group.try_enable_overdrive(&mut bus, &mut NoDelay).unwrap());
+        assert!(group.overdrive() && bus.get_overdrive_mode());
+        // devices that do not follow to overdrive speed leave the group at standard speed
+        group.disable_overdrive(&mut bus).unwrap();
+        bus.set_overdrive_capable(false);
+        assert!(!group.try_enable_overdrive(&mut bus, &mut NoDelay).unwrap());
+        assert!(!group.overdrive() && !bus.get_overdrive_mode());
+        assert!(
+            group
+                .read_temperatures_detailed(&mut bus, true)
+                .all(|(_, res)| res.is_ok())
+        );
+        // errors other than a missing device are returned once back at standard speed
+        bus.set_short_circuit(true);
+        assert!(matches!(
+            group.try_enable_overdrive(&mut bus, &mut NoDelay),
+            Err(OneWireError::ShortCircuit)
+        ));
+        assert!(!group.overdrive());
+    }
+
+    #[test]
+    fn test_set_resolution() {
+        use super::{Ds28ea00Group, ReadOptions, ReadoutResolution, Temperature, mock::*};
+        use embedded_hal::delay::DelayNs;
+        struct NoDelay;
+        impl DelayNs for NoDelay {
+            fn delay_ns(&mut self, _ns: u32) {}
+        }
+        let mut devices = [
+            MockDevice::new(0x42, 0x1234, Temperature::from_millidegrees(21_125)),
+            MockDevice::new(0x42, 0x5678, Temperature::from_millidegrees(22_125)),
+        ];
+        let mut bus = MockBus::new(&mut devices);
+        let mut group = Ds28ea00Group::<2>::default();
+        assert_eq!(group.enumerate(&mut bus).unwrap(), 2);
+        let roms: [u64; 2] = core::array::from_fn(|i| group.roms().nth(i).unwrap());
+        let temps = group
+            .read_temperatures(&mut bus, ReadOptions::new())
+            .unwrap();
+        assert!(
+            temps
+                .iter()
+                .all(|(_, temp)| temp.millidegrees() % 1000 == 125)
+        );
+        // switched to fast readings, without touching the EEPROM
+        let res = ReadoutResolution::Resolution9bit;
+        assert_eq!(
+            group
+                .set_resolution(&mut bus, &mut NoDelay, res, false)
+                .unwrap(),
+            2
+        );
+        assert_eq!(group.conversion_time(), res.conversion_time());
+        assert_eq!(group.verify_configuration(&mut bus).unwrap().count(), 0);
+        for rom in roms {
+            let dev = bus.device_mut(rom).unwrap();
+            assert_eq!(dev.configuration().2, res as u8);
+            assert_ne!(dev.eeprom().2, res as u8);
+        }
+        let temps = group
+            .read_temperatures(&mut bus, ReadOptions::new())
+            .unwrap();
+        assert!(
+            temps
+                .iter()
+                .all(|(_, temp)| temp.millidegrees() % 1000 == 0)
+        );
+        // and persisted
+        let res = ReadoutResolution::Resolution11bit;
+        group
+            .set_resolution(&mut bus, &mut NoDelay, res, true)
+            .unwrap();
+        for rom in roms {
+            assert_eq!(bus.device_mut(rom).unwrap().eeprom().2, res as u8);
+        }
+    }
+
+    #[test]
+    fn test_retries() {
+        use super::{Ds28ea00Group, ReadError, ReadOptions, Temperature, mock::*};
+        use embedded_onewire::OneWireError;
+        let mut devices = [
+            MockDevice::new(0x42, 0x1234, Temperature::from_millidegrees(21_000)),
+            MockDevice::new(0x42, 0x5678, Temperature::from_millidegrees(22_000)),
+        ];
+        let corrupt = devices[1].rom();
+        let mut bus = MockBus::new(&mut devices);
+        let mut group = Ds28ea00Group::<2>::default();
+        bus.fail_resets(1);
+        assert!(matches!(
+            group.enumerate(&mut bus),
+            Err(OneWireError::NoDevicePresent)
+        ));
+        let mut group = Ds28ea00Group::<2>::default().with_retries(2);
+        bus.fail_resets(2);
+        assert_eq!(group.enumerate(&mut bus).unwrap(), 2);
+        // a glitch is retried, even through a failed reset in between
+        bus.fail_resets(2);
+        assert!(
+            group
+                .read_temperatures_detailed(&mut bus, true)
+                .all(|(_, res)| res.is_ok())
+        );
+        // a persistent error is returned once the retries are exhausted
+        bus.device_mut(corrupt)
+            .unwrap()
+            .set_corrupt_scratchpad(true);
+        for (rom, res) in group.read_temperatures_detailed(&mut bus, true) {
+            assert_eq!(res.is_err(), rom == corrupt);
+            if let Err(e) = res {
+                assert_eq!(e, ReadError::InvalidCrc);
+                assert!(e.is_transient());
+            }
+        }
+        bus.set_short_circuit(true);
+        assert!(matches!(
+            group.read_temperatures(&mut bus, ReadOptions::new()),
+            Err(OneWireError::ShortCircuit)
+        ));
+        assert!(!ReadError::ShortCircuit.is_transient());
+    }
+
+    #[test]
+    fn test_read_options() {
+        use super::{CrcPolicy, Ds28ea00Group, ErrorPolicy, ReadOptions, Temperature, mock::*};
+        use core::{
+            sync::atomic::{AtomicU64, Ordering},
+            time::Duration,
+        };
+        use embedded_onewire::OneWireError;
+        let mut devices = [
+            MockDevice::new(0x42, 0x1234, Temperature::from_millidegrees(21_000)),
+            MockDevice::new(0x42, 0x5678, Temperature::from_millidegrees(22_000)),
+        ];
+        let corrupt = devices[1].rom();
+        let mut bus = MockBus::new(&mut devices);
+        let mut group = Ds28ea00Group::<2>::default().with_retries(2);
+        assert_eq!(group.enumerate(&mut bus).unwrap(), 2);
+        // the retries of the group are overridden
+        bus.fail_resets(1);
+        assert!(
+            group
+                .read_temperatures(&mut bus, ReadOptions::new())
+                .is_ok()
+        );
+        bus.fail_resets(1);
+        assert!(matches!(
+            group.read_temperatures(&mut bus, ReadOptions::new().with_retries(0)),
+            Err(OneWireError::NoDevicePresent)
+        ));
+        // a device is no longer retried once its timeout has elapsed
+        static NOW: AtomicU64 = AtomicU64::new(0);
+        let clock = || Duration::from_millis(NOW.fetch_add(10, Ordering::Relaxed));
+        bus.fail_resets(1);
+        assert!(
+            group
+                .read_temperatures(
+                    &mut bus,
+                    ReadOptions::new().with_timeout(Duration::from_millis(50), clock)
+                )
+                .is_ok()
+        );
+        bus.fail_resets(1);
+        assert!(matches!(
+            group.read_temperatures(
+                &mut bus,
+                ReadOptions::new().with_timeout(Duration::from_millis(5), clock)
+            ),
+            Err(OneWireError::NoDevicePresent)
+        ));
+        // the corrupted scratchpad is only detected with a CRC check
+        bus.device_mut(corrupt)
+            .unwrap()
+            .set_corrupt_scratchpad(true);
+        let options = ReadOptions::new()
+            .with_crc(CrcPolicy::Skip)
+            .with_errors(ErrorPolicy::Substitute);
+        let temps = group.read_temperatures(&mut bus, options).unwrap();
+        assert!(temps.iter().all(|(_, temp)| *temp != ErrorPolicy::SENTINEL));
+        let temps = group
+            .read_temperatures(&mut bus, options.with_crc(CrcPolicy::Verify))
+            .unwrap();
+        for (rom, temp) in temps {
+            assert_eq!(*temp == ErrorPolicy::SENTINEL, *rom == corrupt);
+        }
+    }
+
+    #[test]
+    fn test_scratchpad() {
+        use super::{Ds28ea00Group, Temperature, mock::*};
+        use embedded_onewire::OneWireError;
+        let mut devices = [
+            MockDevice::new(0x42, 0x1234, Temperature::from_millidegrees(21_000)),
+            MockDevice::new(0x42, 0x5678, Temperature::ZERO),
+        ];
+        let roms = devices.map(|dev| dev.rom());
+        let mut bus = MockBus::new(&mut devices);
+        let mut group = Ds28ea00Group::<2>::default()
+            .with_t_high(40)
+            .with_t_low(-10);
+        assert_eq!(group.enumerate(&mut bus).unwrap(), 2);
+        let scratchpad = group.read_scratchpad(&mut bus, roms[0]).unwrap();
+        assert_eq!(scratchpad[..2], (21i16 << 4).to_le_bytes());
+        assert_eq!(scratchpad[2..4], [40, (-10i8) as u8]);
+        // only the addressed device is written
+        group
+            .write_scratchpad(&mut bus, roms[1], 30, -20, 0x3f)
+            .unwrap();
+        assert_eq!(
+            bus.device_mut(roms[1]).unwrap().configuration(),
+            (30, -20, 0x3f)
+        );
+        assert_eq!(
+            group.read_scratchpad(&mut bus, roms[0]).unwrap()[2..4],
+            [40, (-10i8) as u8]
+        );
+        bus.device_mut(roms[1])
+            .unwrap()
+            .set_corrupt_scratchpad(true);
+        assert!(matches!(
+            group.read_scratchpad(&mut bus, roms[1]),
+            Err(OneWireError::InvalidCrc)
+        ));
+    }
+
+    #[test]
+    fn test_configuration_order() {
+        use super::{Ds28ea00Group, ReadoutResolution, Temperature, mock::*};
+        let mut devices = [
+            MockDevice::new(0x42, 0x1234, Temperature::ZERO),
+            MockDevice::new(0x42, 0x5678, Temperature::ZERO),
+        ];
+        let roms = devices.map(|dev| dev.rom());
+        let mut bus = MockBus::new(&mut devices);
+        let mut group = Ds28ea00Group::<2>::default()
+            .with_t_high(55)
+            .with_t_low(-25)
+            .with_resolution(ReadoutResolution::Resolution10bit);
+        // TH lands in byte 2 and TL in byte 3, broadcast and written per device
+        assert_eq!(group.enumerate(&mut bus).unwrap(), 2);
+        let scratchpad = group.read_scratchpad(&mut bus, roms[0]).unwrap();
+        assert_eq!(scratchpad[2..5], [55, (-25i8) as u8, 0x3f]);
+        group
+            .set_device_resolution(roms[1], ReadoutResolution::Resolution9bit)
+            .unwrap();
+        assert_eq!(group.enumerate(&mut bus).unwrap(), 2);
+        let scratchpad = group.read_scratchpad(&mut bus, roms[1]).unwrap();
+        assert_eq!(scratchpad[2..5], [55, (-25i8) as u8, 0x1f]);
+    }
+
+    #[test]
+    fn test_alarms() {
+        use super::{AlarmDirection, Ds28ea00Group, Temperature, mock::*};
+        let mut devices = [
+            MockDevice::new(0x42, 0x1234, Temperature::from_millidegrees(21_000)),
+            MockDevice::new(0x42, 0x5678, Temperature::from_millidegrees(60_000)),
+            MockDevice::new(0x42, 0x9abc, Temperature::from_millidegrees(-45_000)),
+        ];
+        let roms = devices.map(|dev| dev.rom());
+        let mut bus = MockBus::new(&mut devices);
+        let mut group = Ds28ea00Group::<3>::default()
+            .with_t_high(50)
+            .with_t_low(-40);
+        assert_eq!(group.enumerate(&mut bus).unwrap(), 3);
+        let alarms = |group: &mut Ds28ea00Group<3>, bus: &mut MockBus, crc| {
+            let mut alarms = [None; 3];
+            // in the order of the devices, not of the search
+            for (rom, res) in group.read_readings_iter(bus, crc) {
+                let idx = roms.iter().position(|r| *r == rom).unwrap();
+                alarms[idx] = res.unwrap().alarm;
+            }
+            alarms
+        };
+        let expected = [None, Some(AlarmDirection::High), Some(AlarmDirection::Low)];
+        assert_eq!(alarms(&mut group, &mut bus, true), expected);
+        assert_eq!(alarms(&mut group, &mut bus, false), expected);
+        // the thresholds stored on the device take precedence, if they are read
+        group
+            .write_scratchpad(&mut bus, roms[0], 20, -40, 0x7f)
+            .unwrap();
+        assert_eq!(
+            alarms(&mut group, &mut bus, true)[0],
+            Some(AlarmDirection::High)
+        );
+        assert_eq!(alarms(&mut group, &mut bus, false)[0], None);
+        let (_, reading) = group
+            .read_readings_iter(&mut bus, true)
+            .find(|(rom, _)| *rom == roms[0])
+            .unwrap();
+        assert_eq!(
+            reading.unwrap().temperature,
+            Temperature::from_millidegrees(21_000)
+        );
+    }
+
+    #[test]
+    fn test_pio() {
+        use super::{Ds28ea00Group, Family, PioState, Temperature, mock::*};
+        let mut devices = [
+            MockDevice::new(0x42, 0x1234, Temperature::ZERO),
+            MockDevice::new(0x28, 0x5678, Temperature::ZERO),
+        ];
+        let roms = devices.map(|dev| dev.rom());
+        let mut bus = MockBus::new(&mut devices);
+        let mut group = Ds28ea00Group::<2>::default()
+            .with_families(&[Family::Ds28ea00, Family::Ds18b20])
+            .with_toggle_pio(false);
+        assert_eq!(group.enumerate(&mut bus).unwrap(), 2);
+        let state = group.pio_write(&mut bus, roms[0], false, true).unwrap();
+        assert_eq!(
+            state,
+            PioState {
+                a: false,
+                b: true,
+                latch_a: false,
+                latch_b: true
+            }
+        );
+        assert_eq!(bus.devices()[0].pio_latches(), (false, true));
+        // a released pin reads the level of the external circuit
+        bus.device_mut(roms[0]).unwrap().set_pio_inputs(true, false);
+        let state = group.pio_read(&mut bus, roms[0]).unwrap();
+        assert!(!state.a && !state.b && state.latch_b);
+        bus.device_mut(roms[0]).unwrap().set_present(false);
+        assert!(group.pio_read(&mut bus, roms[0]).is_err());
+        assert!(group.pio_write(&mut bus, roms[1], true, true).is_err());
+    }
+
+    #[test]
+    fn test_sort_order() {
+        use super::{Ds28ea00Group, SortOrder, Temperature, mock::*};
+        let mut devices =
+            [0x30, 0x11, 0x21, 0x02].map(|serial| MockDevice::new(0x42, serial, Temperature::ZERO));
+        let roms = devices.map(|dev| dev.rom());
+        let mut bus = MockBus::new(&mut devices);
+        let mut group = Ds28ea00Group::<4>::default().with_sort_order(SortOrder::Rom);
+        assert_eq!(group.enumerate(&mut bus).unwrap(), 4);
+        let mut sorted = roms;
+        sorted.sort();
+        assert!(group.roms().eq(sorted));
+        // the order is kept when a device goes missing
+        bus.device_mut(roms[2]).unwrap().set_present(false);
+        group.enumerate(&mut bus).unwrap();
+        assert!(
+            group
+                .roms()
+                .eq(sorted.into_iter().filter(|rom| *rom != roms[2]))
+        );
+        bus.device_mut(roms[2]).unwrap().set_present(true);
+        let mut group = Ds28ea00Group::<4>::default()
+            .with_sort_order(SortOrder::Key(|rom| !(rom >> 8) as u32))
+            .with_device_order(&[roms[1], 0x1234]);
+        group.enumerate(&mut bus).unwrap();
+        assert!(group.roms().eq([roms[1], roms[0], roms[2], roms[3]]));
+    }
+
+    #[test]
+    fn test_decode_scratchpad() {
+        use super::{ReadoutResolution::*, decode_scratchpad};
+        // temperature register values of the datasheet, at 12 bits
+        for (bytes, celsius) in [
+            ([0xd0, 0x07], 125.0),
+            ([0x50, 0x05], 85.0), // power-on value
+            ([0x91, 0x01], 25.0625),
+            ([0xa2, 0x00], 10.125),
+            ([0x08, 0x00], 0.5),
+            ([0x00, 0x00], 0.0),
+            ([0xf8, 0xff], -0.5),
+            ([0x5e, 0xff], -10.125),
+            ([0x6f, 0xfe], -25.0625),
+            ([0xb0, 0xfa], -85.0),
+            ([0x90, 0xfc], -55.0),
+        ] {
+            assert_eq!(
+                decode_scratchpad(&bytes, Resolution12bit).celsius(),
+                celsius
+            );
+        }
+        // undefined bits below the resolution are cleared, rounding towards minus infinity
+        for (resolution, positive, negative) in [
+            (Resolution12bit, 25.4375, -25.0625),
+            (Resolution11bit, 25.375, -25.125),
+            (Resolution10bit, 25.25, -25.25),
+            (Resolution9bit, 25.0, -25.5),
+        ] {
+            assert_eq!(
+                decode_scratchpad(&[0x97, 0x01], resolution).celsius(),
+                positive
+            );
+            assert_eq!(
+                decode_scratchpad(&[0x6f, 0xfe], resolution).celsius(),
+                negative
+            );
+        }
+        // the rest of the scratchpad is ignored
+        let scratchpad = [0x50, 0x05, 0x55, 0x00, 0x7f, 0xff, 0x0c, 0x10, 0x21];
+        assert_eq!(
+            decode_scratchpad(&scratchpad, Resolution12bit).celsius(),
+            85.0
+        );
+    }
+}
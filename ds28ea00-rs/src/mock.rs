@@ -0,0 +1,468 @@
+//! A simulated 1-Wire bus of DS28EA00 compatible devices, to test code using a [`Ds28ea00Group`](crate::Ds28ea00Group)
+//! without hardware.
+//!
+//! The [`MockBus`] implements the ROM commands (search, match, skip and read ROM) bit by bit as the devices
+//! would, and the read scratchpad, write scratchpad, copy scratchpad, convert temperature, PIO access read
+//! and PIO access write function commands. Other commands are ignored until the next reset, and reads return `0xff` as for an idle bus.
+//!
+//! Note: The mock implements the [`OneWire`] trait without the `triplet-read` feature of `embedded-onewire`.
+use core::convert::Infallible;
+
+use embedded_onewire::{OneWire, OneWireCrc, OneWireError, OneWireResult, OneWireStatus};
+
+use crate::{OneWireBlock, Temperature};
+
+/// A simulated DS28EA00 compatible device.
+#[derive(Debug, Clone, Copy)]
+pub struct MockDevice {
+    rom: u64,
+    scratchpad: [u8; 8],
+    /// TH, TL and configuration registers stored in the EEPROM.
+    eeprom: [u8; 3],
+    present: bool,
+    corrupt: bool,
+    /// Output latches of PIOA and PIOB in bits 0 and 1.
+    latches: u8,
+    /// Levels driven by the external circuit on PIOA and PIOB, in bits 0 and 1.
+    inputs: u8,
+    selected: bool,
+    searching: bool,
+}
+
+impl MockDevice {
+    /// Create a device reading `temperature`, with a ROM made of `family`, the lower 48 bits of `serial`
+    /// and a valid CRC.
+    pub fn new(family: u8, serial: u64, temperature: Temperature) -> Self {
+        let mut rom = [0; 8];
+        rom[0] = family;
+        rom[1..7].copy_from_slice(&serial.to_le_bytes()[..6]);
+        rom[7] = crc(&rom[..7]);
+        let mut dev = Self {
+            rom: u64::from_le_bytes(rom),
+            // power-on defaults of TH, TL and the configuration register
+            scratchpad: [0, 0, 85, 0, 0x7f, 0xff, 0x0c, 0x10],
+            eeprom: [85, 0, 0x7f],
+            present: true,
+            corrupt: false,
+            latches: 0b11,
+            inputs: 0b11,
+            selected: false,
+            searching: false,
+        };
+        dev.set_temperature(temperature);
+        dev
+    }
+
+    /// The ROM of the device.
+    pub fn rom(&self) -> u64 {
+        self.rom
+    }
+
+    /// Set the temperature read from the device.
+    pub fn set_temperature(&mut self, temperature: Temperature) {
+        let raw = (temperature.to_bits() >> 12) as i16;
+        self.scratchpad[..2].copy_from_slice(&raw.to_le_bytes());
+    }
+
+    /// Connect or disconnect the device. A disconnected device does not respond to any command.
+    pub fn set_present(&mut self, present: bool) {
+        self.present = present;
+    }
+
+    /// Corrupt the CRC of the scratchpad read from the device.
+    pub fn set_corrupt_scratchpad(&mut self, corrupt: bool) {
+        self.corrupt = corrupt;
+    }
+
+    /// The TH, TL and configuration registers of the device, as last written.
+    pub fn configuration(&self) -> (i8, i8, u8) {
+        (
+            self.scratchpad[2] as i8,
+            self.scratchpad[3] as i8,
+            self.scratchpad[4],
+        )
+    }
+
+    /// The TH, TL and configuration registers of the device, as last copied to the EEPROM.
+    pub fn eeprom(&self) -> (i8, i8, u8) {
+        (self.eeprom[0] as i8, self.eeprom[1] as i8, self.eeprom[2])
+    }
+
+    /// Returns `true` if the LED on the PIOA pin of the device is lit, i.e. the pin is driven low.
+    pub fn led(&self) -> bool {
+        self.latches & 1 == 0
+    }
+
+    /// The output latches of the PIOA and PIOB pins, `false` if the pin is driven low.
+    pub fn pio_latches(&self) -> (bool, bool) {
+        (self.latches & 0b01 != 0, self.latches & 0b10 != 0)
+    }
+
+    /// Set the levels the external circuit drives on the released PIOA and PIOB pins, e.g. `false`
+    /// for a closed switch to ground.
+    pub fn set_pio_inputs(&mut self, a: bool, b: bool) {
+        self.inputs = a as u8 | (b as u8) << 1;
+    }
+
+    fn active(&self) -> bool {
+        self.present && self.selected
+    }
+
+    fn rom_bit(&self, bit: u8) -> bool {
+        self.rom >> bit & 1 == 1
+    }
+
+    fn alarmed(&self) -> bool {
+        let temp = i16::from_le_bytes([self.scratchpad[0], self.scratchpad[1]]) >> 4;
+        let (high, low, _) = self.configuration();
+        temp >= high as i16 || temp <= low as i16
+    }
+
+    /// The PIO pin state byte: the level and latch of PIOA, then of PIOB, and their complement.
+    fn pio_state(&self) -> u8 {
+        if self.rom as u8 != crate::Family::Ds28ea00.code() {
+            return 0xff;
+        }
+        let levels = self.latches & self.inputs;
+        let state =
+            (levels & 1) | (self.latches & 1) << 1 | (levels & 2) << 1 | (self.latches & 2) << 2;
+        state | !state << 4
+    }
+
+    fn scratchpad_byte(&self, pos: usize) -> u8 {
+        match pos {
+            0..8 => self.scratchpad[pos],
+            8 => crc(&self.scratchpad) ^ self.corrupt as u8,
+            _ => 0xff,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    /// Waiting for a ROM command after a reset.
+    Rom,
+    /// Receiving the ROM of a match ROM command.
+    Match {
+        rom: u64,
+        received: u8,
+    },
+    /// Running a search, at the given bit and step (id bit, complement bit, direction).
+    Search {
+        bit: u8,
+        step: u8,
+    },
+    /// Sending the ROM after a read ROM command.
+    ReadRom {
+        pos: usize,
+    },
+    /// Waiting for a function command.
+    Function,
+    ReadScratchpad {
+        pos: usize,
+    },
+    WriteScratchpad {
+        pos: usize,
+    },
+    /// Receiving the PIO output state, then its complement.
+    PioWrite {
+        state: Option<u8>,
+    },
+    /// Sending the confirmation of a PIO write, then the PIO pin state.
+    PioConfirm {
+        confirmed: bool,
+    },
+    /// Sending the PIO pin state after a PIO access read command.
+    PioRead,
+    /// Ignoring everything until the next reset.
+    Idle,
+}
+
+/// Status of a [`MockBus`] after a reset.
+#[derive(Debug, Clone, Copy)]
+pub struct MockStatus {
+    presence: bool,
+}
+
+impl OneWireStatus for MockStatus {
+    fn presence(&self) -> bool {
+        self.presence
+    }
+
+    fn shortcircuit(&self) -> bool {
+        false
+    }
+}
+
+/// A simulated 1-Wire bus with the given devices connected to it.
+pub struct MockBus<'a> {
+    devices: &'a mut [MockDevice],
+    state: State,
+    overdrive: bool,
+    overdrive_capable: bool,
+    failed_resets: usize,
+    short: bool,
+    conversions: usize,
+    blocks: usize,
+}
+
+impl<'a> MockBus<'a> {
+    /// Create a bus with `devices` connected to it.
+    pub fn new(devices: &'a mut [MockDevice]) -> Self {
+        Self {
+            devices,
+            state: State::Idle,
+            overdrive: false,
+            overdrive_capable: true,
+            failed_resets: 0,
+            short: false,
+            conversions: 0,
+            blocks: 0,
+        }
+    }
+
+    /// Make the next `count` resets fail without a presence pulse, as for a glitch on the bus.
+    pub fn fail_resets(&mut self, count: usize) {
+        self.failed_resets = count;
+    }
+
+    /// Let the devices follow the bus to overdrive speed, or not, e.g. for a bus too long for overdrive.
+    /// Resets at overdrive speed fail with [`OneWireError::NoDevicePresent`] if they do not.
+    pub fn set_overdrive_capable(&mut self, capable: bool) {
+        self.overdrive_capable = capable;
+    }
+
+    /// Short the bus, so that every reset fails with [`OneWireError::ShortCircuit`].
+    pub fn set_short_circuit(&mut self, short: bool) {
+        self.short = short;
+    }
+
+    /// The devices connected to the bus.
+    pub fn devices(&self) -> &[MockDevice] {
+        self.devices
+    }
+
+    /// Get the device with the given ROM.
+    pub fn device_mut(&mut self, rom: u64) -> Option<&mut MockDevice> {
+        self.devices.iter_mut().find(|dev| dev.rom == rom)
+    }
+
+    /// Number of convert temperature commands received.
+    pub fn conversions(&self) -> usize {
+        self.conversions
+    }
+
+    /// Number of block transfers, see [`OneWireBlock`].
+    pub fn blocks(&self) -> usize {
+        self.blocks
+    }
+
+    fn select(&mut self, f: impl Fn(&MockDevice) -> bool) {
+        for dev in self.devices.iter_mut() {
+            dev.selected = dev.present && f(dev);
+        }
+    }
+
+    /// The wired-AND of the bytes sent by the active devices.
+    fn wired_and(&self, f: impl Fn(&MockDevice) -> u8) -> u8 {
+        self.devices
+            .iter()
+            .filter(|dev| dev.active())
+            .fold(0xff, |acc, dev| acc & f(dev))
+    }
+}
+
+impl OneWire for MockBus<'_> {
+    type Status = MockStatus;
+    type BusError = Infallible;
+
+    fn reset(&mut self) -> OneWireResult<MockStatus, Infallible> {
+        self.state = State::Rom;
+        self.select(|_| false);
+        if self.short {
+            return Err(OneWireError::ShortCircuit);
+        }
+        if self.failed_resets > 0 {
+            self.failed_resets -= 1;
+            return Err(OneWireError::NoDevicePresent);
+        }
+        if !self.devices.iter().any(|dev| dev.present) || self.overdrive && !self.overdrive_capable
+        {
+            return Err(OneWireError::NoDevicePresent);
+        }
+        Ok(MockStatus { presence: true })
+    }
+
+    fn write_byte(&mut self, byte: u8) -> OneWireResult<(), Infallible> {
+        self.state = match self.state {
+            State::Rom => match byte {
+                0xf0 | 0xec => {
+                    for dev in self.devices.iter_mut() {
+                        dev.searching = dev.present && (byte == 0xf0 || dev.alarmed());
+                    }
+                    State::Search { bit: 0, step: 0 }
+                }
+                0x55 | 0x69 => State::Match {
+                    rom: 0,
+                    received: 0,
+                },
+                0xcc | 0x3c => {
+                    self.select(|_| true);
+                    State::Function
+                }
+                0x33 => {
+                    self.select(|_| true);
+                    State::ReadRom { pos: 0 }
+                }
+                _ => State::Idle,
+            },
+            State::Match { rom, received } => {
+                let rom = rom | (byte as u64) << (8 * received);
+                if received == 7 {
+                    self.select(|dev| dev.rom == rom);
+                    State::Function
+                } else {
+                    State::Match {
+                        rom,
+                        received: received + 1,
+                    }
+                }
+            }
+            State::Function => match byte {
+                0xbe => State::ReadScratchpad { pos: 0 },
+                0x4e => State::WriteScratchpad { pos: 0 },
+                0x48 => {
+                    for dev in self.devices.iter_mut().filter(|dev| dev.active()) {
+                        dev.eeprom.copy_from_slice(&dev.scratchpad[2..5]);
+                    }
+                    State::Idle
+                }
+                0x44 => {
+                    self.conversions += 1;
+                    State::Idle
+                }
+                0xa5 => State::PioWrite { state: None },
+                0xf5 => State::PioRead,
+                _ => State::Idle,
+            },
+            State::WriteScratchpad { pos } => {
+                for dev in self.devices.iter_mut().filter(|dev| dev.active()) {
+                    dev.scratchpad[2 + pos] = byte;
+                }
+                if pos == 2 {
+                    State::Idle
+                } else {
+                    State::WriteScratchpad { pos: pos + 1 }
+                }
+            }
+            State::PioWrite { state: None } => State::PioWrite { state: Some(byte) },
+            State::PioWrite { state: Some(state) } => {
+                if byte == !state {
+                    for dev in self.devices.iter_mut().filter(|dev| dev.active()) {
+                        if dev.rom as u8 == crate::Ds28ea00Group::<1>::family() {
+                            dev.latches = state & 0b11;
+                        }
+                    }
+                    State::PioConfirm { confirmed: false }
+                } else {
+                    State::Idle
+                }
+            }
+            state => state,
+        };
+        Ok(())
+    }
+
+    fn read_byte(&mut self) -> OneWireResult<u8, Infallible> {
+        let byte = match self.state {
+            State::ReadScratchpad { pos } => {
+                self.state = State::ReadScratchpad { pos: pos + 1 };
+                self.wired_and(|dev| dev.scratchpad_byte(pos))
+            }
+            State::PioConfirm { confirmed: false } => {
+                self.state = State::PioConfirm { confirmed: true };
+                0xaa
+            }
+            State::PioConfirm { confirmed: true } | State::PioRead => {
+                self.wired_and(MockDevice::pio_state)
+            }
+            State::ReadRom { pos } => {
+                self.state = State::ReadRom { pos: pos + 1 };
+                self.wired_and(|dev| dev.rom.to_le_bytes().get(pos).copied().unwrap_or(0xff))
+            }
+            _ => 0xff,
+        };
+        Ok(byte)
+    }
+
+    fn write_bit(&mut self, bit: bool) -> OneWireResult<(), Infallible> {
+        if let State::Search { bit: idx, step: 2 } = self.state {
+            for dev in self.devices.iter_mut() {
+                dev.searching &= dev.rom_bit(idx) == bit;
+            }
+            self.state = if idx == 63 {
+                for dev in self.devices.iter_mut() {
+                    dev.selected = dev.searching;
+                }
+                State::Function
+            } else {
+                State::Search {
+                    bit: idx + 1,
+                    step: 0,
+                }
+            };
+        }
+        Ok(())
+    }
+
+    fn read_bit(&mut self) -> OneWireResult<bool, Infallible> {
+        let State::Search { bit, step } = self.state else {
+            return Ok(true);
+        };
+        if step > 1 {
+            return Ok(true);
+        }
+        self.state = State::Search {
+            bit,
+            step: step + 1,
+        };
+        // the devices send their bit, then its complement
+        Ok(self
+            .devices
+            .iter()
+            .filter(|dev| dev.searching)
+            .all(|dev| dev.rom_bit(bit) == (step == 0)))
+    }
+
+    fn get_overdrive_mode(&mut self) -> bool {
+        self.overdrive
+    }
+
+    fn set_overdrive_mode(&mut self, enable: bool) -> OneWireResult<(), Infallible> {
+        self.overdrive = enable;
+        Ok(())
+    }
+}
+
+impl OneWireBlock for MockBus<'_> {
+    fn write_block(&mut self, bytes: &[u8]) -> OneWireResult<(), Infallible> {
+        self.blocks += 1;
+        bytes.iter().try_for_each(|&byte| self.write_byte(byte))
+    }
+
+    fn read_block(&mut self, buf: &mut [u8]) -> OneWireResult<(), Infallible> {
+        self.blocks += 1;
+        for b in buf.iter_mut() {
+            *b = self.read_byte()?;
+        }
+        Ok(())
+    }
+}
+
+fn crc(bytes: &[u8]) -> u8 {
+    let mut crc = OneWireCrc::default();
+    for byte in bytes {
+        crc.update(*byte);
+    }
+    crc.value()
+}
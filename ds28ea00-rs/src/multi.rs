@@ -0,0 +1,189 @@
+//! Groups of devices on several 1-Wire buses, converting at the same time.
+use alloc::vec::Vec;
+use core::time::Duration;
+
+use embedded_hal::delay::DelayNs;
+use embedded_onewire::{OneWire, OneWireResult};
+
+use crate::{Ds28ea00Group, ReadError, Temperature};
+
+/// Readout of a bus of a [`MultiBusGroup`]: the ROM address and either the temperature or the
+/// [`ReadError`] of every device, or the error that kept the conversion from starting.
+pub type BusReadout<E> = OneWireResult<Vec<(u64, Result<Temperature, ReadError>)>, E>;
+
+/// Groups of devices on several 1-Wire buses, e.g. behind several DS2484 bridges.
+///
+/// The conversions are started on all buses before waiting for the slowest of them, so that the
+/// devices on every bus are read out after a single wait, and their readings are taken at the same
+/// time.
+#[derive(Debug)]
+pub struct MultiBusGroup<O, const N: usize> {
+    buses: Vec<(O, Ds28ea00Group<N>)>,
+}
+
+impl<O, const N: usize> Default for MultiBusGroup<O, N> {
+    fn default() -> Self {
+        Self { buses: Vec::new() }
+    }
+}
+
+impl<O: OneWire, const N: usize> MultiBusGroup<O, N> {
+    /// Creates a group without any buses.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a bus, with the group of its devices.
+    pub fn with_bus(mut self, bus: O, group: Ds28ea00Group<N>) -> Self {
+        self.add_bus(bus, group);
+        self
+    }
+
+    /// Adds a bus, with the group of its devices.
+    ///
+    /// # Returns
+    /// The index of the bus, in the order of the readouts.
+    pub fn add_bus(&mut self, bus: O, group: Ds28ea00Group<N>) -> usize {
+        self.buses.push((bus, group));
+        self.buses.len() - 1
+    }
+
+    /// Number of buses.
+    pub fn len(&self) -> usize {
+        self.buses.len()
+    }
+
+    /// Check whether there are no buses.
+    pub fn is_empty(&self) -> bool {
+        self.buses.is_empty()
+    }
+
+    /// The bus at `index`, with the group of its devices.
+    pub fn bus(&self, index: usize) -> Option<(&O, &Ds28ea00Group<N>)> {
+        self.buses.get(index).map(|(bus, group)| (bus, group))
+    }
+
+    /// The bus at `index`, with the group of its devices, e.g. to toggle the LED of a device.
+    pub fn bus_mut(&mut self, index: usize) -> Option<(&mut O, &mut Ds28ea00Group<N>)> {
+        self.buses.get_mut(index).map(|(bus, group)| (bus, group))
+    }
+
+    /// Removes the buses, returning them with the groups of their devices.
+    pub fn into_buses(self) -> Vec<(O, Ds28ea00Group<N>)> {
+        self.buses
+    }
+
+    /// Enumerates the devices on every bus, see [`Ds28ea00Group::enumerate`].
+    ///
+    /// # Returns
+    /// The number of devices found on every bus, or the error encountered on that bus.
+    pub fn enumerate(&mut self) -> Vec<OneWireResult<usize, O::BusError>> {
+        self.buses
+            .iter_mut()
+            .map(|(bus, group)| group.enumerate(bus))
+            .collect()
+    }
+
+    /// The time the conversion of the slowest bus takes.
+    pub fn conversion_time(&self) -> Duration {
+        self.buses
+            .iter()
+            .map(|(_, group)| group.conversion_time())
+            .max()
+            .unwrap_or_default()
+    }
+
+    /// Starts a temperature conversion on every bus, then waits for the slowest bus and reads out
+    /// the devices on every bus.
+    ///
+    /// A bus on which the conversion fails to start is not read out, and does not hold up the
+    /// others.
+    /// # Arguments
+    /// * `delay` - A mutable reference to a delay provider.
+    /// * `crc` - A boolean indicating whether to validate the CRC of the read data.
+    ///
+    /// # Returns
+    /// The readout of every bus, in the order the buses were added.
+    pub fn read_all<D: DelayNs>(
+        &mut self,
+        delay: &mut D,
+        crc: bool,
+    ) -> Vec<BusReadout<O::BusError>> {
+        let sessions = self
+            .buses
+            .iter_mut()
+            .map(|(bus, group)| (group.begin_conversion(bus), bus))
+            .collect::<Vec<_>>();
+        let wait = sessions
+            .iter()
+            .filter_map(|(session, _)| session.as_ref().ok())
+            .map(|session| session.delay())
+            .max();
+        if let Some(wait) = wait {
+            delay.delay_us(wait.as_micros() as u32);
+        }
+        sessions
+            .into_iter()
+            .map(|(session, bus)| session.map(|session| session.collect(bus, crc).collect()))
+            .collect()
+    }
+}
+
+mod test {
+    #[test]
+    fn test_read_all() {
+        use super::MultiBusGroup;
+        use crate::{Ds28ea00Group, ReadoutResolution, Temperature, mock::*};
+        use embedded_hal::delay::DelayNs;
+        use embedded_onewire::OneWireError;
+        struct Delay(u32);
+        impl DelayNs for Delay {
+            fn delay_ns(&mut self, ns: u32) {
+                self.0 += ns / 1000;
+            }
+        }
+        let temp = Temperature::from_millidegrees;
+        let mut first = [
+            MockDevice::new(0x42, 0x1234, temp(21_500)),
+            MockDevice::new(0x42, 0x5678, temp(22_000)),
+        ];
+        let mut second = [MockDevice::new(0x42, 0x9abc, temp(-5_250))];
+        let mut third = [MockDevice::new(0x42, 0xdef0, temp(0))];
+        let mut buses = MultiBusGroup::new()
+            .with_bus(
+                MockBus::new(&mut first),
+                Ds28ea00Group::<4>::default()
+                    .with_resolution(ReadoutResolution::Resolution9bit)
+                    .with_toggle_pio(false),
+            )
+            .with_bus(
+                MockBus::new(&mut second),
+                Ds28ea00Group::default().with_toggle_pio(false),
+            );
+        let idx = buses.add_bus(
+            MockBus::new(&mut third),
+            Ds28ea00Group::default().with_toggle_pio(false),
+        );
+        assert_eq!(buses.len(), 3);
+        assert!(buses.enumerate().iter().all(Result::is_ok));
+        // the third bus fails after the enumeration
+        buses.bus_mut(idx).unwrap().0.set_short_circuit(true);
+        let mut delay = Delay(0);
+        let readouts = buses.read_all(&mut delay, true);
+        // a single wait, for the slowest bus
+        assert_eq!(delay.0, 750_000);
+        assert_eq!(buses.conversion_time().as_micros(), 750_000);
+        let first = readouts[0].as_ref().unwrap();
+        assert_eq!(first.len(), 2);
+        assert!(first.iter().all(|(_, res)| res.is_ok()));
+        assert_eq!(readouts[1].as_ref().unwrap()[0].1, Ok(temp(-5_250)));
+        assert!(matches!(readouts[2], Err(OneWireError::ShortCircuit)));
+        assert!(
+            buses
+                .into_buses()
+                .iter()
+                .take(2)
+                .all(|(bus, _)| bus.conversions() == 1)
+        );
+    }
+}
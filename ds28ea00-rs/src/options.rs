@@ -0,0 +1,111 @@
+//! Options of a readout of a [`Ds28ea00Group`](crate::Ds28ea00Group), see
+//! [`Ds28ea00Group::read_temperatures`](crate::Ds28ea00Group::read_temperatures).
+use core::time::Duration;
+
+use crate::Temperature;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+/// How the scratchpad of a device is read.
+pub enum CrcPolicy {
+    /// Read the two temperature bytes only, without a CRC check.
+    Skip,
+    #[default]
+    /// Read the full scratchpad back and validate its CRC.
+    Verify,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+/// What a readout does when a device cannot be read.
+pub enum ErrorPolicy {
+    #[default]
+    /// Stop at the first device that cannot be read, and return its error.
+    Abort,
+    /// Record [`ErrorPolicy::SENTINEL`] as the temperature of the device, and read the next one.
+    Substitute,
+}
+
+impl ErrorPolicy {
+    /// The temperature recorded for a device that cannot be read, the power-on value of the scratchpad.
+    pub const SENTINEL: Temperature = Temperature::from_millidegrees(-85_000);
+}
+
+#[derive(Debug, Copy, Clone, Default)]
+/// Options of [`Ds28ea00Group::read_temperatures`](crate::Ds28ea00Group::read_temperatures).
+///
+/// The default options read the full scratchpad of every device and validate its CRC, retry each
+/// device as configured with [`with_retries`](crate::Ds28ea00Group::with_retries), and stop at the first
+/// device that cannot be read.
+pub struct ReadOptions {
+    pub(crate) crc: CrcPolicy,
+    pub(crate) errors: ErrorPolicy,
+    pub(crate) retries: Option<u8>,
+    pub(crate) timeout: Option<(Duration, fn() -> Duration)>,
+}
+
+impl ReadOptions {
+    /// Creates the default options.
+    pub const fn new() -> Self {
+        Self {
+            crc: CrcPolicy::Verify,
+            errors: ErrorPolicy::Abort,
+            retries: None,
+            timeout: None,
+        }
+    }
+
+    /// Sets how the scratchpad of each device is read.
+    pub const fn with_crc(mut self, crc: CrcPolicy) -> Self {
+        self.crc = crc;
+        self
+    }
+
+    /// Sets what the readout does when a device cannot be read.
+    pub const fn with_errors(mut self, errors: ErrorPolicy) -> Self {
+        self.errors = errors;
+        self
+    }
+
+    /// Sets the number of times a device is read again after a transient error, instead of the
+    /// retries of the group.
+    pub const fn with_retries(mut self, retries: u8) -> Self {
+        self.retries = Some(retries);
+        self
+    }
+
+    /// Bounds the time spent on a device: once `timeout` has elapsed since its first attempt, a
+    /// transient error is no longer retried.
+    /// # Arguments
+    /// * `timeout` - The time after which a device is no longer retried.
+    /// * `clock` - A monotonic clock, e.g. the time since boot.
+    pub const fn with_timeout(mut self, timeout: Duration, clock: fn() -> Duration) -> Self {
+        self.timeout = Some((timeout, clock));
+        self
+    }
+
+    /// Returns how the scratchpad of each device is read.
+    pub const fn crc(&self) -> CrcPolicy {
+        self.crc
+    }
+
+    /// Returns what the readout does when a device cannot be read.
+    pub const fn errors(&self) -> ErrorPolicy {
+        self.errors
+    }
+
+    /// Returns the retries of each device, or `None` if the retries of the group are used.
+    pub const fn retries(&self) -> Option<u8> {
+        self.retries
+    }
+
+    /// Returns the time after which a device is no longer retried, if any.
+    pub const fn timeout(&self) -> Option<Duration> {
+        match self.timeout {
+            Some((timeout, _)) => Some(timeout),
+            None => None,
+        }
+    }
+}
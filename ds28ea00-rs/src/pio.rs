@@ -0,0 +1,98 @@
+//! General-purpose use of the PIOA and PIOB pins of the DS28EA00, see [`Ds28ea00Group::pio_write`]
+//! and [`Ds28ea00Group::pio_read`].
+use embedded_onewire::{OneWire, OneWireError, OneWireResult};
+
+use crate::{DS28EA00_PIO_CONFIRM, DS28EA00_READ_PIO, DS28EA00_TOGGLE_PIO, Ds28ea00Group, Family};
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+/// State of the PIO pins of a DS28EA00, as reported by the PIO access read and write commands.
+///
+/// The PIO pins are open drain outputs: a pin whose output latch is `false` is pulled low by the
+/// device, a pin whose output latch is `true` is released, and its level is set by the external
+/// circuit, e.g. a pull-up resistor or a switch to ground.
+pub struct PioState {
+    /// Level of the PIOA pin.
+    pub a: bool,
+    /// Level of the PIOB pin.
+    pub b: bool,
+    /// Output latch of the PIOA pin.
+    pub latch_a: bool,
+    /// Output latch of the PIOB pin.
+    pub latch_b: bool,
+}
+
+impl PioState {
+    /// Decode a PIO pin state byte, whose upper nibble is the complement of the lower nibble.
+    fn from_byte(byte: u8) -> Option<Self> {
+        if byte >> 4 != !byte & 0x0f {
+            return None;
+        }
+        Some(Self {
+            a: byte & 0b0001 != 0,
+            latch_a: byte & 0b0010 != 0,
+            b: byte & 0b0100 != 0,
+            latch_b: byte & 0b1000 != 0,
+        })
+    }
+}
+
+impl<const N: usize> Ds28ea00Group<N> {
+    /// Address a DS28EA00 of the group for a PIO access command.
+    fn select_pio<O: OneWire>(&self, bus: &mut O, rom: u64) -> OneWireResult<(), O::BusError> {
+        if Family::from_rom(rom) != Some(Family::Ds28ea00) {
+            return Err(OneWireError::InvalidValue("device has no PIO"));
+        }
+        Self::select(bus, rom, self.single && self.roms[0].0 == rom)
+    }
+
+    /// Set the output latches of the PIO pins of a DS28EA00 device.
+    ///
+    /// # Arguments
+    /// * `bus` - A mutable reference to a type that implements the [`OneWire`] trait.
+    /// * `rom` - The ROM address of the DS28EA00 device.
+    /// * `a` - Output of the PIOA pin, `false` to pull the pin low and `true` to release it.
+    /// * `b` - Output of the PIOB pin, `false` to pull the pin low and `true` to release it.
+    ///
+    /// # Returns
+    /// The state of the PIO pins after the write, or [`OneWireError::InvalidValue`] if the device did not
+    /// confirm the write or is not a DS28EA00.
+    pub fn pio_write<O: OneWire>(
+        &self,
+        bus: &mut O,
+        rom: u64,
+        a: bool,
+        b: bool,
+    ) -> OneWireResult<PioState, O::BusError> {
+        // the unused upper bits of the output byte must be set
+        let output = 0xfc | a as u8 | (b as u8) << 1;
+        self.select_pio(bus, rom)?;
+        bus.write_byte(DS28EA00_TOGGLE_PIO)?;
+        bus.write_byte(output)?;
+        bus.write_byte(!output)?;
+        if bus.read_byte()? != DS28EA00_PIO_CONFIRM {
+            return Err(OneWireError::InvalidValue("PIO write not confirmed"));
+        }
+        PioState::from_byte(bus.read_byte()?).ok_or(OneWireError::InvalidValue("invalid PIO state"))
+    }
+
+    /// Read the state of the PIO pins of a DS28EA00 device.
+    ///
+    /// # Arguments
+    /// * `bus` - A mutable reference to a type that implements the [`OneWire`] trait.
+    /// * `rom` - The ROM address of the DS28EA00 device.
+    ///
+    /// # Returns
+    /// The state of the PIO pins, or [`OneWireError::InvalidValue`] if the state byte is corrupted or the
+    /// device is not a DS28EA00.
+    pub fn pio_read<O: OneWire>(
+        &self,
+        bus: &mut O,
+        rom: u64,
+    ) -> OneWireResult<PioState, O::BusError> {
+        self.select_pio(bus, rom)?;
+        bus.write_byte(DS28EA00_READ_PIO)?;
+        PioState::from_byte(bus.read_byte()?).ok_or(OneWireError::InvalidValue("invalid PIO state"))
+    }
+}
@@ -0,0 +1,244 @@
+//! Resolutions of single devices in a [`Ds28ea00Group`], see [`Ds28ea00Group::set_device_resolution`].
+use core::time::Duration;
+
+use embedded_onewire::OneWire;
+
+use crate::{
+    Ds28ea00Group, ReadError, ReadSettings, ReadoutResolution, RomError, Temperature, calibration,
+};
+
+/// The resolution of `rom` in `table`, or `default` if it has none of its own.
+pub(crate) fn lookup(
+    table: &[(u64, ReadoutResolution)],
+    default: ReadoutResolution,
+    rom: u64,
+) -> ReadoutResolution {
+    table
+        .iter()
+        .find_map(|(r, resolution)| (*r == rom).then_some(*resolution))
+        .unwrap_or(default)
+}
+
+impl<const N: usize> Ds28ea00Group<N> {
+    /// Sets the resolution of a device, in place of the resolution of the group.
+    ///
+    /// The resolutions are kept by ROM code like the calibrations, so that they can be set before
+    /// the bus is searched. They are written by [`enumerate`](Self::enumerate),
+    /// [`apply_configuration`](Self::apply_configuration) and [`set_resolution`](Self::set_resolution),
+    /// which then configure the devices of the group one at a time instead of broadcasting the
+    /// configuration. A conversion lasts as long as the conversion of the device with the highest
+    /// resolution; the devices with a lower one can be read earlier with
+    /// [`read_ready_devices`](Self::read_ready_devices).
+    /// # Arguments
+    /// * `rom` - The ROM code of the device.
+    /// * `resolution` - The readout resolution of the device.
+    ///
+    /// # Returns
+    /// [`RomError::Full`] if the table holds the resolutions of `N` other devices.
+    pub fn set_device_resolution(
+        &mut self,
+        rom: u64,
+        resolution: ReadoutResolution,
+    ) -> Result<(), RomError> {
+        let table = &mut self.resolutions[..self.resolved];
+        if let Some((_, entry)) = table.iter_mut().find(|(r, _)| *r == rom) {
+            *entry = resolution;
+            return Ok(());
+        }
+        if self.resolved == N {
+            return Err(RomError::Full);
+        }
+        self.resolutions[self.resolved] = (rom, resolution);
+        self.resolved += 1;
+        Ok(())
+    }
+
+    /// Removes the resolution of a device, which is configured with the resolution of the group
+    /// from the next configuration on.
+    ///
+    /// # Returns
+    /// `true` if the device had its own resolution.
+    pub fn clear_device_resolution(&mut self, rom: u64) -> bool {
+        let Some(idx) = self.resolutions[..self.resolved]
+            .iter()
+            .position(|(r, _)| *r == rom)
+        else {
+            return false;
+        };
+        self.resolutions[idx..self.resolved].rotate_left(1);
+        self.resolved -= 1;
+        true
+    }
+
+    /// Returns the resolution a device is configured and read with.
+    pub fn device_resolution(&self, rom: u64) -> ReadoutResolution {
+        lookup(&self.resolutions[..self.resolved], self.resolution, rom)
+    }
+
+    /// Returns the devices with their own resolution, see [`set_device_resolution`](Self::set_device_resolution).
+    pub fn device_resolutions(&self) -> &[(u64, ReadoutResolution)] {
+        &self.resolutions[..self.resolved]
+    }
+
+    /// Reads the devices whose conversion has completed `elapsed` after it was started with
+    /// [`start_temperature_conversion`](Self::start_temperature_conversion), and that were not read
+    /// by the previous call.
+    ///
+    /// This reads the devices at a low resolution while the devices at a higher resolution still
+    /// convert, e.g. once after 93.75 ms and once after 750 ms for a mix of 9-bit and 12-bit devices.
+    /// Each device is read once per conversion: a call with an `elapsed` time no longer than that of
+    /// the previous call reads no device, until the next conversion is started.
+    /// # Arguments
+    /// * `bus` - A mutable reference to a type that implements the [`OneWire`] trait.
+    /// * `elapsed` - The time elapsed since the conversion was started.
+    /// * `crc` - A boolean indicating whether to validate the CRC of the read data.
+    /// # Returns
+    /// An iterator over tuples of the ROM address and either the temperature reading or the [`ReadError`]
+    /// encountered while reading that device, for the devices read by this call.
+    pub fn read_ready_devices<O: OneWire>(
+        &mut self,
+        bus: &mut O,
+        elapsed: Duration,
+        crc: bool,
+    ) -> impl Iterator<Item = (u64, Result<Temperature, ReadError>)> + '_ {
+        let after = self.ready_read;
+        self.ready_read = self.ready_read.max(elapsed);
+        let settings = self.read_settings();
+        let calibrations = &self.calibrations[..self.calibrated];
+        let resolutions = &self.resolutions[..self.resolved];
+        let ready = move |rom| {
+            let conversion = lookup(resolutions, settings.resolution, rom).conversion_time();
+            after < conversion && conversion <= elapsed
+        };
+        for ((rom, temp), state) in self.roms[..self.devices]
+            .iter_mut()
+            .zip(self.state[..self.devices].iter_mut())
+        {
+            if !ready(*rom) {
+                continue;
+            }
+            let res = Self::read_temperature_internal(
+                bus,
+                *rom,
+                temp,
+                crc,
+                ReadSettings::for_device(settings, resolutions, *rom),
+                state,
+                calibration::lookup(calibrations, *rom),
+            );
+            state.error = res.err().as_ref().map(ReadError::from);
+        }
+        self.roms[..self.devices]
+            .iter()
+            .zip(self.state[..self.devices].iter())
+            .filter(move |((rom, _), _)| ready(*rom))
+            .map(|((rom, temp), state)| (*rom, state.error.map_or(Ok(*temp), Err)))
+    }
+}
+
+mod test {
+    #[test]
+    fn test_device_resolution() {
+        use crate::{Ds28ea00Group, ReadoutResolution, RomError, Temperature, mock::*};
+        use ReadoutResolution::*;
+        use alloc::vec::Vec;
+        use core::time::Duration;
+        let mut devices = [
+            MockDevice::new(0x42, 0x1234, Temperature::from_millidegrees(21_125)),
+            MockDevice::new(0x42, 0x5678, Temperature::from_millidegrees(22_500)),
+            MockDevice::new(0x42, 0x9abc, Temperature::from_millidegrees(23_500)),
+        ];
+        let roms = devices.map(|dev| dev.rom());
+        let mut bus = MockBus::new(&mut devices);
+        let mut group = Ds28ea00Group::<2>::default().with_resolution(Resolution12bit);
+        // set before the devices are found, and kept for devices left out
+        group
+            .set_device_resolution(roms[1], Resolution9bit)
+            .unwrap();
+        group
+            .set_device_resolution(roms[2], Resolution10bit)
+            .unwrap();
+        assert_eq!(
+            group.set_device_resolution(0xdead, Resolution9bit),
+            Err(RomError::Full)
+        );
+        assert_eq!(
+            group
+                .enumerate_filtered(&mut bus, |rom| rom != roms[2])
+                .unwrap(),
+            2
+        );
+        assert_eq!(bus.device_mut(roms[0]).unwrap().configuration().2, 0x7f);
+        assert_eq!(bus.device_mut(roms[1]).unwrap().configuration().2, 0x1f);
+        assert_eq!(group.verify_configuration(&mut bus).unwrap().count(), 0);
+        assert_eq!(group.conversion_time(), Resolution12bit.conversion_time());
+        // the 9-bit device is read first, then the 12-bit one
+        group.start_temperature_conversion(&mut bus).unwrap();
+        let early: Vec<_> = group
+            .read_ready_devices(&mut bus, Resolution9bit.conversion_time(), true)
+            .collect();
+        assert_eq!(
+            early,
+            [(roms[1], Ok(Temperature::from_millidegrees(22_500)))]
+        );
+        let late: Vec<_> = group
+            .read_ready_devices(&mut bus, Duration::from_secs(1), true)
+            .collect();
+        assert_eq!(
+            late,
+            [(roms[0], Ok(Temperature::from_millidegrees(21_125)))]
+        );
+        // read once per conversion, the next conversion starts over
+        let again = group.read_ready_devices(&mut bus, Duration::from_secs(1), true);
+        assert_eq!(again.count(), 0);
+        group.start_temperature_conversion(&mut bus).unwrap();
+        let all = group.read_ready_devices(&mut bus, Duration::from_secs(1), true);
+        assert_eq!(all.count(), 2);
+        // the 9-bit reading is truncated to its resolution
+        bus.device_mut(roms[1])
+            .unwrap()
+            .set_temperature(Temperature::from_millidegrees(22_625));
+        let temps = group
+            .read_temperatures(&mut bus, crate::ReadOptions::new())
+            .unwrap();
+        assert!(temps.contains(&(roms[1], Temperature::from_millidegrees(22_500))));
+        assert!(group.clear_device_resolution(roms[1]));
+        assert!(!group.clear_device_resolution(roms[1]));
+        assert_eq!(group.device_resolution(roms[1]), Resolution12bit);
+        assert_eq!(group.device_resolutions(), &[(roms[2], Resolution10bit)]);
+    }
+
+    #[test]
+    fn test_ready_devices_conversions() {
+        use crate::{Ds28ea00Group, ReadoutResolution, Temperature, mock::*};
+        use ReadoutResolution::*;
+        use core::time::Duration;
+        let mut devices = [
+            MockDevice::new(0x42, 0x1234, Temperature::from_millidegrees(21_125)),
+            MockDevice::new(0x42, 0x5678, Temperature::from_millidegrees(22_500)),
+        ];
+        let roms = devices.map(|dev| dev.rom());
+        let mut bus = MockBus::new(&mut devices);
+        let mut group = Ds28ea00Group::<2>::default().with_resolution(Resolution12bit);
+        group
+            .set_device_resolution(roms[1], Resolution9bit)
+            .unwrap();
+        assert_eq!(group.enumerate(&mut bus).unwrap(), 2);
+        // each conversion reads all devices, although the elapsed times increase
+        for elapsed in [760, 770] {
+            group.start_temperature_conversion(&mut bus).unwrap();
+            let read = group.read_ready_devices(&mut bus, Duration::from_millis(elapsed), true);
+            assert_eq!(read.count(), 2);
+        }
+        // an early read of a conversion does not hide devices of the next one
+        group.start_temperature_conversion(&mut bus).unwrap();
+        let early = group.read_ready_devices(&mut bus, Duration::from_millis(100), true);
+        assert_eq!(
+            early.map(|(rom, _)| rom).collect::<alloc::vec::Vec<_>>(),
+            [roms[1]]
+        );
+        group.start_temperature_conversion(&mut bus).unwrap();
+        let all = group.read_ready_devices(&mut bus, Duration::from_millis(800), true);
+        assert_eq!(all.count(), 2);
+    }
+}
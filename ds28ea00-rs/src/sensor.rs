@@ -0,0 +1,37 @@
+use core::time::Duration;
+
+use embedded_onewire::{OneWire, OneWireError};
+use piccthermo_core::{SensorDriver, TemperatureSensor};
+
+use crate::{Ds28ea00Group, Temperature};
+
+impl<const N: usize, O: OneWire> SensorDriver<O> for Ds28ea00Group<N> {
+    type Error = OneWireError<O::BusError>;
+
+    fn trigger(&mut self, bus: &mut O) -> Result<(), Self::Error> {
+        self.start_temperature_conversion(bus)
+    }
+
+    fn ready_after(&self) -> Duration {
+        self.conversion_time()
+    }
+}
+
+impl<const N: usize, O: OneWire> TemperatureSensor<O> for Ds28ea00Group<N> {
+    /// Reads all devices in the group, with CRC validation, identified by their ROM codes.
+    ///
+    /// Devices that fail to read are skipped; use [`Ds28ea00Group::read_temperatures_detailed`]
+    /// to find out why.
+    fn read(
+        &mut self,
+        bus: &mut O,
+        sink: &mut dyn FnMut(u64, Temperature),
+    ) -> Result<(), Self::Error> {
+        for (rom, temp) in self.read_temperatures_detailed(bus, true) {
+            if let Ok(temp) = temp {
+                sink(rom, temp);
+            }
+        }
+        Ok(())
+    }
+}
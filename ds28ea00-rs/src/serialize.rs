@@ -0,0 +1,140 @@
+//! Serde support for [`Ds28ea00Group`].
+//!
+//! The group is persisted as the table of enumerated devices together with the
+//! configuration applied during enumeration. The overdrive state and whether the device
+//! is alone on the bus describe the bus rather than the devices, and are therefore not
+//! persisted, unless the group assumes a single device. The sort order is not persisted
+//! either, the table is stored in its current order. The calibration and resolution tables are
+//! persisted with the devices, so that a calibrated group is restored as a whole.
+use core::{fmt, marker::PhantomData, time::Duration};
+
+use serde::{
+    Deserialize, Deserializer, Serialize, Serializer,
+    de::{self, SeqAccess, Visitor},
+    ser::SerializeStruct,
+};
+
+use crate::{
+    Calibration, DeviceState, Ds28ea00Group, Family, ReadoutResolution, SortOrder, Temperature,
+};
+
+impl<const N: usize> Serialize for Ds28ea00Group<N> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("Ds28ea00Group", 11)?;
+        state.serialize_field("roms", &self.roms[..self.devices])?;
+        state.serialize_field("resolution", &self.resolution)?;
+        state.serialize_field("low", &self.low)?;
+        state.serialize_field("high", &self.high)?;
+        state.serialize_field("toggle_pio", &self.toggle_pio)?;
+        state.serialize_field("skip_invalid_roms", &self.skip_invalid_roms)?;
+        state.serialize_field("retries", &self.retries)?;
+        state.serialize_field("families", &self.families)?;
+        state.serialize_field("assume_single", &self.assume_single)?;
+        state.serialize_field("calibrations", self.calibrations())?;
+        state.serialize_field("device_resolutions", self.device_resolutions())?;
+        state.end()
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename = "Ds28ea00Group")]
+struct GroupRepr<const N: usize> {
+    roms: RomTable<Temperature, N>,
+    resolution: ReadoutResolution,
+    low: i8,
+    high: i8,
+    toggle_pio: bool,
+    #[serde(default)]
+    skip_invalid_roms: bool,
+    #[serde(default)]
+    retries: u8,
+    #[serde(default = "default_families")]
+    families: u8,
+    #[serde(default)]
+    assume_single: bool,
+    #[serde(default)]
+    calibrations: RomTable<Calibration, N>,
+    #[serde(default)]
+    device_resolutions: RomTable<ReadoutResolution, N>,
+}
+
+fn default_families() -> u8 {
+    Family::Ds28ea00.mask()
+}
+
+/// Fixed capacity table of values by ROM code, deserialized from a sequence of at most `N` entries.
+struct RomTable<T, const N: usize> {
+    devices: usize,
+    roms: [(u64, T); N],
+}
+
+impl<T: Copy + Default, const N: usize> Default for RomTable<T, N> {
+    fn default() -> Self {
+        Self {
+            devices: 0,
+            roms: [(0, T::default()); N],
+        }
+    }
+}
+
+impl<'de, T: Copy + Default + Deserialize<'de>, const N: usize> Deserialize<'de>
+    for RomTable<T, N>
+{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct RomTableVisitor<T, const N: usize>(PhantomData<[T; N]>);
+
+        impl<'de, T: Copy + Default + Deserialize<'de>, const N: usize> Visitor<'de>
+            for RomTableVisitor<T, N>
+        {
+            type Value = RomTable<T, N>;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                write!(f, "a sequence of at most {N} (rom, value) pairs")
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+                let mut table = RomTable::default();
+                while let Some(entry) = seq.next_element()? {
+                    if table.devices == N {
+                        return Err(de::Error::invalid_length(table.devices + 1, &self));
+                    }
+                    table.roms[table.devices] = entry;
+                    table.devices += 1;
+                }
+                Ok(table)
+            }
+        }
+
+        deserializer.deserialize_seq(RomTableVisitor::<T, N>(PhantomData))
+    }
+}
+
+impl<'de, const N: usize> Deserialize<'de> for Ds28ea00Group<N> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let repr = GroupRepr::<N>::deserialize(deserializer)?;
+        Ok(Self {
+            devices: repr.roms.devices,
+            roms: repr.roms.roms,
+            state: [DeviceState::new(); N],
+            resolution: repr.resolution,
+            low: repr.low,
+            high: repr.high,
+            toggle_pio: repr.toggle_pio,
+            overdrive: false,
+            skip_invalid_roms: repr.skip_invalid_roms,
+            invalid_roms: 0,
+            retries: repr.retries,
+            families: repr.families,
+            assume_single: repr.assume_single,
+            single: repr.assume_single && repr.roms.devices == 1,
+            sort_order: SortOrder::Search,
+            device_order: [0; N],
+            device_order_len: 0,
+            calibrations: repr.calibrations.roms,
+            calibrated: repr.calibrations.devices,
+            resolutions: repr.device_resolutions.roms,
+            resolved: repr.device_resolutions.devices,
+            ready_read: Duration::ZERO,
+        })
+    }
+}
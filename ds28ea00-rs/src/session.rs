@@ -0,0 +1,101 @@
+//! Temperature conversions split into a broadcast trigger and a later readout, see [`ConversionSession`].
+use core::{ops::Add, time::Duration};
+
+use embedded_onewire::{OneWire, OneWireResult};
+
+use crate::{Ds28ea00Group, ReadError, Reading, Temperature};
+
+/// A temperature conversion running on all devices of a [`Ds28ea00Group`].
+///
+/// The conversion is started with a single skip-ROM broadcast by [`Ds28ea00Group::begin_conversion`],
+/// which returns immediately. The caller is free to use the time until the deadline returned by
+/// [`ready_at`](Self::ready_at) for unrelated work, e.g. polling other sensors, before reading the
+/// devices out with [`collect`](Self::collect).
+///
+/// The session holds the group until it is collected, so that the group can not be reconfigured or
+/// read while the conversion is running. Dropping the session abandons the readout.
+#[must_use = "the conversion is only read out by `collect`"]
+pub struct ConversionSession<'a, const N: usize> {
+    group: &'a mut Ds28ea00Group<N>,
+    delay: Duration,
+}
+
+impl<'a, const N: usize> ConversionSession<'a, N> {
+    pub(crate) fn new(group: &'a mut Ds28ea00Group<N>) -> Self {
+        let delay = group.conversion_time();
+        Self { group, delay }
+    }
+
+    /// The duration to wait after [`Ds28ea00Group::begin_conversion`] for the conversion to complete.
+    pub fn delay(&self) -> Duration {
+        self.delay
+    }
+
+    /// The time at which the conversion completes, for a conversion begun at `now`.
+    ///
+    /// # Arguments
+    /// * `now` - The time [`Ds28ea00Group::begin_conversion`] returned at, on any monotonic clock,
+    ///   e.g. the time since boot or `std::time::Instant`.
+    pub fn ready_at<T: Add<Duration, Output = T>>(&self, now: T) -> T {
+        now + self.delay
+    }
+
+    /// Number of devices that will be read out.
+    pub fn devices(&self) -> usize {
+        self.group.roms().count()
+    }
+
+    /// Read out the devices of the group, one at a time as the iterator is advanced.
+    ///
+    /// This must be called once [`delay`](Self::delay) has elapsed, otherwise the devices report the
+    /// result of the previous conversion.
+    /// # Arguments
+    /// * `bus` - A mutable reference to a type that implements the [`OneWire`] trait.
+    /// * `crc` - A boolean indicating whether to validate the CRC of the read data.
+    /// # Returns
+    /// An iterator over tuples of the ROM address and either the temperature reading or the [`ReadError`]
+    /// encountered while reading that device, see [`Ds28ea00Group::read_temperatures_iter`].
+    pub fn collect<O: OneWire>(
+        self,
+        bus: &mut O,
+        crc: bool,
+    ) -> impl Iterator<Item = (u64, Result<Temperature, ReadError>)> {
+        self.group.read_temperatures_iter(bus, crc)
+    }
+
+    /// Read out the devices of the group with their alarm status, one at a time as the iterator is
+    /// advanced.
+    ///
+    /// This must be called once [`delay`](Self::delay) has elapsed, as with [`collect`](Self::collect).
+    /// # Arguments
+    /// * `bus` - A mutable reference to a type that implements the [`OneWire`] trait.
+    /// * `crc` - A boolean indicating whether to read the full scratchpad and validate its CRC.
+    /// # Returns
+    /// An iterator over tuples of the ROM address and either the reading or the [`ReadError`]
+    /// encountered while reading that device, see [`Ds28ea00Group::read_readings_iter`].
+    pub fn collect_readings<O: OneWire>(
+        self,
+        bus: &mut O,
+        crc: bool,
+    ) -> impl Iterator<Item = (u64, Result<Reading, ReadError>)> {
+        self.group.read_readings_iter(bus, crc)
+    }
+}
+
+impl<const N: usize> Ds28ea00Group<N> {
+    /// Starts a temperature conversion on all devices in the group, to be read out later with
+    /// [`ConversionSession::collect`].
+    ///
+    /// # Arguments
+    /// * `bus` - A mutable reference to a type that implements the [`OneWire`] trait.
+    ///
+    /// # Returns
+    /// A result containing the running conversion, or an error if the conversion could not be started.
+    pub fn begin_conversion<O: OneWire>(
+        &mut self,
+        bus: &mut O,
+    ) -> OneWireResult<ConversionSession<'_, N>, O::BusError> {
+        self.start_temperature_conversion(bus)?;
+        Ok(ConversionSession::new(self))
+    }
+}
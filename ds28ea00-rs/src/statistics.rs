@@ -0,0 +1,106 @@
+//! Aggregate statistics over the readout of a [`Ds28ea00Group`](crate::Ds28ea00Group).
+use crate::Temperature;
+
+#[derive(Debug, Clone)]
+/// Minimum, maximum, mean and median temperature across a group readout, together
+/// with the devices whose reading deviates too far from the group median.
+///
+/// The statistics can hold up to `N` readings, where `N` is a compile-time constant,
+/// and are computed without heap allocations.
+pub struct GroupStatistics<const N: usize> {
+    count: usize,
+    min: Temperature,
+    max: Temperature,
+    mean: Temperature,
+    median: Temperature,
+    outliers: [(u64, Temperature); N],
+    num_outliers: usize,
+}
+
+impl<const N: usize> GroupStatistics<N> {
+    /// Computes the statistics of a readout.
+    ///
+    /// # Arguments
+    /// * `readout` - The readout as returned by [`Ds28ea00Group::read_temperatures`](crate::Ds28ea00Group::read_temperatures).
+    ///   Only the first `N` readings are considered.
+    /// * `max_deviation` - Readings that deviate from the group median by more than this amount are flagged as outliers.
+    ///
+    /// # Returns
+    /// The statistics of the readout, or `None` if the readout is empty.
+    pub fn new(readout: &[(u64, Temperature)], max_deviation: Temperature) -> Option<Self> {
+        let readout = &readout[..readout.len().min(N)];
+        let count = readout.len();
+        if count == 0 {
+            return None;
+        }
+        // sort a copy of the raw readings to find the median
+        let mut sorted = [0i32; N];
+        for (dst, (_, temp)) in sorted.iter_mut().zip(readout.iter()) {
+            *dst = temp.to_bits();
+        }
+        let sorted = &mut sorted[..count];
+        sorted.sort_unstable();
+        let median = if count % 2 == 1 {
+            sorted[count / 2] as i64
+        } else {
+            (sorted[count / 2 - 1] as i64 + sorted[count / 2] as i64) / 2
+        };
+        // the sum is accumulated in wider raw units to avoid overflowing the temperature type
+        let mean = sorted.iter().map(|&t| t as i64).sum::<i64>() / count as i64;
+        let mut stats = Self {
+            count,
+            min: Temperature::from_bits(sorted[0]),
+            max: Temperature::from_bits(sorted[count - 1]),
+            mean: Self::from_raw(mean),
+            median: Self::from_raw(median),
+            outliers: [(0, Temperature::ZERO); N],
+            num_outliers: 0,
+        };
+        let max_deviation = max_deviation.to_bits().unsigned_abs() as i64;
+        for (rom, temp) in readout {
+            let deviation = temp.to_bits() as i64 - median;
+            if deviation.abs() > max_deviation {
+                stats.outliers[stats.num_outliers] = (*rom, Self::from_raw(deviation));
+                stats.num_outliers += 1;
+            }
+        }
+        Some(stats)
+    }
+
+    fn from_raw(bits: i64) -> Temperature {
+        Temperature::from_bits(bits.clamp(i32::MIN as i64, i32::MAX as i64) as i32)
+    }
+
+    /// Number of readings the statistics were computed from.
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    /// Lowest temperature in the readout.
+    pub fn min(&self) -> Temperature {
+        self.min
+    }
+
+    /// Highest temperature in the readout.
+    pub fn max(&self) -> Temperature {
+        self.max
+    }
+
+    /// Mean temperature of the readout.
+    pub fn mean(&self) -> Temperature {
+        self.mean
+    }
+
+    /// Median temperature of the readout.
+    pub fn median(&self) -> Temperature {
+        self.median
+    }
+
+    /// Devices whose reading deviates from the group median by more than the allowed amount.
+    ///
+    /// Each item contains the ROM address of the device and the signed deviation of its
+    /// reading from the median.
+    pub fn outliers(&self) -> &[(u64, Temperature)] {
+        &self.outliers[..self.num_outliers]
+    }
+}
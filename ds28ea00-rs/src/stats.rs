@@ -0,0 +1,205 @@
+//! Bus health counters of the devices in a [`Ds28ea00Group`], see [`Ds28ea00Group::bus_stats`].
+use embedded_onewire::{OneWire, OneWireError, OneWireResult};
+
+use crate::{Ds28ea00Group, OneWireBlock};
+
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+/// Bus health counters of a device, counted over its temperature readouts since the last
+/// [`clear_stats`](Ds28ea00Group::clear_stats).
+pub struct BusStats {
+    /// Bus resets issued while reading the device, including the resets between retries.
+    pub resets: u32,
+    /// Resets that no device answered with a presence pulse.
+    pub presence_failures: u32,
+    /// Readouts of the device that failed the CRC check.
+    pub crc_errors: u32,
+    /// Readouts of the device that were attempted again after a transient error.
+    pub retries: u32,
+}
+
+impl BusStats {
+    pub(crate) const fn new() -> Self {
+        Self {
+            resets: 0,
+            presence_failures: 0,
+            crc_errors: 0,
+            retries: 0,
+        }
+    }
+}
+
+/// A bus counting its resets and presence failures in the statistics of the device being read.
+pub(crate) struct Counted<'a, O> {
+    bus: &'a mut O,
+    stats: &'a mut BusStats,
+    attempted: bool,
+}
+
+impl<'a, O: OneWire> Counted<'a, O> {
+    pub(crate) fn new(bus: &'a mut O, stats: &'a mut BusStats) -> Self {
+        Self {
+            bus,
+            stats,
+            attempted: false,
+        }
+    }
+
+    /// Count the outcome of an attempt to read the device.
+    pub(crate) fn record<T>(&mut self, res: &OneWireResult<T, O::BusError>) {
+        if self.attempted {
+            self.stats.retries = self.stats.retries.saturating_add(1);
+        }
+        self.attempted = true;
+        if let Err(OneWireError::InvalidCrc) = res {
+            self.stats.crc_errors = self.stats.crc_errors.saturating_add(1);
+        }
+    }
+}
+
+impl<O: OneWire> OneWire for Counted<'_, O> {
+    type Status = O::Status;
+    type BusError = O::BusError;
+
+    fn reset(&mut self) -> OneWireResult<Self::Status, Self::BusError> {
+        self.stats.resets = self.stats.resets.saturating_add(1);
+        let res = self.bus.reset();
+        if let Err(OneWireError::NoDevicePresent) = res {
+            self.stats.presence_failures = self.stats.presence_failures.saturating_add(1);
+        }
+        res
+    }
+
+    fn write_byte(&mut self, byte: u8) -> OneWireResult<(), Self::BusError> {
+        self.bus.write_byte(byte)
+    }
+
+    fn read_byte(&mut self) -> OneWireResult<u8, Self::BusError> {
+        self.bus.read_byte()
+    }
+
+    fn write_bit(&mut self, bit: bool) -> OneWireResult<(), Self::BusError> {
+        self.bus.write_bit(bit)
+    }
+
+    fn read_bit(&mut self) -> OneWireResult<bool, Self::BusError> {
+        self.bus.read_bit()
+    }
+
+    fn get_overdrive_mode(&mut self) -> bool {
+        self.bus.get_overdrive_mode()
+    }
+
+    fn set_overdrive_mode(&mut self, enable: bool) -> OneWireResult<(), Self::BusError> {
+        self.bus.set_overdrive_mode(enable)
+    }
+}
+
+impl<O: OneWireBlock> OneWireBlock for Counted<'_, O> {
+    fn write_block(&mut self, bytes: &[u8]) -> OneWireResult<(), Self::BusError> {
+        self.bus.write_block(bytes)
+    }
+
+    fn read_block(&mut self, buf: &mut [u8]) -> OneWireResult<(), Self::BusError> {
+        self.bus.read_block(buf)
+    }
+}
+
+impl<const N: usize> Ds28ea00Group<N> {
+    /// Returns the bus health counters of every device in the group, in the order of
+    /// [`roms`](Self::roms).
+    ///
+    /// The counters cover the temperature readouts of the group, and are kept over enumerations for
+    /// the devices that are found again. Devices read on their own with
+    /// [`read_temperature`](Self::read_temperature) are not counted.
+    pub fn bus_stats(&self) -> impl Iterator<Item = (u64, BusStats)> + '_ {
+        self.roms[..self.devices]
+            .iter()
+            .zip(self.state[..self.devices].iter())
+            .map(|((rom, _), state)| (*rom, state.stats))
+    }
+
+    /// Resets the bus health counters of every device in the group.
+    pub fn clear_stats(&mut self) {
+        for state in self.state.iter_mut() {
+            state.stats = BusStats::new();
+        }
+    }
+
+    /// The counters of the devices, to carry them over an enumeration with [`Self::restore_stats`].
+    pub(crate) fn rom_stats(&self) -> [(u64, BusStats); N] {
+        let mut stats = [(0, BusStats::new()); N];
+        for (dst, ((rom, _), state)) in stats
+            .iter_mut()
+            .zip(self.roms[..self.devices].iter().zip(self.state.iter()))
+        {
+            *dst = (*rom, state.stats);
+        }
+        stats
+    }
+
+    /// Restore the counters of the devices found again, and clear those of the new devices.
+    pub(crate) fn restore_stats(&mut self, stats: &[(u64, BusStats); N]) {
+        for ((rom, _), state) in self.roms[..self.devices].iter().zip(self.state.iter_mut()) {
+            state.stats = stats
+                .iter()
+                .find(|(r, _)| r == rom)
+                .map_or(BusStats::new(), |(_, stats)| *stats);
+        }
+    }
+}
+
+mod test {
+    #[test]
+    fn test_bus_stats() {
+        use crate::{Ds28ea00Group, Temperature, mock::*};
+        use alloc::vec::Vec;
+        let mut devices = [
+            MockDevice::new(0x42, 0x1234, Temperature::from_millidegrees(20_000)),
+            MockDevice::new(0x42, 0x5678, Temperature::from_millidegrees(21_000)),
+        ];
+        let mut bus = MockBus::new(&mut devices);
+        let mut group = Ds28ea00Group::<2>::default().with_retries(2);
+        assert_eq!(group.enumerate(&mut bus).unwrap(), 2);
+        let roms: Vec<_> = group.bus_stats().map(|(rom, _)| rom).collect();
+        assert!(
+            group
+                .bus_stats()
+                .all(|(_, stats)| stats == Default::default())
+        );
+        group
+            .read_temperatures_detailed(&mut bus, true)
+            .for_each(drop);
+        bus.device_mut(roms[1])
+            .unwrap()
+            .set_corrupt_scratchpad(true);
+        group
+            .read_temperatures_detailed(&mut bus, true)
+            .for_each(drop);
+        let stats: Vec<_> = group.bus_stats().collect();
+        assert_eq!(stats[0].1.resets, 2);
+        assert_eq!(stats[0].1.crc_errors, 0);
+        // three attempts, with a reset before each retry
+        assert_eq!(stats[1].1.crc_errors, 3);
+        assert_eq!(stats[1].1.retries, 2);
+        assert_eq!(stats[1].1.resets, 1 + 3 + 2);
+        bus.device_mut(roms[1])
+            .unwrap()
+            .set_corrupt_scratchpad(false);
+        bus.fail_resets(1);
+        group.clear_stats();
+        group
+            .read_temperatures_detailed(&mut bus, true)
+            .for_each(drop);
+        let stats: Vec<_> = group.bus_stats().collect();
+        assert_eq!(stats[0].1.presence_failures, 1);
+        assert_eq!(stats[0].1.retries, 1);
+        assert_eq!(stats[0].1.resets, 3);
+        assert_eq!(stats[1].1.resets, 1);
+        assert_eq!(stats[1].1.retries, 0);
+        // kept over an enumeration
+        assert_eq!(group.enumerate(&mut bus).unwrap(), 2);
+        assert_eq!(group.bus_stats().collect::<Vec<_>>(), stats);
+    }
+}
@@ -1,6 +1,7 @@
 use bitfield_struct::bitfield;
 
-#[bitfield(u8)]
+#[bitfield(u8, defmt = cfg(feature = "defmt"))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 /// Represents the slave address for the HDC1010 sensor.
 /// The address is 7 bits long, with the least significant bit (LSB) used for read/write operations.
 /// The default address is 0x40, which is the standard I2C address for the HDC1010.
@@ -14,6 +15,16 @@ pub struct SlaveAddress {
     reserved: u8,
 }
 
+impl SlaveAddress {
+    /// The four addresses selectable with the `a0` and `a1` pins.
+    pub const ALL: [SlaveAddress; 4] = [
+        SlaveAddress::new(),
+        SlaveAddress::new().with_a0(true),
+        SlaveAddress::new().with_a1(true),
+        SlaveAddress::new().with_a0(true).with_a1(true),
+    ];
+}
+
 mod test {
     #[test]
     fn test_addr() {
@@ -1,4 +1,4 @@
-use core::time::Duration;
+use core::{marker::PhantomData, time::Duration};
 
 use embedded_hal::{
     delay::DelayNs,
@@ -6,11 +6,11 @@ use embedded_hal::{
 };
 
 use crate::{
-    Error, Humidity, Temperature,
+    Error, Humidity, MeasurementWindow, PendingMeasurement, Temperature,
     address::SlaveAddress,
     register::{
         self, AcquisitionModeEnum, Configuration, DeviceId, Hdc1010Register, HumidityResolution,
-        ManufacturerId, TemperatureResolution, Trigger,
+        ManufacturerId, PowerStatus, TemperatureResolution, temperature_from_raw,
     },
 };
 
@@ -19,7 +19,9 @@ pub struct Hdc1010<M> {
     pub(crate) address: u8,
     pub(crate) hres: HumidityResolution,
     pub(crate) tres: TemperatureResolution,
-    pub(crate) trig: M,
+    pub(crate) mode: PhantomData<M>,
+    pub(crate) power: PowerStatus,
+    pub(crate) brownout: bool,
 }
 
 #[derive(Debug, Default)]
@@ -37,7 +39,7 @@ pub trait AcquisitionMode {
 }
 
 /// Acquire humidity and temperature data in separate measurements.
-pub struct Separate(Trigger);
+pub struct Separate;
 impl AcquisitionMode for Separate {
     const MODE: AcquisitionModeEnum = AcquisitionModeEnum::Separate;
 }
@@ -66,6 +68,50 @@ impl Hdc1010Builder {
         self.tres = resolution;
         self
     }
+
+    /// Check that a HDC1010 sensor answers at the configured address, without changing its configuration.
+    ///
+    /// # Returns:
+    /// - [`Error::InvalidId`] if the device at the address is not a HDC1010.
+    pub fn probe<T: I2c<SevenBitAddress>>(&self, i2c: &mut T) -> Result<(), Error<T::Error>> {
+        Self::probe_device(&mut self.device::<Both>(), i2c)
+    }
+
+    fn device<M>(&self) -> Hdc1010<M> {
+        Hdc1010 {
+            address: self.address.into_bits(),
+            hres: self.hres,
+            tres: self.tres,
+            mode: PhantomData,
+            power: PowerStatus::Ok,
+            brownout: false,
+        }
+    }
+
+    fn probe_device<T: I2c<SevenBitAddress>, M>(
+        dev: &mut Hdc1010<M>,
+        i2c: &mut T,
+    ) -> Result<(), Error<T::Error>> {
+        // Check if the device is present by reading its ID register
+        ManufacturerId::default().read(dev, i2c)?;
+        DeviceId::default().read(dev, i2c)?;
+        Ok(())
+    }
+}
+
+/// Find the HDC1010 sensors on the bus, by probing the four addresses selectable with the address pins.
+///
+/// The configuration of the sensors found is left unchanged.
+pub fn scan<T: I2c<SevenBitAddress>>(i2c: &mut T) -> heapless::Vec<SlaveAddress, 4> {
+    SlaveAddress::ALL
+        .into_iter()
+        .filter(|addr| {
+            Hdc1010Builder::default()
+                .with_address(*addr)
+                .probe(i2c)
+                .is_ok()
+        })
+        .collect()
 }
 
 impl Hdc1010Builder {
@@ -74,17 +120,8 @@ impl Hdc1010Builder {
         self,
         i2c: &mut T,
     ) -> Result<Hdc1010<Both>, Error<T::Error>> {
-        let mut dev = Hdc1010 {
-            address: self.address.into_bits(),
-            hres: self.hres,
-            tres: self.tres,
-            trig: Both,
-        };
-        // Check if the device is present by reading its ID register
-        let mut mfg = ManufacturerId::default();
-        mfg.read(&mut dev, i2c)?;
-        let mut dev_id = DeviceId::default();
-        dev_id.read(&mut dev, i2c)?;
+        let mut dev = self.device();
+        Self::probe_device(&mut dev, i2c)?;
         let mut cfg = Configuration::default();
         cfg.read(&mut dev, i2c)?;
         cfg.set_mode(Both::MODE);
@@ -101,17 +138,8 @@ impl Hdc1010Builder {
         self,
         i2c: &mut T,
     ) -> Result<Hdc1010<Separate>, Error<T::Error>> {
-        let mut dev = Hdc1010 {
-            address: self.address.into_bits(),
-            hres: self.hres,
-            tres: self.tres,
-            trig: Separate(Trigger::Temperature),
-        };
-        // Check if the device is present by reading its ID register
-        let mut mfg = ManufacturerId::default();
-        mfg.read(&mut dev, i2c)?;
-        let mut dev_id = DeviceId::default();
-        dev_id.read(&mut dev, i2c)?;
+        let mut dev = self.device();
+        Self::probe_device(&mut dev, i2c)?;
         let mut cfg = Configuration::default();
         cfg.read(&mut dev, i2c)?;
         cfg.set_mode(Separate::MODE);
@@ -174,13 +202,40 @@ impl<U: AcquisitionMode> Hdc1010<U> {
     }
 
     /// Get the power status of the HDC1010 sensor.
+    ///
+    /// Returns `true` if the battery status bit is set, i.e. the supply voltage is below 2.8 V.
     pub fn get_power_status<T: I2c<SevenBitAddress>>(
         &mut self,
         i2c: &mut T,
     ) -> Result<bool, Error<T::Error>> {
+        self.refresh_power_status(i2c)?;
+        Ok(self.power.is_low())
+    }
+
+    /// Get the supply voltage status recorded during the last readout.
+    ///
+    /// The status is refreshed after every temperature or humidity readout, since the battery
+    /// status bit is only valid after a measurement.
+    pub fn power_status(&self) -> PowerStatus {
+        self.power
+    }
+
+    /// Returns `true` if a low supply voltage was observed since the last call, and clears the flag.
+    ///
+    /// Use this to detect supply brown-outs that may have recovered between two polls of [`Hdc1010::power_status`].
+    pub fn take_brownout(&mut self) -> bool {
+        core::mem::take(&mut self.brownout)
+    }
+
+    pub(crate) fn refresh_power_status<T: I2c<SevenBitAddress>>(
+        &mut self,
+        i2c: &mut T,
+    ) -> Result<(), Error<T::Error>> {
         let mut conf = Configuration::default();
         conf.read(self, i2c)?;
-        Ok(conf.power_ok())
+        self.power = PowerStatus::from(conf.power_ok());
+        self.brownout |= self.power.is_low();
+        Ok(())
     }
 
     /// Get the serial number of the HDC1010 sensor.
@@ -193,6 +248,31 @@ impl<U: AcquisitionMode> Hdc1010<U> {
         Ok(serial.value())
     }
 
+    /// Read a register of the HDC1010 sensor, e.g. one defined outside of the driver.
+    pub fn read_register<R: Hdc1010Register, T: I2c<SevenBitAddress>>(
+        &mut self,
+        i2c: &mut T,
+    ) -> Result<R, Error<T::Error>> {
+        let mut register = R::default();
+        register.read(self, i2c)?;
+        Ok(register)
+    }
+
+    /// Write a register of the HDC1010 sensor, e.g. one defined outside of the driver.
+    ///
+    /// Writing a register the driver keeps track of, such as the configuration register, leaves the
+    /// state of the driver as it was.
+    ///
+    /// # Returns
+    /// [`Error::ReadOnly`] if the register is read-only.
+    pub fn write_register<R: Hdc1010Register, T: I2c<SevenBitAddress>>(
+        &mut self,
+        i2c: &mut T,
+        mut register: R,
+    ) -> Result<(), Error<T::Error>> {
+        register.write(self, i2c)
+    }
+
     /// Perform a soft reset of the HDC1010 sensor.
     pub fn reset<T: I2c<SevenBitAddress>, D: DelayNs>(
         &mut self,
@@ -243,14 +323,14 @@ impl Hdc1010<Both> {
     ///   Note: If the acquisition mode is not set to [`AcquisitionMode::Both`] while trigger is [`Trigger::Both`], an error is returned.
     ///
     /// # Returns:
-    /// - [`Duration`]: The duration to wait for the measurement to complete.
+    /// - [`MeasurementWindow`]: The duration to wait for the measurement to complete.
     pub fn trigger<T: I2c<SevenBitAddress>>(
         &mut self,
         i2c: &mut T,
-    ) -> Result<Duration, Error<T::Error>> {
+    ) -> Result<MeasurementWindow, Error<T::Error>> {
         let delay = self.hres.delay_time() + self.tres.delay_time();
         Temperature::default().write(self, i2c)?;
-        Ok(Duration::from_micros(delay as _))
+        Ok(MeasurementWindow::new(Duration::from_micros(delay as _)))
     }
 
     /// Read the current temperature value.
@@ -260,67 +340,303 @@ impl Hdc1010<Both> {
     ) -> Result<(Temperature, Humidity), Error<T::Error>> {
         let mut buf = [0u8; 4];
         i2c.read(self.address, &mut buf)?;
-        let temp = Temperature {
-            value: u16::from_be_bytes([buf[0], buf[1]]),
-        };
+        let temp = temperature_from_raw(u16::from_be_bytes([buf[0], buf[1]]));
         let hum = Humidity {
             value: u16::from_be_bytes([buf[2], buf[3]]),
         };
+        self.refresh_power_status(i2c)?;
         Ok((temp, hum))
     }
-}
 
-impl Hdc1010<Separate> {
-    /// Trigger a measurement of temperature, humidity, or both.
+    /// Trigger a measurement, wait for it to complete, and read out the temperature and humidity.
+    pub fn sample_blocking<T: I2c<SevenBitAddress>, D: DelayNs>(
+        &mut self,
+        i2c: &mut T,
+        delay: &mut D,
+    ) -> Result<(Temperature, Humidity), Error<T::Error>> {
+        let window = self.trigger(i2c)?;
+        delay.delay_us(window.delay().as_micros() as u32);
+        self.read_temperature_humidity(i2c)
+    }
+
+    /// Fill `samples` with consecutive measurements, see [`sample_blocking`](Self::sample_blocking).
     ///
-    /// # Parameters:
-    /// - `kind`: An optional [`Trigger`] enum that specifies whether to measure temperature, humidity, or both.
-    ///   Note: If the acquisition mode is not set to [`AcquisitionMode::Separate`] while trigger is [`Trigger::Both`], an error is returned.
+    /// The measurements are triggered every `spacing`, or back to back if `spacing` is shorter
+    /// than the conversion time. The time spent on the bus is not accounted for.
     ///
-    /// # Returns:
-    /// - [`Duration`]: The duration to wait for the measurement to complete.
-    pub fn trigger<T: I2c<SevenBitAddress>>(
+    /// # Returns
+    /// The error of the first measurement that failed, in which case the following samples are
+    /// left untouched.
+    pub fn sample_n<T: I2c<SevenBitAddress>, D: DelayNs>(
         &mut self,
         i2c: &mut T,
-        kind: Trigger,
-    ) -> Result<Duration, Error<T::Error>> {
-        let delay = match kind {
-            Trigger::Temperature => {
-                Temperature::default().write(self, i2c)?;
-                self.tres.delay_time()
-            }
-            Trigger::Humidity => {
-                Humidity::default().write(self, i2c)?;
-                self.hres.delay_time()
+        delay: &mut D,
+        samples: &mut [(Temperature, Humidity)],
+        spacing: Duration,
+    ) -> Result<(), Error<T::Error>> {
+        let conversion =
+            Duration::from_micros((self.hres.delay_time() + self.tres.delay_time()) as _);
+        let wait = spacing.saturating_sub(conversion);
+        for (idx, sample) in samples.iter_mut().enumerate() {
+            if idx > 0 && !wait.is_zero() {
+                delay.delay_us(wait.as_micros() as u32);
             }
-        };
-        self.trig.0 = kind;
-        Ok(Duration::from_micros(delay as _))
+            *sample = self.sample_blocking(i2c, delay)?;
+        }
+        Ok(())
     }
+}
 
-    /// Read the current temperature value.
-    pub fn read_temperature<T: I2c<SevenBitAddress>>(
-        &mut self,
+impl Hdc1010<Separate> {
+    /// Trigger a temperature measurement.
+    ///
+    /// The handle is consumed and returned as part of the [`PendingMeasurement`], which is the only
+    /// way to retrieve the measured value. On error, the handle is returned along with the error.
+    pub fn trigger_temperature<T: I2c<SevenBitAddress>>(
+        mut self,
         i2c: &mut T,
-    ) -> Result<Temperature, Error<T::Error>> {
-        if self.trig.0 != Trigger::Temperature {
-            return Err(Error::InvalidOperation);
+    ) -> Result<PendingMeasurement<Temperature>, (Self, Error<T::Error>)> {
+        if let Err(e) = Temperature::default().write(&mut self, i2c) {
+            return Err((self, e));
         }
-        let mut v = Temperature::default();
-        v.read(self, i2c)?;
-        Ok(v)
+        let delay = Duration::from_micros(self.tres.delay_time() as _);
+        Ok(PendingMeasurement::new(self, delay))
     }
 
-    /// Read the current humidity value.
-    pub fn read_humidity<T: I2c<SevenBitAddress>>(
-        &mut self,
+    /// Trigger a humidity measurement.
+    ///
+    /// The handle is consumed and returned as part of the [`PendingMeasurement`], which is the only
+    /// way to retrieve the measured value. On error, the handle is returned along with the error.
+    pub fn trigger_humidity<T: I2c<SevenBitAddress>>(
+        mut self,
         i2c: &mut T,
-    ) -> Result<Humidity, Error<T::Error>> {
-        if self.trig.0 != Trigger::Humidity {
-            return Err(Error::InvalidOperation);
+    ) -> Result<PendingMeasurement<Humidity>, (Self, Error<T::Error>)> {
+        if let Err(e) = Humidity::default().write(&mut self, i2c) {
+            return Err((self, e));
         }
-        let mut v = Humidity::default();
-        v.read(self, i2c)?;
-        Ok(v)
+        let delay = Duration::from_micros(self.hres.delay_time() as _);
+        Ok(PendingMeasurement::new(self, delay))
+    }
+}
+
+mod test {
+    #[test]
+    fn test_build_and_read() {
+        extern crate std;
+        use super::Hdc1010Builder;
+        use embedded_hal_mock::eh1::i2c::{Mock, Transaction};
+        use std::vec;
+        let mut i2c = Mock::new(&[
+            Transaction::write_read(0x40, vec![0xfe], vec![0x54, 0x49]),
+            Transaction::write_read(0x40, vec![0xff], vec![0x10, 0x00]),
+            Transaction::write_read(0x40, vec![0x02], vec![0x10, 0x00]),
+            // acquisition of both values, 14-bit resolutions
+            Transaction::write(0x40, vec![0x02, 0x00, 0x00]),
+            Transaction::write(0x40, vec![0x00]),
+            Transaction::read(0x40, vec![0x80, 0x00, 0x80, 0x00]),
+            // battery status bit set
+            Transaction::write_read(0x40, vec![0x02], vec![0x08, 0x00]),
+        ]);
+        let mut hdc = Hdc1010Builder::default().build_mode_both(&mut i2c).unwrap();
+        hdc.trigger(&mut i2c).unwrap();
+        let (temp, hum) = hdc.read_temperature_humidity(&mut i2c).unwrap();
+        assert_eq!(temp.millidegrees(), 42_500);
+        assert_eq!(hum.percentage(), 50.0);
+        assert_eq!(temp.centidegrees(), 4_250);
+        assert_eq!(hum.raw(), 0x8000);
+        assert_eq!(hum.relative_humidity().centipercent(), 5_000);
+        assert!(hdc.power_status().is_low());
+        assert!(hdc.take_brownout());
+        assert!(!hdc.take_brownout());
+        i2c.done();
+    }
+
+    #[test]
+    fn test_register_extension() {
+        extern crate std;
+        use super::Hdc1010Builder;
+        use crate::{Error, Hdc1010Register16};
+        use embedded_hal_mock::eh1::i2c::{Mock, Transaction};
+        use std::vec;
+        /// A register of the device, as defined by a user of the driver.
+        #[derive(Debug, Default, PartialEq)]
+        struct Scratch(u16);
+        impl Hdc1010Register16 for Scratch {
+            const ADDRESS: u8 = 0x05;
+            fn from_raw(raw: u16) -> Self {
+                Self(raw)
+            }
+            fn to_raw(&self) -> Option<u16> {
+                Some(self.0)
+            }
+        }
+        #[derive(Debug, Default)]
+        struct Fixed;
+        impl Hdc1010Register16 for Fixed {
+            const ADDRESS: u8 = 0x06;
+            fn from_raw(_raw: u16) -> Self {
+                Self
+            }
+        }
+        let mut i2c = Mock::new(&[
+            Transaction::write_read(0x40, vec![0xfe], vec![0x54, 0x49]),
+            Transaction::write_read(0x40, vec![0xff], vec![0x10, 0x00]),
+            Transaction::write_read(0x40, vec![0x02], vec![0x10, 0x00]),
+            Transaction::write(0x40, vec![0x02, 0x00, 0x00]),
+            Transaction::write(0x40, vec![0x05, 0xbe, 0xef]),
+            Transaction::write_read(0x40, vec![0x05], vec![0xbe, 0xef]),
+        ]);
+        let mut hdc = Hdc1010Builder::default().build_mode_both(&mut i2c).unwrap();
+        hdc.write_register(&mut i2c, Scratch(0xbeef)).unwrap();
+        assert_eq!(
+            hdc.read_register::<Scratch, _>(&mut i2c).unwrap(),
+            Scratch(0xbeef)
+        );
+        assert!(matches!(
+            hdc.write_register(&mut i2c, Fixed),
+            Err(Error::ReadOnly)
+        ));
+        i2c.done();
+    }
+
+    #[test]
+    fn test_sample_n() {
+        extern crate std;
+        use super::Hdc1010Builder;
+        use crate::{Humidity, Temperature};
+        use core::time::Duration;
+        use embedded_hal::delay::DelayNs;
+        use embedded_hal_mock::eh1::i2c::{Mock, Transaction};
+        use std::{vec, vec::Vec};
+        struct Delay(u32);
+        impl DelayNs for Delay {
+            fn delay_ns(&mut self, ns: u32) {
+                self.0 += ns / 1000;
+            }
+        }
+        let mut expected = vec![
+            Transaction::write_read(0x40, vec![0xfe], vec![0x54, 0x49]),
+            Transaction::write_read(0x40, vec![0xff], vec![0x10, 0x00]),
+            Transaction::write_read(0x40, vec![0x02], vec![0x10, 0x00]),
+            Transaction::write(0x40, vec![0x02, 0x00, 0x00]),
+        ];
+        for raw in [0x4000u16, 0x6000, 0x8000] {
+            let [hi, lo] = raw.to_be_bytes();
+            expected.extend([
+                Transaction::write(0x40, vec![0x00]),
+                Transaction::read(0x40, vec![hi, lo, hi, lo]),
+                Transaction::write_read(0x40, vec![0x02], vec![0x10, 0x00]),
+            ]);
+        }
+        let mut i2c = Mock::new(&expected);
+        let mut hdc = Hdc1010Builder::default().build_mode_both(&mut i2c).unwrap();
+        let mut delay = Delay(0);
+        let mut samples: [(Temperature, Humidity); 3] = Default::default();
+        hdc.sample_n(
+            &mut i2c,
+            &mut delay,
+            &mut samples,
+            Duration::from_millis(100),
+        )
+        .unwrap();
+        let humidity = samples
+            .iter()
+            .map(|(_, hum)| hum.percentage())
+            .collect::<Vec<_>>();
+        assert_eq!(humidity, [25.0, 37.5, 50.0]);
+        // a conversion of 12.85 ms for every sample, and the rest of the spacing in between
+        assert_eq!(delay.0, 3 * 12_850 + 2 * (100_000 - 12_850));
+        i2c.done();
+    }
+
+    #[test]
+    fn test_pending_measurement() {
+        extern crate std;
+        use super::Hdc1010Builder;
+        use crate::{Error, SlaveAddress};
+        use embedded_hal::i2c::ErrorKind;
+        use embedded_hal_mock::eh1::i2c::{Mock, Transaction};
+        use std::vec;
+        let mut i2c = Mock::new(&[
+            Transaction::write_read(0x41, vec![0xfe], vec![0x54, 0x49]),
+            Transaction::write_read(0x41, vec![0xff], vec![0x10, 0x00]),
+            Transaction::write_read(0x41, vec![0x02], vec![0x00, 0x00]),
+            // separate acquisitions
+            Transaction::write(0x41, vec![0x02, 0x10, 0x00]),
+            Transaction::write(0x41, vec![0x00]),
+            // the conversion is not complete yet, so the read is not acknowledged
+            Transaction::read(0x41, vec![0x00, 0x00]).with_error(ErrorKind::Other),
+            Transaction::read(0x41, vec![0x00, 0x00]),
+            Transaction::write_read(0x41, vec![0x02], vec![0x10, 0x00]),
+        ]);
+        let hdc = Hdc1010Builder::default()
+            .with_address(SlaveAddress::new().with_a0(true))
+            .build_mode_separate(&mut i2c)
+            .unwrap();
+        let Ok(pending) = hdc.trigger_temperature(&mut i2c) else {
+            panic!("trigger failed");
+        };
+        let Err((pending, Error::I2c(ErrorKind::Other))) = pending.read(&mut i2c) else {
+            panic!("read should fail");
+        };
+        let Ok((temp, hdc)) = pending.read(&mut i2c) else {
+            panic!("read failed");
+        };
+        assert_eq!(temp.millidegrees(), -40_000);
+        assert!(!hdc.power_status().is_low());
+        i2c.done();
+    }
+
+    #[test]
+    fn test_scan() {
+        extern crate std;
+        use crate::scan;
+        use embedded_hal::i2c::{ErrorKind, NoAcknowledgeSource};
+        use embedded_hal_mock::eh1::i2c::{Mock, Transaction};
+        use std::vec;
+        let nack = ErrorKind::NoAcknowledge(NoAcknowledgeSource::Address);
+        let mut i2c = Mock::new(&[
+            Transaction::write_read(0x40, vec![0xfe], vec![0x54, 0x49]),
+            Transaction::write_read(0x40, vec![0xff], vec![0x10, 0x00]),
+            Transaction::write_read(0x41, vec![0xfe], vec![0x00, 0x00]).with_error(nack),
+            // another sensor sharing the address range
+            Transaction::write_read(0x42, vec![0xfe], vec![0x54, 0x49]),
+            Transaction::write_read(0x42, vec![0xff], vec![0x30, 0x00]),
+            Transaction::write_read(0x43, vec![0xfe], vec![0x54, 0x49]),
+            Transaction::write_read(0x43, vec![0xff], vec![0x10, 0x00]),
+        ]);
+        let found = scan(&mut i2c);
+        assert_eq!(
+            found
+                .iter()
+                .map(|addr| addr.into_bits())
+                .collect::<vec::Vec<_>>(),
+            [0x40, 0x43]
+        );
+        i2c.done();
+    }
+
+    #[test]
+    fn test_invalid_id() {
+        extern crate std;
+        use super::Hdc1010Builder;
+        use crate::Error;
+        use embedded_hal::i2c::{ErrorKind, NoAcknowledgeSource};
+        use embedded_hal_mock::eh1::i2c::{Mock, Transaction};
+        use std::vec;
+        let mut i2c = Mock::new(&[
+            Transaction::write_read(0x40, vec![0xfe], vec![0x12, 0x34]),
+            Transaction::write_read(0x40, vec![0xfe], vec![0x00, 0x00])
+                .with_error(ErrorKind::NoAcknowledge(NoAcknowledgeSource::Address)),
+        ]);
+        assert!(matches!(
+            Hdc1010Builder::default().build_mode_both(&mut i2c),
+            Err(Error::InvalidId)
+        ));
+        assert!(matches!(
+            Hdc1010Builder::default().build_mode_both(&mut i2c),
+            Err(Error::I2c(ErrorKind::NoAcknowledge(_)))
+        ));
+        i2c.done();
     }
 }
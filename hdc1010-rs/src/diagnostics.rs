@@ -0,0 +1,117 @@
+use embedded_hal::i2c::{I2c, SevenBitAddress};
+
+use crate::{
+    AcquisitionModeEnum, Error, Hdc1010, HumidityResolution, PowerStatus, TemperatureResolution,
+    core::AcquisitionMode,
+    register::{
+        Configuration, DeviceId, HDC1010_DEVICE_ID, HDC1010_MANUFACTURER_ID, Hdc1010Register,
+        ManufacturerId, SerialId,
+    },
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+/// Identification and configuration registers of a HDC1010 sensor, for bug reports and bring-up logging.
+pub struct Diagnostics {
+    /// I2C address of the sensor.
+    pub address: u8,
+    /// Manufacturer ID, `0x5449` for Texas Instruments.
+    pub manufacturer_id: u16,
+    /// Device ID, `0x1000` for the HDC1010.
+    pub device_id: u16,
+    /// Serial number of the sensor.
+    pub serial: u64,
+    /// Raw value of the configuration register.
+    pub configuration: u16,
+    /// Acquisition mode set in the configuration register.
+    pub mode: AcquisitionModeEnum,
+    /// Humidity resolution set in the configuration register.
+    pub humidity_resolution: HumidityResolution,
+    /// Temperature resolution set in the configuration register.
+    pub temperature_resolution: TemperatureResolution,
+    /// Whether the heater is enabled.
+    pub heater: bool,
+    /// Supply voltage status reported by the battery status bit.
+    pub power: PowerStatus,
+}
+
+impl Diagnostics {
+    /// Returns `true` if the manufacturer and device IDs are those of a HDC1010.
+    pub fn valid_id(&self) -> bool {
+        self.manufacturer_id == HDC1010_MANUFACTURER_ID && self.device_id == HDC1010_DEVICE_ID
+    }
+}
+
+impl<U: AcquisitionMode> Hdc1010<U> {
+    /// Read the identification and configuration registers of the sensor.
+    ///
+    /// Unlike the builder, mismatching IDs are reported as read instead of failing with
+    /// [`Error::InvalidId`], see [`Diagnostics::valid_id`].
+    pub fn diagnostics<T: I2c<SevenBitAddress>>(
+        &mut self,
+        i2c: &mut T,
+    ) -> Result<Diagnostics, Error<T::Error>> {
+        let mut mfg = ManufacturerId::default();
+        match mfg.read(self, i2c) {
+            Ok(()) | Err(Error::InvalidId) => {}
+            Err(e) => return Err(e),
+        }
+        let mut dev_id = DeviceId::default();
+        match dev_id.read(self, i2c) {
+            Ok(()) | Err(Error::InvalidId) => {}
+            Err(e) => return Err(e),
+        }
+        let mut serial = SerialId::default();
+        serial.read(self, i2c)?;
+        let mut conf = Configuration::default();
+        conf.read(self, i2c)?;
+        Ok(Diagnostics {
+            address: self.address,
+            manufacturer_id: mfg.value(),
+            device_id: dev_id.value(),
+            serial: serial.value(),
+            configuration: conf.into_bits(),
+            mode: conf.mode(),
+            humidity_resolution: conf.humidity_resolution(),
+            temperature_resolution: conf.temperature_resolution(),
+            heater: conf.heater_enable(),
+            power: PowerStatus::from(conf.power_ok()),
+        })
+    }
+}
+
+mod test {
+    #[test]
+    fn test_diagnostics() {
+        extern crate std;
+        use crate::{Hdc1010Builder, HumidityResolution, PowerStatus, SlaveAddress};
+        use embedded_hal_mock::eh1::i2c::{Mock, Transaction};
+        use std::vec;
+        let mut i2c = Mock::new(&[
+            Transaction::write_read(0x42, vec![0xfe], vec![0x54, 0x49]),
+            Transaction::write_read(0x42, vec![0xff], vec![0x10, 0x00]),
+            Transaction::write_read(0x42, vec![0x02], vec![0x00, 0x00]),
+            Transaction::write(0x42, vec![0x02, 0x02, 0x00]),
+            // a HDC1080 answering with its own device ID
+            Transaction::write_read(0x42, vec![0xfe], vec![0x54, 0x49]),
+            Transaction::write_read(0x42, vec![0xff], vec![0x10, 0x50]),
+            Transaction::write_read(0x42, vec![0xfb], vec![0, 0, 0, 0, 0x01, 0x80]),
+            Transaction::write_read(0x42, vec![0x02], vec![0x2a, 0x00]),
+        ]);
+        let mut hdc = Hdc1010Builder::default()
+            .with_address(SlaveAddress::new().with_a1(true))
+            .with_humidity_resolution(HumidityResolution::EightBit)
+            .build_mode_both(&mut i2c)
+            .unwrap();
+        let diag = hdc.diagnostics(&mut i2c).unwrap();
+        assert!(!diag.valid_id());
+        assert_eq!(diag.device_id, 0x1050);
+        assert_eq!(diag.serial, 3);
+        assert_eq!(diag.configuration, 0x2a00);
+        assert_eq!(diag.humidity_resolution, HumidityResolution::EightBit);
+        assert!(diag.heater);
+        assert_eq!(diag.power, PowerStatus::Low);
+        i2c.done();
+    }
+}
@@ -0,0 +1,83 @@
+use core::time::Duration;
+
+use embedded_hal::{
+    delay::DelayNs,
+    digital::{Error as _, InputPin},
+};
+
+use crate::{AcquisitionMode, Error, Hdc1010};
+
+/// Interval between two consecutive polls of the DRDYn pin, in microseconds.
+const DRDY_POLL_INTERVAL_US: u32 = 100;
+
+impl<U: AcquisitionMode> Hdc1010<U> {
+    /// Wait for the DRDYn pin to signal the end of a conversion.
+    ///
+    /// The HDC1010 drives DRDYn high when a measurement is triggered and pulls it low as soon as the
+    /// conversion is complete. This polls the pin until it goes low instead of sleeping for the
+    /// worst-case conversion time returned by `trigger`.
+    ///
+    /// # Parameters:
+    /// - `pin`: The input pin connected to DRDYn.
+    /// - `delay`: Delay provider used between two polls.
+    /// - `timeout`: Maximum time to wait for the conversion to complete.
+    ///
+    /// # Returns:
+    /// - [`Error::Timeout`] if DRDYn did not go low within `timeout`.
+    /// - [`Error::Pin`] if the pin could not be read.
+    pub fn wait_ready<P: InputPin, D: DelayNs, E>(
+        &self,
+        pin: &mut P,
+        delay: &mut D,
+        timeout: Duration,
+    ) -> Result<(), Error<E>> {
+        let mut waited = 0;
+        let timeout = timeout.as_micros();
+        loop {
+            if pin.is_low().map_err(|e| Error::Pin(e.kind()))? {
+                return Ok(());
+            }
+            if waited >= timeout {
+                return Err(Error::Timeout);
+            }
+            delay.delay_us(DRDY_POLL_INTERVAL_US);
+            waited += DRDY_POLL_INTERVAL_US as u128;
+        }
+    }
+
+    #[cfg(feature = "async")]
+    /// Asynchronously wait for the DRDYn pin to signal the end of a conversion.
+    ///
+    /// This is the asynchronous counterpart of [`Hdc1010::wait_ready`], which waits on a falling edge
+    /// of DRDYn (or returns immediately if the pin is already low) while racing against `timeout`.
+    ///
+    /// # Returns:
+    /// - [`Error::Timeout`] if DRDYn did not go low within `timeout`.
+    /// - [`Error::Pin`] if the pin could not be read.
+    pub async fn wait_ready_async<P, D, E>(
+        &self,
+        pin: &mut P,
+        delay: &mut D,
+        timeout: Duration,
+    ) -> Result<(), Error<E>>
+    where
+        P: embedded_hal_async::digital::Wait,
+        D: embedded_hal_async::delay::DelayNs,
+    {
+        use core::{future::Future, pin::pin, task::Poll};
+
+        let timeout = timeout.as_micros().min(u32::MAX as u128) as u32;
+        let mut ready = pin!(pin.wait_for_low());
+        let mut expired = pin!(delay.delay_us(timeout));
+        core::future::poll_fn(|cx| {
+            if let Poll::Ready(res) = ready.as_mut().poll(cx) {
+                return Poll::Ready(res.map_err(|e| Error::Pin(e.kind())));
+            }
+            if expired.as_mut().poll(cx).is_ready() {
+                return Poll::Ready(Err(Error::Timeout));
+            }
+            Poll::Pending
+        })
+        .await
+    }
+}
@@ -11,8 +11,55 @@ pub enum Error<E> {
     ReadOnly,
     /// An error occurred due to an invalid operation.
     Timeout,
-    /// The sensor is not configured for the requested operation.
-    InvalidOperation,
+    /// An error occurred while reading the DRDYn pin.
+    Pin(embedded_hal::digital::ErrorKind),
+}
+
+impl<E: core::fmt::Debug> core::fmt::Display for Error<E> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Error::I2c(e) => write!(f, "I2C error: {e:?}"),
+            Error::InvalidAddress => write!(f, "invalid address"),
+            Error::InvalidId => write!(f, "invalid manufacturer or device ID"),
+            Error::ReadOnly => write!(f, "register is read-only"),
+            Error::Timeout => write!(f, "timed out"),
+            Error::Pin(e) => write!(f, "DRDYn pin error: {e:?}"),
+        }
+    }
+}
+
+impl<E: core::fmt::Debug> core::error::Error for Error<E> {}
+
+#[cfg(feature = "defmt")]
+impl<E: defmt::Format> defmt::Format for Error<E> {
+    fn format(&self, f: defmt::Formatter) {
+        match self {
+            Error::I2c(e) => defmt::write!(f, "I2C error: {}", e),
+            Error::InvalidAddress => defmt::write!(f, "invalid address"),
+            Error::InvalidId => defmt::write!(f, "invalid manufacturer or device ID"),
+            Error::ReadOnly => defmt::write!(f, "register is read-only"),
+            Error::Timeout => defmt::write!(f, "timed out"),
+            Error::Pin(e) => defmt::write!(f, "DRDYn pin error: {}", defmt::Debug2Format(e)),
+        }
+    }
+}
+
+impl<E> Error<E> {
+    /// Returns the error of the I2C bus, if the error occurred while communicating with it.
+    pub fn i2c(&self) -> Option<&E> {
+        match self {
+            Error::I2c(e) => Some(e),
+            _ => None,
+        }
+    }
+
+    /// Returns the error of the I2C bus, if the error occurred while communicating with it.
+    pub fn into_i2c(self) -> Option<E> {
+        match self {
+            Error::I2c(e) => Some(e),
+            _ => None,
+        }
+    }
 }
 
 impl<E> From<E> for Error<E> {
@@ -20,3 +67,20 @@ impl<E> From<E> for Error<E> {
         Error::I2c(e)
     }
 }
+
+mod test {
+    #[test]
+    fn test_error() {
+        use super::Error;
+        use core::fmt::Write;
+        use embedded_hal::i2c::{ErrorKind, NoAcknowledgeSource};
+        let nack = ErrorKind::NoAcknowledge(NoAcknowledgeSource::Address);
+        let e: &dyn core::error::Error = &Error::I2c(nack);
+        let mut text = heapless::String::<64>::new();
+        write!(text, "{e}").unwrap();
+        assert_eq!(text, "I2C error: NoAcknowledge(Address)");
+        // the error of the bus is kept
+        assert_eq!(Error::I2c(nack).i2c(), Some(&nack));
+        assert_eq!(Error::<ErrorKind>::Timeout.into_i2c(), None);
+    }
+}
@@ -0,0 +1,274 @@
+use core::time::Duration;
+
+use embedded_hal::i2c::{I2c, SevenBitAddress};
+
+use crate::{AcquisitionMode, Error, Hdc1010};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+/// State of the integrated heater as scheduled by a [`HeaterController`].
+pub enum HeaterState {
+    #[default]
+    /// The heater is off, and the sensor is at ambient temperature.
+    Off,
+    /// The heater is on.
+    On,
+    /// The heater is off, but the sensor has not cooled down to ambient temperature yet.
+    Settling,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+/// A reading tagged with the heater state at the time it was taken.
+pub enum Reading<V> {
+    /// The reading was taken while the sensor was at ambient temperature.
+    Valid(V),
+    /// The reading was taken while the heater was on or the sensor was cooling down,
+    /// and does not reflect the ambient temperature and humidity.
+    Heated(V),
+}
+
+impl<V> Reading<V> {
+    /// Returns `true` if the reading was taken with the heater off.
+    pub fn is_valid(&self) -> bool {
+        matches!(self, Reading::Valid(_))
+    }
+
+    /// Returns the reading if it was taken with the heater off, masking heated readings.
+    pub fn valid(self) -> Option<V> {
+        match self {
+            Reading::Valid(v) => Some(v),
+            Reading::Heated(_) => None,
+        }
+    }
+
+    /// Returns the reading regardless of the heater state.
+    pub fn into_inner(self) -> V {
+        match self {
+            Reading::Valid(v) | Reading::Heated(v) => v,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+/// Duty-cycle controller for the integrated heater of the HDC1010 sensor.
+///
+/// The heater is run for `on_time` at the start of every `period`, e.g. to burn off condensation.
+/// Since heat from the element corrupts both temperature and humidity readings, readings taken
+/// while the heater is on, or within the settle time after it is turned off, are tagged as
+/// [`Reading::Heated`] by [`HeaterController::tag`].
+///
+/// The duty cycle is paused with [`HeaterController::stop`] and resumed with
+/// [`HeaterController::restart`].
+///
+/// Note: The heater only dissipates power while conversions are running, so measurements should
+/// be triggered continuously during the on-time for the heater to be effective.
+pub struct HeaterController {
+    on_time: Duration,
+    period: Duration,
+    settle: Duration,
+    start: Option<Duration>,
+    stopped: Option<Duration>,
+    state: HeaterState,
+    heater: bool,
+}
+
+impl HeaterController {
+    /// Create a new heater controller.
+    ///
+    /// # Parameters:
+    /// - `on_time`: The duration for which the heater is on in every period. Clamped to `period`.
+    /// - `period`: The period of the heater duty cycle.
+    pub fn new(on_time: Duration, period: Duration) -> Self {
+        Self {
+            on_time: on_time.min(period),
+            period,
+            settle: Duration::ZERO,
+            start: None,
+            stopped: None,
+            state: HeaterState::Off,
+            heater: false,
+        }
+    }
+
+    /// Set the duration after the heater is turned off during which readings are still tagged as heated.
+    ///
+    /// Defaults to zero. Clamped to the off-time of the duty cycle.
+    pub fn with_settle_time(mut self, settle: Duration) -> Self {
+        self.settle = settle.min(self.period - self.on_time);
+        self
+    }
+
+    /// Update the heater according to the duty cycle.
+    ///
+    /// The heater is only written when its scheduled state changes. The duty cycle starts at the
+    /// first call to this function. While the controller is stopped, the heater is left off, and
+    /// the state turns from [`HeaterState::Settling`] to [`HeaterState::Off`] once the settle time
+    /// has elapsed since the stop.
+    ///
+    /// # Parameters:
+    /// - `hdc`: The sensor whose heater is controlled.
+    /// - `i2c`: The I2C bus the sensor is connected to.
+    /// - `now`: The current time, measured from an arbitrary monotonic epoch.
+    ///
+    /// # Returns:
+    /// - [`HeaterState`]: The heater state after the update.
+    pub fn poll<U: AcquisitionMode, T: I2c<SevenBitAddress>>(
+        &mut self,
+        hdc: &mut Hdc1010<U>,
+        i2c: &mut T,
+        now: Duration,
+    ) -> Result<HeaterState, Error<T::Error>> {
+        if let Some(stopped) = self.stopped {
+            if now.saturating_sub(stopped) >= self.settle {
+                self.state = HeaterState::Off;
+            }
+            return Ok(self.state);
+        }
+        let start = *self.start.get_or_insert(now);
+        let phase = if self.period.is_zero() {
+            Duration::ZERO
+        } else {
+            let elapsed = now.saturating_sub(start).as_nanos() % self.period.as_nanos();
+            Duration::from_nanos(elapsed as u64)
+        };
+        let state = if phase < self.on_time {
+            HeaterState::On
+        } else if phase < self.on_time + self.settle {
+            HeaterState::Settling
+        } else {
+            HeaterState::Off
+        };
+        let heater = state == HeaterState::On;
+        if heater != self.heater {
+            hdc.set_heater(i2c, heater)?;
+            self.heater = heater;
+        }
+        self.state = state;
+        Ok(state)
+    }
+
+    /// Turn the heater off, and stop the duty cycle until [`HeaterController::restart`].
+    ///
+    /// If the heater was on or the sensor was cooling down, readings remain tagged as heated until
+    /// a call to [`HeaterController::poll`] after the settle time.
+    ///
+    /// # Parameters:
+    /// - `hdc`: The sensor whose heater is controlled.
+    /// - `i2c`: The I2C bus the sensor is connected to.
+    /// - `now`: The current time, on the clock of [`HeaterController::poll`].
+    pub fn stop<U: AcquisitionMode, T: I2c<SevenBitAddress>>(
+        &mut self,
+        hdc: &mut Hdc1010<U>,
+        i2c: &mut T,
+        now: Duration,
+    ) -> Result<(), Error<T::Error>> {
+        hdc.set_heater(i2c, false)?;
+        self.heater = false;
+        self.start = None;
+        self.stopped = Some(now);
+        if self.state != HeaterState::Off {
+            self.state = if self.settle.is_zero() {
+                HeaterState::Off
+            } else {
+                HeaterState::Settling
+            };
+        }
+        Ok(())
+    }
+
+    /// Resume the duty cycle after [`HeaterController::stop`].
+    ///
+    /// The duty cycle starts over with the heater on at the next call to [`HeaterController::poll`].
+    pub fn restart(&mut self) {
+        self.stopped = None;
+        self.start = None;
+    }
+
+    /// Returns `true` if the duty cycle is stopped.
+    pub fn is_stopped(&self) -> bool {
+        self.stopped.is_some()
+    }
+
+    /// Get the heater state as of the last update.
+    pub fn state(&self) -> HeaterState {
+        self.state
+    }
+
+    /// Returns `true` if readings taken now are affected by the heater.
+    pub fn is_heating(&self) -> bool {
+        self.state != HeaterState::Off
+    }
+
+    /// Tag a reading with the current heater state.
+    pub fn tag<V>(&self, value: V) -> Reading<V> {
+        if self.is_heating() {
+            Reading::Heated(value)
+        } else {
+            Reading::Valid(value)
+        }
+    }
+}
+
+mod test {
+    #[test]
+    fn test_heater_stop() {
+        extern crate std;
+        use super::{HeaterController, HeaterState, Reading};
+        use crate::Hdc1010Builder;
+        use core::time::Duration;
+        use embedded_hal_mock::eh1::i2c::{Mock, Transaction};
+        use std::vec;
+        let heater = |on: bool| {
+            [
+                Transaction::write_read(0x40, vec![0x02], vec![0x00, 0x00]),
+                Transaction::write(0x40, vec![0x02, if on { 0x20 } else { 0x00 }, 0x00]),
+            ]
+        };
+        let mut expected = vec![
+            Transaction::write_read(0x40, vec![0xfe], vec![0x54, 0x49]),
+            Transaction::write_read(0x40, vec![0xff], vec![0x10, 0x00]),
+            Transaction::write_read(0x40, vec![0x02], vec![0x10, 0x00]),
+            Transaction::write(0x40, vec![0x02, 0x00, 0x00]),
+        ];
+        expected.extend(heater(true));
+        expected.extend(heater(false));
+        expected.extend(heater(true));
+        let mut i2c = Mock::new(&expected);
+        let mut hdc = Hdc1010Builder::default().build_mode_both(&mut i2c).unwrap();
+        let ms = Duration::from_millis;
+        let mut ctrl = HeaterController::new(ms(500), ms(10_000)).with_settle_time(ms(1_000));
+        assert_eq!(
+            ctrl.poll(&mut hdc, &mut i2c, ms(0)).unwrap(),
+            HeaterState::On
+        );
+        // stopped while on: readings stay heated for the settle time, with the heater off
+        ctrl.stop(&mut hdc, &mut i2c, ms(100)).unwrap();
+        assert!(ctrl.is_stopped());
+        assert_eq!(ctrl.state(), HeaterState::Settling);
+        assert_eq!(ctrl.tag(1), Reading::Heated(1));
+        assert_eq!(
+            ctrl.poll(&mut hdc, &mut i2c, ms(600)).unwrap(),
+            HeaterState::Settling
+        );
+        assert_eq!(
+            ctrl.poll(&mut hdc, &mut i2c, ms(1_100)).unwrap(),
+            HeaterState::Off
+        );
+        assert_eq!(ctrl.tag(1), Reading::Valid(1));
+        // and the duty cycle does not resume by itself
+        assert_eq!(
+            ctrl.poll(&mut hdc, &mut i2c, ms(20_000)).unwrap(),
+            HeaterState::Off
+        );
+        ctrl.restart();
+        assert!(!ctrl.is_stopped());
+        assert_eq!(
+            ctrl.poll(&mut hdc, &mut i2c, ms(20_100)).unwrap(),
+            HeaterState::On
+        );
+        i2c.done();
+    }
+}
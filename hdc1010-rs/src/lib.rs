@@ -3,14 +3,51 @@
 //!# HDC1010 - Driver for the Texas Instruments HDC1010 Humidity and Temperature Sensor
 //! This crate provides a driver for the HDC1010 sensor, allowing you to read humidity and temperature data.
 //! It supports various configurations such as acquisition mode and resolution settings.
+//!
+//! The driver is no-std, and builds for bare metal targets such as `thumbv7em-none-eabihf` with
+//! any combination of the following features:
+//! - `async`: Asynchronous triggers and reads with `embedded-hal-async`.
+//! - `defmt`: `defmt::Format` implementations of the readings and of [`Error`].
+//! - `serde`: Serialization of the readings.
+//! - `std`: A [`Clock`] over `std::time::Instant`.
+//!
+//! [`Error`] implements `core::error::Error` without any feature, so that host binaries can return
+//! it as a `Box<dyn Error>`.
+//!
+//! Registers that the driver does not cover are accessed by implementing [`Hdc1010Register`], or
+//! [`Hdc1010Register16`] for 16-bit registers, and reading or writing them with
+//! [`Hdc1010::read_register`] and [`Hdc1010::write_register`].
+#[cfg(feature = "std")]
+extern crate std;
+
 mod address;
 mod core;
+mod diagnostics;
+mod drdy;
 mod error;
+mod heater;
+mod owned;
+mod pending;
 mod register;
+mod sensor;
+mod window;
 
 pub use address::SlaveAddress;
-pub use core::{AcquisitionMode, Both, Hdc1010, Hdc1010Builder, Separate};
+pub use core::{AcquisitionMode, Both, Hdc1010, Hdc1010Builder, Separate, scan};
+pub use diagnostics::Diagnostics;
 pub use error::Error;
+pub use heater::{HeaterController, HeaterState, Reading};
+pub use owned::{Hdc1010Owned, OwnedResult};
+pub use pending::{PendingMeasurement, ReadResult};
+/// Relative humidity measurement reported by the sensor.
+pub use piccthermo_core::RelativeHumidity;
+/// Temperature measurement reported by the sensor.
+pub use piccthermo_core::Temperature;
+pub use piccthermo_core::{conditioning, hygrometry};
 pub use register::{
-    AcquisitionModeEnum, Humidity, HumidityResolution, Temperature, TemperatureResolution, Trigger,
+    AcquisitionModeEnum, Hdc1010Register, Hdc1010Register16, Humidity, HumidityResolution,
+    PowerStatus, TemperatureResolution, Trigger,
 };
+#[cfg(feature = "std")]
+pub use window::StdClock;
+pub use window::{Clock, MeasurementWindow};
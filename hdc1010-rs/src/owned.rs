@@ -0,0 +1,201 @@
+//! A HDC1010 sensor holding its I2C bus, see [`Hdc1010Owned`].
+use core::time::Duration;
+
+use embedded_hal::{
+    delay::DelayNs,
+    i2c::{ErrorType, I2c, SevenBitAddress},
+};
+
+use crate::{
+    AcquisitionMode, Both, Error, Hdc1010, Hdc1010Builder, Humidity, HumidityResolution,
+    MeasurementWindow, PowerStatus, Separate, Temperature, TemperatureResolution,
+};
+
+/// Outcome of building a [`Hdc1010Owned`]: the sensor, or the bus along with the error.
+pub type OwnedResult<T, M> = Result<Hdc1010Owned<T, M>, (T, Error<<T as ErrorType>::Error>)>;
+
+/// A HDC1010 sensor that owns its I2C bus.
+///
+/// The [`Hdc1010`] handle borrows the bus in every call, so that several sensors can share a bus
+/// that is passed around by the caller. This variant holds the bus instead, e.g. a device of a bus
+/// shared with the `embedded-hal-bus` utilities, so that a task can own a sensor and its bus
+/// outright. Use [`borrowed`](Self::borrowed) for the methods of [`Hdc1010`] that are not mirrored
+/// here, and [`release`](Self::release) to take the bus back.
+pub struct Hdc1010Owned<I2C, M> {
+    hdc: Hdc1010<M>,
+    i2c: I2C,
+}
+
+impl<I2C: I2c<SevenBitAddress>, M: AcquisitionMode> Hdc1010Owned<I2C, M> {
+    /// Create a sensor owning `i2c` from a handle built on that bus.
+    pub fn new(hdc: Hdc1010<M>, i2c: I2C) -> Self {
+        Self { hdc, i2c }
+    }
+
+    /// Release the bus, returning it with the handle of the sensor.
+    pub fn release(self) -> (Hdc1010<M>, I2C) {
+        (self.hdc, self.i2c)
+    }
+
+    /// Borrow the handle of the sensor along with its bus, to call the methods of [`Hdc1010`].
+    pub fn borrowed(&mut self) -> (&mut Hdc1010<M>, &mut I2C) {
+        (&mut self.hdc, &mut self.i2c)
+    }
+
+    /// Get the address of the device.
+    pub fn get_address(&self) -> u8 {
+        self.hdc.get_address()
+    }
+
+    /// Get the current temperature and humidity resolutions.
+    pub fn get_resolution(&mut self) -> (HumidityResolution, TemperatureResolution) {
+        self.hdc.get_resolution()
+    }
+
+    /// Set the humidity and temperature resolutions.
+    pub fn set_resolution(
+        &mut self,
+        humidity_resolution: HumidityResolution,
+        temperature_resolution: TemperatureResolution,
+    ) -> Result<(), Error<I2C::Error>> {
+        self.hdc
+            .set_resolution(&mut self.i2c, humidity_resolution, temperature_resolution)
+    }
+
+    /// Set the heater state of the HDC1010 sensor.
+    pub fn set_heater(&mut self, enable: bool) -> Result<(), Error<I2C::Error>> {
+        self.hdc.set_heater(&mut self.i2c, enable)
+    }
+
+    /// Get the heater state of the HDC1010 sensor.
+    pub fn get_heater(&mut self) -> Result<bool, Error<I2C::Error>> {
+        self.hdc.get_heater(&mut self.i2c)
+    }
+
+    /// Get the power status of the HDC1010 sensor, see [`Hdc1010::get_power_status`].
+    pub fn get_power_status(&mut self) -> Result<bool, Error<I2C::Error>> {
+        self.hdc.get_power_status(&mut self.i2c)
+    }
+
+    /// Get the supply voltage status recorded during the last readout.
+    pub fn power_status(&self) -> PowerStatus {
+        self.hdc.power_status()
+    }
+
+    /// Returns `true` if a low supply voltage was observed since the last call, and clears the flag.
+    pub fn take_brownout(&mut self) -> bool {
+        self.hdc.take_brownout()
+    }
+
+    /// Get the serial number of the HDC1010 sensor.
+    pub fn get_serial(&mut self) -> Result<u64, Error<I2C::Error>> {
+        self.hdc.get_serial(&mut self.i2c)
+    }
+
+    /// Perform a soft reset of the HDC1010 sensor.
+    pub fn reset<D: DelayNs>(&mut self, delay: &mut D) -> Result<(), Error<I2C::Error>> {
+        self.hdc.reset(&mut self.i2c, delay)
+    }
+}
+
+impl<I2C: I2c<SevenBitAddress>> Hdc1010Owned<I2C, Both> {
+    /// Trigger a measurement of temperature and humidity, see [`Hdc1010::trigger`].
+    pub fn trigger(&mut self) -> Result<MeasurementWindow, Error<I2C::Error>> {
+        self.hdc.trigger(&mut self.i2c)
+    }
+
+    /// Read the temperature and humidity values.
+    pub fn read_temperature_humidity(
+        &mut self,
+    ) -> Result<(Temperature, Humidity), Error<I2C::Error>> {
+        self.hdc.read_temperature_humidity(&mut self.i2c)
+    }
+
+    /// Trigger a measurement, wait for it and read it out, see [`Hdc1010::sample_blocking`].
+    pub fn sample_blocking<D: DelayNs>(
+        &mut self,
+        delay: &mut D,
+    ) -> Result<(Temperature, Humidity), Error<I2C::Error>> {
+        self.hdc.sample_blocking(&mut self.i2c, delay)
+    }
+
+    /// Fill `samples` with measurements triggered every `spacing`, see [`Hdc1010::sample_n`].
+    pub fn sample_n<D: DelayNs>(
+        &mut self,
+        delay: &mut D,
+        samples: &mut [(Temperature, Humidity)],
+        spacing: Duration,
+    ) -> Result<(), Error<I2C::Error>> {
+        self.hdc.sample_n(&mut self.i2c, delay, samples, spacing)
+    }
+}
+
+impl Hdc1010Builder {
+    /// Build the HDC1010 sensor with the specified configuration, owning `i2c`.
+    ///
+    /// On error, the bus is returned along with the error.
+    pub fn build_owned_both<T: I2c<SevenBitAddress>>(self, mut i2c: T) -> OwnedResult<T, Both> {
+        match self.build_mode_both(&mut i2c) {
+            Ok(hdc) => Ok(Hdc1010Owned::new(hdc, i2c)),
+            Err(e) => Err((i2c, e)),
+        }
+    }
+
+    /// Build the HDC1010 sensor in separate acquisition mode, owning `i2c`.
+    ///
+    /// The measurements are triggered through [`Hdc1010Owned::borrowed`], or on the handle
+    /// returned by [`Hdc1010Owned::release`] since the pending measurements hold the handle.
+    /// On error, the bus is returned along with the error.
+    pub fn build_owned_separate<T: I2c<SevenBitAddress>>(
+        self,
+        mut i2c: T,
+    ) -> OwnedResult<T, Separate> {
+        match self.build_mode_separate(&mut i2c) {
+            Ok(hdc) => Ok(Hdc1010Owned::new(hdc, i2c)),
+            Err(e) => Err((i2c, e)),
+        }
+    }
+}
+
+mod test {
+    #[test]
+    fn test_owned() {
+        extern crate std;
+        use crate::{Hdc1010Builder, SlaveAddress};
+        use embedded_hal_mock::eh1::i2c::{Mock, Transaction};
+        use std::vec;
+        let mut i2c = Mock::new(&[
+            Transaction::write_read(0x40, vec![0xfe], vec![0x54, 0x49]),
+            Transaction::write_read(0x40, vec![0xff], vec![0x10, 0x00]),
+            Transaction::write_read(0x40, vec![0x02], vec![0x10, 0x00]),
+            Transaction::write(0x40, vec![0x02, 0x00, 0x00]),
+            Transaction::write(0x40, vec![0x00]),
+            Transaction::read(0x40, vec![0x80, 0x00, 0x80, 0x00]),
+            Transaction::write_read(0x40, vec![0x02], vec![0x10, 0x00]),
+            // heater enabled through the borrowed handle
+            Transaction::write_read(0x40, vec![0x02], vec![0x00, 0x00]),
+            Transaction::write(0x40, vec![0x02, 0x20, 0x00]),
+            // no sensor at the next address
+            Transaction::write_read(0x41, vec![0xfe], vec![0x00, 0x00]),
+        ]);
+        let mut hdc = Hdc1010Builder::default()
+            .build_owned_both(i2c.clone())
+            .unwrap_or_else(|_| panic!("build failed"));
+        hdc.trigger().unwrap();
+        let (temp, hum) = hdc.read_temperature_humidity().unwrap();
+        assert_eq!(temp.millidegrees(), 42_500);
+        assert_eq!(hum.percentage(), 50.0);
+        assert!(!hdc.take_brownout());
+        let (handle, bus) = hdc.borrowed();
+        handle.set_heater(bus, true).unwrap();
+        let (handle, _) = hdc.release();
+        assert_eq!(handle.get_address(), 0x40);
+        let Err((_, _)) = Hdc1010Builder::default()
+            .with_address(SlaveAddress::new().with_a0(true))
+            .build_owned_separate(i2c.clone())
+        else {
+            panic!("build should fail");
+        };
+        i2c.done();
+    }
+}
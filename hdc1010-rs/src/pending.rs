@@ -0,0 +1,98 @@
+use core::{marker::PhantomData, time::Duration};
+
+use embedded_hal::i2c::{I2c, SevenBitAddress};
+
+use crate::{
+    Clock, Error, Hdc1010, Humidity, MeasurementWindow, Separate, Temperature,
+    register::{Hdc1010Register, Trigger},
+};
+
+/// Result of reading a [`PendingMeasurement`].
+///
+/// On success, this holds the measured value and the sensor handle. On error, this holds the
+/// pending measurement, so that the read can be retried, and the error.
+pub type ReadResult<K, E> = Result<(K, Hdc1010<Separate>), (PendingMeasurement<K>, Error<E>)>;
+
+/// A measurement that has been triggered on a HDC1010 sensor in [`Separate`] acquisition mode.
+///
+/// The sensor handle is held by the pending measurement until the value is read back with
+/// `read`, so that a temperature can not be read after a humidity measurement has been
+/// triggered, and vice versa.
+pub struct PendingMeasurement<K> {
+    dev: Hdc1010<Separate>,
+    window: MeasurementWindow,
+    _kind: PhantomData<K>,
+}
+
+impl<K> PendingMeasurement<K> {
+    pub(crate) fn new(dev: Hdc1010<Separate>, delay: Duration) -> Self {
+        Self {
+            dev,
+            window: MeasurementWindow::new(delay),
+            _kind: PhantomData,
+        }
+    }
+
+    /// The duration to wait for the measurement to complete.
+    pub fn delay(&self) -> Duration {
+        self.window.delay()
+    }
+
+    /// Stamp the measurement with the current time of `clock`, see [`MeasurementWindow::timed`].
+    pub fn timed(mut self, clock: &impl Clock) -> Self {
+        self.window = self.window.timed(clock);
+        self
+    }
+
+    /// The time the measurement takes to complete.
+    pub fn window(&self) -> MeasurementWindow {
+        self.window
+    }
+
+    /// Get the address of the device.
+    pub fn get_address(&self) -> u8 {
+        self.dev.address
+    }
+
+    /// Abandon the measurement and return the sensor handle.
+    pub fn cancel(self) -> Hdc1010<Separate> {
+        self.dev
+    }
+}
+
+impl PendingMeasurement<Temperature> {
+    /// The kind of measurement that was triggered.
+    pub const KIND: Trigger = Trigger::Temperature;
+
+    /// Read the measured temperature value.
+    pub fn read<T: I2c<SevenBitAddress>>(
+        mut self,
+        i2c: &mut T,
+    ) -> ReadResult<Temperature, T::Error> {
+        let mut v = Temperature::default();
+        if let Err(e) = v.read(&mut self.dev, i2c) {
+            return Err((self, e));
+        }
+        if let Err(e) = self.dev.refresh_power_status(i2c) {
+            return Err((self, e));
+        }
+        Ok((v, self.dev))
+    }
+}
+
+impl PendingMeasurement<Humidity> {
+    /// The kind of measurement that was triggered.
+    pub const KIND: Trigger = Trigger::Humidity;
+
+    /// Read the measured humidity value.
+    pub fn read<T: I2c<SevenBitAddress>>(mut self, i2c: &mut T) -> ReadResult<Humidity, T::Error> {
+        let mut v = Humidity::default();
+        if let Err(e) = v.read(&mut self.dev, i2c) {
+            return Err((self, e));
+        }
+        if let Err(e) = self.dev.refresh_power_status(i2c) {
+            return Err((self, e));
+        }
+        Ok((v, self.dev))
+    }
+}
@@ -1,20 +1,33 @@
 use bitfield_struct::bitfield;
 use embedded_hal::i2c::{I2c, SevenBitAddress};
 
-use crate::{Error, core::Hdc1010};
+use crate::{Error, RelativeHumidity, Temperature, core::Hdc1010};
 
 pub(crate) const HDC1010_MANUFACTURER_ID: u16 = 0x5449; // Texas Instruments
 pub(crate) const HDC1010_DEVICE_ID: u16 = 0x1000; // HDC1010 Device ID
 
-pub(crate) trait Hdc1010Register: Default {
+/// A register of the HDC1010, read and written with [`Hdc1010::read_register`] and
+/// [`Hdc1010::write_register`].
+///
+/// The driver implements the registers it uses, and the trait is the extension point to access the
+/// other registers, e.g. registers that are not documented, through the same plumbing. The 16-bit
+/// big-endian registers are implemented most easily through [`Hdc1010Register16`], which implements
+/// this trait.
+pub trait Hdc1010Register: Default {
+    /// Address of the register, written to the pointer register of the device.
     const ADDRESS: u8;
+    /// Length of the register, in bytes.
     const REGISTER_LEN: usize;
 
+    /// Reads the register from the device at [`Hdc1010::get_address`] into `self`.
     fn read<T: I2c<SevenBitAddress>, U>(
         &mut self,
         hdc: &mut Hdc1010<U>,
         i2c: &mut T,
     ) -> Result<(), Error<T::Error>>;
+    /// Writes `self` to the register of the device at [`Hdc1010::get_address`].
+    ///
+    /// The default implementation returns [`Error::ReadOnly`].
     fn write<T: I2c<SevenBitAddress>, U>(
         &mut self,
         _hdc: &mut Hdc1010<U>,
@@ -24,7 +37,61 @@ pub(crate) trait Hdc1010Register: Default {
     }
 }
 
+/// A 16-bit big-endian register of the HDC1010, given by its conversions from and to the raw
+/// register value.
+///
+/// Every type implementing this trait implements [`Hdc1010Register`], reading the register with
+/// its address written to the pointer register, and writing it after the address.
+pub trait Hdc1010Register16: Default {
+    /// Address of the register, written to the pointer register of the device.
+    const ADDRESS: u8;
+
+    /// Converts the raw register value read from the device.
+    fn from_raw(raw: u16) -> Self;
+    /// The raw register value written to the device, or `None` if the register is read-only.
+    ///
+    /// The default implementation returns `None`.
+    fn to_raw(&self) -> Option<u16> {
+        None
+    }
+}
+
+impl<R: Hdc1010Register16> Hdc1010Register for R {
+    const ADDRESS: u8 = <R as Hdc1010Register16>::ADDRESS;
+    const REGISTER_LEN: usize = 2;
+
+    fn read<T: I2c<SevenBitAddress>, U>(
+        &mut self,
+        hdc: &mut Hdc1010<U>,
+        i2c: &mut T,
+    ) -> Result<(), Error<T::Error>> {
+        let mut buffer = [0u8; 2];
+        i2c.write_read(
+            hdc.address,
+            &[<Self as Hdc1010Register>::ADDRESS],
+            &mut buffer,
+        )?;
+        *self = R::from_raw(u16::from_be_bytes(buffer));
+        Ok(())
+    }
+
+    fn write<T: I2c<SevenBitAddress>, U>(
+        &mut self,
+        hdc: &mut Hdc1010<U>,
+        i2c: &mut T,
+    ) -> Result<(), Error<T::Error>> {
+        let [high, low] = self.to_raw().ok_or(Error::ReadOnly)?.to_be_bytes();
+        i2c.write(
+            hdc.address,
+            &[<Self as Hdc1010Register>::ADDRESS, high, low],
+        )?;
+        Ok(())
+    }
+}
+
 #[derive(Debug, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 /// Trigger a measurement for either temperature or humidity.
 pub enum Trigger {
     /// Trigger a temperature measurement.
@@ -33,18 +100,10 @@ pub enum Trigger {
     Humidity,
 }
 
-#[derive(Debug, Default)]
-/// Represents a temperature measurement from the HDC1010 sensor.
-pub struct Temperature {
-    pub(crate) value: u16,
-}
-
-impl Temperature {
-    /// Converts the raw temperature value to Celsius.
-    pub fn celsius(&self) -> core::primitive::f32 {
-        // Convert the raw value to Celsius
-        (self.value as f32 * 165.0 / 65536.0) - 40.0
-    }
+/// Converts a raw temperature register value to a [`Temperature`].
+pub(crate) fn temperature_from_raw(value: u16) -> Temperature {
+    // T = raw * 165 / 2^16 - 40, expressed directly in 16.16 fixed-point units
+    Temperature::from_bits(value as i32 * 165 - (40 << 16))
 }
 
 impl Hdc1010Register for Temperature {
@@ -59,7 +118,7 @@ impl Hdc1010Register for Temperature {
     ) -> Result<(), Error<T::Error>> {
         let mut buffer = [0u8; Self::REGISTER_LEN];
         i2c.read(hdc.address, &mut buffer)?;
-        self.value = u16::from_be_bytes(buffer);
+        *self = temperature_from_raw(u16::from_be_bytes(buffer));
         Ok(())
     }
 
@@ -74,16 +133,29 @@ impl Hdc1010Register for Temperature {
 }
 
 #[derive(Debug, Default)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 /// Represents a humidity measurement from the HDC1010 sensor.
 pub struct Humidity {
     pub(crate) value: u16,
 }
 
 impl Humidity {
+    /// Returns the raw humidity value as read from the sensor.
+    pub const fn raw(&self) -> u16 {
+        self.value
+    }
+
     /// Converts the raw humidity value to percentage (0-100).
     pub fn percentage(&self) -> core::primitive::f32 {
         self.value as f32 * 100.0 / 65536.0
     }
+
+    /// Returns the relative humidity as a fixed-point value.
+    pub fn relative_humidity(&self) -> RelativeHumidity {
+        // RH = raw * 100 / 2^16, expressed directly in 16.16 fixed-point units
+        RelativeHumidity::from_bits(self.value as i32 * 100)
+    }
 }
 
 impl Hdc1010Register for Humidity {
@@ -134,6 +206,8 @@ pub struct Configuration {
 
 #[repr(u8)]
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 /// Acquisition mode for the HDC1010 sensor.
 pub enum AcquisitionModeEnum {
     #[default]
@@ -188,6 +262,8 @@ impl Hdc1010Register for Configuration {
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[repr(u8)]
 /// Humidity measurement resolution for the HDC1010 sensor.
 pub enum HumidityResolution {
@@ -229,6 +305,8 @@ impl HumidityResolution {
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[repr(u8)]
 /// Temperature measurement resolution for the HDC1010 sensor.
 pub enum TemperatureResolution {
@@ -264,6 +342,37 @@ impl TemperatureResolution {
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+/// Supply voltage status of the HDC1010 sensor, as reported by the battery status bit.
+///
+/// The battery status bit is only updated by the sensor after a measurement has completed.
+pub enum PowerStatus {
+    #[default]
+    /// The supply voltage is above 2.8 V.
+    Ok,
+    /// The supply voltage is below 2.8 V.
+    Low,
+}
+
+impl PowerStatus {
+    /// Returns `true` if the supply voltage is below 2.8 V.
+    pub fn is_low(&self) -> bool {
+        *self == PowerStatus::Low
+    }
+}
+
+impl From<bool> for PowerStatus {
+    fn from(low: bool) -> Self {
+        if low {
+            PowerStatus::Low
+        } else {
+            PowerStatus::Ok
+        }
+    }
+}
+
 #[derive(Debug, Default)]
 pub struct SerialId(u64);
 
@@ -297,6 +406,12 @@ impl Hdc1010Register for SerialId {
 #[derive(Debug, Default)]
 pub struct ManufacturerId(u16);
 
+impl ManufacturerId {
+    pub fn value(&self) -> u16 {
+        self.0
+    }
+}
+
 impl Hdc1010Register for ManufacturerId {
     const ADDRESS: u8 = 0xFE;
     const REGISTER_LEN: usize = 2;
@@ -319,6 +434,12 @@ impl Hdc1010Register for ManufacturerId {
 #[derive(Debug, Default)]
 pub struct DeviceId(u16);
 
+impl DeviceId {
+    pub fn value(&self) -> u16 {
+        self.0
+    }
+}
+
 impl Hdc1010Register for DeviceId {
     const ADDRESS: u8 = 0xFF;
     const REGISTER_LEN: usize = 2;
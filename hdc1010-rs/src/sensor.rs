@@ -0,0 +1,31 @@
+use core::time::Duration;
+
+use embedded_hal::i2c::{I2c, SevenBitAddress};
+use piccthermo_core::{HumiditySensor, RelativeHumidity, SensorDriver};
+
+use crate::{Both, Error, Hdc1010, Temperature};
+
+impl<T: I2c<SevenBitAddress>> SensorDriver<T> for Hdc1010<Both> {
+    type Error = Error<T::Error>;
+
+    fn trigger(&mut self, bus: &mut T) -> Result<(), Self::Error> {
+        Hdc1010::trigger(self, bus).map(|_| ())
+    }
+
+    fn ready_after(&self) -> Duration {
+        Duration::from_micros((self.hres.delay_time() + self.tres.delay_time()) as _)
+    }
+}
+
+impl<T: I2c<SevenBitAddress>> HumiditySensor<T> for Hdc1010<Both> {
+    /// Reads the temperature and humidity, identified by the I2C address of the sensor.
+    fn read(
+        &mut self,
+        bus: &mut T,
+        sink: &mut dyn FnMut(u64, Temperature, RelativeHumidity),
+    ) -> Result<(), Self::Error> {
+        let (temp, hum) = self.read_temperature_humidity(bus)?;
+        sink(self.address as _, temp, hum.relative_humidity());
+        Ok(())
+    }
+}
@@ -0,0 +1,119 @@
+use core::time::Duration;
+
+/// A monotonic clock, used to time stamp triggered measurements.
+///
+/// The clock reports the time elapsed since an arbitrary origin, e.g. the start of the program.
+/// It is implemented for closures, so that on a host `move || start.elapsed()` can be used.
+pub trait Clock {
+    /// The time elapsed since the origin of the clock.
+    fn now(&self) -> Duration;
+}
+
+impl<F: Fn() -> Duration> Clock for F {
+    fn now(&self) -> Duration {
+        self()
+    }
+}
+
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, Copy)]
+/// A [`Clock`] counting from the time it was created, using [`std::time::Instant`].
+pub struct StdClock(std::time::Instant);
+
+#[cfg(feature = "std")]
+impl StdClock {
+    /// Create a clock with the current time as origin.
+    pub fn new() -> Self {
+        Self(std::time::Instant::now())
+    }
+}
+
+#[cfg(feature = "std")]
+impl Default for StdClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "std")]
+impl Clock for StdClock {
+    fn now(&self) -> Duration {
+        self.0.elapsed()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// The time a triggered measurement takes to complete.
+///
+/// The window is returned by `trigger`, and can be stamped with the time of the trigger with
+/// [`timed`](Self::timed), so that the time left until the measurement can be read is known
+/// without keeping track of the trigger instant.
+pub struct MeasurementWindow {
+    delay: Duration,
+    triggered: Option<Duration>,
+}
+
+impl MeasurementWindow {
+    pub(crate) const fn new(delay: Duration) -> Self {
+        Self {
+            delay,
+            triggered: None,
+        }
+    }
+
+    /// Stamp the window with the current time of `clock`. Call this right after the trigger.
+    pub fn timed(mut self, clock: &impl Clock) -> Self {
+        self.triggered = Some(clock.now());
+        self
+    }
+
+    /// The duration to wait for the measurement to complete.
+    pub fn delay(&self) -> Duration {
+        self.delay
+    }
+
+    /// The time the measurement was triggered at, if the window was [`timed`](Self::timed).
+    pub fn triggered_at(&self) -> Option<Duration> {
+        self.triggered
+    }
+
+    /// The time the measurement can be read at, if the window was [`timed`](Self::timed).
+    pub fn ready_at(&self) -> Option<Duration> {
+        self.triggered.map(|triggered| triggered + self.delay)
+    }
+
+    /// The time left until the measurement can be read, zero once it can be read.
+    ///
+    /// If the window was not [`timed`](Self::timed), this is the full [`delay`](Self::delay).
+    pub fn remaining(&self, clock: &impl Clock) -> Duration {
+        self.ready_at()
+            .map_or(self.delay, |ready| ready.saturating_sub(clock.now()))
+    }
+
+    /// Returns `true` if the measurement can be read.
+    pub fn is_ready(&self, clock: &impl Clock) -> bool {
+        self.remaining(clock).is_zero()
+    }
+}
+
+mod test {
+    #[test]
+    fn test_window() {
+        use super::MeasurementWindow;
+        use core::{cell::Cell, time::Duration};
+        let now = Cell::new(Duration::from_millis(100));
+        let clock = || now.get();
+        let window = MeasurementWindow::new(Duration::from_millis(6));
+        assert_eq!(window.ready_at(), None);
+        assert_eq!(window.remaining(&clock), Duration::from_millis(6));
+        let window = window.timed(&clock);
+        assert_eq!(window.triggered_at(), Some(Duration::from_millis(100)));
+        assert_eq!(window.ready_at(), Some(Duration::from_millis(106)));
+        now.set(Duration::from_millis(102));
+        assert_eq!(window.remaining(&clock), Duration::from_millis(4));
+        assert!(!window.is_ready(&clock));
+        now.set(Duration::from_millis(110));
+        assert_eq!(window.remaining(&clock), Duration::ZERO);
+        assert!(window.is_ready(&clock));
+    }
+}
@@ -1,6 +1,7 @@
 use bitfield_struct::bitfield;
 
-#[bitfield(u8)]
+#[bitfield(u8, defmt = cfg(feature = "defmt"))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 /// Represents the slave address for the HDC3022 sensor.
 /// The address is 7 bits long, with the least significant bit (LSB) used for read/write operations.
 /// The default address is 0x44, which is the standard I2C address for the HDC3022.
@@ -10,6 +11,6 @@ pub struct SlaveAddress {
     pub a0: bool,
     #[bits(1, default = false)]
     pub a1: bool,
-    #[bits(6, default = 0x44)]
+    #[bits(6, default = 0x44 >> 2)]
     reserved: u8,
 }
@@ -0,0 +1,136 @@
+use embedded_hal::{
+    digital::{Error as _, InputPin},
+    i2c::{I2c, SevenBitAddress},
+};
+
+use crate::{
+    Error, Hdc3022, Humidity, RelativeHumidity, Temperature,
+    command::{self, read_words, write_command, write_command_data},
+    register::{humidity_to_raw, temperature_from_raw, temperature_to_raw},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+/// One of the four alert thresholds of the HDC3022 sensor.
+///
+/// An alert is raised when a measurement crosses the set threshold, and is cleared once a
+/// measurement crosses back past the clear threshold, which provides hysteresis.
+pub enum AlertLimit {
+    /// Threshold below which the low alert is raised.
+    SetLow,
+    /// Threshold above which the high alert is raised.
+    SetHigh,
+    /// Threshold above which the low alert is cleared.
+    ClearLow,
+    /// Threshold below which the high alert is cleared.
+    ClearHigh,
+}
+
+impl AlertLimit {
+    const fn write_command(self) -> u16 {
+        match self {
+            AlertLimit::SetLow => 0x6100,
+            AlertLimit::SetHigh => 0x611D,
+            AlertLimit::ClearLow => 0x610B,
+            AlertLimit::ClearHigh => 0x6116,
+        }
+    }
+
+    const fn read_command(self) -> u16 {
+        match self {
+            AlertLimit::SetLow => 0xE102,
+            AlertLimit::SetHigh => 0xE11F,
+            AlertLimit::ClearLow => 0xE109,
+            AlertLimit::ClearHigh => 0xE114,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+/// A temperature and humidity alert threshold.
+///
+/// The sensor stores the 9 most significant bits of the temperature (about 0.34 °C steps) and
+/// the 7 most significant bits of the humidity (about 0.78 % steps), so thresholds read back
+/// from the sensor are quantized.
+pub struct AlertThreshold {
+    /// The temperature threshold.
+    pub temperature: Temperature,
+    /// The relative humidity threshold.
+    pub humidity: RelativeHumidity,
+}
+
+impl AlertThreshold {
+    pub(crate) fn into_bits(self) -> u16 {
+        (humidity_to_raw(self.humidity) & 0xFE00) | (temperature_to_raw(self.temperature) >> 7)
+    }
+
+    pub(crate) fn from_bits(bits: u16) -> Self {
+        Self {
+            temperature: temperature_from_raw((bits & 0x01FF) << 7),
+            humidity: Humidity {
+                value: bits & 0xFE00,
+            }
+            .relative_humidity(),
+        }
+    }
+}
+
+impl Hdc3022 {
+    /// Set one of the alert thresholds of the HDC3022 sensor.
+    ///
+    /// The threshold is lost on power cycle unless [`Hdc3022::store_alert_thresholds`] is called.
+    pub fn set_alert_threshold<T: I2c<SevenBitAddress>>(
+        &mut self,
+        i2c: &mut T,
+        limit: AlertLimit,
+        threshold: AlertThreshold,
+    ) -> Result<(), Error<T::Error>> {
+        write_command_data(
+            i2c,
+            self.address,
+            limit.write_command(),
+            threshold.into_bits(),
+        )
+    }
+
+    /// Read back one of the alert thresholds of the HDC3022 sensor.
+    pub fn get_alert_threshold<T: I2c<SevenBitAddress>>(
+        &mut self,
+        i2c: &mut T,
+        limit: AlertLimit,
+    ) -> Result<AlertThreshold, Error<T::Error>> {
+        let [bits] = read_words(i2c, self.address, Some(limit.read_command()))?;
+        Ok(AlertThreshold::from_bits(bits))
+    }
+
+    /// Transfer the alert thresholds to the non-volatile memory of the HDC3022 sensor,
+    /// so that they are restored on power up.
+    pub fn store_alert_thresholds<T: I2c<SevenBitAddress>>(
+        &mut self,
+        i2c: &mut T,
+    ) -> Result<(), Error<T::Error>> {
+        write_command(i2c, self.address, command::STORE_ALERT_THRESHOLDS)
+    }
+
+    /// Check whether the ALERT pin of the HDC3022 sensor is asserted.
+    ///
+    /// The ALERT output is driven high while any alert is active. Use [`Hdc3022::get_status`]
+    /// to find out which alert is active.
+    pub fn is_alert_asserted<P: InputPin, E>(&self, pin: &mut P) -> Result<bool, Error<E>> {
+        pin.is_high().map_err(|e| Error::Pin(e.kind()))
+    }
+
+    #[cfg(feature = "async")]
+    /// Asynchronously wait for the ALERT pin of the HDC3022 sensor to be asserted.
+    ///
+    /// Returns immediately if the pin is already high.
+    pub async fn wait_for_alert<P, E>(&self, pin: &mut P) -> Result<(), Error<E>>
+    where
+        P: embedded_hal_async::digital::Wait,
+    {
+        pin.wait_for_high().await.map_err(|e| Error::Pin(e.kind()))
+    }
+}
@@ -0,0 +1,106 @@
+use embedded_hal::i2c::{I2c, SevenBitAddress};
+
+use crate::Error;
+
+/// Exit the auto measurement mode and return to the sleep state.
+pub(crate) const EXIT_AUTO_MODE: u16 = 0x3093;
+/// Read out the last temperature and humidity measurement in auto measurement mode.
+pub(crate) const READ_AUTO_MEASUREMENT: u16 = 0xE000;
+/// Read out the minimum temperature measured in auto measurement mode.
+pub(crate) const READ_AUTO_MIN_TEMPERATURE: u16 = 0xE002;
+/// Read out the maximum temperature measured in auto measurement mode.
+pub(crate) const READ_AUTO_MAX_TEMPERATURE: u16 = 0xE003;
+/// Read out the minimum humidity measured in auto measurement mode.
+pub(crate) const READ_AUTO_MIN_HUMIDITY: u16 = 0xE004;
+/// Read out the maximum humidity measured in auto measurement mode.
+pub(crate) const READ_AUTO_MAX_HUMIDITY: u16 = 0xE005;
+/// Enable the integrated heater.
+pub(crate) const HEATER_ENABLE: u16 = 0x306D;
+/// Disable the integrated heater.
+pub(crate) const HEATER_DISABLE: u16 = 0x3066;
+/// Read the status register.
+pub(crate) const READ_STATUS: u16 = 0xF32D;
+/// Clear the status register.
+pub(crate) const CLEAR_STATUS: u16 = 0x3041;
+/// Perform a soft reset.
+pub(crate) const SOFT_RESET: u16 = 0x30A2;
+/// Read the NIST traceable serial number, bytes 5 and 4.
+pub(crate) const READ_NIST_ID_HIGH: u16 = 0x3683;
+/// Read the NIST traceable serial number, bytes 3 and 2.
+pub(crate) const READ_NIST_ID_MID: u16 = 0x3684;
+/// Read the NIST traceable serial number, bytes 1 and 0.
+pub(crate) const READ_NIST_ID_LOW: u16 = 0x3685;
+/// Read the manufacturer ID.
+pub(crate) const READ_MANUFACTURER_ID: u16 = 0x3781;
+/// Read or program the temperature and humidity offsets.
+pub(crate) const OFFSETS: u16 = 0xA004;
+/// Transfer the alert thresholds to the non-volatile memory.
+pub(crate) const STORE_ALERT_THRESHOLDS: u16 = 0x6155;
+
+/// Compute the CRC-8 checksum of a data word, as used by the HDC3022 (polynomial 0x31, initial value 0xFF).
+pub(crate) const fn crc8(data: &[u8]) -> u8 {
+    let mut crc = 0xFFu8;
+    let mut i = 0;
+    while i < data.len() {
+        crc ^= data[i];
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 0x80 != 0 {
+                (crc << 1) ^ 0x31
+            } else {
+                crc << 1
+            };
+            bit += 1;
+        }
+        i += 1;
+    }
+    crc
+}
+
+/// Send a command to the sensor.
+pub(crate) fn write_command<T: I2c<SevenBitAddress>>(
+    i2c: &mut T,
+    address: u8,
+    command: u16,
+) -> Result<(), Error<T::Error>> {
+    i2c.write(address, &command.to_be_bytes())?;
+    Ok(())
+}
+
+/// Send a command followed by a data word and its checksum to the sensor.
+pub(crate) fn write_command_data<T: I2c<SevenBitAddress>>(
+    i2c: &mut T,
+    address: u8,
+    command: u16,
+    data: u16,
+) -> Result<(), Error<T::Error>> {
+    let [c0, c1] = command.to_be_bytes();
+    let [d0, d1] = data.to_be_bytes();
+    i2c.write(address, &[c0, c1, d0, d1, crc8(&[d0, d1])])?;
+    Ok(())
+}
+
+/// Read `N` (at most 6) data words from the sensor, validating the checksum of each word.
+///
+/// If `command` is `None`, the words are read without sending a command first,
+/// e.g. to read out the result of a measurement triggered on demand.
+pub(crate) fn read_words<T: I2c<SevenBitAddress>, const N: usize>(
+    i2c: &mut T,
+    address: u8,
+    command: Option<u16>,
+) -> Result<[u16; N], Error<T::Error>> {
+    let mut buf = [0u8; 18];
+    let buf = &mut buf[..3 * N];
+    match command {
+        Some(command) => i2c.write_read(address, &command.to_be_bytes(), buf)?,
+        None => i2c.read(address, buf)?,
+    }
+    let mut words = [0u16; N];
+    for (word, chunk) in words.iter_mut().zip(buf.chunks_exact(3)) {
+        if crc8(&chunk[..2]) != chunk[2] {
+            return Err(Error::Crc);
+        }
+        *word = u16::from_be_bytes([chunk[0], chunk[1]]);
+    }
+    Ok(words)
+}
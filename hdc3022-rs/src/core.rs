@@ -6,224 +6,565 @@ use embedded_hal::{
 };
 
 use crate::{
-    AcquisitionMode, Error, Humidity, Temperature,
+    AcquisitionMode, AutoReading, AutoReadout, AutoSummary, Error, Humidity, MeasurementRate,
+    PowerMode, Status, Temperature,
     address::SlaveAddress,
-    register::{
-        self, Configuration, DeviceId, Hdc3022Register, HumidityResolution, ManufacturerId,
-        TemperatureResolution, Trigger,
-    },
+    command::{self, read_words, write_command, write_command_data},
+    register::{HDC3022_MANUFACTURER_ID, Hdc3022Register, temperature_from_raw},
 };
 
-/// Represents the HDC1010 sensor.
-pub struct Hdc3022<'a, T> {
-    pub(crate) i2c: &'a mut T,
+/// Time for the sensor to come out of a soft reset, in microseconds.
+const RESET_TIME_US: u32 = 3000;
+
+/// Represents the HDC3022 sensor.
+pub struct Hdc3022 {
     pub(crate) address: u8,
     pub(crate) mode: AcquisitionMode,
-    pub(crate) hres: HumidityResolution,
-    pub(crate) tres: TemperatureResolution,
+    pub(crate) power: PowerMode,
 }
 
 #[derive(Debug, Default)]
-/// Builder for a HDC1010 sensor.
+/// Builder for a HDC3022 sensor.
 pub struct Hdc3022Builder {
     pub(crate) address: SlaveAddress,
-    pub(crate) mode: AcquisitionMode,
-    pub(crate) hres: HumidityResolution,
-    pub(crate) tres: TemperatureResolution,
+    pub(crate) power: PowerMode,
 }
 
 impl Hdc3022Builder {
-    /// Set the address of the HDC1010 sensor.
+    /// Set the address of the HDC3022 sensor.
     pub fn with_address(mut self, address: SlaveAddress) -> Self {
         self.address = address;
         self
     }
 
-    /// Set the acquisition mode for the HDC1010 sensor.
-    pub fn with_mode(mut self, mode: AcquisitionMode) -> Self {
-        self.mode = mode;
-        self
-    }
-
-    /// Set the humidity resolution for the HDC1010 sensor.
-    pub fn with_humidity_resolution(mut self, resolution: HumidityResolution) -> Self {
-        self.hres = resolution;
-        self
-    }
-
-    /// Set the temperature resolution for the HDC1010 sensor.
-    pub fn with_temperature_resolution(mut self, resolution: TemperatureResolution) -> Self {
-        self.tres = resolution;
+    /// Set the default power mode of the HDC3022 sensor.
+    ///
+    /// This power mode is used for measurements triggered through the
+    /// [`SensorDriver`](piccthermo_core::SensorDriver) trait.
+    pub fn with_power_mode(mut self, power: PowerMode) -> Self {
+        self.power = power;
         self
     }
 
-    /// Build the HDC1010 sensor with the specified configuration.
-    pub fn build<T: I2c<SevenBitAddress>>(
-        self,
-        i2c: &mut T,
-    ) -> Result<Hdc3022<'_, T>, Error<T::Error>> {
+    /// Build the HDC3022 sensor.
+    ///
+    /// The sensor is taken out of auto measurement mode if it was left running, so that
+    /// measurements are triggered on demand.
+    pub fn build<T: I2c<SevenBitAddress>>(self, i2c: &mut T) -> Result<Hdc3022, Error<T::Error>> {
         let mut dev = Hdc3022 {
-            i2c,
             address: self.address.into_bits(),
-            mode: self.mode,
-            hres: self.hres,
-            tres: self.tres,
+            mode: AcquisitionMode::OnDemand,
+            power: self.power,
         };
-        // Check if the device is present by reading its ID register
-        let mut mfg = ManufacturerId::default();
-        mfg.read(&mut dev)?;
-        let mut dev_id = DeviceId::default();
-        dev_id.read(&mut dev)?;
-        let mut cfg = Configuration::default();
-        cfg.read(&mut dev)?;
-        cfg.set_mode(self.mode);
-        cfg.set_humidity_resolution(self.hres);
-        cfg.set_temperature_resolution(self.tres);
-        cfg.write(&mut dev)?;
+        dev.exit_auto_mode(i2c)?;
+        // Check if the device is present by reading its ID
+        if dev.get_manufacturer_id(i2c)? != HDC3022_MANUFACTURER_ID {
+            return Err(Error::InvalidId);
+        }
         Ok(dev)
     }
 }
 
-impl<T: I2c<SevenBitAddress>> Hdc3022<'_, T> {
-    /// Get the current temperature and humidity resolutions.
-    pub fn get_resolution(&mut self) -> (HumidityResolution, TemperatureResolution) {
-        (self.hres, self.tres)
+impl Hdc3022 {
+    /// Get the address of the device.
+    pub fn get_address(&self) -> u8 {
+        self.address
     }
 
-    /// Set the humidity and temperature resolutions.
-    pub fn set_resolution(
-        &mut self,
-        humidity_resolution: HumidityResolution,
-        temperature_resolution: TemperatureResolution,
-    ) -> Result<(), Error<T::Error>> {
-        let mut conf = Configuration::default();
-        conf.set_humidity_resolution(humidity_resolution);
-        conf.set_temperature_resolution(temperature_resolution);
-        conf.write(self)?;
-        conf.read(self)?;
-        self.hres = conf.humidity_resolution();
-        self.tres = conf.temperature_resolution();
-        Ok(())
+    /// Get the current acquisition mode of the HDC3022 sensor.
+    pub fn get_mode(&self) -> AcquisitionMode {
+        self.mode
     }
 
-    /// Get the current acquisition mode of the HDC1010 sensor.
-    pub fn get_mode(&mut self) -> AcquisitionMode {
-        self.mode
+    /// Get the default power mode of the HDC3022 sensor.
+    pub fn get_power_mode(&self) -> PowerMode {
+        self.power
     }
 
-    /// Set the acquisition mode of the HDC1010 sensor.
-    pub fn set_mode(&mut self, mode: AcquisitionMode) -> Result<(), Error<T::Error>> {
-        let mut conf = Configuration::default();
-        conf.read(self)?;
-        conf.set_mode(mode);
-        conf.write(self)?;
-        conf.read(self)?;
-        self.mode = mode;
-        Ok(())
+    /// Set the default power mode of the HDC3022 sensor, see [`Hdc3022Builder::with_power_mode`].
+    ///
+    /// The auto measurement mode keeps the power mode it was started with until it is started again
+    /// with [`Hdc3022::start_auto_mode`].
+    pub fn set_power_mode(&mut self, power: PowerMode) {
+        self.power = power;
     }
 
-    /// Set the heater state of the HDC1010 sensor.
-    pub fn set_heater(&mut self, enable: bool) -> Result<(), Error<T::Error>> {
-        let mut conf = Configuration::default();
-        conf.read(self)?;
-        conf.set_heater_enable(enable);
-        conf.write(self)?;
-        Ok(())
+    /// Get the manufacturer ID of the HDC3022 sensor.
+    pub fn get_manufacturer_id<T: I2c<SevenBitAddress>>(
+        &mut self,
+        i2c: &mut T,
+    ) -> Result<u16, Error<T::Error>> {
+        let [id] = read_words(i2c, self.address, Some(command::READ_MANUFACTURER_ID))?;
+        Ok(id)
     }
 
-    /// Get the power status of the HDC1010 sensor.
-    pub fn get_power_status(&mut self) -> Result<bool, Error<T::Error>> {
-        let mut conf = Configuration::default();
-        conf.read(self)?;
-        Ok(conf.power_ok())
+    /// Get the NIST traceable serial number of the HDC3022 sensor.
+    ///
+    /// The 6-byte ID is unique to each sensor, and is returned most significant byte first.
+    pub fn get_nist_id<T: I2c<SevenBitAddress>>(
+        &mut self,
+        i2c: &mut T,
+    ) -> Result<[u8; 6], Error<T::Error>> {
+        let mut id = [0u8; 6];
+        for (bytes, cmd) in id.chunks_exact_mut(2).zip([
+            command::READ_NIST_ID_HIGH,
+            command::READ_NIST_ID_MID,
+            command::READ_NIST_ID_LOW,
+        ]) {
+            let [word] = read_words(i2c, self.address, Some(cmd))?;
+            bytes.copy_from_slice(&word.to_be_bytes());
+        }
+        Ok(id)
     }
 
-    /// Get the serial number of the HDC1010 sensor.
-    pub fn get_serial(&mut self) -> Result<u64, Error<T::Error>> {
-        let mut serial = register::SerialId::default();
-        serial.read(self)?;
-        Ok(serial.value())
+    /// Get the NIST traceable serial number of the HDC3022 sensor as an integer.
+    pub fn get_serial<T: I2c<SevenBitAddress>>(
+        &mut self,
+        i2c: &mut T,
+    ) -> Result<u64, Error<T::Error>> {
+        let id = self.get_nist_id(i2c)?;
+        let mut bytes = [0u8; 8];
+        bytes[2..].copy_from_slice(&id);
+        Ok(u64::from_be_bytes(bytes))
     }
 
-    /// Perform a soft reset of the HDC1010 sensor.
-    pub fn reset<D: DelayNs>(&mut self, delay: &mut D) -> Result<(), Error<T::Error>> {
-        let mut conf = Configuration::default();
-        conf.set_reset(true);
-        conf.write(self)?;
-        for _ in 0..10 {
-            delay.delay_ms(500);
-            conf.read(self)?;
-            if !conf.reset() {
-                break;
-            }
+    /// Trigger a temperature and humidity measurement.
+    ///
+    /// # Parameters:
+    /// - `power`: The [`PowerMode`] used for the measurement.
+    ///
+    /// # Returns:
+    /// - [`Duration`]: The duration to wait for the measurement to complete.
+    /// - [`Error::InvalidOperation`] if the sensor is in auto measurement mode.
+    pub fn trigger_on_demand<T: I2c<SevenBitAddress>>(
+        &mut self,
+        i2c: &mut T,
+        power: PowerMode,
+    ) -> Result<Duration, Error<T::Error>> {
+        if self.mode != AcquisitionMode::OnDemand {
+            return Err(Error::InvalidOperation);
         }
-        if conf.reset() {
-            return Err(Error::Timeout);
+        write_command(i2c, self.address, power.trigger_command())?;
+        Ok(power.conversion_time())
+    }
+
+    /// Read the temperature and humidity measured after [`Hdc3022::trigger_on_demand`].
+    ///
+    /// The sensor does not acknowledge the read until the measurement is complete.
+    pub fn read_temperature_humidity<T: I2c<SevenBitAddress>>(
+        &mut self,
+        i2c: &mut T,
+    ) -> Result<(Temperature, Humidity), Error<T::Error>> {
+        if self.mode != AcquisitionMode::OnDemand {
+            return Err(Error::InvalidOperation);
+        }
+        let [temp, hum] = read_words(i2c, self.address, None)?;
+        Ok((temperature_from_raw(temp), Humidity { value: hum }))
+    }
+
+    /// Start the auto measurement mode.
+    ///
+    /// The sensor measures temperature and humidity at the given `rate` until
+    /// [`Hdc3022::exit_auto_mode`] is called. The measurements are read out with [`Hdc3022::read_auto`].
+    ///
+    /// # Returns:
+    /// - [`Duration`]: The interval between two measurements.
+    pub fn start_auto_mode<T: I2c<SevenBitAddress>>(
+        &mut self,
+        i2c: &mut T,
+        rate: MeasurementRate,
+        power: PowerMode,
+    ) -> Result<Duration, Error<T::Error>> {
+        if self.mode != AcquisitionMode::OnDemand {
+            self.exit_auto_mode(i2c)?;
         }
-        // Reconfigure the device with the current settings
-        conf.set_reset(false);
-        conf.set_mode(self.mode);
-        conf.set_humidity_resolution(self.hres);
-        conf.set_temperature_resolution(self.tres);
-        conf.write(self)?;
-        conf.read(self)?;
-        self.mode = conf.mode();
-        self.hres = conf.humidity_resolution();
-        self.tres = conf.temperature_resolution();
+        write_command(i2c, self.address, rate.command(power))?;
+        self.mode = AcquisitionMode::Auto { rate, power };
+        Ok(Duration::from_micros(rate.interval() as _))
+    }
+
+    /// Exit the auto measurement mode, and return to on demand measurements.
+    pub fn exit_auto_mode<T: I2c<SevenBitAddress>>(
+        &mut self,
+        i2c: &mut T,
+    ) -> Result<(), Error<T::Error>> {
+        write_command(i2c, self.address, command::EXIT_AUTO_MODE)?;
+        self.mode = AcquisitionMode::OnDemand;
         Ok(())
     }
 
-    /// Trigger a measurement of temperature, humidity, or both.
+    /// Read out a value in auto measurement mode.
     ///
     /// # Parameters:
-    /// - `kind`: An optional [`Trigger`] enum that specifies whether to measure temperature, humidity, or both.
-    ///   Note: If the acquisition mode is not set to [`AcquisitionMode::Both`] while trigger is [`Trigger::Both`], an error is returned.
+    /// - `fifo`: The [`AutoReadout`] to read, either the last measurement or one of the
+    ///   extrema recorded since the auto measurement mode was started.
     ///
     /// # Returns:
-    /// - [`Duration`]: The duration to wait for the measurement to complete.
-    pub fn trigger(&mut self, kind: Trigger) -> Result<Duration, Error<T::Error>> {
-        let mut delay = 0;
-
-        match kind {
-            Trigger::Both => {
-                if self.mode != AcquisitionMode::Both {
-                    return Err(Error::InvalidOperation);
-                }
-                delay += self.hres.delay_time() + self.tres.delay_time();
-                Temperature::default().read(self)?
+    /// - [`AutoReading`]: The value read out, matching the requested `fifo`.
+    /// - [`Error::InvalidOperation`] if the sensor is not in auto measurement mode.
+    pub fn read_auto<T: I2c<SevenBitAddress>>(
+        &mut self,
+        i2c: &mut T,
+        fifo: AutoReadout,
+    ) -> Result<AutoReading, Error<T::Error>> {
+        if self.mode == AcquisitionMode::OnDemand {
+            return Err(Error::InvalidOperation);
+        }
+        let reading = match fifo {
+            AutoReadout::Measurement => {
+                let (temp, hum) = self.read_auto_measurement(i2c)?;
+                AutoReading::Measurement(temp, hum)
             }
-            Trigger::Temperature => {
-                delay += self.tres.delay_time();
-                if self.mode == AcquisitionMode::Both {
-                    delay += self.hres.delay_time();
-                }
-                Temperature::default().read(self)?
+            AutoReadout::MinTemperature | AutoReadout::MaxTemperature => {
+                let cmd = if fifo == AutoReadout::MinTemperature {
+                    command::READ_AUTO_MIN_TEMPERATURE
+                } else {
+                    command::READ_AUTO_MAX_TEMPERATURE
+                };
+                let [temp] = read_words(i2c, self.address, Some(cmd))?;
+                AutoReading::Temperature(temperature_from_raw(temp))
             }
-            Trigger::Humidity => {
-                if self.mode == AcquisitionMode::Both {
-                    delay += self.tres.delay_time();
-                    Temperature::default().read(self)? // Trigger using temperature in case acquisition mode is Both
+            AutoReadout::MinHumidity | AutoReadout::MaxHumidity => {
+                let cmd = if fifo == AutoReadout::MinHumidity {
+                    command::READ_AUTO_MIN_HUMIDITY
                 } else {
-                    Humidity::default().read(self)?; // Trigger using humidity if acquisition mode is Separate
-                }
-                delay += self.hres.delay_time();
+                    command::READ_AUTO_MAX_HUMIDITY
+                };
+                let [hum] = read_words(i2c, self.address, Some(cmd))?;
+                AutoReading::Humidity(Humidity { value: hum })
             }
+        };
+        Ok(reading)
+    }
+
+    /// Read out the last measurement in auto measurement mode.
+    ///
+    /// # Returns:
+    /// - `(Temperature, Humidity)`: The last measurement, as read by [`Hdc3022::read_auto`] with
+    ///   [`AutoReadout::Measurement`].
+    /// - [`Error::InvalidOperation`] if the sensor is not in auto measurement mode.
+    pub fn read_auto_measurement<T: I2c<SevenBitAddress>>(
+        &mut self,
+        i2c: &mut T,
+    ) -> Result<(Temperature, Humidity), Error<T::Error>> {
+        if self.mode == AcquisitionMode::OnDemand {
+            return Err(Error::InvalidOperation);
         }
+        let [temp, hum] = read_words(i2c, self.address, Some(command::READ_AUTO_MEASUREMENT))?;
+        Ok((temperature_from_raw(temp), Humidity { value: hum }))
+    }
 
-        Ok(Duration::from_micros(delay as _))
+    /// Read out the last measurement and the extrema in auto measurement mode, and reset the extrema.
+    ///
+    /// The sensor keeps track of the minimum and maximum temperature and humidity it measured, so that
+    /// the extrema between two slow polls are not lost. The extrema are reset by restarting the auto
+    /// measurement mode with the same rate and power mode, so that every call covers the measurements
+    /// since the previous one.
+    ///
+    /// # Returns:
+    /// - [`AutoSummary`]: The last measurement and the extrema.
+    /// - [`Error::InvalidOperation`] if the sensor is not in auto measurement mode.
+    pub fn drain_fifo<T: I2c<SevenBitAddress>>(
+        &mut self,
+        i2c: &mut T,
+    ) -> Result<AutoSummary, Error<T::Error>> {
+        let AcquisitionMode::Auto { rate, power } = self.mode else {
+            return Err(Error::InvalidOperation);
+        };
+        let (temperature, humidity) = self.read_auto_measurement(i2c)?;
+        let [min_temp] = read_words(i2c, self.address, Some(command::READ_AUTO_MIN_TEMPERATURE))?;
+        let [max_temp] = read_words(i2c, self.address, Some(command::READ_AUTO_MAX_TEMPERATURE))?;
+        let [min_hum] = read_words(i2c, self.address, Some(command::READ_AUTO_MIN_HUMIDITY))?;
+        let [max_hum] = read_words(i2c, self.address, Some(command::READ_AUTO_MAX_HUMIDITY))?;
+        self.start_auto_mode(i2c, rate, power)?;
+        Ok(AutoSummary {
+            temperature,
+            humidity,
+            min_temperature: temperature_from_raw(min_temp),
+            max_temperature: temperature_from_raw(max_temp),
+            min_humidity: Humidity { value: min_hum },
+            max_humidity: Humidity { value: max_hum },
+        })
     }
 
-    /// Read the current humidity value.
-    pub fn read_humidity(&mut self) -> Result<Humidity, Error<T::Error>> {
-        let mut humidity = Humidity::default();
-        humidity.read(self)?;
-        Ok(humidity)
+    /// Set the heater state of the HDC3022 sensor.
+    pub fn set_heater<T: I2c<SevenBitAddress>>(
+        &mut self,
+        i2c: &mut T,
+        enable: bool,
+    ) -> Result<(), Error<T::Error>> {
+        let cmd = if enable {
+            command::HEATER_ENABLE
+        } else {
+            command::HEATER_DISABLE
+        };
+        write_command(i2c, self.address, cmd)
     }
 
-    /// Read the current temperature value.
-    pub fn read_temperature(&mut self) -> Result<Temperature, Error<T::Error>> {
-        let mut temperature = Temperature::default();
-        temperature.read(self)?;
-        Ok(temperature)
+    /// Get the heater state of the HDC3022 sensor.
+    pub fn get_heater<T: I2c<SevenBitAddress>>(
+        &mut self,
+        i2c: &mut T,
+    ) -> Result<bool, Error<T::Error>> {
+        Ok(self.get_status(i2c)?.heater_enabled())
+    }
+
+    /// Read the status register of the HDC3022 sensor.
+    pub fn get_status<T: I2c<SevenBitAddress>>(
+        &mut self,
+        i2c: &mut T,
+    ) -> Result<Status, Error<T::Error>> {
+        self.read_register(i2c)
+    }
+
+    /// Clear the alert and reset flags of the status register.
+    pub fn clear_status<T: I2c<SevenBitAddress>>(
+        &mut self,
+        i2c: &mut T,
+    ) -> Result<(), Error<T::Error>> {
+        write_command(i2c, self.address, command::CLEAR_STATUS)
+    }
+
+    /// Read a data word of the HDC3022 sensor, e.g. one defined outside of the driver.
+    pub fn read_register<R: Hdc3022Register, T: I2c<SevenBitAddress>>(
+        &mut self,
+        i2c: &mut T,
+    ) -> Result<R, Error<T::Error>> {
+        let [raw] = read_words(i2c, self.address, Some(R::COMMAND))?;
+        Ok(R::from_raw(raw))
+    }
+
+    /// Write a data word of the HDC3022 sensor, e.g. one defined outside of the driver.
+    ///
+    /// # Returns
+    /// [`Error::ReadOnly`] if the data word is read-only.
+    pub fn write_register<R: Hdc3022Register, T: I2c<SevenBitAddress>>(
+        &mut self,
+        i2c: &mut T,
+        register: &R,
+    ) -> Result<(), Error<T::Error>> {
+        let raw = register.to_raw().ok_or(Error::ReadOnly)?;
+        write_command_data(i2c, self.address, R::WRITE_COMMAND, raw)
+    }
+
+    /// Perform a soft reset of the HDC3022 sensor.
+    ///
+    /// The sensor returns to on demand measurements after the reset.
+    pub fn reset<T: I2c<SevenBitAddress>, D: DelayNs>(
+        &mut self,
+        i2c: &mut T,
+        delay: &mut D,
+    ) -> Result<(), Error<T::Error>> {
+        write_command(i2c, self.address, command::SOFT_RESET)?;
+        delay.delay_us(RESET_TIME_US);
+        self.mode = AcquisitionMode::OnDemand;
+        Ok(())
+    }
+}
+
+mod test {
+    #[test]
+    fn test_build_and_read() {
+        extern crate std;
+        use super::Hdc3022Builder;
+        use crate::{PowerMode, command::crc8};
+        use embedded_hal_mock::eh1::i2c::{Mock, Transaction};
+        use std::vec;
+        // checksum example of the datasheet
+        assert_eq!(crc8(&[0xbe, 0xef]), 0x92);
+        let mut i2c = Mock::new(&[
+            Transaction::write(0x44, vec![0x30, 0x93]),
+            Transaction::write_read(
+                0x44,
+                vec![0x37, 0x81],
+                vec![0x30, 0x00, crc8(&[0x30, 0x00])],
+            ),
+            Transaction::write(0x44, vec![0x24, 0x16]),
+            Transaction::read(
+                0x44,
+                vec![
+                    0xff,
+                    0xff,
+                    crc8(&[0xff, 0xff]),
+                    0x00,
+                    0x00,
+                    crc8(&[0x00, 0x00]),
+                ],
+            ),
+        ]);
+        let mut hdc = Hdc3022Builder::default().build(&mut i2c).unwrap();
+        let delay = hdc
+            .trigger_on_demand(&mut i2c, PowerMode::LowPower2)
+            .unwrap();
+        assert_eq!(delay.as_micros(), 5000);
+        assert_eq!(delay, PowerMode::LowPower2.conversion_time());
+        let (temp, hum) = hdc.read_temperature_humidity(&mut i2c).unwrap();
+        assert_eq!(temp.millidegrees(), 130_000);
+        assert_eq!(hum.percentage(), 0.0);
+        assert_eq!(temp.centidegrees(), 13_000);
+        assert_eq!(hum.raw(), 0x0000);
+        assert_eq!(hum.relative_humidity().centipercent(), 0);
+        i2c.done();
+    }
+
+    #[test]
+    fn test_register_extension() {
+        extern crate std;
+        use super::Hdc3022;
+        use crate::{AcquisitionMode, Error, Hdc3022Register, PowerMode, command::crc8};
+        use embedded_hal_mock::eh1::i2c::{Mock, Transaction};
+        use std::vec;
+        /// A data word of the sensor, as defined by a user of the driver.
+        #[derive(Debug, PartialEq)]
+        struct Offsets(u16);
+        impl Hdc3022Register for Offsets {
+            const COMMAND: u16 = 0xa004;
+            fn from_raw(raw: u16) -> Self {
+                Self(raw)
+            }
+            fn to_raw(&self) -> Option<u16> {
+                Some(self.0)
+            }
+        }
+        struct Id;
+        impl Hdc3022Register for Id {
+            const COMMAND: u16 = 0x3781;
+            fn from_raw(_raw: u16) -> Self {
+                Self
+            }
+        }
+        let mut i2c = Mock::new(&[
+            Transaction::write(0x44, vec![0xa0, 0x04, 0x81, 0x02, crc8(&[0x81, 0x02])]),
+            Transaction::write_read(
+                0x44,
+                vec![0xa0, 0x04],
+                vec![0x81, 0x02, crc8(&[0x81, 0x02])],
+            ),
+        ]);
+        let mut hdc = Hdc3022 {
+            address: 0x44,
+            mode: AcquisitionMode::OnDemand,
+            power: PowerMode::default(),
+        };
+        hdc.write_register(&mut i2c, &Offsets(0x8102)).unwrap();
+        assert_eq!(
+            hdc.read_register::<Offsets, _>(&mut i2c).unwrap(),
+            Offsets(0x8102)
+        );
+        assert!(matches!(
+            hdc.write_register(&mut i2c, &Id),
+            Err(Error::ReadOnly)
+        ));
+        i2c.done();
+    }
+
+    #[test]
+    fn test_read_errors() {
+        extern crate std;
+        use super::Hdc3022Builder;
+        use crate::{
+            AcquisitionMode, AutoReading, AutoReadout, Error, Humidity, MeasurementRate, PowerMode,
+            command::crc8,
+        };
+        use embedded_hal::i2c::{ErrorKind, NoAcknowledgeSource};
+        use embedded_hal_mock::eh1::i2c::{Mock, Transaction};
+        use std::vec;
+        let mut i2c = Mock::new(&[
+            Transaction::write(0x44, vec![0x30, 0x93]),
+            Transaction::write_read(
+                0x44,
+                vec![0x37, 0x81],
+                vec![0x30, 0x00, crc8(&[0x30, 0x00])],
+            ),
+            // the measurement is not complete yet
+            Transaction::read(0x44, vec![0; 6])
+                .with_error(ErrorKind::NoAcknowledge(NoAcknowledgeSource::Address)),
+            // corrupted humidity checksum
+            Transaction::read(
+                0x44,
+                vec![0x66, 0x66, crc8(&[0x66, 0x66]), 0x80, 0x00, 0x00],
+            ),
+            Transaction::write(0x44, vec![0x21, 0x30]),
+            Transaction::write_read(
+                0x44,
+                vec![0xe0, 0x05],
+                vec![0xff, 0xff, crc8(&[0xff, 0xff])],
+            ),
+        ]);
+        let mut hdc = Hdc3022Builder::default().build(&mut i2c).unwrap();
+        assert!(matches!(
+            hdc.read_temperature_humidity(&mut i2c),
+            Err(Error::I2c(ErrorKind::NoAcknowledge(_)))
+        ));
+        assert!(matches!(
+            hdc.read_temperature_humidity(&mut i2c),
+            Err(Error::Crc)
+        ));
+        assert!(matches!(
+            hdc.read_auto_measurement(&mut i2c),
+            Err(Error::InvalidOperation)
+        ));
+        hdc.start_auto_mode(&mut i2c, MeasurementRate::OneHz, PowerMode::LowNoise)
+            .unwrap();
+        assert!(matches!(hdc.get_mode(), AcquisitionMode::Auto { .. }));
+        // on demand measurements are rejected without touching the bus
+        assert!(matches!(
+            hdc.trigger_on_demand(&mut i2c, PowerMode::LowNoise),
+            Err(Error::InvalidOperation)
+        ));
+        assert!(matches!(
+            hdc.read_temperature_humidity(&mut i2c),
+            Err(Error::InvalidOperation)
+        ));
+        assert_eq!(
+            hdc.read_auto(&mut i2c, AutoReadout::MaxHumidity).unwrap(),
+            AutoReading::Humidity(Humidity { value: 0xffff })
+        );
+        i2c.done();
+    }
+
+    #[test]
+    fn test_drain_fifo() {
+        extern crate std;
+        use super::Hdc3022Builder;
+        use crate::{Error, Humidity, MeasurementRate, PowerMode, command::crc8};
+        use embedded_hal_mock::eh1::i2c::{Mock, Transaction};
+        use std::vec;
+        let word = |word: u16| {
+            let [hi, lo] = word.to_be_bytes();
+            vec![hi, lo, crc8(&[hi, lo])]
+        };
+        let mut i2c = Mock::new(&[
+            Transaction::write(0x44, vec![0x30, 0x93]),
+            Transaction::write_read(0x44, vec![0x37, 0x81], word(0x3000)),
+            Transaction::write(0x44, vec![0x20, 0x24]),
+            Transaction::write_read(
+                0x44,
+                vec![0xe0, 0x00],
+                [word(0x6666), word(0x8000)].concat(),
+            ),
+            Transaction::write_read(0x44, vec![0xe0, 0x02], word(0x0000)),
+            Transaction::write_read(0x44, vec![0xe0, 0x03], word(0xffff)),
+            Transaction::write_read(0x44, vec![0xe0, 0x04], word(0x4000)),
+            Transaction::write_read(0x44, vec![0xe0, 0x05], word(0xc000)),
+            // the extrema are reset by restarting the auto measurement mode
+            Transaction::write(0x44, vec![0x30, 0x93]),
+            Transaction::write(0x44, vec![0x20, 0x24]),
+        ]);
+        let mut hdc = Hdc3022Builder::default().build(&mut i2c).unwrap();
+        assert!(matches!(
+            hdc.drain_fifo(&mut i2c),
+            Err(Error::InvalidOperation)
+        ));
+        hdc.start_auto_mode(&mut i2c, MeasurementRate::HalfHz, PowerMode::LowPower1)
+            .unwrap();
+        let summary = hdc.drain_fifo(&mut i2c).unwrap();
+        assert_eq!(summary.humidity, Humidity { value: 0x8000 });
+        assert_eq!(summary.min_temperature.millidegrees(), -45_000);
+        assert_eq!(summary.max_temperature.millidegrees(), 130_000);
+        assert_eq!(summary.min_humidity.raw(), 0x4000);
+        assert_eq!(summary.max_humidity.raw(), 0xc000);
+        assert!(summary.min_temperature <= summary.temperature);
+        i2c.done();
     }
 }
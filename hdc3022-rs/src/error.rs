@@ -1,5 +1,5 @@
 #[derive(Debug)]
-/// Represents errors that can occur while interacting with the HDC1010 sensor.
+/// Represents errors that can occur while interacting with the HDC3022 sensor.
 pub enum Error<E> {
     /// An error occurred while communicating with the I2C bus.
     I2c(E),
@@ -7,12 +7,69 @@ pub enum Error<E> {
     InvalidAddress,
     /// An error occurred due to an invalid ID.
     InvalidId,
-    /// Attempted to write to a register that is not writable.
+    /// The checksum of a data word read from the sensor is invalid.
+    Crc,
+    /// Attempted to write a data word that is not writable.
     ReadOnly,
     /// An error occurred due to an invalid operation.
     Timeout,
     /// The sensor is not configured for the requested operation.
     InvalidOperation,
+    /// An error occurred while reading the ALERT pin.
+    Pin(embedded_hal::digital::ErrorKind),
+}
+
+impl<E: core::fmt::Debug> core::fmt::Display for Error<E> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Error::I2c(e) => write!(f, "I2C error: {e:?}"),
+            Error::InvalidAddress => write!(f, "invalid address"),
+            Error::InvalidId => write!(f, "invalid manufacturer or device ID"),
+            Error::Crc => write!(f, "invalid checksum"),
+            Error::ReadOnly => write!(f, "data word is read-only"),
+            Error::Timeout => write!(f, "timed out"),
+            Error::InvalidOperation => write!(f, "operation not supported in the configured mode"),
+            Error::Pin(e) => write!(f, "ALERT pin error: {e:?}"),
+        }
+    }
+}
+
+impl<E: core::fmt::Debug> core::error::Error for Error<E> {}
+
+#[cfg(feature = "defmt")]
+impl<E: defmt::Format> defmt::Format for Error<E> {
+    fn format(&self, f: defmt::Formatter) {
+        match self {
+            Error::I2c(e) => defmt::write!(f, "I2C error: {}", e),
+            Error::InvalidAddress => defmt::write!(f, "invalid address"),
+            Error::InvalidId => defmt::write!(f, "invalid manufacturer or device ID"),
+            Error::Crc => defmt::write!(f, "invalid checksum"),
+            Error::ReadOnly => defmt::write!(f, "data word is read-only"),
+            Error::Timeout => defmt::write!(f, "timed out"),
+            Error::InvalidOperation => {
+                defmt::write!(f, "operation not supported in the configured mode")
+            }
+            Error::Pin(e) => defmt::write!(f, "ALERT pin error: {}", defmt::Debug2Format(e)),
+        }
+    }
+}
+
+impl<E> Error<E> {
+    /// Returns the error of the I2C bus, if the error occurred while communicating with it.
+    pub fn i2c(&self) -> Option<&E> {
+        match self {
+            Error::I2c(e) => Some(e),
+            _ => None,
+        }
+    }
+
+    /// Returns the error of the I2C bus, if the error occurred while communicating with it.
+    pub fn into_i2c(self) -> Option<E> {
+        match self {
+            Error::I2c(e) => Some(e),
+            _ => None,
+        }
+    }
 }
 
 impl<E> From<E> for Error<E> {
@@ -1,16 +1,33 @@
 #![no_std]
 #![deny(missing_docs)]
-//!# HDC1010 - Driver for the Texas Instruments HDC1010 Humidity and Temperature Sensor
-//! This crate provides a driver for the HDC1010 sensor, allowing you to read humidity and temperature data.
-//! It supports various configurations such as acquisition mode and resolution settings.
+//!# HDC3022 - Driver for the Texas Instruments HDC3022 Humidity and Temperature Sensor
+//! This crate provides a driver for the HDC3022 sensor, allowing you to read humidity and temperature data.
+//! It supports measurements triggered on demand as well as the auto measurement mode, in all power modes.
+//!
+//! Data words that the driver does not cover are accessed by implementing [`Hdc3022Register`], and
+//! reading or writing them with [`Hdc3022::read_register`] and [`Hdc3022::write_register`].
+//!
+//! [`Error`] implements `core::error::Error`, so that host binaries can return it as a
+//! `Box<dyn Error>`, and `defmt::Format` with the `defmt` feature.
 mod address;
+mod alert;
+mod command;
 mod core;
 mod error;
+mod offset;
 mod register;
+mod sensor;
 
 pub use address::SlaveAddress;
+pub use alert::{AlertLimit, AlertThreshold};
 pub use core::{Hdc3022, Hdc3022Builder};
 pub use error::Error;
+/// Relative humidity measurement reported by the sensor.
+pub use piccthermo_core::RelativeHumidity;
+/// Temperature measurement reported by the sensor.
+pub use piccthermo_core::Temperature;
+pub use piccthermo_core::{conditioning, hygrometry};
 pub use register::{
-    AcquisitionMode, Humidity, HumidityResolution, Temperature, TemperatureResolution, Trigger,
+    AcquisitionMode, Alert, AutoReading, AutoReadout, AutoSummary, Hdc3022Register, Humidity,
+    MeasurementRate, PowerMode, Status,
 };
@@ -0,0 +1,67 @@
+use embedded_hal::i2c::{I2c, SevenBitAddress};
+
+use crate::{
+    Error, Hdc3022, RelativeHumidity, Temperature,
+    command::{self, read_words, write_command_data},
+};
+
+/// Temperature offset step, 175 / 1024 °C, in 16.16 fixed-point units.
+const TEMPERATURE_OFFSET_LSB: i32 = 175 << 6;
+/// Humidity offset step, 100 / 512 %, in 16.16 fixed-point units.
+const HUMIDITY_OFFSET_LSB: i32 = 100 << 7;
+
+impl Hdc3022 {
+    /// Program the temperature and humidity offsets of the HDC3022 sensor.
+    ///
+    /// The offsets are added to every measurement by the sensor, and persist in its non-volatile memory.
+    /// Each offset is quantized to a sign and 7-bit magnitude, in steps of about 0.17 °C up to about
+    /// ±21.7 °C for the temperature, and in steps of about 0.2 % up to about ±24.8 % for the humidity.
+    /// Offsets outside of this range saturate.
+    pub fn set_offsets<T: I2c<SevenBitAddress>>(
+        &mut self,
+        i2c: &mut T,
+        temp_offset: Temperature,
+        rh_offset: RelativeHumidity,
+    ) -> Result<(), Error<T::Error>> {
+        let rh = encode_offset(rh_offset.to_bits(), HUMIDITY_OFFSET_LSB);
+        let temp = encode_offset(temp_offset.to_bits(), TEMPERATURE_OFFSET_LSB);
+        write_command_data(
+            i2c,
+            self.address,
+            command::OFFSETS,
+            u16::from_be_bytes([rh, temp]),
+        )
+    }
+
+    /// Read back the temperature and humidity offsets of the HDC3022 sensor.
+    pub fn get_offsets<T: I2c<SevenBitAddress>>(
+        &mut self,
+        i2c: &mut T,
+    ) -> Result<(Temperature, RelativeHumidity), Error<T::Error>> {
+        let [offsets] = read_words(i2c, self.address, Some(command::OFFSETS))?;
+        let [rh, temp] = offsets.to_be_bytes();
+        Ok((
+            Temperature::from_bits(decode_offset(temp, TEMPERATURE_OFFSET_LSB)),
+            RelativeHumidity::from_bits(decode_offset(rh, HUMIDITY_OFFSET_LSB)),
+        ))
+    }
+}
+
+/// Encode an offset as a sign bit (set for positive offsets) and a 7-bit magnitude in units of `lsb`.
+fn encode_offset(bits: i32, lsb: i32) -> u8 {
+    let magnitude = ((bits.unsigned_abs() + lsb as u32 / 2) / lsb as u32).min(0x7F) as u8;
+    if bits >= 0 {
+        0x80 | magnitude
+    } else {
+        magnitude
+    }
+}
+
+fn decode_offset(offset: u8, lsb: i32) -> i32 {
+    let magnitude = (offset & 0x7F) as i32 * lsb;
+    if offset & 0x80 != 0 {
+        magnitude
+    } else {
+        -magnitude
+    }
+}
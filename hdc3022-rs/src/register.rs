@@ -1,337 +1,305 @@
+use core::time::Duration;
+
 use bitfield_struct::bitfield;
-use embedded_hal::i2c::{I2c, SevenBitAddress};
 
-use crate::{Error, core::Hdc3022};
+use crate::{RelativeHumidity, Temperature};
 
 pub(crate) const HDC3022_MANUFACTURER_ID: u16 = 0x3000; // Texas Instruments
-pub(crate) const HDC3022_DEVICE_ID: u16 = 0x1000; // HDC3022 Device ID
-
-pub(crate) trait Hdc3022Register: Default {
-    const ADDRESS: u8;
-    const REGISTER_LEN: usize;
 
-    fn read<T: I2c<SevenBitAddress>>(
-        &mut self,
-        hdc: &mut Hdc3022<T>,
-    ) -> Result<(), Error<T::Error>>;
-    fn write<T: I2c<SevenBitAddress>>(
-        &mut self,
-        _hdc: &mut Hdc3022<T>,
-    ) -> Result<(), Error<T::Error>> {
-        Err(Error::ReadOnly)
+/// A 16-bit data word of the HDC3022, read and written with [`Hdc3022::read_register`] and
+/// [`Hdc3022::write_register`].
+///
+/// The trait is the extension point to access the data words the driver does not cover, e.g.
+/// commands that are not documented, through the same plumbing: the word is read big-endian after
+/// its command, and written big-endian after its command, with the checksum of the sensor.
+///
+/// [`Hdc3022::read_register`]: crate::Hdc3022::read_register
+/// [`Hdc3022::write_register`]: crate::Hdc3022::write_register
+pub trait Hdc3022Register: Sized {
+    /// Command reading the word.
+    const COMMAND: u16;
+    /// Command writing the word, the read command by default.
+    const WRITE_COMMAND: u16 = Self::COMMAND;
+
+    /// Converts the raw word read from the sensor.
+    fn from_raw(raw: u16) -> Self;
+    /// The raw word written to the sensor, or `None` if the word is read-only.
+    ///
+    /// The default implementation returns `None`.
+    fn to_raw(&self) -> Option<u16> {
+        None
     }
 }
 
-#[derive(Debug, PartialEq)]
-/// Trigger a measurement for either temperature or humidity.
-pub enum Trigger {
-    /// Trigger a measurement for both temperature and humidity.
-    Both,
-    /// Trigger a temperature measurement.
-    Temperature,
-    /// Trigger a humidity measurement.
-    Humidity,
+/// Converts a raw temperature value to a [`Temperature`].
+pub(crate) fn temperature_from_raw(value: u16) -> Temperature {
+    // T = -45 + 175 * raw / (2^16 - 1), expressed in 16.16 fixed-point units
+    Temperature::from_bits(((value as i64 * (175 << 16)) / 0xFFFF) as i32 - (45 << 16))
 }
 
-#[derive(Debug, Default)]
-/// Represents a temperature measurement from the HDC3022 sensor.
-pub struct Temperature {
-    pub(crate) value: u16,
+/// Converts a [`Temperature`] to a raw temperature value, saturating outside of -45 °C..130 °C.
+pub(crate) fn temperature_to_raw(temperature: Temperature) -> u16 {
+    let bits = temperature.to_bits() as i64 + (45 << 16);
+    ((bits * 0xFFFF) / (175 << 16)).clamp(0, 0xFFFF) as u16
 }
 
-impl Temperature {
-    /// Converts the raw temperature value to Celsius.
-    pub fn celsius(&self) -> core::primitive::f32 {
-        // Convert the raw value to Celsius
-        (self.value as f32 * 165.0 / 65536.0) - 40.0
-    }
-}
-
-impl Hdc3022Register for Temperature {
-    const ADDRESS: u8 = 0x0;
-
-    const REGISTER_LEN: usize = 2;
-
-    fn read<T: I2c<SevenBitAddress>>(
-        &mut self,
-        hdc: &mut Hdc3022<T>,
-    ) -> Result<(), Error<T::Error>> {
-        let mut buffer = [0u8; Self::REGISTER_LEN];
-        hdc.i2c
-            .write_read(hdc.address, &[Self::ADDRESS], &mut buffer)?;
-        self.value = u16::from_be_bytes(buffer);
-        Ok(())
-    }
-
-    fn write<T: I2c<SevenBitAddress>>(
-        &mut self,
-        hdc: &mut Hdc3022<T>,
-    ) -> Result<(), Error<T::Error>> {
-        hdc.i2c.write(hdc.address, &[Self::ADDRESS])?;
-        Ok(())
-    }
+/// Converts a [`RelativeHumidity`] to a raw humidity value, saturating outside of 0 %..100 %.
+pub(crate) fn humidity_to_raw(humidity: RelativeHumidity) -> u16 {
+    ((humidity.to_bits() as i64 * 0xFFFF) / (100 << 16)).clamp(0, 0xFFFF) as u16
 }
 
-#[derive(Debug, Default)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 /// Represents a humidity measurement from the HDC3022 sensor.
 pub struct Humidity {
     pub(crate) value: u16,
 }
 
 impl Humidity {
-    /// Converts the raw humidity value to percentage (0-100).
-    pub fn percentage(&self) -> core::primitive::f32 {
-        self.value as f32 * 100.0 / 65536.0
+    /// Returns the raw humidity value as read from the sensor.
+    pub const fn raw(&self) -> u16 {
+        self.value
     }
-}
 
-impl Hdc3022Register for Humidity {
-    const ADDRESS: u8 = 0x1;
-
-    const REGISTER_LEN: usize = 2;
-
-    fn read<T: I2c<SevenBitAddress>>(
-        &mut self,
-        hdc: &mut Hdc3022<T>,
-    ) -> Result<(), Error<T::Error>> {
-        let mut buffer = [0u8; Self::REGISTER_LEN];
-        hdc.i2c
-            .write_read(hdc.address, &[Self::ADDRESS], &mut buffer)?;
-        self.value = u16::from_be_bytes(buffer);
-        Ok(())
+    /// Converts the raw humidity value to percentage (0-100).
+    pub fn percentage(&self) -> core::primitive::f32 {
+        self.value as f32 * 100.0 / 65535.0
     }
 
-    fn write<T: I2c<SevenBitAddress>>(
-        &mut self,
-        hdc: &mut Hdc3022<T>,
-    ) -> Result<(), Error<T::Error>> {
-        hdc.i2c.write(hdc.address, &[Self::ADDRESS])?;
-        Ok(())
+    /// Returns the relative humidity as a fixed-point value.
+    pub fn relative_humidity(&self) -> RelativeHumidity {
+        // RH = 100 * raw / (2^16 - 1), expressed in 16.16 fixed-point units
+        RelativeHumidity::from_bits(((self.value as i64 * (100 << 16)) / 0xFFFF) as i32)
     }
 }
 
-#[bitfield(u16)]
-pub struct Configuration {
-    #[bits(8, default=0x0, access=RO)]
-    rsvd: u8,
-    #[bits(2, default=HumidityResolution::FourteenBit)]
-    pub humidity_resolution: HumidityResolution,
-    #[bits(1, default=TemperatureResolution::FourteenBit)]
-    pub temperature_resolution: TemperatureResolution,
-    #[bits(1, access=RO)]
-    pub power_ok: bool,
-    #[bits(1, default = AcquisitionMode::Both)]
-    pub mode: AcquisitionMode,
-    #[bits(1, default = false)]
-    pub heater_enable: bool,
-    #[bits(1, default=0, access=RO)]
-    rsvd2: bool,
-    #[bits(1, default = false)]
-    pub reset: bool,
-}
-
-#[repr(u8)]
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
-/// Acquisition mode for the HDC3022 sensor.
-pub enum AcquisitionMode {
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+/// Power mode of the HDC3022 sensor, trading measurement noise for conversion time and supply current.
+pub enum PowerMode {
     #[default]
-    /// Both temperature and humidity are acquired in sequence.
-    Both = 0b0,
-    /// Temperature and humidity are acquired separately.
-    Separate = 0b1,
+    /// Lowest noise, with a conversion time of 12.5 milliseconds.
+    LowNoise,
+    /// Low power mode 1, with a conversion time of 7.5 milliseconds.
+    LowPower1,
+    /// Low power mode 2, with a conversion time of 5 milliseconds.
+    LowPower2,
+    /// Lowest power, with a conversion time of 3.7 milliseconds.
+    LowPower3,
 }
 
-impl AcquisitionMode {
-    pub(crate) const fn from_bits(bits: u8) -> Self {
-        match bits {
-            0b0 => AcquisitionMode::Both,
-            0b1 => AcquisitionMode::Separate,
-            _ => panic!("Invalid AcquisitionMode bits"),
-        }
-    }
-
-    pub(crate) const fn into_bits(self) -> u8 {
+impl PowerMode {
+    /// Index of the power mode in the command tables.
+    pub(crate) const fn index(self) -> usize {
         match self {
-            AcquisitionMode::Both => 0b0,
-            AcquisitionMode::Separate => 0b1,
+            PowerMode::LowNoise => 0,
+            PowerMode::LowPower1 => 1,
+            PowerMode::LowPower2 => 2,
+            PowerMode::LowPower3 => 3,
         }
     }
-}
-
-impl Hdc3022Register for Configuration {
-    const ADDRESS: u8 = 0x2;
 
-    const REGISTER_LEN: usize = 2;
-
-    fn read<T: I2c<SevenBitAddress>>(
-        &mut self,
-        hdc: &mut Hdc3022<T>,
-    ) -> Result<(), Error<T::Error>> {
-        let mut buffer = [0u8; Self::REGISTER_LEN];
-        hdc.i2c
-            .write_read(hdc.address, &[Self::ADDRESS], &mut buffer)?;
-        *self = u16::from_be_bytes(buffer).into();
-        Ok(())
+    /// Command to trigger a measurement on demand in this power mode.
+    pub(crate) const fn trigger_command(self) -> u16 {
+        [0x2400, 0x240B, 0x2416, 0x24FF][self.index()]
     }
 
-    fn write<T: I2c<SevenBitAddress>>(
-        &mut self,
-        hdc: &mut Hdc3022<T>,
-    ) -> Result<(), Error<T::Error>> {
-        let buffer = self.into_bits().to_be_bytes();
-        hdc.i2c
-            .write(hdc.address, &[Self::ADDRESS, buffer[0], buffer[1]])?;
-        Ok(())
+    /// Returns the conversion time of a measurement triggered on demand in this power mode.
+    pub const fn conversion_time(self) -> Duration {
+        Duration::from_micros([12500, 7500, 5000, 3700][self.index()])
     }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
-#[repr(u8)]
-/// Humidity measurement resolution for the HDC3022 sensor.
-pub enum HumidityResolution {
-    /// 8-bit resolution, with a conversion time of 2.5 milliseconds.
-    EightBit = 0b10,
-    /// 11-bit resolution, with a conversion time of 3.85 milliseconds.
-    ElevenBit = 0b01,
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+/// Measurement rate of the HDC3022 sensor in auto measurement mode.
+pub enum MeasurementRate {
+    /// One measurement every 2 seconds.
+    HalfHz,
     #[default]
-    /// 14-bit resolution, with a conversion time of 6.5 milliseconds.
-    FourteenBit = 0b00,
+    /// One measurement every second.
+    OneHz,
+    /// Two measurements every second.
+    TwoHz,
+    /// Four measurements every second.
+    FourHz,
+    /// Ten measurements every second.
+    TenHz,
 }
 
-impl HumidityResolution {
-    pub(crate) const fn from_bits(bits: u8) -> Self {
-        match bits {
-            0b10 => HumidityResolution::EightBit,
-            0b01 => HumidityResolution::ElevenBit,
-            0b00 => HumidityResolution::FourteenBit,
-            _ => panic!("Invalid HumidityResolution bits"),
-        }
+impl MeasurementRate {
+    /// Command to start the auto measurement mode with this rate in the given power mode.
+    pub(crate) const fn command(self, power: PowerMode) -> u16 {
+        let commands = match self {
+            MeasurementRate::HalfHz => [0x2032, 0x2024, 0x202F, 0x20FF],
+            MeasurementRate::OneHz => [0x2130, 0x2126, 0x212D, 0x21FF],
+            MeasurementRate::TwoHz => [0x2236, 0x2220, 0x222B, 0x22FF],
+            MeasurementRate::FourHz => [0x2334, 0x2322, 0x2329, 0x23FF],
+            MeasurementRate::TenHz => [0x2737, 0x2721, 0x272A, 0x27FF],
+        };
+        commands[power.index()]
     }
 
-    pub(crate) const fn into_bits(self) -> u8 {
+    /// Returns the interval between two measurements in microseconds.
+    pub(crate) fn interval(self) -> u32 {
         match self {
-            HumidityResolution::EightBit => 0b10,
-            HumidityResolution::ElevenBit => 0b01,
-            HumidityResolution::FourteenBit => 0b00,
-        }
-    }
-
-    /// Returns the delay time in microseconds for the given humidity resolution.
-    pub(crate) fn delay_time(self) -> u32 {
-        match self {
-            HumidityResolution::EightBit => 2500,
-            HumidityResolution::ElevenBit => 3850,
-            HumidityResolution::FourteenBit => 6500,
+            MeasurementRate::HalfHz => 2_000_000,
+            MeasurementRate::OneHz => 1_000_000,
+            MeasurementRate::TwoHz => 500_000,
+            MeasurementRate::FourHz => 250_000,
+            MeasurementRate::TenHz => 100_000,
         }
     }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
-#[repr(u8)]
-/// Temperature measurement resolution for the HDC3022 sensor.
-pub enum TemperatureResolution {
-    /// 11-bit resolution, with a conversion time of 3.65 milliseconds.
-    ElevenBit = 0b1,
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+/// Acquisition mode of the HDC3022 sensor.
+pub enum AcquisitionMode {
     #[default]
-    /// 14-bit resolution, with a conversion time of 6.35 milliseconds.
-    FourteenBit = 0b0,
+    /// The sensor sleeps between measurements, which are triggered on demand.
+    OnDemand,
+    /// The sensor measures temperature and humidity periodically.
+    Auto {
+        /// The rate at which measurements are taken.
+        rate: MeasurementRate,
+        /// The power mode used for the measurements.
+        power: PowerMode,
+    },
 }
 
-impl TemperatureResolution {
-    pub(crate) const fn from_bits(bits: u8) -> Self {
-        match bits {
-            0b1 => TemperatureResolution::ElevenBit,
-            0b0 => TemperatureResolution::FourteenBit,
-            _ => panic!("Invalid TemperatureResolution bits"),
-        }
-    }
-
-    pub(crate) const fn into_bits(self) -> u8 {
-        match self {
-            TemperatureResolution::ElevenBit => 0b1,
-            TemperatureResolution::FourteenBit => 0b0,
-        }
-    }
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+/// Value to read out in auto measurement mode.
+pub enum AutoReadout {
+    /// The last temperature and humidity measurement.
+    Measurement,
+    /// The minimum temperature measured since the auto measurement mode was started.
+    MinTemperature,
+    /// The maximum temperature measured since the auto measurement mode was started.
+    MaxTemperature,
+    /// The minimum humidity measured since the auto measurement mode was started.
+    MinHumidity,
+    /// The maximum humidity measured since the auto measurement mode was started.
+    MaxHumidity,
+}
 
-    /// Returns the delay time in microseconds for the given temperature resolution.
-    pub(crate) fn delay_time(self) -> u32 {
-        match self {
-            TemperatureResolution::ElevenBit => 3650,
-            TemperatureResolution::FourteenBit => 6350,
-        }
-    }
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+/// Value read out in auto measurement mode, see [`AutoReadout`].
+pub enum AutoReading {
+    /// A temperature and humidity measurement.
+    Measurement(Temperature, Humidity),
+    /// A temperature extremum.
+    Temperature(Temperature),
+    /// A humidity extremum.
+    Humidity(Humidity),
 }
 
-#[derive(Debug, Default)]
-pub struct SerialId(u64);
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+/// The last measurement and the extrema recorded in auto measurement mode, see
+/// [`Hdc3022::drain_fifo`](crate::Hdc3022::drain_fifo).
+pub struct AutoSummary {
+    /// The last temperature measured.
+    pub temperature: Temperature,
+    /// The last humidity measured.
+    pub humidity: Humidity,
+    /// The minimum temperature measured since the extrema were last reset.
+    pub min_temperature: Temperature,
+    /// The maximum temperature measured since the extrema were last reset.
+    pub max_temperature: Temperature,
+    /// The minimum humidity measured since the extrema were last reset.
+    pub min_humidity: Humidity,
+    /// The maximum humidity measured since the extrema were last reset.
+    pub max_humidity: Humidity,
+}
 
-impl SerialId {
-    pub fn value(&self) -> u64 {
-        self.0
-    }
+#[bitfield(u16, defmt = cfg(feature = "defmt"))]
+#[derive(PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+/// Status register of the HDC3022 sensor.
+pub struct Status {
+    /// The checksum of the last data write was invalid.
+    #[bits(1, access = RO)]
+    pub checksum_failed: bool,
+    #[bits(3, access = RO)]
+    rsvd: u8,
+    /// A reset was detected since the status register was last cleared.
+    #[bits(1, access = RO)]
+    pub reset_detected: bool,
+    #[bits(1, access = RO)]
+    rsvd2: bool,
+    /// The temperature low tracking alert is active.
+    #[bits(1, access = RO)]
+    pub temperature_low_alert: bool,
+    /// The temperature high tracking alert is active.
+    #[bits(1, access = RO)]
+    pub temperature_high_alert: bool,
+    /// The humidity low tracking alert is active.
+    #[bits(1, access = RO)]
+    pub humidity_low_alert: bool,
+    /// The humidity high tracking alert is active.
+    #[bits(1, access = RO)]
+    pub humidity_high_alert: bool,
+    /// A temperature tracking alert is active.
+    #[bits(1, access = RO)]
+    pub temperature_alert: bool,
+    /// A humidity tracking alert is active.
+    #[bits(1, access = RO)]
+    pub humidity_alert: bool,
+    #[bits(1, access = RO)]
+    rsvd3: bool,
+    /// The integrated heater is enabled.
+    #[bits(1, access = RO)]
+    pub heater_enabled: bool,
+    #[bits(1, access = RO)]
+    rsvd4: bool,
+    /// At least one alert is active.
+    #[bits(1, access = RO)]
+    pub alert: bool,
 }
 
-impl Hdc3022Register for SerialId {
-    const ADDRESS: u8 = 0xFB;
-    const REGISTER_LEN: usize = 6;
+impl Hdc3022Register for Status {
+    const COMMAND: u16 = crate::command::READ_STATUS;
 
-    fn read<T: I2c<SevenBitAddress>>(
-        &mut self,
-        hdc: &mut Hdc3022<T>,
-    ) -> Result<(), Error<T::Error>> {
-        let mut buffer = [0u8; Self::REGISTER_LEN];
-        hdc.i2c
-            .write_read(hdc.address, &[Self::ADDRESS], &mut buffer)?;
-        self.0 = (buffer[0] as u64) << 33
-            | (buffer[1] as u64) << 25
-            | (buffer[2] as u64) << 17
-            | (buffer[3] as u64) << 9
-            | (buffer[4] as u64) << 1
-            | (buffer[5] as u64) >> 7;
-        Ok(())
+    fn from_raw(raw: u16) -> Self {
+        Self::from_bits(raw)
     }
 }
 
-#[derive(Debug, Default)]
-pub struct ManufacturerId(u16);
-
-impl Hdc3022Register for ManufacturerId {
-    const ADDRESS: u8 = 0xFE;
-    const REGISTER_LEN: usize = 2;
-
-    fn read<T: I2c<SevenBitAddress>>(
-        &mut self,
-        hdc: &mut Hdc3022<T>,
-    ) -> Result<(), Error<T::Error>> {
-        let mut buffer = [0u8; Self::REGISTER_LEN];
-        hdc.i2c
-            .write_read(hdc.address, &[Self::ADDRESS], &mut buffer)?;
-        self.0 = u16::from_be_bytes(buffer);
-        if self.0 != HDC3022_MANUFACTURER_ID {
-            return Err(Error::InvalidId);
-        }
-        Ok(())
+impl Status {
+    /// Returns the tracking alerts that are active, as indicated by the alert bits.
+    pub fn active_alerts(&self) -> impl Iterator<Item = Alert> + use<> {
+        [
+            (self.temperature_high_alert(), Alert::TemperatureHigh),
+            (self.temperature_low_alert(), Alert::TemperatureLow),
+            (self.humidity_high_alert(), Alert::HumidityHigh),
+            (self.humidity_low_alert(), Alert::HumidityLow),
+        ]
+        .into_iter()
+        .filter_map(|(active, alert)| active.then_some(alert))
     }
 }
 
-#[derive(Debug, Default)]
-pub struct DeviceId(u16);
-
-impl Hdc3022Register for DeviceId {
-    const ADDRESS: u8 = 0xFF;
-    const REGISTER_LEN: usize = 2;
-
-    fn read<T: I2c<SevenBitAddress>>(
-        &mut self,
-        hdc: &mut Hdc3022<T>,
-    ) -> Result<(), Error<T::Error>> {
-        let mut buffer = [0u8; Self::REGISTER_LEN];
-        hdc.i2c
-            .write_read(hdc.address, &[Self::ADDRESS], &mut buffer)?;
-        self.0 = u16::from_be_bytes(buffer);
-        if self.0 != HDC3022_DEVICE_ID {
-            return Err(Error::InvalidId);
-        }
-        Ok(())
-    }
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+/// A tracking alert raised by the HDC3022 sensor.
+pub enum Alert {
+    /// The temperature is above the high alert threshold.
+    TemperatureHigh,
+    /// The temperature is below the low alert threshold.
+    TemperatureLow,
+    /// The humidity is above the high alert threshold.
+    HumidityHigh,
+    /// The humidity is below the low alert threshold.
+    HumidityLow,
 }
@@ -0,0 +1,45 @@
+use core::time::Duration;
+
+use embedded_hal::i2c::{I2c, SevenBitAddress};
+use piccthermo_core::{HumiditySensor, RelativeHumidity, SensorDriver};
+
+use crate::{AcquisitionMode, Error, Hdc3022, Temperature};
+
+impl<T: I2c<SevenBitAddress>> SensorDriver<T> for Hdc3022 {
+    type Error = Error<T::Error>;
+
+    /// Triggers an on demand measurement in the default power mode.
+    ///
+    /// In auto measurement mode the sensor measures on its own, and this does nothing.
+    fn trigger(&mut self, bus: &mut T) -> Result<(), Self::Error> {
+        match self.mode {
+            AcquisitionMode::OnDemand => self.trigger_on_demand(bus, self.power).map(|_| ()),
+            AcquisitionMode::Auto { .. } => Ok(()),
+        }
+    }
+
+    fn ready_after(&self) -> Duration {
+        match self.mode {
+            AcquisitionMode::OnDemand => self.power.conversion_time(),
+            AcquisitionMode::Auto { .. } => Duration::ZERO,
+        }
+    }
+}
+
+impl<T: I2c<SevenBitAddress>> HumiditySensor<T> for Hdc3022 {
+    /// Reads the temperature and humidity, identified by the I2C address of the sensor.
+    ///
+    /// In auto measurement mode, the last measurement is read out.
+    fn read(
+        &mut self,
+        bus: &mut T,
+        sink: &mut dyn FnMut(u64, Temperature, RelativeHumidity),
+    ) -> Result<(), Self::Error> {
+        let (temp, hum) = match self.mode {
+            AcquisitionMode::OnDemand => self.read_temperature_humidity(bus)?,
+            AcquisitionMode::Auto { .. } => self.read_auto_measurement(bus)?,
+        };
+        sink(self.address as _, temp, hum.relative_humidity());
+        Ok(())
+    }
+}
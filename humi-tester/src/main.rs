@@ -1,88 +1,235 @@
 use std::time::{Duration, Instant};
 
-use clap::Parser;
-use hdc1010::{Hdc1010Builder, SlaveAddress as H10SlaveAddress, Trigger};
+use clap::{Parser, ValueEnum};
+use embedded_hal::i2c::ErrorType;
+use hdc1010::{
+    AcquisitionMode, Both, Clock, Error, Hdc1010, Hdc1010Builder, Humidity, PendingMeasurement,
+    ReadResult, Separate, StdClock,
+};
 use linux_embedded_hal::{Delay, I2cdev};
+use piccthermo_core::{
+    HumiditySensor, RelativeHumidity, SensorDriver, Temperature, sim::SimulatedSensor,
+};
 
 /// Simple program to greet a person
 #[derive(Parser, Debug)]
 #[command(version, about, long_about = None)]
 struct Args {
     /// Path to I2C bus (e.g., /dev/i2c-1)
-    #[arg(short, long)]
-    path: String,
+    #[arg(short, long, required_unless_present = "simulate")]
+    path: Option<String>,
+    /// Acquisition mode of the sensors, the synthetic sensors convert both channels at once
+    #[arg(long, value_enum, default_value_t = Mode::Separate)]
+    mode: Mode,
+    /// Channels to read out and print
+    #[arg(long, value_enum, default_value_t = Channel::Humidity)]
+    channel: Channel,
+    /// Read synthetic sensors instead of the sensors on the bus
+    #[arg(long, default_value_t = false)]
+    simulate: bool,
+    /// Number of synthetic sensors, at most 4
+    #[arg(long, requires = "simulate", default_value_t = 4)]
+    sim_sensors: usize,
+    /// Largest step of the random walk of a synthetic reading per measurement
+    #[arg(long, requires = "simulate", default_value_t = 0.05)]
+    sim_drift: f32,
+    /// Largest noise added to a synthetic reading
+    #[arg(long, requires = "simulate", default_value_t = 0.1)]
+    sim_noise: f32,
+    /// Probability, between 0 and 1, that a synthetic sensor is missing from a measurement
+    #[arg(long, requires = "simulate", default_value_t = 0.0)]
+    sim_dropout: f32,
 }
 
+/// Acquisition mode of the HDC1010, see [`Hdc1010Builder::build_mode_both`] and
+/// [`Hdc1010Builder::build_mode_separate`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum Mode {
+    /// Temperature and humidity in a single conversion
+    Both,
+    /// Temperature and humidity triggered one after the other
+    Separate,
+}
+
+/// Channels of the sensors that are read out.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum Channel {
+    Temp,
+    Humidity,
+    Both,
+}
+
+impl Channel {
+    fn temperature(self) -> bool {
+        self != Channel::Humidity
+    }
+
+    fn humidity(self) -> bool {
+        self != Channel::Temp
+    }
+}
+
+type I2cError = <I2cdev as ErrorType>::Error;
+
 fn main() {
     // Initialize the logger
     env_logger::init();
     // Parse command line arguments
     let args = Args::parse();
-    init(args.path);
+    match args.path {
+        Some(ref path) if !args.simulate => init(path, &args),
+        _ => simulate(&args),
+    }
+}
+
+/// Read synthetic sensors at the addresses of the HDC1010, through the same traits as the driver.
+fn simulate(args: &Args) {
+    println!("[HUM] Simulating sensors");
+    let mut sim = SimulatedSensor::<4>::new(0)
+        .with_channels(args.sim_sensors)
+        .with_first_id(hdc1010::SlaveAddress::default().into_bits() as u64)
+        .with_drift(args.sim_drift)
+        .with_noise(args.sim_noise)
+        .with_dropout(args.sim_dropout)
+        .with_conversion_time(Duration::from_micros(6500));
+    for id in sim.ids() {
+        println!("[HUM] Device found at address {id:02x}");
+    }
+    println!("[HUM] Devices found: {}", sim.ids().count());
+
+    loop {
+        let start = Instant::now();
+        match SensorDriver::<()>::trigger(&mut sim, &mut ()) {
+            Ok(()) => {
+                std::thread::sleep(SensorDriver::<()>::ready_after(&sim));
+                let mut read = 0;
+                let res = HumiditySensor::read(
+                    &mut sim,
+                    &mut (),
+                    &mut |id, t: Temperature, r: RelativeHumidity| {
+                        log::info!(
+                            "[HUM] Sensor 0x{id:02x}: {}",
+                            format_reading(args.channel, t, r)
+                        );
+                        read += 1;
+                    },
+                );
+                if let Err(e) = res {
+                    log::warn!("[HUM] Error reading: {e:?}");
+                }
+                log::info!(
+                    "[HUM] Read {read} sensors in {:.2} ms.",
+                    start.elapsed().as_secs_f64() * 1000.0
+                );
+            }
+            Err(e) => log::warn!("[HUM] Could not trigger: {e:?}"),
+        }
+        if start.elapsed().as_secs() < 1 {
+            std::thread::sleep(Duration::from_secs(1) - start.elapsed());
+        }
+    }
+}
+
+/// Format the channels of a reading that are read out.
+fn format_reading(channel: Channel, t: Temperature, r: RelativeHumidity) -> String {
+    match channel {
+        Channel::Temp => format!("{:.2}°C", t.celsius()),
+        Channel::Humidity => format!("{}%", r.percentage()),
+        Channel::Both => format!("{:.2}°C, {}%", t.celsius(), r.percentage()),
+    }
 }
 
-fn init(path: String) {
+fn init(path: &str, args: &Args) {
     println!("[HUM] Opening bus: {path}");
     // Open the I2C bus
-    let mut i2c = I2cdev::new(&path).expect("Failed to open I2C device");
+    let mut i2c = I2cdev::new(path).expect("Failed to open I2C device");
+    match args.mode {
+        Mode::Both => {
+            let hdc10s = open(&mut i2c, |builder, i2c| builder.build_mode_both(i2c));
+            run_both(&mut i2c, hdc10s, args.channel)
+        }
+        Mode::Separate => {
+            let hdc10s = open(&mut i2c, |builder, i2c| builder.build_mode_separate(i2c));
+            run_separate(&mut i2c, hdc10s, args.channel)
+        }
+    }
+}
+
+/// Open all available devices, in the acquisition mode set by `build`.
+fn open<M: AcquisitionMode>(
+    i2c: &mut I2cdev,
+    build: impl Fn(Hdc1010Builder, &mut I2cdev) -> Result<Hdc1010<M>, Error<I2cError>>,
+) -> Vec<Hdc1010<M>> {
     let mut delay = Delay;
-    // Open all available devices
-    let addrs = [
-        H10SlaveAddress::default(),
-        H10SlaveAddress::default().with_a0(true),
-        H10SlaveAddress::default().with_a1(true),
-        H10SlaveAddress::default().with_a0(true).with_a1(true),
-    ];
-    let mut hdc10s = addrs
+    let addrs = hdc1010::scan(i2c);
+    let hdc10s = addrs
         .iter()
-        .filter_map(|addr| {
-            match Hdc1010Builder::default()
-                .with_address(*addr)
-                .build_mode_separate(&mut i2c)
-            {
+        .filter_map(
+            |addr| match build(Hdc1010Builder::default().with_address(*addr), i2c) {
                 Ok(mut hdc) => {
                     println!("[HUM] Device found at address {:02x}", hdc.get_address());
-                    hdc.reset(&mut i2c, &mut delay).unwrap_or_else(|_| {
+                    match hdc.diagnostics(i2c) {
+                        Ok(diag) => log::info!("[HUM] {diag:?}"),
+                        Err(e) => log::warn!(
+                            "[HUM] Sensor 0x{:02x}: Could not read diagnostics: {e:?}",
+                            hdc.get_address()
+                        ),
+                    }
+                    hdc.reset(i2c, &mut delay).unwrap_or_else(|_| {
                         panic!("[HUM] Sensor 0x{:02x}: Could not reset.", hdc.get_address())
                     });
                     Some(hdc)
                 }
                 Err(e) => {
-                    log::warn!("[HUM] Address {:02x} not found: {e:?}", addr.into_bits());
+                    log::warn!(
+                        "[HUM] Address {:02x} could not be set up: {e:?}",
+                        addr.into_bits()
+                    );
                     None
                 }
-            }
-        })
+            },
+        )
         .collect::<Vec<_>>();
 
     println!("[HUM] Devices found: {}", hdc10s.len());
     std::thread::sleep(Duration::from_secs(1));
+    hdc10s
+}
 
+/// Read out the sensors with a single conversion of both channels.
+fn run_both(i2c: &mut I2cdev, mut hdc10s: Vec<Hdc1010<Both>>, channel: Channel) {
     loop {
-        let start = Instant::now();
-        if let Some(delay) = hdc10s
+        let clock = StdClock::new();
+        let windows = hdc10s
             .iter_mut()
-            .filter_map(|hdc| {
-                hdc.trigger(&mut i2c, Trigger::Humidity)
-                    .map_err(|e| {
-                        log::warn!(
-                            "[HUM] Sensor 0x{:02x}: Could not trigger: {e:?}",
-                            hdc.get_address()
-                        );
-                        e
-                    })
-                    .ok()
+            .map(|hdc| match hdc.trigger(i2c) {
+                Ok(window) => Some(window.timed(&clock)),
+                Err(e) => {
+                    log::warn!(
+                        "[HUM] Sensor 0x{:02x}: Could not trigger: {e:?}",
+                        hdc.get_address()
+                    );
+                    None
+                }
             })
-            .max()
-        {
-            std::thread::sleep(delay);
-            for hdc in hdc10s.iter_mut() {
-                match hdc.read_humidity(&mut i2c) {
-                    Ok(r) => log::info!(
-                        "[HUM] Sensor 0x{:02x}: {}%",
-                        hdc.get_address(),
-                        r.percentage()
-                    ),
+            .collect::<Vec<_>>();
+        if let Some(remaining) = windows.iter().flatten().map(|w| w.remaining(&clock)).max() {
+            std::thread::sleep(remaining);
+            let mut read = 0;
+            for (hdc, window) in hdc10s.iter_mut().zip(windows) {
+                let Some(window) = window else {
+                    continue;
+                };
+                match hdc.read_temperature_humidity(i2c) {
+                    Ok((t, r)) => {
+                        log::info!(
+                            "[HUM] Sensor 0x{:02x}: {} (conversion {})",
+                            hdc.get_address(),
+                            format_reading(channel, t, r.relative_humidity()),
+                            format_timing(window.delay(), window.triggered_at(), &clock)
+                        );
+                        read += 1;
+                    }
                     Err(e) => log::warn!(
                         "[HUM] Sensor 0x{:02x}: Error reading: {e:?}",
                         hdc.get_address()
@@ -90,13 +237,111 @@ fn init(path: String) {
                 }
             }
             log::info!(
-                "[HUM] Read {} sensors in {:.2} ms.",
-                hdc10s.len(),
-                start.elapsed().as_secs_f64() * 1000.0
+                "[HUM] Read {read} sensors in {:.2} ms.",
+                clock.now().as_secs_f64() * 1000.0
             );
         }
-        if start.elapsed().as_secs() < 1 {
-            std::thread::sleep(Duration::from_secs(1) - start.elapsed());
+        if clock.now().as_secs() < 1 {
+            std::thread::sleep(Duration::from_secs(1) - clock.now());
+        }
+    }
+}
+
+/// Read out the sensors, triggering the channels one after the other.
+fn run_separate(i2c: &mut I2cdev, mut hdc10s: Vec<Hdc1010<Separate>>, channel: Channel) {
+    loop {
+        let clock = StdClock::new();
+        if channel.temperature() {
+            measure(
+                i2c,
+                &mut hdc10s,
+                &clock,
+                Hdc1010::trigger_temperature,
+                PendingMeasurement::<Temperature>::read,
+                |t| format!("{:.2}°C", t.celsius()),
+            );
+        }
+        if channel.humidity() {
+            measure(
+                i2c,
+                &mut hdc10s,
+                &clock,
+                Hdc1010::trigger_humidity,
+                PendingMeasurement::<Humidity>::read,
+                |r| format!("{}%", r.percentage()),
+            );
+        }
+        log::info!(
+            "[HUM] Read {} sensors in {:.2} ms.",
+            hdc10s.len(),
+            clock.now().as_secs_f64() * 1000.0
+        );
+        if clock.now().as_secs() < 1 {
+            std::thread::sleep(Duration::from_secs(1) - clock.now());
+        }
+    }
+}
+
+/// Trigger a channel of every sensor, wait for the conversions and read them out.
+fn measure<K>(
+    i2c: &mut I2cdev,
+    hdc10s: &mut Vec<Hdc1010<Separate>>,
+    clock: &StdClock,
+    trigger: impl Fn(
+        Hdc1010<Separate>,
+        &mut I2cdev,
+    ) -> Result<PendingMeasurement<K>, (Hdc1010<Separate>, Error<I2cError>)>,
+    read: impl Fn(PendingMeasurement<K>, &mut I2cdev) -> ReadResult<K, I2cError>,
+    format: impl Fn(K) -> String,
+) {
+    let mut idle = Vec::new();
+    let pending = hdc10s
+        .drain(..)
+        .filter_map(|hdc| match trigger(hdc, i2c) {
+            Ok(pending) => Some(pending.timed(clock)),
+            Err((hdc, e)) => {
+                log::warn!(
+                    "[HUM] Sensor 0x{:02x}: Could not trigger: {e:?}",
+                    hdc.get_address()
+                );
+                idle.push(hdc);
+                None
+            }
+        })
+        .collect::<Vec<_>>();
+    if let Some(remaining) = pending.iter().map(|p| p.window().remaining(clock)).max() {
+        std::thread::sleep(remaining);
+        for p in pending {
+            let window = p.window();
+            match read(p, i2c) {
+                Ok((v, hdc)) => {
+                    log::info!(
+                        "[HUM] Sensor 0x{:02x}: {} (conversion {})",
+                        hdc.get_address(),
+                        format(v),
+                        format_timing(window.delay(), window.triggered_at(), clock)
+                    );
+                    hdc10s.push(hdc);
+                }
+                Err((p, e)) => {
+                    log::warn!(
+                        "[HUM] Sensor 0x{:02x}: Error reading: {e:?}",
+                        p.get_address()
+                    );
+                    hdc10s.push(p.cancel());
+                }
+            }
         }
     }
+    hdc10s.append(&mut idle);
+}
+
+/// Format the conversion time of a measurement, and the time it was read out after its trigger.
+fn format_timing(delay: Duration, triggered: Option<Duration>, clock: &StdClock) -> String {
+    let read = triggered.map_or(Duration::ZERO, |t| clock.now().saturating_sub(t));
+    format!(
+        "{:.2} ms, read after {:.2} ms",
+        delay.as_secs_f64() * 1000.0,
+        read.as_secs_f64() * 1000.0
+    )
 }
@@ -0,0 +1,341 @@
+#![no_std]
+#![deny(missing_docs)]
+//! # onewire-gpio
+//!
+//! A no-std 1-Wire bus master bit-banged on a single GPIO pin, implementing the
+//! [`OneWire`](https://docs.rs/embedded-onewire/latest/embedded_onewire/trait.OneWire.html) trait so that
+//! the 1-Wire device drivers, e.g. the DS28EA00 group, can be used on microcontrollers without a DS2484 bridge.
+//!
+//! The pin must be an open drain output with a pull-up resistor on the line: it is pulled low with
+//! [`OutputPin::set_low`], released with [`OutputPin::set_high`], and the level of the line is sampled with
+//! [`InputPin::is_high`]. The time slots are generated with a [`DelayNs`], using the standard and overdrive
+//! timings recommended by Maxim application note 126. The time slots are timing critical: interrupts that
+//! run longer than a few microseconds in the middle of a slot corrupt it, so they should be masked while the
+//! bus is in use, e.g. by sharing the bus with the `critical-section` backend of `shared-onewire`.
+//!
+//! Note: The master implements the [`OneWire`] trait without the `triplet-read` feature of `embedded-onewire`.
+#[cfg(test)]
+mod sim;
+
+use embedded_hal::{
+    delay::DelayNs,
+    digital::{InputPin, OutputPin},
+};
+use embedded_onewire::{OneWire, OneWireError, OneWireResult, OneWireStatus};
+
+/// Overdrive skip ROM command, which switches the devices to overdrive speed.
+const OVERDRIVE_SKIP_ROM: u8 = 0x3c;
+
+/// Durations of the phases of the 1-Wire time slots, in nanoseconds.
+///
+/// The letters refer to the timing diagrams of Maxim application note 126.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Timing {
+    /// (A) Low time of a write one slot, and of a read slot.
+    pub write_one_low: u32,
+    /// (B) Recovery time after a write one slot.
+    pub write_one_recovery: u32,
+    /// (C) Low time of a write zero slot.
+    pub write_zero_low: u32,
+    /// (D) Recovery time after a write zero slot.
+    pub write_zero_recovery: u32,
+    /// (E) Time from the release of the line to the sampling of a read slot.
+    pub read_sample: u32,
+    /// (F) Recovery time after the sampling of a read slot.
+    pub read_recovery: u32,
+    /// (G) Delay before a reset.
+    pub reset_delay: u32,
+    /// (H) Low time of a reset.
+    pub reset_low: u32,
+    /// (I) Time from the release of the line to the sampling of the presence pulse.
+    pub presence_sample: u32,
+    /// (J) Recovery time after the sampling of the presence pulse.
+    pub reset_recovery: u32,
+}
+
+impl Timing {
+    /// Recommended standard speed timing.
+    pub const STANDARD: Self = Self {
+        write_one_low: 6_000,
+        write_one_recovery: 64_000,
+        write_zero_low: 60_000,
+        write_zero_recovery: 10_000,
+        read_sample: 9_000,
+        read_recovery: 55_000,
+        reset_delay: 0,
+        reset_low: 480_000,
+        presence_sample: 70_000,
+        reset_recovery: 410_000,
+    };
+
+    /// Recommended overdrive speed timing.
+    pub const OVERDRIVE: Self = Self {
+        write_one_low: 1_000,
+        write_one_recovery: 7_500,
+        write_zero_low: 7_500,
+        write_zero_recovery: 2_500,
+        read_sample: 1_000,
+        read_recovery: 7_000,
+        reset_delay: 2_500,
+        reset_low: 70_000,
+        presence_sample: 8_500,
+        reset_recovery: 40_000,
+    };
+}
+
+/// Status of a [`GpioOneWire`] bus after a reset.
+#[derive(Debug, Clone, Copy)]
+pub struct GpioStatus {
+    presence: bool,
+}
+
+impl OneWireStatus for GpioStatus {
+    fn presence(&self) -> bool {
+        self.presence
+    }
+
+    fn shortcircuit(&self) -> bool {
+        false
+    }
+
+    fn logic_level(&self) -> Option<bool> {
+        Some(true) // the line is released after a successful reset
+    }
+}
+
+/// A 1-Wire bus master bit-banged on an open drain GPIO pin.
+pub struct GpioOneWire<P, D> {
+    pin: P,
+    delay: D,
+    standard: Timing,
+    overdrive: Timing,
+    od: bool,
+}
+
+impl<P: InputPin + OutputPin, D: DelayNs> GpioOneWire<P, D> {
+    /// Create a bus master on `pin`, timing the slots with `delay`.
+    ///
+    /// The pin is released, and the bus starts at standard speed.
+    pub fn new(mut pin: P, delay: D) -> OneWireResult<Self, P::Error> {
+        pin.set_high()?;
+        Ok(Self {
+            pin,
+            delay,
+            standard: Timing::STANDARD,
+            overdrive: Timing::OVERDRIVE,
+            od: false,
+        })
+    }
+
+    /// Sets the timing of the time slots at standard and overdrive speed, e.g. to account for the latency
+    /// of the pin accesses on a slow microcontroller.
+    pub fn with_timing(mut self, standard: Timing, overdrive: Timing) -> Self {
+        self.standard = standard;
+        self.overdrive = overdrive;
+        self
+    }
+
+    /// Release the pin and the delay.
+    pub fn release(self) -> (P, D) {
+        (self.pin, self.delay)
+    }
+
+    fn timing(&self) -> Timing {
+        if self.od {
+            self.overdrive
+        } else {
+            self.standard
+        }
+    }
+
+    /// Pull the line low for `low`, release it and wait for `high`.
+    fn pulse(&mut self, low: u32, high: u32) -> Result<(), P::Error> {
+        self.pin.set_low()?;
+        self.delay.delay_ns(low);
+        self.pin.set_high()?;
+        self.delay.delay_ns(high);
+        Ok(())
+    }
+
+    fn reset_at(&mut self, timing: Timing) -> OneWireResult<GpioStatus, P::Error> {
+        self.delay.delay_ns(timing.reset_delay);
+        if self.pin.is_low()? {
+            return Err(OneWireError::ShortCircuit); // the line is held low
+        }
+        self.pulse(timing.reset_low, timing.presence_sample)?;
+        let presence = self.pin.is_low()?;
+        self.delay.delay_ns(timing.reset_recovery);
+        if !presence {
+            return Err(OneWireError::NoDevicePresent);
+        }
+        Ok(GpioStatus { presence })
+    }
+}
+
+impl<P: InputPin + OutputPin, D: DelayNs> OneWire for GpioOneWire<P, D> {
+    type Status = GpioStatus;
+    type BusError = P::Error;
+
+    fn reset(&mut self) -> OneWireResult<GpioStatus, P::Error> {
+        self.reset_at(self.timing())
+    }
+
+    fn write_byte(&mut self, byte: u8) -> OneWireResult<(), P::Error> {
+        for bit in 0..8 {
+            self.write_bit(byte >> bit & 1 == 1)?;
+        }
+        Ok(())
+    }
+
+    fn read_byte(&mut self) -> OneWireResult<u8, P::Error> {
+        let mut byte = 0;
+        for bit in 0..8 {
+            byte |= (self.read_bit()? as u8) << bit;
+        }
+        Ok(byte)
+    }
+
+    fn write_bit(&mut self, bit: bool) -> OneWireResult<(), P::Error> {
+        let timing = self.timing();
+        if bit {
+            self.pulse(timing.write_one_low, timing.write_one_recovery)?;
+        } else {
+            self.pulse(timing.write_zero_low, timing.write_zero_recovery)?;
+        }
+        Ok(())
+    }
+
+    fn read_bit(&mut self) -> OneWireResult<bool, P::Error> {
+        let timing = self.timing();
+        self.pulse(timing.write_one_low, timing.read_sample)?;
+        let bit = self.pin.is_high()?;
+        self.delay.delay_ns(timing.read_recovery);
+        Ok(bit)
+    }
+
+    fn get_overdrive_mode(&mut self) -> bool {
+        self.od
+    }
+
+    /// Switch the bus speed.
+    ///
+    /// The devices are switched to overdrive speed with an overdrive skip ROM command sent at standard speed,
+    /// and back to standard speed with a standard speed reset. Devices without overdrive support stop taking
+    /// part in the communication until the bus returns to standard speed.
+    fn set_overdrive_mode(&mut self, enable: bool) -> OneWireResult<(), P::Error> {
+        if enable == self.od {
+            return Ok(());
+        }
+        let res = self.reset_at(self.standard);
+        if enable {
+            res?;
+            self.write_byte(OVERDRIVE_SKIP_ROM)?;
+        } else if let Err(e) = res
+            && !matches!(e, OneWireError::NoDevicePresent)
+        {
+            return Err(e);
+        }
+        self.od = enable;
+        Ok(())
+    }
+}
+
+mod test {
+    #[test]
+    fn test_reset_and_bytes() {
+        use super::{GpioOneWire, Timing};
+        use crate::sim::{Line, SimDelay, SimPin};
+        use core::cell::RefCell;
+        use ds28ea00::{Temperature, mock::*};
+        use embedded_onewire::{OneWire, OneWireError};
+        let mut devices = [MockDevice::new(0x42, 0x1234, Temperature::ZERO)];
+        let rom = devices[0].rom();
+        let line = RefCell::new(Line::new(MockBus::new(&mut devices)));
+        let mut bus = GpioOneWire::new(SimPin(&line), SimDelay(&line)).unwrap();
+        assert!(bus.reset().is_ok());
+        assert!(line.borrow().elapsed() >= 960_000);
+        // read ROM
+        bus.write_byte(0x33).unwrap();
+        let mut buf = [0; 8];
+        for b in buf.iter_mut() {
+            *b = bus.read_byte().unwrap();
+        }
+        assert_eq!(u64::from_le_bytes(buf), rom);
+        line.borrow_mut()
+            .bus()
+            .device_mut(rom)
+            .unwrap()
+            .set_present(false);
+        assert!(matches!(bus.reset(), Err(OneWireError::NoDevicePresent)));
+        line.borrow_mut()
+            .bus()
+            .device_mut(rom)
+            .unwrap()
+            .set_present(true);
+        line.borrow_mut().hold_low(true);
+        assert!(matches!(bus.reset(), Err(OneWireError::ShortCircuit)));
+        line.borrow_mut().hold_low(false);
+        // a reset shorter than the minimum of 480 us goes unnoticed by the devices
+        let mut bus = bus.with_timing(
+            Timing {
+                reset_low: 300_000,
+                ..Timing::STANDARD
+            },
+            Timing::OVERDRIVE,
+        );
+        assert!(matches!(bus.reset(), Err(OneWireError::NoDevicePresent)));
+    }
+
+    #[test]
+    fn test_group() {
+        use super::GpioOneWire;
+        use crate::sim::{Line, SimDelay, SimPin};
+        use core::cell::RefCell;
+        use ds28ea00::{Ds28ea00Group, Family, ReadOptions, Temperature, mock::*};
+        let mut devices = [
+            MockDevice::new(0x42, 0x1234, Temperature::from_millidegrees(21_500)),
+            MockDevice::new(0x42, 0x5678, Temperature::from_millidegrees(-10_125)),
+            MockDevice::new(0x28, 0x9abc, Temperature::from_millidegrees(30_500)),
+        ];
+        let roms = devices.map(|dev| dev.rom());
+        let line = RefCell::new(Line::new(MockBus::new(&mut devices)));
+        let mut bus = GpioOneWire::new(SimPin(&line), SimDelay(&line)).unwrap();
+        let mut group = Ds28ea00Group::<4>::default()
+            .with_families(&[Family::Ds28ea00, Family::Ds18b20])
+            .with_toggle_pio(false);
+        assert_eq!(group.enumerate(&mut bus).unwrap(), 3);
+        assert!(group.roms().all(|rom| roms.contains(&rom)));
+        for overdrive in [false, true] {
+            if overdrive {
+                group.enable_overdrive(&mut bus).unwrap();
+                assert!(line.borrow().overdrive());
+            }
+            let start = line.borrow().elapsed();
+            group.start_temperature_conversion(&mut bus).unwrap();
+            let temps = group
+                .read_temperatures(&mut bus, ReadOptions::new())
+                .unwrap();
+            for (rom, temp) in temps {
+                let expected = match roms.iter().position(|r| r == rom).unwrap() {
+                    0 => 21_500,
+                    1 => -10_125,
+                    _ => 30_500,
+                };
+                assert_eq!(temp.millidegrees(), expected);
+            }
+            let elapsed = line.borrow().elapsed() - start;
+            // 1 conversion and 3 readouts of 1 + 1 + 8 + 1 + 9 bytes of 70 us slots at standard speed
+            let slots = (2 + 3 * 19) * 8;
+            if overdrive {
+                assert!(elapsed < slots * 10_000 + 4 * 125_000);
+            } else {
+                assert!(elapsed > slots * 70_000);
+            }
+        }
+        group.disable_overdrive(&mut bus).unwrap();
+        assert!(!line.borrow().overdrive());
+        let mut line = line.into_inner();
+        line.finish();
+        assert_eq!(line.bus().conversions(), 2);
+    }
+}
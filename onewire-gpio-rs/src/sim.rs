@@ -0,0 +1,215 @@
+//! A simulated 1-Wire line, decoding the time slots generated on a pin into the calls of a
+//! [`MockBus`] of the `ds28ea00` crate.
+use core::{cell::RefCell, convert::Infallible};
+
+use ds28ea00::mock::MockBus;
+use embedded_hal::{
+    delay::DelayNs,
+    digital::{ErrorType, InputPin, OutputPin},
+};
+use embedded_onewire::OneWire;
+
+const US: u64 = 1000;
+
+/// Decoding of the bits of a transaction.
+enum Mode {
+    /// Bits are collected into bytes, the first of which is the ROM command.
+    Bytes { rom_command: bool },
+    /// Bits are passed through during a search, until `writes` direction bits are written.
+    Search { writes: u8 },
+}
+
+/// The state of the line, advanced by the pin and the delay.
+pub struct Line<'a> {
+    bus: MockBus<'a>,
+    now: u64,
+    /// Start of the pulse, while the master pulls the line low.
+    low_since: Option<u64>,
+    /// Start of a short slot that has not been sampled, which is a write one unless it is sampled.
+    short_slot: Option<u64>,
+    /// Interval during which the devices send their presence pulse.
+    presence: (u64, u64),
+    held_low: bool,
+    overdrive: bool,
+    mode: Mode,
+    /// Bits written and their count.
+    written: (u8, u8),
+    /// Bits left to read and their count.
+    read: (u8, u8),
+}
+
+impl<'a> Line<'a> {
+    pub fn new(bus: MockBus<'a>) -> Self {
+        Self {
+            bus,
+            now: 0,
+            low_since: None,
+            short_slot: None,
+            presence: (0, 0),
+            held_low: false,
+            overdrive: false,
+            mode: Mode::Bytes { rom_command: false },
+            written: (0, 0),
+            read: (0, 0),
+        }
+    }
+
+    /// The simulated bus.
+    pub fn bus(&mut self) -> &mut MockBus<'a> {
+        &mut self.bus
+    }
+
+    /// Time elapsed on the line, in nanoseconds.
+    pub fn elapsed(&self) -> u64 {
+        self.now
+    }
+
+    /// Whether the devices are at overdrive speed.
+    pub fn overdrive(&self) -> bool {
+        self.overdrive
+    }
+
+    /// Short the line to ground.
+    pub fn hold_low(&mut self, short: bool) {
+        self.held_low = short;
+    }
+
+    /// Decode the last slot, at the end of a transaction.
+    pub fn finish(&mut self) {
+        if self.short_slot.take().is_some() {
+            self.write_bit(true);
+        }
+    }
+
+    fn pull_low(&mut self) {
+        self.finish();
+        self.low_since = Some(self.now);
+    }
+
+    fn release(&mut self) {
+        let Some(start) = self.low_since.take() else {
+            return;
+        };
+        let pulse = self.now - start;
+        if pulse >= 480 * US {
+            self.overdrive = false;
+            self.reset();
+        } else if self.overdrive && pulse >= 48 * US {
+            self.reset();
+        } else if pulse >= if self.overdrive { 2 * US } else { 15 * US } {
+            self.write_bit(false);
+        } else {
+            self.short_slot = Some(start);
+        }
+    }
+
+    fn level(&mut self) -> bool {
+        if self.held_low || self.low_since.is_some() {
+            return false;
+        }
+        let window = if self.overdrive { 3 * US } else { 15 * US };
+        if let Some(start) = self.short_slot
+            && self.now - start <= window
+        {
+            self.short_slot = None;
+            return self.read_bit();
+        }
+        !(self.presence.0..self.presence.1).contains(&self.now)
+    }
+
+    fn reset(&mut self) {
+        self.short_slot = None;
+        self.mode = Mode::Bytes { rom_command: true };
+        self.written = (0, 0);
+        self.read = (0, 0);
+        self.presence = (0, 0);
+        if self.bus.reset().is_ok() {
+            self.presence = if self.overdrive {
+                (self.now + 2 * US, self.now + 10 * US)
+            } else {
+                (self.now + 15 * US, self.now + 75 * US)
+            };
+        }
+    }
+
+    fn write_bit(&mut self, bit: bool) {
+        match self.mode {
+            Mode::Search { writes } => {
+                self.bus.write_bit(bit).unwrap();
+                self.mode = if writes > 1 {
+                    Mode::Search { writes: writes - 1 }
+                } else {
+                    Mode::Bytes { rom_command: false }
+                };
+            }
+            Mode::Bytes { rom_command } => {
+                let (byte, count) = self.written;
+                let byte = byte | (bit as u8) << count;
+                if count < 7 {
+                    self.written = (byte, count + 1);
+                    return;
+                }
+                self.written = (0, 0);
+                self.bus.write_byte(byte).unwrap();
+                self.mode = Mode::Bytes { rom_command: false };
+                if rom_command {
+                    match byte {
+                        0xf0 | 0xec => self.mode = Mode::Search { writes: 64 },
+                        0x3c | 0x69 => self.overdrive = true,
+                        _ => {}
+                    }
+                }
+            }
+        }
+    }
+
+    fn read_bit(&mut self) -> bool {
+        if let Mode::Search { .. } = self.mode {
+            return self.bus.read_bit().unwrap();
+        }
+        let (mut byte, mut count) = self.read;
+        if count == 0 {
+            (byte, count) = (self.bus.read_byte().unwrap(), 8);
+        }
+        self.read = (byte >> 1, count - 1);
+        byte & 1 == 1
+    }
+}
+
+/// The pin of the master on a simulated [`Line`].
+pub struct SimPin<'l, 'a>(pub &'l RefCell<Line<'a>>);
+
+impl ErrorType for SimPin<'_, '_> {
+    type Error = Infallible;
+}
+
+impl OutputPin for SimPin<'_, '_> {
+    fn set_low(&mut self) -> Result<(), Infallible> {
+        self.0.borrow_mut().pull_low();
+        Ok(())
+    }
+
+    fn set_high(&mut self) -> Result<(), Infallible> {
+        self.0.borrow_mut().release();
+        Ok(())
+    }
+}
+
+impl InputPin for SimPin<'_, '_> {
+    fn is_high(&mut self) -> Result<bool, Infallible> {
+        Ok(self.0.borrow_mut().level())
+    }
+
+    fn is_low(&mut self) -> Result<bool, Infallible> {
+        self.is_high().map(|high| !high)
+    }
+}
+
+/// The delay of the master on a simulated [`Line`].
+pub struct SimDelay<'l, 'a>(pub &'l RefCell<Line<'a>>);
+
+impl DelayNs for SimDelay<'_, '_> {
+    fn delay_ns(&mut self, ns: u32) {
+        self.0.borrow_mut().now += ns as u64;
+    }
+}
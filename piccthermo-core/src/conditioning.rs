@@ -0,0 +1,152 @@
+//! Conditioning of the readings of humidity sensors, with quality flags for the readings that can
+//! not be trusted as they are.
+//!
+//! A [`Conditioner`] is kept for every sensor, and every reading of the sensor is passed through
+//! [`Conditioner::condition`]:
+//! - Readings beyond 0 %..100 % are clamped, so that the dew point derived from a reading never
+//!   exceeds its temperature, and flagged [`Quality::CLAMPED`].
+//! - The readings that follow a reset or a heater burn-off, marked with [`Conditioner::settle`],
+//!   are flagged [`Quality::SETTLING`], since the sensor takes a few readings to settle.
+//! - Readings that have not changed over several samples are flagged [`Quality::STUCK`], as the
+//!   sensor or its bus likely hangs.
+use core::ops::{BitOr, BitOrAssign};
+
+use fixed::types::I16F16;
+
+use crate::{RelativeHumidity, Temperature};
+
+/// Quality flags of a conditioned reading, see the [module documentation](self).
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(transparent))]
+pub struct Quality(u8);
+
+impl Quality {
+    /// No flags, the reading is good.
+    pub const GOOD: Self = Self(0);
+    /// The relative humidity was outside 0 %..100 % and was clamped.
+    pub const CLAMPED: Self = Self(1 << 0);
+    /// The reading was taken while the sensor settles after a reset or a heater burn-off.
+    pub const SETTLING: Self = Self(1 << 1);
+    /// The reading is identical to the previous ones.
+    pub const STUCK: Self = Self(1 << 2);
+
+    /// Creates the flags from their bits. Unknown bits are kept.
+    pub const fn from_bits(bits: u8) -> Self {
+        Self(bits)
+    }
+
+    /// Returns the bits of the flags.
+    pub const fn bits(self) -> u8 {
+        self.0
+    }
+
+    /// Returns `true` if no flag is set.
+    pub const fn is_good(self) -> bool {
+        self.0 == 0
+    }
+
+    /// Returns `true` if all flags of `other` are set.
+    pub const fn contains(self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+}
+
+impl BitOr for Quality {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self {
+        Self(self.0 | rhs.0)
+    }
+}
+
+impl BitOrAssign for Quality {
+    fn bitor_assign(&mut self, rhs: Self) {
+        self.0 |= rhs.0;
+    }
+}
+
+/// Conditioning state of the readings of a sensor, see the [module documentation](self).
+#[derive(Debug, Clone)]
+pub struct Conditioner {
+    clamp: bool,
+    settle_samples: u8,
+    stuck_samples: u8,
+    settling: u8,
+    last: Option<(Temperature, RelativeHumidity)>,
+    repeats: u8,
+}
+
+impl Default for Conditioner {
+    fn default() -> Self {
+        Self {
+            clamp: true,
+            settle_samples: 1,
+            stuck_samples: 0,
+            settling: 0,
+            last: None,
+            repeats: 0,
+        }
+    }
+}
+
+impl Conditioner {
+    /// Clamp the relative humidity to 0 %..100 %. Enabled by default.
+    pub fn with_clamp(mut self, clamp: bool) -> Self {
+        self.clamp = clamp;
+        self
+    }
+
+    /// Number of readings flagged after [`settle`](Self::settle). One by default.
+    pub fn with_settle_samples(mut self, samples: u8) -> Self {
+        self.settle_samples = samples;
+        self
+    }
+
+    /// Flag the readings once `samples` consecutive readings are identical, e.g. 10. Stuck readings
+    /// are not detected if zero, the default.
+    pub fn with_stuck_samples(mut self, samples: u8) -> Self {
+        self.stuck_samples = samples;
+        self
+    }
+
+    /// Flag the next readings as settling, after the sensor was reset or its heater turned off.
+    pub fn settle(&mut self) {
+        self.settling = self.settle_samples;
+        self.last = None;
+        self.repeats = 0;
+    }
+
+    /// Condition a reading of the sensor.
+    ///
+    /// # Returns
+    /// The conditioned relative humidity, and the quality flags of the reading.
+    pub fn condition(
+        &mut self,
+        temperature: Temperature,
+        humidity: RelativeHumidity,
+    ) -> (RelativeHumidity, Quality) {
+        let mut quality = Quality::GOOD;
+        if self.settling > 0 {
+            self.settling -= 1;
+            quality |= Quality::SETTLING;
+        }
+        if self.last == Some((temperature, humidity)) {
+            self.repeats = self.repeats.saturating_add(1);
+        } else {
+            self.last = Some((temperature, humidity));
+            self.repeats = 1;
+        }
+        if self.stuck_samples > 0 && self.repeats >= self.stuck_samples {
+            quality |= Quality::STUCK;
+        }
+        let fixed = humidity.to_fixed();
+        let clamped = fixed.clamp(I16F16::ZERO, I16F16::const_from_int(100));
+        if self.clamp && clamped != fixed {
+            quality |= Quality::CLAMPED;
+            return (RelativeHumidity::from_fixed(clamped), quality);
+        }
+        (humidity, quality)
+    }
+}
@@ -0,0 +1,291 @@
+//! Derived hygrometric quantities computed from paired temperature and relative humidity readings.
+//!
+//! Dew point and absolute humidity use the Magnus approximation of the saturation vapor pressure
+//! over water (Sonntag, 1990), and the heat index uses the NOAA Rothfusz regression with the
+//! Steadman approximation below 80 °F.
+//!
+//! Every quantity is available as a fixed-point function operating on [`Temperature`] and
+//! [`RelativeHumidity`], which does not require an FPU, and as an `f32` function operating on
+//! degrees Celsius and percent. In both variants, the temperature is clamped to -100 °C..150 °C and
+//! the relative humidity is clamped to 0 %..100 % before the computation.
+use fixed::types::I16F16;
+
+use crate::{RelativeHumidity, Temperature};
+
+/// Magnus coefficient `b` (dimensionless).
+const MAGNUS_B: f64 = 17.62;
+/// Magnus coefficient `c`, in °C.
+const MAGNUS_C: f64 = 243.12;
+/// Saturation vapor pressure at 0 °C, in hPa.
+const MAGNUS_P0: f64 = 6.112;
+/// Conversion factor from hPa / K to g / m³ for water vapor (100 * M_w / R).
+const VAPOR_DENSITY: f64 = 2.1674;
+/// 0 °C in Kelvin.
+const ZERO_CELSIUS: f64 = 273.15;
+
+const T_MIN: f64 = -100.0;
+const T_MAX: f64 = 150.0;
+const RH_MIN: f64 = 0.0;
+const RH_MAX: f64 = 100.0;
+
+/// Computes the dew point from a temperature and relative humidity reading.
+///
+/// Returns `None` if the relative humidity is zero, in which case the dew point is undefined.
+pub fn dew_point(temperature: Temperature, humidity: RelativeHumidity) -> Option<Temperature> {
+    let (t, rh) = clamp(temperature, humidity);
+    if rh <= 0 {
+        return None;
+    }
+    let gamma = ln(div(rh, q(100.0))) + magnus_exponent(t);
+    let td = div(mul(q(MAGNUS_C), gamma), q(MAGNUS_B) - gamma);
+    Some(Temperature::from_bits(to_i16f16_bits(td)))
+}
+
+/// Computes the absolute humidity, in grams of water vapor per cubic meter of air,
+/// from a temperature and relative humidity reading.
+pub fn absolute_humidity(temperature: Temperature, humidity: RelativeHumidity) -> I16F16 {
+    let (t, rh) = clamp(temperature, humidity);
+    let vapor = mul(
+        mul(q(MAGNUS_P0 * VAPOR_DENSITY), exp(magnus_exponent(t))),
+        rh,
+    );
+    I16F16::from_bits(to_i16f16_bits(div(vapor, q(ZERO_CELSIUS) + t)))
+}
+
+/// Computes the heat index (apparent temperature) from a temperature and relative humidity reading.
+pub fn heat_index(temperature: Temperature, humidity: RelativeHumidity) -> Temperature {
+    let (t, rh) = clamp(temperature, humidity);
+    let tf = mul(t, q(1.8)) + q(32.0);
+    let simple = mul(
+        q(0.5),
+        tf + q(61.0) + mul(tf - q(68.0), q(1.2)) + mul(rh, q(0.094)),
+    );
+    let hi = if (simple + tf) / 2 < q(80.0) {
+        simple
+    } else {
+        let t2 = mul(tf, tf);
+        let a = q(-42.379) + mul(q(2.04901523), tf) - mul(q(0.00683783), t2);
+        let b = q(10.14333127) - mul(q(0.22475541), tf) + mul(q(0.00122874), t2);
+        let c = q(-0.05481717) + mul(q(0.00085282), tf) - mul(q(0.00000199), t2);
+        let mut hi = a + mul(rh, b + mul(rh, c));
+        if rh < q(13.0) && tf > q(80.0) && tf < q(112.0) {
+            let spread = div(q(17.0) - (tf - q(95.0)).abs(), q(17.0));
+            hi -= mul(div(q(13.0) - rh, q(4.0)), sqrt(spread));
+        } else if rh > q(85.0) && tf > q(80.0) && tf < q(87.0) {
+            hi += mul(div(rh - q(85.0), q(10.0)), div(q(87.0) - tf, q(5.0)));
+        }
+        hi
+    };
+    Temperature::from_bits(to_i16f16_bits(div(hi - q(32.0), q(1.8))))
+}
+
+/// Computes the dew point, in °C, from a temperature in °C and a relative humidity in percent.
+///
+/// Returns `None` if the relative humidity is zero, in which case the dew point is undefined.
+pub fn dew_point_f32(temperature: f32, humidity: f32) -> Option<f32> {
+    let (t, rh) = clamp_f32(temperature, humidity);
+    if rh <= 0.0 {
+        return None;
+    }
+    let gamma = libm::logf(rh / 100.0) + magnus_exponent_f32(t);
+    Some(MAGNUS_C as f32 * gamma / (MAGNUS_B as f32 - gamma))
+}
+
+/// Computes the absolute humidity, in g/m³, from a temperature in °C and a relative humidity in percent.
+pub fn absolute_humidity_f32(temperature: f32, humidity: f32) -> f32 {
+    let (t, rh) = clamp_f32(temperature, humidity);
+    (MAGNUS_P0 * VAPOR_DENSITY) as f32 * libm::expf(magnus_exponent_f32(t)) * rh
+        / (ZERO_CELSIUS as f32 + t)
+}
+
+/// Computes the heat index, in °C, from a temperature in °C and a relative humidity in percent.
+pub fn heat_index_f32(temperature: f32, humidity: f32) -> f32 {
+    let (t, rh) = clamp_f32(temperature, humidity);
+    let tf = t * 1.8 + 32.0;
+    let simple = 0.5 * (tf + 61.0 + (tf - 68.0) * 1.2 + rh * 0.094);
+    let hi = if (simple + tf) / 2.0 < 80.0 {
+        simple
+    } else {
+        let mut hi = -42.379 + 2.049_015_2 * tf + 10.143_331 * rh
+            - 0.224_755_4 * tf * rh
+            - 0.006_837_83 * tf * tf
+            - 0.054_817_17 * rh * rh
+            + 0.001_228_74 * tf * tf * rh
+            + 0.000_852_82 * tf * rh * rh
+            - 0.000_001_99 * tf * tf * rh * rh;
+        if rh < 13.0 && tf > 80.0 && tf < 112.0 {
+            hi -= (13.0 - rh) / 4.0 * libm::sqrtf((17.0 - libm::fabsf(tf - 95.0)) / 17.0);
+        } else if rh > 85.0 && tf > 80.0 && tf < 87.0 {
+            hi += (rh - 85.0) / 10.0 * ((87.0 - tf) / 5.0);
+        }
+        hi
+    };
+    (hi - 32.0) / 1.8
+}
+
+fn magnus_exponent_f32(t: f32) -> f32 {
+    MAGNUS_B as f32 * t / (MAGNUS_C as f32 + t)
+}
+
+fn clamp_f32(temperature: f32, humidity: f32) -> (f32, f32) {
+    (
+        temperature.clamp(T_MIN as f32, T_MAX as f32),
+        humidity.clamp(RH_MIN as f32, RH_MAX as f32),
+    )
+}
+
+/// Fractional bits of the intermediate signed Q32.32 fixed-point format.
+const FRAC: u32 = 32;
+/// Natural logarithm of 2.
+const LN2: i64 = q(core::f64::consts::LN_2);
+
+const fn q(v: f64) -> i64 {
+    (v * (1u64 << FRAC) as f64) as i64
+}
+
+fn mul(a: i64, b: i64) -> i64 {
+    ((a as i128 * b as i128) >> FRAC) as i64
+}
+
+fn div(a: i64, b: i64) -> i64 {
+    (((a as i128) << FRAC) / b as i128) as i64
+}
+
+fn sqrt(x: i64) -> i64 {
+    (((x.max(0) as u128) << FRAC).isqrt()) as i64
+}
+
+/// Natural logarithm of a strictly positive value.
+fn ln(x: i64) -> i64 {
+    // x = m * 2^k with m in [1, 2)
+    let k = (63 - FRAC as i32) - x.leading_zeros() as i32;
+    let m = if k >= 0 { x >> k } else { x << -k };
+    // ln(m) = 2 * atanh(z) with z = (m - 1) / (m + 1) in [0, 1/3)
+    let z = div(m - q(1.0), m + q(1.0));
+    let z2 = mul(z, z);
+    let mut term = z;
+    let mut sum = 0;
+    for n in (1..=13).step_by(2) {
+        sum += term / n;
+        term = mul(term, z2);
+    }
+    k as i64 * LN2 + 2 * sum
+}
+
+/// Exponential function, saturating on overflow.
+fn exp(x: i64) -> i64 {
+    // x = k * ln(2) + r with r in [0, ln(2))
+    let k = x.div_euclid(LN2);
+    let r = x - k * LN2;
+    let mut term = q(1.0);
+    let mut sum = q(1.0);
+    for n in 1..=12 {
+        term = mul(term, r) / n;
+        sum += term;
+    }
+    match k {
+        ..=-63 => 0,
+        -62..0 => sum >> -k,
+        0..=29 => sum << k,
+        _ => i64::MAX,
+    }
+}
+
+fn magnus_exponent(t: i64) -> i64 {
+    div(mul(q(MAGNUS_B), t), q(MAGNUS_C) + t)
+}
+
+fn clamp(temperature: Temperature, humidity: RelativeHumidity) -> (i64, i64) {
+    (
+        ((temperature.to_bits() as i64) << 16).clamp(q(T_MIN), q(T_MAX)),
+        ((humidity.to_bits() as i64) << 16).clamp(q(RH_MIN), q(RH_MAX)),
+    )
+}
+
+fn to_i16f16_bits(x: i64) -> i32 {
+    ((x + (1 << 15)) >> 16).clamp(i32::MIN as i64, i32::MAX as i64) as i32
+}
+
+mod test {
+    #[test]
+    fn test_hygrometry() {
+        use super::{
+            absolute_humidity, absolute_humidity_f32, dew_point, dew_point_f32, heat_index,
+            heat_index_f32,
+        };
+        use crate::{RelativeHumidity, Temperature};
+        let close = |a: f32, b: f32, tol: f32| (a - b).abs() <= tol;
+        // reference values of the Magnus approximation and of the NOAA heat index
+        let dp = dew_point_f32(25.0, 50.0).unwrap();
+        assert!(close(dp, 13.85, 0.01), "{dp}");
+        assert!(close(absolute_humidity_f32(25.0, 50.0), 11.50, 0.05));
+        assert!(close(heat_index_f32(32.0, 70.0), 40.6, 0.5));
+        // the fixed-point variants follow the f32 ones
+        let table = [
+            (25.0, 50.0),
+            (25.0, 100.0),
+            (0.0, 80.0),
+            (-10.0, 60.0),
+            (-40.0, 5.0),
+            (35.0, 10.0),
+            (32.0, 70.0),
+            (29.0, 90.0),
+            (150.0, 100.0),
+            (-120.0, 30.0), // clamped to -100 °C
+        ];
+        for (t, rh) in table {
+            let (tq, rhq) = (
+                Temperature::from_celsius(t),
+                RelativeHumidity::from_percentage(rh),
+            );
+            let expected = dew_point_f32(t, rh).unwrap();
+            let dp = dew_point(tq, rhq).unwrap().celsius();
+            assert!(
+                close(dp, expected, 0.01),
+                "{t} °C {rh} %: {dp} != {expected}"
+            );
+            let expected = absolute_humidity_f32(t, rh);
+            let ah = absolute_humidity(tq, rhq).to_num::<f32>();
+            assert!(
+                close(ah, expected, 0.001 + expected * 1e-3),
+                "{t} °C {rh} %: {ah} != {expected}"
+            );
+            let expected = heat_index_f32(t, rh);
+            let hi = heat_index(tq, rhq).celsius();
+            assert!(
+                close(hi, expected, 0.01 + expected.abs() * 1e-4),
+                "{t} °C {rh} %: {hi} != {expected}"
+            );
+        }
+        // saturated air condenses at its own temperature
+        let dp = dew_point(
+            Temperature::from_celsius(-15.0),
+            RelativeHumidity::from_percentage(100.0),
+        );
+        assert!(close(dp.unwrap().celsius(), -15.0, 0.01));
+        // dry air has no dew point, and holds no water
+        assert_eq!(dew_point_f32(20.0, 0.0), None);
+        assert_eq!(
+            dew_point(
+                Temperature::from_celsius(20.0),
+                RelativeHumidity::from_percentage(0.0)
+            ),
+            None
+        );
+        assert_eq!(
+            dew_point(
+                Temperature::from_celsius(20.0),
+                RelativeHumidity::from_percentage(-5.0)
+            ),
+            None
+        );
+        assert_eq!(
+            absolute_humidity(
+                Temperature::from_celsius(20.0),
+                RelativeHumidity::from_percentage(0.0)
+            )
+            .to_num::<f32>(),
+            0.0
+        );
+    }
+}
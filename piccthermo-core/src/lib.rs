@@ -0,0 +1,14 @@
+#![no_std]
+#![deny(missing_docs)]
+//! # piccthermo-core
+//!
+//! A no-std crate of common types shared by the DS28EA00, HDC1010 and HDC3022 drivers.
+pub mod conditioning;
+pub mod hygrometry;
+mod sensor;
+#[cfg(feature = "sim")]
+pub mod sim;
+mod units;
+
+pub use sensor::{HumiditySensor, SensorDriver, TemperatureSensor};
+pub use units::{RelativeHumidity, Temperature};
@@ -0,0 +1,46 @@
+use core::time::Duration;
+
+use crate::{RelativeHumidity, Temperature};
+
+/// A sensor driver with a trigger, wait, then read measurement cycle.
+///
+/// The type parameter `B` is the bus the sensor is connected to, e.g. an I2C or 1-Wire bus,
+/// which is passed to every call so that several sensors can share a bus.
+pub trait SensorDriver<B> {
+    /// The error type returned by the driver.
+    type Error;
+
+    /// Start a measurement, without waiting for it to complete.
+    fn trigger(&mut self, bus: &mut B) -> Result<(), Self::Error>;
+
+    /// The time to wait after [`SensorDriver::trigger`] before the measurement can be read.
+    fn ready_after(&self) -> Duration;
+}
+
+/// A sensor that measures temperature on one or more channels.
+pub trait TemperatureSensor<B>: SensorDriver<B> {
+    /// Read the temperatures measured since the last trigger.
+    ///
+    /// `sink` is called with the channel ID and the temperature of every channel that was read
+    /// successfully. The channel ID uniquely identifies the channel on the bus, e.g. a 1-Wire ROM code.
+    fn read(
+        &mut self,
+        bus: &mut B,
+        sink: &mut dyn FnMut(u64, Temperature),
+    ) -> Result<(), Self::Error>;
+}
+
+/// A sensor that measures relative humidity, along with the temperature it was measured at,
+/// on one or more channels.
+pub trait HumiditySensor<B>: SensorDriver<B> {
+    /// Read the humidities measured since the last trigger.
+    ///
+    /// `sink` is called with the channel ID, the temperature and the relative humidity of every
+    /// channel that was read successfully. The channel ID uniquely identifies the channel on the bus,
+    /// e.g. an I2C address.
+    fn read(
+        &mut self,
+        bus: &mut B,
+        sink: &mut dyn FnMut(u64, Temperature, RelativeHumidity),
+    ) -> Result<(), Self::Error>;
+}
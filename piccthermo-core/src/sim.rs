@@ -0,0 +1,204 @@
+//! Synthetic sensors, to develop and test the acquisition of the readings without any hardware.
+//!
+//! A [`SimulatedSensor`] implements [`TemperatureSensor`] and [`HumiditySensor`] for any bus type,
+//! so it stands in for the drivers wherever they are used through these traits. The readings of
+//! every channel follow a random walk from the starting values, with noise added to every read,
+//! and channels may drop out of single reads. The readings are reproducible from the seed.
+use core::time::Duration;
+
+use crate::{HumiditySensor, RelativeHumidity, SensorDriver, Temperature, TemperatureSensor};
+
+/// Error returned by a [`SimulatedSensor`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SimError {
+    /// The sensor was read without triggering a measurement first.
+    NotTriggered,
+}
+
+/// Offsets of the random walks of a channel from the starting values.
+#[derive(Debug, Clone, Copy, Default)]
+struct Channel {
+    temperature: f32,
+    humidity: f32,
+}
+
+/// A synthetic sensor with up to `N` channels.
+///
+/// The channels are identified by consecutive IDs, starting at the ID set with
+/// [`with_first_id`](Self::with_first_id).
+#[derive(Debug, Clone)]
+pub struct SimulatedSensor<const N: usize> {
+    rng: u64,
+    channels: usize,
+    first_id: u64,
+    temperature: f32,
+    humidity: f32,
+    drift: f32,
+    noise: f32,
+    dropout: f32,
+    conversion_time: Duration,
+    triggered: bool,
+    state: [Channel; N],
+}
+
+impl<const N: usize> SimulatedSensor<N> {
+    /// Creates a sensor with `N` channels at 22 °C and 45 %RH, drifting by up to 0.05 per
+    /// measurement with a noise of up to 0.1, in degrees Celsius and percent, without dropouts.
+    ///
+    /// # Arguments
+    /// * `seed` - Seed of the random readings.
+    pub fn new(seed: u64) -> Self {
+        let mut sim = Self {
+            // xorshift does not leave the all-zero state
+            rng: seed | 1,
+            channels: N,
+            first_id: 0,
+            temperature: 22.0,
+            humidity: 45.0,
+            drift: 0.05,
+            noise: 0.1,
+            dropout: 0.0,
+            conversion_time: Duration::from_millis(10),
+            triggered: false,
+            state: [Channel::default(); N],
+        };
+        // spread the channels around the starting values
+        for idx in 0..N {
+            sim.state[idx] = Channel {
+                temperature: sim.uniform(0.5),
+                humidity: sim.uniform(2.0),
+            };
+        }
+        sim
+    }
+
+    /// Sets the number of channels, at most `N`.
+    pub fn with_channels(mut self, channels: usize) -> Self {
+        self.channels = channels.min(N);
+        self
+    }
+
+    /// Sets the ID of the first channel.
+    pub fn with_first_id(mut self, id: u64) -> Self {
+        self.first_id = id;
+        self
+    }
+
+    /// Sets the starting temperature, in degrees Celsius.
+    pub fn with_temperature(mut self, celsius: f32) -> Self {
+        self.temperature = celsius;
+        self
+    }
+
+    /// Sets the starting relative humidity, in percent.
+    pub fn with_humidity(mut self, percentage: f32) -> Self {
+        self.humidity = percentage;
+        self
+    }
+
+    /// Sets the largest step of the random walk of every channel per measurement.
+    pub fn with_drift(mut self, drift: f32) -> Self {
+        self.drift = drift.abs();
+        self
+    }
+
+    /// Sets the largest noise added to every reading.
+    pub fn with_noise(mut self, noise: f32) -> Self {
+        self.noise = noise.abs();
+        self
+    }
+
+    /// Sets the probability, between 0 and 1, that a channel is missing from a read.
+    pub fn with_dropout(mut self, probability: f32) -> Self {
+        self.dropout = probability.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Sets the time a measurement takes.
+    pub fn with_conversion_time(mut self, time: Duration) -> Self {
+        self.conversion_time = time;
+        self
+    }
+
+    /// IDs of the channels.
+    pub fn ids(&self) -> impl Iterator<Item = u64> {
+        (0..self.channels as u64).map(move |idx| self.first_id + idx)
+    }
+
+    /// Uniformly distributed value in `-amplitude..amplitude`, from a xorshift64* generator.
+    fn uniform(&mut self, amplitude: f32) -> f32 {
+        self.rng ^= self.rng >> 12;
+        self.rng ^= self.rng << 25;
+        self.rng ^= self.rng >> 27;
+        let bits = self.rng.wrapping_mul(0x2545_f491_4f6c_dd1d) >> 40;
+        (bits as f32 / (1u64 << 24) as f32 * 2.0 - 1.0) * amplitude
+    }
+
+    /// Calls `sink` with the readings of the channels that did not drop out.
+    fn read_channels(&mut self, sink: &mut dyn FnMut(u64, f32, f32)) -> Result<(), SimError> {
+        if !core::mem::take(&mut self.triggered) {
+            return Err(SimError::NotTriggered);
+        }
+        for idx in 0..self.channels {
+            if self.uniform(0.5) + 0.5 < self.dropout {
+                continue;
+            }
+            let channel = self.state[idx];
+            let temperature = self.temperature + channel.temperature + self.uniform(self.noise);
+            let humidity = self.humidity + channel.humidity + self.uniform(self.noise);
+            sink(
+                self.first_id + idx as u64,
+                temperature,
+                humidity.clamp(0.0, 100.0),
+            );
+        }
+        Ok(())
+    }
+}
+
+impl<const N: usize, B> SensorDriver<B> for SimulatedSensor<N> {
+    type Error = SimError;
+
+    fn trigger(&mut self, _bus: &mut B) -> Result<(), Self::Error> {
+        for idx in 0..self.channels {
+            let step = (self.uniform(self.drift), self.uniform(self.drift));
+            let channel = &mut self.state[idx];
+            channel.temperature += step.0;
+            channel.humidity += step.1;
+        }
+        self.triggered = true;
+        Ok(())
+    }
+
+    fn ready_after(&self) -> Duration {
+        self.conversion_time
+    }
+}
+
+impl<const N: usize, B> TemperatureSensor<B> for SimulatedSensor<N> {
+    fn read(
+        &mut self,
+        _bus: &mut B,
+        sink: &mut dyn FnMut(u64, Temperature),
+    ) -> Result<(), Self::Error> {
+        self.read_channels(&mut |id, temperature, _| {
+            sink(id, Temperature::from_celsius(temperature))
+        })
+    }
+}
+
+impl<const N: usize, B> HumiditySensor<B> for SimulatedSensor<N> {
+    fn read(
+        &mut self,
+        _bus: &mut B,
+        sink: &mut dyn FnMut(u64, Temperature, RelativeHumidity),
+    ) -> Result<(), Self::Error> {
+        self.read_channels(&mut |id, temperature, humidity| {
+            sink(
+                id,
+                Temperature::from_celsius(temperature),
+                RelativeHumidity::from_percentage(humidity),
+            )
+        })
+    }
+}
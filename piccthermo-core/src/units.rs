@@ -0,0 +1,177 @@
+use core::fmt;
+
+use fixed::types::{I12F4, I16F16};
+
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(transparent))]
+/// A temperature in degrees Celsius.
+///
+/// The temperature is stored as a fixed-point number with 16 integer and 16 fractional bits,
+/// which represents the readings of the DS28EA00 (1/16 °C steps) and the HDC1010 (165/65536 °C steps)
+/// exactly.
+pub struct Temperature(I16F16);
+
+impl Temperature {
+    /// Zero degrees Celsius.
+    pub const ZERO: Self = Self(I16F16::ZERO);
+
+    /// Creates a temperature from the raw bits of the underlying `I16F16` representation.
+    pub const fn from_bits(bits: i32) -> Self {
+        Self(I16F16::from_bits(bits))
+    }
+
+    /// Returns the raw bits of the underlying `I16F16` representation.
+    pub const fn to_bits(self) -> i32 {
+        self.0.to_bits()
+    }
+
+    /// Creates a temperature from a fixed-point value in degrees Celsius.
+    pub const fn from_fixed(celsius: I16F16) -> Self {
+        Self(celsius)
+    }
+
+    /// Returns the temperature in degrees Celsius as a fixed-point value.
+    pub const fn to_fixed(self) -> I16F16 {
+        self.0
+    }
+
+    /// Creates a temperature from a value in degrees Celsius.
+    pub fn from_celsius(celsius: f32) -> Self {
+        Self(I16F16::saturating_from_num(celsius))
+    }
+
+    /// Creates a temperature from a value in thousandths of a degree Celsius.
+    pub const fn from_millidegrees(millidegrees: i32) -> Self {
+        Self::from_bits(((millidegrees as i64 * 65536) / 1000) as i32)
+    }
+
+    /// Returns the temperature in degrees Celsius.
+    pub fn celsius(&self) -> f32 {
+        self.0.to_num()
+    }
+
+    /// Returns the temperature in degrees Fahrenheit.
+    pub fn fahrenheit(&self) -> f32 {
+        self.celsius() * 9.0 / 5.0 + 32.0
+    }
+
+    /// Returns the temperature in Kelvin.
+    pub fn kelvin(&self) -> f32 {
+        self.celsius() + 273.15
+    }
+
+    /// Returns the temperature in thousandths of a degree Celsius, rounded to the nearest integer.
+    pub const fn millidegrees(&self) -> i32 {
+        ((self.to_bits() as i64 * 1000 + 0x8000) >> 16) as i32
+    }
+
+    /// Returns the temperature in hundredths of a degree Celsius, rounded to the nearest integer.
+    ///
+    /// Like [`millidegrees`](Self::millidegrees), this uses integer arithmetic only, and is suited
+    /// to targets without a floating-point unit.
+    pub const fn centidegrees(&self) -> i32 {
+        ((self.to_bits() as i64 * 100 + 0x8000) >> 16) as i32
+    }
+}
+
+impl From<I12F4> for Temperature {
+    fn from(value: I12F4) -> Self {
+        Self::from_bits((value.to_bits() as i32) << 12)
+    }
+}
+
+impl From<I16F16> for Temperature {
+    fn from(value: I16F16) -> Self {
+        Self(value)
+    }
+}
+
+impl fmt::Display for Temperature {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match f.precision() {
+            Some(precision) => write!(f, "{:.*} °C", precision, self.celsius()),
+            None => write!(f, "{} °C", self.celsius()),
+        }
+    }
+}
+
+#[cfg(feature = "defmt")]
+impl defmt::Format for Temperature {
+    fn format(&self, f: defmt::Formatter) {
+        defmt::write!(f, "{=f32} °C", self.celsius());
+    }
+}
+
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(transparent))]
+/// A relative humidity in percent.
+///
+/// The humidity is stored as a fixed-point number with 16 integer and 16 fractional bits,
+/// which represents the readings of the HDC1010 (100/65536 % steps) exactly.
+pub struct RelativeHumidity(I16F16);
+
+impl RelativeHumidity {
+    /// Zero percent relative humidity.
+    pub const ZERO: Self = Self(I16F16::ZERO);
+
+    /// Creates a relative humidity from the raw bits of the underlying `I16F16` representation.
+    pub const fn from_bits(bits: i32) -> Self {
+        Self(I16F16::from_bits(bits))
+    }
+
+    /// Returns the raw bits of the underlying `I16F16` representation.
+    pub const fn to_bits(self) -> i32 {
+        self.0.to_bits()
+    }
+
+    /// Creates a relative humidity from a fixed-point value in percent.
+    pub const fn from_fixed(percentage: I16F16) -> Self {
+        Self(percentage)
+    }
+
+    /// Returns the relative humidity in percent as a fixed-point value.
+    pub const fn to_fixed(self) -> I16F16 {
+        self.0
+    }
+
+    /// Creates a relative humidity from a value in percent.
+    pub fn from_percentage(percentage: f32) -> Self {
+        Self(I16F16::saturating_from_num(percentage))
+    }
+
+    /// Returns the relative humidity in percent.
+    pub fn percentage(&self) -> f32 {
+        self.0.to_num()
+    }
+
+    /// Returns the relative humidity in hundredths of a percent, rounded to the nearest integer.
+    ///
+    /// This uses integer arithmetic only, and is suited to targets without a floating-point unit.
+    pub const fn centipercent(&self) -> i32 {
+        ((self.to_bits() as i64 * 100 + 0x8000) >> 16) as i32
+    }
+}
+
+impl From<I16F16> for RelativeHumidity {
+    fn from(value: I16F16) -> Self {
+        Self(value)
+    }
+}
+
+impl fmt::Display for RelativeHumidity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match f.precision() {
+            Some(precision) => write!(f, "{:.*} %RH", precision, self.percentage()),
+            None => write!(f, "{} %RH", self.percentage()),
+        }
+    }
+}
+
+#[cfg(feature = "defmt")]
+impl defmt::Format for RelativeHumidity {
+    fn format(&self, f: defmt::Formatter) {
+        defmt::write!(f, "{=f32} %RH", self.percentage());
+    }
+}
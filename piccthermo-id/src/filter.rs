@@ -0,0 +1,272 @@
+//! Filters selecting sensors by their ROM code, their ID or their label.
+use alloc::{string::String, vec::Vec};
+use core::{fmt, str::FromStr};
+
+use crate::SensorId;
+
+/// Pattern matching a sensor.
+///
+/// Patterns are parsed from:
+/// - 9 to 16 hexadecimal digits, with or without a `0x` prefix: a full 64-bit ROM code.
+/// - Up to 8 hexadecimal digits, with or without a `0x` prefix: a sensor ID.
+/// - `label:<glob>`, or any other text: a glob on the label of the sensor, where `*` matches any
+///   characters and `?` matches a single character. The `label:` prefix is needed for the labels
+///   that read as hexadecimal numbers.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Pattern {
+    /// Full ROM code of a 1-Wire sensor.
+    Rom(u64),
+    /// ID of a sensor.
+    Id(SensorId),
+    /// Glob on the label of a sensor.
+    Label(String),
+}
+
+/// Error returned when parsing a [`Pattern`] fails.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PatternError {
+    /// The pattern is empty.
+    Empty,
+    /// The pattern has a `0x` prefix, but is not a hexadecimal number of up to 16 digits.
+    InvalidHex,
+}
+
+impl fmt::Display for PatternError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PatternError::Empty => write!(f, "empty pattern"),
+            PatternError::InvalidHex => write!(f, "invalid ROM code or sensor ID"),
+        }
+    }
+}
+
+impl Pattern {
+    /// Check whether the pattern matches a sensor.
+    ///
+    /// # Arguments
+    /// * `id` - ID of the sensor.
+    /// * `rom` - ROM code of the sensor, if it is a 1-Wire sensor.
+    /// * `label` - Label of the sensor, if it has one.
+    pub fn matches(&self, id: SensorId, rom: Option<u64>, label: Option<&str>) -> bool {
+        match self {
+            Pattern::Rom(pattern) => rom == Some(*pattern),
+            Pattern::Id(pattern) => id == *pattern,
+            Pattern::Label(pattern) => label.is_some_and(|label| glob(pattern, label)),
+        }
+    }
+}
+
+impl FromStr for Pattern {
+    type Err = PatternError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if let Some(label) = s.strip_prefix("label:") {
+            return match label.trim() {
+                "" => Err(PatternError::Empty),
+                label => Ok(Pattern::Label(label.into())),
+            };
+        }
+        let hex = s.strip_prefix("0x").or_else(|| s.strip_prefix("0X"));
+        let digits = hex.unwrap_or(s);
+        if !digits.is_empty() && digits.len() <= 16 && digits.bytes().all(|b| b.is_ascii_hexdigit())
+        {
+            let value = u64::from_str_radix(digits, 16).map_err(|_| PatternError::InvalidHex)?;
+            return Ok(if digits.len() > 8 {
+                Pattern::Rom(value)
+            } else {
+                Pattern::Id(SensorId::new(value as u32))
+            });
+        }
+        match (hex, s) {
+            (Some(_), _) => Err(PatternError::InvalidHex),
+            (None, "") => Err(PatternError::Empty),
+            (None, label) => Ok(Pattern::Label(label.into())),
+        }
+    }
+}
+
+impl fmt::Display for Pattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Pattern::Rom(rom) => write!(f, "0x{rom:016x}"),
+            Pattern::Id(id) => write!(f, "0x{id}"),
+            Pattern::Label(label) => write!(f, "label:{label}"),
+        }
+    }
+}
+
+/// Selection of sensors, from the patterns of the sensors to include and to exclude.
+///
+/// A sensor is selected if it matches one of the include patterns, or if there are none, and none
+/// of the exclude patterns.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SensorFilter {
+    include: Vec<Pattern>,
+    exclude: Vec<Pattern>,
+}
+
+impl SensorFilter {
+    /// Creates a filter selecting every sensor.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Selects only the sensors matching one of `patterns`.
+    pub fn with_include_only(mut self, patterns: Vec<Pattern>) -> Self {
+        self.include = patterns;
+        self
+    }
+
+    /// Leaves out the sensors matching one of `patterns`.
+    pub fn with_exclude(mut self, patterns: Vec<Pattern>) -> Self {
+        self.exclude = patterns;
+        self
+    }
+
+    /// Replaces the exclude patterns.
+    pub fn set_exclude(&mut self, patterns: Vec<Pattern>) {
+        self.exclude = patterns;
+    }
+
+    /// Patterns of the sensors to include, or an empty slice if all sensors are included.
+    pub fn include(&self) -> &[Pattern] {
+        &self.include
+    }
+
+    /// Patterns of the sensors to exclude.
+    pub fn exclude(&self) -> &[Pattern] {
+        &self.exclude
+    }
+
+    /// Check whether the filter selects every sensor.
+    pub fn is_empty(&self) -> bool {
+        self.include.is_empty() && self.exclude.is_empty()
+    }
+
+    /// Check whether a sensor is selected.
+    ///
+    /// # Arguments
+    /// * `id` - ID of the sensor.
+    /// * `rom` - ROM code of the sensor, if it is a 1-Wire sensor.
+    /// * `label` - Label of the sensor, if it has one.
+    pub fn selects(&self, id: SensorId, rom: Option<u64>, label: Option<&str>) -> bool {
+        let matches = |pattern: &Pattern| pattern.matches(id, rom, label);
+        (self.include.is_empty() || self.include.iter().any(matches))
+            && !self.exclude.iter().any(matches)
+    }
+
+    /// Check whether a 1-Wire sensor is selected, from its ROM code.
+    pub fn selects_rom(&self, rom: u64, label: Option<&str>) -> bool {
+        self.selects(SensorId::from_rom(rom), Some(rom), label)
+    }
+}
+
+/// Parse a comma separated list of patterns, skipping the empty items.
+pub fn parse_patterns(list: &str) -> Result<Vec<Pattern>, PatternError> {
+    list.split(',')
+        .filter(|item| !item.trim().is_empty())
+        .map(str::parse)
+        .collect()
+}
+
+/// Match `text` against a glob, where `*` matches any characters and `?` a single character.
+pub fn glob(pattern: &str, text: &str) -> bool {
+    let pattern = pattern.chars().collect::<Vec<_>>();
+    let text = text.chars().collect::<Vec<_>>();
+    let (mut p, mut t) = (0, 0);
+    // position of the last `*` in the pattern, and of the text it was matched up to
+    let mut star = None;
+    while t < text.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                star = Some((p, t));
+                p += 1;
+            }
+            Some(c) if *c == '?' || *c == text[t] => {
+                p += 1;
+                t += 1;
+            }
+            _ => match star {
+                // let the last `*` match one more character
+                Some((sp, st)) => {
+                    star = Some((sp, st + 1));
+                    p = sp + 1;
+                    t = st + 1;
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+mod test {
+    #[test]
+    fn test_pattern() {
+        use super::{Pattern, PatternError};
+        use crate::SensorId;
+        assert_eq!(
+            "0xdeadbeef".parse(),
+            Ok(Pattern::Id(SensorId::new(0xdeadbeef)))
+        );
+        assert_eq!(" 1a2b ".parse(), Ok(Pattern::Id(SensorId::new(0x1a2b))));
+        assert_eq!(
+            "5e00000012345642".parse(),
+            Ok(Pattern::Rom(0x5e00_0000_1234_5642))
+        );
+        assert_eq!("chamber-*".parse(), Ok(Pattern::Label("chamber-*".into())));
+        assert_eq!("label:cafe".parse(), Ok(Pattern::Label("cafe".into())));
+        assert_eq!(
+            "0xchamber".parse::<Pattern>(),
+            Err(PatternError::InvalidHex)
+        );
+        assert_eq!(
+            "0x5e0000001234564200".parse::<Pattern>(),
+            Err(PatternError::InvalidHex)
+        );
+        assert_eq!("label: ".parse::<Pattern>(), Err(PatternError::Empty));
+        // patterns are displayed as they are parsed
+        for pattern in ["0x5e00000012345642", "0x00001a2b", "label:cafe"] {
+            extern crate std;
+            use std::string::ToString;
+            assert_eq!(pattern.parse::<Pattern>().unwrap().to_string(), pattern);
+        }
+    }
+
+    #[test]
+    fn test_filter() {
+        use super::{Pattern, SensorFilter, glob, parse_patterns};
+        use crate::SensorId;
+        extern crate std;
+        use std::vec;
+        assert!(glob("chamber-*", "chamber-top"));
+        assert!(glob("*-t?p", "chamber-top"));
+        assert!(glob("*", ""));
+        assert!(glob("a*b*c", "aXbYbZc"));
+        assert!(!glob("chamber-?", "chamber-top"));
+        assert!(!glob("*-top", "chamber-bottom"));
+        let rom = 0x5e00_0000_1234_5642;
+        let id = SensorId::from_rom(rom);
+        let filter = SensorFilter::new();
+        assert!(filter.is_empty() && filter.selects_rom(rom, None));
+        let filter =
+            SensorFilter::new().with_exclude(parse_patterns("0x5e00000012345642,").unwrap());
+        assert!(!filter.selects_rom(rom, None));
+        // the ROM code of the I2C sensors is not known
+        assert!(filter.selects(id, None, None));
+        let filter = SensorFilter::new().with_exclude(vec![Pattern::Id(id)]);
+        assert!(!filter.selects_rom(rom, None));
+        // only the included sensors that are not excluded are selected
+        let mut filter =
+            SensorFilter::new().with_include_only(parse_patterns("chamber-*, 0x42").unwrap());
+        assert!(filter.selects_rom(rom, Some("chamber-top")));
+        assert!(!filter.selects_rom(rom, Some("oven")));
+        assert!(!filter.selects_rom(rom, None));
+        assert!(filter.selects(SensorId::new(0x42), None, None));
+        filter.set_exclude(parse_patterns("*-top").unwrap());
+        assert!(!filter.selects_rom(rom, Some("chamber-top")));
+        assert!(filter.selects_rom(rom, Some("chamber-bottom")));
+        assert!(parse_patterns("0x42,0xnope").is_err());
+    }
+}
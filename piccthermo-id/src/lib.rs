@@ -0,0 +1,143 @@
+#![no_std]
+#![deny(missing_docs)]
+//! # piccthermo-id
+//!
+//! A no-std crate of the 32-bit sensor IDs published by thermo-server, and used by thermo-ident
+//! and thermo-tester to refer to the same sensors, e.g. in sensor maps and exclusion filters.
+//!
+//! The IDs are displayed as 8 hexadecimal digits, and parsed from hexadecimal with or without a
+//! `0x` prefix. Sensors are selected with a [`SensorFilter`], from patterns matching their ROM
+//! code, their ID or their label.
+extern crate alloc;
+
+mod filter;
+
+use core::{fmt, num::ParseIntError, str::FromStr};
+
+pub use filter::{Pattern, PatternError, SensorFilter, glob, parse_patterns};
+
+/// ID of a sensor.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct SensorId(u32);
+
+impl SensorId {
+    /// Flag of the ID of the temperature channel of an I2C humidity sensor.
+    pub const TEMPERATURE_CHANNEL: u32 = 0x8000_0000;
+
+    /// ID from its raw value.
+    pub const fn new(id: u32) -> Self {
+        Self(id)
+    }
+
+    /// ID of a 1-Wire sensor from its ROM code: the CRC32 hash of the serial number, without the
+    /// CRC and the family code bytes.
+    pub fn from_rom(rom: u64) -> Self {
+        Self(crc32fast::hash(
+            &((rom & 0x00ffffff_ffffffff) >> 8).to_le_bytes(),
+        ))
+    }
+
+    /// ID of an I2C sensor: its address in the least significant byte, and the CRC32 hash of the
+    /// name of its bus in the bits above, so that sensors at the same address on different buses
+    /// have different IDs.
+    ///
+    /// # Arguments
+    /// * `bus` - Name of the bus, e.g. `i2c-1`.
+    /// * `addr` - 7-bit address of the sensor.
+    /// * `temperature` - Whether the ID is that of the temperature channel of a humidity sensor,
+    ///   flagged with [`TEMPERATURE_CHANNEL`](Self::TEMPERATURE_CHANNEL).
+    pub fn from_i2c(bus: &str, addr: u8, temperature: bool) -> Self {
+        let bus = (crc32fast::hash(bus.as_bytes()) << 8) & !Self::TEMPERATURE_CHANNEL;
+        let id = bus | addr as u32;
+        if temperature {
+            Self(id | Self::TEMPERATURE_CHANNEL)
+        } else {
+            Self(id)
+        }
+    }
+
+    /// ID of a sensor known by its label only, e.g. a CPU thermal zone: the CRC32 hash of the label.
+    pub fn from_label(label: &str) -> Self {
+        Self(crc32fast::hash(label.as_bytes()))
+    }
+
+    /// Raw value of the ID.
+    pub const fn value(self) -> u32 {
+        self.0
+    }
+}
+
+impl From<u32> for SensorId {
+    fn from(id: u32) -> Self {
+        Self(id)
+    }
+}
+
+impl From<SensorId> for u32 {
+    fn from(id: SensorId) -> Self {
+        id.0
+    }
+}
+
+impl fmt::Display for SensorId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:08x}", self.0)
+    }
+}
+
+impl FromStr for SensorId {
+    type Err = ParseIntError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let s = s
+            .strip_prefix("0x")
+            .or_else(|| s.strip_prefix("0X"))
+            .unwrap_or(s);
+        u32::from_str_radix(s, 16).map(Self)
+    }
+}
+
+mod test {
+    #[test]
+    fn test_sensor_id() {
+        use super::SensorId;
+        // the family code and the CRC are not part of the ID
+        let rom = 0x5e00_0000_1234_5642;
+        assert_eq!(
+            SensorId::from_rom(rom),
+            SensorId::from_rom(rom & 0x00ff_ffff_ffff_ff00)
+        );
+        assert_ne!(SensorId::from_rom(rom), SensorId::from_rom(rom + 0x100));
+        // the address and the bus are both part of the ID
+        let id = SensorId::from_i2c("i2c-1", 0x44, false).value();
+        assert_eq!(id & 0xff, 0x44);
+        assert_eq!(id & SensorId::TEMPERATURE_CHANNEL, 0);
+        assert_eq!(
+            SensorId::from_i2c("i2c-1", 0x44, true).value(),
+            id | SensorId::TEMPERATURE_CHANNEL
+        );
+        assert_ne!(SensorId::from_i2c("i2c-2", 0x44, false).value(), id);
+        assert_ne!(SensorId::from_i2c("i2c-1", 0x45, false).value(), id);
+        assert_eq!(
+            SensorId::from_label("cpu_thermal"),
+            SensorId::new(crc32fast::hash(b"cpu_thermal"))
+        );
+    }
+
+    #[test]
+    fn test_parse() {
+        use super::SensorId;
+        extern crate std;
+        use std::{format, string::ToString};
+        let id = SensorId::new(0xdeadbeef);
+        assert_eq!(id.to_string(), "deadbeef");
+        assert_eq!(format!("{}", SensorId::new(0x42)), "00000042");
+        assert_eq!(" 0xdeadbeef ".parse(), Ok(id));
+        assert_eq!("DEADBEEF".parse(), Ok(id));
+        assert_eq!("42".parse(), Ok(SensorId::new(0x42)));
+        assert!("0x".parse::<SensorId>().is_err());
+        assert!("deadbeefa".parse::<SensorId>().is_err());
+        assert!("sensor".parse::<SensorId>().is_err());
+    }
+}
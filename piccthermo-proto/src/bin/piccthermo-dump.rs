@@ -0,0 +1,192 @@
+use std::{
+    fs::{File, OpenOptions},
+    io::{self, BufWriter, Write},
+    net::{Ipv4Addr, Ipv6Addr, SocketAddr, TcpStream, ToSocketAddrs, UdpSocket},
+    path::PathBuf,
+    time::Duration,
+};
+
+use clap::{Parser, ValueEnum};
+use piccthermo_proto::{
+    data_format::Measurement,
+    stream::{Reader, StreamError, frames},
+};
+
+/// Tail the measurement stream of a thermo-server, and print or record its frames
+#[derive(Parser, Debug)]
+#[command(version, about, long_about = None)]
+struct Args {
+    /// Serial port (e.g. /dev/ttyUSB0), tcp://HOST:PORT of a TCP sink, udp://ADDR:PORT to receive
+    /// the datagrams of a UDP sink, joining ADDR if it is a multicast group, or unix://PATH of a
+    /// UNIX socket sink in the binary format. IPv6 addresses are written in brackets, e.g.
+    /// `tcp://[fd00::1]:9000`
+    source: String,
+    /// Baud rate of the serial port
+    #[arg(short, long, default_value_t = 115200)]
+    baud: u32,
+    /// Output format
+    #[arg(short, long, value_enum, default_value_t = OutputFormat::Human)]
+    format: OutputFormat,
+    /// Append the frames to a file, one JSON object per line
+    #[arg(short, long)]
+    record: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum OutputFormat {
+    /// One line per record
+    Human,
+    /// One JSON object per frame and line
+    Json,
+    /// Nothing, e.g. to only record the frames
+    Quiet,
+}
+
+fn main() {
+    let args = Args::parse();
+    let mut record = match &args.record {
+        Some(path) => match OpenOptions::new().create(true).append(true).open(path) {
+            Ok(file) => Some(BufWriter::new(file)),
+            Err(e) => {
+                eprintln!("Failed to open {}: {e}", path.display());
+                std::process::exit(1);
+            }
+        },
+        None => None,
+    };
+    let mut output = |res: Result<Measurement, StreamError>| {
+        let measurement = match res {
+            Ok(measurement) => measurement,
+            Err(e) => {
+                eprintln!("{e}");
+                return;
+            }
+        };
+        print(&measurement, args.format);
+        if let Some(file) = record.as_mut()
+            && let Err(e) = write_record(file, &measurement)
+        {
+            eprintln!("Failed to record frame: {e}");
+        }
+    };
+    let res = if let Some(addr) = args.source.strip_prefix("tcp://") {
+        resolve(addr).and_then(|addr| {
+            let stream = TcpStream::connect(addr)?;
+            eprintln!("Connected to {addr}");
+            Reader::new(stream).for_each(&mut output);
+            Ok(())
+        })
+    } else if let Some(path) = args.source.strip_prefix("unix://") {
+        connect_unix(path).map(|stream| {
+            eprintln!("Connected to {path}");
+            Reader::new(stream).for_each(&mut output);
+        })
+    } else if let Some(addr) = args.source.strip_prefix("udp://") {
+        resolve(addr).and_then(|addr| {
+            let socket = bind(addr)?;
+            eprintln!("Listening on {addr}");
+            let mut buf = vec![0; u16::MAX as usize];
+            loop {
+                let (len, _) = socket.recv_from(&mut buf)?;
+                frames(&buf[..len])
+                    .map(|res| res.map_err(StreamError::Frame))
+                    .for_each(&mut output);
+            }
+        })
+    } else {
+        serialport::new(&args.source, args.baud)
+            .timeout(Duration::from_secs(1))
+            .open()
+            .map_err(io::Error::from)
+            .map(|port| {
+                eprintln!("Opened {} at {} baud", args.source, args.baud);
+                Reader::new(port).for_each(&mut output);
+            })
+    };
+    if let Err(e) = res {
+        eprintln!("{}: {e}", args.source);
+        std::process::exit(1);
+    }
+}
+
+/// Resolve a `HOST:PORT` address, e.g. `localhost:9000` or `[fd00::1]:9000`.
+fn resolve(addr: &str) -> io::Result<SocketAddr> {
+    addr.to_socket_addrs()?.next().ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("no address for {addr}"),
+        )
+    })
+}
+
+#[cfg(unix)]
+fn connect_unix(path: &str) -> io::Result<std::os::unix::net::UnixStream> {
+    std::os::unix::net::UnixStream::connect(path)
+}
+
+#[cfg(not(unix))]
+fn connect_unix(_path: &str) -> io::Result<std::fs::File> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "UNIX sockets are not supported on this platform",
+    ))
+}
+
+/// Bind a UDP socket receiving the datagrams sent to `addr`, joining it if it is a multicast group.
+fn bind(addr: SocketAddr) -> io::Result<UdpSocket> {
+    if !addr.ip().is_multicast() {
+        return UdpSocket::bind(addr);
+    }
+    match addr {
+        SocketAddr::V4(group) => {
+            let socket = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, group.port()))?;
+            socket.join_multicast_v4(group.ip(), &Ipv4Addr::UNSPECIFIED)?;
+            Ok(socket)
+        }
+        SocketAddr::V6(group) => {
+            let socket = UdpSocket::bind((Ipv6Addr::UNSPECIFIED, group.port()))?;
+            socket.join_multicast_v6(group.ip(), group.scope_id())?;
+            Ok(socket)
+        }
+    }
+}
+
+fn print(measurement: &Measurement, format: OutputFormat) {
+    match format {
+        OutputFormat::Human => {
+            for record in measurement.records() {
+                let mut line = format!(
+                    "{} #{} {} {:08x}",
+                    record.timestamp, record.sequence, record.kind, record.id
+                );
+                if let Some(value) = record.value {
+                    line += &format!(" {value}");
+                }
+                if let Some((label, location)) = record.label {
+                    line += &format!(" {label} @ {location}");
+                }
+                if let Some(text) = record.text {
+                    line += &format!(" {text}");
+                }
+                if let Some((consecutive, total)) = record.failures {
+                    line += &format!(" failures {consecutive}/{total}");
+                }
+                if let Some((uptime, counter)) = record.sync {
+                    line += &format!(" uptime {uptime} ms, sync {counter}");
+                }
+                println!("{line}");
+            }
+        }
+        OutputFormat::Json => match serde_json::to_string(measurement) {
+            Ok(json) => println!("{json}"),
+            Err(e) => eprintln!("Failed to serialize frame: {e}"),
+        },
+        OutputFormat::Quiet => {}
+    }
+}
+
+fn write_record(file: &mut BufWriter<File>, measurement: &Measurement) -> io::Result<()> {
+    serde_json::to_writer(&mut *file, measurement)?;
+    writeln!(file)?;
+    file.flush()
+}
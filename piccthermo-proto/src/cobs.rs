@@ -0,0 +1,129 @@
+//! Consistent Overhead Byte Stuffing (COBS) of the frames sent over the serial link.
+//!
+//! Every frame is encoded so that it contains no zero bytes, and is terminated by a zero byte.
+//! A receiver that joins mid-stream, or loses bytes, resynchronizes at the next zero byte.
+
+/// Delimiter between two encoded frames.
+pub const DELIMITER: u8 = 0;
+
+/// Encode `data`, and append the [`DELIMITER`].
+pub fn encode(data: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(data.len() + data.len() / 254 + 2);
+    let mut code_idx = 0;
+    out.push(0); // placeholder for the first code byte
+    for &byte in data {
+        if byte != 0 {
+            out.push(byte);
+        }
+        if byte == 0 || out.len() - code_idx == 0xff {
+            out[code_idx] = (out.len() - code_idx) as u8;
+            code_idx = out.len();
+            out.push(0);
+        }
+    }
+    out[code_idx] = (out.len() - code_idx) as u8;
+    out.push(DELIMITER);
+    out
+}
+
+/// Errors encountered while decoding a COBS frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DecodeError {
+    /// The frame contains a zero byte.
+    UnexpectedZero,
+    /// A code byte points past the end of the frame.
+    Truncated,
+}
+
+/// Decode a single frame, without the [`DELIMITER`].
+pub fn decode(data: &[u8]) -> Result<Vec<u8>, DecodeError> {
+    let mut out = Vec::with_capacity(data.len());
+    let mut idx = 0;
+    while idx < data.len() {
+        let code = data[idx] as usize;
+        if code == 0 {
+            return Err(DecodeError::UnexpectedZero);
+        }
+        let end = idx + code;
+        if end > data.len() {
+            return Err(DecodeError::Truncated);
+        }
+        let block = &data[idx + 1..end];
+        if block.contains(&0) {
+            return Err(DecodeError::UnexpectedZero);
+        }
+        out.extend_from_slice(block);
+        idx = end;
+        if code != 0xff && idx < data.len() {
+            out.push(0);
+        }
+    }
+    Ok(out)
+}
+
+/// Streaming decoder that splits a byte stream at the [`DELIMITER`].
+#[derive(Debug, Default)]
+pub struct Decoder {
+    buf: Vec<u8>,
+}
+
+impl Decoder {
+    /// Feed bytes received from the link.
+    ///
+    /// # Returns
+    /// The decoded frames completed by `data`. Empty frames, e.g. from consecutive delimiters,
+    /// are skipped.
+    pub fn feed(&mut self, data: &[u8]) -> Vec<Result<Vec<u8>, DecodeError>> {
+        let mut frames = Vec::new();
+        for &byte in data {
+            if byte == DELIMITER {
+                if !self.buf.is_empty() {
+                    frames.push(decode(&self.buf));
+                    self.buf.clear();
+                }
+            } else {
+                self.buf.push(byte);
+            }
+        }
+        frames
+    }
+}
+
+mod test {
+    #[test]
+    fn test_roundtrip() {
+        use super::{DELIMITER, decode, encode};
+        let long = (0..=255u8).cycle().take(1000).collect::<Vec<_>>();
+        let nonzero = vec![1u8; 600];
+        for data in [
+            &[][..],
+            &[0],
+            &[0, 0],
+            &[1, 2, 3],
+            &[1, 0, 2, 0],
+            &long,
+            &nonzero,
+        ] {
+            let encoded = encode(data);
+            assert_eq!(encoded.last(), Some(&DELIMITER));
+            assert!(!encoded[..encoded.len() - 1].contains(&DELIMITER));
+            assert_eq!(decode(&encoded[..encoded.len() - 1]).unwrap(), data);
+        }
+    }
+
+    #[test]
+    fn test_resync() {
+        use super::{Decoder, encode};
+        let mut stream = vec![0x11, 0x22]; // tail of a frame that was lost
+        stream.push(super::DELIMITER);
+        stream.extend_from_slice(&encode(&[1, 0, 2]));
+        stream.extend_from_slice(&encode(&[3]));
+        let mut decoder = Decoder::default();
+        let (head, tail) = stream.split_at(5);
+        let mut frames = decoder.feed(head);
+        frames.extend(decoder.feed(tail));
+        assert_eq!(frames.len(), 3);
+        assert_eq!(frames[1], Ok(vec![1, 0, 2]));
+        assert_eq!(frames[2], Ok(vec![3]));
+    }
+}
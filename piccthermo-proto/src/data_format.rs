@@ -0,0 +1,767 @@
+use std::sync::atomic::{AtomicU32, Ordering};
+
+/// Sequence number of the next record, shared by all backends.
+static SEQUENCE: AtomicU32 = AtomicU32::new(0);
+
+/// Magic number at the start of every frame.
+pub const FRAME_MAGIC: [u8; 4] = *b"CHRS";
+/// Version of the frame format.
+pub const FRAME_VERSION: u8 = 1;
+/// Size of the frame header: magic, version, payload length and measurement type.
+const HEADER_LEN: usize = 8;
+/// Size of the CRC32 trailer.
+const CRC_LEN: usize = 4;
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Readings {
+    Temperature(Vec<(u32, f32)>),
+    Humidity(Vec<(u32, f32)>),
+    DewPoint(Vec<(u32, f32)>),
+    /// Humidity sensors that observed a supply brown-out since the last readout.
+    Brownout(Vec<u32>),
+    /// Humidity sensors that completed a heater burn-off cycle, whose readings are published again.
+    BurnOff(Vec<u32>),
+    /// Labels and locations of the sensors in the preceding measurement.
+    Labels(Vec<(u32, String, String)>),
+    /// Response to a command received over the serial link, e.g. `ACK i2c-1 rate 500 ms`.
+    Response(String),
+    /// Bus whose acquisition thread was restarted by the watchdog, e.g. `i2c-1`.
+    Restart(String),
+    /// Inventory of a bus, sent whenever its sensors are enumerated, or the settings of the serial
+    /// port, sent whenever it is opened.
+    Metadata(Metadata),
+    /// Failure counters of the sensors of a bus, sent periodically.
+    Health(Vec<SensorHealth>),
+    /// Time reference, sent periodically and on request, to map the sequence numbers to UTC.
+    Sync(SyncMarker),
+    /// Temperature sensors changing faster than the configured limit, with their rate of change in
+    /// °C per minute, sent once when the limit is exceeded.
+    RateAlarm(Vec<(u32, f32)>),
+    /// Last frame of the stream, sent once the queued measurements are written on shutdown.
+    EndOfStream,
+    /// Quality flags of the humidity readings in the preceding measurement that are not good, see
+    /// `piccthermo_core::conditioning::Quality`.
+    Quality(Vec<(u32, u8)>),
+    /// Expected sensors of a bus that were not seen, sent after every enumeration and periodically
+    /// while sensors are missing, and once empty when they are all back.
+    Missing(Vec<u32>),
+    /// Sensors of a bus that are not expected, sent along with [`Readings::Missing`].
+    Unexpected(Vec<u32>),
+    /// Serial port the link switched to, e.g. `/dev/ttyUSB0`, sent when the primary port fails
+    /// over to the backup port and when it fails back.
+    Failover(String),
+}
+
+/// Inventory of the sensors on a bus, to map sensor IDs to devices without configuration.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Metadata {
+    /// Version of the server.
+    pub version: String,
+    /// Path of the bus, e.g. `/dev/i2c-1`.
+    pub path: String,
+    pub sensors: Vec<SensorEntry>,
+}
+
+/// A sensor in a [`Metadata`] inventory.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SensorEntry {
+    /// ID the sensor's readings are tagged with.
+    pub id: u32,
+    /// 1-Wire ROM code or I2C address of the sensor, zero if it has none, or the baud rate of the
+    /// serial port.
+    pub address: u64,
+    /// Part number of the sensor, e.g. `ds28ea00`, the label of a CPU component, or the settings
+    /// of the serial port.
+    pub model: String,
+}
+
+/// Failure counters of a sensor in a [`Readings::Health`] report.
+///
+/// Sensors that are excluded from the readout are not reported, so that a sensor that keeps failing
+/// can be told apart from one left out on purpose.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SensorHealth {
+    /// ID the sensor's readings are tagged with.
+    pub id: u32,
+    /// Failed readouts since the last successful one.
+    pub consecutive: u32,
+    /// Failed readouts since the acquisition on the bus started.
+    pub total: u32,
+}
+
+/// Time reference in a [`Readings::Sync`] frame.
+///
+/// The wall-clock time of the server is the timestamp of the measurement. Comparing it with the
+/// uptime across sync frames tells the drift of the wall clock, and the counter tells how many
+/// sync frames were lost in between.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SyncMarker {
+    /// Time since the server started, in milliseconds, from a monotonic clock.
+    pub uptime_ms: u64,
+    /// Number of sync frames sent before this one since the server started.
+    pub counter: u32,
+}
+
+impl Readings {
+    /// IDs of the sensors in the readings.
+    pub fn ids(&self) -> Vec<u32> {
+        match self {
+            Readings::Temperature(data)
+            | Readings::Humidity(data)
+            | Readings::DewPoint(data)
+            | Readings::RateAlarm(data) => data.iter().map(|(id, _)| *id).collect(),
+            Readings::Brownout(data)
+            | Readings::BurnOff(data)
+            | Readings::Missing(data)
+            | Readings::Unexpected(data) => data.clone(),
+            Readings::Labels(data) => data.iter().map(|(id, _, _)| *id).collect(),
+            Readings::Response(_)
+            | Readings::Restart(_)
+            | Readings::Failover(_)
+            | Readings::Sync(_)
+            | Readings::EndOfStream => Vec::new(),
+            Readings::Metadata(metadata) => metadata.sensors.iter().map(|s| s.id).collect(),
+            Readings::Health(data) => data.iter().map(|s| s.id).collect(),
+            Readings::Quality(data) => data.iter().map(|(id, _)| *id).collect(),
+        }
+    }
+
+    /// Number of records in the readings.
+    pub fn len(&self) -> usize {
+        match self {
+            Readings::Temperature(data)
+            | Readings::Humidity(data)
+            | Readings::DewPoint(data)
+            | Readings::RateAlarm(data) => data.len(),
+            Readings::Brownout(data)
+            | Readings::BurnOff(data)
+            | Readings::Missing(data)
+            | Readings::Unexpected(data) => data.len(),
+            Readings::Labels(data) => data.len(),
+            Readings::Response(_)
+            | Readings::Restart(_)
+            | Readings::Failover(_)
+            | Readings::Sync(_)
+            | Readings::EndOfStream => 1,
+            Readings::Metadata(metadata) => metadata.sensors.len(),
+            Readings::Health(data) => data.len(),
+            Readings::Quality(data) => data.len(),
+        }
+    }
+
+    /// Returns `true` if there are no records in the readings.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Measurement type byte of the frame.
+    fn kind(&self) -> u8 {
+        match self {
+            Readings::Temperature(_) => b'T',
+            Readings::Humidity(_) => b'H',
+            Readings::DewPoint(_) => b'D',
+            Readings::RateAlarm(_) => b'A',
+            Readings::Brownout(_) => b'B',
+            Readings::BurnOff(_) => b'C',
+            Readings::Labels(_) => b'N',
+            Readings::Response(_) => b'R',
+            Readings::Restart(_) => b'W',
+            Readings::Metadata(_) => b'M',
+            Readings::Health(_) => b'E',
+            Readings::Sync(_) => b'S',
+            Readings::EndOfStream => b'Z',
+            Readings::Quality(_) => b'Q',
+            Readings::Missing(_) => b'X',
+            Readings::Unexpected(_) => b'U',
+            Readings::Failover(_) => b'F',
+        }
+    }
+}
+
+/// Readings stamped with their acquisition time and sequence numbers.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Measurement {
+    /// Sequence number of the first record. The following records are numbered consecutively.
+    pub sequence: u32,
+    /// Acquisition time, in milliseconds since the Unix epoch.
+    pub timestamp: u64,
+    /// Bus the readings were acquired on, e.g. `i2c-1`. Not part of the frame.
+    pub source: String,
+    pub readings: Readings,
+}
+
+/// A single record of a [`Measurement`], for text output.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Record<'a> {
+    pub sequence: u32,
+    pub timestamp: u64,
+    /// Name of the measurement type, e.g. `temperature`.
+    pub kind: &'static str,
+    pub id: u32,
+    /// Measured value, if the record carries one.
+    pub value: Option<f32>,
+    /// Label and location, for label records.
+    pub label: Option<(&'a str, &'a str)>,
+    /// Text of response, restart and failover records, and the model of metadata records.
+    pub text: Option<&'a str>,
+    /// Address of metadata records.
+    pub address: Option<u64>,
+    /// Bus path and server version of metadata records.
+    pub origin: Option<(&'a str, &'a str)>,
+    /// Consecutive and total failures of health records.
+    pub failures: Option<(u32, u32)>,
+    /// Uptime, in milliseconds, and counter of sync records.
+    pub sync: Option<(u64, u32)>,
+}
+
+/// Errors encountered while encoding or decoding a frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum FrameError {
+    /// More bytes are needed to decode the frame.
+    Incomplete,
+    /// The buffer does not start with [`FRAME_MAGIC`].
+    InvalidMagic,
+    /// The frame has an unsupported version.
+    UnsupportedVersion(u8),
+    /// The CRC of the frame does not match its contents.
+    InvalidCrc,
+    /// The frame has an unknown measurement type.
+    UnknownType(u8),
+    /// The payload does not match the measurement type.
+    InvalidPayload,
+    /// The payload of the measurement, of the given length, does not fit the u16 length field.
+    TooLarge(usize),
+}
+
+impl Measurement {
+    /// Stamp readings acquired on `source` at `timestamp`, in milliseconds since the Unix epoch,
+    /// with the next sequence numbers.
+    pub fn new(source: String, readings: Readings, timestamp: u64) -> Self {
+        let sequence = SEQUENCE.fetch_add(readings.len() as u32, Ordering::Relaxed);
+        Self {
+            sequence,
+            timestamp,
+            source,
+            readings,
+        }
+    }
+
+    /// Split the measurement into its records.
+    pub fn records(&self) -> Vec<Record<'_>> {
+        let record = |idx: usize, kind, id| Record {
+            sequence: self.sequence.wrapping_add(idx as u32),
+            timestamp: self.timestamp,
+            kind,
+            id,
+            value: None,
+            label: None,
+            text: None,
+            address: None,
+            origin: None,
+            failures: None,
+            sync: None,
+        };
+        match &self.readings {
+            Readings::Temperature(data)
+            | Readings::Humidity(data)
+            | Readings::DewPoint(data)
+            | Readings::RateAlarm(data) => {
+                let kind = match self.readings {
+                    Readings::Temperature(_) => "temperature",
+                    Readings::Humidity(_) => "humidity",
+                    Readings::DewPoint(_) => "dew_point",
+                    _ => "rate_alarm",
+                };
+                data.iter()
+                    .enumerate()
+                    .map(|(idx, (id, value))| Record {
+                        value: Some(*value),
+                        ..record(idx, kind, *id)
+                    })
+                    .collect()
+            }
+            Readings::Brownout(data)
+            | Readings::BurnOff(data)
+            | Readings::Missing(data)
+            | Readings::Unexpected(data) => {
+                let kind = match self.readings {
+                    Readings::Brownout(_) => "brownout",
+                    Readings::BurnOff(_) => "burn_off",
+                    Readings::Missing(_) => "missing",
+                    _ => "unexpected",
+                };
+                data.iter()
+                    .enumerate()
+                    .map(|(idx, id)| record(idx, kind, *id))
+                    .collect()
+            }
+            Readings::Labels(data) => data
+                .iter()
+                .enumerate()
+                .map(|(idx, (id, label, location))| Record {
+                    label: Some((label.as_str(), location.as_str())),
+                    ..record(idx, "label", *id)
+                })
+                .collect(),
+            Readings::Response(text) => vec![Record {
+                text: Some(text.as_str()),
+                ..record(0, "response", 0)
+            }],
+            Readings::Restart(bus) => vec![Record {
+                text: Some(bus.as_str()),
+                ..record(0, "restart", 0)
+            }],
+            Readings::Failover(port) => vec![Record {
+                text: Some(port.as_str()),
+                ..record(0, "failover", 0)
+            }],
+            Readings::Metadata(metadata) => metadata
+                .sensors
+                .iter()
+                .enumerate()
+                .map(|(idx, sensor)| Record {
+                    text: Some(sensor.model.as_str()),
+                    address: Some(sensor.address),
+                    origin: Some((metadata.path.as_str(), metadata.version.as_str())),
+                    ..record(idx, "metadata", sensor.id)
+                })
+                .collect(),
+            Readings::Health(data) => data
+                .iter()
+                .enumerate()
+                .map(|(idx, sensor)| Record {
+                    failures: Some((sensor.consecutive, sensor.total)),
+                    ..record(idx, "health", sensor.id)
+                })
+                .collect(),
+            Readings::Sync(marker) => vec![Record {
+                sync: Some((marker.uptime_ms, marker.counter)),
+                ..record(0, "sync", 0)
+            }],
+            Readings::EndOfStream => vec![record(0, "end_of_stream", 0)],
+            Readings::Quality(data) => data
+                .iter()
+                .enumerate()
+                .map(|(idx, (id, flags))| Record {
+                    value: Some(*flags as f32),
+                    ..record(idx, "quality", *id)
+                })
+                .collect(),
+        }
+    }
+
+    /// Serialize the measurement into a frame.
+    ///
+    /// All integers are little-endian. The frame layout is:
+    /// - magic ([`FRAME_MAGIC`], 4 bytes)
+    /// - version ([`FRAME_VERSION`], 1 byte)
+    /// - payload length (u16)
+    /// - measurement type (1 byte, `T`, `H`, `D`, `A`, `B`, `C`, `N`, `R`, `W`, `M`, `E`, `S`, `Z`,
+    ///   `Q`, `X`, `U` or `F`)
+    /// - payload: sequence number (u32), timestamp (u64), then the records:
+    ///   - `T`, `H`, `D`, `A`: sensor ID (u32) and value (f32)
+    ///   - `B`, `C`, `X`, `U`: sensor ID (u32)
+    ///   - `N`: sensor ID (u32), label and location, each as a length (u16) followed by UTF-8 bytes
+    ///   - `R`, `W`, `F`: a single text, as a length (u16) followed by UTF-8 bytes
+    ///   - `M`: server version and bus path as texts, then for every sensor its ID (u32),
+    ///     address (u64) and model as a text
+    ///   - `E`: sensor ID (u32), consecutive failures (u32) and total failures (u32)
+    ///   - `S`: uptime in milliseconds (u64) and counter (u32)
+    ///   - `Z`: no records
+    ///   - `Q`: sensor ID (u32) and quality flags (u8)
+    /// - CRC32 of everything before it (u32)
+    ///
+    /// Texts longer than 65535 bytes are truncated at a character boundary.
+    ///
+    /// # Errors
+    /// [`FrameError::TooLarge`] if the payload is longer than 65535 bytes, e.g. the labels or
+    /// inventory of a very large bus.
+    pub fn to_bytes(&self) -> Result<Vec<u8>, FrameError> {
+        let mut payload = Vec::with_capacity(12 + 8 * self.readings.len());
+        payload.extend_from_slice(&self.sequence.to_le_bytes());
+        payload.extend_from_slice(&self.timestamp.to_le_bytes());
+        match &self.readings {
+            Readings::Temperature(data)
+            | Readings::Humidity(data)
+            | Readings::DewPoint(data)
+            | Readings::RateAlarm(data) => {
+                for (id, value) in data {
+                    payload.extend_from_slice(&id.to_le_bytes());
+                    payload.extend_from_slice(&value.to_le_bytes());
+                }
+            }
+            Readings::Brownout(data)
+            | Readings::BurnOff(data)
+            | Readings::Missing(data)
+            | Readings::Unexpected(data) => {
+                for id in data {
+                    payload.extend_from_slice(&id.to_le_bytes());
+                }
+            }
+            Readings::Labels(data) => {
+                for (id, label, location) in data {
+                    payload.extend_from_slice(&id.to_le_bytes());
+                    for text in [label, location] {
+                        push_text(&mut payload, text);
+                    }
+                }
+            }
+            Readings::Response(text) | Readings::Restart(text) | Readings::Failover(text) => {
+                push_text(&mut payload, text)
+            }
+            Readings::Metadata(metadata) => {
+                push_text(&mut payload, &metadata.version);
+                push_text(&mut payload, &metadata.path);
+                for sensor in &metadata.sensors {
+                    payload.extend_from_slice(&sensor.id.to_le_bytes());
+                    payload.extend_from_slice(&sensor.address.to_le_bytes());
+                    push_text(&mut payload, &sensor.model);
+                }
+            }
+            Readings::Health(data) => {
+                for sensor in data {
+                    payload.extend_from_slice(&sensor.id.to_le_bytes());
+                    payload.extend_from_slice(&sensor.consecutive.to_le_bytes());
+                    payload.extend_from_slice(&sensor.total.to_le_bytes());
+                }
+            }
+            Readings::Sync(marker) => {
+                payload.extend_from_slice(&marker.uptime_ms.to_le_bytes());
+                payload.extend_from_slice(&marker.counter.to_le_bytes());
+            }
+            Readings::EndOfStream => {}
+            Readings::Quality(data) => {
+                for (id, flags) in data {
+                    payload.extend_from_slice(&id.to_le_bytes());
+                    payload.push(*flags);
+                }
+            }
+        }
+        let len = u16::try_from(payload.len()).map_err(|_| FrameError::TooLarge(payload.len()))?;
+        let mut bytes = Vec::with_capacity(HEADER_LEN + payload.len() + CRC_LEN);
+        bytes.extend_from_slice(&FRAME_MAGIC);
+        bytes.push(FRAME_VERSION);
+        bytes.extend_from_slice(&len.to_le_bytes());
+        bytes.push(self.readings.kind());
+        bytes.extend_from_slice(&payload);
+        bytes.extend_from_slice(&crc32fast::hash(&bytes).to_le_bytes());
+        Ok(bytes)
+    }
+
+    /// Decode a frame from the start of `bytes`.
+    ///
+    /// # Returns
+    /// The measurement and the number of bytes consumed. To resynchronize after an error other than
+    /// [`FrameError::Incomplete`], skip a byte and look for the next [`FRAME_MAGIC`].
+    pub fn from_bytes(bytes: &[u8]) -> Result<(Self, usize), FrameError> {
+        if bytes.len() < HEADER_LEN {
+            return Err(if FRAME_MAGIC.starts_with(&bytes[..bytes.len().min(4)]) {
+                FrameError::Incomplete
+            } else {
+                FrameError::InvalidMagic
+            });
+        }
+        if bytes[..4] != FRAME_MAGIC {
+            return Err(FrameError::InvalidMagic);
+        }
+        if bytes[4] != FRAME_VERSION {
+            return Err(FrameError::UnsupportedVersion(bytes[4]));
+        }
+        let len = HEADER_LEN + u16::from_le_bytes([bytes[5], bytes[6]]) as usize;
+        if bytes.len() < len + CRC_LEN {
+            return Err(FrameError::Incomplete);
+        }
+        let crc = u32::from_le_bytes(bytes[len..len + CRC_LEN].try_into().unwrap());
+        if crc32fast::hash(&bytes[..len]) != crc {
+            return Err(FrameError::InvalidCrc);
+        }
+        let mut payload = Payload(&bytes[HEADER_LEN..len]);
+        let sequence = payload.u32()?;
+        let timestamp = payload.u64()?;
+        let readings = match bytes[7] {
+            kind @ (b'T' | b'H' | b'D' | b'A') => {
+                let mut data = Vec::new();
+                while !payload.0.is_empty() {
+                    data.push((payload.u32()?, f32::from_bits(payload.u32()?)));
+                }
+                match kind {
+                    b'T' => Readings::Temperature(data),
+                    b'H' => Readings::Humidity(data),
+                    b'D' => Readings::DewPoint(data),
+                    _ => Readings::RateAlarm(data),
+                }
+            }
+            kind @ (b'B' | b'C' | b'X' | b'U') => {
+                let mut data = Vec::new();
+                while !payload.0.is_empty() {
+                    data.push(payload.u32()?);
+                }
+                match kind {
+                    b'B' => Readings::Brownout(data),
+                    b'C' => Readings::BurnOff(data),
+                    b'X' => Readings::Missing(data),
+                    _ => Readings::Unexpected(data),
+                }
+            }
+            b'N' => {
+                let mut data = Vec::new();
+                while !payload.0.is_empty() {
+                    data.push((payload.u32()?, payload.text()?, payload.text()?));
+                }
+                Readings::Labels(data)
+            }
+            kind @ (b'R' | b'W' | b'F') => {
+                let text = payload.text()?;
+                if !payload.0.is_empty() {
+                    return Err(FrameError::InvalidPayload);
+                }
+                match kind {
+                    b'R' => Readings::Response(text),
+                    b'W' => Readings::Restart(text),
+                    _ => Readings::Failover(text),
+                }
+            }
+            b'M' => {
+                let version = payload.text()?;
+                let path = payload.text()?;
+                let mut sensors = Vec::new();
+                while !payload.0.is_empty() {
+                    sensors.push(SensorEntry {
+                        id: payload.u32()?,
+                        address: payload.u64()?,
+                        model: payload.text()?,
+                    });
+                }
+                Readings::Metadata(Metadata {
+                    version,
+                    path,
+                    sensors,
+                })
+            }
+            b'E' => {
+                let mut data = Vec::new();
+                while !payload.0.is_empty() {
+                    data.push(SensorHealth {
+                        id: payload.u32()?,
+                        consecutive: payload.u32()?,
+                        total: payload.u32()?,
+                    });
+                }
+                Readings::Health(data)
+            }
+            b'S' => {
+                let marker = SyncMarker {
+                    uptime_ms: payload.u64()?,
+                    counter: payload.u32()?,
+                };
+                if !payload.0.is_empty() {
+                    return Err(FrameError::InvalidPayload);
+                }
+                Readings::Sync(marker)
+            }
+            b'Z' => {
+                if !payload.0.is_empty() {
+                    return Err(FrameError::InvalidPayload);
+                }
+                Readings::EndOfStream
+            }
+            b'Q' => {
+                let mut data = Vec::new();
+                while !payload.0.is_empty() {
+                    data.push((payload.u32()?, payload.u8()?));
+                }
+                Readings::Quality(data)
+            }
+            kind => return Err(FrameError::UnknownType(kind)),
+        };
+        Ok((
+            Self {
+                sequence,
+                timestamp,
+                source: String::new(),
+                readings,
+            },
+            len + CRC_LEN,
+        ))
+    }
+}
+
+/// Append a text to a payload as a length (u16) followed by its UTF-8 bytes, truncated at a
+/// character boundary if needed.
+fn push_text(payload: &mut Vec<u8>, text: &str) {
+    let mut end = text.len().min(u16::MAX as usize);
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    let text = &text.as_bytes()[..end];
+    payload.extend_from_slice(&(text.len() as u16).to_le_bytes());
+    payload.extend_from_slice(text);
+}
+
+/// Cursor over the payload of a frame.
+struct Payload<'a>(&'a [u8]);
+
+impl Payload<'_> {
+    fn take<const N: usize>(&mut self) -> Result<[u8; N], FrameError> {
+        let (head, tail) = self
+            .0
+            .split_first_chunk()
+            .ok_or(FrameError::InvalidPayload)?;
+        self.0 = tail;
+        Ok(*head)
+    }
+
+    fn u8(&mut self) -> Result<u8, FrameError> {
+        self.take().map(u8::from_le_bytes)
+    }
+
+    fn u32(&mut self) -> Result<u32, FrameError> {
+        self.take().map(u32::from_le_bytes)
+    }
+
+    fn u64(&mut self) -> Result<u64, FrameError> {
+        self.take().map(u64::from_le_bytes)
+    }
+
+    fn text(&mut self) -> Result<String, FrameError> {
+        let len = self.take().map(u16::from_le_bytes)? as usize;
+        if self.0.len() < len {
+            return Err(FrameError::InvalidPayload);
+        }
+        let (text, tail) = self.0.split_at(len);
+        self.0 = tail;
+        String::from_utf8(text.to_vec()).map_err(|_| FrameError::InvalidPayload)
+    }
+}
+
+mod test {
+    #[test]
+    fn test_roundtrip() {
+        use super::{Measurement, Metadata, Readings, SensorEntry, SensorHealth, SyncMarker};
+        for readings in [
+            Readings::Temperature(vec![(0xdeadbeef, 21.5), (1, -40.0)]),
+            Readings::Humidity(vec![(0x40, 45.25)]),
+            Readings::DewPoint(vec![]),
+            Readings::RateAlarm(vec![(0xdeadbeef, -3.5)]),
+            Readings::Brownout(vec![0x40, 0x41]),
+            Readings::BurnOff(vec![0x44]),
+            Readings::Labels(vec![(7, "top".into(), "Chamber, top shelf".into())]),
+            Readings::Response("ACK i2c-1 rate 500 ms".into()),
+            Readings::Restart("i2c-1".into()),
+            Readings::Failover("/dev/ttyUSB0".into()),
+            Readings::Health(vec![SensorHealth {
+                id: 0x40,
+                consecutive: 3,
+                total: 17,
+            }]),
+            Readings::Sync(SyncMarker {
+                uptime_ms: 3_600_000,
+                counter: 60,
+            }),
+            Readings::EndOfStream,
+            Readings::Quality(vec![(0x40, 0b101), (0x41, 0b010)]),
+            Readings::Missing(vec![0xdeadbeef]),
+            Readings::Unexpected(vec![]),
+            Readings::Metadata(Metadata {
+                version: "0.0.1".into(),
+                path: "/dev/i2c-1".into(),
+                sensors: vec![SensorEntry {
+                    id: 0xdeadbeef,
+                    address: 0x4200_0012_3456_7801,
+                    model: "ds28ea00".into(),
+                }],
+            }),
+        ] {
+            let measurement = Measurement {
+                sequence: 42,
+                timestamp: 1_700_000_000_000,
+                source: String::new(),
+                readings,
+            };
+            let bytes = measurement.to_bytes().unwrap();
+            let (decoded, len) = Measurement::from_bytes(&bytes).unwrap();
+            assert_eq!(decoded, measurement);
+            assert_eq!(len, bytes.len());
+        }
+    }
+
+    #[test]
+    fn test_corruption() {
+        use super::{FrameError, Measurement, Readings};
+        let measurement = Measurement {
+            sequence: 1,
+            timestamp: 2,
+            source: String::new(),
+            readings: Readings::Temperature(vec![(3, 4.0)]),
+        };
+        let bytes = measurement.to_bytes().unwrap();
+        for len in 0..bytes.len() {
+            assert_eq!(
+                Measurement::from_bytes(&bytes[..len]),
+                Err(FrameError::Incomplete)
+            );
+        }
+        let mut corrupted = bytes.clone();
+        corrupted[12] ^= 0x01;
+        assert_eq!(
+            Measurement::from_bytes(&corrupted),
+            Err(FrameError::InvalidCrc)
+        );
+        let mut version = bytes.clone();
+        version[4] = 0;
+        assert_eq!(
+            Measurement::from_bytes(&version),
+            Err(FrameError::UnsupportedVersion(0))
+        );
+    }
+
+    #[test]
+    fn test_too_large() {
+        use super::{FrameError, Measurement, Readings, push_text};
+        // 8192 labels of 8 bytes each, after the sequence number and timestamp
+        let labels = (0..8192).map(|id| (id, String::new(), String::new()));
+        let measurement = Measurement {
+            sequence: 1,
+            timestamp: 2,
+            source: String::new(),
+            readings: Readings::Labels(labels.collect()),
+        };
+        assert_eq!(measurement.to_bytes(), Err(FrameError::TooLarge(65_548)));
+        // a long text is truncated before its last character, not inside it
+        let text = "a".repeat(65_534) + "é";
+        let mut payload = Vec::new();
+        push_text(&mut payload, &text);
+        assert_eq!(payload[..2], 65_534u16.to_le_bytes());
+        assert!(std::str::from_utf8(&payload[2..]).is_ok());
+    }
+
+    #[test]
+    fn test_resync() {
+        use super::{FrameError, Measurement, Readings};
+        let measurement = Measurement {
+            sequence: 5,
+            timestamp: 6,
+            source: String::new(),
+            readings: Readings::Brownout(vec![0x40]),
+        };
+        let mut stream = b"garbage".to_vec();
+        stream.extend_from_slice(&measurement.to_bytes().unwrap());
+        let mut offset = 0;
+        let decoded = loop {
+            match Measurement::from_bytes(&stream[offset..]) {
+                Ok((decoded, _)) => break decoded,
+                Err(FrameError::Incomplete) => panic!("frame not found"),
+                Err(_) => offset += 1,
+            }
+        };
+        assert_eq!(offset, 7);
+        assert_eq!(decoded, measurement);
+    }
+}
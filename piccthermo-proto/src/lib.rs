@@ -0,0 +1,16 @@
+//! # piccthermo-proto
+//!
+//! Wire format of the measurement stream sent by `thermo-server` over the serial link and the
+//! network sinks, and decoders of the stream for the receiving stations.
+//!
+//! The stream is decoded into typed [`Measurement`](data_format::Measurement)s, by
+//! [`stream::Reader`] for the COBS-encoded serial link and TCP sink, or by [`stream::frames`] for
+//! the datagrams of the UDP sink. The `piccthermo-dump` binary tails a serial port or a network
+//! sink, and prints or records the frames.
+//!
+//! Features:
+//! - `serde`: Serialization of the measurements.
+//! - `cli`: The `piccthermo-dump` binary, enabled by default.
+pub mod cobs;
+pub mod data_format;
+pub mod stream;
@@ -0,0 +1,206 @@
+//! Resynchronizing decoders of the measurement stream.
+//!
+//! The serial link and the TCP sink send COBS frames, each holding one or more binary frames one
+//! after the other, decoded from any [`Read`] with [`Reader`]. The UDP sink sends a binary frame per
+//! datagram, decoded with [`frames`].
+use std::{
+    collections::VecDeque,
+    io::{self, Read},
+};
+
+use crate::{
+    cobs::{DecodeError, Decoder},
+    data_format::{FRAME_MAGIC, FrameError, Measurement},
+};
+
+/// Decode the binary frames held one after the other by `bytes`, e.g. a COBS frame or a datagram.
+///
+/// A frame that fails to decode is reported once, and the decoding resumes at the next
+/// [`FRAME_MAGIC`] after its start.
+pub fn frames(bytes: &[u8]) -> Frames<'_> {
+    Frames { bytes }
+}
+
+/// Iterator over the binary frames of a buffer, see [`frames`].
+#[derive(Debug, Clone)]
+pub struct Frames<'a> {
+    bytes: &'a [u8],
+}
+
+impl Iterator for Frames<'_> {
+    type Item = Result<Measurement, FrameError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.bytes.is_empty() {
+            return None;
+        }
+        match Measurement::from_bytes(self.bytes) {
+            Ok((measurement, len)) => {
+                self.bytes = &self.bytes[len..];
+                Some(Ok(measurement))
+            }
+            Err(e) => {
+                let skip = match e {
+                    // a truncated frame leaves nothing to decode after it
+                    FrameError::Incomplete => self.bytes.len(),
+                    _ => self.bytes[1..]
+                        .windows(FRAME_MAGIC.len())
+                        .position(|window| window == FRAME_MAGIC)
+                        .map_or(self.bytes.len(), |pos| pos + 1),
+                };
+                self.bytes = &self.bytes[skip..];
+                Some(Err(e))
+            }
+        }
+    }
+}
+
+/// Errors encountered while reading the measurement stream.
+#[derive(Debug)]
+pub enum StreamError {
+    /// Reading the stream failed, after which the [`Reader`] ends.
+    Io(io::Error),
+    /// A COBS frame is corrupted, and its binary frames are lost.
+    Cobs(DecodeError),
+    /// A binary frame is corrupted.
+    Frame(FrameError),
+}
+
+impl std::fmt::Display for StreamError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StreamError::Io(e) => write!(f, "read failed: {e}"),
+            StreamError::Cobs(e) => write!(f, "corrupted COBS frame: {e:?}"),
+            StreamError::Frame(e) => write!(f, "corrupted frame: {e:?}"),
+        }
+    }
+}
+
+impl std::error::Error for StreamError {}
+
+/// Iterator over the measurements of a COBS-encoded stream, e.g. a serial port or a TCP
+/// connection to the server.
+///
+/// A receiver that joins mid-stream, or loses bytes, resynchronizes at the next COBS delimiter,
+/// and within a COBS frame at the next [`FRAME_MAGIC`]. The corrupted frames are reported as
+/// errors, and the iteration goes on. Reads that time out are retried, so that a serial port
+/// opened with a timeout is tailed until it fails. The iteration ends at the end of the stream,
+/// or after the first other read error.
+pub struct Reader<R> {
+    inner: Option<R>,
+    decoder: Decoder,
+    pending: VecDeque<Result<Measurement, StreamError>>,
+    buf: Box<[u8]>,
+}
+
+impl<R: Read> Reader<R> {
+    /// Decode the stream read from `inner`.
+    pub fn new(inner: R) -> Self {
+        Self {
+            inner: Some(inner),
+            decoder: Decoder::default(),
+            pending: VecDeque::new(),
+            buf: vec![0; 4096].into_boxed_slice(),
+        }
+    }
+}
+
+impl<R: Read> Iterator for Reader<R> {
+    type Item = Result<Measurement, StreamError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(item) = self.pending.pop_front() {
+                return Some(item);
+            }
+            let inner = self.inner.as_mut()?;
+            let len = match inner.read(&mut self.buf) {
+                Ok(0) => {
+                    self.inner = None;
+                    return None;
+                }
+                Ok(len) => len,
+                Err(e)
+                    if matches!(
+                        e.kind(),
+                        io::ErrorKind::TimedOut
+                            | io::ErrorKind::WouldBlock
+                            | io::ErrorKind::Interrupted
+                    ) =>
+                {
+                    continue;
+                }
+                Err(e) => {
+                    self.inner = None;
+                    return Some(Err(StreamError::Io(e)));
+                }
+            };
+            for frame in self.decoder.feed(&self.buf[..len]) {
+                match frame {
+                    Ok(bytes) => self.pending.extend(
+                        frames(&bytes).map(|measurement| measurement.map_err(StreamError::Frame)),
+                    ),
+                    Err(e) => self.pending.push_back(Err(StreamError::Cobs(e))),
+                }
+            }
+        }
+    }
+}
+
+mod test {
+    #[test]
+    fn test_reader() {
+        use super::{Reader, StreamError, frames};
+        use crate::{
+            cobs,
+            data_format::{FrameError, Measurement, Readings},
+        };
+        let measurement = |sequence| Measurement {
+            sequence,
+            timestamp: 1_700_000_000_000,
+            source: String::new(),
+            readings: Readings::Temperature(vec![(0x1234, 21.5)]),
+        };
+        // a batch with a corrupted frame between two good ones
+        let mut corrupted = measurement(2).to_bytes().unwrap();
+        corrupted[10] ^= 0xff;
+        let mut batch = measurement(1).to_bytes().unwrap();
+        batch.extend_from_slice(&corrupted);
+        batch.extend_from_slice(&measurement(3).to_bytes().unwrap());
+        let decoded = frames(&batch).collect::<Vec<_>>();
+        assert_eq!(
+            decoded,
+            [
+                Ok(measurement(1)),
+                Err(FrameError::InvalidCrc),
+                Ok(measurement(3))
+            ]
+        );
+        // joined mid-stream, and read in small chunks
+        let mut stream = cobs::encode(&measurement(0).to_bytes().unwrap())[5..].to_vec();
+        stream.extend_from_slice(&cobs::encode(&batch));
+        stream.extend_from_slice(&cobs::encode(&measurement(4).to_bytes().unwrap()));
+        struct Chunked<'a>(&'a [u8]);
+        impl std::io::Read for Chunked<'_> {
+            fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+                let len = self.0.len().min(buf.len()).min(7);
+                buf[..len].copy_from_slice(&self.0[..len]);
+                self.0 = &self.0[len..];
+                Ok(len)
+            }
+        }
+        let decoded = Reader::new(Chunked(&stream)).collect::<Vec<_>>();
+        assert_eq!(decoded.len(), 5);
+        // the tail of the first frame is not decoded
+        assert!(decoded[0].is_err());
+        assert!(matches!(
+            decoded[2],
+            Err(StreamError::Frame(FrameError::InvalidCrc))
+        ));
+        let sequences = decoded
+            .iter()
+            .filter_map(|measurement| measurement.as_ref().ok().map(|m| m.sequence))
+            .collect::<Vec<_>>();
+        assert_eq!(sequences, [1, 3, 4]);
+    }
+}
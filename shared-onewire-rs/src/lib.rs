@@ -0,0 +1,168 @@
+#![no_std]
+#![deny(missing_docs)]
+//! # shared-onewire
+//!
+//! A no-std crate to share a 1-Wire bus master, e.g. a DS2484, between several device drivers,
+//! such as a DS28EA00 group and a DS2431 EEPROM driver, running in different tasks or threads.
+//!
+//! Unlike an I2C transfer, a 1-Wire transaction spans several calls to the
+//! [`OneWire`](https://docs.rs/embedded-onewire/latest/embedded_onewire/trait.OneWire.html) trait:
+//! a reset, a ROM command selecting the devices, then the function command and its data. A device
+//! stays selected until the next reset, so calls of another driver must not slip in between.
+//! The bus is therefore not shared call by call, but transaction by transaction: the
+//! [`BusProxy`] handed to each driver locks the bus for the duration of
+//! [`BusProxy::transaction`], in which the driver has exclusive access to the bus master.
+//!
+//! The bus master is protected by a [`BusMutex`]. The following backends are available:
+//! - [`RefCell`], to share the bus within a single thread, e.g. between drivers polled in turn.
+//! - `std::sync::Mutex`, with the `std` feature, to share the bus between threads.
+//! - `critical_section::Mutex<RefCell<_>>`, with the `critical-section` feature, to share the bus
+//!   between tasks and interrupt handlers on bare metal targets.
+//!
+//! Note: The overdrive mode is a property of the bus, and is shared by all drivers. Long waits,
+//! such as temperature conversions, should happen outside of a transaction so that they do not
+//! hold up the other drivers, which matters most with the `critical-section` backend since it
+//! masks interrupts while the bus is locked.
+#[cfg(feature = "std")]
+extern crate std;
+
+use core::cell::RefCell;
+
+/// A mutex protecting a bus master.
+pub trait BusMutex {
+    /// The bus master protected by the mutex.
+    type Bus;
+
+    /// Create a mutex protecting `bus`.
+    fn create(bus: Self::Bus) -> Self;
+
+    /// Lock the bus for the duration of `f`.
+    fn lock<R>(&self, f: impl FnOnce(&mut Self::Bus) -> R) -> R;
+}
+
+/// Shares a bus within a single thread.
+///
+/// # Panics
+/// Panics if a transaction is started from within another transaction.
+impl<T> BusMutex for RefCell<T> {
+    type Bus = T;
+
+    fn create(bus: T) -> Self {
+        RefCell::new(bus)
+    }
+
+    fn lock<R>(&self, f: impl FnOnce(&mut T) -> R) -> R {
+        f(&mut self.borrow_mut())
+    }
+}
+
+#[cfg(feature = "std")]
+/// Shares a bus between threads.
+///
+/// A poisoned mutex is recovered, since a panicking driver leaves the bus in no worse state
+/// than a failed transaction.
+impl<T> BusMutex for std::sync::Mutex<T> {
+    type Bus = T;
+
+    fn create(bus: T) -> Self {
+        std::sync::Mutex::new(bus)
+    }
+
+    fn lock<R>(&self, f: impl FnOnce(&mut T) -> R) -> R {
+        f(&mut self
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner))
+    }
+}
+
+#[cfg(feature = "critical-section")]
+/// Shares a bus between tasks and interrupt handlers.
+///
+/// # Panics
+/// Panics if a transaction is started from within another transaction.
+impl<T> BusMutex for critical_section::Mutex<RefCell<T>> {
+    type Bus = T;
+
+    fn create(bus: T) -> Self {
+        critical_section::Mutex::new(RefCell::new(bus))
+    }
+
+    fn lock<R>(&self, f: impl FnOnce(&mut T) -> R) -> R {
+        critical_section::with(|cs| f(&mut self.borrow_ref_mut(cs)))
+    }
+}
+
+/// Owner of a shared bus master, handing out a [`BusProxy`] to every driver.
+pub struct BusManager<M> {
+    mutex: M,
+}
+
+/// Bus manager for drivers in a single thread.
+pub type BusManagerSimple<T> = BusManager<RefCell<T>>;
+
+#[cfg(feature = "std")]
+/// Bus manager for drivers in several threads.
+pub type BusManagerStd<T> = BusManager<std::sync::Mutex<T>>;
+
+#[cfg(feature = "critical-section")]
+/// Bus manager for drivers in several tasks or interrupt handlers.
+pub type BusManagerCs<T> = BusManager<critical_section::Mutex<RefCell<T>>>;
+
+impl<M: BusMutex> BusManager<M> {
+    /// Take ownership of a bus master to share it.
+    pub fn new(bus: M::Bus) -> Self {
+        Self {
+            mutex: M::create(bus),
+        }
+    }
+
+    /// Get a handle to the bus for a driver.
+    pub fn acquire(&self) -> BusProxy<'_, M> {
+        BusProxy { mutex: &self.mutex }
+    }
+}
+
+/// Handle to a shared bus master, used by a single driver.
+pub struct BusProxy<'a, M> {
+    mutex: &'a M,
+}
+
+impl<M> Clone for BusProxy<'_, M> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<M> Copy for BusProxy<'_, M> {}
+
+impl<M: BusMutex> BusProxy<'_, M> {
+    /// Run a transaction with exclusive access to the bus master.
+    ///
+    /// # Parameters:
+    /// - `f`: The transaction, e.g. `|bus| group.read_temperatures(bus, ReadOptions::new())`.
+    ///
+    /// # Returns:
+    /// The result of `f`.
+    pub fn transaction<R>(&self, f: impl FnOnce(&mut M::Bus) -> R) -> R {
+        self.mutex.lock(f)
+    }
+}
+
+mod test {
+    #[test]
+    fn test_transactions() {
+        use super::BusManagerSimple;
+        // bytes written to the bus, and their count
+        let manager = BusManagerSimple::new(([0u8; 4], 0usize));
+        let write = |bus: &mut ([u8; 4], usize), bytes: &[u8]| {
+            for b in bytes {
+                bus.0[bus.1] = *b;
+                bus.1 += 1;
+            }
+        };
+        let (a, b) = (manager.acquire(), manager.acquire());
+        a.transaction(|bus| write(bus, &[0x55, 0xbe]));
+        b.transaction(|bus| write(bus, &[0xcc, 0x44]));
+        assert_eq!(a.transaction(|bus| *bus), ([0x55, 0xbe, 0xcc, 0x44], 4));
+    }
+}
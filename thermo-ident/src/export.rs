@@ -0,0 +1,89 @@
+use std::path::Path;
+
+use piccthermo_id::SensorId;
+
+/// A labelled sensor, as exported to a sensor map.
+pub struct Entry {
+    /// Path of the I2C bus the sensor is on.
+    pub bus: String,
+    /// ROM of the sensor.
+    pub rom: u64,
+    /// Label assigned to the sensor.
+    pub label: String,
+}
+
+/// Write the entries to `path`, as CSV if the file extension is `csv` and as a thermo-server
+/// sensor map (TOML) otherwise.
+pub fn write(path: &Path, entries: &[Entry]) -> Result<(), String> {
+    let content = if path.extension().is_some_and(|ext| ext == "csv") {
+        to_csv(entries)
+    } else {
+        to_toml(entries)
+    };
+    std::fs::write(path, content).map_err(|e| format!("Failed to write {}: {e}", path.display()))
+}
+
+/// Format the entries as a thermo-server sensor map. The ROM of every sensor is kept as a comment,
+/// since the sensor map does not allow other fields.
+pub fn to_toml(entries: &[Entry]) -> String {
+    let mut out = String::new();
+    for entry in entries {
+        out.push_str(&format!(
+            "# {} 0x{:016x}\n[sensor.\"0x{}\"]\nlabel = {}\n\n",
+            entry.bus,
+            entry.rom,
+            SensorId::from_rom(entry.rom),
+            toml::Value::from(entry.label.as_str()),
+        ));
+    }
+    out
+}
+
+/// Format the entries as CSV, with a header.
+pub fn to_csv(entries: &[Entry]) -> String {
+    let mut out = String::from("bus,rom,id,label\n");
+    for entry in entries {
+        out.push_str(&format!(
+            "{},0x{:016x},0x{},{}\n",
+            escape(&entry.bus),
+            entry.rom,
+            SensorId::from_rom(entry.rom),
+            escape(&entry.label),
+        ));
+    }
+    out
+}
+
+/// Quote a CSV field if needed.
+fn escape(field: &str) -> String {
+    if field.contains([',', '"', '\n']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.into()
+    }
+}
+
+mod test {
+    #[test]
+    fn test_export() {
+        use super::{Entry, to_csv, to_toml};
+        use piccthermo_id::SensorId;
+        let entries = [Entry {
+            bus: "/dev/i2c-1".into(),
+            rom: 0x4200_0000_1234_5642,
+            label: "chamber \"top\", left".into(),
+        }];
+        let id = format!("0x{}", SensorId::from_rom(entries[0].rom));
+        let map: toml::Table = toml::from_str(&to_toml(&entries)).unwrap();
+        assert_eq!(
+            map["sensor"][id.as_str()]["label"].as_str(),
+            Some("chamber \"top\", left")
+        );
+        assert_eq!(
+            to_csv(&entries),
+            format!(
+                "bus,rom,id,label\n/dev/i2c-1,0x4200000012345642,{id},\"chamber \"\"top\"\", left\"\n"
+            )
+        );
+    }
+}
@@ -1,13 +1,46 @@
+mod export;
+
+use std::{
+    collections::HashMap,
+    path::PathBuf,
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicBool, Ordering},
+    },
+    thread,
+    time::Duration,
+};
+
+use clap::Parser;
 use cursive::{
-    With,
-    view::Resizable,
-    views::{self, Dialog, ListView},
-    reexports::log::LevelFilter
+    CbSink, Cursive, With,
+    reexports::log::LevelFilter,
+    utils::Counter,
+    view::{Nameable, Resizable},
+    views::{self, Dialog, EditView, ListView, ProgressBar, TextView},
 };
-use ds28ea00::Ds28ea00Group;
+use ds28ea00::{Ds28ea00Group, ReadError, SortOrder, Temperature};
 use ds2484::{Ds2484, Interact};
+use piccthermo_id::SensorId;
+
+#[derive(Parser, Debug)]
+#[command(version, about, long_about = None)]
+struct Args {
+    /// Interval between two temperature readouts, in milliseconds
+    #[arg(long, default_value_t = 1000)]
+    refresh_ms: u64,
+    /// File the sensor labels are exported to, as CSV if it ends in `.csv` and as a
+    /// thermo-server sensor map otherwise
+    #[arg(long, default_value = "sensor_map.toml")]
+    output: PathBuf,
+    /// Time each LED is lit for when blinking the sensors of a bus in sequence, in milliseconds
+    #[arg(long, default_value_t = 1000)]
+    blink_ms: u64,
+}
 
 fn main() {
+    let args = Args::parse();
+
     // Initialize the cursive logger.
     cursive::logger::init();
     cursive::logger::set_internal_filter_level(LevelFilter::Info);
@@ -32,116 +65,137 @@ fn main() {
     siv.add_global_callback('~', cursive::Cursive::toggle_debug_console);
     siv.add_global_callback('`', cursive::Cursive::toggle_debug_console);
 
-    let sensors = TempSensors::new();
-    let paths = sensors.paths.clone();
+    let sensors = Arc::new(Mutex::new(TempSensors::new(Duration::from_millis(
+        args.blink_ms,
+    ))));
+    spawn_readout(
+        sensors.clone(),
+        siv.cb_sink().clone(),
+        Duration::from_millis(args.refresh_ms),
+    );
     siv.set_user_data(sensors);
+
+    let output = args.output;
+    siv.add_layer(
+        Dialog::new()
+            .title("I2C Buses")
+            .content(ListView::new())
+            .button("Export", move |s| {
+                let res = with_sensors(s, |sensors: &mut TempSensors| {
+                    export::write(&output, &sensors.entries())
+                })
+                .unwrap();
+                let text = match res {
+                    Ok(()) => {
+                        log::info!("[TMP] Exported labels to {}", output.display());
+                        format!("Exported labels to {}", output.display())
+                    }
+                    Err(e) => {
+                        log::error!("[TMP] {e}");
+                        e
+                    }
+                };
+                s.add_layer(Dialog::text(text).title("Export").button("OK", |s| {
+                    s.pop_layer();
+                }));
+            })
+            .with_name(BUSES),
+    );
+    enumerate_buses(&mut siv, bus_paths());
+    siv.run();
+}
+
+/// Name of the dialog listing the buses.
+const BUSES: &str = "buses";
+/// Name of the dialog showing the progress of the enumeration.
+const PROGRESS: &str = "progress";
+/// Name of the view listing the outcome of every enumerated bus.
+const PROGRESS_LOG: &str = "progress-log";
+
+/// Paths of the I2C buses of the system.
+fn bus_paths() -> Vec<String> {
+    glob("/dev/i2c-*")
+        .expect("Failed to find I2C devices")
+        .filter_map(|path| match path {
+            Ok(path) => Some(path.to_string_lossy().into_owned()),
+            Err(e) => {
+                log::error!("Failed to read glob pattern: {}", e);
+                None
+            }
+        })
+        .collect()
+}
+
+/// Open and enumerate the buses at `paths`, every bus on its own thread, while showing the
+/// progress in a dialog.
+///
+/// Most I2C buses have no DS2484 bridge, and take a while to time out, so that enumerating them
+/// one after the other would keep the UI waiting. The buses are added to the list as they are
+/// opened, and the dialog is closed once all buses are done.
+fn enumerate_buses(s: &mut Cursive, paths: Vec<String>) {
+    if paths.is_empty() {
+        refresh_buses(s);
+        return;
+    }
+    let total = paths.len();
+    let done = Counter::new(0);
+    s.add_layer(
+        Dialog::around(
+            views::LinearLayout::vertical()
+                .child(ProgressBar::new().max(total).with_value(done.clone()))
+                .child(TextView::new("").with_name(PROGRESS_LOG)),
+        )
+        .title("Enumerating I2C buses")
+        .min_width(40)
+        .with_name(PROGRESS),
+    );
+    for path in paths {
+        let done = done.clone();
+        let cb_sink = s.cb_sink().clone();
+        thread::spawn(move || {
+            log::info!("[TMP] Found I2C device: {path}");
+            let bus = open_bus(&path);
+            let _ = cb_sink.send(Box::new(move |s| {
+                let status = match &bus {
+                    Ok((_, sensors)) => format!("{path}: {} sensors\n", sensors.roms().count()),
+                    Err(e) => format!("{path}: {e}\n"),
+                };
+                with_sensors(s, |sensors: &mut TempSensors| sensors.add_bus(path, bus));
+                s.call_on_name(PROGRESS_LOG, |view: &mut TextView| view.append(status));
+                done.tick(1);
+                if done.get() == total {
+                    if let Some(layer) = s.screen_mut().find_layer_from_name(PROGRESS) {
+                        s.screen_mut().remove_layer(layer);
+                    }
+                    refresh_buses(s);
+                }
+            }));
+        });
+    }
+}
+
+/// Show the buses in the bus list, including the ones that failed to open, which can be
+/// enumerated again.
+fn refresh_buses(s: &mut Cursive) {
+    let Some((paths, failed)) = with_sensors(s, |sensors: &mut TempSensors| {
+        (sensors.paths.clone(), sensors.failed.clone())
+    }) else {
+        return;
+    };
     let list = ListView::new().with(|tree| {
-        for (idx, path) in paths.iter().enumerate() {
-            let path = path.clone();
+        for (idx, path) in paths.into_iter().enumerate() {
             tree.add_child(
                 format!("I2C Bus {}", idx + 1),
                 views::LinearLayout::horizontal()
                     .child(
                         views::Button::new(path.clone(), move |s| {
                             log::info!("[TMP] Selected I2C Bus: {}", &path);
-                            if let Some(subtree) = s.with_user_data(|sensors: &mut TempSensors| {
-                                log::info!("[TMP] Selected I2C Bus: {}", &path);
-                                ListView::new().with(|stree| {
-                                    let sensor = &sensors.sensors[idx];
-                                    let ndigits =
-                                        sensor.roms().count().checked_ilog10().unwrap_or(0)
-                                            as usize
-                                            + 1;
-                                    for (i, sensor) in sensor.roms().enumerate() {
-                                        let sensor_id = sensor;
-                                        let sensor_hash = crc32fast::hash(
-                                            &((sensor_id & 0x00ffffff_ffffffff) >> 8).to_le_bytes(),
-                                        );
-                                        stree.add_child(
-                                        format!(
-                                            "[Sensor {:ndigits$}] 0x{:016x} 0x{:08x}",
-                                            i + 1,
-                                            sensor_id,
-                                            sensor_hash,
-                                        ),
-                                        views::LinearLayout::horizontal()
-                                            .child(views::Button::new("ON", move |s| {
-                                                s.with_user_data(|sensors: &mut TempSensors| {
-                                                sensors.toggle_led(idx, i, true);
-                                                log::info!(
-                                                    "[TMP] Toggled LED ON for sensor {} on bus {}",
-                                                    i,
-                                                    idx
-                                                );
-                                            });
-                                            }).fixed_width(5))
-                                            .child(views::Button::new("OFF", move |s| {
-                                                s.with_user_data(|sensors: &mut TempSensors| {
-                                                sensors.toggle_led(idx, i, false);
-                                                log::info!(
-                                                    "[TMP] Toggled LED OFF for sensor {} on bus {}",
-                                                    i,
-                                                    idx
-                                                );
-                                            });
-                                            }).fixed_width(5))
-                                            .child(views::Button::new("MEASURE", move |s| {
-                                                let res = s.with_user_data(|sensors: &mut TempSensors| {
-                                                    sensors.read_temperature(idx, i, true)
-                                                }).unwrap();
-                                                s.add_layer(
-                                                    Dialog::text(
-                                                        res.map_or_else(
-                                                            |e| format!("Error: {}", e),
-                                                            |temp| format!("Temperature: {:.2}°C", temp),
-                                                        ),
-                                                    ).title(format!(
-                                                        "Bus {:ndigits$} 0x{:016x} 0x{:08x}",
-                                                        i + 1,
-                                                        sensor_id,
-                                                        sensor_hash,
-                                                    ))
-                                                    .button("OK", |s| {
-                                                        s.pop_layer();
-                                                    }),
-                                                );
-                                            }).fixed_width(11)),
-                                    );
-                                    }
-                                })
-                            }) {
-                                s.add_layer(
-                                    Dialog::new()
-                                        .title(format!("I2C Bus {}", idx + 1))
-                                        .content(subtree)
-                                        .button("All ON", move |s| {
-                                            s.with_user_data(|sensors: &mut TempSensors| {
-                                                sensors.toggle_led_all(idx, true);
-                                                log::info!(
-                                                    "[TMP] Toggled all LEDs ON for bus {}",
-                                                    idx
-                                                );
-                                            });
-                                        })
-                                        .button("All OFF", move |s| {
-                                            s.with_user_data(|sensors: &mut TempSensors| {
-                                                sensors.toggle_led_all(idx, false);
-                                                log::info!(
-                                                    "[TMP] Toggled all LEDs OFF for bus {}",
-                                                    idx
-                                                );
-                                            });
-                                        })
-                                        .button("Back", |s| {
-                                            s.pop_layer();
-                                        }),
-                                );
-                            }
+                            show_bus(s, idx);
                         })
                         .fixed_width(16),
                     )
                     .child(views::Button::new("Enumerate", move |s| {
-                        s.with_user_data(|sensors: &mut TempSensors| {
+                        with_sensors(s, |sensors: &mut TempSensors| {
                             if let Some(sensor) = sensors.sensors.get_mut(idx) {
                                 if let Err(e) = sensor.enumerate(&mut sensors.buses[idx]) {
                                     log::error!(
@@ -162,10 +216,295 @@ fn main() {
                     })),
             );
         }
+        for (path, error) in failed {
+            tree.add_child(
+                "Unavailable",
+                views::LinearLayout::horizontal()
+                    .child(
+                        views::Button::new(path.clone(), move |s| {
+                            s.add_layer(
+                                Dialog::text(error.clone()).title("Bus unavailable").button(
+                                    "OK",
+                                    |s| {
+                                        s.pop_layer();
+                                    },
+                                ),
+                            );
+                        })
+                        .fixed_width(16),
+                    )
+                    .child(views::Button::new("Retry", move |s| {
+                        with_sensors(s, |sensors: &mut TempSensors| {
+                            sensors.failed.retain(|(other, _)| *other != path)
+                        });
+                        enumerate_buses(s, vec![path.clone()]);
+                    })),
+            );
+        }
     });
+    s.call_on_name(BUSES, |dialog: &mut Dialog| {
+        dialog.set_content(list);
+    });
+}
 
-    siv.add_layer(Dialog::new().title("I2C Buses").content(list));
-    siv.run();
+/// Show the sensors of bus `idx`, in the order they are stored in the group.
+fn show_bus(s: &mut Cursive, idx: usize) {
+    if let Some(subtree) = with_sensors(s, |sensors: &mut TempSensors| {
+        ListView::new().with(|stree| {
+            let sensor = &sensors.sensors[idx];
+            let ndigits = sensor.roms().count().checked_ilog10().unwrap_or(0) as usize + 1;
+            for (i, sensor) in sensor.roms().enumerate() {
+                let sensor_id = sensor;
+                let sensor_hash = SensorId::from_rom(sensor_id).value();
+                let label = sensors.labels.get(&sensor_id).cloned().unwrap_or_default();
+                stree.add_child(
+                    format!(
+                        "[Sensor {:ndigits$}] 0x{:016x} 0x{:08x}",
+                        i + 1,
+                        sensor_id,
+                        sensor_hash,
+                    ),
+                    views::LinearLayout::horizontal()
+                        .child(
+                            views::Button::new("ON", move |s| {
+                                with_sensors(s, |sensors: &mut TempSensors| {
+                                    sensors.toggle_led(idx, i, true);
+                                    log::info!(
+                                        "[TMP] Toggled LED ON for sensor {} on bus {}",
+                                        i,
+                                        idx
+                                    );
+                                });
+                            })
+                            .fixed_width(5),
+                        )
+                        .child(
+                            views::Button::new("OFF", move |s| {
+                                with_sensors(s, |sensors: &mut TempSensors| {
+                                    sensors.toggle_led(idx, i, false);
+                                    log::info!(
+                                        "[TMP] Toggled LED OFF for sensor {} on bus {}",
+                                        i,
+                                        idx
+                                    );
+                                });
+                            })
+                            .fixed_width(5),
+                        )
+                        .child(
+                            views::Button::new("MEASURE", move |s| {
+                                let res = with_sensors(s, |sensors: &mut TempSensors| {
+                                    sensors.read_temperature(idx, i, true)
+                                })
+                                .unwrap();
+                                s.add_layer(
+                                    Dialog::text(res.map_or_else(
+                                        |e| format!("Error: {}", e),
+                                        |temp| format!("Temperature: {:.2}°C", temp),
+                                    ))
+                                    .title(format!(
+                                        "Bus {:ndigits$} 0x{:016x} 0x{:08x}",
+                                        i + 1,
+                                        sensor_id,
+                                        sensor_hash,
+                                    ))
+                                    .button("OK", |s| {
+                                        s.pop_layer();
+                                    }),
+                                );
+                            })
+                            .fixed_width(11),
+                        )
+                        .child(
+                            EditView::new()
+                                .content(label)
+                                .on_edit(move |s, label, _| {
+                                    with_sensors(s, |sensors: &mut TempSensors| {
+                                        sensors.set_label(sensor_id, label);
+                                    });
+                                })
+                                .fixed_width(20),
+                        )
+                        .child(
+                            TextView::new("--.-- °C")
+                                .with_name(readout_name(idx, sensor_id))
+                                .fixed_width(10),
+                        ),
+                );
+            }
+        })
+    }) {
+        s.add_layer(
+            Dialog::new()
+                .title(format!("I2C Bus {}", idx + 1))
+                .content(
+                    views::LinearLayout::vertical()
+                        .child(subtree)
+                        .child(TextView::new("").with_name(blink_name(idx))),
+                )
+                .button("All ON", move |s| {
+                    with_sensors(s, |sensors: &mut TempSensors| {
+                        sensors.toggle_led_all(idx, true);
+                        log::info!("[TMP] Toggled all LEDs ON for bus {}", idx);
+                    });
+                })
+                .button("All OFF", move |s| {
+                    with_sensors(s, |sensors: &mut TempSensors| {
+                        sensors.toggle_led_all(idx, false);
+                        log::info!("[TMP] Toggled all LEDs OFF for bus {}", idx);
+                    });
+                })
+                .button("Blink", move |s| {
+                    start_blink(s, idx);
+                })
+                .button("Stop", |s| {
+                    with_sensors(s, TempSensors::stop_blink);
+                })
+                .button("Chain order", move |s| {
+                    let res = with_sensors(s, |sensors: &mut TempSensors| sensors.chain_order(idx))
+                        .unwrap();
+                    match res {
+                        Ok(()) => {
+                            s.pop_layer();
+                            show_bus(s, idx);
+                        }
+                        Err(e) => {
+                            s.add_layer(Dialog::text(e).title("Chain order").button("OK", |s| {
+                                s.pop_layer();
+                            }));
+                        }
+                    }
+                })
+                .button("Back", |s| {
+                    with_sensors(s, TempSensors::stop_blink);
+                    s.pop_layer();
+                }),
+        );
+    }
+}
+
+/// Run `f` on the sensors shared with the readout thread.
+fn with_sensors<R>(s: &mut Cursive, f: impl FnOnce(&mut TempSensors) -> R) -> Option<R> {
+    s.with_user_data(|sensors: &mut Arc<Mutex<TempSensors>>| {
+        f(&mut sensors.lock().expect("Sensors lock poisoned"))
+    })
+}
+
+/// Name of the view showing the sensor being blinked on bus `bus_idx`.
+fn blink_name(bus_idx: usize) -> String {
+    format!("blink-{bus_idx}")
+}
+
+/// Light the LED of each sensor on bus `idx` in turn, until [`TempSensors::stop_blink`] is called.
+fn start_blink(s: &mut Cursive, idx: usize) {
+    let Some(sensors) = s.user_data::<Arc<Mutex<TempSensors>>>().cloned() else {
+        return;
+    };
+    let running = Arc::new(AtomicBool::new(true));
+    let period = {
+        let mut sensors = sensors.lock().expect("Sensors lock poisoned");
+        sensors.stop_blink();
+        sensors.blinking = Some((idx, running.clone()));
+        sensors.blink_period
+    };
+    log::info!("[TMP] Blinking sensors on bus {idx}");
+    let cb_sink = s.cb_sink().clone();
+    thread::spawn(move || {
+        let mut pos = 0;
+        loop {
+            let status = {
+                let sensors = &mut *sensors.lock().expect("Sensors lock poisoned");
+                // checked under the lock, so that no LED is lit after the blink is stopped
+                if !running.load(Ordering::Relaxed) {
+                    break;
+                }
+                let Some((i, count, rom)) = sensors.blink_step(idx, pos) else {
+                    break;
+                };
+                pos = i + 1;
+                format!(
+                    "Blinking sensor {}/{count}: 0x{rom:016x} 0x{}",
+                    i + 1,
+                    SensorId::from_rom(rom)
+                )
+            };
+            let update = cb_sink.send(Box::new(move |s| {
+                s.call_on_name(&blink_name(idx), |view: &mut TextView| {
+                    view.set_content(status)
+                });
+            }));
+            if update.is_err() {
+                return;
+            }
+            thread::sleep(period);
+        }
+        let _ = cb_sink.send(Box::new(move |s| {
+            s.call_on_name(&blink_name(idx), |view: &mut TextView| view.set_content(""));
+        }));
+    });
+}
+
+/// Name of the view showing the temperature of sensor `rom` on bus `bus_idx`.
+fn readout_name(bus_idx: usize, rom: u64) -> String {
+    format!("temp-{bus_idx}-{rom:016x}")
+}
+
+/// Read out all sensors every `interval`, and show the temperatures in the sensor rows.
+///
+/// The sensors are only locked while talking to the bus, not while the conversion runs, so that
+/// the UI stays responsive. The thread stops once the UI is closed.
+fn spawn_readout(sensors: Arc<Mutex<TempSensors>>, cb_sink: CbSink, interval: Duration) {
+    thread::spawn(move || {
+        loop {
+            let conversion = {
+                let sensors = &mut *sensors.lock().expect("Sensors lock poisoned");
+                let mut conversion = Duration::ZERO;
+                for (idx, (bus, group)) in sensors
+                    .buses
+                    .iter_mut()
+                    .zip(sensors.sensors.iter_mut())
+                    .enumerate()
+                {
+                    if let Err(e) = group.start_temperature_conversion(bus) {
+                        log::error!("[TMP] Failed to start conversion on bus {idx}: {e:?}");
+                    }
+                    conversion = conversion.max(group.conversion_time());
+                }
+                conversion
+            };
+            thread::sleep(conversion);
+            let readings: Vec<(usize, u64, Result<Temperature, ReadError>)> = {
+                let sensors = &mut *sensors.lock().expect("Sensors lock poisoned");
+                sensors
+                    .buses
+                    .iter_mut()
+                    .zip(sensors.sensors.iter_mut())
+                    .enumerate()
+                    .flat_map(|(idx, (bus, group))| {
+                        group
+                            .read_temperatures_detailed(bus, true)
+                            .map(|(rom, res)| (idx, rom, res))
+                            .collect::<Vec<_>>()
+                    })
+                    .collect()
+            };
+            let update = cb_sink.send(Box::new(move |s| {
+                for (idx, rom, res) in readings {
+                    let text = match res {
+                        Ok(temp) => format!("{:.2} °C", temp.celsius()),
+                        Err(e) => format!("{e:?}"),
+                    };
+                    s.call_on_name(&readout_name(idx, rom), |view: &mut TextView| {
+                        view.set_content(text)
+                    });
+                }
+            }));
+            if update.is_err() {
+                break;
+            }
+            thread::sleep(interval.saturating_sub(conversion));
+        }
+    });
 }
 
 fn add_quit_layer(s: &mut cursive::Cursive) {
@@ -178,109 +517,180 @@ fn add_quit_layer(s: &mut cursive::Cursive) {
     )
 }
 
+/// DS2484 bridge on an I2C bus.
+type Bridge = Ds2484<linux_embedded_hal::I2cdev, linux_embedded_hal::Delay>;
+
+/// Open the DS2484 bridge on the I2C bus at `path`, and enumerate its sensors.
+///
+/// A bus whose sensors fail to enumerate is still opened, so that the enumeration can be retried.
+fn open_bus(path: &str) -> Result<(Bridge, Ds28ea00Group<32>), String> {
+    let i2c = linux_embedded_hal::I2cdev::new(path).map_err(|e| {
+        log::error!("[TMP] {path}> Failed to open I2C device: {e:?}");
+        format!("Failed to open I2C device: {e:?}")
+    })?;
+    let mut ds2484 = ds2484::Ds2484Builder::default()
+        .build(i2c, linux_embedded_hal::Delay)
+        .map_err(|e| {
+            log::error!("[TMP] {path}> Failed to create DS2484 instance: {e:?}");
+            format!("Failed to create DS2484 instance: {e:?}")
+        })?;
+    log::info!("[TMP] {path}> DS2484 instance created successfully");
+    let mut cfg = ds2484::DeviceConfiguration::default();
+    cfg.read(&mut ds2484).map_err(|e| {
+        log::error!("[TMP] {path}> Failed to read device configuration: {e:?}");
+        format!("Failed to read device configuration: {e:?}")
+    })?;
+    cfg.set_active_pullup(true);
+    cfg.write(&mut ds2484).map_err(|e| {
+        log::error!("[TMP] {path}> Failed to write device configuration: {e:?}");
+        format!("Failed to write device configuration: {e:?}")
+    })?;
+    // Set the port configuration
+    let mut port_cfg = ds2484::OneWireConfigurationBuilder::default()
+        .reset_pulse(440000, 44000)
+        .presence_detect_time(58000, 5500)
+        .write_zero_low_time(52000, 5000)
+        .write_zero_recovery_time(2750)
+        .weak_pullup_resistor(1000)
+        .build();
+    port_cfg.write(&mut ds2484).map_err(|e| {
+        log::error!("[TMP] {path}> Failed to write port configuration: {e:?}");
+        format!("Failed to write port configuration: {e:?}")
+    })?;
+    log::info!("[TMP] {path}> Port configuration written successfully");
+    let mut sensors = Ds28ea00Group::default()
+        .with_toggle_pio(false)
+        .with_sort_order(SortOrder::Key(|rom| SensorId::from_rom(rom).value()));
+    match sensors.enumerate(&mut ds2484) {
+        Ok(n) => log::info!("[TMP] {path}> Found {n} sensors"),
+        Err(e) => log::error!("[TMP] {path}> Failed to enumerate sensors: {e:?}"),
+    }
+    Ok((ds2484, sensors))
+}
+
 pub struct TempSensors {
     pub paths: Vec<String>,
-    pub buses: Vec<Ds2484<linux_embedded_hal::I2cdev, linux_embedded_hal::Delay>>,
+    pub buses: Vec<Bridge>,
     pub sensors: Vec<ds28ea00::Ds28ea00Group<32>>,
+    /// Buses that failed to open, with the reason.
+    pub failed: Vec<(String, String)>,
+    pub labels: HashMap<u64, String>,
+    pub blink_period: Duration,
+    /// Bus whose sensors are being blinked, and the flag to stop the blinking.
+    pub blinking: Option<(usize, Arc<AtomicBool>)>,
 }
 
 use glob::glob;
 use linux_embedded_hal::Delay;
 impl TempSensors {
-    fn new() -> Self {
-        let mut paths = Vec::new();
-        let mut buses = Vec::new();
-        let mut sensors = Vec::new();
-
-        for path in glob("/dev/i2c-*").expect("Failed to find I2C devices") {
-            match path {
-                Ok(path) => {
-                    let lpath = path.to_string_lossy();
-                    log::info!("[TMP] Found I2C device: {lpath}");
-                    match linux_embedded_hal::I2cdev::new(&path) {
-                        Err(e) => {
-                            log::error!("[TMP] {lpath}> Failed to open I2C device: {e:?}");
-                            continue;
-                        }
-                        Ok(i2c) => {
-                            match ds2484::Ds2484Builder::default()
-                                .build(i2c, linux_embedded_hal::Delay)
-                            {
-                                Err(e) => {
-                                    log::error!(
-                                        "[TMP] {lpath}> Failed to create DS2484 instance: {e:?}"
-                                    );
-                                    continue;
-                                }
-                                Ok(mut ds2484) => {
-                                    log::info!(
-                                        "[TMP] {lpath}> DS2484 instance created successfully"
-                                    );
-                                    let mut cfg = ds2484::DeviceConfiguration::default();
-                                    if let Err(e) = cfg.read(&mut ds2484) {
-                                        log::error!(
-                                            "[TMP] {lpath}> Failed to read device configuration: {e:?}",
-                                        );
-                                        continue;
-                                    }
-                                    cfg.set_active_pullup(true);
-                                    if let Err(e) = cfg.write(&mut ds2484) {
-                                        log::error!(
-                                            "[TMP] {lpath}> Failed to write device configuration: {e:?}",
-                                        );
-                                        continue;
-                                    }
-                                    // Set the port configuration
-                                    let mut port_cfg =
-                                        ds2484::OneWireConfigurationBuilder::default()
-                                            .reset_pulse(440000, 44000)
-                                            .presence_detect_time(58000, 5500)
-                                            .write_zero_low_time(52000, 5000)
-                                            .write_zero_recovery_time(2750)
-                                            .weak_pullup_resistor(1000)
-                                            .build();
-                                    if let Err(e) = port_cfg.write(&mut ds2484) {
-                                        log::error!(
-                                            "[TMP] {lpath}> Failed to write port configuration: {e:?}",
-                                        );
-                                        continue;
-                                    } else {
-                                        log::info!(
-                                            "[TMP] {lpath}> Port configuration written successfully"
-                                        );
-                                    }
-                                    let mut tmpsensors =
-                                        Ds28ea00Group::default().with_toggle_pio(false);
-                                    match tmpsensors.enumerate(&mut ds2484) {
-                                        Ok(n) => {
-                                            log::info!("[TMP] {lpath}> Found {n} sensors");
-                                        }
-                                        Err(e) => {
-                                            log::error!(
-                                                "[TMP] {lpath}> Failed to enumerate sensors: {e:?}"
-                                            );
-                                        }
-                                    }
-                                    paths.push(lpath.to_string());
-                                    buses.push(ds2484);
-                                    sensors.push(tmpsensors);
-                                }
-                            }
-                        }
-                    }
-                }
-                Err(e) => log::error!("Failed to read glob pattern: {}", e),
+    fn new(blink_period: Duration) -> Self {
+        TempSensors {
+            paths: Vec::new(),
+            buses: Vec::new(),
+            sensors: Vec::new(),
+            failed: Vec::new(),
+            labels: HashMap::new(),
+            blink_period,
+            blinking: None,
+        }
+    }
+
+    /// Add a bus opened by [`open_bus`], or record the reason it failed to open.
+    pub fn add_bus(&mut self, path: String, bus: Result<(Bridge, Ds28ea00Group<32>), String>) {
+        match bus {
+            Ok((bus, sensors)) => {
+                log::info!("[TMP] {path}> Added as I2C Bus {}", self.buses.len() + 1);
+                self.paths.push(path);
+                self.buses.push(bus);
+                self.sensors.push(sensors);
             }
+            Err(e) => self.failed.push((path, e)),
         }
+    }
 
-        log::info!("[TMP] Found {} I2C devices", buses.len());
-        TempSensors {
-            paths,
-            buses,
-            sensors,
+    /// Light the LED of the sensor at `sensor_idx` on bus `bus_idx`, wrapping around the end of
+    /// the group, and turn the others off.
+    ///
+    /// # Returns
+    /// The index of the lit sensor, the number of sensors and the ROM of the lit sensor, or `None`
+    /// if there are no sensors on the bus.
+    pub fn blink_step(&mut self, bus_idx: usize, sensor_idx: usize) -> Option<(usize, usize, u64)> {
+        let bus = self.buses.get_mut(bus_idx)?;
+        let sensors = self.sensors.get(bus_idx)?;
+        let count = sensors.roms().count();
+        if count == 0 {
+            return None;
+        }
+        let idx = sensor_idx % count;
+        let rom = sensors.roms().nth(idx)?;
+        if let Err(e) = sensors
+            .led_toggle_all(bus, false)
+            .and_then(|_| sensors.led_toggle(bus, rom, true))
+        {
+            log::error!("[TMP] Failed to blink sensor {idx} on bus {bus_idx}: {e:?}");
+        }
+        Some((idx, count, rom))
+    }
+
+    /// Stop blinking the sensors, and turn their LEDs off.
+    pub fn stop_blink(&mut self) {
+        if let Some((bus_idx, running)) = self.blinking.take() {
+            running.store(false, Ordering::Relaxed);
+            self.toggle_led_all(bus_idx, false);
+        }
+    }
+
+    /// Sort the sensors on bus `bus_idx` in the physical order of the chain.
+    pub fn chain_order(&mut self, bus_idx: usize) -> Result<(), String> {
+        let (Some(bus), Some(sensors)) =
+            (self.buses.get_mut(bus_idx), self.sensors.get_mut(bus_idx))
+        else {
+            return Err(format!("No bus found at index {bus_idx}"));
+        };
+        match sensors.discover_chain_order(bus) {
+            Ok(n) => {
+                log::info!(
+                    "[TMP] Found {n} of {} sensors in the chain on bus {bus_idx}",
+                    sensors.roms().count()
+                );
+                Ok(())
+            }
+            Err(e) => {
+                log::error!("[TMP] Failed to discover the chain order on bus {bus_idx}: {e:?}");
+                Err(format!(
+                    "Failed to discover the chain order on bus {bus_idx}: {e:?}"
+                ))
+            }
+        }
+    }
+
+    /// Set the label of the sensor with the given ROM. An empty label removes it.
+    pub fn set_label(&mut self, rom: u64, label: &str) {
+        let label = label.trim();
+        if label.is_empty() {
+            self.labels.remove(&rom);
+        } else {
+            self.labels.insert(rom, label.to_string());
         }
     }
 
+    /// Get the labelled sensors on all buses, in bus and chain order.
+    pub fn entries(&self) -> Vec<export::Entry> {
+        self.paths
+            .iter()
+            .zip(self.sensors.iter())
+            .flat_map(|(bus, sensors)| {
+                sensors.roms().filter_map(|rom| {
+                    Some(export::Entry {
+                        bus: bus.clone(),
+                        rom,
+                        label: self.labels.get(&rom)?.clone(),
+                    })
+                })
+            })
+            .collect()
+    }
+
     pub fn toggle_led(&mut self, bus_idx: usize, sensor_idx: usize, enable: bool) {
         if let Some(bus) = self.buses.get_mut(bus_idx) {
             if let Some(sensor) = self.sensors.get_mut(bus_idx) {
@@ -326,13 +736,13 @@ impl TempSensors {
                     match sensor.read_temperature(bus, &mut Delay, rom, crc) {
                         Ok(temp) => {
                             log::info!(
-                                "[TMP] Temperature for sensor {} on bus {}: {:.2}°C [{:?}]",
+                                "[TMP] Temperature for sensor {} on bus {}: {:.2} [{:?}]",
                                 sensor_idx,
                                 bus_idx,
                                 temp,
                                 temp
                             );
-                            Ok(f32::from(temp))
+                            Ok(temp.celsius())
                         }
                         Err(e) => {
                             log::error!(
@@ -368,17 +778,17 @@ impl TempSensors {
     }
 
     pub fn toggle_led_all(&mut self, bus_idx: usize, enable: bool) {
-        if let Some(bus) = self.buses.get_mut(bus_idx) {
-            if let Some(sensors) = self.sensors.get_mut(bus_idx) {
-                if let Err(e) = sensors.led_toggle_all(bus, enable) {
-                    log::error!(
-                        "[TMP] Failed to toggle all LEDs on bus {}: {:?}",
-                        bus_idx,
-                        e
-                    );
-                } else {
-                    log::info!("[TMP] Successfully toggled all LEDs on bus {}", bus_idx);
-                }
+        if let Some(bus) = self.buses.get_mut(bus_idx)
+            && let Some(sensors) = self.sensors.get_mut(bus_idx)
+        {
+            if let Err(e) = sensors.led_toggle_all(bus, enable) {
+                log::error!(
+                    "[TMP] Failed to toggle all LEDs on bus {}: {:?}",
+                    bus_idx,
+                    e
+                );
+            } else {
+                log::info!("[TMP] Successfully toggled all LEDs on bus {}", bus_idx);
             }
         }
     }
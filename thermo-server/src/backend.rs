@@ -0,0 +1,69 @@
+use std::time::{Duration, Instant};
+
+use crate::{Metadata, Readings, SensorEntry, control::Command};
+
+/// A source of measurements driven by the scheduler in `main`.
+///
+/// Every backend runs on its own thread. The scheduler calls [`SensorBackend::init`] until it
+/// succeeds, then calls [`SensorBackend::begin_acquire`] and [`SensorBackend::acquire`] every
+/// [`SensorBackend::poll_interval`] and forwards the measurements to the data sink. If acquisition
+/// fails, the backend is initialized again.
+pub trait SensorBackend: Send {
+    /// Label used as a prefix for log messages, e.g. `[TMP] /dev/i2c-1`.
+    fn name(&self) -> String;
+
+    /// Short name of the bus, e.g. `i2c-1`, that the measurements are tagged with.
+    fn bus(&self) -> String;
+
+    /// Path of the bus, e.g. `/dev/i2c-1`.
+    fn path(&self) -> String {
+        self.bus()
+    }
+
+    /// Open the bus and set up the sensors.
+    fn init(&mut self) -> Result<(), String>;
+
+    /// Sensors found by the last [`SensorBackend::init`], announced after every initialization.
+    fn inventory(&self) -> Vec<SensorEntry> {
+        Vec::new()
+    }
+
+    /// Interval between the start of two acquisitions.
+    fn poll_interval(&self) -> Duration {
+        Duration::from_secs(1)
+    }
+
+    /// Start an acquisition that completes on its own, e.g. a temperature conversion.
+    ///
+    /// The scheduler handles the commands of the backend until the returned deadline, then reads the
+    /// sensors out with [`SensorBackend::acquire`]. Backends without such a step return `None`.
+    fn begin_acquire(&mut self) -> Result<Option<Instant>, String> {
+        Ok(None)
+    }
+
+    /// Read out the sensors, after the acquisition started by [`SensorBackend::begin_acquire`] if
+    /// any, or starting and completing one otherwise.
+    fn acquire(&mut self) -> Result<Vec<Readings>, String>;
+
+    /// Apply a command received at runtime, between two acquisitions.
+    ///
+    /// [`Command::SetPollInterval`] and [`Command::Enumerate`] are handled by the scheduler and
+    /// never reach the backend.
+    ///
+    /// # Returns
+    /// A short description of the outcome, e.g. the listed sensors, or the reason the command
+    /// was rejected.
+    fn command(&mut self, command: &Command) -> Result<String, String> {
+        let _ = command;
+        Err("unsupported command".into())
+    }
+}
+
+/// Inventory of a backend, as sent after every initialization.
+pub fn metadata(backend: &(impl SensorBackend + ?Sized)) -> Readings {
+    Readings::Metadata(Metadata {
+        version: env!("CARGO_PKG_VERSION").into(),
+        path: backend.path(),
+        sensors: backend.inventory(),
+    })
+}
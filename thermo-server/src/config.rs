@@ -0,0 +1,854 @@
+use std::{collections::HashMap, net::SocketAddr, path::PathBuf, time::Duration};
+
+use ds28ea00::ReadoutResolution;
+use hdc1010::{HumidityResolution, TemperatureResolution};
+use log::LevelFilter;
+use piccthermo_id::{Pattern, SensorFilter, SensorId};
+use serde::Deserialize;
+
+use crate::Args;
+
+/// Server configuration, loaded from a TOML file with `--config` or assembled from the
+/// command line arguments.
+///
+/// ```toml
+/// leds = true
+/// watchdog_ms = 30000
+/// sync_interval_ms = 60000
+/// cpu_exclude = ["^nvme"]
+/// cpu_source = "sysfs"
+///
+/// [serial]
+/// port = "/dev/ttyGS0"
+/// baud = 115200
+/// parity = "none"
+/// flow_control = "none"
+/// timeout_ms = 1000
+/// buffer = "/var/lib/thermo/serial.buf"
+/// batch_ms = 50
+/// max_bytes_per_sec = 8000
+/// backup = "/dev/ttyUSB0"
+/// failover_after = 3
+/// failback_ms = 60000
+///
+/// [[sink]]
+/// type = "tcp"
+/// bind = "0.0.0.0:9000"
+///
+/// [[sink]]
+/// type = "unix"
+/// path = "/run/thermo/measurements.sock"
+/// format = "json"
+///
+/// [[sink]]
+/// type = "csv"
+/// path = "/var/log/thermo/measurements.csv"
+/// max_bytes = 10485760
+/// keep = 5
+///
+/// [[bus]]
+/// path = "/dev/i2c-1"
+/// sensor = "ds28ea00"
+/// resolution = 12
+/// exclude = ["0xdeadbeef", "spare-*"]
+///
+/// [[bus]]
+/// path = "/dev/i2c-2"
+/// sensor = "ds28ea00"
+/// roms = ["0x5e00000000123442", "0x1f00000000567842"]
+/// expected = ["0x1a2b3c4d", "0x5e6f7a8b"]
+///
+/// [[bus]]
+/// path = "/dev/i2c-3"
+/// sensor = "hdc1010"
+/// poll_interval_ms = 2000
+/// stuck_samples = 10
+///
+/// [filter.hdc1010]
+/// smoothing = { type = "exponential", alpha = 0.3 }
+/// threshold = 0.5
+/// max_hold = 60
+///
+/// [gradient]
+/// max_rate = 2.0
+/// window_ms = 60000
+///
+/// [names]
+/// "0x1a2b3c4d" = "Chamber top"
+///
+/// [simulate]
+/// sensors = 8
+/// drift = 0.05
+/// noise = 0.1
+/// dropout = 0.01
+///
+/// [log]
+/// level = "info"
+/// file = "/var/log/thermo/server.jsonl"
+/// max_bytes = 10485760
+/// keep = 5
+///
+/// [log.modules]
+/// "thermo_server::serial_comm" = "debug"
+/// ```
+///
+/// Labels, locations and calibrations of individual sensors are loaded from the
+/// [`SensorMap`](crate::sensor_map::SensorMap) file given by `sensor_map`.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    /// Serial port for data sink. Measurements are only logged if not set.
+    #[serde(default)]
+    pub serial: Option<SerialConfig>,
+    /// Additional sinks the measurements are written to.
+    #[serde(default, rename = "sink")]
+    pub sinks: Vec<SinkConfig>,
+    /// Enable LED control on the 1-Wire buses.
+    #[serde(default)]
+    pub leds: bool,
+    /// Report the CPU temperatures.
+    #[serde(default = "default_true")]
+    pub cpu: bool,
+    /// Regular expressions matching the labels of the CPU components to report. All components
+    /// are reported if empty.
+    #[serde(default)]
+    pub cpu_include: Vec<String>,
+    /// Regular expressions matching the labels of the CPU components to leave out.
+    #[serde(default)]
+    pub cpu_exclude: Vec<String>,
+    /// Where the CPU temperatures are read from.
+    #[serde(default)]
+    pub cpu_source: CpuSource,
+    /// Sensor buses.
+    #[serde(default, rename = "bus")]
+    pub buses: Vec<BusConfig>,
+    /// Filters applied to the readings before they are published, by sensor family.
+    #[serde(default, rename = "filter")]
+    pub filters: HashMap<SensorType, FilterConfig>,
+    /// Alarm on temperatures changing faster than a limit, on every bus.
+    #[serde(default)]
+    pub gradient: Option<GradientConfig>,
+    /// Human readable sensor names, keyed by hexadecimal sensor ID.
+    #[serde(default, deserialize_with = "deserialize_names")]
+    pub names: HashMap<u32, String>,
+    /// Sensor map file with labels, locations and calibrations.
+    #[serde(default)]
+    pub sensor_map: Option<PathBuf>,
+    /// Time after which an acquisition thread stuck on its bus is restarted, in milliseconds,
+    /// on top of the poll interval.
+    #[serde(default = "default_watchdog_ms")]
+    pub watchdog_ms: u64,
+    /// Interval between two sync frames, which map the sequence numbers to the wall-clock time,
+    /// in milliseconds. No sync frames are sent periodically if zero.
+    #[serde(default = "default_sync_interval_ms")]
+    pub sync_interval_ms: u64,
+    /// Replace the sensors of every bus with synthetic ones.
+    #[serde(default)]
+    pub simulate: Option<SimulationConfig>,
+    /// Log levels and the structured log file.
+    #[serde(default)]
+    pub log: Option<LogConfig>,
+}
+
+/// Serial port settings.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SerialConfig {
+    /// Path to the serial port.
+    pub port: String,
+    /// Baud rate.
+    #[serde(default = "default_baud")]
+    pub baud: u32,
+    /// Parity bit.
+    #[serde(default)]
+    pub parity: Parity,
+    /// Flow control.
+    #[serde(default)]
+    pub flow_control: FlowControl,
+    /// Timeout of the writes to the serial port, in milliseconds. The sink is opened again when
+    /// a write times out.
+    #[serde(default = "default_serial_timeout_ms")]
+    pub timeout_ms: u64,
+    /// File measurements are buffered in while the serial port is disconnected.
+    #[serde(default)]
+    pub buffer: Option<PathBuf>,
+    /// Size of the buffer, in bytes. The oldest measurements are dropped when it is full.
+    #[serde(default = "default_buffer_bytes")]
+    pub buffer_bytes: u64,
+    /// Measurements queued within this time of each other are written as a single frame, in
+    /// milliseconds.
+    #[serde(default)]
+    pub batch_ms: u64,
+    /// Most bytes written to the serial port per second. The oldest measurements are dropped
+    /// when more than a second worth of them is queued.
+    #[serde(default)]
+    pub max_bytes_per_sec: Option<u64>,
+    /// Backup serial port, with the same settings, used while the primary port fails.
+    #[serde(default)]
+    pub backup: Option<String>,
+    /// Failures of the primary port in a row, to open or to write, after which the backup port
+    /// is used.
+    #[serde(default = "default_failover_after")]
+    pub failover_after: u32,
+    /// Interval between two attempts to fail back to the primary port while the backup port is
+    /// used, in milliseconds.
+    #[serde(default = "default_failback_ms")]
+    pub failback_ms: u64,
+}
+
+/// Parity bit of the serial port.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum Parity {
+    /// No parity bit.
+    #[default]
+    None,
+    /// Odd parity.
+    Odd,
+    /// Even parity.
+    Even,
+}
+
+/// Format of the measurements served on a UNIX socket.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum StreamFormat {
+    /// Newline-delimited JSON, one object per record, as written by the JSON sink.
+    #[default]
+    Json,
+    /// COBS-encoded binary frames, as served by the TCP sink.
+    Binary,
+}
+
+/// Flow control of the serial port.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum FlowControl {
+    /// No flow control.
+    #[default]
+    None,
+    /// XON/XOFF flow control.
+    Software,
+    /// RTS/CTS flow control.
+    Hardware,
+}
+
+impl From<Parity> for serialport::Parity {
+    fn from(value: Parity) -> Self {
+        match value {
+            Parity::None => serialport::Parity::None,
+            Parity::Odd => serialport::Parity::Odd,
+            Parity::Even => serialport::Parity::Even,
+        }
+    }
+}
+
+impl From<FlowControl> for serialport::FlowControl {
+    fn from(value: FlowControl) -> Self {
+        match value {
+            FlowControl::None => serialport::FlowControl::None,
+            FlowControl::Software => serialport::FlowControl::Software,
+            FlowControl::Hardware => serialport::FlowControl::Hardware,
+        }
+    }
+}
+
+/// Settings of an additional sink.
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum SinkConfig {
+    /// Newline-delimited JSON, written to a file or to stdout if the path is `-`.
+    Json { path: PathBuf },
+    /// CSV file, rotated when it grows beyond `max_bytes`, keeping `keep` rotated files.
+    Csv {
+        path: PathBuf,
+        #[serde(default = "default_max_bytes")]
+        max_bytes: u64,
+        #[serde(default = "default_keep")]
+        keep: usize,
+    },
+    /// Binary frames served to TCP clients, dropping the oldest of `queue` frames for slow clients.
+    Tcp {
+        bind: SocketAddr,
+        #[serde(default = "default_queue")]
+        queue: usize,
+    },
+    /// Binary frames sent as UDP datagrams, e.g. to a multicast group.
+    Udp {
+        addr: SocketAddr,
+        #[serde(default = "default_ttl")]
+        ttl: u32,
+    },
+    /// Measurements served to local processes on a UNIX socket, dropping the oldest of `queue`
+    /// frames for slow clients.
+    Unix {
+        path: PathBuf,
+        #[serde(default)]
+        format: StreamFormat,
+        #[serde(default = "default_queue")]
+        queue: usize,
+    },
+    /// Measurements published to an MQTT broker.
+    #[cfg(feature = "mqtt")]
+    Mqtt {
+        host: String,
+        #[serde(default = "default_mqtt_port")]
+        port: u16,
+        /// Topic prefix.
+        #[serde(default = "default_mqtt_prefix")]
+        prefix: String,
+        /// Quality of service: 0, 1 or 2.
+        #[serde(default)]
+        qos: u8,
+        /// Retain the last value of every topic.
+        #[serde(default)]
+        retain: bool,
+        /// Publish Home Assistant discovery payloads.
+        #[serde(default = "default_true")]
+        discovery: bool,
+    },
+    /// Prometheus metrics served over HTTP.
+    #[cfg(feature = "metrics")]
+    Metrics { bind: SocketAddr },
+    /// Status and control API served over HTTP.
+    #[cfg(feature = "http")]
+    Http { bind: SocketAddr },
+}
+
+/// Settings of a single sensor bus.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BusConfig {
+    /// Path to the I2C bus.
+    pub path: PathBuf,
+    /// Sensors connected to the bus.
+    pub sensor: SensorType,
+    /// Interval between two acquisitions, in milliseconds.
+    #[serde(default = "default_poll_interval_ms")]
+    pub poll_interval_ms: u64,
+    /// Measurement resolution in bits. Defaults to the highest resolution of the sensor, or for
+    /// DS28EA00 sensors, to the highest resolution whose conversion fits in the poll interval.
+    #[serde(default)]
+    pub resolution: Option<u8>,
+    /// Patterns of the sensors to leave out of the readout: ROM codes, IDs or label globs, see
+    /// [`Pattern`]. The 1-Wire sensors left out are not configured either.
+    #[serde(default, deserialize_with = "deserialize_patterns")]
+    pub exclude: Vec<Pattern>,
+    /// Patterns of the only sensors to read, or all sensors if empty.
+    #[serde(default, deserialize_with = "deserialize_patterns")]
+    pub include_only: Vec<Pattern>,
+    /// Enable 1-Wire overdrive mode.
+    #[serde(default = "default_true")]
+    pub overdrive: bool,
+    /// Number of identical consecutive readings after which the readings of a humidity sensor are
+    /// flagged as stuck. Stuck sensors are not detected if zero.
+    #[serde(default)]
+    pub stuck_samples: u8,
+    /// ROM codes of the 1-Wire sensors expected on the bus. If set, the bus is not searched and
+    /// exactly these sensors are read in the listed order, the missing ones being reported as
+    /// failed.
+    #[serde(default, deserialize_with = "deserialize_roms")]
+    pub roms: Vec<u64>,
+    /// IDs of the sensors expected on the bus. If set, missing and unexpected sensors are reported
+    /// after every enumeration and periodically at runtime.
+    #[serde(default, deserialize_with = "deserialize_ids")]
+    pub expected: Vec<u32>,
+}
+
+/// Synthetic sensors generating the readings of a bus, instead of the hardware.
+///
+/// Every channel follows a random walk, with noise added to every reading. The temperature buses
+/// hold up to 16 sensors, the humidity buses up to 4.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SimulationConfig {
+    /// Number of sensors on every bus.
+    #[serde(default = "default_sim_sensors")]
+    pub sensors: usize,
+    /// Largest step of the random walk per acquisition, in the unit of the reading.
+    #[serde(default = "default_sim_drift")]
+    pub drift: f32,
+    /// Largest noise added to a reading, in the unit of the reading.
+    #[serde(default = "default_sim_noise")]
+    pub noise: f32,
+    /// Probability, between 0 and 1, that a sensor is missing from an acquisition.
+    #[serde(default)]
+    pub dropout: f32,
+    /// Seed of the readings, combined with the bus path so that every bus reads differently.
+    #[serde(default)]
+    pub seed: u64,
+}
+
+/// Sensor family on a bus.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SensorType {
+    /// DS28EA00 (and compatible) temperature sensors behind a DS2484 1-Wire bridge.
+    Ds28ea00,
+    /// HDC1010 humidity sensors.
+    Hdc1010,
+    /// HDC3022 humidity sensors.
+    Hdc3022,
+}
+
+/// Filter applied to the temperature, humidity and dew point readings of a sensor family.
+///
+/// The readings of every sensor are smoothed, and a smoothed reading is only published once it
+/// differs from the last published one by at least `threshold`, which cuts the bandwidth used by
+/// slowly changing readings.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FilterConfig {
+    /// Smoothing of consecutive readings.
+    #[serde(default)]
+    pub smoothing: Smoothing,
+    /// Minimum change of a smoothed reading before it is published, in the unit of the reading.
+    #[serde(default)]
+    pub threshold: f32,
+    /// Publish a reading at least every `max_hold` acquisitions, even if it has not changed.
+    #[serde(default)]
+    pub max_hold: Option<u32>,
+}
+
+/// Logging settings.
+///
+/// The levels apply to the log file, and to the console unless `RUST_LOG` is set. The log file
+/// holds one JSON object per record, see [`logging`](crate::logging), and is rotated as the
+/// `csv` sink is.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct LogConfig {
+    /// Level of the records of every module not listed in `modules`.
+    #[serde(default = "default_log_level", deserialize_with = "deserialize_level")]
+    pub level: LevelFilter,
+    /// Levels by module path, e.g. `thermo_server::serial_comm`.
+    #[serde(default, deserialize_with = "deserialize_levels")]
+    pub modules: HashMap<String, LevelFilter>,
+    /// Log file. Records are only written to the console if not set.
+    #[serde(default)]
+    pub file: Option<PathBuf>,
+    /// Size beyond which the log file is rotated, in bytes.
+    #[serde(default = "default_max_bytes")]
+    pub max_bytes: u64,
+    /// Number of rotated log files kept.
+    #[serde(default = "default_keep")]
+    pub keep: usize,
+}
+
+/// Rate-of-change alarm on the temperature readings of every sensor of a bus.
+///
+/// The rate of a sensor is the change of its temperature over the last `window_ms`, in °C per
+/// minute. It catches a sudden heating or cooling failure well before the temperature drifts out
+/// of its expected range.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct GradientConfig {
+    /// Largest rate of change, either way, in °C per minute.
+    pub max_rate: f32,
+    /// Time over which the rate of change is measured, in milliseconds.
+    #[serde(default = "default_gradient_window_ms")]
+    pub window_ms: u64,
+}
+
+impl GradientConfig {
+    pub fn window(&self) -> Duration {
+        Duration::from_millis(self.window_ms)
+    }
+}
+
+/// Smoothing of consecutive readings of a sensor.
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum Smoothing {
+    /// Readings are not smoothed.
+    #[default]
+    None,
+    /// Mean of the last `window` readings.
+    Average { window: usize },
+    /// Exponential moving average, weighing the latest reading by `alpha` between 0 and 1.
+    Exponential { alpha: f32 },
+}
+
+/// Source of the CPU temperatures.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CpuSource {
+    /// The components listed by `sysinfo`, or the kernel interfaces if there are none.
+    #[default]
+    Auto,
+    /// The components listed by `sysinfo`.
+    Sysinfo,
+    /// The hwmon devices and thermal zones exposed by the kernel, read directly from sysfs.
+    Sysfs,
+}
+
+/// Humidity sensor family on the buses given on the command line.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum HumidityType {
+    /// HDC1010 humidity sensors.
+    Hdc1010,
+    /// HDC3022 humidity sensors.
+    Hdc3022,
+}
+
+impl From<HumidityType> for SensorType {
+    fn from(value: HumidityType) -> Self {
+        match value {
+            HumidityType::Hdc1010 => SensorType::Hdc1010,
+            HumidityType::Hdc3022 => SensorType::Hdc3022,
+        }
+    }
+}
+
+impl Config {
+    /// Load the configuration from a TOML file.
+    pub fn load(path: &PathBuf) -> Result<Self, String> {
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read {}: {e}", path.display()))?;
+        toml::from_str(&content).map_err(|e| format!("Failed to parse {}: {e}", path.display()))
+    }
+
+    /// Assemble the configuration from the command line arguments.
+    pub fn from_args(args: &Args) -> Self {
+        let exclude = parse_filter(&args.exclude);
+        let include_only = parse_filter(&args.include_only);
+        let bus = |path: &u8, sensor| BusConfig {
+            path: PathBuf::from(format!("/dev/i2c-{path}")),
+            sensor,
+            poll_interval_ms: args.poll_interval_ms,
+            resolution: args.resolution,
+            exclude: exclude.clone(),
+            include_only: include_only.clone(),
+            overdrive: !args.no_overdrive,
+            stuck_samples: 0,
+            roms: Vec::new(),
+            expected: Vec::new(),
+        };
+        Self {
+            serial: args.serial.clone().map(|port| SerialConfig {
+                port,
+                baud: args.baud,
+                parity: args.parity,
+                flow_control: args.flow_control,
+                timeout_ms: args.serial_timeout_ms,
+                buffer: args.serial_buffer.clone(),
+                buffer_bytes: default_buffer_bytes(),
+                batch_ms: 0,
+                max_bytes_per_sec: None,
+                backup: args.serial_backup.clone(),
+                failover_after: default_failover_after(),
+                failback_ms: default_failback_ms(),
+            }),
+            sinks: args
+                .json
+                .iter()
+                .map(|path| SinkConfig::Json { path: path.clone() })
+                .chain(args.csv.iter().map(|path| SinkConfig::Csv {
+                    path: path.clone(),
+                    max_bytes: default_max_bytes(),
+                    keep: default_keep(),
+                }))
+                .chain(args.tcp.iter().map(|bind| SinkConfig::Tcp {
+                    bind: *bind,
+                    queue: default_queue(),
+                }))
+                .chain(args.udp.iter().map(|addr| SinkConfig::Udp {
+                    addr: *addr,
+                    ttl: default_ttl(),
+                }))
+                .chain(args.unix.iter().map(|path| SinkConfig::Unix {
+                    path: path.clone(),
+                    format: args.unix_format,
+                    queue: default_queue(),
+                }))
+                .chain(metrics_sink(args))
+                .chain(http_sink(args))
+                .collect(),
+            leds: args.leds,
+            cpu: true,
+            cpu_include: Vec::new(),
+            cpu_exclude: Vec::new(),
+            cpu_source: CpuSource::default(),
+            buses: args
+                .thermo_paths
+                .iter()
+                .map(|path| bus(path, SensorType::Ds28ea00))
+                .chain(
+                    args.humidity_paths
+                        .iter()
+                        .map(|path| bus(path, args.humidity_type.into())),
+                )
+                .collect(),
+            filters: HashMap::new(),
+            gradient: None,
+            names: HashMap::new(),
+            sensor_map: args.sensor_map.clone(),
+            watchdog_ms: args.watchdog_ms,
+            sync_interval_ms: args.sync_interval_ms,
+            simulate: args.simulate.then(|| SimulationConfig::from_args(args)),
+            log: None,
+        }
+    }
+
+    pub fn watchdog(&self) -> Duration {
+        Duration::from_millis(self.watchdog_ms)
+    }
+
+    pub fn sync_interval(&self) -> Option<Duration> {
+        (self.sync_interval_ms > 0).then(|| Duration::from_millis(self.sync_interval_ms))
+    }
+}
+
+impl SimulationConfig {
+    /// Assemble the simulation settings from the command line arguments.
+    pub fn from_args(args: &Args) -> Self {
+        Self {
+            sensors: args.sim_sensors,
+            drift: args.sim_drift,
+            noise: args.sim_noise,
+            dropout: args.sim_dropout,
+            seed: 0,
+        }
+    }
+}
+
+impl BusConfig {
+    /// Filter of the sensors to read.
+    pub fn filter(&self) -> SensorFilter {
+        SensorFilter::new()
+            .with_include_only(self.include_only.clone())
+            .with_exclude(self.exclude.clone())
+    }
+
+    pub fn poll_interval(&self) -> Duration {
+        Duration::from_millis(self.poll_interval_ms)
+    }
+
+    /// Resolution of DS28EA00 sensors. Unless set, this is the highest resolution whose
+    /// conversion completes within the poll interval.
+    pub fn ds28ea00_resolution(&self) -> ReadoutResolution {
+        let resolution = match self.resolution {
+            Some(9) => ReadoutResolution::Resolution9bit,
+            Some(10) => ReadoutResolution::Resolution10bit,
+            Some(11) => ReadoutResolution::Resolution11bit,
+            Some(12) => ReadoutResolution::Resolution12bit,
+            _ => return ReadoutResolution::for_period(self.poll_interval()),
+        };
+        if resolution.conversion_time() > self.poll_interval() {
+            log::warn!(
+                "[MAIN] {}: Conversion time of {:?} exceeds the poll interval of {} ms",
+                self.path.display(),
+                resolution,
+                self.poll_interval_ms
+            );
+        }
+        resolution
+    }
+
+    pub fn hdc1010_resolution(&self) -> (TemperatureResolution, HumidityResolution) {
+        match self.resolution {
+            Some(8) => (
+                TemperatureResolution::ElevenBit,
+                HumidityResolution::EightBit,
+            ),
+            Some(11) => (
+                TemperatureResolution::ElevenBit,
+                HumidityResolution::ElevenBit,
+            ),
+            _ => (
+                TemperatureResolution::FourteenBit,
+                HumidityResolution::FourteenBit,
+            ),
+        }
+    }
+}
+
+/// Parse a comma separated list of sensor patterns, skipping invalid items.
+fn parse_filter(list: &str) -> Vec<Pattern> {
+    list.split(',')
+        .filter(|item| !item.trim().is_empty())
+        .filter_map(|item| {
+            let pattern = item.parse::<Pattern>();
+            if let Err(e) = &pattern {
+                log::warn!("[MAIN] Invalid sensor pattern {}: {e}", item.trim());
+            }
+            pattern.ok()
+        })
+        .collect()
+}
+
+pub fn parse_id(item: &str) -> Option<u32> {
+    item.parse::<SensorId>().ok().map(SensorId::value)
+}
+
+fn deserialize_patterns<'de, D: serde::Deserializer<'de>>(de: D) -> Result<Vec<Pattern>, D::Error> {
+    let items = Vec::<String>::deserialize(de)?;
+    items
+        .iter()
+        .map(|item| {
+            item.parse().map_err(|e| {
+                serde::de::Error::custom(format!("invalid sensor pattern {item}: {e}"))
+            })
+        })
+        .collect()
+}
+
+fn deserialize_roms<'de, D: serde::Deserializer<'de>>(de: D) -> Result<Vec<u64>, D::Error> {
+    let items = Vec::<String>::deserialize(de)?;
+    items
+        .iter()
+        .map(|item| match item.parse() {
+            Ok(Pattern::Rom(rom)) => Ok(rom),
+            _ => Err(serde::de::Error::custom(format!("invalid ROM code {item}"))),
+        })
+        .collect()
+}
+
+fn deserialize_ids<'de, D: serde::Deserializer<'de>>(de: D) -> Result<Vec<u32>, D::Error> {
+    let items = Vec::<String>::deserialize(de)?;
+    items
+        .iter()
+        .map(|item| {
+            parse_id(item)
+                .ok_or_else(|| serde::de::Error::custom(format!("invalid sensor ID {item}")))
+        })
+        .collect()
+}
+
+fn deserialize_names<'de, D: serde::Deserializer<'de>>(
+    de: D,
+) -> Result<HashMap<u32, String>, D::Error> {
+    let items = HashMap::<String, String>::deserialize(de)?;
+    items
+        .into_iter()
+        .map(|(id, name)| {
+            parse_id(&id)
+                .map(|id| (id, name))
+                .ok_or_else(|| serde::de::Error::custom(format!("invalid sensor ID: {id}")))
+        })
+        .collect()
+}
+
+fn deserialize_level<'de, D: serde::Deserializer<'de>>(de: D) -> Result<LevelFilter, D::Error> {
+    let level = String::deserialize(de)?;
+    level
+        .parse()
+        .map_err(|_| serde::de::Error::custom(format!("invalid log level: {level}")))
+}
+
+fn deserialize_levels<'de, D: serde::Deserializer<'de>>(
+    de: D,
+) -> Result<HashMap<String, LevelFilter>, D::Error> {
+    let items = HashMap::<String, String>::deserialize(de)?;
+    items
+        .into_iter()
+        .map(|(module, level)| {
+            level
+                .parse()
+                .map(|level| (module, level))
+                .map_err(|_| serde::de::Error::custom(format!("invalid log level: {level}")))
+        })
+        .collect()
+}
+
+#[cfg(feature = "metrics")]
+fn metrics_sink(args: &Args) -> Option<SinkConfig> {
+    args.metrics.map(|bind| SinkConfig::Metrics { bind })
+}
+
+#[cfg(not(feature = "metrics"))]
+fn metrics_sink(_args: &Args) -> Option<SinkConfig> {
+    None
+}
+
+#[cfg(feature = "http")]
+fn http_sink(args: &Args) -> Option<SinkConfig> {
+    args.http.map(|bind| SinkConfig::Http { bind })
+}
+
+#[cfg(not(feature = "http"))]
+fn http_sink(_args: &Args) -> Option<SinkConfig> {
+    None
+}
+
+#[cfg(feature = "mqtt")]
+fn default_mqtt_port() -> u16 {
+    1883
+}
+
+#[cfg(feature = "mqtt")]
+fn default_mqtt_prefix() -> String {
+    "piccthermo".into()
+}
+
+fn default_true() -> bool {
+    true
+}
+
+pub fn default_baud() -> u32 {
+    115200
+}
+
+pub fn default_serial_timeout_ms() -> u64 {
+    1000
+}
+
+pub fn default_watchdog_ms() -> u64 {
+    30_000
+}
+
+pub fn default_sync_interval_ms() -> u64 {
+    60_000
+}
+
+pub fn default_poll_interval_ms() -> u64 {
+    1000
+}
+
+fn default_gradient_window_ms() -> u64 {
+    60_000
+}
+
+pub fn default_sim_sensors() -> usize {
+    4
+}
+
+pub fn default_sim_drift() -> f32 {
+    0.05
+}
+
+pub fn default_sim_noise() -> f32 {
+    0.1
+}
+
+fn default_buffer_bytes() -> u64 {
+    16 * 1024 * 1024
+}
+
+fn default_failover_after() -> u32 {
+    3
+}
+
+fn default_failback_ms() -> u64 {
+    60_000
+}
+
+fn default_max_bytes() -> u64 {
+    10 * 1024 * 1024
+}
+
+fn default_log_level() -> LevelFilter {
+    LevelFilter::Info
+}
+
+fn default_keep() -> usize {
+    5
+}
+
+fn default_queue() -> usize {
+    64
+}
+
+fn default_ttl() -> u32 {
+    1
+}
@@ -0,0 +1,220 @@
+use std::{
+    sync::{Mutex, mpsc},
+    time::{Duration, Instant},
+};
+
+use piccthermo_id::{Pattern, SensorId, parse_patterns};
+
+/// Maximum time to wait for the backends to handle a command.
+const REPLY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Runtime reconfiguration of a backend.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Command {
+    /// List the sensors of the backend.
+    List,
+    /// Initialize the backend again, enumerating its sensors.
+    Enumerate,
+    /// Change the interval between two acquisitions.
+    SetPollInterval(Duration),
+    /// Turn the LED of the sensor with the given ID on or off.
+    SetLed(u32, bool),
+    /// Enable or disable 1-Wire overdrive mode.
+    SetOverdrive(bool),
+    /// Replace the patterns of the sensors left out of the readout.
+    SetExclusions(Vec<Pattern>),
+    /// Run the heater of the humidity sensor with the given ID for a while, to burn off
+    /// condensation.
+    Heat(u32, Duration),
+}
+
+impl Command {
+    /// Parse a command line of the form `<command> <bus|*> [arguments]`:
+    /// - `list <bus|*>`
+    /// - `enumerate <bus|*>`
+    /// - `rate <bus|*> <milliseconds>`
+    /// - `led <bus|*> <id> <on|off>`
+    /// - `overdrive <bus|*> <on|off>`
+    /// - `exclude <bus|*> <pattern,pattern,...|none>`, see [`Pattern`] for the syntax
+    /// - `heat <bus|*> <id> <seconds>`
+    ///
+    /// # Returns
+    /// The target bus, or `None` for all buses, and the command.
+    pub fn parse(line: &str) -> Result<(Option<String>, Self), String> {
+        let mut words = line.split_whitespace();
+        let name = words.next().ok_or("empty command")?;
+        let target = match words.next().ok_or("missing bus")? {
+            "*" => None,
+            bus => Some(bus.to_string()),
+        };
+        let mut arg = || {
+            words
+                .next()
+                .ok_or_else(|| format!("missing argument to {name}"))
+        };
+        let switch = |word: &str| match word {
+            "on" => Ok(true),
+            "off" => Ok(false),
+            _ => Err(format!("expected on or off, got {word}")),
+        };
+        let id = |id: &str| {
+            id.parse::<SensorId>()
+                .map(SensorId::value)
+                .map_err(|e| format!("invalid sensor ID {id}: {e}"))
+        };
+        let command = match name {
+            "list" => Command::List,
+            "enumerate" => Command::Enumerate,
+            "rate" => {
+                let ms = arg()?
+                    .parse::<u64>()
+                    .map_err(|e| format!("invalid rate: {e}"))?;
+                if ms == 0 {
+                    return Err("rate must be positive".into());
+                }
+                Command::SetPollInterval(Duration::from_millis(ms))
+            }
+            "led" => Command::SetLed(id(arg()?)?, switch(arg()?)?),
+            "overdrive" => Command::SetOverdrive(switch(arg()?)?),
+            "exclude" => match arg()? {
+                "none" => Command::SetExclusions(Vec::new()),
+                list => Command::SetExclusions(
+                    parse_patterns(list).map_err(|e| format!("invalid pattern: {e}"))?,
+                ),
+            },
+            "heat" => {
+                let id = id(arg()?)?;
+                let secs = arg()?
+                    .parse::<u64>()
+                    .map_err(|e| format!("invalid duration: {e}"))?;
+                if secs == 0 {
+                    return Err("duration must be positive".into());
+                }
+                Command::Heat(id, Duration::from_secs(secs))
+            }
+            _ => return Err(format!("unknown command {name}")),
+        };
+        Ok((target, command))
+    }
+}
+
+/// A command sent to a backend, with the channel to send the outcome to.
+pub struct Request {
+    pub command: Command,
+    pub reply: mpsc::Sender<Result<String, String>>,
+}
+
+/// Routes commands to the backends by bus name.
+#[derive(Default)]
+pub struct Router {
+    backends: Mutex<Vec<(String, mpsc::Sender<Request>)>>,
+}
+
+impl Router {
+    /// Register a backend, and get the channel its commands are received on.
+    ///
+    /// An earlier registration of the same bus, e.g. by a thread that was restarted, is replaced.
+    pub fn register(&self, bus: String) -> mpsc::Receiver<Request> {
+        let (tx, rx) = mpsc::channel();
+        if let Ok(mut backends) = self.backends.lock() {
+            backends.retain(|(other, _)| *other != bus);
+            backends.push((bus, tx));
+        }
+        rx
+    }
+
+    /// Execute a command line.
+    ///
+    /// # Returns
+    /// One response line per backend the command was sent to, `ACK <bus> <details>` on success
+    /// and `NACK <bus> <reason>` on failure.
+    pub fn execute(&self, line: &str) -> Vec<String> {
+        let (target, command) = match Command::parse(line) {
+            Ok(parsed) => parsed,
+            Err(e) => return vec![format!("NACK * {e}")],
+        };
+        let mut pending = Vec::new();
+        if let Ok(backends) = self.backends.lock() {
+            for (bus, tx) in backends.iter() {
+                if target.as_ref().is_some_and(|target| target != bus) {
+                    continue;
+                }
+                let (reply, rx) = mpsc::channel();
+                let request = Request {
+                    command: command.clone(),
+                    reply,
+                };
+                if tx.send(request).is_ok() {
+                    pending.push((bus.clone(), rx));
+                }
+            }
+        }
+        if pending.is_empty() {
+            return vec![format!(
+                "NACK {} no such bus",
+                target.as_deref().unwrap_or("*")
+            )];
+        }
+        let deadline = Instant::now() + REPLY_TIMEOUT;
+        pending
+            .into_iter()
+            .map(|(bus, rx)| {
+                match rx.recv_timeout(deadline.saturating_duration_since(Instant::now())) {
+                    Ok(Ok(details)) => format!("ACK {bus} {details}"),
+                    Ok(Err(reason)) => format!("NACK {bus} {reason}"),
+                    Err(_) => format!("NACK {bus} timeout"),
+                }
+            })
+            .collect()
+    }
+}
+
+mod test {
+    #[test]
+    fn test_parse() {
+        use super::Command;
+        use piccthermo_id::{Pattern, SensorId};
+        use std::time::Duration;
+        assert_eq!(
+            Command::parse("rate i2c-1 500"),
+            Ok((
+                Some("i2c-1".into()),
+                Command::SetPollInterval(Duration::from_millis(500))
+            ))
+        );
+        assert_eq!(
+            Command::parse("led * 0xdeadbeef on"),
+            Ok((None, Command::SetLed(0xdeadbeef, true)))
+        );
+        assert_eq!(
+            Command::parse("exclude i2c-1 none"),
+            Ok((Some("i2c-1".into()), Command::SetExclusions(vec![])))
+        );
+        assert_eq!(
+            Command::parse("exclude * 0x1234,chamber-*"),
+            Ok((
+                None,
+                Command::SetExclusions(vec![
+                    Pattern::Id(SensorId::new(0x1234)),
+                    Pattern::Label("chamber-*".into())
+                ])
+            ))
+        );
+        assert!(Command::parse("exclude * 0xzz").is_err());
+        assert_eq!(
+            Command::parse("heat i2c-3 0x40 30"),
+            Ok((
+                Some("i2c-3".into()),
+                Command::Heat(0x40, Duration::from_secs(30))
+            ))
+        );
+        assert!(Command::parse("heat i2c-3 0x40 0").is_err());
+        assert_eq!(
+            Command::parse("enumerate *"),
+            Ok((None, Command::Enumerate))
+        );
+        assert!(Command::parse("rate i2c-1 0").is_err());
+        assert!(Command::parse("overdrive i2c-1 maybe").is_err());
+        assert!(Command::parse("reboot *").is_err());
+    }
+}
@@ -1,36 +1,154 @@
-use std::{
-    sync::{
-        Arc,
-        atomic::{AtomicBool, Ordering},
-    },
-    thread,
-    time::{Duration, Instant},
+use std::path::Path;
+
+use piccthermo_id::SensorId;
+use regex::Regex;
+
+use crate::{
+    Readings, SensorEntry,
+    backend::SensorBackend,
+    config::CpuSource,
+    thermal::{self, SYSFS_CLASS},
 };
 
-use crate::{Measurement, safe_mpsc};
+/// Maximum number of reported components.
+const MAX_COMPONENTS: usize = 10;
+
+/// CPU temperatures reported by the operating system.
+///
+/// Components are identified by the CRC32 hash of their label, which is stable across boots
+/// unlike their enumeration order. The labels are sent once after every initialization.
+/// The labels read from sysfs differ from the ones listed by `sysinfo`, so switching the
+/// [`CpuSource`] changes the IDs.
+#[derive(Debug, Clone)]
+pub struct CpuBackend {
+    include: Vec<Regex>,
+    exclude: Vec<Regex>,
+    source: CpuSource,
+    announced: bool,
+    /// Labels of the selected components found by the last initialization.
+    components: Vec<String>,
+}
+
+impl CpuBackend {
+    /// Report the components of `source` whose label matches one of `include`, or all if it is
+    /// empty, and none of `exclude`.
+    pub fn new(include: &[String], exclude: &[String], source: CpuSource) -> Result<Self, String> {
+        let compile = |patterns: &[String]| {
+            patterns
+                .iter()
+                .map(|pattern| {
+                    Regex::new(pattern).map_err(|e| format!("Invalid pattern {pattern}: {e}"))
+                })
+                .collect::<Result<Vec<_>, _>>()
+        };
+        Ok(Self {
+            include: compile(include)?,
+            exclude: compile(exclude)?,
+            source,
+            announced: false,
+            components: Vec::new(),
+        })
+    }
+
+    fn selected(&self, label: &str) -> bool {
+        (self.include.is_empty() || self.include.iter().any(|re| re.is_match(label)))
+            && !self.exclude.iter().any(|re| re.is_match(label))
+    }
 
-pub fn cputemp_thread(running: Arc<AtomicBool>, sink: safe_mpsc::SafeSender<Measurement>) {
-    while running.load(Ordering::Relaxed) {
-        let start = Instant::now();
-        let components = sysinfo::Components::new_with_refreshed_list();
-        let mut meas = components
+    /// Labels and temperatures, in °C, of the components of the configured source.
+    fn components(&self) -> Vec<(String, Option<f32>)> {
+        let sysinfo = || {
+            sysinfo::Components::new_with_refreshed_list()
+                .iter()
+                .map(|component| (component.label().to_string(), component.temperature()))
+                .collect::<Vec<_>>()
+        };
+        let sysfs = || {
+            thermal::read(Path::new(SYSFS_CLASS))
+                .into_iter()
+                .map(|zone| (zone.name, Some(zone.millidegrees as f32 / 1000.0)))
+                .collect()
+        };
+        match self.source {
+            CpuSource::Sysinfo => sysinfo(),
+            CpuSource::Sysfs => sysfs(),
+            CpuSource::Auto => {
+                let components = sysinfo();
+                if components.is_empty() {
+                    sysfs()
+                } else {
+                    components
+                }
+            }
+        }
+    }
+}
+
+impl SensorBackend for CpuBackend {
+    fn name(&self) -> String {
+        "[CPU]".into()
+    }
+
+    fn bus(&self) -> String {
+        "cpu".into()
+    }
+
+    fn init(&mut self) -> Result<(), String> {
+        self.announced = false;
+        let components = self.components();
+        self.components.clear();
+        for (label, _) in components {
+            if self.selected(&label) && !self.components.contains(&label) {
+                self.components.push(label);
+            }
+        }
+        self.components.truncate(MAX_COMPONENTS);
+        Ok(())
+    }
+
+    fn inventory(&self) -> Vec<SensorEntry> {
+        self.components
             .iter()
-            .enumerate()
-            .filter_map(|(idx, component)| component.temperature().map(|temp| (idx as u32, temp)))
-            .collect::<Vec<_>>();
-        meas.truncate(10); // Limit to 10 measurements
-        if !meas.is_empty() {
-            let measurement = Measurement::Temperature(meas);
-            if let Err(e) = sink.send(measurement) {
-                log::error!("[CPU] Failed to send measurement: {e:?}");
-                continue; // we are probably shutting down
+            .map(|label| SensorEntry {
+                id: SensorId::from_label(label).value(),
+                address: 0,
+                model: label.clone(),
+            })
+            .collect()
+    }
+
+    fn acquire(&mut self) -> Result<Vec<Readings>, String> {
+        let mut labels = Vec::new();
+        let mut meas = Vec::new();
+        for (label, temp) in self.components() {
+            if !self.selected(&label) {
+                continue;
+            }
+            let Some(temp) = temp else {
+                continue;
+            };
+            let id = SensorId::from_label(&label).value();
+            if meas.iter().any(|(other, _)| *other == id) {
+                continue; // duplicate label
             }
-        } else {
+            if meas.len() == MAX_COMPONENTS {
+                break;
+            }
+            meas.push((id, temp));
+            labels.push((id, label, "cpu".to_string()));
+        }
+        if meas.is_empty() {
             log::warn!("[CPU] No temperature data available");
+            return Ok(Vec::new());
         }
-        let elapsed = start.elapsed();
-        if elapsed < Duration::from_secs(1) {
-            thread::sleep(Duration::from_secs(1) - elapsed);
+        let mut data = vec![Readings::Temperature(meas)];
+        if !self.announced {
+            for (id, label, _) in &labels {
+                log::info!("[CPU] Component {id:08x}: {label}");
+            }
+            data.push(Readings::Labels(labels));
+            self.announced = true;
         }
+        Ok(data)
     }
 }
@@ -0,0 +1,233 @@
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use crate::{Measurement, Readings, safe_mpsc::SafeSender, sink::MeasurementSink};
+
+/// A primary sink with a backup, used only while the primary sink fails.
+///
+/// Every failure of the primary sink to open or to write counts towards `failover_after`, and a
+/// successful write starts the count over. Once it is reached, the primary sink is closed and the
+/// backup sink takes over. While the backup sink is used, the primary sink is opened again every
+/// `failback`, and takes over again once it opens. Both switches are logged and sent down the
+/// measurement stream as a [`Readings::Failover`] frame with the path of the sink taking over.
+pub struct FailoverSink {
+    primary: Box<dyn MeasurementSink>,
+    backup: Box<dyn MeasurementSink>,
+    /// Paths of the primary and backup sinks, as reported in the failover frames.
+    paths: [String; 2],
+    failover_after: u32,
+    failback: Duration,
+    failures: u32,
+    on_backup: bool,
+    /// Last attempt to fail back to the primary sink.
+    last_failback: Option<Instant>,
+    events: SafeSender<Measurement>,
+}
+
+impl FailoverSink {
+    pub fn new(
+        primary: (Box<dyn MeasurementSink>, String),
+        backup: (Box<dyn MeasurementSink>, String),
+        failover_after: u32,
+        failback: Duration,
+        events: SafeSender<Measurement>,
+    ) -> Self {
+        Self {
+            primary: primary.0,
+            backup: backup.0,
+            paths: [primary.1, backup.1],
+            failover_after: failover_after.max(1),
+            failback,
+            failures: 0,
+            on_backup: false,
+            last_failback: None,
+            events,
+        }
+    }
+
+    fn active(&mut self) -> &mut Box<dyn MeasurementSink> {
+        if self.on_backup {
+            &mut self.backup
+        } else {
+            &mut self.primary
+        }
+    }
+
+    /// Count a failure of the primary sink, switching to the backup sink once there are enough.
+    fn fail(&mut self) {
+        self.failures += 1;
+        if self.on_backup || self.failures < self.failover_after {
+            return;
+        }
+        let [primary, backup] = &self.paths;
+        log::warn!(
+            "{}> Primary sink {primary} failed {} times in a row, switching to backup sink {backup}",
+            self.primary.name(),
+            self.failures
+        );
+        self.primary.close();
+        self.on_backup = true;
+        self.last_failback = Some(Instant::now());
+        self.report();
+    }
+
+    /// Open the primary sink again, if the backup sink is used and the last attempt is old enough.
+    fn try_failback(&mut self) {
+        if !self.on_backup
+            || self
+                .last_failback
+                .is_some_and(|last| last.elapsed() < self.failback)
+        {
+            return;
+        }
+        self.last_failback = Some(Instant::now());
+        match self.primary.open() {
+            Ok(()) => {
+                log::info!(
+                    "{}> Primary sink {} is back, switching from backup sink {}",
+                    self.primary.name(),
+                    self.paths[0],
+                    self.paths[1]
+                );
+                self.backup.close();
+                self.on_backup = false;
+                self.failures = 0;
+                self.report();
+            }
+            Err(e) => log::warn!("{}> Primary sink still fails: {e}", self.primary.name()),
+        }
+    }
+
+    /// Send the path of the active sink as a [`Readings::Failover`] frame.
+    fn report(&self) {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |t| t.as_millis() as u64);
+        let path = self.paths[self.on_backup as usize].clone();
+        if let Err(e) = self.events.send(Measurement::new(
+            "serial".into(),
+            Readings::Failover(path),
+            timestamp,
+        )) {
+            log::error!("{}> Failed to send failover frame: {e:?}", self.name());
+        }
+    }
+}
+
+impl MeasurementSink for FailoverSink {
+    fn name(&self) -> String {
+        if self.on_backup {
+            self.backup.name()
+        } else {
+            self.primary.name()
+        }
+    }
+
+    fn open(&mut self) -> Result<(), String> {
+        let res = self.active().open();
+        if res.is_err() && !self.on_backup {
+            self.fail();
+            if self.on_backup {
+                if let Err(e) = &res {
+                    log::error!("{}> {e}", self.primary.name());
+                }
+                return self.backup.open();
+            }
+        }
+        res
+    }
+
+    fn write(&mut self, measurement: &Measurement) -> Result<(), String> {
+        self.try_failback();
+        let res = self.active().write(measurement);
+        if !self.on_backup {
+            match res {
+                Ok(()) => self.failures = 0,
+                Err(_) => self.fail(),
+            }
+        }
+        res
+    }
+
+    fn close(&mut self) {
+        self.active().close();
+    }
+}
+
+mod test {
+    #[test]
+    fn test_failover() {
+        use super::FailoverSink;
+        use crate::{Measurement, Readings, safe_mpsc, sink::MeasurementSink};
+        use std::{
+            sync::{Arc, Mutex},
+            time::Duration,
+        };
+
+        /// A sink whose failures are set by the test, recording the measurements written to it.
+        struct Flaky(Arc<Mutex<(bool, Vec<u32>)>>);
+        impl MeasurementSink for Flaky {
+            fn name(&self) -> String {
+                "[TST]".into()
+            }
+
+            fn open(&mut self) -> Result<(), String> {
+                let (fail, _) = *self.0.lock().unwrap();
+                if fail {
+                    Err("open failed".into())
+                } else {
+                    Ok(())
+                }
+            }
+
+            fn write(&mut self, measurement: &Measurement) -> Result<(), String> {
+                let mut state = self.0.lock().unwrap();
+                if state.0 {
+                    return Err("write failed".into());
+                }
+                state.1.push(measurement.sequence);
+                Ok(())
+            }
+        }
+
+        let primary = Arc::new(Mutex::new((false, Vec::new())));
+        let backup = Arc::new(Mutex::new((false, Vec::new())));
+        let (tx, rx) = safe_mpsc::channel();
+        let mut sink = FailoverSink::new(
+            (Box::new(Flaky(primary.clone())), "primary".into()),
+            (Box::new(Flaky(backup.clone())), "backup".into()),
+            2,
+            Duration::ZERO,
+            tx,
+        );
+        let measurement = || Measurement::new("test".into(), Readings::EndOfStream, 0);
+        let failover = || {
+            rx.receiver().try_iter().find_map(|m| match m.readings {
+                Readings::Failover(path) => Some(path),
+                _ => None,
+            })
+        };
+        sink.open().unwrap();
+        sink.write(&measurement()).unwrap();
+        // a single failure is retried on the primary sink
+        primary.lock().unwrap().0 = true;
+        assert!(sink.write(&measurement()).is_err());
+        primary.lock().unwrap().0 = false;
+        sink.open().unwrap();
+        sink.write(&measurement()).unwrap();
+        assert_eq!(failover(), None);
+        // repeated failures switch to the backup sink
+        primary.lock().unwrap().0 = true;
+        assert!(sink.write(&measurement()).is_err());
+        assert!(sink.open().is_ok());
+        assert_eq!(failover(), Some("backup".into()));
+        sink.write(&measurement()).unwrap();
+        assert_eq!(primary.lock().unwrap().1.len(), 2);
+        assert_eq!(backup.lock().unwrap().1.len(), 1);
+        // and back once the primary sink opens again
+        primary.lock().unwrap().0 = false;
+        sink.write(&measurement()).unwrap();
+        assert_eq!(failover(), Some("primary".into()));
+        assert_eq!(primary.lock().unwrap().1.len(), 3);
+        assert_eq!(backup.lock().unwrap().1.len(), 1);
+    }
+}
@@ -0,0 +1,229 @@
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{self, BufWriter, Write},
+    path::{Path, PathBuf},
+};
+
+use piccthermo_id::SensorId;
+
+use crate::{Measurement, sink::MeasurementSink};
+
+/// Newline-delimited JSON, one object per record, written to a file or to stdout.
+pub struct JsonSink {
+    path: PathBuf,
+    out: Option<Box<dyn Write + Send>>,
+}
+
+impl JsonSink {
+    /// Create a JSON sink writing to `path`, or to stdout if `path` is `-`.
+    pub fn new(path: PathBuf) -> Self {
+        Self { path, out: None }
+    }
+}
+
+impl MeasurementSink for JsonSink {
+    fn name(&self) -> String {
+        format!("[JSN] {}", self.path.display())
+    }
+
+    fn open(&mut self) -> Result<(), String> {
+        self.out = Some(if self.path.as_os_str() == "-" {
+            Box::new(io::stdout())
+        } else {
+            let file = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&self.path)
+                .map_err(|e| format!("Failed to open file: {e}"))?;
+            Box::new(BufWriter::new(file))
+        });
+        Ok(())
+    }
+
+    fn write(&mut self, measurement: &Measurement) -> Result<(), String> {
+        let out = self.out.as_mut().ok_or("Sink not open")?;
+        out.write_all(json_lines(measurement).as_bytes())
+            .map_err(|e| format!("Failed to write: {e}"))?;
+        out.flush().map_err(|e| format!("Failed to flush: {e}"))
+    }
+
+    fn close(&mut self) {
+        self.out = None;
+    }
+}
+
+/// The records of a measurement as newline-delimited JSON, one object per record.
+pub fn json_lines(measurement: &Measurement) -> String {
+    let mut lines = String::new();
+    for record in measurement.records() {
+        let mut obj = serde_json::json!({
+            "sequence": record.sequence,
+            "timestamp": record.timestamp,
+            "type": record.kind,
+            "id": SensorId::new(record.id).to_string(),
+        });
+        if let Some(value) = record.value {
+            obj["value"] = value.into();
+        }
+        if let Some((label, location)) = record.label {
+            obj["label"] = label.into();
+            obj["location"] = location.into();
+        }
+        if let Some(text) = record.text {
+            obj["text"] = text.into();
+        }
+        if let Some(address) = record.address {
+            obj["address"] = format!("{address:016x}").into();
+        }
+        if let Some((path, version)) = record.origin {
+            obj["path"] = path.into();
+            obj["version"] = version.into();
+        }
+        if let Some((consecutive, total)) = record.failures {
+            obj["failures"] = consecutive.into();
+            obj["total_failures"] = total.into();
+        }
+        if let Some((uptime_ms, counter)) = record.sync {
+            obj["uptime_ms"] = uptime_ms.into();
+            obj["counter"] = counter.into();
+        }
+        lines += &format!("{obj}\n");
+    }
+    lines
+}
+
+/// CSV file, one row per record, rotated when it grows beyond a size limit.
+///
+/// On rotation, `log.csv` is renamed to `log.csv.1`, `log.csv.1` to `log.csv.2` and so on,
+/// keeping at most `keep` rotated files.
+pub struct CsvSink {
+    path: PathBuf,
+    max_bytes: u64,
+    keep: usize,
+    file: Option<BufWriter<File>>,
+    written: u64,
+}
+
+const CSV_HEADER: &str = "sequence,timestamp,type,id,value,label,location,text,address,path,version,failures,total_failures,uptime_ms,counter\n";
+
+impl CsvSink {
+    pub fn new(path: PathBuf, max_bytes: u64, keep: usize) -> Self {
+        Self {
+            path,
+            max_bytes,
+            keep,
+            file: None,
+            written: 0,
+        }
+    }
+
+    fn rotate(&mut self) -> io::Result<()> {
+        self.file = None;
+        rotate(&self.path, self.keep)?;
+        log::info!("[CSV] {}> Rotated file", self.path.display());
+        Ok(())
+    }
+}
+
+impl MeasurementSink for CsvSink {
+    fn name(&self) -> String {
+        format!("[CSV] {}", self.path.display())
+    }
+
+    fn open(&mut self) -> Result<(), String> {
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .map_err(|e| format!("Failed to open file: {e}"))?;
+        self.written = file
+            .metadata()
+            .map_err(|e| format!("Failed to read file size: {e}"))?
+            .len();
+        let mut file = BufWriter::new(file);
+        if self.written == 0 {
+            file.write_all(CSV_HEADER.as_bytes())
+                .and_then(|_| file.flush())
+                .map_err(|e| format!("Failed to write header: {e}"))?;
+            self.written = CSV_HEADER.len() as u64;
+        }
+        self.file = Some(file);
+        Ok(())
+    }
+
+    fn write(&mut self, measurement: &Measurement) -> Result<(), String> {
+        let file = self.file.as_mut().ok_or("Sink not open")?;
+        for record in measurement.records() {
+            let value = record.value.map(|v| v.to_string()).unwrap_or_default();
+            let (label, location) = record.label.unwrap_or_default();
+            let address = record
+                .address
+                .map(|a| format!("{a:016x}"))
+                .unwrap_or_default();
+            let (path, version) = record.origin.unwrap_or_default();
+            let (failures, total_failures) = record
+                .failures
+                .map(|(consecutive, total)| (consecutive.to_string(), total.to_string()))
+                .unwrap_or_default();
+            let (uptime_ms, counter) = record
+                .sync
+                .map(|(uptime_ms, counter)| (uptime_ms.to_string(), counter.to_string()))
+                .unwrap_or_default();
+            let row = format!(
+                "{},{},{},{},{value},{},{},{},{address},{},{},{failures},{total_failures},{uptime_ms},{counter}\n",
+                record.sequence,
+                record.timestamp,
+                record.kind,
+                SensorId::new(record.id),
+                escape(label),
+                escape(location),
+                escape(record.text.unwrap_or_default()),
+                escape(path),
+                escape(version)
+            );
+            file.write_all(row.as_bytes())
+                .map_err(|e| format!("Failed to write: {e}"))?;
+            self.written += row.len() as u64;
+        }
+        file.flush().map_err(|e| format!("Failed to flush: {e}"))?;
+        if self.written >= self.max_bytes {
+            self.rotate()
+                .map_err(|e| format!("Failed to rotate file: {e}"))?;
+            self.open()?;
+        }
+        Ok(())
+    }
+
+    fn close(&mut self) {
+        self.file = None;
+    }
+}
+
+/// Quote a CSV field if needed.
+/// Rename `path` to `path.1`, `path.1` to `path.2` and so on, keeping at most `keep` rotated files.
+pub fn rotate(path: &Path, keep: usize) -> io::Result<()> {
+    let rotated = |idx: usize| {
+        let mut path = path.to_path_buf().into_os_string();
+        path.push(format!(".{idx}"));
+        PathBuf::from(path)
+    };
+    if keep == 0 {
+        fs::remove_file(path)
+    } else {
+        for idx in (1..keep).rev() {
+            let from = rotated(idx);
+            if from.exists() {
+                fs::rename(from, rotated(idx + 1))?;
+            }
+        }
+        fs::rename(path, rotated(1))
+    }
+}
+
+fn escape(field: &str) -> String {
+    if field.contains([',', '"', '\n']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.into()
+    }
+}
@@ -0,0 +1,234 @@
+use std::collections::{HashMap, VecDeque};
+
+use crate::{
+    Readings, SensorEntry,
+    backend::SensorBackend,
+    config::{FilterConfig, Smoothing},
+    control::Command,
+};
+
+/// Filter state of the readings of one kind from a single sensor.
+#[derive(Default)]
+struct Channel {
+    window: VecDeque<f32>,
+    smoothed: Option<f32>,
+    published: Option<f32>,
+    held: u32,
+}
+
+impl Channel {
+    /// Smooth a reading, and return the smoothed value if it is to be published.
+    fn update(&mut self, value: f32, config: &FilterConfig) -> Option<f32> {
+        if !value.is_finite() {
+            return Some(value); // would poison the smoothing
+        }
+        let smoothed = match config.smoothing {
+            Smoothing::None => value,
+            Smoothing::Average { window } => {
+                self.window.push_back(value);
+                while self.window.len() > window.max(1) {
+                    self.window.pop_front();
+                }
+                self.window.iter().sum::<f32>() / self.window.len() as f32
+            }
+            Smoothing::Exponential { alpha } => {
+                let alpha = alpha.clamp(0.0, 1.0);
+                self.smoothed
+                    .map_or(value, |prev| prev + alpha * (value - prev))
+            }
+        };
+        self.smoothed = Some(smoothed);
+        self.held += 1;
+        let publish = self
+            .published
+            .is_none_or(|published| (smoothed - published).abs() >= config.threshold)
+            || config
+                .max_hold
+                .is_some_and(|max_hold| self.held >= max_hold);
+        if !publish {
+            return None;
+        }
+        self.published = Some(smoothed);
+        self.held = 0;
+        Some(smoothed)
+    }
+}
+
+/// Smoothing and change threshold applied to the temperature, humidity and dew point readings
+/// of every sensor.
+pub struct Filter {
+    config: FilterConfig,
+    channels: HashMap<(&'static str, u32), Channel>,
+}
+
+impl Filter {
+    pub fn new(config: FilterConfig) -> Self {
+        Self {
+            config,
+            channels: HashMap::new(),
+        }
+    }
+
+    /// Forget the readings seen so far, so that the next reading of every sensor is published.
+    pub fn reset(&mut self) {
+        self.channels.clear();
+    }
+
+    /// Filter the readings of an acquisition. Readings that are left without any value are dropped,
+    /// and readings of other kinds are passed through.
+    pub fn apply(&mut self, data: Vec<Readings>) -> Vec<Readings> {
+        data.into_iter()
+            .filter_map(|readings| match readings {
+                Readings::Temperature(values) => self
+                    .values("temperature", values)
+                    .map(Readings::Temperature),
+                Readings::Humidity(values) => {
+                    self.values("humidity", values).map(Readings::Humidity)
+                }
+                Readings::DewPoint(values) => {
+                    self.values("dewpoint", values).map(Readings::DewPoint)
+                }
+                readings => Some(readings),
+            })
+            .collect()
+    }
+
+    fn values(&mut self, kind: &'static str, values: Vec<(u32, f32)>) -> Option<Vec<(u32, f32)>> {
+        let config = &self.config;
+        let values = values
+            .into_iter()
+            .filter_map(|(id, value)| {
+                let channel = self.channels.entry((kind, id)).or_default();
+                channel.update(value, config).map(|value| (id, value))
+            })
+            .collect::<Vec<_>>();
+        (!values.is_empty()).then_some(values)
+    }
+}
+
+/// A backend whose readings are passed through a [`Filter`] before they are published.
+///
+/// The filter is reset whenever the backend is initialized, since the sensors may have changed.
+pub struct FilteredBackend {
+    inner: Box<dyn SensorBackend>,
+    filter: Filter,
+}
+
+impl FilteredBackend {
+    pub fn new(inner: Box<dyn SensorBackend>, config: FilterConfig) -> Self {
+        Self {
+            inner,
+            filter: Filter::new(config),
+        }
+    }
+}
+
+impl SensorBackend for FilteredBackend {
+    fn name(&self) -> String {
+        self.inner.name()
+    }
+
+    fn bus(&self) -> String {
+        self.inner.bus()
+    }
+
+    fn path(&self) -> String {
+        self.inner.path()
+    }
+
+    fn init(&mut self) -> Result<(), String> {
+        self.filter.reset();
+        self.inner.init()
+    }
+
+    fn inventory(&self) -> Vec<SensorEntry> {
+        self.inner.inventory()
+    }
+
+    fn poll_interval(&self) -> std::time::Duration {
+        self.inner.poll_interval()
+    }
+
+    fn begin_acquire(&mut self) -> Result<Option<std::time::Instant>, String> {
+        self.inner.begin_acquire()
+    }
+
+    fn acquire(&mut self) -> Result<Vec<Readings>, String> {
+        self.inner.acquire().map(|data| self.filter.apply(data))
+    }
+
+    fn command(&mut self, command: &Command) -> Result<String, String> {
+        self.inner.command(command)
+    }
+}
+
+mod test {
+    #[test]
+    fn test_filter() {
+        use super::Filter;
+        use crate::{
+            Readings,
+            config::{FilterConfig, Smoothing},
+        };
+        let mut filter = Filter::new(FilterConfig {
+            smoothing: Smoothing::Average { window: 2 },
+            threshold: 1.0,
+            max_hold: Some(3),
+        });
+        let mut humidity = |values: &[(u32, f32)]| {
+            filter.apply(vec![
+                Readings::Humidity(values.to_vec()),
+                Readings::Brownout(vec![]),
+            ])
+        };
+        assert_eq!(
+            humidity(&[(1, 40.0), (2, 50.0)]),
+            vec![
+                Readings::Humidity(vec![(1, 40.0), (2, 50.0)]),
+                Readings::Brownout(vec![])
+            ]
+        );
+        // single-sample noise is averaged out below the threshold
+        assert_eq!(
+            humidity(&[(1, 41.5), (2, 50.0)]),
+            vec![Readings::Brownout(vec![])]
+        );
+        assert_eq!(
+            humidity(&[(1, 42.5), (2, 50.0)]),
+            vec![
+                Readings::Humidity(vec![(1, 42.0)]),
+                Readings::Brownout(vec![])
+            ]
+        );
+        // unchanged readings are published again after `max_hold` acquisitions
+        assert_eq!(
+            humidity(&[(2, 50.0)]),
+            vec![
+                Readings::Humidity(vec![(2, 50.0)]),
+                Readings::Brownout(vec![])
+            ]
+        );
+
+        let mut filter = Filter::new(FilterConfig {
+            smoothing: Smoothing::Exponential { alpha: 0.5 },
+            threshold: 0.0,
+            max_hold: None,
+        });
+        let temperature = |filter: &mut Filter, value| {
+            filter.apply(vec![Readings::Temperature(vec![(1, value)])])
+        };
+        assert_eq!(
+            temperature(&mut filter, 20.0),
+            vec![Readings::Temperature(vec![(1, 20.0)])]
+        );
+        assert_eq!(
+            temperature(&mut filter, 22.0),
+            vec![Readings::Temperature(vec![(1, 21.0)])]
+        );
+        filter.reset();
+        assert_eq!(
+            temperature(&mut filter, 22.0),
+            vec![Readings::Temperature(vec![(1, 22.0)])]
+        );
+    }
+}
@@ -0,0 +1,201 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    time::{Duration, Instant},
+};
+
+use crate::{
+    Readings, SensorEntry, backend::SensorBackend, config::GradientConfig, control::Command,
+};
+
+/// Recent temperature readings of a single sensor.
+#[derive(Default)]
+struct Channel {
+    samples: VecDeque<(Instant, f32)>,
+    alarmed: bool,
+}
+
+impl Channel {
+    /// Record a reading, and return the rate of change over the window in °C per minute once the
+    /// readings span the window.
+    fn update(&mut self, now: Instant, value: f32, window: Duration) -> Option<f32> {
+        if !value.is_finite() {
+            return None; // would poison the rate
+        }
+        self.samples.push_back((now, value));
+        // keep the latest reading at least a window old, as the start of the window
+        while self
+            .samples
+            .get(1)
+            .is_some_and(|(time, _)| now.duration_since(*time) >= window)
+        {
+            self.samples.pop_front();
+        }
+        let (start, first) = *self.samples.front()?;
+        let elapsed = now.duration_since(start);
+        if elapsed < window || elapsed.is_zero() {
+            return None;
+        }
+        Some((value - first) * 60.0 / elapsed.as_secs_f32())
+    }
+}
+
+/// Rate-of-change monitor of the temperature readings of every sensor.
+///
+/// A [`Readings::RateAlarm`] is emitted for the sensors whose temperature changes faster than the
+/// limit. A sensor is only reported again once its rate has fallen back below the limit.
+pub struct GradientMonitor {
+    config: GradientConfig,
+    channels: HashMap<u32, Channel>,
+}
+
+impl GradientMonitor {
+    pub fn new(config: GradientConfig) -> Self {
+        Self {
+            config,
+            channels: HashMap::new(),
+        }
+    }
+
+    /// Forget the readings seen so far.
+    pub fn reset(&mut self) {
+        self.channels.clear();
+    }
+
+    /// Check the temperature readings of an acquisition made at `now`.
+    ///
+    /// # Returns
+    /// The sensors that exceeded the limit, with their rate of change, or `None` if there are none.
+    pub fn update(&mut self, now: Instant, data: &[Readings]) -> Option<Readings> {
+        let window = self.config.window();
+        let mut alarms = Vec::new();
+        for readings in data {
+            let Readings::Temperature(values) = readings else {
+                continue;
+            };
+            for &(id, value) in values {
+                let channel = self.channels.entry(id).or_default();
+                let Some(rate) = channel.update(now, value, window) else {
+                    continue;
+                };
+                let exceeded = rate.abs() > self.config.max_rate;
+                if exceeded && !channel.alarmed {
+                    alarms.push((id, rate));
+                }
+                channel.alarmed = exceeded;
+            }
+        }
+        (!alarms.is_empty()).then_some(Readings::RateAlarm(alarms))
+    }
+}
+
+/// A backend whose temperature readings are watched by a [`GradientMonitor`].
+///
+/// The monitor is reset whenever the backend is initialized, since the sensors may have changed.
+pub struct GradientBackend {
+    inner: Box<dyn SensorBackend>,
+    monitor: GradientMonitor,
+}
+
+impl GradientBackend {
+    pub fn new(inner: Box<dyn SensorBackend>, config: GradientConfig) -> Self {
+        Self {
+            inner,
+            monitor: GradientMonitor::new(config),
+        }
+    }
+}
+
+impl SensorBackend for GradientBackend {
+    fn name(&self) -> String {
+        self.inner.name()
+    }
+
+    fn bus(&self) -> String {
+        self.inner.bus()
+    }
+
+    fn path(&self) -> String {
+        self.inner.path()
+    }
+
+    fn init(&mut self) -> Result<(), String> {
+        self.monitor.reset();
+        self.inner.init()
+    }
+
+    fn inventory(&self) -> Vec<SensorEntry> {
+        self.inner.inventory()
+    }
+
+    fn poll_interval(&self) -> Duration {
+        self.inner.poll_interval()
+    }
+
+    fn begin_acquire(&mut self) -> Result<Option<Instant>, String> {
+        self.inner.begin_acquire()
+    }
+
+    fn acquire(&mut self) -> Result<Vec<Readings>, String> {
+        let mut data = self.inner.acquire()?;
+        if let Some(alarm) = self.monitor.update(Instant::now(), &data) {
+            if let Readings::RateAlarm(alarms) = &alarm {
+                for (id, rate) in alarms {
+                    log::warn!(
+                        sensor:% = format_args!("{id:08x}");
+                        "{}> Temperature of sensor {id:08x} changing at {rate:+.2} °C/min",
+                        self.inner.name()
+                    );
+                }
+            }
+            data.push(alarm);
+        }
+        Ok(data)
+    }
+
+    fn command(&mut self, command: &Command) -> Result<String, String> {
+        self.inner.command(command)
+    }
+}
+
+mod test {
+    #[test]
+    fn test_gradient() {
+        use super::GradientMonitor;
+        use crate::{Readings, config::GradientConfig};
+        use std::time::{Duration, Instant};
+        let mut monitor = GradientMonitor::new(GradientConfig {
+            max_rate: 1.0,
+            window_ms: 60_000,
+        });
+        let start = Instant::now();
+        let mut update = |secs, values: &[(u32, f32)]| {
+            monitor.update(
+                start + Duration::from_secs(secs),
+                &[
+                    Readings::Humidity(vec![(1, 90.0)]),
+                    Readings::Temperature(values.to_vec()),
+                ],
+            )
+        };
+        // no rate until the readings span the window
+        assert_eq!(update(0, &[(1, 20.0), (2, 20.0)]), None);
+        assert_eq!(update(30, &[(1, 20.0), (2, 20.0)]), None);
+        assert_eq!(
+            update(60, &[(1, 20.5), (2, 18.0)]),
+            Some(Readings::RateAlarm(vec![(2, -2.0)]))
+        );
+        // reported once while the limit is exceeded
+        assert_eq!(update(90, &[(1, 20.5), (2, 16.0)]), None);
+        // steady again, over the last window
+        assert_eq!(update(150, &[(1, 20.5), (2, 16.0)]), None);
+        assert_eq!(
+            update(210, &[(1, 23.0), (2, 16.0)]),
+            Some(Readings::RateAlarm(vec![(1, 2.5)]))
+        );
+        monitor.reset();
+        assert_eq!(
+            monitor.update(start, &[Readings::Temperature(vec![(1, 0.0)])]),
+            None
+        );
+    }
+}
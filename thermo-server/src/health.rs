@@ -0,0 +1,102 @@
+use std::{
+    collections::BTreeMap,
+    time::{Duration, Instant},
+};
+
+use crate::{Readings, SensorHealth};
+
+/// Interval between two health reports of a backend.
+const REPORT_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Consecutive and total failure counters of the sensors of a backend, reported as
+/// [`Readings::Health`] every [`REPORT_INTERVAL`].
+///
+/// A sensor is tracked from its first readout on, and keeps being counted as failing while it is
+/// missing from the readouts, e.g. after it dropped off the bus. Excluded sensors are never read
+/// out, and are therefore never reported.
+#[derive(Default)]
+pub struct Health {
+    sensors: BTreeMap<u32, (u32, u32)>,
+    reported: Option<Instant>,
+}
+
+impl Health {
+    /// Record the outcome of an acquisition, as the ID and success of every sensor read out.
+    pub fn update(&mut self, outcomes: impl IntoIterator<Item = (u32, bool)>) {
+        let mut seen = Vec::new();
+        for (id, ok) in outcomes {
+            let (consecutive, total) = self.sensors.entry(id).or_default();
+            if ok {
+                *consecutive = 0;
+            } else {
+                *consecutive = consecutive.saturating_add(1);
+                *total = total.saturating_add(1);
+            }
+            seen.push(id);
+        }
+        for (_, (consecutive, total)) in
+            self.sensors.iter_mut().filter(|(id, _)| !seen.contains(id))
+        {
+            *consecutive = consecutive.saturating_add(1);
+            *total = total.saturating_add(1);
+        }
+    }
+
+    /// The health report, if one is due.
+    pub fn report(&mut self, now: Instant) -> Option<Readings> {
+        if self.sensors.is_empty()
+            || self
+                .reported
+                .is_some_and(|reported| now.duration_since(reported) < REPORT_INTERVAL)
+        {
+            return None;
+        }
+        self.reported = Some(now);
+        Some(Readings::Health(
+            self.sensors
+                .iter()
+                .map(|(&id, &(consecutive, total))| SensorHealth {
+                    id,
+                    consecutive,
+                    total,
+                })
+                .collect(),
+        ))
+    }
+}
+
+mod test {
+    #[test]
+    fn test_health() {
+        use super::{Health, REPORT_INTERVAL};
+        use crate::{Readings, SensorHealth};
+        use std::time::Instant;
+        let mut health = Health::default();
+        let now = Instant::now();
+        assert_eq!(health.report(now), None);
+        health.update([(1, true), (2, false)]);
+        health.update([(1, false), (2, false)]);
+        let health_of = |id, consecutive, total| SensorHealth {
+            id,
+            consecutive,
+            total,
+        };
+        assert_eq!(
+            health.report(now),
+            Some(Readings::Health(vec![
+                health_of(1, 1, 1),
+                health_of(2, 2, 2)
+            ]))
+        );
+        assert_eq!(health.report(now), None);
+        // a recovered sensor keeps its total, a missing one keeps failing
+        health.update([(1, true)]);
+        assert_eq!(
+            health.report(now + REPORT_INTERVAL),
+            Some(Readings::Health(vec![
+                health_of(1, 0, 1),
+                health_of(2, 3, 3)
+            ]))
+        );
+    }
+}
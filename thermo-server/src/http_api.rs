@@ -0,0 +1,316 @@
+use std::{
+    io::{self, BufRead, BufReader, Read, Write},
+    net::{SocketAddr, TcpListener, TcpStream},
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+
+use piccthermo_id::SensorId;
+use serde_json::{Value, json};
+
+use crate::{
+    Measurement,
+    control::Router,
+    latest::{LatestValues, Snapshot},
+    sink::MeasurementSink,
+};
+
+/// Interval at which the server checks for new connections and shutdown.
+const ACCEPT_INTERVAL: Duration = Duration::from_millis(100);
+/// Longest request header accepted, in bytes.
+const MAX_HEADER_LEN: u64 = 8192;
+
+impl Snapshot {
+    fn readings(&self) -> Value {
+        self.readings
+            .iter()
+            .map(|((bus, kind, id), (value, timestamp))| {
+                json!({
+                    "bus": bus,
+                    "type": kind,
+                    "id": SensorId::new(*id).to_string(),
+                    "value": value,
+                    "timestamp": timestamp,
+                })
+            })
+            .collect()
+    }
+
+    fn sensors(&self) -> Value {
+        self.inventory
+            .iter()
+            .map(|(bus, metadata)| {
+                let sensors = metadata
+                    .sensors
+                    .iter()
+                    .map(|sensor| {
+                        json!({
+                            "id": SensorId::new(sensor.id).to_string(),
+                            "address": format!("{:016x}", sensor.address),
+                            "model": sensor.model,
+                        })
+                    })
+                    .collect::<Vec<_>>();
+                json!({
+                    "bus": bus,
+                    "path": metadata.path,
+                    "version": metadata.version,
+                    "sensors": sensors,
+                })
+            })
+            .collect()
+    }
+
+    fn health(&self) -> Value {
+        self.health
+            .iter()
+            .map(|((bus, id), sensor)| {
+                json!({
+                    "bus": bus,
+                    "id": SensorId::new(*id).to_string(),
+                    "failures": sensor.consecutive,
+                    "total_failures": sensor.total,
+                })
+            })
+            .collect()
+    }
+}
+
+/// HTTP endpoint serving the state of the buses as JSON, and accepting commands.
+///
+/// - `GET /readings`: the most recent value of every sensor.
+/// - `GET /sensors`: the inventory of every bus.
+/// - `GET /health`: the failure counters of the sensors.
+/// - `POST /buses/<bus|*>/led/<id>/<on|off>`: turn the LED of a sensor on or off.
+/// - `POST /buses/<bus|*>/enumerate`: enumerate the sensors of a bus again.
+/// - `POST /buses/<bus|*>/rate/<milliseconds>`: change the poll interval of a bus.
+///
+/// The commands are dispatched by the [`Router`] shared with the serial link, and answered with
+/// the `ACK`/`NACK` responses of the backends. The state is served from the [`LatestValues`]
+/// updated by the acquisition threads, so the measurements written to the sink are not used.
+pub struct HttpSink {
+    addr: SocketAddr,
+    router: Arc<Router>,
+    latest: Arc<LatestValues>,
+    server: Option<(Arc<AtomicBool>, JoinHandle<()>)>,
+}
+
+impl HttpSink {
+    pub fn new(addr: SocketAddr, router: Arc<Router>, latest: Arc<LatestValues>) -> Self {
+        Self {
+            addr,
+            router,
+            latest,
+            server: None,
+        }
+    }
+}
+
+impl MeasurementSink for HttpSink {
+    fn name(&self) -> String {
+        format!("[API] {}", self.addr)
+    }
+
+    fn open(&mut self) -> Result<(), String> {
+        let listener = TcpListener::bind(self.addr).map_err(|e| format!("Failed to bind: {e}"))?;
+        listener
+            .set_nonblocking(true)
+            .map_err(|e| format!("Failed to set listener non-blocking: {e}"))?;
+        let sig = Arc::new(AtomicBool::new(true));
+        let hdl = {
+            let sig = sig.clone();
+            let addr = self.addr;
+            let router = self.router.clone();
+            let latest = self.latest.clone();
+            thread::spawn(move || server_thread(addr, listener, sig, router, latest))
+        };
+        self.server = Some((sig, hdl));
+        Ok(())
+    }
+
+    fn write(&mut self, _measurement: &Measurement) -> Result<(), String> {
+        Ok(())
+    }
+
+    fn close(&mut self) {
+        if let Some((sig, hdl)) = self.server.take() {
+            sig.store(false, Ordering::Relaxed);
+            let _ = hdl.join();
+        }
+    }
+}
+
+fn server_thread(
+    addr: SocketAddr,
+    listener: TcpListener,
+    running: Arc<AtomicBool>,
+    router: Arc<Router>,
+    latest: Arc<LatestValues>,
+) {
+    while running.load(Ordering::Relaxed) {
+        match listener.accept() {
+            Ok((stream, peer)) => {
+                // commands wait for the backends, so that every client is served on its own
+                let router = router.clone();
+                let latest = latest.clone();
+                thread::spawn(move || {
+                    if let Err(e) = serve(stream, &router, &latest.snapshot()) {
+                        log::warn!("[API] {addr}> Failed to serve {peer}: {e}");
+                    }
+                });
+            }
+            Err(e) if e.kind() == io::ErrorKind::WouldBlock => thread::sleep(ACCEPT_INTERVAL),
+            Err(e) => {
+                log::error!("[API] {addr}> Failed to accept connection: {e}");
+                thread::sleep(ACCEPT_INTERVAL);
+            }
+        }
+    }
+}
+
+fn serve(mut stream: TcpStream, router: &Router, state: &Snapshot) -> io::Result<()> {
+    stream.set_nonblocking(false)?;
+    stream.set_read_timeout(Some(Duration::from_secs(1)))?;
+    let mut reader = BufReader::new((&stream).take(MAX_HEADER_LEN));
+    let mut request = String::new();
+    reader.read_line(&mut request)?;
+    // skip the headers, the requests have no body
+    let mut header = String::new();
+    while reader.read_line(&mut header)? > 2 {
+        header.clear();
+    }
+    let mut words = request.split_whitespace();
+    let (method, path) = (
+        words.next().unwrap_or_default(),
+        words.next().unwrap_or_default(),
+    );
+    let (status, body) = route(method, path, router, state);
+    let body = body.to_string();
+    write!(
+        stream,
+        "HTTP/1.1 {status}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        body.len()
+    )
+}
+
+/// Answer a request.
+///
+/// # Returns
+/// The status line and the JSON body of the response.
+fn route(method: &str, path: &str, router: &Router, state: &Snapshot) -> (&'static str, Value) {
+    let segments = path
+        .trim_matches('/')
+        .split('/')
+        .filter(|segment| !segment.is_empty())
+        .collect::<Vec<_>>();
+    let command = match (method, segments.as_slice()) {
+        ("GET", [endpoint @ ("readings" | "sensors" | "health")]) => {
+            let body = match *endpoint {
+                "readings" => state.readings(),
+                "sensors" => state.sensors(),
+                _ => state.health(),
+            };
+            return ("200 OK", body);
+        }
+        ("POST", ["buses", bus, "led", id, on]) => format!("led {bus} {id} {on}"),
+        ("POST", ["buses", bus, "enumerate"]) => format!("enumerate {bus}"),
+        ("POST", ["buses", bus, "rate", ms]) => format!("rate {bus} {ms}"),
+        (_, ["readings" | "sensors" | "health"])
+        | (_, ["buses", _, "led", _, _] | ["buses", _, "enumerate"] | ["buses", _, "rate", _]) => {
+            return (
+                "405 Method Not Allowed",
+                json!({ "error": "method not allowed" }),
+            );
+        }
+        _ => return ("404 Not Found", json!({ "error": "not found" })),
+    };
+    log::info!("[API] Command: {command}");
+    let responses = router.execute(&command);
+    let status = if responses.iter().all(|response| response.starts_with("ACK")) {
+        "200 OK"
+    } else {
+        "400 Bad Request"
+    };
+    (status, json!({ "responses": responses }))
+}
+
+mod test {
+    #[test]
+    fn test_route() {
+        use super::route;
+        use crate::{
+            Measurement, Metadata, Readings, SensorEntry, SensorHealth, control::Router,
+            latest::LatestValues,
+        };
+        use serde_json::json;
+        let latest = LatestValues::default();
+        let router = Router::default();
+        let measurement = |readings| Measurement {
+            sequence: 0,
+            timestamp: 1_700_000_000_000,
+            source: "i2c-1".into(),
+            readings,
+        };
+        let update = |readings| latest.update(&measurement(readings));
+        update(Readings::Temperature(vec![(0xdeadbeef, 21.5)]));
+        update(Readings::Metadata(Metadata {
+            version: "0.0.1".into(),
+            path: "/dev/i2c-1".into(),
+            sensors: vec![SensorEntry {
+                id: 0x1234,
+                address: 0x42,
+                model: "ds28ea00".into(),
+            }],
+        }));
+        update(Readings::Temperature(vec![(0x1234, 22.5)]));
+        update(Readings::Health(vec![SensorHealth {
+            id: 0x1234,
+            consecutive: 1,
+            total: 2,
+        }]));
+        let state = latest.snapshot();
+        // the readings of the sensors gone before the enumeration are dropped
+        assert_eq!(
+            route("GET", "/readings", &router, &state),
+            (
+                "200 OK",
+                json!([{
+                    "bus": "i2c-1",
+                    "type": "temperature",
+                    "id": "00001234",
+                    "value": 22.5,
+                    "timestamp": 1_700_000_000_000u64,
+                }])
+            )
+        );
+        let (status, sensors) = route("GET", "/sensors", &router, &state);
+        assert_eq!(status, "200 OK");
+        assert_eq!(sensors[0]["sensors"][0]["model"], "ds28ea00");
+        let (_, health) = route("GET", "/health/", &router, &state);
+        assert_eq!(health[0]["total_failures"], 2);
+        // commands are dispatched by the router
+        assert_eq!(
+            route("POST", "/buses/i2c-9/enumerate", &router, &state),
+            (
+                "400 Bad Request",
+                json!({ "responses": ["NACK i2c-9 no such bus"] })
+            )
+        );
+        assert_eq!(
+            route("POST", "/buses/*/rate/0", &router, &state),
+            (
+                "400 Bad Request",
+                json!({ "responses": ["NACK * rate must be positive"] })
+            )
+        );
+        assert_eq!(
+            route("GET", "/buses/*/enumerate", &router, &state).0,
+            "405 Method Not Allowed"
+        );
+        assert_eq!(route("GET", "/metrics", &router, &state).0, "404 Not Found");
+    }
+}
@@ -1,121 +1,582 @@
 use std::{
+    fmt::Debug,
     path::PathBuf,
-    sync::{
-        Arc,
-        atomic::{AtomicBool, Ordering},
-    },
+    sync::Arc,
+    thread,
     time::{Duration, Instant},
 };
 
-use hdc1010::{Hdc1010Builder, SlaveAddress as H10SlaveAddress, Trigger};
+use hdc1010::{
+    Both, Hdc1010, Hdc1010Builder, HumidityResolution, SlaveAddress as H10SlaveAddress,
+    Temperature, TemperatureResolution, conditioning::Conditioner, hygrometry,
+};
+use hdc3022::{Hdc3022, Hdc3022Builder, SlaveAddress as H30SlaveAddress};
 use linux_embedded_hal::{Delay, I2cdev};
+use piccthermo_core::{HumiditySensor, RelativeHumidity, SensorDriver};
+use piccthermo_id::SensorId;
+
+use crate::{
+    Readings, SensorEntry,
+    backend::{self, SensorBackend},
+    config::BusConfig,
+    control::Command,
+    health::Health,
+    sensor_map::SensorMap,
+};
+
+/// Interval between two probes for sensors added to the bus.
+const PROBE_INTERVAL: Duration = Duration::from_secs(30);
+/// Consecutive failed measurements after which a sensor is reset.
+const MAX_FAILURES: u32 = 3;
+/// Time for a sensor to cool down after a heater burn-off cycle, during which its readings are
+/// still masked.
+const HEATER_SETTLE: Duration = Duration::from_secs(10);
+
+/// The `(a0, a1)` address straps of the four sensors a bus can hold.
+const STRAPS: [(bool, bool); 4] = [(false, false), (true, false), (false, true), (true, true)];
+
+/// A humidity sensor model driven by [`HumidityBackend`].
+pub trait Hygrometer: HumiditySensor<I2cdev> + SensorDriver<I2cdev, Error: Debug> + Sized {
+    /// Part number, e.g. `hdc1010`.
+    const MODEL: &'static str;
+    /// Measurement settings taken from the bus configuration.
+    type Settings: Copy;
+
+    /// Measurement settings of the sensors on a bus.
+    fn settings(config: &BusConfig) -> Self::Settings;
+
+    /// I2C address of a sensor with the given address straps.
+    fn strapped_address(a0: bool, a1: bool) -> u8;
+
+    /// Address straps of the sensors that may be on the bus, to be set up with [`Hygrometer::build`].
+    fn scan(_i2c: &mut I2cdev) -> Vec<(bool, bool)> {
+        STRAPS.into()
+    }
+
+    /// Set up the sensor with the given address straps.
+    fn build(
+        i2c: &mut I2cdev,
+        a0: bool,
+        a1: bool,
+        settings: Self::Settings,
+    ) -> Result<Self, String>;
+
+    /// I2C address of the sensor.
+    fn address(&self) -> u8;
+
+    /// Soft reset the sensor, keeping its settings.
+    fn reset(&mut self, i2c: &mut I2cdev) -> Result<(), String>;
+
+    /// Turn the integrated heater on or off.
+    fn set_heater(&mut self, i2c: &mut I2cdev, on: bool) -> Result<(), String>;
+
+    /// Returns `true` once if the sensor observed a supply brown-out since the last call.
+    fn take_brownout(&mut self) -> bool {
+        false
+    }
+}
+
+impl Hygrometer for Hdc1010<Both> {
+    const MODEL: &'static str = "hdc1010";
+    type Settings = (TemperatureResolution, HumidityResolution);
+
+    fn settings(config: &BusConfig) -> Self::Settings {
+        config.hdc1010_resolution()
+    }
+
+    fn strapped_address(a0: bool, a1: bool) -> u8 {
+        H10SlaveAddress::default()
+            .with_a0(a0)
+            .with_a1(a1)
+            .into_bits()
+    }
+
+    fn scan(i2c: &mut I2cdev) -> Vec<(bool, bool)> {
+        hdc1010::scan(i2c)
+            .into_iter()
+            .map(|addr| (addr.a0(), addr.a1()))
+            .collect()
+    }
 
-use crate::{Measurement, safe_mpsc};
+    fn build(
+        i2c: &mut I2cdev,
+        a0: bool,
+        a1: bool,
+        (tres, hres): Self::Settings,
+    ) -> Result<Self, String> {
+        Hdc1010Builder::default()
+            .with_address(H10SlaveAddress::default().with_a0(a0).with_a1(a1))
+            .with_temperature_resolution(tres)
+            .with_humidity_resolution(hres)
+            .build_mode_both(i2c)
+            .map_err(|e| format!("{e:?}"))
+    }
+
+    fn address(&self) -> u8 {
+        self.get_address()
+    }
+
+    fn reset(&mut self, i2c: &mut I2cdev) -> Result<(), String> {
+        Hdc1010::reset(self, i2c, &mut Delay).map_err(|e| format!("{e:?}"))
+    }
+
+    fn set_heater(&mut self, i2c: &mut I2cdev, on: bool) -> Result<(), String> {
+        Hdc1010::set_heater(self, i2c, on).map_err(|e| format!("{e:?}"))
+    }
 
-pub fn humidity_thread(
+    fn take_brownout(&mut self) -> bool {
+        Hdc1010::take_brownout(self)
+    }
+}
+
+impl Hygrometer for Hdc3022 {
+    const MODEL: &'static str = "hdc3022";
+    type Settings = ();
+
+    fn settings(_: &BusConfig) -> Self::Settings {}
+
+    fn strapped_address(a0: bool, a1: bool) -> u8 {
+        H30SlaveAddress::default()
+            .with_a0(a0)
+            .with_a1(a1)
+            .into_bits()
+    }
+
+    fn build(i2c: &mut I2cdev, a0: bool, a1: bool, _: Self::Settings) -> Result<Self, String> {
+        Hdc3022Builder::default()
+            .with_address(H30SlaveAddress::default().with_a0(a0).with_a1(a1))
+            .build(i2c)
+            .map_err(|e| format!("{e:?}"))
+    }
+
+    fn address(&self) -> u8 {
+        self.get_address()
+    }
+
+    fn reset(&mut self, i2c: &mut I2cdev) -> Result<(), String> {
+        Hdc3022::reset(self, i2c, &mut Delay).map_err(|e| format!("{e:?}"))
+    }
+
+    fn set_heater(&mut self, i2c: &mut I2cdev, on: bool) -> Result<(), String> {
+        Hdc3022::set_heater(self, i2c, on).map_err(|e| format!("{e:?}"))
+    }
+}
+
+/// A sensor on the bus, with its count of consecutive failed measurements.
+struct Device<D> {
+    hdc: D,
+    failures: u32,
+    burn_off: Option<BurnOff>,
+    conditioner: Conditioner,
+}
+
+/// A heater burn-off cycle in progress on a sensor.
+struct BurnOff {
+    /// Time the heater is turned off at.
+    until: Instant,
+    /// The heater has not been turned off yet.
+    heating: bool,
+}
+
+/// HDC1010 or HDC3022 humidity sensors on an I2C bus.
+///
+/// Every sensor reports its humidity and the dew point under the ID of its I2C address on the bus,
+/// see [`SensorId::from_i2c`], and its temperature under that ID with the most significant bit set.
+///
+/// The addresses without a sensor are probed again every 30 seconds, so that sensors plugged in
+/// later are picked up. A sensor failing three measurements in a row is reset, and dropped if the
+/// reset fails too, until it is found again by a later probe.
+///
+/// The heater of a sensor can be run for a while with [`Command::Heat`], to burn off condensation.
+/// The sensor keeps being measured, since the heater only dissipates power during conversions, but
+/// its readings are masked until it has cooled down. A [`Readings::BurnOff`] event is sent once
+/// they are published again.
+///
+/// The readings are conditioned with a [`Conditioner`], and a [`Readings::Quality`] event lists
+/// the readings that are clamped, taken right after a reset or a burn-off, or stuck.
+pub struct HumidityBackend<D: Hygrometer> {
     path: PathBuf,
-    running: Arc<AtomicBool>,
-    sink: safe_mpsc::SafeSender<Measurement>,
-) {
-    let lpath = path.to_string_lossy();
-    'root: while running.load(Ordering::Relaxed) {
-        log::info!("[HUM] {lpath}> Opening bus");
+    settings: D::Settings,
+    stuck_samples: u8,
+    poll_interval: Duration,
+    sensors: Arc<SensorMap>,
+    health: Health,
+    bus: Option<(I2cdev, Vec<Device<D>>)>,
+    last_probe: Instant,
+}
 
-        // Open the I2C busString
-        let mut i2c = I2cdev::new(&path).expect("Failed to open I2C device");
-        let mut delay = Delay;
-        // Open all available devices
-        let addrs = [
-            H10SlaveAddress::default(),
-            H10SlaveAddress::default().with_a0(true),
-            H10SlaveAddress::default().with_a1(true),
-            H10SlaveAddress::default().with_a0(true).with_a1(true),
-        ];
-        let mut hdc10s = addrs
+impl<D: Hygrometer> HumidityBackend<D> {
+    pub fn new(config: &BusConfig, sensors: Arc<SensorMap>) -> Self {
+        Self {
+            path: config.path.clone(),
+            settings: D::settings(config),
+            stuck_samples: config.stuck_samples,
+            poll_interval: config.poll_interval(),
+            sensors,
+            health: Health::default(),
+            bus: None,
+            last_probe: Instant::now(),
+        }
+    }
+
+    /// Name of the bus, the file name of its device.
+    fn bus_name(&self) -> String {
+        self.path
+            .file_name()
+            .map_or_else(
+                || self.path.to_string_lossy(),
+                |name| name.to_string_lossy(),
+            )
+            .into_owned()
+    }
+
+    /// ID of the sensor at the given address, see [`SensorId::from_i2c`].
+    fn id(&self, addr: u8) -> u32 {
+        SensorId::from_i2c(&self.bus_name(), addr, false).value()
+    }
+
+    /// Set up the sensor with the given address straps, if there is one.
+    fn probe(&self, i2c: &mut I2cdev, (a0, a1): (bool, bool)) -> Option<Device<D>> {
+        let lpath = self.path.to_string_lossy();
+        match D::build(i2c, a0, a1, self.settings) {
+            Ok(mut hdc) => {
+                log::info!(
+                    "[HUM] {lpath}> Device found at address {:02x}",
+                    hdc.address()
+                );
+                if let Err(e) = hdc.reset(i2c) {
+                    log::error!(
+                        sensor:% = format_args!("{:08x}", self.id(hdc.address()));
+                        "[HUM] {lpath}> Error resetting sensor {:02x}: {e}.",
+                        hdc.address()
+                    );
+                    return None;
+                }
+                let mut conditioner = Conditioner::default().with_stuck_samples(self.stuck_samples);
+                conditioner.settle();
+                Some(Device {
+                    hdc,
+                    failures: 0,
+                    burn_off: None,
+                    conditioner,
+                })
+            }
+            Err(e) => {
+                log::debug!(
+                    "[HUM] {lpath}> Address {:02x} not found: {e}",
+                    D::strapped_address(a0, a1)
+                );
+                None
+            }
+        }
+    }
+}
+
+impl<D: Hygrometer + Send> SensorBackend for HumidityBackend<D>
+where
+    D::Settings: Send,
+{
+    fn name(&self) -> String {
+        format!("[HUM] {}", self.path.to_string_lossy())
+    }
+
+    fn bus(&self) -> String {
+        self.bus_name()
+    }
+
+    fn path(&self) -> String {
+        self.path.to_string_lossy().into_owned()
+    }
+
+    fn poll_interval(&self) -> Duration {
+        self.poll_interval
+    }
+
+    fn inventory(&self) -> Vec<SensorEntry> {
+        let Some((_, devices)) = self.bus.as_ref() else {
+            return Vec::new();
+        };
+        devices
             .iter()
-            .filter_map(|addr| {
-                match Hdc1010Builder::default()
-                    .with_address(*addr)
-                    .build_mode_separate(&mut i2c)
-                {
-                    Ok(mut hdc) => {
-                        log::info!(
-                            "[HUM] {lpath}> Device found at address {:02x}",
-                            hdc.get_address()
-                        );
-                        if let Err(e) = hdc.reset(&mut i2c, &mut delay) {
-                            log::error!(
-                                "[HUM] {lpath}> Error resetting sensor {:02x}: {e:?}.",
-                                hdc.get_address()
-                            );
-                            return None;
-                        }
-                        Some(hdc)
+            .flat_map(|dev| {
+                let address = dev.hdc.address();
+                [false, true].map(|temperature| SensorEntry {
+                    id: SensorId::from_i2c(&self.bus(), address, temperature).value(),
+                    address: address as u64,
+                    model: D::MODEL.into(),
+                })
+            })
+            .collect()
+    }
+
+    fn init(&mut self) -> Result<(), String> {
+        let lpath = self.path.to_string_lossy().into_owned();
+        self.bus = None;
+        log::info!("[HUM] {lpath}> Opening bus");
+        // Open the I2C bus
+        let mut i2c = I2cdev::new(&self.path).map_err(|e| format!("Failed to open bus: {e}"))?;
+        // Open all available devices
+        let devices = D::scan(&mut i2c)
+            .into_iter()
+            .filter_map(|straps| self.probe(&mut i2c, straps))
+            .collect::<Vec<_>>();
+        log::info!("[HUM] {lpath}> {} devices found.", devices.len());
+        thread::sleep(Duration::from_secs(1));
+        self.bus = Some((i2c, devices));
+        self.last_probe = Instant::now();
+        Ok(())
+    }
+
+    fn acquire(&mut self) -> Result<Vec<Readings>, String> {
+        let lpath = self.path.to_string_lossy().into_owned();
+        let bus = self.bus();
+        let sensor_id = |addr| SensorId::from_i2c(&bus, addr, false).value();
+        if self.bus.is_none() {
+            return Err("Bus not initialized".into());
+        }
+        // Look for sensors that were added since the last probe
+        let mut changed = false;
+        if self.last_probe.elapsed() >= PROBE_INTERVAL {
+            self.last_probe = Instant::now();
+            let (mut i2c, mut devices) = self.bus.take().unwrap();
+            for (a0, a1) in D::scan(&mut i2c) {
+                let addr = D::strapped_address(a0, a1);
+                if devices.iter().any(|dev| dev.hdc.address() == addr) {
+                    continue;
+                }
+                if let Some(dev) = self.probe(&mut i2c, (a0, a1)) {
+                    log::info!("[HUM] {lpath}> Sensor 0x{addr:02x} added");
+                    devices.push(dev);
+                    changed = true;
+                }
+            }
+            devices.sort_by_key(|dev| dev.hdc.address());
+            self.bus = Some((i2c, devices));
+        }
+        let Some((i2c, devices)) = self.bus.as_mut() else {
+            return Err("Bus not initialized".into());
+        };
+        let start = Instant::now();
+        // Advance the heater burn-off cycles, and mask the readings of the sensors they affect
+        let mut burnt_off = Vec::new();
+        for dev in devices.iter_mut() {
+            let Some(burn_off) = dev.burn_off.as_mut() else {
+                continue;
+            };
+            let addr = dev.hdc.address();
+            if burn_off.heating && start >= burn_off.until {
+                match dev.hdc.set_heater(i2c, false) {
+                    Ok(()) => {
+                        log::info!("[HUM] {lpath}> Sensor 0x{addr:02x}: Heater turned off");
+                        burn_off.heating = false;
                     }
                     Err(e) => {
-                        log::warn!(
-                            "[HUM] {lpath}> Address {:02x} not found: {e:?}",
-                            addr.into_bits()
-                        );
-                        None
+                        log::error!(
+                            sensor:% = format_args!("{:08x}", sensor_id(addr));
+                            "[HUM] {lpath}> Sensor 0x{addr:02x}: Could not turn off heater: {e}"
+                        )
                     }
                 }
+            } else if !burn_off.heating && start >= burn_off.until + HEATER_SETTLE {
+                log::info!("[HUM] {lpath}> Sensor 0x{addr:02x}: Burn-off cycle completed");
+                dev.burn_off = None;
+                dev.conditioner.settle();
+                burnt_off.push(sensor_id(addr));
+            }
+        }
+        let masked = devices
+            .iter()
+            .filter(|dev| dev.burn_off.is_some())
+            .map(|dev| sensor_id(dev.hdc.address()))
+            .collect::<Vec<_>>();
+        let triggered = devices
+            .iter_mut()
+            .map(|dev| match SensorDriver::trigger(&mut dev.hdc, i2c) {
+                Ok(()) => Some(SensorDriver::ready_after(&dev.hdc)),
+                Err(e) => {
+                    log::warn!(
+                        sensor:% = format_args!("{:08x}", sensor_id(dev.hdc.address()));
+                        "[HUM] {lpath}> Sensor 0x{:02x}: Could not trigger: {e:?}",
+                        dev.hdc.address()
+                    );
+                    dev.failures += 1;
+                    None
+                }
             })
             .collect::<Vec<_>>();
-        log::info!("[HUM] {lpath}> {} devices found.", hdc10s.len());
-        std::thread::sleep(Duration::from_secs(1));
-        while running.load(Ordering::Relaxed) {
-            let start = Instant::now();
-            if let Some(delay) = hdc10s
+        let mut outcomes = triggered
+            .iter()
+            .zip(devices.iter())
+            .filter(|(delay, _)| delay.is_none())
+            .map(|(_, dev)| (sensor_id(dev.hdc.address()), false))
+            .collect::<Vec<_>>();
+        let mut mes = Vec::with_capacity(devices.len());
+        let mut temps = Vec::with_capacity(devices.len());
+        let mut dew = Vec::with_capacity(devices.len());
+        let mut flags = Vec::new();
+        if let Some(delay) = triggered.iter().flatten().max() {
+            thread::sleep(*delay);
+            for (dev, _) in devices
                 .iter_mut()
-                .filter_map(|hdc| {
-                    hdc.trigger(&mut i2c, Trigger::Humidity)
-                        .map_err(|e| {
-                            log::warn!(
-                                "[HUM] {lpath} Sensor 0x{:02x}: Could not trigger: {e:?}",
-                                hdc.get_address()
-                            );
-                            e
-                        })
-                        .ok()
-                })
-                .max()
+                .zip(&triggered)
+                .filter(|(_, delay)| delay.is_some())
             {
-                std::thread::sleep(delay);
-                let mes = hdc10s
+                let mut readings = Vec::new();
+                let res = HumiditySensor::read(&mut dev.hdc, i2c, &mut |addr, t, r| {
+                    readings.push((addr as u8, t, r))
+                });
+                for (addr, t, r) in readings {
+                    let id = sensor_id(addr);
+                    if masked.contains(&id) {
+                        log::debug!("[HUM] {lpath}> Sensor 0x{addr:02x}: Heated reading masked");
+                        continue;
+                    }
+                    let tid = id | SensorId::TEMPERATURE_CHANNEL;
+                    // calibrate both channels first, so that the dew point follows the calibrated
+                    // values
+                    let t = Temperature::from_celsius(self.sensors.calibrate(tid, t.celsius()));
+                    let (r, quality) = dev.conditioner.condition(
+                        t,
+                        RelativeHumidity::from_percentage(
+                            self.sensors.calibrate(id, r.percentage()),
+                        ),
+                    );
+                    if !quality.is_good() {
+                        flags.push((id, quality.bits()));
+                    }
+                    // undefined in dry air, and then left out
+                    let dp = hygrometry::dew_point(t, r).map(|dp| dp.celsius());
+                    log::info!(
+                        "[HUM] {lpath}> Sensor 0x{addr:02x}: {:.2}°C, {}%, dew point {}",
+                        t.celsius(),
+                        r.percentage(),
+                        dp.map_or("undefined".into(), |dp| format!("{dp:.2}°C")),
+                    );
+                    mes.push((id, r.percentage()));
+                    temps.push((tid, t.celsius()));
+                    dew.extend(dp.map(|dp| (id, dp)));
+                }
+                outcomes.push((sensor_id(dev.hdc.address()), res.is_ok()));
+                if let Err(e) = res {
+                    log::error!(
+                        sensor:% = format_args!("{:08x}", sensor_id(dev.hdc.address()));
+                        "[HUM] {lpath}> Sensor 0x{:02x}: Error reading: {e:?}",
+                        dev.hdc.address()
+                    );
+                    #[cfg(feature = "metrics")]
+                    crate::metrics::read_error(&lpath, sensor_id(dev.hdc.address()));
+                    dev.failures += 1;
+                } else {
+                    dev.failures = 0;
+                }
+            }
+        }
+        log::info!(
+            "[HUM] {lpath}> Read {} sensors in {:.2} ms.",
+            devices.len(),
+            start.elapsed().as_secs_f64() * 1000.0
+        );
+        // Reset the sensors that keep failing, and drop the ones that do not recover
+        let before = devices.len();
+        devices.retain_mut(|dev| {
+            if dev.failures < MAX_FAILURES {
+                return true;
+            }
+            let addr = dev.hdc.address();
+            match dev.hdc.reset(i2c) {
+                Ok(()) => {
+                    log::warn!(
+                        sensor:% = format_args!("{:08x}", sensor_id(addr));
+                        "[HUM] {lpath}> Sensor 0x{addr:02x}: Reset after {MAX_FAILURES} failures"
+                    );
+                    dev.failures = 0;
+                    dev.conditioner.settle();
+                    true
+                }
+                Err(e) => {
+                    log::error!(
+                        sensor:% = format_args!("{:08x}", sensor_id(addr));
+                        "[HUM] {lpath}> Sensor 0x{addr:02x}: Removed, reset failed: {e}"
+                    );
+                    false
+                }
+            }
+        });
+        changed |= devices.len() != before;
+        let mut data = Vec::new();
+        if !mes.is_empty() {
+            data.push(Readings::Humidity(mes));
+            data.push(Readings::Temperature(temps));
+        }
+        if !dew.is_empty() {
+            data.push(Readings::DewPoint(dew));
+        }
+        if !flags.is_empty() {
+            data.push(Readings::Quality(flags));
+        }
+        let brownouts = devices
+            .iter_mut()
+            .filter_map(|dev| {
+                if !dev.hdc.take_brownout() {
+                    return None;
+                }
+                log::warn!(
+                    sensor:% = format_args!("{:08x}", sensor_id(dev.hdc.address()));
+                    "[HUM] {lpath}> Sensor 0x{:02x}: Supply voltage below 2.8 V",
+                    dev.hdc.address()
+                );
+                Some(sensor_id(dev.hdc.address()))
+            })
+            .collect::<Vec<_>>();
+        if !brownouts.is_empty() {
+            data.push(Readings::Brownout(brownouts));
+        }
+        if !burnt_off.is_empty() {
+            data.push(Readings::BurnOff(burnt_off));
+        }
+        self.health.update(outcomes);
+        data.extend(self.health.report(Instant::now()));
+        if changed {
+            data.push(backend::metadata(self));
+        }
+        Ok(data)
+    }
+
+    fn command(&mut self, command: &Command) -> Result<String, String> {
+        let lpath = self.path.to_string_lossy();
+        let bus = self.bus();
+        let sensor_id = |addr| SensorId::from_i2c(&bus, addr, false).value();
+        let Some((i2c, devices)) = self.bus.as_mut() else {
+            return Err("bus not initialized".into());
+        };
+        match command {
+            Command::List => Ok(devices
+                .iter()
+                .map(|dev| format!("{:08x}", sensor_id(dev.hdc.address())))
+                .collect::<Vec<_>>()
+                .join(",")),
+            Command::Heat(id, duration) => {
+                // either channel of the sensor selects it
+                let humidity = id & !SensorId::TEMPERATURE_CHANNEL;
+                let dev = devices
                     .iter_mut()
-                    .filter_map(|hdc| match hdc.read_humidity(&mut i2c) {
-                        Ok(r) => {
-                            log::info!(
-                                "[HUM] {lpath}> Sensor 0x{:02x}: {}%",
-                                hdc.get_address(),
-                                r.percentage()
-                            );
-                            Some((hdc.get_address() as u32, r.percentage()))
-                        }
-                        Err(e) => {
-                            log::error!(
-                                "[HUM] {lpath}> Sensor 0x{:02x}: Error reading: {e:?}",
-                                hdc.get_address()
-                            );
-                            None
-                        }
-                    })
-                    .collect::<Vec<_>>();
+                    .find(|dev| sensor_id(dev.hdc.address()) == humidity)
+                    .ok_or_else(|| format!("no sensor with ID {id:08x}"))?;
+                let addr = dev.hdc.address();
+                dev.hdc
+                    .set_heater(i2c, true)
+                    .map_err(|e| format!("failed to turn on heater: {e}"))?;
+                dev.burn_off = Some(BurnOff {
+                    until: Instant::now() + *duration,
+                    heating: true,
+                });
                 log::info!(
-                    "[HUM] {lpath}> Read {} sensors in {:.2} ms.",
-                    hdc10s.len(),
-                    start.elapsed().as_secs_f64() * 1000.0
+                    "[HUM] {lpath}> Sensor 0x{addr:02x}: Heater on for {} s",
+                    duration.as_secs()
                 );
-                if let Err(e) = sink.send(Measurement::Humidity(mes)) {
-                    log::error!("[HUM] {lpath}> We are leaving {e:?}.");
-                    continue 'root;
-                }
-            }
-            if start.elapsed().as_secs() < 1 {
-                std::thread::sleep(Duration::from_secs(1) - start.elapsed());
+                Ok(format!("heat {addr:02x} {} s", duration.as_secs()))
             }
+            _ => Err("unsupported command".into()),
         }
     }
-    log::info!("[HUM] {lpath}> Exiting thread.")
 }
@@ -0,0 +1,125 @@
+use std::{
+    collections::BTreeMap,
+    sync::{Arc, RwLock},
+};
+
+use crate::{Measurement, Metadata, Readings, SensorHealth};
+
+/// Latest state of the buses, as seen by the acquisition threads.
+#[derive(Clone, Default)]
+pub struct Snapshot {
+    /// Most recent value and its timestamp, keyed by bus, measurement type and sensor ID.
+    pub readings: BTreeMap<(String, &'static str, u32), (f32, u64)>,
+    /// Latest inventory, keyed by bus.
+    pub inventory: BTreeMap<String, Metadata>,
+    /// Latest failure counters, keyed by bus and sensor ID.
+    pub health: BTreeMap<(String, u32), SensorHealth>,
+}
+
+impl Snapshot {
+    fn update(&mut self, measurement: &Measurement) {
+        let source = &measurement.source;
+        match &measurement.readings {
+            Readings::Temperature(data) | Readings::Humidity(data) | Readings::DewPoint(data) => {
+                let kind = match measurement.readings {
+                    Readings::Temperature(_) => "temperature",
+                    Readings::Humidity(_) => "humidity",
+                    _ => "dew_point",
+                };
+                for (id, value) in data {
+                    self.readings
+                        .insert((source.clone(), kind, *id), (*value, measurement.timestamp));
+                }
+            }
+            Readings::Metadata(metadata) => {
+                // the sensors that are gone are no longer current
+                self.readings.retain(|(bus, _, _), _| bus != source);
+                self.health.retain(|(bus, _), _| bus != source);
+                self.inventory.insert(source.clone(), metadata.clone());
+            }
+            Readings::Health(data) => {
+                for sensor in data {
+                    self.health.insert((source.clone(), sensor.id), *sensor);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Latest values of the buses, updated by the acquisition threads and read by the endpoints.
+///
+/// The endpoints serve the state of the buses as soon as it is acquired, instead of as the sink
+/// thread gets to it, so that a slow or blocked sink never delays them. The state is double
+/// buffered: a reader takes the current [`Snapshot`] without copying it and reads it at its own
+/// pace, while the next update is written to a copy that replaces it once complete.
+#[derive(Default)]
+pub struct LatestValues {
+    current: RwLock<Arc<Snapshot>>,
+}
+
+impl LatestValues {
+    /// Record a measurement in the state of its bus.
+    pub fn update(&self, measurement: &Measurement) {
+        match &measurement.readings {
+            Readings::Temperature(_)
+            | Readings::Humidity(_)
+            | Readings::DewPoint(_)
+            | Readings::Metadata(_)
+            | Readings::Health(_) => {}
+            _ => return,
+        }
+        let Ok(mut current) = self.current.write() else {
+            log::error!("[MAIN] Latest values poisoned");
+            return;
+        };
+        // copied only while a reader holds the previous snapshot
+        Arc::make_mut(&mut current).update(measurement);
+    }
+
+    /// The current state of the buses, coherent across buses and sensors.
+    #[cfg_attr(not(any(feature = "http", feature = "metrics")), allow(dead_code))]
+    pub fn snapshot(&self) -> Arc<Snapshot> {
+        self.current
+            .read()
+            .map_or_else(|_| Arc::default(), |current| current.clone())
+    }
+}
+
+mod test {
+    #[test]
+    fn test_latest_values() {
+        use super::LatestValues;
+        use crate::{Measurement, Metadata, Readings, SensorEntry};
+        let latest = LatestValues::default();
+        let update = |readings| latest.update(&Measurement::new("i2c-1".into(), readings, 1));
+        update(Readings::Temperature(vec![(0xdeadbeef, 21.5)]));
+        let before = latest.snapshot();
+        update(Readings::Metadata(Metadata {
+            version: "0.0.1".into(),
+            path: "/dev/i2c-1".into(),
+            sensors: vec![SensorEntry {
+                id: 0x1234,
+                address: 0x42,
+                model: "ds28ea00".into(),
+            }],
+        }));
+        update(Readings::Temperature(vec![(0x1234, 22.5)]));
+        update(Readings::EndOfStream);
+        // a snapshot taken earlier is left as it was
+        assert_eq!(
+            before
+                .readings
+                .get(&("i2c-1".into(), "temperature", 0xdeadbeef)),
+            Some(&(21.5, 1))
+        );
+        assert!(before.inventory.is_empty());
+        let after = latest.snapshot();
+        assert_eq!(after.readings.len(), 1);
+        assert_eq!(
+            after.readings.get(&("i2c-1".into(), "temperature", 0x1234)),
+            Some(&(22.5, 1))
+        );
+        assert_eq!(after.inventory["i2c-1"].sensors.len(), 1);
+    }
+}
@@ -0,0 +1,5 @@
+//! # thermo-server
+//!
+//! Wire format of the measurement stream sent by the `thermo-server` binary, shared with the
+//! consumers of the stream through the `piccthermo-proto` crate.
+pub use piccthermo_proto::{cobs, data_format};
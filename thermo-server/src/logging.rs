@@ -0,0 +1,283 @@
+//! Console and structured file logging, configured with [`LogConfig`].
+//!
+//! Every record is written to the log file as a JSON object on its own line:
+//!
+//! ```json
+//! {"bus":"/dev/i2c-1","component":"TMP","level":"WARN","message":"Failed to read sensor with ID 1a2b3c4d: InvalidCrc","sensor":"1a2b3c4d","target":"thermo_server::temp_sensors","timestamp":1718000000000}
+//! ```
+//!
+//! The `component` and `bus` fields are taken from the `[TMP] /dev/i2c-1>` prefix of the
+//! messages, other fields are the key-values attached to the record, e.g. with
+//! `log::warn!(sensor:% = id; "...")`.
+use std::{
+    fs::{File, OpenOptions},
+    io::{self, Write},
+    path::PathBuf,
+    sync::Mutex,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use log::{LevelFilter, Log, Metadata, Record, kv};
+use serde_json::{Map, Number, Value};
+
+use crate::{config::LogConfig, file_sinks};
+
+/// Install the logger.
+///
+/// The console follows `RUST_LOG` if set, the levels of `config` otherwise. If the log file can
+/// not be opened, the logger is installed for the console only and the error is returned.
+pub fn init(config: Option<&LogConfig>) -> Result<(), String> {
+    let mut console = env_logger::Builder::new();
+    match config {
+        Some(config) if std::env::var_os("RUST_LOG").is_none() => {
+            console.filter_level(config.level);
+            for (module, level) in &config.modules {
+                console.filter_module(module, *level);
+            }
+        }
+        _ => {
+            console.parse_default_env();
+        }
+    }
+    let mut res = Ok(());
+    let file = config.and_then(|config| {
+        let path = config.file.as_ref()?;
+        LogFile::open(config, path.clone())
+            .inspect_err(|e| res = Err(format!("Failed to open {}: {e}", path.display())))
+            .ok()
+    });
+    let logger = Logger {
+        console: console.build(),
+        file,
+    };
+    log::set_max_level(logger.max_level());
+    log::set_boxed_logger(Box::new(logger)).map_err(|e| e.to_string())?;
+    res
+}
+
+/// Logger writing to the console and to the log file, each with its own levels.
+struct Logger {
+    console: env_logger::Logger,
+    file: Option<LogFile>,
+}
+
+impl Logger {
+    fn max_level(&self) -> LevelFilter {
+        self.file
+            .as_ref()
+            .map_or(LevelFilter::Off, |file| file.filter.filter())
+            .max(self.console.filter())
+    }
+}
+
+impl Log for Logger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        self.console.enabled(metadata)
+            || self
+                .file
+                .as_ref()
+                .is_some_and(|file| file.filter.enabled(metadata))
+    }
+
+    fn log(&self, record: &Record) {
+        self.console.log(record);
+        if let Some(ref file) = self.file
+            && file.filter.matches(record)
+        {
+            file.write(record);
+        }
+    }
+
+    fn flush(&self) {
+        self.console.flush();
+    }
+}
+
+/// JSON lines log file, rotated when it grows beyond a size limit.
+struct LogFile {
+    filter: env_filter::Filter,
+    writer: Mutex<Writer>,
+}
+
+impl LogFile {
+    fn open(config: &LogConfig, path: PathBuf) -> io::Result<Self> {
+        let mut filter = env_filter::Builder::new();
+        filter.filter_level(config.level);
+        for (module, level) in &config.modules {
+            filter.filter_module(module, *level);
+        }
+        let mut writer = Writer {
+            path,
+            max_bytes: config.max_bytes,
+            keep: config.keep,
+            file: None,
+            written: 0,
+            failed: false,
+        };
+        writer.open()?;
+        Ok(Self {
+            filter: filter.build(),
+            writer: Mutex::new(writer),
+        })
+    }
+
+    fn write(&self, record: &Record) {
+        let mut line = format_record(record);
+        line.push('\n');
+        let Ok(mut writer) = self.writer.lock() else {
+            return;
+        };
+        match writer.write(line.as_bytes()) {
+            Ok(()) => writer.failed = false,
+            Err(e) => {
+                // reported once, as logging the error would fail in turn
+                if !writer.failed {
+                    eprintln!("[LOG] {}> Failed to write: {e}", writer.path.display());
+                }
+                writer.failed = true;
+                writer.file = None;
+            }
+        }
+    }
+}
+
+struct Writer {
+    path: PathBuf,
+    max_bytes: u64,
+    keep: usize,
+    file: Option<File>,
+    written: u64,
+    failed: bool,
+}
+
+impl Writer {
+    fn open(&mut self) -> io::Result<&mut File> {
+        match self.file {
+            Some(ref mut file) => Ok(file),
+            None => {
+                let file = OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(&self.path)?;
+                self.written = file.metadata()?.len();
+                Ok(self.file.insert(file))
+            }
+        }
+    }
+
+    fn write(&mut self, line: &[u8]) -> io::Result<()> {
+        if self.written >= self.max_bytes {
+            self.file = None;
+            file_sinks::rotate(&self.path, self.keep)?;
+        }
+        self.open()?.write_all(line)?;
+        self.written += line.len() as u64;
+        Ok(())
+    }
+}
+
+/// Format a record as a JSON object.
+fn format_record(record: &Record) -> String {
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |t| t.as_millis() as u64);
+    let message = record.args().to_string();
+    let (component, bus, message) = split_prefix(&message);
+    let mut entry = Map::new();
+    entry.insert("timestamp".into(), timestamp.into());
+    entry.insert("level".into(), record.level().as_str().into());
+    entry.insert("target".into(), record.target().into());
+    if let Some(component) = component {
+        entry.insert("component".into(), component.into());
+    }
+    if let Some(bus) = bus {
+        entry.insert("bus".into(), bus.into());
+    }
+    let _ = record.key_values().visit(&mut Fields(&mut entry));
+    entry.insert("message".into(), message.into());
+    Value::Object(entry).to_string()
+}
+
+/// Split the `[TMP] /dev/i2c-1> ` prefix of a message into the component and the bus.
+fn split_prefix(message: &str) -> (Option<&str>, Option<&str>, &str) {
+    let Some((component, rest)) = message
+        .strip_prefix('[')
+        .and_then(|rest| rest.split_once("] "))
+        .filter(|(component, _)| component.chars().all(|c| c.is_ascii_uppercase()))
+    else {
+        return (None, None, message);
+    };
+    match rest.split_once("> ") {
+        Some((bus, rest)) if !bus.is_empty() && !bus.contains(char::is_whitespace) => {
+            (Some(component), Some(bus), rest)
+        }
+        _ => (Some(component), None, rest),
+    }
+}
+
+/// Collects the key-values of a record into the fields of its JSON object.
+struct Fields<'a>(&'a mut Map<String, Value>);
+
+impl<'kvs> kv::VisitSource<'kvs> for Fields<'_> {
+    fn visit_pair(&mut self, key: kv::Key<'kvs>, value: kv::Value<'kvs>) -> Result<(), kv::Error> {
+        let value = if let Some(value) = value.to_u64() {
+            value.into()
+        } else if let Some(value) = value.to_i64() {
+            value.into()
+        } else if let Some(value) = value.to_f64().and_then(Number::from_f64) {
+            value.into()
+        } else if let Some(value) = value.to_bool() {
+            value.into()
+        } else {
+            value.to_string().into()
+        };
+        self.0.insert(key.to_string(), value);
+        Ok(())
+    }
+}
+
+mod test {
+    #[test]
+    fn test_split_prefix() {
+        use super::split_prefix;
+        assert_eq!(
+            split_prefix("[TMP] /dev/i2c-1> Found 4 devices"),
+            (Some("TMP"), Some("/dev/i2c-1"), "Found 4 devices")
+        );
+        assert_eq!(
+            split_prefix("[MAIN] Fatal error: no bus"),
+            (Some("MAIN"), None, "Fatal error: no bus")
+        );
+        assert_eq!(
+            split_prefix("[COM] Sent 3 frames > limit"),
+            (Some("COM"), None, "Sent 3 frames > limit")
+        );
+        assert_eq!(
+            split_prefix("Received Ctrl+C"),
+            (None, None, "Received Ctrl+C")
+        );
+    }
+
+    #[test]
+    fn test_format_record() {
+        use super::format_record;
+        use log::{Level, Record};
+        let fields: &[(&str, u32)] = &[("sensor", 0x1a2b3c4d)];
+        let line = format_record(
+            &Record::builder()
+                .level(Level::Warn)
+                .target("thermo_server::temp_sensors")
+                .args(format_args!("[TMP] /dev/i2c-1> Failed to read sensor"))
+                .key_values(&fields)
+                .build(),
+        );
+        let entry: serde_json::Value = serde_json::from_str(&line).unwrap();
+        assert_eq!(entry["level"], "WARN");
+        assert_eq!(entry["target"], "thermo_server::temp_sensors");
+        assert_eq!(entry["component"], "TMP");
+        assert_eq!(entry["bus"], "/dev/i2c-1");
+        assert_eq!(entry["sensor"], 0x1a2b3c4d);
+        assert_eq!(entry["message"], "Failed to read sensor");
+        assert!(entry["timestamp"].is_u64());
+    }
+}
@@ -1,26 +1,78 @@
 use std::{
+    net::SocketAddr,
     path::PathBuf,
     sync::{
         Arc,
         atomic::{AtomicBool, Ordering},
+        mpsc,
     },
     thread,
-    time::Duration,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
 };
 
 use clap::Parser;
+use hdc1010::{Both, Hdc1010};
+use hdc3022::Hdc3022;
 
 // Local imports
+mod backend;
+mod config;
+mod control;
 mod cpu_sensors;
-mod data_format;
+mod failover;
+mod file_sinks;
+mod filter;
+mod gradient;
+mod health;
+#[cfg(feature = "http")]
+mod http_api;
 mod humi_sensors;
+mod latest;
+mod logging;
+mod manifest;
+#[cfg(feature = "metrics")]
+mod metrics;
+#[cfg(feature = "mqtt")]
+mod mqtt_sink;
+mod net_sink;
+mod ring_buffer;
 mod safe_mpsc;
+mod self_test;
+mod sensor_map;
 mod serial_comm;
+mod sim_sensors;
+mod sink;
+#[cfg(feature = "systemd")]
+mod systemd;
 mod temp_sensors;
+mod thermal;
+mod time_sync;
+mod watchdog;
 
-pub use data_format::Measurement;
-use humi_sensors::humidity_thread;
-use temp_sensors::onewire_thread;
+use backend::SensorBackend;
+use config::{Config, SensorType, SinkConfig};
+use control::{Command, Request, Router};
+use cpu_sensors::CpuBackend;
+use failover::FailoverSink;
+use file_sinks::{CsvSink, JsonSink};
+use filter::FilteredBackend;
+use gradient::GradientBackend;
+use humi_sensors::HumidityBackend;
+use latest::LatestValues;
+use manifest::ManifestBackend;
+use net_sink::{TcpSink, UdpSink, UnixSink};
+use ring_buffer::BufferedSink;
+use self_test::Report;
+use sensor_map::SensorMap;
+use serial_comm::SerialSink;
+use sim_sensors::SimBackend;
+use sink::MeasurementSink;
+use temp_sensors::OneWireBackend;
+pub use thermo_server::data_format::{
+    Measurement, Metadata, Readings, SensorEntry, SensorHealth, SyncMarker,
+};
+use time_sync::SyncClock;
+use watchdog::Heartbeat;
 
 /// Simple program to greet a person
 #[derive(Parser, Debug)]
@@ -31,53 +83,230 @@ struct Args {
         long,
         use_value_delimiter = true,
         value_delimiter = ',',
-        default_value = "1",
+        default_value = "1"
     )]
     thermo_paths: Vec<u8>,
     /// I2C bus IDs for humidity sensors (e.g. 0,1,2 for /dev/i2c-0, /dev/i2c-1, /dev/i2c-2)
     #[arg(long, use_value_delimiter = true, value_delimiter = ',')]
     humidity_paths: Vec<u8>,
+    /// Humidity sensor family on the humidity buses
+    #[arg(long, value_enum, default_value_t = config::HumidityType::Hdc1010)]
+    humidity_type: config::HumidityType,
     /// Serial port for data sink
     #[arg(long, required = false)]
     serial: Option<String>,
+    /// Buffer up to 16 MiB of measurements in this file while the serial port is disconnected
+    #[arg(long, requires = "serial")]
+    serial_buffer: Option<PathBuf>,
+    /// Backup serial port, used while the primary serial port fails
+    #[arg(long, requires = "serial")]
+    serial_backup: Option<String>,
+    /// Baud rate of the serial port
+    #[arg(long, default_value_t = config::default_baud())]
+    baud: u32,
+    /// Parity bit of the serial port
+    #[arg(long, value_enum, default_value_t = config::Parity::None)]
+    parity: config::Parity,
+    /// Flow control of the serial port
+    #[arg(long, value_enum, default_value_t = config::FlowControl::None)]
+    flow_control: config::FlowControl,
+    /// Timeout of the writes to the serial port, in milliseconds
+    #[arg(long, default_value_t = config::default_serial_timeout_ms())]
+    serial_timeout_ms: u64,
     /// Enable LED control
     #[arg(long, default_value_t = false)]
     leds: bool,
-    /// Exclusion filter
+    /// Sensors to leave out of the readout, as comma separated ROM codes, IDs or label globs
     #[arg(long, default_value_t = String::from(""))]
     exclude: String,
+    /// Only read these sensors, as comma separated ROM codes, IDs or label globs
+    #[arg(long, default_value_t = String::from(""))]
+    include_only: String,
     /// Disable overdriven mode
     #[arg(long, default_value_t = false)]
     no_overdrive: bool,
+    /// Interval between two acquisitions on every bus, in milliseconds
+    #[arg(long, default_value_t = config::default_poll_interval_ms())]
+    poll_interval_ms: u64,
+    /// Measurement resolution in bits. Picked from the poll interval for DS28EA00 sensors if unset
+    #[arg(long)]
+    resolution: Option<u8>,
+    /// Write newline-delimited JSON to a file, or to stdout if set to `-`
+    #[arg(long)]
+    json: Option<PathBuf>,
+    /// Write CSV to a file, rotated every 10 MiB
+    #[arg(long)]
+    csv: Option<PathBuf>,
+    /// Serve binary frames to TCP clients connecting to this address (e.g. 0.0.0.0:9000)
+    #[arg(long)]
+    tcp: Option<SocketAddr>,
+    /// Send binary frames as UDP datagrams to this address (e.g. a multicast group 239.0.0.1:9000)
+    #[arg(long)]
+    udp: Option<SocketAddr>,
+    /// Serve measurements to local processes on this UNIX socket (e.g. /run/thermo/measurements.sock)
+    #[arg(long)]
+    unix: Option<PathBuf>,
+    /// Format of the measurements served on the UNIX socket
+    #[arg(long, value_enum, requires = "unix", default_value_t = config::StreamFormat::Json)]
+    unix_format: config::StreamFormat,
+    /// Serve the status and control API at http://<ADDR>/ (e.g. 0.0.0.0:8080)
+    #[cfg(feature = "http")]
+    #[arg(long)]
+    http: Option<SocketAddr>,
+    /// Serve Prometheus metrics at http://<ADDR>/metrics (e.g. 0.0.0.0:9100)
+    #[cfg(feature = "metrics")]
+    #[arg(long)]
+    metrics: Option<SocketAddr>,
+    /// TOML configuration file. Overrides the bus, serial, LED and exclusion options when set.
+    #[arg(long)]
+    config: Option<PathBuf>,
+    /// TOML file with sensor labels, locations and calibrations
+    #[arg(long)]
+    sensor_map: Option<PathBuf>,
+    /// Restart an acquisition thread stuck on its bus for this long past its poll interval
+    #[arg(long, default_value_t = config::default_watchdog_ms())]
+    watchdog_ms: u64,
+    /// Interval between two time synchronization frames, in milliseconds. Disabled if zero
+    #[arg(long, default_value_t = config::default_sync_interval_ms())]
+    sync_interval_ms: u64,
+    /// Replace the sensors of every bus with synthetic ones, to run without the hardware.
+    /// Also applies with --config, unless the file has a [simulate] section
+    #[arg(long, default_value_t = false)]
+    simulate: bool,
+    /// Number of synthetic sensors on every bus
+    #[arg(long, requires = "simulate", default_value_t = config::default_sim_sensors())]
+    sim_sensors: usize,
+    /// Largest step of the random walk of a synthetic reading per acquisition
+    #[arg(long, requires = "simulate", default_value_t = config::default_sim_drift())]
+    sim_drift: f32,
+    /// Largest noise added to a synthetic reading
+    #[arg(long, requires = "simulate", default_value_t = config::default_sim_noise())]
+    sim_noise: f32,
+    /// Probability, between 0 and 1, that a synthetic sensor is missing from an acquisition
+    #[arg(long, requires = "simulate", default_value_t = 0.0)]
+    sim_dropout: f32,
+    /// Initialize every bus, read its sensors once, open the serial ports, print a JSON report
+    /// and exit, with a non-zero status if a check failed
+    #[arg(long, default_value_t = false)]
+    self_test: bool,
+}
+
+/// An acquisition thread watched by the supervisor in `main`.
+struct Worker<'a> {
+    name: String,
+    bus: String,
+    /// Build a fresh backend for the thread, e.g. after a restart.
+    build: Box<dyn Fn() -> Box<dyn SensorBackend> + 'a>,
+    heartbeat: Arc<Heartbeat>,
+    hdl: thread::JoinHandle<()>,
 }
 
 fn main() {
-    // Initialize the logger
-    env_logger::init();
     // Parse command line arguments
     let args = Args::parse();
+    let config = match args.config {
+        Some(ref path) => Config::load(path),
+        None => Ok(Config::from_args(&args)),
+    };
+    // Initialize the logger
+    if let Err(e) = logging::init(config.as_ref().ok().and_then(|config| config.log.as_ref())) {
+        log::error!("[MAIN] {e}");
+    }
     log::info!("Arguments: {args:#?}");
-    // let serial = args.serial;
-    if let Some(ref serial) = args.serial
-        && !PathBuf::from(serial).exists()
-    {
-        log::error!("[COM] Fatal error: {} does not exist.", serial);
-        return;
+    let mut config = match config {
+        Ok(config) => config,
+        Err(e) => {
+            log::error!("[MAIN] Fatal error: {e}");
+            return;
+        }
+    };
+    if args.simulate && config.simulate.is_none() {
+        config.simulate = Some(config::SimulationConfig::from_args(&args));
     }
-    // Exclusion filter
-    let mut exclude = Vec::new();
-    if !args.exclude.is_empty() {
-        for item in args.exclude.split(',') {
-            let item = item.trim().split("0x").last().unwrap_or(item);
-            if let Ok(num) = u32::from_str_radix(item, 16) {
-                exclude.push(num);
-            } else {
-                log::warn!("[MAIN] Invalid exclusion filter item: {item}");
+    log::info!("[MAIN] Configuration: {config:#?}");
+    let sensors = match config.sensor_map {
+        Some(ref path) => match SensorMap::load(path) {
+            Ok(sensors) => sensors,
+            Err(e) => {
+                log::error!("[MAIN] Fatal error: {e}");
+                return;
             }
+        },
+        None => SensorMap::default(),
+    };
+    let sensors = Arc::new(sensors.with_names(&config.names));
+    log::info!("[MAIN] Sensor map: {sensors:#?}");
+    // Register the sensor backends
+    let mut builders: Vec<Box<dyn Fn() -> Box<dyn SensorBackend>>> = Vec::new();
+    let (leds, print) = (config.leds, config.serial.is_none());
+    for bus in &config.buses {
+        // the self-test reports the missing buses as failed
+        if config.simulate.is_none() && !args.self_test && !bus.path.exists() {
+            log::warn!("[MAIN] {} does not exist, skipping.", bus.path.display());
+            continue;
+        }
+        let sensors = sensors.clone();
+        let build: Box<dyn Fn() -> Box<dyn SensorBackend>> = match (bus.sensor, &config.simulate) {
+            (_, Some(sim)) => {
+                Box::new(move || Box::new(SimBackend::new(bus, sim, print, sensors.clone())))
+            }
+            (SensorType::Ds28ea00, None) => {
+                Box::new(move || Box::new(OneWireBackend::new(bus, leds, print, sensors.clone())))
+            }
+            (SensorType::Hdc1010, None) => Box::new(move || {
+                Box::new(HumidityBackend::<Hdc1010<Both>>::new(bus, sensors.clone()))
+            }),
+            (SensorType::Hdc3022, None) => {
+                Box::new(move || Box::new(HumidityBackend::<Hdc3022>::new(bus, sensors.clone())))
+            }
+        };
+        let build: Box<dyn Fn() -> Box<dyn SensorBackend>> = match bus.expected.as_slice() {
+            [] => build,
+            expected => Box::new(move || Box::new(ManifestBackend::new(build(), expected))),
+        };
+        let build: Box<dyn Fn() -> Box<dyn SensorBackend>> = match &config.gradient {
+            Some(gradient) => {
+                Box::new(move || Box::new(GradientBackend::new(build(), gradient.clone())))
+            }
+            None => build,
+        };
+        match config.filters.get(&bus.sensor) {
+            Some(filter) => builders.push(Box::new(move || {
+                Box::new(FilteredBackend::new(build(), filter.clone()))
+            })),
+            None => builders.push(build),
         }
-        log::info!("[MAIN] Exclusion filter: {exclude:#?}");
-    } else {
-        log::info!("[MAIN] No exclusion filter set.");
+    }
+    if config.cpu {
+        let cpu = match CpuBackend::new(&config.cpu_include, &config.cpu_exclude, config.cpu_source)
+        {
+            Ok(cpu) => cpu,
+            Err(e) => {
+                log::error!("[CPU] Fatal error: {e}");
+                return;
+            }
+        };
+        builders.push(Box::new(move || Box::new(cpu.clone())));
+    }
+    if args.self_test {
+        let report = Report::run(builders.iter().map(|build| build()), config.serial.as_ref());
+        match serde_json::to_string_pretty(&report) {
+            Ok(json) => println!("{json}"),
+            Err(e) => log::error!("[MAIN] Failed to serialize the self-test report: {e}"),
+        }
+        std::process::exit(if report.passed { 0 } else { 1 });
+    }
+    if let Some(ref serial) = config.serial
+        && !PathBuf::from(&serial.port).exists()
+    {
+        if serial.backup.is_none() {
+            log::error!("[COM] Fatal error: {} does not exist.", serial.port);
+            return;
+        }
+        log::warn!(
+            "[COM] {} does not exist, using the backup port",
+            serial.port
+        );
     }
     // Synchronizer
     let running = Arc::new(AtomicBool::new(true));
@@ -92,85 +321,388 @@ fn main() {
     }
     // Channel
     let (data_tx, data_rx) = safe_mpsc::channel();
-    // Spawn the serial communication thread
-    let ser_hdl = if let Some(ref serial) = args.serial {
-        let running = running.clone();
-        let serial = serial.clone();
+    // Commands received over the serial link and the HTTP API
+    let router = Arc::new(Router::default());
+    // Latest values of the buses, served without waiting for the sink thread
+    let latest = Arc::new(LatestValues::default());
+    // Time reference of the stream
+    let clock = Arc::new(SyncClock::new());
+    // Register the sinks
+    let mut sinks: Vec<Box<dyn MeasurementSink>> = Vec::new();
+    if let Some(ref serial) = config.serial {
+        let serial_sink = |port: &String| {
+            Box::new(SerialSink::new(
+                config::SerialConfig {
+                    port: port.clone(),
+                    ..serial.clone()
+                },
+                router.clone(),
+                clock.clone(),
+                data_tx.clone(),
+            ))
+        };
+        let sink: Box<dyn MeasurementSink> = match serial.backup {
+            Some(ref backup) => Box::new(FailoverSink::new(
+                (serial_sink(&serial.port), serial.port.clone()),
+                (serial_sink(backup), backup.clone()),
+                serial.failover_after,
+                Duration::from_millis(serial.failback_ms),
+                data_tx.clone(),
+            )),
+            None => serial_sink(&serial.port),
+        };
+        match serial.buffer {
+            Some(ref path) => sinks.push(Box::new(BufferedSink::new(
+                sink,
+                path.clone(),
+                serial.buffer_bytes,
+            ))),
+            None => sinks.push(sink),
+        }
+    }
+    for sink in &config.sinks {
+        match sink {
+            SinkConfig::Json { path } => sinks.push(Box::new(JsonSink::new(path.clone()))),
+            SinkConfig::Csv {
+                path,
+                max_bytes,
+                keep,
+            } => sinks.push(Box::new(CsvSink::new(path.clone(), *max_bytes, *keep))),
+            SinkConfig::Tcp { bind, queue } => sinks.push(Box::new(TcpSink::new(*bind, *queue))),
+            SinkConfig::Udp { addr, ttl } => sinks.push(Box::new(UdpSink::new(*addr, *ttl))),
+            SinkConfig::Unix {
+                path,
+                format,
+                queue,
+            } => sinks.push(Box::new(UnixSink::new(path.clone(), *format, *queue))),
+            #[cfg(feature = "mqtt")]
+            SinkConfig::Mqtt {
+                host,
+                port,
+                prefix,
+                qos,
+                retain,
+                discovery,
+            } => sinks.push(Box::new(mqtt_sink::MqttSink::new(
+                host.clone(),
+                *port,
+                prefix.clone(),
+                *qos,
+                *retain,
+                *discovery,
+            ))),
+            #[cfg(feature = "metrics")]
+            SinkConfig::Metrics { bind } => {
+                sinks.push(Box::new(metrics::MetricsSink::new(*bind, latest.clone())))
+            }
+            #[cfg(feature = "http")]
+            SinkConfig::Http { bind } => sinks.push(Box::new(http_api::HttpSink::new(
+                *bind,
+                router.clone(),
+                latest.clone(),
+            ))),
+        }
+    }
+    // Spawn the sink thread, stopped once the acquisition threads are joined
+    let streaming = Arc::new(AtomicBool::new(true));
+    let sink_hdl = if !sinks.is_empty() {
+        let streaming = streaming.clone();
         Some(thread::spawn(move || {
-            serial_comm::serial_thread(serial, running, data_rx)
+            sink::sink_thread(sinks, streaming, data_rx)
         }))
     } else {
         None
     };
-    // Spawn the temperature sensor threads
-    let mut temp_hdls = args
-        .thermo_paths
-        .iter()
-        .filter_map(|path| {
-            let path = PathBuf::from(format!("/dev/i2c-{path}"));
-            if path.exists() {
-                let running = running.clone();
-                let sink = data_tx.clone();
-                let exclude = exclude.clone();
-                let print = args.serial.is_none();
-                Some(thread::spawn({
-                    move || {
-                        onewire_thread(path, running, args.leds, sink, exclude, args.no_overdrive, print)
-                    }
-                }))
-            } else {
-                None
-            }
-        })
-        .collect::<Vec<_>>();
-    temp_hdls.push(thread::spawn({
+    // Spawn a scheduler thread for every backend
+    let spawn = |backend: Box<dyn SensorBackend>| {
         let running = running.clone();
         let sink = data_tx.clone();
-        move || cpu_sensors::cputemp_thread(running, sink)
-    }));
-    // Spawn humidity sensor threads if needed
-    let hum_hdls = args
-        .humidity_paths
-        .iter()
-        .filter_map(|path| {
-            let path = PathBuf::from(format!("/dev/i2c-{path}"));
-            if path.exists() {
-                let running = running.clone();
-                let sink = data_tx.clone();
-                Some(thread::spawn({
-                    move || humidity_thread(path, running, sink)
-                }))
-            } else {
-                None
+        let latest = latest.clone();
+        let sensors = sensors.clone();
+        let commands = router.register(backend.bus());
+        let heartbeat = Arc::new(Heartbeat::new(backend.poll_interval(), config.watchdog()));
+        let hdl = {
+            let heartbeat = heartbeat.clone();
+            thread::spawn(move || {
+                schedule(backend, running, sink, latest, sensors, commands, heartbeat)
+            })
+        };
+        (heartbeat, hdl)
+    };
+    let mut workers = builders
+        .into_iter()
+        .map(|build| {
+            let backend = build();
+            let name = backend.name();
+            let bus = backend.bus();
+            let (heartbeat, hdl) = spawn(backend);
+            Worker {
+                name,
+                bus,
+                build,
+                heartbeat,
+                hdl,
             }
         })
         .collect::<Vec<_>>();
-    // Main thread: wait for threads to finish
+    #[cfg(feature = "systemd")]
+    let mut notifier = systemd::Notifier::from_env();
+    // Main thread: send sync frames, and restart threads that are stuck or have panicked
+    let mut last_sync: Option<Instant> = None;
     while running.load(Ordering::Relaxed) {
+        if let Some(interval) = config.sync_interval()
+            && last_sync.is_none_or(|last| last.elapsed() >= interval)
+        {
+            if let Err(e) = data_tx.send(clock.marker()) {
+                log::error!("[MAIN] Failed to send sync frame: {e:?}");
+            }
+            last_sync = Some(Instant::now());
+        }
         thread::sleep(Duration::from_secs(1));
+        for worker in workers.iter_mut() {
+            let panicked = worker.hdl.is_finished();
+            if !panicked && !worker.heartbeat.expired() {
+                continue;
+            }
+            if !running.load(Ordering::Relaxed) {
+                break;
+            }
+            if panicked {
+                log::error!("{}> Thread exited unexpectedly, restarting", worker.name);
+            } else {
+                log::error!("{}> Thread stalled, restarting", worker.name);
+            }
+            // a stalled thread can not be interrupted, it exits once it is unblocked
+            worker.heartbeat.stop();
+            let (heartbeat, hdl) = spawn((worker.build)());
+            worker.heartbeat = heartbeat;
+            let old = std::mem::replace(&mut worker.hdl, hdl);
+            if panicked && let Err(e) = old.join() {
+                log::error!("{}> Thread panicked with error: {e:#?}", worker.name);
+            }
+            #[cfg(feature = "metrics")]
+            metrics::restart(&worker.name);
+            let timestamp = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |t| t.as_millis() as u64);
+            if let Err(e) = data_tx.send(Measurement::new(
+                worker.bus.clone(),
+                Readings::Restart(worker.bus.clone()),
+                timestamp,
+            )) {
+                log::error!("{}> Failed to report restart: {e:?}", worker.name);
+            }
+        }
+        #[cfg(feature = "systemd")]
+        if let Some(notifier) = notifier.as_mut() {
+            let healthy = !workers.iter().any(|worker| worker.heartbeat.expired());
+            notifier.update(workers.len(), healthy);
+        }
     }
-    // Join temp sensor threads
-    for temp_hdl in temp_hdls {
-        if let Err(e) = temp_hdl.join() {
-            log::error!("[TMP] Thread panicked with error: {e:#?}");
+    #[cfg(feature = "systemd")]
+    if let Some(notifier) = notifier.as_ref() {
+        notifier.stopping();
+    }
+    // Join sensor threads, which finish their readout in progress
+    for worker in workers {
+        if worker.heartbeat.expired() && !worker.hdl.is_finished() {
+            log::warn!("{}> Thread stalled, not joining.", worker.name);
+        } else if let Err(e) = worker.hdl.join() {
+            log::error!("{}> Thread panicked with error: {e:#?}", worker.name);
         } else {
-            log::info!("[TMP] Thread joined successfully.");
+            log::info!("{}> Thread joined successfully.", worker.name);
         }
     }
-    // Join humidity sensor threads
-    for humi_hdl in hum_hdls {
-        if let Err(e) = humi_hdl.join() {
-            log::error!("[HUM] Thread panicked with error: {e:#?}");
+    // Join the sink thread, once it has written the queued measurements and the end of the stream
+    streaming.store(false, Ordering::Relaxed);
+    if let Some(sink_hdl) = sink_hdl {
+        if let Err(e) = sink_hdl.join() {
+            log::error!("[SNK] Thread panicked: {e:#?}");
         } else {
-            log::info!("[HUM] Thread joined successfully.");
+            log::info!("[SNK] Thread joined successfully.");
         }
     }
-    // Join the serial communication thread
-    if let Some(ser_hdl) = ser_hdl {
-        if let Err(e) = ser_hdl.join() {
-            log::error!("[COM] Thread panicked: {e:#?}");
-        } else {
-            log::info!("[COM] Thread joined successfully.");
+}
+
+/// Drive a sensor backend until the server is stopped.
+///
+/// The backend is (re-)initialized every second until it succeeds, and then polled at its
+/// [`SensorBackend::poll_interval`]. A failed acquisition triggers a re-initialization.
+/// Every measurement is followed by the labels of its sensors, if any are labelled.
+/// Commands are handled while waiting for the next acquisition. The sensors are announced again
+/// when they are enumerated on command. The measurements and announcements are recorded in
+/// `latest` before they are sent, so that the endpoints serving it never wait for the sink thread.
+///
+/// The heartbeat is renewed whenever the backend returns, and whenever the poll interval changes.
+/// The thread exits once the supervisor stops the heartbeat. When the server is stopped, the
+/// readout in progress is still sent, so that the sink can write it out before the stream ends.
+fn schedule(
+    mut backend: Box<dyn SensorBackend>,
+    running: Arc<AtomicBool>,
+    sink: safe_mpsc::SafeSender<Measurement>,
+    latest: Arc<LatestValues>,
+    sensors: Arc<SensorMap>,
+    commands: mpsc::Receiver<Request>,
+    heartbeat: Arc<Heartbeat>,
+) {
+    let name = backend.name();
+    let bus = backend.bus();
+    let mut interval = backend.poll_interval();
+    let alive = || running.load(Ordering::Relaxed) && !heartbeat.stopped();
+    'init: while alive() {
+        let init = backend.init();
+        heartbeat.beat(interval);
+        #[cfg(feature = "systemd")]
+        systemd::initialized(&name, init.is_ok().then(|| backend.inventory().len()));
+        if let Err(e) = init {
+            log::error!("{name}> {e}");
+            #[cfg(feature = "metrics")]
+            metrics::reconnect(&name);
+            handle_commands(
+                &mut backend,
+                &commands,
+                &heartbeat,
+                &mut interval,
+                Duration::from_secs(1),
+            );
+            continue 'init;
+        }
+        // announce the sensors found on the bus
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |t| t.as_millis() as u64);
+        let metadata = Measurement::new(bus.clone(), backend::metadata(&*backend), timestamp);
+        latest.update(&metadata);
+        if let Err(e) = sink.send(metadata) {
+            log::error!("{name}> Failed to send metadata: {e:?}");
+        }
+        while alive() {
+            let start = Instant::now();
+            let timestamp = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |t| t.as_millis() as u64);
+            // handle the commands while the acquisition completes, instead of waiting for it
+            let mut enumerated = false;
+            let data = match backend.begin_acquire() {
+                Ok(Some(ready)) => {
+                    let window = ready.saturating_duration_since(Instant::now());
+                    enumerated =
+                        handle_commands(&mut backend, &commands, &heartbeat, &mut interval, window);
+                    backend.acquire()
+                }
+                Ok(None) => backend.acquire(),
+                Err(e) => Err(e),
+            };
+            heartbeat.beat(interval);
+            if heartbeat.stopped() {
+                break; // abandoned by the supervisor while stalled
+            }
+            match data {
+                Ok(data) => {
+                    for readings in data {
+                        let labels = sensors.labels(readings.ids());
+                        let measurement = Measurement::new(bus.clone(), readings, timestamp);
+                        latest.update(&measurement);
+                        if let Err(e) = sink.send(measurement) {
+                            log::error!("{name}> Failed to send data: {e:?}");
+                            break; // probably the receiver has been dropped, meaning we are leaving
+                        }
+                        if !labels.is_empty()
+                            && let Err(e) = sink.send(Measurement::new(
+                                bus.clone(),
+                                Readings::Labels(labels),
+                                timestamp,
+                            ))
+                        {
+                            log::error!("{name}> Failed to send data: {e:?}");
+                            break;
+                        }
+                    }
+                }
+                Err(e) => {
+                    log::error!("{name}> {e}");
+                    #[cfg(feature = "metrics")]
+                    metrics::reconnect(&name);
+                    handle_commands(
+                        &mut backend,
+                        &commands,
+                        &heartbeat,
+                        &mut interval,
+                        Duration::from_secs(1),
+                    );
+                    continue 'init;
+                }
+            }
+            // wait so that there is a poll interval between measurements
+            let remaining = interval.saturating_sub(start.elapsed());
+            if handle_commands(
+                &mut backend,
+                &commands,
+                &heartbeat,
+                &mut interval,
+                remaining,
+            ) || enumerated
+            {
+                let timestamp = SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .map_or(0, |t| t.as_millis() as u64);
+                let metadata =
+                    Measurement::new(bus.clone(), backend::metadata(&*backend), timestamp);
+                latest.update(&metadata);
+                if let Err(e) = sink.send(metadata) {
+                    log::error!("{name}> Failed to send metadata: {e:?}");
+                }
+            }
+        }
+    }
+    log::info!("{name}> Exiting thread");
+}
+
+/// Handle the commands sent to a backend for `timeout`.
+///
+/// A new poll interval takes effect from the next wait on. A failed enumeration is retried by the
+/// next acquisition, which fails and initializes the backend again.
+///
+/// # Returns
+/// `true` if the backend was initialized again to enumerate its sensors.
+fn handle_commands(
+    backend: &mut Box<dyn SensorBackend>,
+    commands: &mpsc::Receiver<Request>,
+    heartbeat: &Heartbeat,
+    interval: &mut Duration,
+    timeout: Duration,
+) -> bool {
+    let mut enumerated = false;
+    let deadline = Instant::now() + timeout;
+    loop {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        let Request { command, reply } = match commands.recv_timeout(remaining) {
+            Ok(request) => request,
+            Err(mpsc::RecvTimeoutError::Timeout) => break,
+            Err(mpsc::RecvTimeoutError::Disconnected) => {
+                thread::sleep(remaining);
+                break;
+            }
+        };
+        log::info!("{}> Command: {command:?}", backend.name());
+        let result = match command {
+            Command::SetPollInterval(new) => {
+                *interval = new;
+                heartbeat.beat(new);
+                Ok(format!("rate {} ms", new.as_millis()))
+            }
+            Command::Enumerate => {
+                enumerated = true;
+                let init = backend.init();
+                heartbeat.beat(*interval);
+                init.map(|_| format!("enumerate {} sensors", backend.inventory().len()))
+            }
+            command => backend.command(&command),
+        };
+        if let Err(ref e) = result {
+            log::warn!("{}> Command failed: {e}", backend.name());
         }
+        let _ = reply.send(result);
     }
+    enumerated
 }
@@ -0,0 +1,186 @@
+use std::{
+    collections::BTreeSet,
+    time::{Duration, Instant},
+};
+
+use crate::{Readings, SensorEntry, backend::SensorBackend, control::Command};
+
+/// Interval between two checks of the sensors seen at runtime.
+const CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Comparison of the sensors seen on a bus with the sensors expected by the configuration.
+///
+/// The sensors seen are collected until the next [`Manifest::check`]. A sensor only has to be read
+/// once between two checks to count as present, so that a single failed readout is not reported.
+pub struct Manifest {
+    expected: BTreeSet<u32>,
+    seen: BTreeSet<u32>,
+    missing: BTreeSet<u32>,
+    unexpected: BTreeSet<u32>,
+}
+
+impl Manifest {
+    pub fn new(expected: impl IntoIterator<Item = u32>) -> Self {
+        Self {
+            expected: expected.into_iter().collect(),
+            seen: BTreeSet::new(),
+            missing: BTreeSet::new(),
+            unexpected: BTreeSet::new(),
+        }
+    }
+
+    /// Record the sensors seen on the bus.
+    pub fn observe(&mut self, ids: impl IntoIterator<Item = u32>) {
+        self.seen.extend(ids);
+    }
+
+    /// Compare the sensors seen since the last check with the expected ones, and start over.
+    ///
+    /// The changes since the last check are logged with the `name` of the backend.
+    ///
+    /// # Returns
+    /// The missing and unexpected sensors, if any, or an empty [`Readings::Missing`] once all
+    /// sensors are back.
+    pub fn check(&mut self, name: &str) -> Vec<Readings> {
+        let seen = std::mem::take(&mut self.seen);
+        let missing: BTreeSet<_> = self.expected.difference(&seen).copied().collect();
+        let unexpected: BTreeSet<_> = seen.difference(&self.expected).copied().collect();
+        for id in missing.difference(&self.missing) {
+            log::warn!(
+                sensor:% = format_args!("{id:08x}");
+                "{name}> Expected sensor {id:08x} is missing"
+            );
+        }
+        for id in self.missing.difference(&missing) {
+            log::info!(
+                sensor:% = format_args!("{id:08x}");
+                "{name}> Expected sensor {id:08x} is back"
+            );
+        }
+        for id in unexpected.difference(&self.unexpected) {
+            log::warn!(
+                sensor:% = format_args!("{id:08x}");
+                "{name}> Found unexpected sensor {id:08x}"
+            );
+        }
+        let recovered = missing.is_empty() && !self.missing.is_empty();
+        self.missing = missing;
+        self.unexpected = unexpected;
+        let mut data = Vec::new();
+        if !self.missing.is_empty() || recovered {
+            data.push(Readings::Missing(self.missing.iter().copied().collect()));
+        }
+        if !self.unexpected.is_empty() {
+            data.push(Readings::Unexpected(
+                self.unexpected.iter().copied().collect(),
+            ));
+        }
+        data
+    }
+}
+
+/// A backend whose sensors are compared with a [`Manifest`] of the expected sensors.
+///
+/// The inventory is checked after every initialization, and the sensors read out every
+/// [`CHECK_INTERVAL`] at runtime, e.g. to detect a broken harness before it goes unnoticed.
+pub struct ManifestBackend {
+    inner: Box<dyn SensorBackend>,
+    manifest: Manifest,
+    checked: Option<Instant>,
+}
+
+impl ManifestBackend {
+    pub fn new(inner: Box<dyn SensorBackend>, expected: &[u32]) -> Self {
+        Self {
+            inner,
+            manifest: Manifest::new(expected.iter().copied()),
+            checked: None,
+        }
+    }
+}
+
+impl SensorBackend for ManifestBackend {
+    fn name(&self) -> String {
+        self.inner.name()
+    }
+
+    fn bus(&self) -> String {
+        self.inner.bus()
+    }
+
+    fn path(&self) -> String {
+        self.inner.path()
+    }
+
+    fn init(&mut self) -> Result<(), String> {
+        self.inner.init()?;
+        // checked with the first readout
+        self.manifest
+            .observe(self.inner.inventory().iter().map(|s| s.id));
+        self.checked = None;
+        Ok(())
+    }
+
+    fn inventory(&self) -> Vec<SensorEntry> {
+        self.inner.inventory()
+    }
+
+    fn poll_interval(&self) -> Duration {
+        self.inner.poll_interval()
+    }
+
+    fn begin_acquire(&mut self) -> Result<Option<Instant>, String> {
+        self.inner.begin_acquire()
+    }
+
+    fn acquire(&mut self) -> Result<Vec<Readings>, String> {
+        let mut data = self.inner.acquire()?;
+        let now = Instant::now();
+        if self.checked.is_none() {
+            // the sensors enumerated by the initialization
+            data.extend(self.manifest.check(&self.inner.name()));
+            self.checked = Some(now);
+        }
+        for readings in &data {
+            if let Readings::Temperature(_) | Readings::Humidity(_) = readings {
+                self.manifest.observe(readings.ids());
+            }
+        }
+        if self
+            .checked
+            .is_some_and(|checked| now.duration_since(checked) >= CHECK_INTERVAL)
+        {
+            data.extend(self.manifest.check(&self.inner.name()));
+            self.checked = Some(now);
+        }
+        Ok(data)
+    }
+
+    fn command(&mut self, command: &Command) -> Result<String, String> {
+        self.inner.command(command)
+    }
+}
+
+mod test {
+    #[test]
+    fn test_manifest() {
+        use super::Manifest;
+        use crate::Readings;
+        let mut manifest = Manifest::new([1, 2, 3]);
+        manifest.observe([1, 2, 3]);
+        assert_eq!(manifest.check("test"), vec![]);
+        // seen once between two checks
+        manifest.observe([1, 3]);
+        manifest.observe([2, 3, 4]);
+        assert_eq!(manifest.check("test"), vec![Readings::Unexpected(vec![4])]);
+        manifest.observe([1]);
+        assert_eq!(manifest.check("test"), vec![Readings::Missing(vec![2, 3])]);
+        // reported until all are back
+        manifest.observe([1, 2]);
+        assert_eq!(manifest.check("test"), vec![Readings::Missing(vec![3])]);
+        manifest.observe([1, 2, 3]);
+        assert_eq!(manifest.check("test"), vec![Readings::Missing(vec![])]);
+        manifest.observe([1, 2, 3]);
+        assert_eq!(manifest.check("test"), vec![]);
+    }
+}
@@ -0,0 +1,230 @@
+use std::{
+    collections::BTreeMap,
+    fmt::Write as _,
+    io::{self, BufRead, BufReader, Write},
+    net::{SocketAddr, TcpListener, TcpStream},
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicBool, Ordering},
+    },
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+
+use crate::{
+    Measurement,
+    latest::{LatestValues, Snapshot},
+    sink::MeasurementSink,
+};
+
+/// Interval at which the server checks for new connections and shutdown.
+const ACCEPT_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Counters exported to Prometheus.
+#[derive(Default)]
+struct Registry {
+    /// Read errors, keyed by bus and sensor ID.
+    read_errors: BTreeMap<(String, u32), u64>,
+    /// Reconnects, keyed by backend.
+    reconnects: BTreeMap<String, u64>,
+    /// Thread restarts by the watchdog, keyed by backend.
+    restarts: BTreeMap<String, u64>,
+}
+
+static REGISTRY: Mutex<Registry> = Mutex::new(Registry {
+    read_errors: BTreeMap::new(),
+    reconnects: BTreeMap::new(),
+    restarts: BTreeMap::new(),
+});
+
+/// Count a failed read of a sensor on a bus.
+pub fn read_error(bus: &str, id: u32) {
+    if let Ok(mut registry) = REGISTRY.lock() {
+        *registry.read_errors.entry((bus.into(), id)).or_default() += 1;
+    }
+}
+
+/// Count a re-initialization of a backend.
+pub fn reconnect(backend: &str) {
+    if let Ok(mut registry) = REGISTRY.lock() {
+        *registry.reconnects.entry(backend.into()).or_default() += 1;
+    }
+}
+
+/// Count a restart of the acquisition thread of a backend.
+pub fn restart(backend: &str) {
+    if let Ok(mut registry) = REGISTRY.lock() {
+        *registry.restarts.entry(backend.into()).or_default() += 1;
+    }
+}
+
+/// Render the latest values and the registry in the Prometheus text exposition format.
+fn render(latest: &Snapshot) -> String {
+    let Ok(registry) = REGISTRY.lock() else {
+        return String::new();
+    };
+    let mut out = String::new();
+    for (kind, name, help) in [
+        (
+            "temperature",
+            "thermo_temperature_celsius",
+            "Most recent temperature reading.",
+        ),
+        (
+            "humidity",
+            "thermo_humidity_percent",
+            "Most recent relative humidity reading.",
+        ),
+        (
+            "dew_point",
+            "thermo_dew_point_celsius",
+            "Most recent dew point.",
+        ),
+    ] {
+        let _ = writeln!(out, "# HELP {name} {help}\n# TYPE {name} gauge");
+        // one series per sensor, the IDs being unique across the buses
+        let values = latest
+            .readings
+            .iter()
+            .filter(|((_, k, _), _)| *k == kind)
+            .map(|((_, _, id), (value, _))| (*id, *value))
+            .collect::<BTreeMap<_, _>>();
+        for (id, value) in values {
+            let _ = writeln!(out, "{name}{{sensor=\"{id:08x}\"}} {value}");
+        }
+    }
+    let _ = writeln!(
+        out,
+        "# HELP thermo_read_errors_total Failed sensor reads.\n# TYPE thermo_read_errors_total counter"
+    );
+    for ((bus, id), count) in &registry.read_errors {
+        let _ = writeln!(
+            out,
+            "thermo_read_errors_total{{bus=\"{}\",sensor=\"{id:08x}\"}} {count}",
+            escape(bus)
+        );
+    }
+    let _ = writeln!(
+        out,
+        "# HELP thermo_reconnects_total Bus re-initializations.\n# TYPE thermo_reconnects_total counter"
+    );
+    for (backend, count) in &registry.reconnects {
+        let _ = writeln!(
+            out,
+            "thermo_reconnects_total{{backend=\"{}\"}} {count}",
+            escape(backend)
+        );
+    }
+    let _ = writeln!(
+        out,
+        "# HELP thermo_restarts_total Acquisition threads restarted by the watchdog.\n# TYPE thermo_restarts_total counter"
+    );
+    for (backend, count) in &registry.restarts {
+        let _ = writeln!(
+            out,
+            "thermo_restarts_total{{backend=\"{}\"}} {count}",
+            escape(backend)
+        );
+    }
+    out
+}
+
+fn escape(label: &str) -> String {
+    label
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+/// HTTP endpoint serving the most recent measurements and the error counters at `/metrics`.
+///
+/// The measurements are served from the [`LatestValues`] updated by the acquisition threads, so
+/// the measurements written to the sink are not used.
+pub struct MetricsSink {
+    addr: SocketAddr,
+    latest: Arc<LatestValues>,
+    server: Option<(Arc<AtomicBool>, JoinHandle<()>)>,
+}
+
+impl MetricsSink {
+    pub fn new(addr: SocketAddr, latest: Arc<LatestValues>) -> Self {
+        Self {
+            addr,
+            latest,
+            server: None,
+        }
+    }
+}
+
+impl MeasurementSink for MetricsSink {
+    fn name(&self) -> String {
+        format!("[MET] {}", self.addr)
+    }
+
+    fn open(&mut self) -> Result<(), String> {
+        let listener = TcpListener::bind(self.addr).map_err(|e| format!("Failed to bind: {e}"))?;
+        listener
+            .set_nonblocking(true)
+            .map_err(|e| format!("Failed to set listener non-blocking: {e}"))?;
+        let sig = Arc::new(AtomicBool::new(true));
+        let hdl = {
+            let sig = sig.clone();
+            let addr = self.addr;
+            let latest = self.latest.clone();
+            thread::spawn(move || server_thread(addr, listener, sig, latest))
+        };
+        self.server = Some((sig, hdl));
+        Ok(())
+    }
+
+    fn write(&mut self, _measurement: &Measurement) -> Result<(), String> {
+        Ok(())
+    }
+
+    fn close(&mut self) {
+        if let Some((sig, hdl)) = self.server.take() {
+            sig.store(false, Ordering::Relaxed);
+            let _ = hdl.join();
+        }
+    }
+}
+
+fn server_thread(
+    addr: SocketAddr,
+    listener: TcpListener,
+    running: Arc<AtomicBool>,
+    latest: Arc<LatestValues>,
+) {
+    while running.load(Ordering::Relaxed) {
+        match listener.accept() {
+            Ok((stream, peer)) => {
+                if let Err(e) = serve(stream, &latest.snapshot()) {
+                    log::warn!("[MET] {addr}> Failed to serve {peer}: {e}");
+                }
+            }
+            Err(e) if e.kind() == io::ErrorKind::WouldBlock => thread::sleep(ACCEPT_INTERVAL),
+            Err(e) => {
+                log::error!("[MET] {addr}> Failed to accept connection: {e}");
+                thread::sleep(ACCEPT_INTERVAL);
+            }
+        }
+    }
+}
+
+fn serve(mut stream: TcpStream, latest: &Snapshot) -> io::Result<()> {
+    stream.set_nonblocking(false)?;
+    stream.set_read_timeout(Some(Duration::from_secs(1)))?;
+    let mut request = String::new();
+    BufReader::new(&stream).read_line(&mut request)?;
+    let path = request.split_whitespace().nth(1).unwrap_or_default();
+    let (status, body) = if path == "/metrics" {
+        ("200 OK", render(latest))
+    } else {
+        ("404 Not Found", String::new())
+    };
+    write!(
+        stream,
+        "HTTP/1.1 {status}\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        body.len()
+    )
+}
@@ -0,0 +1,170 @@
+use std::{
+    collections::HashSet,
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+
+use rumqttc::{Client, Event, MqttOptions, Packet, QoS, RecvTimeoutError};
+
+use crate::{Measurement, sink::MeasurementSink};
+
+/// Topic prefix of Home Assistant MQTT discovery.
+const DISCOVERY_PREFIX: &str = "homeassistant";
+/// Capacity of the request queue between the sink and the MQTT event loop.
+const QUEUE_LEN: usize = 64;
+
+/// Measurements published to an MQTT broker, one message per record, to
+/// `<prefix>/<host>/<bus>/<sensor_id>/<type>`.
+///
+/// If enabled, Home Assistant discovery payloads are published (retained) the first time a sensor
+/// is seen. Messages are dropped while the request queue to the broker is full, so that an
+/// unreachable broker cannot block acquisition.
+pub struct MqttSink {
+    host: String,
+    port: u16,
+    prefix: String,
+    qos: QoS,
+    retain: bool,
+    discovery: bool,
+    hostname: String,
+    discovered: HashSet<(String, u32, &'static str)>,
+    client: Option<(Client, Arc<AtomicBool>, JoinHandle<()>)>,
+}
+
+impl MqttSink {
+    pub fn new(
+        host: String,
+        port: u16,
+        prefix: String,
+        qos: u8,
+        retain: bool,
+        discovery: bool,
+    ) -> Self {
+        let qos = match qos {
+            0 => QoS::AtMostOnce,
+            1 => QoS::AtLeastOnce,
+            _ => QoS::ExactlyOnce,
+        };
+        Self {
+            host,
+            port,
+            prefix,
+            qos,
+            retain,
+            discovery,
+            hostname: sysinfo::System::host_name().unwrap_or_else(|| "piccthermo".into()),
+            discovered: HashSet::new(),
+            client: None,
+        }
+    }
+
+    fn publish(&self, client: &Client, topic: String, retain: bool, payload: String) {
+        if let Err(e) = client.try_publish(topic, self.qos, retain, payload) {
+            log::warn!("[MQT] {}> Dropping message: {e}", self.host);
+        }
+    }
+
+    /// Publish the Home Assistant discovery payload of a sensor.
+    fn announce(&self, client: &Client, bus: &str, id: u32, kind: &'static str, state: &str) {
+        let (device_class, unit) = match kind {
+            "humidity" => ("humidity", "%"),
+            _ => ("temperature", "°C"),
+        };
+        let unique_id = format!("piccthermo_{}_{bus}_{id:08x}_{kind}", self.hostname);
+        let config = serde_json::json!({
+            "name": format!("{id:08x} {}", kind.replace('_', " ")),
+            "unique_id": unique_id,
+            "state_topic": state,
+            "device_class": device_class,
+            "unit_of_measurement": unit,
+            "state_class": "measurement",
+            "device": {
+                "identifiers": [format!("piccthermo_{}", self.hostname)],
+                "name": format!("piccthermo {}", self.hostname),
+            },
+        });
+        self.publish(
+            client,
+            format!("{DISCOVERY_PREFIX}/sensor/{unique_id}/config"),
+            true,
+            config.to_string(),
+        );
+    }
+}
+
+impl MeasurementSink for MqttSink {
+    fn name(&self) -> String {
+        format!("[MQT] {}:{}", self.host, self.port)
+    }
+
+    fn open(&mut self) -> Result<(), String> {
+        let mut options = MqttOptions::new(
+            format!("piccthermo-{}", self.hostname),
+            &self.host,
+            self.port,
+        );
+        options.set_keep_alive(Duration::from_secs(10));
+        let (client, mut connection) = Client::new(options, QUEUE_LEN);
+        let sig = Arc::new(AtomicBool::new(true));
+        let hdl = {
+            let sig = sig.clone();
+            let host = self.host.clone();
+            // Drive the event loop, which reconnects to the broker as long as it is polled
+            thread::spawn(move || {
+                while sig.load(Ordering::Relaxed) {
+                    match connection.recv_timeout(Duration::from_secs(1)) {
+                        Ok(Ok(Event::Incoming(Packet::ConnAck(_)))) => {
+                            log::info!("[MQT] {host}> Connected to broker");
+                        }
+                        Ok(Ok(_)) => {}
+                        Ok(Err(e)) => {
+                            log::warn!("[MQT] {host}> Connection error: {e}");
+                            thread::sleep(Duration::from_secs(1));
+                        }
+                        Err(RecvTimeoutError::Timeout) => {}
+                        Err(RecvTimeoutError::Disconnected) => break,
+                    }
+                }
+            })
+        };
+        // Announce the sensors again, since the broker may have been restarted
+        self.discovered.clear();
+        self.client = Some((client, sig, hdl));
+        Ok(())
+    }
+
+    fn write(&mut self, measurement: &Measurement) -> Result<(), String> {
+        let (client, _, _) = self.client.as_ref().ok_or("Client not open")?;
+        let client = client.clone();
+        for record in measurement.records() {
+            let Some(value) = record.value else {
+                continue; // only measured values are published
+            };
+            let topic = format!(
+                "{}/{}/{}/{:08x}/{}",
+                self.prefix, self.hostname, measurement.source, record.id, record.kind
+            );
+            if self.discovery
+                && self
+                    .discovered
+                    .insert((measurement.source.clone(), record.id, record.kind))
+            {
+                self.announce(&client, &measurement.source, record.id, record.kind, &topic);
+            }
+            self.publish(&client, topic, self.retain, value.to_string());
+        }
+        Ok(())
+    }
+
+    fn close(&mut self) {
+        if let Some((client, sig, hdl)) = self.client.take() {
+            let _ = client.disconnect();
+            sig.store(false, Ordering::Relaxed);
+            let _ = hdl.join();
+        }
+    }
+}
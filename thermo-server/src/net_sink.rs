@@ -0,0 +1,545 @@
+use std::{
+    collections::{BTreeMap, VecDeque},
+    fs,
+    io::{self, Write},
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, Shutdown, SocketAddr, TcpListener, TcpStream, UdpSocket},
+    os::unix::{
+        fs::FileTypeExt,
+        net::{UnixListener, UnixStream},
+    },
+    path::PathBuf,
+    sync::{
+        Arc, Condvar, Mutex,
+        atomic::{AtomicBool, Ordering},
+    },
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+
+use thermo_server::cobs;
+
+use crate::{
+    Measurement, Readings,
+    config::StreamFormat,
+    file_sinks::json_lines,
+    sink::{MeasurementSink, encode_frame},
+};
+
+/// Interval at which the listener checks for new clients and shutdown.
+const ACCEPT_INTERVAL: Duration = Duration::from_millis(100);
+/// Longest time a client may take to accept a frame, after which it is disconnected, so that a
+/// client that stops reading cannot block the sink from closing.
+const WRITE_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// A connected client, with a bounded queue of frames to send.
+struct Client {
+    peer: String,
+    queue: Mutex<VecDeque<Arc<[u8]>>>,
+    ready: Condvar,
+    closed: AtomicBool,
+    shutdown: Box<dyn Fn() + Send + Sync>,
+}
+
+impl Client {
+    /// Stop serving the client, and shut down its connection, so that a write in progress fails.
+    fn close(&self) {
+        self.closed.store(true, Ordering::Relaxed);
+        self.ready.notify_all();
+        (self.shutdown)();
+    }
+}
+
+/// The connection of a client, written by its thread.
+trait Connection: Write + Send + 'static {
+    /// A handle that shuts the connection down while it is written.
+    fn shutdown_handle(&self) -> io::Result<Box<dyn Fn() + Send + Sync>>;
+}
+
+impl Connection for TcpStream {
+    fn shutdown_handle(&self) -> io::Result<Box<dyn Fn() + Send + Sync>> {
+        let stream = self.try_clone()?;
+        Ok(Box::new(move || {
+            let _ = stream.shutdown(Shutdown::Both);
+        }))
+    }
+}
+
+impl Connection for UnixStream {
+    fn shutdown_handle(&self) -> io::Result<Box<dyn Fn() + Send + Sync>> {
+        let stream = self.try_clone()?;
+        Ok(Box::new(move || {
+            let _ = stream.shutdown(Shutdown::Both);
+        }))
+    }
+}
+
+/// Connected clients, with the threads serving them.
+type Clients = Arc<Mutex<Vec<(Arc<Client>, JoinHandle<()>)>>>;
+
+/// Latest frames of every bus, sent to every new client before the live frames, keyed by bus and
+/// [`replay_slot`].
+type Replay = Arc<Mutex<BTreeMap<(String, u8), Arc<[u8]>>>>;
+
+/// Position of a measurement in the latest value set replayed to new clients, in the order they
+/// are sent: the inventory first, then the labels, the readings and the health report.
+fn replay_slot(readings: &Readings) -> Option<u8> {
+    match readings {
+        Readings::Metadata(_) => Some(0),
+        Readings::Labels(_) => Some(1),
+        Readings::Temperature(_) => Some(2),
+        Readings::Humidity(_) => Some(3),
+        Readings::DewPoint(_) => Some(4),
+        Readings::Health(_) => Some(5),
+        _ => None,
+    }
+}
+
+/// COBS-encoded binary frames served to every client connected to a TCP port.
+///
+/// Every client is served by its own thread from a queue of at most `queue_len` frames. If a client
+/// does not keep up, the oldest frames are dropped, so that a stalled client cannot block acquisition.
+/// A client that does not accept a frame for [`WRITE_TIMEOUT`] is disconnected, and the connections
+/// are shut down when the sink is closed. Clients may connect and disconnect at any time.
+pub struct TcpSink {
+    addr: SocketAddr,
+    queue_len: usize,
+    clients: Clients,
+    listener: Option<(Arc<AtomicBool>, JoinHandle<()>)>,
+}
+
+impl TcpSink {
+    pub fn new(addr: SocketAddr, queue_len: usize) -> Self {
+        Self {
+            addr,
+            queue_len: queue_len.max(1),
+            clients: Arc::new(Mutex::new(Vec::new())),
+            listener: None,
+        }
+    }
+}
+
+impl MeasurementSink for TcpSink {
+    fn name(&self) -> String {
+        format!("[TCP] {}", self.addr)
+    }
+
+    fn open(&mut self) -> Result<(), String> {
+        let listener = TcpListener::bind(self.addr).map_err(|e| format!("Failed to bind: {e}"))?;
+        listener
+            .set_nonblocking(true)
+            .map_err(|e| format!("Failed to set listener non-blocking: {e}"))?;
+        log::info!("[TCP] {}> Listening for clients", self.addr);
+        let sig = Arc::new(AtomicBool::new(true));
+        let hdl = {
+            let sig = sig.clone();
+            let clients = self.clients.clone();
+            let name = self.name();
+            let accept = move || {
+                let (stream, peer) = listener.accept()?;
+                stream.set_nonblocking(false)?;
+                stream.set_write_timeout(Some(WRITE_TIMEOUT))?;
+                Ok((stream, peer.to_string()))
+            };
+            thread::spawn(move || accept_thread(name, accept, sig, clients, None))
+        };
+        self.listener = Some((sig, hdl));
+        Ok(())
+    }
+
+    fn write(&mut self, measurement: &Measurement) -> Result<(), String> {
+        let Some(bytes) = encode_frame(&self.name(), measurement) else {
+            return Ok(());
+        };
+        let frame: Arc<[u8]> = cobs::encode(&bytes).into();
+        broadcast(&self.name(), &self.clients, frame, self.queue_len)
+    }
+
+    fn close(&mut self) {
+        stop(&mut self.listener, &self.clients);
+        log::info!("[TCP] {}> Closed", self.addr);
+    }
+}
+
+/// Queue `frame` for every connected client, and forget the clients that disconnected.
+fn broadcast(
+    name: &str,
+    clients: &Clients,
+    frame: Arc<[u8]>,
+    queue_len: usize,
+) -> Result<(), String> {
+    let mut clients = clients.lock().map_err(|_| "Client list poisoned")?;
+    // Forget the clients that disconnected
+    let (closed, open): (Vec<_>, Vec<_>) = clients
+        .drain(..)
+        .partition(|(client, _)| client.closed.load(Ordering::Relaxed));
+    *clients = open;
+    for (client, hdl) in closed {
+        log::info!("{name}> Client {} disconnected", client.peer);
+        let _ = hdl.join();
+    }
+    for (client, _) in clients.iter() {
+        let Ok(mut queue) = client.queue.lock() else {
+            continue;
+        };
+        if queue.len() >= queue_len {
+            queue.pop_front(); // drop the oldest frame
+            log::warn!(
+                "{name}> Client {} is not keeping up, dropping data",
+                client.peer
+            );
+        }
+        queue.push_back(frame.clone());
+        client.ready.notify_one();
+    }
+    Ok(())
+}
+
+/// Stop the listener thread, and disconnect every client.
+fn stop(listener: &mut Option<(Arc<AtomicBool>, JoinHandle<()>)>, clients: &Clients) {
+    if let Some((sig, hdl)) = listener.take() {
+        sig.store(false, Ordering::Relaxed);
+        let _ = hdl.join();
+    }
+    if let Ok(mut clients) = clients.lock() {
+        for (client, hdl) in clients.drain(..) {
+            client.close();
+            let _ = hdl.join();
+        }
+    }
+}
+
+/// Accept the clients of a non-blocking listener until `running` is cleared, each served by its
+/// own thread, after the frames of `replay` if any.
+fn accept_thread<S: Connection>(
+    name: String,
+    mut accept: impl FnMut() -> io::Result<(S, String)>,
+    running: Arc<AtomicBool>,
+    clients: Clients,
+    replay: Option<Replay>,
+) {
+    while running.load(Ordering::Relaxed) {
+        match accept() {
+            Ok((stream, peer)) => {
+                log::info!("{name}> Client {peer} connected");
+                let shutdown = match stream.shutdown_handle() {
+                    Ok(shutdown) => shutdown,
+                    Err(e) => {
+                        log::error!("{name}> Failed to configure client {peer}: {e}");
+                        continue;
+                    }
+                };
+                let Ok(mut clients) = clients.lock() else {
+                    break;
+                };
+                // registered while the client list is locked, so that no frame is missed
+                let queue = match replay.as_ref().map(|replay| replay.lock()) {
+                    Some(Ok(replay)) => replay.values().cloned().collect(),
+                    _ => VecDeque::new(),
+                };
+                let client = Arc::new(Client {
+                    peer,
+                    queue: Mutex::new(queue),
+                    ready: Condvar::new(),
+                    closed: AtomicBool::new(false),
+                    shutdown,
+                });
+                let hdl = {
+                    let client = client.clone();
+                    let name = name.clone();
+                    thread::spawn(move || client_thread(name, stream, client))
+                };
+                clients.push((client, hdl));
+            }
+            Err(e) if e.kind() == io::ErrorKind::WouldBlock => thread::sleep(ACCEPT_INTERVAL),
+            Err(e) => {
+                log::error!("{name}> Failed to accept client: {e}");
+                thread::sleep(ACCEPT_INTERVAL);
+            }
+        }
+    }
+}
+
+fn client_thread(name: String, mut stream: impl Write, client: Arc<Client>) {
+    loop {
+        let frame = {
+            let Ok(mut queue) = client.queue.lock() else {
+                break;
+            };
+            loop {
+                if client.closed.load(Ordering::Relaxed) {
+                    return;
+                }
+                if let Some(frame) = queue.pop_front() {
+                    break frame;
+                }
+                queue = match client.ready.wait(queue) {
+                    Ok(queue) => queue,
+                    Err(_) => return,
+                };
+            }
+        };
+        if let Err(e) = stream.write_all(&frame) {
+            log::warn!("{name}> Client {}: {e}", client.peer);
+            break;
+        }
+    }
+    client.close();
+}
+
+/// Binary frames sent as UDP datagrams, e.g. to a multicast group.
+///
+/// The TTL applies to IPv4 multicast groups, IPv6 multicast datagrams use the default hop limit.
+pub struct UdpSink {
+    addr: SocketAddr,
+    ttl: u32,
+    socket: Option<UdpSocket>,
+}
+
+impl UdpSink {
+    pub fn new(addr: SocketAddr, ttl: u32) -> Self {
+        Self {
+            addr,
+            ttl,
+            socket: None,
+        }
+    }
+}
+
+impl MeasurementSink for UdpSink {
+    fn name(&self) -> String {
+        format!("[UDP] {}", self.addr)
+    }
+
+    fn open(&mut self) -> Result<(), String> {
+        // bound in the address family of the destination
+        let local: IpAddr = match self.addr {
+            SocketAddr::V4(_) => Ipv4Addr::UNSPECIFIED.into(),
+            SocketAddr::V6(_) => Ipv6Addr::UNSPECIFIED.into(),
+        };
+        let socket = UdpSocket::bind((local, 0)).map_err(|e| format!("Failed to bind: {e}"))?;
+        if self.addr.is_ipv4() && self.addr.ip().is_multicast() {
+            socket
+                .set_multicast_ttl_v4(self.ttl)
+                .map_err(|e| format!("Failed to set multicast TTL: {e}"))?;
+        }
+        socket
+            .set_nonblocking(true)
+            .map_err(|e| format!("Failed to set socket non-blocking: {e}"))?;
+        self.socket = Some(socket);
+        Ok(())
+    }
+
+    fn write(&mut self, measurement: &Measurement) -> Result<(), String> {
+        let socket = self.socket.as_ref().ok_or("Socket not open")?;
+        let Some(bytes) = encode_frame(&self.name(), measurement) else {
+            return Ok(());
+        };
+        match socket.send_to(&bytes, self.addr) {
+            Ok(_) => Ok(()),
+            Err(e) if e.kind() == io::ErrorKind::WouldBlock => {
+                log::warn!("[UDP] {}> Socket busy, dropping data", self.addr);
+                Ok(())
+            }
+            Err(e) => Err(format!("Failed to send: {e}")),
+        }
+    }
+
+    fn close(&mut self) {
+        self.socket = None;
+    }
+}
+
+/// Measurements served to the local processes connected to a UNIX socket, e.g. a fan control
+/// daemon, as newline-delimited JSON or COBS-encoded binary frames.
+///
+/// Clients are served as by [`TcpSink`]. Every new client is first sent the latest value set:
+/// the latest inventory, labels, readings and health report of every bus, see [`replay_slot`].
+/// The inventory of a bus replaces its previous value set. The socket file is replaced when the
+/// sink is opened, and removed when it is closed.
+pub struct UnixSink {
+    path: PathBuf,
+    format: StreamFormat,
+    queue_len: usize,
+    clients: Clients,
+    replay: Replay,
+    listener: Option<(Arc<AtomicBool>, JoinHandle<()>)>,
+}
+
+impl UnixSink {
+    pub fn new(path: PathBuf, format: StreamFormat, queue_len: usize) -> Self {
+        Self {
+            path,
+            format,
+            queue_len: queue_len.max(1),
+            clients: Arc::new(Mutex::new(Vec::new())),
+            replay: Arc::default(),
+            listener: None,
+        }
+    }
+
+    fn encode(&self, measurement: &Measurement) -> Option<Arc<[u8]>> {
+        match self.format {
+            StreamFormat::Json => Some(json_lines(measurement).into_bytes().into()),
+            StreamFormat::Binary => {
+                encode_frame(&self.name(), measurement).map(|bytes| cobs::encode(&bytes).into())
+            }
+        }
+    }
+}
+
+impl MeasurementSink for UnixSink {
+    fn name(&self) -> String {
+        format!("[UNX] {}", self.path.display())
+    }
+
+    fn open(&mut self) -> Result<(), String> {
+        match fs::symlink_metadata(&self.path) {
+            Ok(meta) if meta.file_type().is_socket() => fs::remove_file(&self.path)
+                .map_err(|e| format!("Failed to remove stale socket: {e}"))?,
+            Ok(_) => return Err("Path exists and is not a socket".into()),
+            Err(_) => {}
+        }
+        let listener =
+            UnixListener::bind(&self.path).map_err(|e| format!("Failed to bind: {e}"))?;
+        listener
+            .set_nonblocking(true)
+            .map_err(|e| format!("Failed to set listener non-blocking: {e}"))?;
+        log::info!("{}> Listening for clients", self.name());
+        let sig = Arc::new(AtomicBool::new(true));
+        let hdl = {
+            let sig = sig.clone();
+            let clients = self.clients.clone();
+            let replay = self.replay.clone();
+            let name = self.name();
+            let mut count = 0;
+            let accept = move || {
+                let (stream, _) = listener.accept()?;
+                stream.set_nonblocking(false)?;
+                stream.set_write_timeout(Some(WRITE_TIMEOUT))?;
+                // local clients have no address of their own
+                count += 1;
+                Ok((stream, format!("#{count}")))
+            };
+            thread::spawn(move || accept_thread(name, accept, sig, clients, Some(replay)))
+        };
+        self.listener = Some((sig, hdl));
+        Ok(())
+    }
+
+    fn write(&mut self, measurement: &Measurement) -> Result<(), String> {
+        let Some(frame) = self.encode(measurement) else {
+            return Ok(());
+        };
+        if let Some(slot) = replay_slot(&measurement.readings) {
+            let mut replay = self.replay.lock().map_err(|_| "Replay poisoned")?;
+            if slot == 0 {
+                // the sensors that are gone are no longer current
+                replay.retain(|(bus, _), _| *bus != measurement.source);
+            }
+            replay.insert((measurement.source.clone(), slot), frame.clone());
+        }
+        broadcast(&self.name(), &self.clients, frame, self.queue_len)
+    }
+
+    fn close(&mut self) {
+        stop(&mut self.listener, &self.clients);
+        let _ = fs::remove_file(&self.path);
+        log::info!("{}> Closed", self.name());
+    }
+}
+
+mod test {
+    #[test]
+    fn test_unix_sink() {
+        use super::UnixSink;
+        use crate::{
+            Measurement, Metadata, Readings, SensorEntry, config::StreamFormat,
+            sink::MeasurementSink,
+        };
+        use std::{
+            io::{BufRead, BufReader},
+            os::unix::net::UnixStream,
+            time::{Duration, Instant},
+        };
+        let path = std::env::temp_dir().join(format!("thermo-unix-{}.sock", std::process::id()));
+        let mut sink = UnixSink::new(path.clone(), StreamFormat::Json, 16);
+        sink.open().unwrap();
+        let measurement = |readings| Measurement::new("i2c-1".into(), readings, 1);
+        let metadata = Readings::Metadata(Metadata {
+            version: "0.0.1".into(),
+            path: "/dev/i2c-1".into(),
+            sensors: vec![SensorEntry {
+                id: 0x1234,
+                address: 0x42,
+                model: "ds28ea00".into(),
+            }],
+        });
+        sink.write(&measurement(Readings::Temperature(vec![(0x1234, 20.5)])))
+            .unwrap();
+        sink.write(&measurement(metadata)).unwrap();
+        sink.write(&measurement(Readings::Temperature(vec![(0x1234, 21.5)])))
+            .unwrap();
+        sink.write(&measurement(Readings::Humidity(vec![(0x1234, 45.0)])))
+            .unwrap();
+        // two subscribers, both replayed the latest value set on connect
+        let connect = || {
+            let stream = UnixStream::connect(&path).unwrap();
+            stream
+                .set_read_timeout(Some(Duration::from_secs(5)))
+                .unwrap();
+            BufReader::new(stream).lines()
+        };
+        let mut clients = [connect(), connect()];
+        let start = Instant::now();
+        while sink.clients.lock().unwrap().len() < 2 {
+            assert!(start.elapsed() < Duration::from_secs(5));
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        sink.write(&measurement(Readings::Temperature(vec![(0x1234, 22.5)])))
+            .unwrap();
+        for lines in clients.iter_mut() {
+            let mut next = || {
+                serde_json::from_str::<serde_json::Value>(&lines.next().unwrap().unwrap()).unwrap()
+            };
+            assert_eq!(next()["path"], "/dev/i2c-1");
+            let replayed = next();
+            assert_eq!(replayed["type"], "temperature");
+            assert_eq!(replayed["value"], 21.5);
+            assert_eq!(next()["type"], "humidity");
+            assert_eq!(next()["value"], 22.5);
+        }
+        sink.close();
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn test_unix_stalled_client() {
+        use super::{UnixSink, WRITE_TIMEOUT};
+        use crate::{Measurement, Readings, config::StreamFormat, sink::MeasurementSink};
+        use std::{
+            os::unix::net::UnixStream,
+            time::{Duration, Instant},
+        };
+        let path = std::env::temp_dir().join(format!("thermo-stalled-{}.sock", std::process::id()));
+        let mut sink = UnixSink::new(path.clone(), StreamFormat::Json, 64);
+        sink.open().unwrap();
+        // a client that never reads
+        let _stream = UnixStream::connect(&path).unwrap();
+        let start = Instant::now();
+        while sink.clients.lock().unwrap().is_empty() {
+            assert!(start.elapsed() < Duration::from_secs(5));
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        // enough to fill the socket buffer
+        let text = "x".repeat(60_000);
+        for _ in 0..64 {
+            let readings = Readings::Response(text.clone());
+            sink.write(&Measurement::new("i2c-1".into(), readings, 1))
+                .unwrap();
+        }
+        std::thread::sleep(Duration::from_millis(100));
+        let start = Instant::now();
+        sink.close();
+        assert!(start.elapsed() < WRITE_TIMEOUT);
+    }
+}
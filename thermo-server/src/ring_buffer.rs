@@ -0,0 +1,363 @@
+use std::{
+    fs::{File, OpenOptions},
+    io::{self, Read, Seek, SeekFrom, Write},
+    path::PathBuf,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    Measurement,
+    sink::{MeasurementSink, encode_frame},
+};
+
+/// Magic number at the start of the buffer file.
+const MAGIC: [u8; 4] = *b"CHRB";
+/// Size of the file header: magic, capacity, head and tail.
+const HEADER_LEN: u64 = 28;
+/// Size of the length prefix of every entry.
+const LEN_PREFIX: u64 = 4;
+/// Most buffered measurements replayed per live measurement, so that a long backlog does not
+/// hold up the other sinks.
+const REPLAY_BATCH: usize = 64;
+
+/// Fixed-size circular buffer of frames in a file.
+///
+/// The file starts with a header holding the capacity and the head and tail offsets, followed by
+/// `capacity` bytes of entries, each a length (u32) followed by the frame. The offsets only ever
+/// grow and wrap around the data region, so that the buffer survives a restart of the server.
+/// When the buffer is full, the oldest entries are dropped.
+pub struct RingBuffer {
+    file: File,
+    capacity: u64,
+    head: u64,
+    tail: u64,
+}
+
+impl RingBuffer {
+    /// Open the buffer at `path`, keeping its contents if it was created with the same capacity.
+    pub fn open(path: &PathBuf, capacity: u64) -> io::Result<Self> {
+        if capacity == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "buffer capacity must be positive",
+            ));
+        }
+        let mut file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(path)?;
+        let mut header = [0u8; HEADER_LEN as usize];
+        let existing = file.read_exact(&mut header).is_ok()
+            && header[..4] == MAGIC
+            && u64::from_le_bytes(header[4..12].try_into().unwrap()) == capacity;
+        let (head, tail) = if existing {
+            (
+                u64::from_le_bytes(header[12..20].try_into().unwrap()),
+                u64::from_le_bytes(header[20..28].try_into().unwrap()),
+            )
+        } else {
+            (0, 0)
+        };
+        let mut buffer = Self {
+            file,
+            capacity,
+            head,
+            tail: tail.max(head),
+        };
+        if !existing || buffer.tail - buffer.head > capacity {
+            buffer.head = 0;
+            buffer.tail = 0;
+            buffer.file.set_len(HEADER_LEN + capacity)?;
+        }
+        buffer.write_header()?;
+        Ok(buffer)
+    }
+
+    /// Number of bytes used by the buffered entries.
+    pub fn used(&self) -> u64 {
+        self.tail - self.head
+    }
+
+    /// Returns `true` if there are no buffered entries.
+    pub fn is_empty(&self) -> bool {
+        self.head == self.tail
+    }
+
+    /// Append an entry, dropping the oldest entries to make room for it.
+    ///
+    /// # Returns
+    /// The number of entries dropped.
+    pub fn push(&mut self, frame: &[u8]) -> io::Result<usize> {
+        let len = LEN_PREFIX + frame.len() as u64;
+        if len > self.capacity {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "entry larger than the buffer",
+            ));
+        }
+        let mut dropped = 0;
+        while self.used() + len > self.capacity {
+            self.skip()?;
+            dropped += 1;
+        }
+        let tail = self.tail;
+        self.write_at(tail, &(frame.len() as u32).to_le_bytes())?;
+        self.write_at(tail + LEN_PREFIX, frame)?;
+        self.tail += len;
+        self.write_header()?;
+        Ok(dropped)
+    }
+
+    /// Read the oldest entry without removing it.
+    pub fn peek(&mut self) -> io::Result<Option<Vec<u8>>> {
+        if self.is_empty() {
+            return Ok(None);
+        }
+        let len = self.entry_len()?;
+        let mut frame = vec![0u8; len as usize];
+        self.read_at(self.head + LEN_PREFIX, &mut frame)?;
+        Ok(Some(frame))
+    }
+
+    /// Remove the oldest entry.
+    pub fn pop(&mut self) -> io::Result<()> {
+        if !self.is_empty() {
+            self.skip()?;
+            self.write_header()?;
+        }
+        Ok(())
+    }
+
+    /// Advance the head past the oldest entry, without updating the header.
+    fn skip(&mut self) -> io::Result<()> {
+        let len = self.entry_len()?;
+        self.head += LEN_PREFIX + len;
+        Ok(())
+    }
+
+    /// Length of the oldest entry.
+    fn entry_len(&mut self) -> io::Result<u64> {
+        let mut len = [0u8; LEN_PREFIX as usize];
+        self.read_at(self.head, &mut len)?;
+        let len = u32::from_le_bytes(len) as u64;
+        if LEN_PREFIX + len > self.used() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "corrupted entry length",
+            ));
+        }
+        Ok(len)
+    }
+
+    fn write_header(&mut self) -> io::Result<()> {
+        let mut header = [0u8; HEADER_LEN as usize];
+        header[..4].copy_from_slice(&MAGIC);
+        header[4..12].copy_from_slice(&self.capacity.to_le_bytes());
+        header[12..20].copy_from_slice(&self.head.to_le_bytes());
+        header[20..28].copy_from_slice(&self.tail.to_le_bytes());
+        self.file.seek(SeekFrom::Start(0))?;
+        self.file.write_all(&header)
+    }
+
+    /// Write `bytes` at `offset` of the data region, wrapping around its end.
+    fn write_at(&mut self, offset: u64, bytes: &[u8]) -> io::Result<()> {
+        let start = offset % self.capacity;
+        let first = bytes.len().min((self.capacity - start) as usize);
+        self.file.seek(SeekFrom::Start(HEADER_LEN + start))?;
+        self.file.write_all(&bytes[..first])?;
+        if first < bytes.len() {
+            self.file.seek(SeekFrom::Start(HEADER_LEN))?;
+            self.file.write_all(&bytes[first..])?;
+        }
+        Ok(())
+    }
+
+    /// Read `bytes` from `offset` of the data region, wrapping around its end.
+    fn read_at(&mut self, offset: u64, bytes: &mut [u8]) -> io::Result<()> {
+        let start = offset % self.capacity;
+        let first = bytes.len().min((self.capacity - start) as usize);
+        self.file.seek(SeekFrom::Start(HEADER_LEN + start))?;
+        self.file.read_exact(&mut bytes[..first])?;
+        if first < bytes.len() {
+            self.file.seek(SeekFrom::Start(HEADER_LEN))?;
+            self.file.read_exact(&mut bytes[first..])?;
+        }
+        Ok(())
+    }
+}
+
+/// A sink that stores measurements in a [`RingBuffer`] while the sink it wraps is not open,
+/// and replays them in order once it is opened again.
+///
+/// The wrapped sink is opened again at most once per second. Since the buffer holds binary
+/// frames, buffered measurements are replayed without their source.
+pub struct BufferedSink {
+    inner: Box<dyn MeasurementSink>,
+    path: PathBuf,
+    capacity: u64,
+    buffer: Option<RingBuffer>,
+    open: bool,
+    last_open: Option<Instant>,
+}
+
+impl BufferedSink {
+    /// Buffer up to `capacity` bytes of measurements for `inner` in the file at `path`.
+    pub fn new(inner: Box<dyn MeasurementSink>, path: PathBuf, capacity: u64) -> Self {
+        Self {
+            inner,
+            path,
+            capacity,
+            buffer: None,
+            open: false,
+            last_open: None,
+        }
+    }
+
+    /// Open the wrapped sink, if it is closed and was not tried within the last second.
+    fn try_open(&mut self) {
+        if self.open
+            || self
+                .last_open
+                .is_some_and(|last| last.elapsed() < Duration::from_secs(1))
+        {
+            return;
+        }
+        self.last_open = Some(Instant::now());
+        match self.inner.open() {
+            Ok(()) => {
+                log::info!("{}> Sink is ready to receive data", self.inner.name());
+                self.open = true;
+            }
+            Err(e) => log::error!("{}> {e}", self.inner.name()),
+        }
+    }
+
+    /// Replay up to [`REPLAY_BATCH`] buffered measurements to the wrapped sink.
+    fn replay(&mut self) -> Result<(), String> {
+        let name = self.name();
+        let buffer = self.buffer.as_mut().ok_or("Buffer not open")?;
+        for _ in 0..REPLAY_BATCH {
+            let Some(frame) = buffer
+                .peek()
+                .map_err(|e| format!("Failed to read buffer: {e}"))?
+            else {
+                log::info!("{name}> Buffer replayed");
+                break;
+            };
+            match Measurement::from_bytes(&frame) {
+                Ok((measurement, _)) => {
+                    if let Err(e) = self.inner.write(&measurement) {
+                        log::error!("{}> {e}", self.inner.name());
+                        self.inner.close();
+                        self.open = false;
+                        return Ok(());
+                    }
+                }
+                Err(e) => log::warn!("{name}> Dropping corrupted measurement: {e:?}"),
+            }
+            buffer
+                .pop()
+                .map_err(|e| format!("Failed to update buffer: {e}"))?;
+        }
+        Ok(())
+    }
+}
+
+impl MeasurementSink for BufferedSink {
+    fn name(&self) -> String {
+        format!("[BUF] {}", self.path.display())
+    }
+
+    fn open(&mut self) -> Result<(), String> {
+        let buffer = RingBuffer::open(&self.path, self.capacity)
+            .map_err(|e| format!("Failed to open buffer: {e}"))?;
+        if !buffer.is_empty() {
+            log::info!(
+                "{}> {} bytes of measurements to replay",
+                self.name(),
+                buffer.used()
+            );
+        }
+        self.buffer = Some(buffer);
+        self.try_open();
+        Ok(())
+    }
+
+    fn write(&mut self, measurement: &Measurement) -> Result<(), String> {
+        self.try_open();
+        let name = self.name();
+        let buffer = self.buffer.as_mut().ok_or("Buffer not open")?;
+        if !self.open || !buffer.is_empty() {
+            let Some(bytes) = encode_frame(&name, measurement) else {
+                return Ok(());
+            };
+            // keep the measurements in order while there is a backlog
+            let dropped = buffer
+                .push(&bytes)
+                .map_err(|e| format!("Failed to write buffer: {e}"))?;
+            if dropped > 0 {
+                log::warn!("{name}> Buffer full, dropped {dropped} oldest measurements");
+            }
+            if self.open {
+                self.replay()?;
+            }
+            return Ok(());
+        }
+        if let Err(e) = self.inner.write(measurement) {
+            log::error!("{}> {e}", self.inner.name());
+            self.inner.close();
+            self.open = false;
+            let Some(bytes) = encode_frame(&name, measurement) else {
+                return Ok(());
+            };
+            buffer
+                .push(&bytes)
+                .map_err(|e| format!("Failed to write buffer: {e}"))?;
+        }
+        Ok(())
+    }
+
+    fn close(&mut self) {
+        if self.open {
+            self.inner.close();
+            self.open = false;
+        }
+        self.buffer = None;
+    }
+}
+
+mod test {
+    #[test]
+    fn test_ring_buffer() {
+        use super::RingBuffer;
+        let path = std::env::temp_dir().join(format!("piccthermo-ring-{}", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        {
+            let mut buffer = RingBuffer::open(&path, 32).unwrap();
+            assert!(buffer.peek().unwrap().is_none());
+            assert_eq!(buffer.push(&[1; 10]).unwrap(), 0);
+            assert_eq!(buffer.push(&[2; 10]).unwrap(), 0);
+            // the first entry is dropped, and the third one wraps around the end of the file
+            assert_eq!(buffer.push(&[3; 10]).unwrap(), 1);
+            assert_eq!(buffer.peek().unwrap(), Some(vec![2; 10]));
+            assert!(buffer.push(&[4; 32]).is_err());
+        }
+        {
+            // the contents survive reopening the buffer
+            let mut buffer = RingBuffer::open(&path, 32).unwrap();
+            assert_eq!(buffer.peek().unwrap(), Some(vec![2; 10]));
+            buffer.pop().unwrap();
+            assert_eq!(buffer.peek().unwrap(), Some(vec![3; 10]));
+            buffer.pop().unwrap();
+            assert!(buffer.is_empty());
+        }
+        {
+            // a different capacity starts over
+            let mut buffer = RingBuffer::open(&path, 64).unwrap();
+            assert!(buffer.peek().unwrap().is_none());
+        }
+        std::fs::remove_file(&path).unwrap();
+    }
+}
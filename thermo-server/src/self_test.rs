@@ -0,0 +1,194 @@
+use serde::Serialize;
+
+use crate::{Readings, SensorEntry, backend::SensorBackend, config::SerialConfig, serial_comm};
+
+/// Outcome of `--self-test`, printed as JSON.
+///
+/// The self-test passes if every bus and every serial port passes.
+#[derive(Debug, Serialize)]
+pub struct Report {
+    pub passed: bool,
+    pub version: &'static str,
+    pub buses: Vec<BusReport>,
+    pub serial: Vec<PortReport>,
+}
+
+/// Outcome of the check of a bus.
+///
+/// A bus passes if its backend is initialized, finds at least one sensor, and acquires once.
+#[derive(Debug, Serialize)]
+pub struct BusReport {
+    pub bus: String,
+    pub path: String,
+    pub passed: bool,
+    /// Sensors found by the initialization.
+    pub sensors: Vec<SensorEntry>,
+    /// Readings of the acquisition.
+    pub readings: Vec<Readings>,
+    pub error: Option<String>,
+}
+
+/// Outcome of the check of a serial port, opened with the configured settings.
+#[derive(Debug, Serialize)]
+pub struct PortReport {
+    pub port: String,
+    pub passed: bool,
+    /// Settings applied to the port.
+    pub settings: Option<String>,
+    pub error: Option<String>,
+}
+
+impl Report {
+    /// Check every backend and the serial port, and its backup if any.
+    pub fn run(
+        backends: impl IntoIterator<Item = Box<dyn SensorBackend>>,
+        serial: Option<&SerialConfig>,
+    ) -> Self {
+        let buses = backends
+            .into_iter()
+            .map(|mut backend| BusReport::check(&mut *backend))
+            .collect::<Vec<_>>();
+        let serial = serial
+            .into_iter()
+            .flat_map(|config| {
+                let backup = config.backup.clone().map(|port| SerialConfig {
+                    port,
+                    ..config.clone()
+                });
+                std::iter::once(config.clone()).chain(backup)
+            })
+            .map(|config| PortReport::check(&config))
+            .collect::<Vec<_>>();
+        Self {
+            passed: buses.iter().all(|bus| bus.passed) && serial.iter().all(|port| port.passed),
+            version: env!("CARGO_PKG_VERSION"),
+            buses,
+            serial,
+        }
+    }
+}
+
+impl BusReport {
+    fn check(backend: &mut dyn SensorBackend) -> Self {
+        let name = backend.name();
+        let mut report = Self {
+            bus: backend.bus(),
+            path: backend.path(),
+            passed: false,
+            sensors: Vec::new(),
+            readings: Vec::new(),
+            error: None,
+        };
+        if let Err(e) = backend.init() {
+            log::error!("{name}> Self-test failed: {e}");
+            report.error = Some(e);
+            return report;
+        }
+        report.sensors = backend.inventory();
+        match backend.acquire() {
+            Ok(readings) => report.readings = readings,
+            Err(e) => {
+                log::error!("{name}> Self-test failed: {e}");
+                report.error = Some(e);
+                return report;
+            }
+        }
+        if report.sensors.is_empty() {
+            log::error!("{name}> Self-test failed: no sensors found");
+            report.error = Some("no sensors found".into());
+        } else {
+            report.passed = true;
+        }
+        report
+    }
+}
+
+impl PortReport {
+    fn check(config: &SerialConfig) -> Self {
+        let res = serial_comm::probe(config);
+        if let Err(ref e) = res {
+            log::error!("[COM] Self-test of {} failed: {e}", config.port);
+        }
+        Self {
+            port: config.port.clone(),
+            passed: res.is_ok(),
+            settings: res.as_ref().ok().cloned(),
+            error: res.err(),
+        }
+    }
+}
+
+mod test {
+    #[test]
+    fn test_self_test() {
+        use super::Report;
+        use crate::{Readings, SensorEntry, backend::SensorBackend, config::SerialConfig};
+        struct Backend {
+            sensors: usize,
+            fail: bool,
+        }
+        impl SensorBackend for Backend {
+            fn name(&self) -> String {
+                "[TST]".into()
+            }
+            fn bus(&self) -> String {
+                format!("bus-{}", self.sensors)
+            }
+            fn init(&mut self) -> Result<(), String> {
+                Ok(())
+            }
+            fn inventory(&self) -> Vec<SensorEntry> {
+                (0..self.sensors)
+                    .map(|id| SensorEntry {
+                        id: id as u32,
+                        address: 0,
+                        model: "test".into(),
+                    })
+                    .collect()
+            }
+            fn acquire(&mut self) -> Result<Vec<Readings>, String> {
+                if self.fail {
+                    return Err("readout failed".into());
+                }
+                Ok(vec![Readings::Temperature(
+                    (0..self.sensors).map(|id| (id as u32, 21.5)).collect(),
+                )])
+            }
+        }
+        let backends = |fail| -> Vec<Box<dyn SensorBackend>> {
+            vec![
+                Box::new(Backend {
+                    sensors: 2,
+                    fail: false,
+                }),
+                Box::new(Backend { sensors: 1, fail }),
+            ]
+        };
+        let report = Report::run(backends(false), None);
+        assert!(report.passed);
+        assert_eq!(report.buses[0].sensors.len(), 2);
+        assert_eq!(
+            report.buses[1].readings,
+            [Readings::Temperature(vec![(0, 21.5)])]
+        );
+        let report = Report::run(backends(true), None);
+        assert!(!report.passed);
+        assert!(report.buses[0].passed);
+        assert_eq!(report.buses[1].error.as_deref(), Some("readout failed"));
+        // a bus without sensors fails, and so does a missing serial port
+        let empty: Vec<Box<dyn SensorBackend>> = vec![Box::new(Backend {
+            sensors: 0,
+            fail: false,
+        })];
+        let serial: SerialConfig =
+            toml::from_str("port = \"/dev/does-not-exist\"\nbackup = \"/dev/nor-this\"").unwrap();
+        let report = Report::run(empty, Some(&serial));
+        assert!(!report.passed);
+        assert!(!report.buses[0].passed);
+        assert_eq!(report.serial.len(), 2);
+        assert!(report.serial.iter().all(|port| !port.passed));
+        let json = serde_json::to_value(&report).unwrap();
+        assert_eq!(json["passed"], false);
+        assert_eq!(json["serial"][1]["port"], "/dev/nor-this");
+    }
+}
@@ -0,0 +1,134 @@
+use std::{collections::HashMap, path::Path};
+
+use serde::Deserialize;
+
+use crate::config::parse_id;
+
+/// Label, location and linear calibration of a single sensor.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SensorInfo {
+    /// Short human readable name.
+    #[serde(default)]
+    pub label: Option<String>,
+    /// Where the sensor is installed.
+    #[serde(default)]
+    pub location: Option<String>,
+    /// Calibration gain.
+    #[serde(default = "default_gain")]
+    pub gain: f32,
+    /// Calibration offset, in the unit of the measurement.
+    #[serde(default)]
+    pub offset: f32,
+}
+
+impl Default for SensorInfo {
+    fn default() -> Self {
+        Self {
+            label: None,
+            location: None,
+            gain: default_gain(),
+            offset: 0.0,
+        }
+    }
+}
+
+impl SensorInfo {
+    /// Apply the calibration to a raw value.
+    pub fn calibrate(&self, value: f32) -> f32 {
+        value * self.gain + self.offset
+    }
+}
+
+/// Sensor IDs mapped to their [`SensorInfo`], loaded from a TOML file:
+///
+/// ```toml
+/// [sensor."0x1a2b3c4d"]
+/// label = "chamber-top"
+/// location = "Thermal chamber, top shelf"
+/// gain = 1.002
+/// offset = -0.15
+/// ```
+///
+/// Sensors are keyed by the ID they are published with, i.e. the CRC32 hash of the serial number
+/// for 1-Wire sensors, and the I2C address combined with the hash of the bus name for humidity
+/// sensors, see [`SensorId::from_i2c`](piccthermo_id::SensorId::from_i2c). Labels for 1-Wire sensors can be
+/// assigned and exported to this format with thermo-ident.
+#[derive(Debug, Default)]
+pub struct SensorMap {
+    sensors: HashMap<u32, SensorInfo>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SensorMapFile {
+    #[serde(default)]
+    sensor: HashMap<String, SensorInfo>,
+}
+
+impl SensorMap {
+    /// Load the sensor map from a TOML file.
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read {}: {e}", path.display()))?;
+        let file: SensorMapFile = toml::from_str(&content)
+            .map_err(|e| format!("Failed to parse {}: {e}", path.display()))?;
+        let sensors = file
+            .sensor
+            .into_iter()
+            .map(|(id, info)| {
+                parse_id(&id)
+                    .map(|id| (id, info))
+                    .ok_or_else(|| format!("Invalid sensor ID in {}: {id}", path.display()))
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Self { sensors })
+    }
+
+    /// Label the sensors that do not have a label yet.
+    pub fn with_names(mut self, names: &HashMap<u32, String>) -> Self {
+        for (id, name) in names {
+            let info = self.sensors.entry(*id).or_default();
+            if info.label.is_none() {
+                info.label = Some(name.clone());
+            }
+        }
+        self
+    }
+
+    /// Get the information of a sensor.
+    pub fn get(&self, id: u32) -> Option<&SensorInfo> {
+        self.sensors.get(&id)
+    }
+
+    /// Get the label of a sensor.
+    pub fn label(&self, id: u32) -> Option<&str> {
+        self.get(id)?.label.as_deref()
+    }
+
+    /// Apply the calibration of a sensor to a raw value. Uncalibrated sensors are passed through.
+    pub fn calibrate(&self, id: u32, value: f32) -> f32 {
+        self.get(id).map_or(value, |info| info.calibrate(value))
+    }
+
+    /// Get the label and location of the given sensors, skipping sensors with neither.
+    pub fn labels(&self, ids: impl IntoIterator<Item = u32>) -> Vec<(u32, String, String)> {
+        ids.into_iter()
+            .filter_map(|id| {
+                let info = self.get(id)?;
+                if info.label.is_none() && info.location.is_none() {
+                    return None;
+                }
+                Some((
+                    id,
+                    info.label.clone().unwrap_or_default(),
+                    info.location.clone().unwrap_or_default(),
+                ))
+            })
+            .collect()
+    }
+}
+
+fn default_gain() -> f32 {
+    1.0
+}
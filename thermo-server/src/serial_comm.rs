@@ -1,119 +1,476 @@
 use std::{
-    fs,
+    collections::VecDeque,
+    fmt, fs,
+    hash::{BuildHasher, RandomState},
     io::{Read, Write},
     path::PathBuf,
     sync::{
-        Arc,
+        Arc, Condvar, Mutex,
         atomic::{AtomicBool, Ordering},
-        mpsc,
     },
-    time::Duration,
+    thread::JoinHandle,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
 };
 
-use crate::{Measurement, safe_mpsc};
+use serialport::SerialPort;
+use thermo_server::cobs;
+
+use crate::{
+    Measurement, Metadata, Readings, SensorEntry, SyncMarker,
+    config::SerialConfig,
+    control::Router,
+    safe_mpsc::SafeSender,
+    sink::{MeasurementSink, encode_frame},
+    time_sync::SyncClock,
+};
 
 const BOOT_CONFIG: &str = "/boot/firmware/cmdline.txt";
-const BOOTLOADER_MODE_CMD: &str = "tmu_bootloader";
+/// Command of the bootloader handshake, see [`BootloaderHandshake`].
+const BOOTLOADER_CMD: &str = "bootloader";
+/// Command requesting an immediate sync frame.
+const SYNC_CMD: &str = "sync";
+/// Time within which a bootloader challenge must be answered.
+const CHALLENGE_TIMEOUT: Duration = Duration::from_secs(30);
+/// Time given to the sink thread to send the acknowledgment before rebooting.
+const ACK_DELAY: Duration = Duration::from_secs(1);
+/// Longest command line accepted, in bytes. Longer lines are truncated.
+const MAX_COMMAND_LEN: usize = 1024;
+/// Longest time the writer thread waits for frames before checking for shutdown.
+const WRITER_IDLE: Duration = Duration::from_millis(500);
+
+/// Binary frames, COBS-encoded, on a serial port.
+///
+/// A reader thread listens on the port for newline-terminated commands while it is open.
+/// Commands are forwarded to the backends through the [`Router`], and every `ACK`/`NACK` response
+/// is sent back as a [`Readings::Response`] frame through `responses`. The `bootloader` command
+/// is handled by the reader itself, see [`BootloaderHandshake`], and so is the `sync` command,
+/// which is answered with `ACK sync <counter>` followed by a [`Readings::Sync`] frame.
+///
+/// Frames are written by a writer thread, which batches and rate limits them as set by
+/// `batch_ms` and `max_bytes_per_sec`, see [`Pacer`]. When the port is closed, the frames still
+/// queued are written at once, so that the stream ends on a complete frame.
+///
+/// The settings read back from the port once it is opened must match the configured ones. They
+/// are logged, and sent as a [`Readings::Metadata`] frame with the path of the port and a single
+/// entry describing the settings, followed by a [`Readings::Sync`] frame.
+pub struct SerialSink {
+    config: SerialConfig,
+    router: Arc<Router>,
+    clock: Arc<SyncClock>,
+    responses: SafeSender<Measurement>,
+    port: Option<Port>,
+}
 
-pub fn serial_thread(
-    path: String,
+/// Threads serving an open serial port.
+struct Port {
+    outbox: Arc<Outbox>,
     running: Arc<AtomicBool>,
-    source: safe_mpsc::SafeReceiver<Measurement>,
-) {
-    log::info!("[COM] Serial thread started");
-    'root: while running.load(Ordering::Relaxed) {
-        source.set_ready(false);
-        let ser = serialport::new(&path, 115200).timeout(Duration::from_secs(1));
-        let mut ser = match serialport::TTYPort::open(&ser) {
-            Ok(ser) => {
-                log::info!("[COM] Serial port opened successfully");
-                ser
-            }
-            Err(e) => {
-                log::error!("[COM] Failed to open serial port: {e}");
-                std::thread::sleep(std::time::Duration::from_secs(1));
-                continue 'root;
-            }
-        };
-        let sig = Arc::new(AtomicBool::new(true));
+    reader: JoinHandle<()>,
+    writer: JoinHandle<()>,
+}
+
+/// Frames waiting for the writer thread, and the error it stopped on.
+struct Outbox {
+    pacer: Mutex<Pacer>,
+    ready: Condvar,
+    error: Mutex<Option<String>>,
+}
+
+impl SerialSink {
+    pub fn new(
+        config: SerialConfig,
+        router: Arc<Router>,
+        clock: Arc<SyncClock>,
+        responses: SafeSender<Measurement>,
+    ) -> Self {
+        Self {
+            config,
+            router,
+            clock,
+            responses,
+            port: None,
+        }
+    }
+
+    /// Send the settings of the port as a [`Readings::Metadata`] frame.
+    fn send_metadata(&self, settings: &LinkSettings) {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |t| t.as_millis() as u64);
+        let metadata = Readings::Metadata(Metadata {
+            version: env!("CARGO_PKG_VERSION").into(),
+            path: self.config.port.clone(),
+            sensors: vec![SensorEntry {
+                id: 0,
+                address: settings.baud as u64,
+                model: settings.to_string(),
+            }],
+        });
+        if let Err(e) = self
+            .responses
+            .send(Measurement::new("serial".into(), metadata, timestamp))
+        {
+            log::error!("[COM] Failed to send metadata: {e:?}");
+        }
+    }
+}
+
+/// Open the serial port of `config`, checking that its settings are applied.
+fn open_port(config: &SerialConfig) -> Result<(serialport::TTYPort, LinkSettings), String> {
+    let requested = LinkSettings::requested(config);
+    let ser = serialport::new(&config.port, requested.baud)
+        .parity(requested.parity)
+        .flow_control(requested.flow_control)
+        .timeout(requested.timeout);
+    let ser =
+        serialport::TTYPort::open(&ser).map_err(|e| format!("Failed to open serial port: {e}"))?;
+    let applied = LinkSettings::applied(&ser)?;
+    if applied != requested {
+        return Err(format!(
+            "Serial port settings not applied: requested {requested}, got {applied}"
+        ));
+    }
+    Ok((ser, applied))
+}
+
+/// Open the serial port of `config` as [`SerialSink`] does, and close it again.
+///
+/// # Returns
+/// The settings applied to the port.
+pub fn probe(config: &SerialConfig) -> Result<String, String> {
+    open_port(config).map(|(_, applied)| applied.to_string())
+}
+
+/// Settings of a serial port, as requested or as read back from the port.
+#[derive(Debug, PartialEq)]
+struct LinkSettings {
+    baud: u32,
+    parity: serialport::Parity,
+    flow_control: serialport::FlowControl,
+    timeout: Duration,
+}
+
+impl LinkSettings {
+    fn requested(config: &SerialConfig) -> Self {
+        Self {
+            baud: config.baud,
+            parity: config.parity.into(),
+            flow_control: config.flow_control.into(),
+            timeout: Duration::from_millis(config.timeout_ms),
+        }
+    }
+
+    fn applied(port: &impl SerialPort) -> Result<Self, String> {
+        let read = |e| format!("Failed to read serial port settings: {e}");
+        Ok(Self {
+            baud: port.baud_rate().map_err(read)?,
+            parity: port.parity().map_err(read)?,
+            flow_control: port.flow_control().map_err(read)?,
+            timeout: port.timeout(),
+        })
+    }
+}
+
+impl fmt::Display for LinkSettings {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} baud, parity {}, flow control {}, timeout {} ms",
+            self.baud,
+            self.parity,
+            self.flow_control,
+            self.timeout.as_millis()
+        )
+    }
+}
+
+impl MeasurementSink for SerialSink {
+    fn name(&self) -> String {
+        "[COM]".into()
+    }
+
+    fn open(&mut self) -> Result<(), String> {
+        let (ser, applied) = open_port(&self.config)?;
+        log::info!("[COM] Serial port opened with {applied}");
+        self.send_metadata(&applied);
+        if let Err(e) = self.responses.send(self.clock.marker()) {
+            log::error!("[COM] Failed to send sync frame: {e:?}");
+        }
         let reader = ser
             .try_clone_native()
-            .expect("[COM] Failed to clone serial port for reading");
-        let reader_hdl = {
-            let sig = sig.clone();
-            std::thread::spawn(move || serial_reader(reader, sig))
+            .map_err(|e| format!("Failed to clone serial port for reading: {e}"))?;
+        let running = Arc::new(AtomicBool::new(true));
+        let outbox = Arc::new(Outbox {
+            pacer: Mutex::new(Pacer::new(
+                Duration::from_millis(self.config.batch_ms),
+                self.config.max_bytes_per_sec,
+                Instant::now(),
+            )),
+            ready: Condvar::new(),
+            error: Mutex::new(None),
+        });
+        let reader = {
+            let running = running.clone();
+            let router = self.router.clone();
+            let clock = self.clock.clone();
+            let responses = self.responses.clone();
+            std::thread::spawn(move || serial_reader(reader, running, router, clock, responses))
         };
-        source.set_ready(true); // here we are ready to receive data from various streams
-        log::info!("[COM] Serial sink is ready to receive data");
-        'readout: while running.load(Ordering::Relaxed) {
-            let samp = match source.receiver().recv_timeout(Duration::from_secs(2)) {
-                Ok(samp) => samp,
-                Err(e) => match e {
-                    mpsc::RecvTimeoutError::Timeout => {
-                        log::warn!("[COM] Timeout while waiting for data: {e}");
-                        continue 'readout;
-                    }
-                    mpsc::RecvTimeoutError::Disconnected => {
-                        log::warn!("[COM] Data source disconnected: {e}");
-                        break 'root;
-                    }
-                },
+        let writer = {
+            let running = running.clone();
+            let outbox = outbox.clone();
+            std::thread::spawn(move || serial_writer(ser, running, outbox))
+        };
+        self.port = Some(Port {
+            outbox,
+            running,
+            reader,
+            writer,
+        });
+        Ok(())
+    }
+
+    fn write(&mut self, measurement: &Measurement) -> Result<(), String> {
+        let port = self.port.as_mut().ok_or("Serial port not open")?;
+        if let Some(e) = port
+            .outbox
+            .error
+            .lock()
+            .map_err(|_| "Outbox poisoned")?
+            .take()
+        {
+            return Err(e);
+        }
+        let Some(bytes) = encode_frame("[COM]", measurement) else {
+            return Ok(());
+        };
+        let dropped = port
+            .outbox
+            .pacer
+            .lock()
+            .map_err(|_| "Outbox poisoned")?
+            .push(bytes, Instant::now());
+        if dropped > 0 {
+            log::warn!("[COM] Serial link is not keeping up, dropped {dropped} frames");
+        }
+        port.outbox.ready.notify_one();
+        Ok(())
+    }
+
+    fn close(&mut self) {
+        if let Some(port) = self.port.take() {
+            log::info!("[COM] Closing serial port");
+            port.running.store(false, Ordering::Relaxed);
+            port.outbox.ready.notify_one();
+            if port.reader.join().is_err() {
+                log::error!("[COM] Reader thread panicked");
+            }
+            if port.writer.join().is_err() {
+                log::error!("[COM] Writer thread panicked");
+            }
+        }
+    }
+}
+
+/// Batching and rate limiting of the binary frames written to the serial port.
+///
+/// The frames queued within `window` of the oldest queued frame are coalesced into a single COBS
+/// frame, which holds the binary frames one after the other; a receiver decodes them in turn with
+/// [`Measurement::from_bytes`]. With a rate limit, at most `rate` bytes are written per second on
+/// average, and at most a second worth of frames is queued: the oldest frames are dropped to make
+/// room for new ones, so that a burst of measurements cannot overrun the link.
+struct Pacer {
+    window: Duration,
+    rate: Option<u64>,
+    queue: VecDeque<(Instant, Vec<u8>)>,
+    queued: u64,
+    /// Bytes that may be written right away, negative after a batch larger than the budget.
+    budget: f64,
+    refilled: Instant,
+}
+
+impl Pacer {
+    fn new(window: Duration, rate: Option<u64>, now: Instant) -> Self {
+        let rate = rate.filter(|&rate| rate > 0);
+        Self {
+            window,
+            rate,
+            queue: VecDeque::new(),
+            queued: 0,
+            budget: rate.unwrap_or(0) as f64,
+            refilled: now,
+        }
+    }
+
+    /// Queue a binary frame.
+    ///
+    /// # Returns
+    /// The number of queued frames dropped to make room for it.
+    fn push(&mut self, frame: Vec<u8>, now: Instant) -> usize {
+        self.queued += frame.len() as u64;
+        self.queue.push_back((now, frame));
+        let Some(rate) = self.rate else {
+            return 0;
+        };
+        let mut dropped = 0;
+        while self.queued > rate && self.queue.len() > 1 {
+            if let Some((_, frame)) = self.queue.pop_front() {
+                self.queued -= frame.len() as u64;
+                dropped += 1;
+            }
+        }
+        dropped
+    }
+
+    /// Time at which the queued frames are to be written, or `None` if there are none.
+    fn due(&mut self, now: Instant) -> Option<Instant> {
+        let (first, _) = self.queue.front()?;
+        let mut due = *first + self.window;
+        if let Some(rate) = self.rate {
+            let elapsed = now.saturating_duration_since(self.refilled).as_secs_f64();
+            self.budget = (self.budget + elapsed * rate as f64).min(rate as f64);
+            self.refilled = now;
+            if self.budget < 0.0 {
+                due = due.max(now + Duration::from_secs_f64(-self.budget / rate as f64));
+            }
+        }
+        Some(due)
+    }
+
+    /// Take the queued frames as a single COBS-encoded frame, if they are due.
+    fn take(&mut self, now: Instant) -> Option<Vec<u8>> {
+        if self.due(now)? > now {
+            return None;
+        }
+        self.flush()
+    }
+
+    /// Take the queued frames as a single COBS-encoded frame, due or not.
+    fn flush(&mut self) -> Option<Vec<u8>> {
+        if self.queue.is_empty() {
+            return None;
+        }
+        let mut batch = Vec::with_capacity(self.queued as usize);
+        for (_, frame) in self.queue.drain(..) {
+            batch.extend_from_slice(&frame);
+        }
+        self.queued = 0;
+        let frame = cobs::encode(&batch);
+        self.budget -= frame.len() as f64;
+        Some(frame)
+    }
+}
+
+fn serial_writer(mut ser: serialport::TTYPort, running: Arc<AtomicBool>, outbox: Arc<Outbox>) {
+    log::info!("[COM] Serial writer thread started");
+    while running.load(Ordering::Relaxed) {
+        let frame = {
+            let Ok(mut pacer) = outbox.pacer.lock() else {
+                break;
             };
-            if let Err(e) = ser.write_all(&samp.to_le_bytes()) {
-                log::error!("[COM] Failed to write data to serial port: {e}");
-                break 'readout;
+            let now = Instant::now();
+            match pacer.take(now) {
+                Some(frame) => frame,
+                None => {
+                    let wait = pacer
+                        .due(now)
+                        .map_or(WRITER_IDLE, |due| due - now)
+                        .min(WRITER_IDLE);
+                    if outbox.ready.wait_timeout(pacer, wait).is_err() {
+                        break;
+                    }
+                    continue;
+                }
             }
-            if let Err(e) = ser.flush() {
-                log::error!("[COM] Failed to flush serial port: {e}");
-                break 'readout;
+        };
+        let result = ser
+            .write_all(&frame)
+            .map_err(|e| format!("Failed to write data to serial port: {e}"))
+            .and_then(|_| {
+                ser.flush()
+                    .map_err(|e| format!("Failed to flush serial port: {e}"))
+            });
+        if let Err(e) = result {
+            log::error!("[COM] {e}");
+            if let Ok(mut error) = outbox.error.lock() {
+                *error = Some(e);
             }
+            break;
         }
-        log::info!("[COM] Closing serial port");
-        sig.store(false, Ordering::Relaxed);
-        reader_hdl.join().expect("[COM] Reader thread panicked");
     }
-    log::info!("[COM] Serial thread exiting");
+    // write out the frames queued before the port was closed
+    let frame = (!running.load(Ordering::Relaxed))
+        .then(|| outbox.pacer.lock().ok().and_then(|mut pacer| pacer.flush()))
+        .flatten();
+    if let Some(frame) = frame {
+        if let Err(e) = ser.write_all(&frame).and_then(|_| ser.flush()) {
+            log::error!("[COM] Failed to write queued frames to serial port: {e}");
+        } else {
+            log::info!("[COM] Wrote {} queued bytes", frame.len());
+        }
+    }
+    log::info!("[COM] Serial writer thread exiting");
 }
 
-fn serial_reader(ser: serialport::TTYPort, running: Arc<AtomicBool>) {
+fn serial_reader(
+    ser: serialport::TTYPort,
+    running: Arc<AtomicBool>,
+    router: Arc<Router>,
+    clock: Arc<SyncClock>,
+    responses: SafeSender<Measurement>,
+) {
     log::info!("[COM] Serial reader thread started");
     let mut ser = ser;
     let mut buf = [0u8; 256];
+    let mut line = Vec::new();
+    let mut bootloader = BootloaderHandshake::default();
+    let respond = |response: String| {
+        log::info!("[COM] {response}");
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |t| t.as_millis() as u64);
+        let response = Measurement::new("serial".into(), Readings::Response(response), timestamp);
+        if let Err(e) = responses.send(response) {
+            log::error!("[COM] Failed to send response: {e:?}");
+        }
+    };
     while running.load(Ordering::Relaxed) {
         match ser.read(&mut buf) {
             Ok(n) => {
-                let cmd = String::from_utf8_lossy(&buf[..n]);
-                if !cmd.is_empty() {
+                for &byte in &buf[..n] {
+                    if byte != b'\n' && byte != b'\r' {
+                        if line.len() < MAX_COMMAND_LEN {
+                            line.push(byte);
+                        }
+                        continue;
+                    }
+                    let cmd = String::from_utf8_lossy(&line).trim().to_string();
+                    line.clear();
+                    if cmd.is_empty() {
+                        continue;
+                    }
                     log::info!("[COM] Received command: {cmd}");
-                }
-                if cmd.contains(BOOTLOADER_MODE_CMD) {
-                    log::info!("[COM] Bootloader command received, exiting reader");
-                    let path = PathBuf::from(BOOT_CONFIG);
-                    if !path.exists() {
-                        log::error!("[COM] Boot config file does not exist: {BOOT_CONFIG}");
-                    } else {
-                        log::info!("[COM] Reading boot config file: {BOOT_CONFIG}");
-                        match fs::read_to_string(&path) {
-                            Ok(content) => {
-                                log::info!("[COM] Boot config content: {content}");
-                                let content = content.replace("g_serial", "g_ether");
-                                if let Err(e) = fs::write(&path, content) {
-                                    log::error!("[COM] Failed to write boot config file: {e}");
-                                } else {
-                                    log::info!(
-                                        "[COM] Boot config file updated successfully, rebooting system..."
-                                    );
-                                    if let Err(e) =
-                                        std::process::Command::new("sudo").arg("reboot").status()
-                                    {
-                                        log::error!("[COM] Failed to reboot system: {e}");
-                                    }
-                                }
-                            }
-                            Err(e) => {
-                                log::error!("[COM] Failed to read boot config file: {e}");
+                    if cmd == SYNC_CMD {
+                        let marker = clock.marker();
+                        if let Readings::Sync(SyncMarker { counter, .. }) = marker.readings {
+                            respond(format!("ACK {SYNC_CMD} {counter}"));
+                        }
+                        if let Err(e) = responses.send(marker) {
+                            log::error!("[COM] Failed to send sync frame: {e:?}");
+                        }
+                        continue;
+                    }
+                    match bootloader.handle(&cmd, Instant::now()) {
+                        Some(Handshake::Reply(response)) => respond(response),
+                        Some(Handshake::Enter(response)) => {
+                            respond(response);
+                            std::thread::sleep(ACK_DELAY);
+                            if let Err(e) = enter_bootloader() {
+                                log::error!("[COM] {e}");
+                                respond(format!("NACK {BOOTLOADER_CMD} {e}"));
                             }
                         }
+                        None => router.execute(&cmd).into_iter().for_each(respond),
                     }
                 }
             }
@@ -129,3 +486,194 @@ fn serial_reader(ser: serialport::TTYPort, running: Arc<AtomicBool>) {
     }
     log::info!("[COM] Serial reader thread exiting");
 }
+
+/// Step of the bootloader handshake reached by a command line.
+#[derive(Debug, PartialEq)]
+enum Handshake {
+    /// Send the response, and wait for the next command.
+    Reply(String),
+    /// Send the acknowledgment, then enter the bootloader.
+    Enter(String),
+}
+
+/// Two-step handshake guarding the `bootloader` command, so that line noise can not reboot the
+/// instrument:
+/// - `bootloader request` is answered with `ACK bootloader challenge <nonce>`, with a random
+///   64-bit hexadecimal nonce.
+/// - `bootloader enter <nonce>`, sent within [`CHALLENGE_TIMEOUT`], is answered with
+///   `ACK bootloader rebooting` before the boot configuration is touched.
+///
+/// A challenge is only valid for a single `enter`, whether or not the nonce matches.
+#[derive(Default)]
+struct BootloaderHandshake {
+    challenge: Option<(u64, Instant)>,
+}
+
+impl BootloaderHandshake {
+    /// Handle a command line.
+    ///
+    /// # Returns
+    /// The step reached, or `None` if the line is not a bootloader command.
+    fn handle(&mut self, cmd: &str, now: Instant) -> Option<Handshake> {
+        let mut words = cmd.split_whitespace();
+        if words.next() != Some(BOOTLOADER_CMD) {
+            return None;
+        }
+        let nack = |reason: &str| Some(Handshake::Reply(format!("NACK {BOOTLOADER_CMD} {reason}")));
+        match (words.next(), words.next(), words.next()) {
+            (Some("request"), None, _) => {
+                let nonce = RandomState::new().hash_one(SystemTime::now());
+                self.challenge = Some((nonce, now));
+                Some(Handshake::Reply(format!(
+                    "ACK {BOOTLOADER_CMD} challenge {nonce:016x}"
+                )))
+            }
+            (Some("enter"), Some(response), None) => match self.challenge.take() {
+                None => nack("no pending challenge"),
+                Some((_, issued)) if now.duration_since(issued) > CHALLENGE_TIMEOUT => {
+                    nack("challenge expired")
+                }
+                Some((nonce, _)) if u64::from_str_radix(response, 16) == Ok(nonce) => {
+                    Some(Handshake::Enter(format!("ACK {BOOTLOADER_CMD} rebooting")))
+                }
+                Some(_) => nack("invalid challenge response"),
+            },
+            _ => nack("expected request or enter <nonce>"),
+        }
+    }
+}
+
+/// Switch the USB gadget to Ethernet mode and reboot into it.
+fn enter_bootloader() -> Result<(), String> {
+    log::info!("[COM] Bootloader handshake completed");
+    let path = PathBuf::from(BOOT_CONFIG);
+    if !path.exists() {
+        return Err(format!("Boot config file does not exist: {BOOT_CONFIG}"));
+    }
+    log::info!("[COM] Reading boot config file: {BOOT_CONFIG}");
+    let content =
+        fs::read_to_string(&path).map_err(|e| format!("Failed to read boot config file: {e}"))?;
+    log::info!("[COM] Boot config content: {content}");
+    let content = content.replace("g_serial", "g_ether");
+    fs::write(&path, content).map_err(|e| format!("Failed to write boot config file: {e}"))?;
+    log::info!("[COM] Boot config file updated successfully, rebooting system...");
+    std::process::Command::new("sudo")
+        .arg("reboot")
+        .status()
+        .map_err(|e| format!("Failed to reboot system: {e}"))?;
+    Ok(())
+}
+
+mod test {
+    #[test]
+    fn test_pacer() {
+        use super::Pacer;
+        use crate::{Measurement, Readings};
+        use std::time::{Duration, Instant};
+        use thermo_server::cobs::Decoder;
+        let frame = |sequence| {
+            Measurement {
+                sequence,
+                timestamp: 0,
+                source: String::new(),
+                readings: Readings::Temperature(vec![(1, 20.0)]),
+            }
+            .to_bytes()
+            .unwrap()
+        };
+        let decode = |frame: Vec<u8>| {
+            let mut decoder = Decoder::default();
+            let mut frames = decoder.feed(&frame);
+            assert_eq!(frames.len(), 1);
+            let bytes = frames.pop().unwrap().unwrap();
+            let mut sequences = Vec::new();
+            let mut offset = 0;
+            while offset < bytes.len() {
+                let (measurement, len) = Measurement::from_bytes(&bytes[offset..]).unwrap();
+                sequences.push(measurement.sequence);
+                offset += len;
+            }
+            sequences
+        };
+        let len = frame(0).len() as u64;
+        let now = Instant::now();
+        let ms = |ms| now + Duration::from_millis(ms);
+
+        // frames within the window are coalesced
+        let mut pacer = Pacer::new(Duration::from_millis(100), None, now);
+        assert_eq!(pacer.take(now), None);
+        assert_eq!(pacer.push(frame(1), now), 0);
+        assert_eq!(pacer.push(frame(2), ms(50)), 0);
+        assert_eq!(pacer.due(ms(50)), Some(ms(100)));
+        assert_eq!(pacer.take(ms(99)), None);
+        assert_eq!(pacer.take(ms(100)).map(decode), Some(vec![1, 2]));
+        assert_eq!(pacer.take(ms(200)), None);
+
+        // a burst beyond a second worth of the rate drops the oldest frames
+        let mut pacer = Pacer::new(Duration::ZERO, Some(3 * len), now);
+        for sequence in 1..=5 {
+            pacer.push(frame(sequence), now);
+        }
+        let batch = pacer.take(now).unwrap();
+        let sent = batch.len() as u64;
+        assert_eq!(decode(batch), vec![3, 4, 5]);
+        // the budget is spent, so the next frame waits until it is refilled
+        pacer.push(frame(6), now);
+        assert_eq!(pacer.take(now), None);
+        let due = pacer.due(now).unwrap();
+        assert!(due > now && due <= now + Duration::from_secs_f64(sent as f64 / (3 * len) as f64));
+        assert_eq!(pacer.take(due).map(decode), Some(vec![6]));
+
+        // on shutdown, the queued frames are written at once
+        pacer.push(frame(7), due);
+        pacer.push(frame(8), due);
+        assert_eq!(pacer.take(due), None);
+        assert_eq!(pacer.flush().map(decode), Some(vec![7, 8]));
+        assert_eq!(pacer.flush(), None);
+    }
+
+    #[test]
+    fn test_bootloader_handshake() {
+        use super::{BootloaderHandshake, CHALLENGE_TIMEOUT, Handshake};
+        use std::time::{Duration, Instant};
+        let nack = |reason: &str| Some(Handshake::Reply(format!("NACK bootloader {reason}")));
+        let request = |handshake: &mut BootloaderHandshake, now| match handshake
+            .handle("bootloader request", now)
+        {
+            Some(Handshake::Reply(reply)) => reply
+                .strip_prefix("ACK bootloader challenge ")
+                .unwrap()
+                .to_string(),
+            reply => panic!("unexpected reply {reply:?}"),
+        };
+        let mut handshake = BootloaderHandshake::default();
+        let now = Instant::now();
+        assert_eq!(handshake.handle("list *", now), None);
+        assert_eq!(handshake.handle("noise tmu_bootloader noise", now), None);
+        assert_eq!(
+            handshake.handle("bootloader enter 0", now),
+            nack("no pending challenge")
+        );
+        // a wrong response consumes the challenge
+        let nonce = request(&mut handshake, now);
+        assert_eq!(
+            handshake.handle(&format!("bootloader enter {nonce}0"), now),
+            nack("invalid challenge response")
+        );
+        assert_eq!(
+            handshake.handle(&format!("bootloader enter {nonce}"), now),
+            nack("no pending challenge")
+        );
+        let nonce = request(&mut handshake, now);
+        let late = now + CHALLENGE_TIMEOUT + Duration::from_secs(1);
+        assert_eq!(
+            handshake.handle(&format!("bootloader enter {nonce}"), late),
+            nack("challenge expired")
+        );
+        let nonce = request(&mut handshake, now);
+        assert_eq!(
+            handshake.handle(&format!("bootloader enter {nonce}"), now),
+            Some(Handshake::Enter("ACK bootloader rebooting".into()))
+        );
+    }
+}
@@ -0,0 +1,385 @@
+use std::{
+    path::PathBuf,
+    sync::Arc,
+    thread,
+    time::{Duration, Instant},
+};
+
+use embedded_onewire::OneWireCrc;
+use hdc1010::{Both, Hdc1010, hygrometry};
+use hdc3022::Hdc3022;
+use piccthermo_core::{
+    HumiditySensor, RelativeHumidity, SensorDriver, Temperature, TemperatureSensor,
+    sim::SimulatedSensor,
+};
+use piccthermo_id::{SensorFilter, SensorId};
+
+use crate::{
+    Readings, SensorEntry,
+    backend::SensorBackend,
+    config::{BusConfig, SensorType, SimulationConfig},
+    control::Command,
+    health::Health,
+    humi_sensors::Hygrometer,
+    sensor_map::SensorMap,
+};
+
+/// Family code of the ROMs of the synthetic temperature sensors, that of the DS28EA00.
+const FAMILY: u64 = 0x42;
+/// Most humidity sensors on a bus, one for every address strap setting.
+const MAX_HYGROMETERS: usize = 4;
+
+/// ROM code of a synthetic temperature sensor, with the channel ID as serial number.
+fn rom(channel: u64) -> u64 {
+    let rom = FAMILY | channel << 8;
+    let mut crc = OneWireCrc::default();
+    for byte in &rom.to_le_bytes()[..7] {
+        crc.update(*byte);
+    }
+    rom | (crc.value() as u64) << 56
+}
+
+/// Synthetic sensors standing in for the sensors of a bus, driven through the same traits as the
+/// drivers.
+///
+/// The temperature sensors are identified like DS28EA00 sensors, by the hash of a ROM code that
+/// is derived from the bus path, and the humidity sensors like HDC1010 or HDC3022 sensors, by
+/// their I2C address on the bus.
+pub struct SimBackend {
+    path: PathBuf,
+    sensor: SensorType,
+    config: SimulationConfig,
+    filter: SensorFilter,
+    conversion_time: Duration,
+    poll_interval: Duration,
+    print: bool,
+    sensors: Arc<SensorMap>,
+    health: Health,
+    sim: Option<SimulatedSensor<16>>,
+}
+
+impl SimBackend {
+    pub fn new(
+        config: &BusConfig,
+        sim: &SimulationConfig,
+        print: bool,
+        sensors: Arc<SensorMap>,
+    ) -> Self {
+        let conversion_time = match config.sensor {
+            SensorType::Ds28ea00 => config.ds28ea00_resolution().conversion_time(),
+            SensorType::Hdc1010 | SensorType::Hdc3022 => Duration::from_millis(10),
+        };
+        Self {
+            path: config.path.clone(),
+            sensor: config.sensor,
+            config: sim.clone(),
+            filter: config.filter(),
+            conversion_time,
+            poll_interval: config.poll_interval(),
+            print,
+            sensors,
+            health: Health::default(),
+            sim: None,
+        }
+    }
+
+    fn humidity(&self) -> bool {
+        self.sensor != SensorType::Ds28ea00
+    }
+
+    /// ID of a channel of the synthetic sensor, as reported by the real sensors.
+    fn id(&self, channel: u64) -> u32 {
+        if self.humidity() {
+            SensorId::from_i2c(&self.bus(), channel as u8, false).value()
+        } else {
+            SensorId::from_rom(rom(channel)).value()
+        }
+    }
+
+    /// Check whether a channel of the synthetic sensor is selected by the filter.
+    fn selected(&self, channel: u64) -> bool {
+        let id = self.id(channel);
+        let label = self.sensors.label(id);
+        if self.humidity() {
+            self.filter.selects(SensorId::new(id), None, label)
+        } else {
+            self.filter.selects_rom(rom(channel), label)
+        }
+    }
+
+    fn temperatures(&mut self) -> Result<Vec<Readings>, String> {
+        let lpath = self.path.to_string_lossy().into_owned();
+        let Some(sim) = self.sim.as_mut() else {
+            return Err("Bus not initialized".into());
+        };
+        SensorDriver::<()>::trigger(sim, &mut ())
+            .map_err(|e| format!("Failed to trigger temperature conversion: {e:?}"))?;
+        thread::sleep(SensorDriver::<()>::ready_after(sim));
+        let mut read = Vec::new();
+        TemperatureSensor::read(sim, &mut (), &mut |channel, temp| {
+            read.push((channel, temp))
+        })
+        .map_err(|e| format!("Failed to read sensors: {e:?}"))?;
+        let channels = sim.ids().collect::<Vec<_>>();
+        let outcomes = channels
+            .iter()
+            .filter(|channel| self.selected(**channel))
+            .map(|channel| {
+                (
+                    self.id(*channel),
+                    read.iter().any(|(read, _)| read == channel),
+                )
+            })
+            .collect::<Vec<_>>();
+        let data = read
+            .into_iter()
+            .filter(|(channel, _)| self.selected(*channel))
+            .map(|(channel, temp)| (self.id(channel), temp))
+            .map(|(id, temp)| (id, self.sensors.calibrate(id, temp.celsius())))
+            .collect::<Vec<_>>();
+        if self.print {
+            let mut msg = String::new();
+            for (id, temp) in &data {
+                msg.push_str(&format!("{id:08x}: {temp:.2} °C, "));
+            }
+            log::info!("[SIM] {lpath}> {msg}");
+        }
+        self.health.update(outcomes);
+        let mut data = vec![Readings::Temperature(data)];
+        data.extend(self.health.report(Instant::now()));
+        Ok(data)
+    }
+
+    fn humidities(&mut self) -> Result<Vec<Readings>, String> {
+        let lpath = self.path.to_string_lossy().into_owned();
+        let bus = self.bus();
+        let sensor_id = |addr| SensorId::from_i2c(&bus, addr, false).value();
+        let Some(sim) = self.sim.as_mut() else {
+            return Err("Bus not initialized".into());
+        };
+        SensorDriver::<()>::trigger(sim, &mut ())
+            .map_err(|e| format!("Failed to trigger measurement: {e:?}"))?;
+        thread::sleep(SensorDriver::<()>::ready_after(sim));
+        let mut mes = Vec::new();
+        let mut temps = Vec::new();
+        let mut dew = Vec::new();
+        let sensors = &self.sensors;
+        HumiditySensor::read(
+            sim,
+            &mut (),
+            &mut |addr, t: Temperature, r: RelativeHumidity| {
+                let addr = addr as u8;
+                let id = sensor_id(addr);
+                let tid = id | SensorId::TEMPERATURE_CHANNEL;
+                let t = Temperature::from_celsius(sensors.calibrate(tid, t.celsius()));
+                let r = RelativeHumidity::from_percentage(
+                    sensors.calibrate(id, r.percentage()).clamp(0.0, 100.0),
+                );
+                // undefined in dry air, and then left out
+                let dp = hygrometry::dew_point(t, r).map(|dp| dp.celsius());
+                log::info!(
+                    "[SIM] {lpath}> Sensor 0x{addr:02x}: {:.2}°C, {}%, dew point {}",
+                    t.celsius(),
+                    r.percentage(),
+                    dp.map_or("undefined".into(), |dp| format!("{dp:.2}°C")),
+                );
+                mes.push((id, r.percentage()));
+                temps.push((tid, t.celsius()));
+                dew.extend(dp.map(|dp| (id, dp)));
+            },
+        )
+        .map_err(|e| format!("Failed to read sensors: {e:?}"))?;
+        let outcomes = sim
+            .ids()
+            .map(|addr| sensor_id(addr as u8))
+            .map(|id| (id, mes.iter().any(|(read, _)| *read == id)))
+            .collect::<Vec<_>>();
+        let mut data = Vec::new();
+        if !mes.is_empty() {
+            data.push(Readings::Humidity(mes));
+            data.push(Readings::Temperature(temps));
+        }
+        if !dew.is_empty() {
+            data.push(Readings::DewPoint(dew));
+        }
+        self.health.update(outcomes);
+        data.extend(self.health.report(Instant::now()));
+        Ok(data)
+    }
+}
+
+impl SensorBackend for SimBackend {
+    fn name(&self) -> String {
+        format!("[SIM] {}", self.path.to_string_lossy())
+    }
+
+    fn bus(&self) -> String {
+        self.path
+            .file_name()
+            .map_or_else(
+                || self.path.to_string_lossy(),
+                |name| name.to_string_lossy(),
+            )
+            .into_owned()
+    }
+
+    fn path(&self) -> String {
+        self.path.to_string_lossy().into_owned()
+    }
+
+    fn poll_interval(&self) -> Duration {
+        self.poll_interval
+    }
+
+    fn inventory(&self) -> Vec<SensorEntry> {
+        let Some(sim) = self.sim.as_ref() else {
+            return Vec::new();
+        };
+        sim.ids()
+            .flat_map(|channel| {
+                let entry = |id| SensorEntry {
+                    id,
+                    address: if self.humidity() {
+                        channel
+                    } else {
+                        rom(channel)
+                    },
+                    model: "simulated".into(),
+                };
+                let id = self.id(channel);
+                let mut entries = vec![entry(id)];
+                if self.humidity() {
+                    entries.push(entry(id | SensorId::TEMPERATURE_CHANNEL));
+                }
+                entries
+            })
+            .collect()
+    }
+
+    fn init(&mut self) -> Result<(), String> {
+        let lpath = self.path.to_string_lossy();
+        let bus = crc32fast::hash(lpath.as_bytes());
+        let (channels, first_id) = match self.sensor {
+            // serial numbers of the ROMs, unique to the bus
+            SensorType::Ds28ea00 => (self.config.sensors, (bus as u64) << 16),
+            SensorType::Hdc1010 => (
+                self.config.sensors.min(MAX_HYGROMETERS),
+                <Hdc1010<Both> as Hygrometer>::strapped_address(false, false) as u64,
+            ),
+            SensorType::Hdc3022 => (
+                self.config.sensors.min(MAX_HYGROMETERS),
+                <Hdc3022 as Hygrometer>::strapped_address(false, false) as u64,
+            ),
+        };
+        let sim = SimulatedSensor::new(self.config.seed ^ bus as u64)
+            .with_channels(channels)
+            .with_first_id(first_id)
+            .with_drift(self.config.drift)
+            .with_noise(self.config.noise)
+            .with_dropout(self.config.dropout)
+            .with_conversion_time(self.conversion_time);
+        log::info!(
+            "[SIM] {lpath}> Simulating {} {:?} sensors",
+            sim.ids().count(),
+            self.sensor
+        );
+        self.sim = Some(sim);
+        Ok(())
+    }
+
+    fn acquire(&mut self) -> Result<Vec<Readings>, String> {
+        if self.humidity() {
+            self.humidities()
+        } else {
+            self.temperatures()
+        }
+    }
+
+    fn command(&mut self, command: &Command) -> Result<String, String> {
+        let Some(sim) = self.sim.as_ref() else {
+            return Err("bus not initialized".into());
+        };
+        match command {
+            Command::List => Ok(sim
+                .ids()
+                .map(|channel| format!("{:08x}", self.id(channel)))
+                .collect::<Vec<_>>()
+                .join(",")),
+            Command::SetExclusions(patterns) if !self.humidity() => {
+                self.filter.set_exclude(patterns.clone());
+                log::info!(
+                    "[SIM] {}> Excluding sensors: {}",
+                    self.path.display(),
+                    patterns
+                        .iter()
+                        .map(ToString::to_string)
+                        .collect::<Vec<_>>()
+                        .join(",")
+                );
+                Ok(format!("exclude {} patterns", patterns.len()))
+            }
+            _ => Err("unsupported command".into()),
+        }
+    }
+}
+
+mod test {
+    #[test]
+    fn test_sim_backend() {
+        use super::SimBackend;
+        use crate::{
+            Readings,
+            backend::SensorBackend,
+            config::{BusConfig, SensorType, SimulationConfig},
+            sensor_map::SensorMap,
+        };
+        use std::{path::PathBuf, sync::Arc};
+        let bus = |sensor| BusConfig {
+            path: PathBuf::from("/dev/i2c-7"),
+            sensor,
+            poll_interval_ms: 1000,
+            resolution: Some(9),
+            exclude: Vec::new(),
+            include_only: Vec::new(),
+            overdrive: true,
+            stuck_samples: 0,
+            roms: Vec::new(),
+            expected: Vec::new(),
+        };
+        let mut sim = SimulationConfig {
+            sensors: 6,
+            drift: 0.05,
+            noise: 0.1,
+            dropout: 0.0,
+            seed: 1,
+        };
+        let sensors = Arc::new(SensorMap::default());
+        let mut backend = SimBackend::new(&bus(SensorType::Ds28ea00), &sim, false, sensors.clone());
+        backend.init().unwrap();
+        assert_eq!(backend.inventory().len(), 6);
+        let data = backend.acquire().unwrap();
+        let Readings::Temperature(temps) = &data[0] else {
+            panic!("expected temperatures, got {data:?}");
+        };
+        assert_eq!(temps.len(), 6);
+        assert!(temps.iter().all(|(_, t)| (20.0..24.0).contains(t)));
+        // the humidity buses hold four sensors, each with a temperature channel
+        sim.dropout = 1.0;
+        let mut backend = SimBackend::new(&bus(SensorType::Hdc1010), &sim, false, sensors);
+        backend.init().unwrap();
+        assert_eq!(backend.inventory().len(), 8);
+        let ids = (0x40..0x44)
+            .map(|addr| piccthermo_id::SensorId::from_i2c("i2c-7", addr, false).to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            backend.command(&crate::Command::List).unwrap(),
+            ids.join(",")
+        );
+        // sensors that drop out are reported as failing
+        let data = backend.acquire().unwrap();
+        let [Readings::Health(health)] = data.as_slice() else {
+            panic!("expected a health report, got {data:?}");
+        };
+        assert!(health.iter().all(|h| h.consecutive == 1));
+    }
+}
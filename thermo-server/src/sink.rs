@@ -0,0 +1,128 @@
+use std::{
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+        mpsc,
+    },
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use crate::{Measurement, Readings, safe_mpsc};
+
+/// Longest time spent writing the queued measurements on shutdown.
+const DRAIN_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// A destination for measurements, driven by [`sink_thread`].
+pub trait MeasurementSink: Send {
+    /// Label used as a prefix for log messages, e.g. `[COM]`.
+    fn name(&self) -> String;
+
+    /// Open the sink. Called again every second while it fails.
+    fn open(&mut self) -> Result<(), String>;
+
+    /// Write a measurement. The sink is closed and opened again if this fails.
+    fn write(&mut self, measurement: &Measurement) -> Result<(), String>;
+
+    /// Close the sink.
+    fn close(&mut self) {}
+}
+
+/// Forward every measurement received from `source` to all open sinks.
+///
+/// Once `running` is cleared, the measurements still queued are written for up to
+/// [`DRAIN_TIMEOUT`], followed by a [`Readings::EndOfStream`] frame, before the sinks are closed.
+/// The acquisition threads are to be joined before, so that their last readouts are queued.
+pub fn sink_thread(
+    mut sinks: Vec<Box<dyn MeasurementSink>>,
+    running: Arc<AtomicBool>,
+    source: safe_mpsc::SafeReceiver<Measurement>,
+) {
+    log::info!("[SNK] Sink thread started");
+    let mut open = vec![false; sinks.len()];
+    let mut last_open: Option<Instant> = None;
+    source.set_ready(true); // measurements are dropped by the sinks that are not open
+    while running.load(Ordering::Relaxed) {
+        if open.contains(&false)
+            && last_open.is_none_or(|last| last.elapsed() >= Duration::from_secs(1))
+        {
+            for (sink, open) in sinks.iter_mut().zip(open.iter_mut()) {
+                if *open {
+                    continue;
+                }
+                match sink.open() {
+                    Ok(()) => {
+                        log::info!("{}> Sink is ready to receive data", sink.name());
+                        *open = true;
+                    }
+                    Err(e) => log::error!("{}> {e}", sink.name()),
+                }
+            }
+            last_open = Some(Instant::now());
+        }
+        let samp = match source.receiver().recv_timeout(Duration::from_secs(2)) {
+            Ok(samp) => samp,
+            Err(e) => match e {
+                mpsc::RecvTimeoutError::Timeout => {
+                    log::warn!("[SNK] Timeout while waiting for data: {e}");
+                    continue;
+                }
+                mpsc::RecvTimeoutError::Disconnected => {
+                    log::warn!("[SNK] Data source disconnected: {e}");
+                    break;
+                }
+            },
+        };
+        write_all(&mut sinks, &mut open, &samp);
+    }
+    let deadline = Instant::now() + DRAIN_TIMEOUT;
+    let mut drained = 0;
+    while Instant::now() < deadline {
+        let Ok(samp) = source.receiver().try_recv() else {
+            break;
+        };
+        write_all(&mut sinks, &mut open, &samp);
+        drained += 1;
+    }
+    match source.receiver().try_iter().count() {
+        0 => log::info!("[SNK] Wrote {drained} queued measurements"),
+        left => log::warn!("[SNK] Wrote {drained} queued measurements, dropped {left}"),
+    }
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |t| t.as_millis() as u64);
+    write_all(
+        &mut sinks,
+        &mut open,
+        &Measurement::new("server".into(), Readings::EndOfStream, timestamp),
+    );
+    for (sink, open) in sinks.iter_mut().zip(open) {
+        if open {
+            sink.close();
+        }
+    }
+    log::info!("[SNK] Sink thread exiting");
+}
+
+/// Encode a measurement as a binary frame, or log that the sink `name` drops it.
+///
+/// A measurement too large for a frame is dropped by the binary sinks, which stay open.
+pub fn encode_frame(name: &str, measurement: &Measurement) -> Option<Vec<u8>> {
+    measurement
+        .to_bytes()
+        .map_err(|e| log::error!("{name}> Dropping a measurement that does not fit a frame: {e:?}"))
+        .ok()
+}
+
+/// Write a measurement to all open sinks, closing the ones that fail.
+fn write_all(sinks: &mut [Box<dyn MeasurementSink>], open: &mut [bool], samp: &Measurement) {
+    for (sink, open) in sinks.iter_mut().zip(open.iter_mut()) {
+        if !*open {
+            continue;
+        }
+        if let Err(e) = sink.write(samp) {
+            log::error!("{}> {e}", sink.name());
+            sink.close();
+            *open = false;
+        }
+    }
+}
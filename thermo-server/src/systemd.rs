@@ -0,0 +1,131 @@
+use std::{
+    collections::BTreeMap,
+    env, io,
+    os::{
+        linux::net::SocketAddrExt,
+        unix::net::{SocketAddr, UnixDatagram},
+    },
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+/// Sensors found by the last initialization of every backend, or `None` if it failed.
+static BACKENDS: Mutex<BTreeMap<String, Option<usize>>> = Mutex::new(BTreeMap::new());
+
+/// Record the outcome of an initialization of a backend.
+pub fn initialized(backend: &str, sensors: Option<usize>) {
+    if let Ok(mut backends) = BACKENDS.lock() {
+        backends.insert(backend.into(), sensors);
+    }
+}
+
+/// Notifications to the service manager, with the `sd_notify` protocol.
+///
+/// `READY=1` is sent once every backend has tried to initialize, whether or not it succeeded,
+/// so that a bus without sensors does not hold up the start of the service. `WATCHDOG=1` is sent
+/// at half the watchdog interval of the service, as long as no acquisition thread has stalled.
+/// `STATUS=` reports the number of sensors found and backends up whenever it changes.
+pub struct Notifier {
+    socket: UnixDatagram,
+    addr: SocketAddr,
+    watchdog: Option<Duration>,
+    last_keepalive: Option<Instant>,
+    ready: bool,
+    status: String,
+}
+
+impl Notifier {
+    /// Connect to the service manager, or return `None` if the server was not started by
+    /// systemd with `Type=notify`.
+    pub fn from_env() -> Option<Self> {
+        let path = env::var_os("NOTIFY_SOCKET")?;
+        let path = path.to_string_lossy();
+        let addr = match path.strip_prefix('@') {
+            Some(name) => SocketAddr::from_abstract_name(name.as_bytes()),
+            None => SocketAddr::from_pathname(path.as_ref()),
+        };
+        let res = addr.and_then(|addr| Ok((UnixDatagram::unbound()?, addr)));
+        let (socket, addr) = match res {
+            Ok(res) => res,
+            Err(e) => {
+                log::error!("[SYS] Failed to open notification socket {path}: {e}");
+                return None;
+            }
+        };
+        // the watchdog applies to this process only if the PID matches, when given
+        let pid_matches =
+            env::var("WATCHDOG_PID").map_or(true, |pid| pid.parse() == Ok(std::process::id()));
+        let watchdog = env::var("WATCHDOG_USEC")
+            .ok()
+            .and_then(|usec| usec.parse().ok())
+            .filter(|_| pid_matches)
+            .map(Duration::from_micros);
+        if let Some(watchdog) = watchdog {
+            log::info!("[SYS] Watchdog interval: {watchdog:?}");
+        }
+        Some(Self {
+            socket,
+            addr,
+            watchdog,
+            last_keepalive: None,
+            ready: false,
+            status: String::new(),
+        })
+    }
+
+    fn send(&self, state: &str) -> io::Result<()> {
+        self.socket.send_to_addr(state.as_bytes(), &self.addr)?;
+        Ok(())
+    }
+
+    /// Notify the service manager of the state of the backends.
+    ///
+    /// # Parameters:
+    /// - `backends`: The number of backends started.
+    /// - `healthy`: `false` if an acquisition thread is stalled.
+    pub fn update(&mut self, backends: usize, healthy: bool) {
+        let (initialized, up, sensors) = BACKENDS.lock().map_or((0, 0, 0), |states| {
+            (
+                states.len(),
+                states.values().flatten().count(),
+                states.values().flatten().sum::<usize>(),
+            )
+        });
+        let mut state = String::new();
+        if !self.ready && initialized >= backends {
+            self.ready = true;
+            state.push_str("READY=1\n");
+            log::info!("[SYS] All backends initialized");
+        }
+        let status = if self.ready {
+            format!("{sensors} sensors, {up} of {backends} backends up")
+        } else {
+            format!("Initializing, {initialized} of {backends} backends done")
+        };
+        if status != self.status {
+            state.push_str(&format!("STATUS={status}\n"));
+            self.status = status;
+        }
+        if let Some(watchdog) = self.watchdog
+            && healthy
+            && self
+                .last_keepalive
+                .is_none_or(|last| last.elapsed() >= watchdog / 2)
+        {
+            self.last_keepalive = Some(Instant::now());
+            state.push_str("WATCHDOG=1\n");
+        }
+        if !state.is_empty()
+            && let Err(e) = self.send(&state)
+        {
+            log::warn!("[SYS] Failed to notify the service manager: {e}");
+        }
+    }
+
+    /// Notify the service manager that the server is shutting down.
+    pub fn stopping(&self) {
+        if let Err(e) = self.send("STOPPING=1\n") {
+            log::warn!("[SYS] Failed to notify the service manager: {e}");
+        }
+    }
+}
@@ -1,67 +1,114 @@
 use std::{
     path::PathBuf,
-    sync::{
-        Arc,
-        atomic::{AtomicBool, Ordering},
-    },
-    thread,
+    sync::Arc,
     time::{Duration, Instant},
 };
 
-use ds28ea00::{Ds28ea00Group, ReadoutResolution};
-use ds2484::{DeviceConfiguration, Ds2484Builder, Interact, OneWireConfigurationBuilder};
+use ds28ea00::{Ds28ea00Group, Family, ReadoutResolution, SortOrder};
+use ds2484::{DeviceConfiguration, Ds2484, Ds2484Builder, Interact, OneWireConfigurationBuilder};
 use linux_embedded_hal::{Delay, I2cdev};
+use piccthermo_id::{SensorFilter, SensorId};
 
-use crate::{Measurement, safe_mpsc};
+use crate::{
+    Readings, SensorEntry, backend::SensorBackend, config::BusConfig, control::Command,
+    health::Health, sensor_map::SensorMap,
+};
+
+/// Number of times a device is read again after a CRC error or a missed presence pulse.
+const READ_RETRIES: u8 = 2;
 
-pub fn onewire_thread(
+/// DS28EA00 temperature sensors on a 1-Wire bus behind a DS2484 bridge.
+pub struct OneWireBackend {
     path: PathBuf,
-    running: Arc<AtomicBool>,
     leds: bool,
-    sink: safe_mpsc::SafeSender<Measurement>,
-    exclude: Vec<u32>,
-    no_overdrive: bool,
+    filter: SensorFilter,
+    roms: Vec<u64>,
+    overdrive: bool,
+    resolution: ReadoutResolution,
+    poll_interval: Duration,
     print: bool,
-) {
-    let lpath = path.to_string_lossy();
-    'root: while running.load(Ordering::Relaxed) {
+    sensors: Arc<SensorMap>,
+    health: Health,
+    bus: Option<(Ds2484<I2cdev, Delay>, Ds28ea00Group<16>)>,
+    /// Completion time of the conversion started by `begin_acquire`, if any.
+    conversion: Option<Instant>,
+}
+
+impl OneWireBackend {
+    pub fn new(config: &BusConfig, leds: bool, print: bool, sensors: Arc<SensorMap>) -> Self {
+        Self {
+            path: config.path.clone(),
+            leds,
+            filter: config.filter(),
+            roms: config.roms.clone(),
+            overdrive: config.overdrive,
+            resolution: config.ds28ea00_resolution(),
+            poll_interval: config.poll_interval(),
+            print,
+            sensors,
+            health: Health::default(),
+            bus: None,
+            conversion: None,
+        }
+    }
+}
+
+impl SensorBackend for OneWireBackend {
+    fn name(&self) -> String {
+        format!("[TMP] {}", self.path.to_string_lossy())
+    }
+
+    fn bus(&self) -> String {
+        self.path
+            .file_name()
+            .map_or_else(
+                || self.path.to_string_lossy(),
+                |name| name.to_string_lossy(),
+            )
+            .into_owned()
+    }
+
+    fn path(&self) -> String {
+        self.path.to_string_lossy().into_owned()
+    }
+
+    fn poll_interval(&self) -> Duration {
+        self.poll_interval
+    }
+
+    fn inventory(&self) -> Vec<SensorEntry> {
+        let Some((_, temp_sensors)) = self.bus.as_ref() else {
+            return Vec::new();
+        };
+        temp_sensors
+            .roms()
+            .map(|rom| SensorEntry {
+                id: SensorId::from_rom(rom).value(),
+                address: rom,
+                model: Family::from_rom(rom)
+                    .map_or_else(|| "unknown".into(), |f| format!("{f:?}").to_lowercase()),
+            })
+            .collect()
+    }
+
+    fn init(&mut self) -> Result<(), String> {
+        let lpath = self.path.to_string_lossy();
+        self.bus = None;
+        self.conversion = None;
         log::info!("[TMP] {lpath}> Opening bus",);
         // Open the I2C bus
-        let mut i2c = match I2cdev::new(&path) {
-            Ok(i2c) => {
-                log::info!("[TMP] {lpath}> Bus opened successfully",);
-                i2c
-            }
-            Err(e) => {
-                log::error!("[TMP] {lpath}> Failed to open bus: {e}",);
-                thread::sleep(Duration::from_secs(1));
-                continue 'root;
-            }
-        };
-        let mut delay = Delay;
-        let mut ds2484 = match Ds2484Builder::default().build(&mut i2c, &mut delay) {
-            Ok(ds2484) => {
-                log::info!("[TMP] {lpath}> DS2484 instance created successfully",);
-                ds2484
-            }
-            Err(e) => {
-                log::error!("[TMP] {lpath}> Failed to create DS2484 instance: {e:?}",);
-                thread::sleep(Duration::from_secs(1));
-                continue 'root;
-            }
-        };
+        let i2c = I2cdev::new(&self.path).map_err(|e| format!("Failed to open bus: {e}"))?;
+        log::info!("[TMP] {lpath}> Bus opened successfully",);
+        let mut ds2484 = Ds2484Builder::default()
+            .build(i2c, Delay)
+            .map_err(|e| format!("Failed to create DS2484 instance: {e:?}"))?;
+        log::info!("[TMP] {lpath}> DS2484 instance created successfully",);
         let mut cfg = DeviceConfiguration::default();
-        if let Err(e) = cfg.read(&mut ds2484) {
-            log::error!("[TMP] {lpath}> Failed to read device configuration: {e:?}",);
-            thread::sleep(Duration::from_secs(1));
-            continue 'root;
-        }
+        cfg.read(&mut ds2484)
+            .map_err(|e| format!("Failed to read device configuration: {e:?}"))?;
         cfg.set_active_pullup(true);
-        if let Err(e) = cfg.write(&mut ds2484) {
-            log::error!("[TMP] {lpath}> Failed to write device configuration: {e:?}",);
-            thread::sleep(Duration::from_secs(1));
-            continue 'root;
-        }
+        cfg.write(&mut ds2484)
+            .map_err(|e| format!("Failed to write device configuration: {e:?}"))?;
         let mut port_cfg = OneWireConfigurationBuilder::default()
             .reset_pulse(440000, 44000)
             .presence_detect_time(58000, 5500)
@@ -76,105 +123,211 @@ pub fn onewire_thread(
         }
         let mut delay = Delay;
         let mut temp_sensors = Ds28ea00Group::<16>::default()
-            .with_resolution(ReadoutResolution::Resolution12bit)
+            .with_resolution(self.resolution)
             .with_t_low(-40)
             .with_t_high(50)
-            .with_toggle_pio(leds);
-        match temp_sensors.enumerate(&mut ds2484) {
-            Ok(devices) => {
-                log::info!("[TMP] {lpath}> Found {devices} devices",);
-                devices
+            .with_toggle_pio(self.leds)
+            .with_skip_invalid_roms(true)
+            .with_retries(READ_RETRIES)
+            .with_families(&[Family::Ds28ea00, Family::Ds18b20, Family::Ds1822])
+            .with_sort_order(SortOrder::Key(|rom| SensorId::from_rom(rom).value()));
+        let keep = |rom| {
+            let id = SensorId::from_rom(rom);
+            let selected = self.filter.selects_rom(rom, self.sensors.label(id.value()));
+            if !selected {
+                log::info!("[TMP] {lpath}> Leaving out sensor with ID {id}");
+            }
+            selected
+        };
+        if self.roms.is_empty() {
+            let devices = temp_sensors
+                .enumerate_filtered(&mut ds2484, keep)
+                .map_err(|e| format!("Failed to enumerate devices: {e:?}"))?;
+            log::info!("[TMP] {lpath}> Found {devices} devices",);
+        } else {
+            // the expected sensors are pinned, skip the search
+            for &rom in self.roms.iter().filter(|&&rom| keep(rom)) {
+                if let Err(e) = temp_sensors.add_rom(rom) {
+                    log::error!("[TMP] {lpath}> Failed to add device 0x{rom:016x}: {e:?}",);
+                }
+            }
+            let devices = temp_sensors
+                .apply_configuration(&mut ds2484)
+                .map_err(|e| format!("Failed to configure devices: {e:?}"))?;
+            log::info!("[TMP] {lpath}> Using {devices} pinned devices",);
+        }
+        if temp_sensors.invalid_roms() > 0 {
+            log::warn!(
+                "[TMP] {lpath}> Rejected {} ROM codes with invalid CRC",
+                temp_sensors.invalid_roms()
+            );
+        }
+        match temp_sensors.verify_configuration(&mut ds2484) {
+            Ok(misconfigured) => {
+                for rom in misconfigured {
+                    log::warn!("[TMP] {lpath}> Configuration did not take on device 0x{rom:016x}",);
+                }
             }
             Err(e) => {
-                log::error!("[TMP] {lpath}> Failed to enumerate devices: {e:?}",);
-                thread::sleep(Duration::from_secs(1));
-                continue 'root;
+                log::error!("[TMP] {lpath}> Failed to verify device configuration: {e:?}",);
             }
-        };
+        }
         let roms = temp_sensors
             .roms()
-            .map(|x| format!("0x{}", (x & 0x00ffffff_ffffffff) >> 8))
+            .map(|x| SensorId::from_rom(x).to_string())
             .collect::<Vec<_>>();
         let roms = roms.join(", ");
         log::info!("[TMP] {lpath}> Roms enumerated: {roms}",);
-        if !no_overdrive {
+        if self.overdrive {
             log::info!("[TMP] {lpath}> Enabling overdrive mode",);
-            if let Err(e) = temp_sensors.enable_overdrive(&mut ds2484) {
-                log::error!("[TMP] {lpath}> Failed to enable overdrive mode: {e:?}",);
+            match temp_sensors.try_enable_overdrive(&mut ds2484, &mut delay) {
+                Ok(true) => {}
+                Ok(false) => {
+                    log::warn!(
+                        "[TMP] {lpath}> Devices not responding in overdrive mode, using standard speed",
+                    );
+                }
+                Err(e) => {
+                    log::error!("[TMP] {lpath}> Failed to enable overdrive mode: {e:?}",);
+                }
             }
-            // At this point, we SHOULD have overdrive mode enabled
-            // Do a conversion to verify
-            if let Err(e) = temp_sensors.trigger_temperature_conversion(&mut ds2484, &mut delay) {
-                match e {
-                    ds2484::OneWireError::NoDevicePresent => {
+        }
+        self.bus = Some((ds2484, temp_sensors));
+        Ok(())
+    }
+
+    fn begin_acquire(&mut self) -> Result<Option<Instant>, String> {
+        let Some((ds2484, temp_sensors)) = self.bus.as_mut() else {
+            return Err("Bus not initialized".into());
+        };
+        // Trigger temperature conversion, which the devices complete on their own
+        let session = temp_sensors
+            .begin_conversion(ds2484)
+            .map_err(|e| format!("Failed to trigger temperature conversion: {e:?}"))?;
+        let ready = session.ready_at(Instant::now());
+        self.conversion = Some(ready);
+        Ok(Some(ready))
+    }
+
+    fn acquire(&mut self) -> Result<Vec<Readings>, String> {
+        let ready = match self.conversion.take() {
+            Some(ready) => ready,
+            None => self.begin_acquire()?.unwrap_or_else(Instant::now),
+        };
+        let lpath = self.path.to_string_lossy();
+        let Some((ds2484, temp_sensors)) = self.bus.as_mut() else {
+            return Err("Bus not initialized".into());
+        };
+        // Wait for the conversion to complete, if the scheduler has not
+        std::thread::sleep(ready.saturating_duration_since(Instant::now()));
+        // Read out every device, keeping track of the ones that failed despite the retries
+        let mut outcomes = Vec::new();
+        let data = temp_sensors
+            .read_temperatures_iter(ds2484, true)
+            .filter_map(|(rom, temp)| {
+                let id = SensorId::from_rom(rom).value();
+                if !self.filter.selects_rom(rom, self.sensors.label(id)) {
+                    log::warn!(
+                        sensor:% = format_args!("{id:08x}");
+                        "[TMP] {lpath}> Excluding sensor with ID {id:08x} from readout"
+                    );
+                    return None; // skip excluded sensors
+                }
+                outcomes.push((id, temp.is_ok()));
+                match temp {
+                    Ok(temp) => Some((id, self.sensors.calibrate(id, temp.celsius()))),
+                    Err(e) => {
                         log::warn!(
-                            "[TMP] {lpath}> No device present on the bus in overdrive mode, disabling overdrive",
+                            sensor:% = format_args!("{id:08x}");
+                            "[TMP] {lpath}> Failed to read sensor with ID {id:08x}: {e:?}"
                         );
-                        if let Err(e) = temp_sensors.disable_overdrive(&mut ds2484) {
-                            log::error!("[TMP] {lpath}> Failed to disable overdrive mode: {e:?}",);
-                        } else {
-                            log::info!("[TMP] {lpath}> Overdrive mode disabled successfully",);
-                        }
-                    }
-                    _ => {
-                        log::error!("[TMP] {lpath}> Failed to trigger temperature conversion: {e:?}",);
+                        #[cfg(feature = "metrics")]
+                        crate::metrics::read_error(&lpath, id);
+                        None
                     }
                 }
+            })
+            .collect::<Vec<_>>();
+        if self.print {
+            let mut msg = String::new();
+            for (id, temp) in &data {
+                msg.push_str(&format!("{id:08x}: {temp:.2} °C, "));
             }
+            log::info!("[TMP] {lpath}> {msg}");
         }
-        // Do a readout
-        'readout: while running.load(Ordering::Relaxed) {
-            // Timekeeping
-            let start = Instant::now();
-            // Trigger temperature conversion
-            if let Err(e) = temp_sensors.trigger_temperature_conversion(&mut ds2484, &mut delay) {
-                log::error!("[TMP] {lpath}> Failed to trigger temperature conversion: {e:?}",);
-                thread::sleep(Duration::from_secs(1));
-                continue 'root;
+        self.health.update(outcomes);
+        let mut data = vec![Readings::Temperature(data)];
+        data.extend(self.health.report(Instant::now()));
+        Ok(data)
+    }
+
+    fn command(&mut self, command: &Command) -> Result<String, String> {
+        let lpath = self.path.to_string_lossy();
+        let Some((ds2484, temp_sensors)) = self.bus.as_mut() else {
+            return Err("bus not initialized".into());
+        };
+        match command {
+            Command::List => Ok(temp_sensors
+                .roms()
+                .map(|rom| {
+                    let id = SensorId::from_rom(rom).value();
+                    let excluded = if !self.filter.selects_rom(rom, self.sensors.label(id)) {
+                        " (excluded)"
+                    } else {
+                        ""
+                    };
+                    format!("{id:08x}{excluded}")
+                })
+                .collect::<Vec<_>>()
+                .join(",")),
+            Command::SetLed(id, on) => {
+                let rom = temp_sensors
+                    .roms()
+                    .find(|rom| SensorId::from_rom(*rom).value() == *id)
+                    .ok_or_else(|| format!("no sensor with ID {id:08x}"))?;
+                temp_sensors
+                    .led_toggle(ds2484, rom, *on)
+                    .map_err(|e| format!("failed to toggle LED: {e:?}"))?;
+                log::info!(
+                    "[TMP] {lpath}> LED of sensor {id:08x} turned {}",
+                    if *on { "on" } else { "off" }
+                );
+                Ok(format!("led {id:08x} {}", if *on { "on" } else { "off" }))
             }
-            // Wait for the conversion to complete
-            let readout = match temp_sensors.read_temperatures(&mut ds2484, false, true) {
-                Ok(readout) => readout,
-                Err(e) => {
-                    log::error!("[TMP] {lpath}> Failed to read temperatures: {e:?}",);
-                    thread::sleep(Duration::from_secs(1));
-                    continue 'readout;
+            Command::SetOverdrive(on) => {
+                let active = if *on {
+                    temp_sensors.try_enable_overdrive(ds2484, &mut Delay)
+                } else {
+                    temp_sensors.disable_overdrive(ds2484).map(|_| false)
                 }
-            };
-            // Send the readout data here
-            let data =
-                readout
-                    .iter()
-                    .filter_map(|(id, temp)| {
-                        let id = crc32fast::hash(&((id & 0x00ffffff_ffffffff) >> 8).to_le_bytes()); // strip the CRC and the family code bytes, and convert to u32 by calculating the CRC32 hash of the serial number bytes
-                        if exclude.contains(&id) {
-                            log::warn!(
-                                "[TMP] {lpath}> Excluding sensor with ID {id:08x} from readout",
-                            );
-                            None // skip excluded sensors
-                        } else {
-                            let temp = f32::from(*temp);
-                            Some((id, temp))
-                        }
-                    })
-                    .collect::<Vec<_>>();
-            if print {
-                let mut msg = String::new();
-                for (id, temp) in &data {
-                    msg.push_str(&format!("{id:08x}: {temp:.2} °C, "));
+                .map_err(|e| format!("failed to set overdrive mode: {e:?}"))?;
+                self.overdrive = *on;
+                if *on && !active {
+                    return Err(
+                        "devices not responding in overdrive mode, using standard speed".into(),
+                    );
                 }
-                log::info!("[TMP] {lpath}> {msg}");
+                log::info!(
+                    "[TMP] {lpath}> Overdrive mode {}",
+                    if *on { "enabled" } else { "disabled" }
+                );
+                Ok(format!("overdrive {}", if *on { "on" } else { "off" }))
             }
-            if let Err(e) = sink.send(Measurement::Temperature(data)) {
-                log::error!("[TMP] {lpath}> Failed to send data: {e:?}",);
-                continue 'readout; // probably the receiver has been dropped, meaning we are leaving
+            Command::SetExclusions(patterns) => {
+                self.filter.set_exclude(patterns.clone());
+                log::info!(
+                    "[TMP] {lpath}> Excluding sensors: {}",
+                    patterns
+                        .iter()
+                        .map(ToString::to_string)
+                        .collect::<Vec<_>>()
+                        .join(",")
+                );
+                Ok(format!("exclude {} patterns", patterns.len()))
             }
-            // wait so that there is 1 second interval between measurements
-            let dur = start.elapsed();
-            if dur.as_secs_f32() < 1.0 {
-                thread::sleep(Duration::from_secs_f32(1.0 - dur.as_secs_f32()));
+            Command::SetPollInterval(_) | Command::Enumerate | Command::Heat(..) => {
+                Err("unsupported command".into())
             }
         }
     }
-    log::info!("[TMP] {lpath}> Exiting thread",);
 }
@@ -0,0 +1,136 @@
+//! Temperatures exposed by the Linux kernel under `/sys/class/hwmon` and `/sys/class/thermal`,
+//! read without going through `sysinfo`, whose component list is empty on some minimal images.
+use std::{fs, path::Path};
+
+/// Root of the sysfs device classes.
+pub const SYSFS_CLASS: &str = "/sys/class";
+
+/// A temperature channel exposed by the kernel.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Zone {
+    /// Name of the channel, stable across boots: the name of the hwmon device followed by the
+    /// label of the channel, e.g. `nvme Composite` or `cpu_thermal temp1`, or the type of the
+    /// thermal zone, e.g. `cpu-thermal`.
+    pub name: String,
+    /// Temperature in millidegrees Celsius.
+    pub millidegrees: i32,
+}
+
+/// Read all temperature channels under `root`, usually [`SYSFS_CLASS`].
+///
+/// Thermal zones that are also registered as a hwmon device, which the kernel names after the
+/// zone type with dashes replaced by underscores, are only reported once, through hwmon.
+/// Channels that can not be read, e.g. because the sensor is asleep, are left out.
+pub fn read(root: &Path) -> Vec<Zone> {
+    let mut zones = Vec::new();
+    let mut hwmon_names = Vec::new();
+    for dir in entries(&root.join("hwmon"), "hwmon") {
+        let Some(device) = read_trimmed(&dir.join("name")) else {
+            continue;
+        };
+        let mut channels = fs::read_dir(&dir)
+            .into_iter()
+            .flatten()
+            .flatten()
+            .filter_map(|entry| {
+                let file = entry.file_name().into_string().ok()?;
+                file.strip_prefix("temp")?
+                    .strip_suffix("_input")?
+                    .parse::<u32>()
+                    .ok()
+            })
+            .collect::<Vec<_>>();
+        channels.sort_unstable();
+        for channel in channels {
+            let Some(millidegrees) = read_millidegrees(&dir.join(format!("temp{channel}_input")))
+            else {
+                continue;
+            };
+            let label = read_trimmed(&dir.join(format!("temp{channel}_label")))
+                .unwrap_or_else(|| format!("temp{channel}"));
+            zones.push(Zone {
+                name: format!("{device} {label}"),
+                millidegrees,
+            });
+        }
+        hwmon_names.push(device);
+    }
+    for dir in entries(&root.join("thermal"), "thermal_zone") {
+        let Some(kind) = read_trimmed(&dir.join("type")) else {
+            continue;
+        };
+        if hwmon_names.contains(&kind.replace('-', "_")) {
+            continue;
+        }
+        if let Some(millidegrees) = read_millidegrees(&dir.join("temp")) {
+            zones.push(Zone {
+                name: kind,
+                millidegrees,
+            });
+        }
+    }
+    zones
+}
+
+/// Entries of `dir` whose name starts with `prefix`, sorted by name.
+fn entries(dir: &Path, prefix: &str) -> Vec<std::path::PathBuf> {
+    let mut entries = fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter(|entry| entry.file_name().to_string_lossy().starts_with(prefix))
+        .map(|entry| entry.path())
+        .collect::<Vec<_>>();
+    entries.sort();
+    entries
+}
+
+fn read_trimmed(path: &Path) -> Option<String> {
+    let content = fs::read_to_string(path).ok()?;
+    let content = content.trim();
+    (!content.is_empty()).then(|| content.to_string())
+}
+
+fn read_millidegrees(path: &Path) -> Option<i32> {
+    read_trimmed(path)?.parse().ok()
+}
+
+mod test {
+    #[test]
+    fn test_read() {
+        use super::{Zone, read};
+        use std::fs;
+        let root = std::env::temp_dir().join(format!("piccthermo-sysfs-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        let files = [
+            ("hwmon/hwmon0/name", "cpu_thermal\n"),
+            ("hwmon/hwmon0/temp1_input", "48312\n"),
+            ("hwmon/hwmon1/name", "nvme\n"),
+            ("hwmon/hwmon1/temp1_input", "35850\n"),
+            ("hwmon/hwmon1/temp1_label", "Composite\n"),
+            ("hwmon/hwmon1/temp2_input", ""),
+            ("thermal/thermal_zone0/type", "cpu-thermal\n"),
+            ("thermal/thermal_zone0/temp", "48312\n"),
+            ("thermal/thermal_zone1/type", "gpu\n"),
+            ("thermal/thermal_zone1/temp", "-1500\n"),
+        ];
+        for (path, content) in files {
+            let path = root.join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, content).unwrap();
+        }
+        let zone = |name: &str, millidegrees| Zone {
+            name: name.into(),
+            millidegrees,
+        };
+        assert_eq!(
+            read(&root),
+            [
+                zone("cpu_thermal temp1", 48312),
+                zone("nvme Composite", 35850),
+                zone("gpu", -1500),
+            ]
+        );
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,60 @@
+use std::{
+    sync::atomic::{AtomicU32, Ordering},
+    time::{Instant, SystemTime, UNIX_EPOCH},
+};
+
+use crate::{Measurement, Readings, SyncMarker};
+
+/// Source of the [`Readings::Sync`] frames, shared by the supervisor, which sends them
+/// periodically, and the serial link, which sends them on request.
+///
+/// The uptime is measured from the creation of the clock, at the start of the server.
+pub struct SyncClock {
+    start: Instant,
+    counter: AtomicU32,
+}
+
+impl SyncClock {
+    pub fn new() -> Self {
+        Self {
+            start: Instant::now(),
+            counter: AtomicU32::new(0),
+        }
+    }
+
+    /// Stamp the next sync frame with the current wall-clock time and uptime.
+    pub fn marker(&self) -> Measurement {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |t| t.as_millis() as u64);
+        let marker = SyncMarker {
+            uptime_ms: self.start.elapsed().as_millis() as u64,
+            counter: self.counter.fetch_add(1, Ordering::Relaxed),
+        };
+        Measurement::new("sync".into(), Readings::Sync(marker), timestamp)
+    }
+}
+
+mod test {
+    #[test]
+    fn test_sync_clock() {
+        use super::SyncClock;
+        use crate::{Readings, SyncMarker};
+        let clock = SyncClock::new();
+        let markers = [clock.marker(), clock.marker()];
+        let [
+            Readings::Sync(SyncMarker {
+                uptime_ms: first,
+                counter: 0,
+            }),
+            Readings::Sync(SyncMarker {
+                uptime_ms: second,
+                counter: 1,
+            }),
+        ] = markers.map(|m| m.readings)
+        else {
+            panic!("expected two consecutive sync markers");
+        };
+        assert!(first <= second);
+    }
+}
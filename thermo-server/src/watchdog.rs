@@ -0,0 +1,52 @@
+use std::{
+    sync::atomic::{AtomicBool, AtomicU64, Ordering},
+    time::{Duration, Instant},
+};
+
+/// Liveness of an acquisition thread, shared with the supervisor in `main`.
+///
+/// The thread calls [`Heartbeat::beat`] every time a call into its backend returns. If the
+/// thread is wedged, e.g. in a read from an I2C adapter that never completes, the heartbeat
+/// expires and the supervisor abandons the thread with [`Heartbeat::stop`] and starts a new one.
+pub struct Heartbeat {
+    epoch: Instant,
+    /// Time allowed on top of the poll interval for the thread to return from its backend.
+    window: Duration,
+    /// Deadline of the next beat, in milliseconds since `epoch`.
+    deadline: AtomicU64,
+    stopped: AtomicBool,
+}
+
+impl Heartbeat {
+    /// Create a heartbeat expecting its first beat within `interval` and `window`.
+    pub fn new(interval: Duration, window: Duration) -> Self {
+        Self {
+            epoch: Instant::now(),
+            window,
+            deadline: AtomicU64::new((interval + window).as_millis() as u64),
+            stopped: AtomicBool::new(false),
+        }
+    }
+
+    /// Expect the next beat within `interval` and the window of the heartbeat.
+    pub fn beat(&self, interval: Duration) {
+        let deadline = self.epoch.elapsed() + interval + self.window;
+        self.deadline
+            .store(deadline.as_millis() as u64, Ordering::Relaxed);
+    }
+
+    /// Returns `true` if the deadline of the next beat has passed.
+    pub fn expired(&self) -> bool {
+        self.epoch.elapsed().as_millis() as u64 > self.deadline.load(Ordering::Relaxed)
+    }
+
+    /// Ask the thread to exit as soon as it is unblocked.
+    pub fn stop(&self) {
+        self.stopped.store(true, Ordering::Relaxed);
+    }
+
+    /// Returns `true` if the thread has been abandoned by the supervisor.
+    pub fn stopped(&self) -> bool {
+        self.stopped.load(Ordering::Relaxed)
+    }
+}
@@ -0,0 +1,240 @@
+use std::time::{Duration, Instant};
+
+use ds28ea00::{Ds28ea00Group, Family, ReadoutResolution, SortOrder};
+use ds2484::Ds2484;
+use embedded_onewire::OneWire;
+use linux_embedded_hal::{Delay, I2cdev};
+use piccthermo_id::SensorFilter;
+
+use crate::{Output, OutputFormat, rom_hash};
+
+/// Timings of the runs at one resolution in one bus mode.
+struct Timings {
+    resolution: ReadoutResolution,
+    overdrive: bool,
+    /// Runs that failed, e.g. because no device answered the reset.
+    failed: usize,
+    sensors: usize,
+    enumerate: Vec<Duration>,
+    trigger: Vec<Duration>,
+    read: Vec<Duration>,
+    sensor_read: Vec<Duration>,
+}
+
+impl Timings {
+    fn new(resolution: ReadoutResolution, overdrive: bool) -> Self {
+        Self {
+            resolution,
+            overdrive,
+            failed: 0,
+            sensors: 0,
+            enumerate: Vec::new(),
+            trigger: Vec::new(),
+            read: Vec::new(),
+            sensor_read: Vec::new(),
+        }
+    }
+
+    fn mode(&self) -> &'static str {
+        if self.overdrive {
+            "overdrive"
+        } else {
+            "standard"
+        }
+    }
+
+    /// Median enumerate, trigger, per-sensor read and whole-group read times.
+    fn medians(&self) -> [Duration; 4] {
+        [
+            &self.enumerate,
+            &self.trigger,
+            &self.sensor_read,
+            &self.read,
+        ]
+        .map(|times| {
+            let mut times = times.clone();
+            times.sort();
+            times
+                .get(times.len().saturating_sub(1) / 2)
+                .copied()
+                .unwrap_or_default()
+        })
+    }
+}
+
+/// Timings of a benchmark, in the order the resolutions were given, standard mode first.
+pub struct Benchmark {
+    timings: Vec<Timings>,
+}
+
+impl Benchmark {
+    /// Print the comparison table, as free-form text or as one row per resolution and mode.
+    ///
+    /// In CSV, the rows form a separate table with its own header, printed after the
+    /// enumeration results.
+    pub fn report(&self, out: &Output) {
+        match out.format {
+            OutputFormat::Human => println!(
+                "{:>10} {:>9} {:>5} {:>7} {:>7} {:>14} {:>12} {:>16} {:>15}",
+                "resolution",
+                "mode",
+                "runs",
+                "failed",
+                "sensors",
+                "enumerate (ms)",
+                "trigger (ms)",
+                "sensor read (ms)",
+                "group read (ms)"
+            ),
+            OutputFormat::Json => {}
+            OutputFormat::Csv => {
+                println!();
+                println!(
+                    "event,resolution,mode,runs,failed,sensors,enumerate_us,trigger_us,sensor_read_us,group_read_us"
+                );
+            }
+        }
+        for timings in &self.timings {
+            let runs = timings.read.len();
+            let [enumerate, trigger, sensor_read, read] = timings.medians();
+            let ms = |d: Duration| d.as_secs_f64() * 1000.0;
+            match out.format {
+                OutputFormat::Human => println!(
+                    "{:>10} {:>9} {runs:>5} {:>7} {:>7} {:>14.3} {:>12.3} {:>16.3} {:>15.3}",
+                    timings.resolution.bits(),
+                    timings.mode(),
+                    timings.failed,
+                    timings.sensors,
+                    ms(enumerate),
+                    ms(trigger),
+                    ms(sensor_read),
+                    ms(read)
+                ),
+                OutputFormat::Json => println!(
+                    "{}",
+                    serde_json::json!({
+                        "event": "benchmark",
+                        "resolution": timings.resolution.bits(),
+                        "mode": timings.mode(),
+                        "runs": runs,
+                        "failed": timings.failed,
+                        "sensors": timings.sensors,
+                        "enumerate_us": enumerate.as_micros() as u64,
+                        "trigger_us": trigger.as_micros() as u64,
+                        "sensor_read_us": sensor_read.as_micros() as u64,
+                        "group_read_us": read.as_micros() as u64,
+                    })
+                ),
+                OutputFormat::Csv => println!(
+                    "benchmark,{},{},{runs},{},{},{},{},{},{}",
+                    timings.resolution.bits(),
+                    timings.mode(),
+                    timings.failed,
+                    timings.sensors,
+                    enumerate.as_micros(),
+                    trigger.as_micros(),
+                    sensor_read.as_micros(),
+                    read.as_micros()
+                ),
+            }
+        }
+    }
+}
+
+/// Time `runs` enumerations and readouts of the bus at every resolution, alternating standard and
+/// overdrive runs so that both modes see the same conditions on the bus.
+///
+/// The times are the medians of the runs. The per-sensor read time is the whole-group read time
+/// divided by the number of sensors read, leaving out the excluded sensors.
+pub fn benchmark(
+    ds2484: &mut Ds2484<&mut I2cdev, &mut Delay>,
+    delay: &mut Delay,
+    resolutions: &[ReadoutResolution],
+    runs: usize,
+    filter: &SensorFilter,
+) -> Benchmark {
+    let mut timings = Vec::new();
+    for &resolution in resolutions {
+        let mut modes = [false, true].map(|overdrive| Timings::new(resolution, overdrive));
+        for _ in 0..runs {
+            for timings in modes.iter_mut() {
+                if let Err(e) = run(ds2484, delay, timings, filter) {
+                    log::warn!(
+                        "Benchmark run at {} bits in {} mode failed: {e}",
+                        resolution.bits(),
+                        timings.mode()
+                    );
+                    timings.failed += 1;
+                }
+            }
+        }
+        timings.extend(modes);
+    }
+    if let Err(e) = ds2484.set_overdrive_mode(false) {
+        log::warn!("Failed to disable overdrive mode: {e:?}");
+    }
+    Benchmark { timings }
+}
+
+/// Enumerate, convert and read out the bus once in the mode and at the resolution of `timings`.
+fn run(
+    ds2484: &mut Ds2484<&mut I2cdev, &mut Delay>,
+    delay: &mut Delay,
+    timings: &mut Timings,
+    filter: &SensorFilter,
+) -> Result<(), String> {
+    let mut group = Ds28ea00Group::<16>::default()
+        .with_resolution(timings.resolution)
+        .with_t_low(-40)
+        .with_t_high(50)
+        .with_families(&[Family::Ds28ea00, Family::Ds18b20, Family::Ds1822])
+        .with_sort_order(SortOrder::Key(rom_hash));
+    let res = if timings.overdrive {
+        group.enable_overdrive(ds2484)
+    } else {
+        group.disable_overdrive(ds2484)
+    };
+    res.map_err(|e| format!("Failed to switch the bus mode: {e:?}"))?;
+    let start = Instant::now();
+    group
+        .enumerate_filtered(ds2484, |rom| filter.selects_rom(rom, None))
+        .map_err(|e| format!("Failed to enumerate devices: {e:?}"))?;
+    let after_enumerate = Instant::now();
+    group
+        .trigger_temperature_conversion(ds2484, delay)
+        .map_err(|e| format!("Failed to trigger temperature conversion: {e:?}"))?;
+    let after_conversion = Instant::now();
+    let read = group
+        .read_temperatures_detailed(ds2484, true)
+        .filter(|(_, res)| res.is_ok())
+        .count();
+    let after_reading = Instant::now();
+    let group_read = after_reading.duration_since(after_conversion);
+    timings.sensors = timings.sensors.max(read);
+    timings
+        .enumerate
+        .push(after_enumerate.duration_since(start));
+    timings
+        .trigger
+        .push(after_conversion.duration_since(after_enumerate));
+    timings.read.push(group_read);
+    timings.sensor_read.push(group_read / read.max(1) as u32);
+    Ok(())
+}
+
+mod test {
+    #[test]
+    fn test_medians() {
+        use super::Timings;
+        use ds28ea00::ReadoutResolution;
+        use std::time::Duration;
+        let mut timings = Timings::new(ReadoutResolution::Resolution9bit, true);
+        assert_eq!(timings.medians(), [Duration::ZERO; 4]);
+        let ms = Duration::from_millis;
+        timings.enumerate = vec![ms(3), ms(1), ms(2)];
+        timings.trigger = vec![ms(94), ms(95)];
+        timings.read = vec![ms(8)];
+        timings.sensor_read = vec![ms(2)];
+        assert_eq!(timings.medians(), [ms(2), ms(94), ms(2), ms(8)]);
+    }
+}
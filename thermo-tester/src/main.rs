@@ -1,24 +1,166 @@
-use std::f32;
+use std::{fmt::Display, time::Duration};
 
-use clap::Parser;
-use ds28ea00::Ds28ea00Group;
+mod benchmark;
+mod soak;
+
+use clap::{Parser, ValueEnum};
+use ds28ea00::{
+    CrcPolicy, Ds28ea00Group, ErrorPolicy, Family, ReadOptions, ReadoutResolution, SortOrder,
+};
 use ds2484::{Ds2484, Interact};
-use embedded_onewire::OneWireStatus;
+use embedded_onewire::{OneWireCrc, OneWireStatus};
 use linux_embedded_hal::{Delay, I2cdev};
+use piccthermo_core::{SensorDriver, Temperature, TemperatureSensor, sim::SimulatedSensor};
+use piccthermo_id::{SensorFilter, SensorId, parse_patterns};
 
 /// Simple program to greet a person
 #[derive(Parser, Debug)]
 #[command(version, about, long_about = None)]
 struct Args {
     /// Path to I2C bus (e.g., /dev/i2c-1)
-    #[arg(short, long)]
-    path: String,
+    #[arg(short, long, required_unless_present = "simulate")]
+    path: Option<String>,
     /// Read temperatures from the sensors
     #[arg(long, default_value = "false")]
     read: bool,
-    /// Exclusion filter
+    /// Sensors to leave out of the readout, as comma separated ROM codes or IDs
     #[arg(long, default_value_t = String::from(""))]
     exclude: String,
+    /// Only read these sensors, as comma separated ROM codes or IDs
+    #[arg(long, default_value_t = String::from(""))]
+    include_only: String,
+    /// Run conversions back to back for this long (e.g. `10m`), half of the time in overdrive
+    /// and half in standard mode, and report per-sensor statistics at the end
+    #[arg(long, value_parser = humantime::parse_duration)]
+    soak: Option<Duration>,
+    /// Enumerate and read out the bus in standard and overdrive mode in turn, and report the
+    /// median timings of every resolution and mode
+    #[arg(long, default_value_t = false, conflicts_with = "soak")]
+    benchmark: bool,
+    /// Resolutions of the benchmark in bits, comma separated
+    #[arg(
+        long,
+        requires = "benchmark",
+        value_delimiter = ',',
+        default_value = "9,10,11,12",
+        value_parser = clap::value_parser!(u8).range(9..=12)
+    )]
+    benchmark_resolutions: Vec<u8>,
+    /// Runs of the benchmark per resolution and mode
+    #[arg(long, requires = "benchmark", default_value_t = 10)]
+    benchmark_runs: usize,
+    /// Output format of the enumeration and readout results
+    #[arg(long, value_enum, default_value_t = OutputFormat::Human)]
+    output: OutputFormat,
+    /// Read synthetic sensors instead of the sensors on the bus. The soak test runs in a single
+    /// mode
+    #[arg(long, default_value_t = false)]
+    simulate: bool,
+    /// Number of synthetic sensors, at most 16
+    #[arg(long, requires = "simulate", default_value_t = 4)]
+    sim_sensors: usize,
+    /// Largest step of the random walk of a synthetic reading per conversion
+    #[arg(long, requires = "simulate", default_value_t = 0.05)]
+    sim_drift: f32,
+    /// Largest noise added to a synthetic reading
+    #[arg(long, requires = "simulate", default_value_t = 0.1)]
+    sim_noise: f32,
+    /// Probability, between 0 and 1, that a synthetic sensor is missing from a readout
+    #[arg(long, requires = "simulate", default_value_t = 0.0)]
+    sim_dropout: f32,
+}
+
+/// In the machine readable formats, every enumerated or read out sensor is printed as a row,
+/// and the other messages go to the standard error.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum OutputFormat {
+    /// Free-form text
+    Human,
+    /// One JSON object per line
+    Json,
+    /// CSV with a header
+    Csv,
+}
+
+/// A sensor, as printed in the machine readable formats.
+struct Row<'a> {
+    /// `enumerate` or `readout`.
+    event: &'a str,
+    rom: u64,
+    hash: u32,
+    excluded: bool,
+    overdrive: bool,
+    temperature: Option<f32>,
+    conversion: Option<Duration>,
+    read: Option<Duration>,
+}
+
+struct Output {
+    format: OutputFormat,
+    resolution: ReadoutResolution,
+}
+
+impl Output {
+    /// Print a free-form message.
+    fn info(&self, msg: impl Display) {
+        if self.format == OutputFormat::Human {
+            println!("{msg}");
+        } else {
+            eprintln!("{msg}");
+        }
+    }
+
+    fn header(&self) {
+        if self.format == OutputFormat::Csv {
+            println!("event,rom,hash,excluded,resolution,mode,temperature,conversion_us,read_us");
+        }
+    }
+
+    fn row(&self, row: &Row) {
+        let mode = if row.overdrive {
+            "overdrive"
+        } else {
+            "standard"
+        };
+        match self.format {
+            OutputFormat::Human => {}
+            OutputFormat::Json => println!(
+                "{}",
+                serde_json::json!({
+                    "event": row.event,
+                    "rom": format!("0x{:016x}", row.rom),
+                    "hash": format!("0x{:08x}", row.hash),
+                    "excluded": row.excluded,
+                    "resolution": self.resolution.bits(),
+                    "mode": mode,
+                    "temperature": row.temperature,
+                    "conversion_us": row.conversion.map(|d| d.as_micros() as u64),
+                    "read_us": row.read.map(|d| d.as_micros() as u64),
+                })
+            ),
+            OutputFormat::Csv => println!(
+                "{},0x{:016x},0x{:08x},{},{},{mode},{},{},{}",
+                row.event,
+                row.rom,
+                row.hash,
+                row.excluded,
+                self.resolution.bits(),
+                row.temperature
+                    .map(|t| format!("{t:.4}"))
+                    .unwrap_or_default(),
+                row.conversion
+                    .map(|d| d.as_micros().to_string())
+                    .unwrap_or_default(),
+                row.read
+                    .map(|d| d.as_micros().to_string())
+                    .unwrap_or_default(),
+            ),
+        }
+    }
+}
+
+fn rom_hash(rom: u64) -> u32 {
+    SensorId::from_rom(rom).value()
 }
 
 fn main() {
@@ -27,25 +169,106 @@ fn main() {
     // Parse command line arguments
     let args = Args::parse();
     // Exclusion filter
-    let mut exclude = Vec::new();
-    if !args.exclude.is_empty() {
-        for item in args.exclude.split(',') {
-            let item = item.trim().split("0x").last().unwrap_or(item);
-            if let Ok(num) = u32::from_str_radix(item, 16) {
-                exclude.push(num);
-            } else {
-                log::warn!("[EXC] Invalid exclusion filter item: {item}");
-            }
-        }
-        log::info!("[EXC] Exclusion filter: {exclude:#?}");
+    let patterns = |list: &str| {
+        parse_patterns(list).unwrap_or_else(|e| {
+            log::error!("[EXC] Invalid sensor pattern in {list}: {e}");
+            std::process::exit(1);
+        })
+    };
+    let filter = SensorFilter::new()
+        .with_include_only(patterns(&args.include_only))
+        .with_exclude(patterns(&args.exclude));
+    if !filter.is_empty() {
+        log::info!("[EXC] Sensor filter: {filter:#?}");
     } else {
         log::info!("[EXC] No exclusion filter set.");
     }
-    init(args.path, args.read, exclude);
+    let out = Output {
+        format: args.output,
+        resolution: ReadoutResolution::Resolution12bit,
+    };
+    match args.path {
+        Some(ref path) if !args.simulate => init(path.clone(), &args, &filter, &out),
+        _ => simulate(&args, &filter, &out),
+    }
+}
+
+/// Enumerate and read synthetic DS28EA00 sensors, through the same traits as the driver.
+fn simulate(args: &Args, filter: &SensorFilter, out: &Output) {
+    out.info("Simulating bus");
+    let mut sim = SimulatedSensor::<16>::new(0)
+        .with_channels(args.sim_sensors)
+        .with_first_id(1)
+        .with_drift(args.sim_drift)
+        .with_noise(args.sim_noise)
+        .with_dropout(args.sim_dropout)
+        .with_conversion_time(out.resolution.conversion_time());
+    // ROM codes with the DS28EA00 family code and the channel ID as serial number
+    let rom = |id: u64| {
+        let rom = id << 8 | Family::Ds28ea00.code() as u64;
+        let mut crc = OneWireCrc::default();
+        for byte in &rom.to_le_bytes()[..7] {
+            crc.update(*byte);
+        }
+        rom | (crc.value() as u64) << 56
+    };
+    let roms = sim.ids().map(rom).collect::<Vec<_>>();
+    out.header();
+    out.info("Enumerated devices: ");
+    for &rom in &roms {
+        let hash = rom_hash(rom);
+        out.info(format!(
+            "\t0x{rom:016x} -> 0x{hash:08x} [Excluded: {}]",
+            !filter.selects_rom(rom, None)
+        ));
+        out.row(&Row {
+            event: "enumerate",
+            rom,
+            hash,
+            excluded: !filter.selects_rom(rom, None),
+            overdrive: false,
+            temperature: None,
+            conversion: None,
+            read: None,
+        });
+    }
+    if args.benchmark {
+        out.info("The benchmark compares the bus modes, and needs a bus to run on");
+    } else if let Some(duration) = args.soak {
+        let report = soak::soak_sensor(&mut sim, &mut (), &roms, rom, duration, filter);
+        soak::header(out);
+        report.report(out);
+    } else if args.read {
+        for _ in 0..10 {
+            let start = std::time::Instant::now();
+            if let Err(e) = SensorDriver::<()>::trigger(&mut sim, &mut ()) {
+                out.info(format!("Failed to trigger temperature conversion: {e:?}"));
+                continue;
+            }
+            std::thread::sleep(SensorDriver::<()>::ready_after(&sim));
+            let after_conversion = std::time::Instant::now();
+            let mut readout = Vec::new();
+            if let Err(e) = TemperatureSensor::read(&mut sim, &mut (), &mut |id, temp| {
+                readout.push((rom(id), temp))
+            }) {
+                out.info(format!("Failed to read temperatures: {e:?}"));
+                continue;
+            }
+            let after_reading = std::time::Instant::now();
+            print_readout(
+                &readout,
+                false,
+                after_conversion.duration_since(start),
+                after_reading.duration_since(after_conversion),
+                filter,
+                out,
+            );
+        }
+    }
 }
 
-fn init(path: String, read: bool, exclude: Vec<u32>) {
-    println!("Opening bus {path}");
+fn init(path: String, args: &Args, filter: &SensorFilter, out: &Output) {
+    out.info(format!("Opening bus {path}"));
     // Open the I2C bus
     let mut i2c = I2cdev::new(&path).expect("Failed to open I2C device");
     let mut delay = Delay;
@@ -78,10 +301,12 @@ fn init(path: String, read: bool, exclude: Vec<u32>) {
     log::info!("Port configuration: {:?}", port_cfg);
     // Create a DS28EA00 temperature sensor group
     let mut temp_sensors = Ds28ea00Group::<16>::default()
-        .with_resolution(ds28ea00::ReadoutResolution::Resolution12bit)
+        .with_resolution(out.resolution)
         .with_t_low(-40)
         .with_t_high(50)
-        .with_toggle_pio(true);
+        .with_toggle_pio(true)
+        .with_families(&[Family::Ds28ea00, Family::Ds18b20, Family::Ds1822])
+        .with_sort_order(SortOrder::Key(rom_hash));
     let mut delay = Delay;
     // Enumerate devices on the 1-Wire bus
     let devices = temp_sensors
@@ -90,47 +315,79 @@ fn init(path: String, read: bool, exclude: Vec<u32>) {
     log::info!("Found {} devices", devices);
     let roms = temp_sensors
         .roms()
-        .map(|rom| {
-            let romcode = (rom & 0x00ffffff_ffffffff) >> 8;
-            let romhash = crc32fast::hash(&romcode.to_le_bytes());
-            (rom, romhash)
-        })
+        .map(|rom| (rom, rom_hash(rom)))
         .collect::<Vec<_>>();
-    println!("Enumerated devices: ");
+    out.header();
+    out.info("Enumerated devices: ");
     for (rom, hash) in roms {
-        println!(
+        out.info(format!(
             "\t0x{rom:016x} -> 0x{hash:08x} [Excluded: {}]",
-            exclude.contains(&hash)
-        );
+            !filter.selects_rom(rom, None)
+        ));
+        out.row(&Row {
+            event: "enumerate",
+            rom,
+            hash,
+            excluded: !filter.selects_rom(rom, None),
+            overdrive: temp_sensors.overdrive(),
+            temperature: None,
+            conversion: None,
+            read: None,
+        });
+    }
+    if args.benchmark {
+        let resolutions = args
+            .benchmark_resolutions
+            .iter()
+            .map(|bits| match bits {
+                9 => ReadoutResolution::Resolution9bit,
+                10 => ReadoutResolution::Resolution10bit,
+                11 => ReadoutResolution::Resolution11bit,
+                _ => ReadoutResolution::Resolution12bit,
+            })
+            .collect::<Vec<_>>();
+        benchmark::benchmark(
+            &mut ds2484,
+            &mut delay,
+            &resolutions,
+            args.benchmark_runs,
+            filter,
+        )
+        .report(out);
+        return;
     }
     if let Err(e) = temp_sensors.enable_overdrive(&mut ds2484) {
-        println!("Failed to enable overdrive mode: {e:?}");
+        out.info(format!("Failed to enable overdrive mode: {e:?}"));
     };
+    let mut reports = Vec::new();
     let mut status = ds2484::DeviceConfiguration::default();
     // Read the device configuration
     status
         .read(&mut ds2484)
         .expect("Failed to read device configuration");
-    println!("Device configuration: {:?}", status);
+    out.info(format!("Device configuration: {:?}", status));
     let mut status = ds2484::DeviceStatus::default();
     status
         .read(&mut ds2484)
         .expect("Failed to read device status");
-    println!("Device status: {:?}", status);
+    out.info(format!("Device status: {:?}", status));
     if !status.presence() {
-        println!("No devices are present after enabling overdrive mode.");
-    } else if read {
+        out.info("No devices are present after enabling overdrive mode.");
+    } else if let Some(duration) = args.soak {
+        reports.push(soak::soak(
+            &mut temp_sensors,
+            &mut ds2484,
+            &mut delay,
+            duration / 2,
+            filter,
+        ));
+    } else if args.read {
         for _ in 0..10 {
-            read_sensors(
-                &mut temp_sensors,
-                &mut ds2484,
-                &mut delay,
-                exclude.as_slice(),
-            )
-            .expect("Failed to read sensors");
+            read_sensors(&mut temp_sensors, &mut ds2484, &mut delay, filter, out)
+                .expect("Failed to read sensors");
         }
     }
-    println!("Disabling overdrive mode...");
+    out.info("Disabling overdrive mode...");
     temp_sensors
         .disable_overdrive(&mut ds2484)
         .expect("Failed to disable overdrive mode");
@@ -138,16 +395,25 @@ fn init(path: String, read: bool, exclude: Vec<u32>) {
         .read(&mut ds2484)
         .expect("Failed to read device status");
     if !status.presence() {
-        println!("No devices are present after disabling overdrive mode!");
-    } else if read {
+        out.info("No devices are present after disabling overdrive mode!");
+    } else if let Some(duration) = args.soak {
+        reports.push(soak::soak(
+            &mut temp_sensors,
+            &mut ds2484,
+            &mut delay,
+            duration / 2,
+            filter,
+        ));
+    } else if args.read {
         for _ in 0..10 {
-            read_sensors(
-                &mut temp_sensors,
-                &mut ds2484,
-                &mut delay,
-                exclude.as_slice(),
-            )
-            .expect("Failed to read sensors");
+            read_sensors(&mut temp_sensors, &mut ds2484, &mut delay, filter, out)
+                .expect("Failed to read sensors");
+        }
+    }
+    if !reports.is_empty() {
+        soak::header(out);
+        for report in reports {
+            report.report(out);
         }
     }
 }
@@ -156,7 +422,8 @@ fn read_sensors(
     temp_sensors: &mut Ds28ea00Group<16>,
     ds2484: &mut Ds2484<&mut I2cdev, &mut Delay>,
     delay: &mut Delay,
-    exclude: &[u32],
+    filter: &SensorFilter,
+    out: &Output,
 ) -> Result<
     (),
     Box<dyn std::error::Error + Send + Sync>,
@@ -164,6 +431,7 @@ fn read_sensors(
     //     ds2484::Ds2484Error<<linux_embedded_hal::I2cdev as embedded_hal::i2c::ErrorType>::Error>,
     // >,
 > {
+    let overdrive = temp_sensors.overdrive();
     let start = std::time::Instant::now();
     temp_sensors
         .trigger_temperature_conversion(ds2484, delay)
@@ -171,37 +439,67 @@ fn read_sensors(
     let after_conversion = std::time::Instant::now();
     // Read temperatures from the sensors
     let readout = temp_sensors
-        .read_temperatures(ds2484, false, true)
+        .read_temperatures(
+            ds2484,
+            ReadOptions::new()
+                .with_crc(CrcPolicy::Skip)
+                .with_errors(ErrorPolicy::Substitute),
+        )
         .expect("Failed to read temperatures");
     let after_reading = std::time::Instant::now();
-    let output = readout
-        .iter()
-        .filter_map(|(rom, temp)| {
-            let hash = crc32fast::hash(&((rom & 0x00ffffff_ffffffff) >> 8).to_le_bytes());
-            if exclude.contains(&hash) {
-                None
-            } else {
-                Some(format!(
-                    "R{:02x}: {:.3}°C, ",
-                    rom.to_be_bytes()[0],
-                    f32::from(*temp)
-                ))
-            }
-        })
-        .collect::<Vec<_>>();
-    let output = output.join(", ");
-    println!(
-        "Mode: {}, Temperatures: {}, Conversion time: {:#?}, Read time: {:#?}",
-        {
-            if temp_sensors.overdrive() {
-                "Overdrive"
-            } else {
-                "Standard"
-            }
-        },
-        output,
+    print_readout(
+        readout,
+        overdrive,
         after_conversion.duration_since(start),
-        after_reading.duration_since(after_conversion)
+        after_reading.duration_since(after_conversion),
+        filter,
+        out,
     );
     Ok(())
 }
+
+/// Print the temperatures of a readout, leaving out the excluded sensors.
+fn print_readout(
+    readout: &[(u64, Temperature)],
+    overdrive: bool,
+    conversion: Duration,
+    read: Duration,
+    filter: &SensorFilter,
+    out: &Output,
+) {
+    for (rom, temp) in readout {
+        let hash = rom_hash(*rom);
+        if filter.selects_rom(*rom, None) {
+            out.row(&Row {
+                event: "readout",
+                rom: *rom,
+                hash,
+                excluded: false,
+                overdrive,
+                temperature: Some(temp.celsius()),
+                conversion: Some(conversion),
+                read: Some(read),
+            });
+        }
+    }
+    if out.format == OutputFormat::Human {
+        let output = readout
+            .iter()
+            .filter_map(|(rom, temp)| {
+                if !filter.selects_rom(*rom, None) {
+                    None
+                } else {
+                    Some(format!("R{:02x}: {:.3}, ", rom.to_be_bytes()[0], temp))
+                }
+            })
+            .collect::<Vec<_>>();
+        let output = output.join(", ");
+        println!(
+            "Mode: {}, Temperatures: {}, Conversion time: {:#?}, Read time: {:#?}",
+            { if overdrive { "Overdrive" } else { "Standard" } },
+            output,
+            conversion,
+            read
+        );
+    }
+}
@@ -0,0 +1,312 @@
+use std::{
+    collections::BTreeMap,
+    time::{Duration, Instant},
+};
+
+use ds28ea00::{Ds28ea00Group, ReadError, Temperature};
+use ds2484::Ds2484;
+use linux_embedded_hal::{Delay, I2cdev};
+use piccthermo_core::TemperatureSensor;
+use piccthermo_id::SensorFilter;
+
+use crate::{Output, OutputFormat, rom_hash};
+
+/// Statistics of the readouts of a single sensor.
+struct SensorStats {
+    readings: usize,
+    sum: f64,
+    min: f32,
+    max: f32,
+    crc_errors: usize,
+    presence_failures: usize,
+    other_errors: usize,
+}
+
+impl Default for SensorStats {
+    fn default() -> Self {
+        Self {
+            readings: 0,
+            sum: 0.0,
+            min: f32::INFINITY,
+            max: f32::NEG_INFINITY,
+            crc_errors: 0,
+            presence_failures: 0,
+            other_errors: 0,
+        }
+    }
+}
+
+/// Statistics of a soak test in one bus mode.
+pub struct SoakStats {
+    overdrive: bool,
+    /// Conversions that could not be started, e.g. because no device answered the reset.
+    failed_conversions: usize,
+    conversion: Vec<Duration>,
+    read: Vec<Duration>,
+    sensors: BTreeMap<u64, SensorStats>,
+}
+
+impl SoakStats {
+    fn new(overdrive: bool) -> Self {
+        Self {
+            overdrive,
+            failed_conversions: 0,
+            conversion: Vec::new(),
+            read: Vec::new(),
+            sensors: BTreeMap::new(),
+        }
+    }
+
+    fn record(
+        &mut self,
+        conversion: Duration,
+        read: Duration,
+        readout: impl Iterator<Item = (u64, Result<Temperature, ReadError>)>,
+    ) {
+        self.conversion.push(conversion);
+        self.read.push(read);
+        for (rom, res) in readout {
+            let stats = self.sensors.entry(rom).or_default();
+            match res {
+                Ok(temp) => {
+                    let temp = temp.celsius();
+                    stats.readings += 1;
+                    stats.sum += temp as f64;
+                    stats.min = stats.min.min(temp);
+                    stats.max = stats.max.max(temp);
+                }
+                Err(ReadError::InvalidCrc) => stats.crc_errors += 1,
+                Err(ReadError::NoDevicePresent) => stats.presence_failures += 1,
+                Err(_) => stats.other_errors += 1,
+            }
+        }
+    }
+
+    fn mode(&self) -> &'static str {
+        if self.overdrive {
+            "overdrive"
+        } else {
+            "standard"
+        }
+    }
+
+    /// Print the statistics, as free-form text or as one row per sensor.
+    ///
+    /// In CSV, the rows form a separate table with its own header, printed after the
+    /// enumeration results.
+    pub fn report(&self, out: &Output) {
+        let mut conversion = self.conversion.clone();
+        conversion.sort();
+        let mut read = self.read.clone();
+        read.sort();
+        out.info(format!(
+            "Soak test in {} mode: {} cycles, {} failed conversions",
+            self.mode(),
+            self.conversion.len(),
+            self.failed_conversions
+        ));
+        for (name, times) in [("Conversion", &conversion), ("Read", &read)] {
+            out.info(format!(
+                "\t{name} time: p50 {:?}, p90 {:?}, p99 {:?}, max {:?}",
+                percentile(times, 50),
+                percentile(times, 90),
+                percentile(times, 99),
+                times.last().copied().unwrap_or_default()
+            ));
+        }
+        for (rom, stats) in &self.sensors {
+            let mean = (stats.readings > 0).then(|| (stats.sum / stats.readings as f64) as f32);
+            let (min, max) = if stats.readings > 0 {
+                (Some(stats.min), Some(stats.max))
+            } else {
+                (None, None)
+            };
+            out.info(format!(
+                "\t0x{rom:016x} -> 0x{:08x}: min {}, max {}, mean {}, {} readings, {} CRC errors, {} presence failures, {} other errors",
+                rom_hash(*rom),
+                celsius(min),
+                celsius(max),
+                celsius(mean),
+                stats.readings,
+                stats.crc_errors,
+                stats.presence_failures,
+                stats.other_errors,
+            ));
+            match out.format {
+                OutputFormat::Human => {}
+                OutputFormat::Json => println!(
+                    "{}",
+                    serde_json::json!({
+                        "event": "soak",
+                        "rom": format!("0x{rom:016x}"),
+                        "hash": format!("0x{:08x}", rom_hash(*rom)),
+                        "resolution": out.resolution.bits(),
+                        "mode": self.mode(),
+                        "cycles": self.conversion.len(),
+                        "failed_conversions": self.failed_conversions,
+                        "readings": stats.readings,
+                        "min": min,
+                        "max": max,
+                        "mean": mean,
+                        "crc_errors": stats.crc_errors,
+                        "presence_failures": stats.presence_failures,
+                        "other_errors": stats.other_errors,
+                        "conversion_us": percentiles_us(&conversion),
+                        "read_us": percentiles_us(&read),
+                    })
+                ),
+                OutputFormat::Csv => {
+                    let [c50, c90, c99, cmax] = percentiles_us(&conversion);
+                    let [r50, r90, r99, rmax] = percentiles_us(&read);
+                    println!(
+                        "soak,0x{rom:016x},0x{:08x},{},{},{},{},{},{},{},{},{},{},{},{c50},{c90},{c99},{cmax},{r50},{r90},{r99},{rmax}",
+                        rom_hash(*rom),
+                        out.resolution.bits(),
+                        self.mode(),
+                        self.conversion.len(),
+                        self.failed_conversions,
+                        stats.readings,
+                        min.map(|t| format!("{t:.4}")).unwrap_or_default(),
+                        max.map(|t| format!("{t:.4}")).unwrap_or_default(),
+                        mean.map(|t| format!("{t:.4}")).unwrap_or_default(),
+                        stats.crc_errors,
+                        stats.presence_failures,
+                        stats.other_errors,
+                    );
+                }
+            }
+        }
+    }
+}
+
+/// Print the header of the CSV table of [`SoakStats::report`].
+pub fn header(out: &Output) {
+    if out.format == OutputFormat::Csv {
+        println!();
+        println!(
+            "event,rom,hash,resolution,mode,cycles,failed_conversions,readings,min,max,mean,crc_errors,presence_failures,other_errors,conversion_p50_us,conversion_p90_us,conversion_p99_us,conversion_max_us,read_p50_us,read_p90_us,read_p99_us,read_max_us"
+        );
+    }
+}
+
+/// Run conversions back to back for `duration` in the current bus mode, and collect the
+/// statistics of the sensors that are not excluded.
+pub fn soak(
+    temp_sensors: &mut Ds28ea00Group<16>,
+    ds2484: &mut Ds2484<&mut I2cdev, &mut Delay>,
+    delay: &mut Delay,
+    duration: Duration,
+    filter: &SensorFilter,
+) -> SoakStats {
+    let mut stats = SoakStats::new(temp_sensors.overdrive());
+    let end = Instant::now() + duration;
+    while Instant::now() < end {
+        let start = Instant::now();
+        if let Err(e) = temp_sensors.trigger_temperature_conversion(ds2484, delay) {
+            log::warn!("Failed to trigger temperature conversion: {e:?}");
+            stats.failed_conversions += 1;
+            continue;
+        }
+        let after_conversion = Instant::now();
+        let readout = temp_sensors
+            .read_temperatures_detailed(ds2484, true)
+            .filter(|(rom, _)| filter.selects_rom(*rom, None))
+            .collect::<Vec<_>>();
+        let after_reading = Instant::now();
+        stats.record(
+            after_conversion.duration_since(start),
+            after_reading.duration_since(after_conversion),
+            readout.into_iter(),
+        );
+    }
+    stats
+}
+
+/// Run conversions back to back for `duration` on a sensor driven through its traits, and collect
+/// the statistics of the sensors that are not excluded.
+///
+/// A sensor missing from a readout counts as a presence failure.
+/// # Arguments
+/// * `roms` - The ROM codes of the sensors expected in every readout.
+/// * `rom` - The ROM code of a channel of the sensor.
+pub fn soak_sensor<B, S: TemperatureSensor<B>>(
+    sensor: &mut S,
+    bus: &mut B,
+    roms: &[u64],
+    rom: impl Fn(u64) -> u64,
+    duration: Duration,
+    filter: &SensorFilter,
+) -> SoakStats
+where
+    S::Error: std::fmt::Debug,
+{
+    let mut stats = SoakStats::new(false);
+    let end = Instant::now() + duration;
+    while Instant::now() < end {
+        let start = Instant::now();
+        if let Err(e) = sensor.trigger(bus) {
+            log::warn!("Failed to trigger temperature conversion: {e:?}");
+            stats.failed_conversions += 1;
+            continue;
+        }
+        std::thread::sleep(sensor.ready_after());
+        let after_conversion = Instant::now();
+        let mut readout = BTreeMap::new();
+        if let Err(e) = sensor.read(bus, &mut |id, temp| {
+            readout.insert(rom(id), temp);
+        }) {
+            log::warn!("Failed to read temperatures: {e:?}");
+        }
+        let after_reading = Instant::now();
+        stats.record(
+            after_conversion.duration_since(start),
+            after_reading.duration_since(after_conversion),
+            roms.iter()
+                .filter(|rom| filter.selects_rom(**rom, None))
+                .map(|rom| {
+                    (
+                        *rom,
+                        readout.get(rom).copied().ok_or(ReadError::NoDevicePresent),
+                    )
+                }),
+        );
+    }
+    stats
+}
+
+/// Nearest-rank percentile of sorted durations.
+fn percentile(sorted: &[Duration], p: usize) -> Duration {
+    if sorted.is_empty() {
+        return Duration::ZERO;
+    }
+    let rank = (p * sorted.len()).div_ceil(100).max(1);
+    sorted[rank - 1]
+}
+
+/// p50, p90, p99 and maximum of sorted durations, in microseconds.
+fn percentiles_us(sorted: &[Duration]) -> [u64; 4] {
+    [
+        percentile(sorted, 50),
+        percentile(sorted, 90),
+        percentile(sorted, 99),
+        sorted.last().copied().unwrap_or_default(),
+    ]
+    .map(|d| d.as_micros() as u64)
+}
+
+fn celsius(temp: Option<f32>) -> String {
+    temp.map_or_else(|| "-".into(), |t| format!("{t:.3} °C"))
+}
+
+mod test {
+    #[test]
+    fn test_percentile() {
+        use super::percentile;
+        use std::time::Duration;
+        let times = (1..=10).map(Duration::from_millis).collect::<Vec<_>>();
+        assert_eq!(percentile(&times, 50), Duration::from_millis(5));
+        assert_eq!(percentile(&times, 90), Duration::from_millis(9));
+        assert_eq!(percentile(&times, 99), Duration::from_millis(10));
+        assert_eq!(percentile(&[], 50), Duration::ZERO);
+    }
+}